@@ -0,0 +1,269 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::physics::GameLayer;
+
+pub struct CharacterPlugin;
+impl Plugin for CharacterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CharacterConfig>()
+            .add_systems(FixedUpdate, move_characters)
+            .add_systems(Update, smooth_character_eyes);
+    }
+}
+
+/// Tunes how every [`CharacterController`] moves.
+#[derive(Resource, Copy, Clone, Debug)]
+pub struct CharacterConfig {
+    /// World units per second walked.
+    pub walk_speed: f32,
+    /// Upward speed a jump starts with.
+    pub jump_speed: f32,
+    /// Steepest slope stood on, in radians; steeper ones are walls. Ramp tiles rise at 45°.
+    pub max_slope: f32,
+    /// Tallest ledge walked up onto without jumping, such as a half-height slab.
+    pub step_height: f32,
+    /// Farthest drop followed while walking, so slopes are walked down instead of off of.
+    pub snap_distance: f32,
+    /// Gap kept between characters and what they run into.
+    pub skin: f32,
+    /// How quickly the eyes catch up with steps, as the fraction left per second's exponent.
+    pub camera_smoothing: f32,
+}
+
+impl Default for CharacterConfig {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            walk_speed: 4.0,
+            jump_speed: 4.5,
+            max_slope: 50f32.to_radians(),
+            step_height: 0.55,
+            snap_distance: 0.2,
+            skin: 0.01,
+            camera_smoothing: 12.0,
+        }
+    }
+}
+
+/// A kinematic body moved by its [`CharacterInput`] every fixed step, colliding with maps and
+/// props. Anything may be one, given a collider.
+#[derive(Component, Copy, Clone, Default, Debug)]
+pub struct CharacterController {
+    pub velocity: Vec3,
+    pub grounded: bool,
+    /// The normal of the ground stood on, if grounded.
+    pub ground_normal: Vec3,
+    /// Added to the height of the eyes, so they lag behind steps up, then eased back to zero.
+    pub eye_offset: f32,
+}
+
+/// What a [`CharacterController`] is told to do, set by whatever drives it.
+#[derive(Component, Copy, Clone, Default, Debug)]
+pub struct CharacterInput {
+    /// World-space direction to walk in along X and Z, up to unit length for full speed.
+    pub walk: Vec2,
+    /// Jumps if grounded, at the next fixed step; cleared once it's run.
+    pub jump: bool,
+}
+
+/// Casts a character's collider through the world, stopping short of what it hits.
+struct Caster<'a> {
+    spatial: &'a SpatialQuery<'a, 'a>,
+    collider: &'a Collider,
+    filter: SpatialQueryFilter,
+    skin: f32,
+    /// Ground is anything facing up at least this much.
+    min_ground: f32,
+}
+
+impl Caster<'_> {
+    /// How far the collider may move from `origin` along `motion` before hitting something, and the
+    /// normal of what it hit.
+    #[inline]
+    fn cast(&self, origin: Vec3, motion: Vec3) -> Option<(f32, Vec3)> {
+        self.cast_at(origin, motion).map(|(travel, normal, _)| (travel, normal))
+    }
+
+    /// Like [`Caster::cast`], along with where the collider touched what it hit.
+    fn cast_at(&self, origin: Vec3, motion: Vec3) -> Option<(f32, Vec3, Vec3)> {
+        let (dir, distance) = Dir3::new_and_length(motion).ok()?;
+        let hit = self.spatial.cast_shape(
+            self.collider,
+            origin,
+            Quat::IDENTITY,
+            dir,
+            distance + self.skin,
+            true,
+            self.filter.clone(),
+        )?;
+
+        // The cast shape is unturned, so its local space is the world's but for the origin.
+        let contact = origin + *dir * hit.time_of_impact + hit.point2;
+        Some(((hit.time_of_impact - self.skin).clamp(0.0, distance), -hit.normal2, contact))
+    }
+
+    #[inline]
+    fn is_ground(&self, normal: Vec3) -> bool {
+        normal.y >= self.min_ground
+    }
+
+    /// Moves `position` along `motion`, sliding along what it runs into and taking velocity into
+    /// it off of `velocity`. Walls are kept upright while `grounded`, so they can't be climbed.
+    /// Returns whether a wall was run into.
+    fn slide(&self, position: &mut Vec3, mut motion: Vec3, velocity: &mut Vec3, grounded: bool) -> bool {
+        let mut blocked = false;
+        for _ in 0..4 {
+            let Some((travel, normal)) = self.cast(*position, motion) else {
+                *position += motion;
+                break
+            };
+
+            let dir = motion.normalize();
+            *position += dir * travel;
+
+            let normal = match self.is_ground(normal) || !grounded {
+                true => normal,
+                false => {
+                    blocked = true;
+                    Vec3::new(normal.x, 0.0, normal.z).try_normalize().unwrap_or(normal)
+                }
+            };
+
+            let remaining = dir * (motion.length() - travel);
+            motion = remaining - normal * remaining.dot(normal).min(0.0);
+            *velocity -= normal * velocity.dot(normal).min(0.0);
+        }
+
+        blocked
+    }
+
+    /// The ground within `distance` below `position`, as how far down it is and its normal.
+    /// Resting on the edge of a ledge, the collider touches it at a slant, so the ground there is
+    /// told by the surface just under the contact instead.
+    fn ground(&self, position: Vec3, distance: f32) -> Option<(f32, Vec3)> {
+        let (drop, normal, contact) = self.cast_at(position, Vec3::NEG_Y * distance)?;
+        if self.is_ground(normal) {
+            return Some((drop, normal))
+        }
+
+        // Edges are touched right on their corner, so look just onto the ledge from it.
+        let onto = -Vec3::new(normal.x, 0.0, normal.z).normalize_or_zero();
+        let under = self.spatial.cast_ray(
+            contact + (onto + Vec3::Y) * self.skin,
+            Dir3::NEG_Y,
+            self.skin * 2.0,
+            true,
+            self.filter.clone(),
+        )?;
+        self.is_ground(under.normal).then_some((drop, under.normal))
+    }
+}
+
+/// Moves characters by their input: walking, jumping, falling, sliding along walls, stepping up
+/// ledges, and following the ground down slopes. Runs in fixed steps, so the same input always
+/// moves them the same.
+pub fn move_characters(
+    time: Res<Time>,
+    config: Res<CharacterConfig>,
+    gravity: Res<Gravity>,
+    spatial: SpatialQuery,
+    mut characters: Query<(
+        Entity,
+        &mut CharacterController,
+        &mut CharacterInput,
+        &mut Transform,
+        &Collider,
+    )>,
+) {
+    let dt = time.delta_seconds();
+    for (e, mut controller, mut input, mut trns, collider) in &mut characters {
+        let caster = Caster {
+            spatial: &spatial,
+            collider,
+            filter: GameLayer::player_filter().with_excluded_entities([e]),
+            skin: config.skin,
+            min_ground: config.max_slope.cos(),
+        };
+
+        let walk = input.walk.clamp_length_max(1.0) * config.walk_speed;
+        let mut velocity = Vec3::new(walk.x, controller.velocity.y, walk.y);
+        let was_grounded = controller.grounded;
+
+        // Grounded characters stay put vertically, leaving the ground only by jumping or walking
+        // off of it.
+        let jumped = std::mem::take(&mut input.jump) && was_grounded;
+        match (was_grounded, jumped) {
+            (true, true) => velocity.y = config.jump_speed,
+            (true, false) => velocity.y = 0.0,
+            (false, _) => velocity += gravity.0 * dt,
+        }
+
+        let start = trns.translation;
+        let mut position = start;
+        let mut moved = velocity;
+        let blocked = caster.slide(&mut position, velocity * dt, &mut moved, was_grounded);
+
+        // Walked into a wall: try again from a step higher, then back down onto whatever's there.
+        if was_grounded && blocked && config.step_height > 0.0 {
+            let rise = caster
+                .cast(start, Vec3::Y * config.step_height)
+                .map_or(config.step_height, |(travel, _)| travel);
+
+            let mut stepped = start + Vec3::Y * rise;
+            let mut stepped_velocity = velocity;
+            caster.slide(
+                &mut stepped,
+                Vec3::new(velocity.x, 0.0, velocity.z) * dt,
+                &mut stepped_velocity,
+                true,
+            );
+
+            if let Some((drop, _)) = caster.ground(stepped, rise + config.snap_distance) {
+                stepped.y -= drop;
+                if (stepped - start).xz().length_squared() > (position - start).xz().length_squared() + 1e-6 {
+                    controller.eye_offset -= (stepped.y - position.y).max(0.0);
+                    position = stepped;
+                    moved = stepped_velocity;
+                }
+            }
+        }
+
+        // Walking up slopes slides upwards, which shouldn't take off from them.
+        controller.grounded = false;
+        if !jumped && (was_grounded || moved.y <= 0.0) {
+            // Stay on the ground walking down slopes, but only catch the ground falling onto it.
+            let reach = match was_grounded {
+                true => config.snap_distance,
+                false => config.skin * 2.0,
+            };
+
+            if let Some((drop, normal)) = caster.ground(position, reach) {
+                position.y -= drop;
+                moved.y = 0.0;
+                controller.grounded = true;
+                controller.ground_normal = normal;
+            }
+        }
+
+        controller.velocity = moved;
+        trns.translation = position;
+    }
+}
+
+/// Eases the eyes of characters back to where they should be after stepping.
+pub fn smooth_character_eyes(
+    time: Res<Time>,
+    config: Res<CharacterConfig>,
+    mut characters: Query<&mut CharacterController>,
+) {
+    let keep = (-config.camera_smoothing * time.delta_seconds()).exp();
+    for mut controller in &mut characters {
+        if controller.eye_offset != 0.0 {
+            controller.eye_offset *= keep;
+            if controller.eye_offset.abs() < 1e-4 {
+                controller.eye_offset = 0.0;
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use bevy::prelude::*;
+use thiserror::Error;
+
+use crate::{
+    content::atlas::{TileBackend, TileFilter},
+    editor::{display::DisplaySettings, session::SessionRequest},
+    export::export_gltf,
+    map::loader::{MapError, MapFile},
+    run_with, AppConfig, AssetRoot, GameState,
+};
+
+/// Exit code of tasks that ran, but found something wrong.
+pub const EXIT_FAILURE: u8 = 1;
+/// Exit code of arguments that don't make sense.
+pub const EXIT_USAGE: u8 = 2;
+
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error("Unknown argument `{0}`.")]
+    Unknown(String),
+    #[error("`{0}` needs a value.")]
+    MissingValue(&'static str),
+    #[error("Invalid map size `{0}`; expected one such as `64x64x8`.")]
+    InvalidSize(String),
+    #[error("`{0}` needs exactly one map.")]
+    OneMap(&'static str),
+    #[error("`{0}` needs at least one map.")]
+    NoMaps(&'static str),
+    #[error("`{0}` and `{1}` can't be used together.")]
+    Conflict(&'static str, &'static str),
+    #[error("`{path}` isn't in the asset directory `{root}`.")]
+    OutsideAssets { path: String, root: String },
+}
+
+/// Command-line arguments, as in `mnemonic [MAP] [--new WxHxD] [--export-gltf OUT] [--validate]`.
+#[derive(Clone, Default, Debug)]
+pub struct Args {
+    /// Maps named without a flag, to open or work on.
+    pub maps: Vec<PathBuf>,
+    /// Size of a new map to start the editor on.
+    pub new: Option<UVec3>,
+    /// File to export the map to as binary glTF, without opening a window.
+    pub export_gltf: Option<PathBuf>,
+    /// Whether to validate the maps without opening a window.
+    pub validate: bool,
+    pub demo: bool,
+    pub texture_array: bool,
+    pub linear_tiles: bool,
+}
+
+/// What the arguments ask to be done.
+#[derive(Clone, Debug)]
+pub enum Task {
+    /// Runs the editor, straight into a map if one is given.
+    Edit(Option<SessionRequest>),
+    ExportGltf { map: PathBuf, out: PathBuf },
+    Validate(Vec<PathBuf>),
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--new" => {
+                    let size = args.next().ok_or(CliError::MissingValue("--new"))?;
+                    parsed.new = Some(parse_size(&size).ok_or(CliError::InvalidSize(size))?);
+                }
+                "--export-gltf" => {
+                    parsed.export_gltf = Some(args.next().ok_or(CliError::MissingValue("--export-gltf"))?.into());
+                }
+                "--validate" => parsed.validate = true,
+                "--demo" => parsed.demo = true,
+                "--texture-array" => parsed.texture_array = true,
+                "--linear-tiles" => parsed.linear_tiles = true,
+                flag if flag.starts_with("--") => return Err(CliError::Unknown(arg)),
+                _ => parsed.maps.push(arg.into()),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// The task asked for, with maps to open named by their path in the asset directory `root`.
+    pub fn task(&self, root: &Path) -> Result<Task, CliError> {
+        match (self.validate, &self.export_gltf, self.new) {
+            (true, Some(..), _) => Err(CliError::Conflict("--validate", "--export-gltf")),
+            (true, _, Some(..)) => Err(CliError::Conflict("--validate", "--new")),
+            (false, Some(..), Some(..)) => Err(CliError::Conflict("--export-gltf", "--new")),
+            (true, None, None) if self.maps.is_empty() => Err(CliError::NoMaps("--validate")),
+            (true, None, None) => Ok(Task::Validate(self.maps.clone())),
+            (false, Some(out), None) => match &self.maps[..] {
+                [map] => Ok(Task::ExportGltf {
+                    map: map.clone(),
+                    out: out.clone(),
+                }),
+                _ => Err(CliError::OneMap("--export-gltf")),
+            },
+            (false, None, Some(size)) => match self.maps.is_empty() {
+                true => Ok(Task::Edit(Some(SessionRequest::New(size)))),
+                false => Err(CliError::Conflict("--new", "MAP")),
+            },
+            (false, None, None) => match &self.maps[..] {
+                [] => Ok(Task::Edit(None)),
+                [map] => Ok(Task::Edit(Some(SessionRequest::Open(asset_path(map, root)?)))),
+                _ => Err(CliError::OneMap("the editor")),
+            },
+        }
+    }
+
+    /// The app configuration the arguments ask for, opening the editor on `open` if given.
+    pub fn config(&self, open: Option<SessionRequest>) -> AppConfig {
+        let mut config = AppConfig::default();
+        if let Some(editor) = &mut config.editor {
+            editor.demo = self.demo;
+        }
+
+        if self.texture_array {
+            config.atlas.backend = TileBackend::Array;
+        }
+
+        if self.linear_tiles {
+            config.atlas.filter = TileFilter::Linear;
+        }
+
+        if open.is_some() {
+            config.after_loading = GameState::Editor;
+            config.open = open;
+        }
+
+        config
+    }
+}
+
+/// Parses a map size such as `64x64x8`, whose sides may not be zero.
+pub fn parse_size(size: &str) -> Option<UVec3> {
+    let mut sides = size.split(['x', 'X']).map(|side| side.trim().parse::<u32>().ok().filter(|&side| side > 0));
+    let size = UVec3::new(sides.next()??, sides.next()??, sides.next()??);
+    sides.next().is_none().then_some(size)
+}
+
+/// Names a map file by its asset path, whether given as one or as a path on disk into `root`.
+pub fn asset_path(path: &Path, root: &Path) -> Result<String, CliError> {
+    let outside = || CliError::OutsideAssets {
+        path: path.display().to_string(),
+        root: root.display().to_string(),
+    };
+
+    let relative = match root.join(path).is_file() {
+        true => path.to_path_buf(),
+        false => {
+            let (path, root) = (path.canonicalize().map_err(|_| outside())?, root.canonicalize().map_err(|_| outside())?);
+            path.strip_prefix(root).map_err(|_| outside())?.to_path_buf()
+        }
+    };
+
+    // Asset paths are separated by forward slashes on every platform.
+    Ok(relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+/// Runs whatever the arguments ask for. Errors are written to stderr and told by the exit code.
+pub fn run_args(args: impl IntoIterator<Item = String>) -> ExitCode {
+    let root = AssetRoot::default();
+    let (args, task) = match Args::parse(args).and_then(|args| args.task(&root).map(|task| (args, task))) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(EXIT_USAGE)
+        }
+    };
+
+    match task {
+        Task::Edit(open) => {
+            let mut config = args.config(open);
+            if let Ok(display) = DisplaySettings::load(&root) {
+                display.apply(&mut config);
+            }
+
+            exit_code(run_with(config))
+        }
+        Task::ExportGltf { map, out } => match asset_path(&map, &root) {
+            Ok(map) => exit_code(export_gltf(map, out)),
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::from(EXIT_USAGE)
+            }
+        },
+        Task::Validate(maps) => validate(&maps),
+    }
+}
+
+#[inline]
+fn exit_code(exit: AppExit) -> ExitCode {
+    match exit {
+        AppExit::Success => ExitCode::SUCCESS,
+        AppExit::Error(code) => ExitCode::from(code.get()),
+    }
+}
+
+/// Reads and validates every map file, reporting each on stderr. Tile names aren't checked, as that
+/// takes the tile folder to be loaded.
+pub fn validate(maps: &[PathBuf]) -> ExitCode {
+    let mut failed = 0;
+    for path in maps {
+        let checked = fs::read_to_string(path)
+            .map_err(MapError::from)
+            .and_then(|text| MapFile::from_ron(&text));
+
+        match checked {
+            Ok(..) => eprintln!("{}: ok", path.display()),
+            Err(e) => {
+                eprintln!("{}: {e}", path.display());
+                failed += 1;
+            }
+        }
+    }
+
+    match failed {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::from(EXIT_FAILURE),
+    }
+}
@@ -0,0 +1,26 @@
+use bevy::{
+    pbr::{ExtendedMaterial, MaterialExtension},
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+};
+
+/// The material maps are drawn with: a [`StandardMaterial`] that may sample its base color from a
+/// tile texture array instead.
+pub type MapMaterial = ExtendedMaterial<StandardMaterial, TileArrayExtension>;
+
+/// Samples the base color from the layer of [`array`](Self::array) given by a mesh's second UV
+/// channel, in [`TileBackend::Array`](crate::content::atlas::TileBackend::Array) mode. Meshes
+/// without a second UV channel are drawn as a plain [`StandardMaterial`].
+#[derive(Asset, AsBindGroup, Reflect, Clone, Default, Debug)]
+pub struct TileArrayExtension {
+    #[texture(100, dimension = "2d_array")]
+    #[sampler(101)]
+    pub array: Option<Handle<Image>>,
+}
+
+impl MaterialExtension for TileArrayExtension {
+    #[inline]
+    fn fragment_shader() -> ShaderRef {
+        "embedded://mnemonic/content/tile_array.wgsl".into()
+    }
+}
@@ -0,0 +1,334 @@
+use bevy::{
+    color::Srgba,
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages},
+        texture::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+/// How built tile textures are kept. They stay readable on the CPU with the `dev` feature, so they
+/// can be dumped for debugging.
+pub const TILE_TEXTURE_USAGE: RenderAssetUsages = match cfg!(feature = "dev") {
+    true => RenderAssetUsages::all(),
+    false => RenderAssetUsages::RENDER_WORLD,
+};
+
+/// How tile textures are filtered when sampled.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub enum TileFilter {
+    /// Keeps texels sharp, for pixel art.
+    #[default]
+    Nearest,
+    /// Blends neighboring texels, relying on the gutter to keep tiles from blending together.
+    Linear,
+}
+
+/// How tile textures are given to the map material.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub enum TileBackend {
+    /// Packed into atlas pages, with tile UVs remapped into each tile's rectangle.
+    #[default]
+    Atlas,
+    /// Stacked into a texture array, a layer per tile, so tile UVs may repeat past their texture.
+    Array,
+}
+
+#[derive(Resource, Copy, Clone)]
+pub struct TileAtlasSettings {
+    pub backend: TileBackend,
+    pub filter: TileFilter,
+    /// Most mip levels the tile atlas is given, including the full-size one. Every level doubles
+    /// the padding between tiles.
+    pub max_mip_levels: u32,
+    /// Whether source tile textures stay in memory after they're packed, rather than loaded again
+    /// whenever the atlas is repacked. Kept by default with the `dev` feature, whose file watcher
+    /// repacks the atlas on every texture change.
+    pub keep_sources: bool,
+    /// Least number of texels each tile's edges are extruded outwards, so linear sampling at its
+    /// borders never reaches a neighbor or the empty space between. Mip levels may need more.
+    pub gutter: u32,
+}
+
+impl Default for TileAtlasSettings {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            backend: TileBackend::Atlas,
+            filter: TileFilter::Nearest,
+            keep_sources: cfg!(feature = "dev"),
+            max_mip_levels: 5,
+            gutter: 2,
+        }
+    }
+}
+
+impl TileAtlasSettings {
+    /// Mip levels for an atlas of the given textures. Levels past the smallest texture's size would
+    /// only blend it with its surroundings.
+    #[inline]
+    pub fn mip_levels<'a>(self, textures: impl IntoIterator<Item = &'a Image>) -> u32 {
+        textures
+            .into_iter()
+            .map(|image| image.width().min(image.height()).max(1).ilog2() + 1)
+            .min()
+            .unwrap_or(1)
+            .clamp(1, self.max_mip_levels.max(1))
+    }
+
+    /// The sampler tile textures are given, with `address` as their addressing mode. Set on every
+    /// built texture rather than left to the image plugin's default.
+    #[inline]
+    pub fn sampler(self, address: ImageAddressMode) -> ImageSampler {
+        let descriptor = match self.filter {
+            TileFilter::Nearest => ImageSamplerDescriptor::nearest(),
+            TileFilter::Linear => ImageSamplerDescriptor::linear(),
+        };
+
+        ImageSampler::Descriptor(ImageSamplerDescriptor {
+            address_mode_u: address,
+            address_mode_v: address,
+            address_mode_w: address,
+            ..descriptor
+        })
+    }
+
+    /// Padding between tiles that fits the gutter and keeps `levels` mip levels from blending
+    /// neighboring tiles, once their edges are extruded halfway into it.
+    #[inline]
+    pub fn padding(self, levels: u32) -> u32 {
+        (1 << levels).max(self.gutter * 2)
+    }
+}
+
+/// Copies the edge texels of every texture in an RGBA8 atlas outwards, `extent` texels into the
+/// padding around it, so smaller mip levels blend tiles with their own edges rather than the
+/// padding.
+pub fn extrude_edges(atlas: &mut Image, layout: &TextureAtlasLayout, extent: u32) {
+    let size = atlas.size().as_ivec2();
+    let extent = extent as i32;
+    for rect in &layout.textures {
+        let (min, max) = (rect.min.as_ivec2(), rect.max.as_ivec2() - 1);
+        let (from, to) = ((min - extent).max(IVec2::ZERO), (max + extent).min(size - 1));
+        for y in from.y..=to.y {
+            for x in from.x..=to.x {
+                let texel = IVec2::new(x, y);
+                let source = texel.clamp(min, max);
+                if texel == source {
+                    continue
+                }
+
+                let (source, target) = (
+                    (source.y * size.x + source.x) as usize * 4,
+                    (texel.y * size.x + texel.x) as usize * 4,
+                );
+                atlas.data.copy_within(source..source + 4, target);
+            }
+        }
+    }
+}
+
+/// Fills a texture laid over the same rectangles as an atlas, such as its normal or emissive map.
+/// Each texture is scaled to the rectangle of its key, and `neutral` is left wherever none is
+/// given.
+pub fn build_parallel_atlas<'a>(
+    layout: &TextureAtlasLayout,
+    textures: impl IntoIterator<Item = (AssetId<Image>, &'a Image)>,
+    format: TextureFormat,
+    neutral: [u8; 4],
+) -> Image {
+    let size = Extent3d {
+        width: layout.size.x,
+        height: layout.size.y,
+        depth_or_array_layers: 1,
+    };
+
+    let mut atlas = Image::new_fill(size, TextureDimension::D2, &neutral, format, TILE_TEXTURE_USAGE);
+    for (id, texture) in textures {
+        if let Some(&rect) = layout.get_texture_index(id).and_then(|index| layout.textures.get(index)) {
+            copy_scaled(texture, &mut atlas, rect);
+        }
+    }
+
+    atlas
+}
+
+/// Copies a texture into a rectangle of an RGBA8 image, nearest-neighbor scaled to fit.
+pub fn copy_scaled(texture: &Image, target: &mut Image, rect: URect) {
+    let converted;
+    let texture = match texture.texture_descriptor.format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => texture,
+        other => match texture.convert(TextureFormat::Rgba8UnormSrgb) {
+            Some(texture) => {
+                converted = texture;
+                &converted
+            }
+            None => {
+                warn!("Couldn't convert a {other:?} tile texture to RGBA8.");
+                return
+            }
+        },
+    };
+
+    let (from, to, width) = (texture.size(), rect.size(), target.width());
+    for y in 0..to.y {
+        for x in 0..to.x {
+            let source = UVec2::new(x * from.x / to.x, y * from.y / to.y);
+            let (source, target_index) = (
+                (source.y * from.x + source.x) as usize * 4,
+                ((rect.min.y + y) * width + rect.min.x + x) as usize * 4,
+            );
+            target.data[target_index..target_index + 4].copy_from_slice(&texture.data[source..source + 4]);
+        }
+    }
+}
+
+/// Stacks textures into the layers of a 2D texture array, each scaled to the largest width and
+/// height among them and given up to `levels` mip levels. Layers follow the order given.
+pub fn build_texture_array<'a>(textures: impl IntoIterator<Item = &'a Image>, levels: u32) -> Image {
+    let textures = textures.into_iter().collect::<Vec<_>>();
+    let size = textures.iter().map(|texture| texture.size()).fold(UVec2::ONE, UVec2::max);
+    let levels = levels.min(size.min_element().ilog2() + 1);
+
+    let mut data = Vec::new();
+    for &texture in &textures {
+        let mut layer = Image::new_fill(
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0, 0, 0, 255],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::RENDER_WORLD,
+        );
+
+        copy_scaled(texture, &mut layer, URect::from_corners(UVec2::ZERO, size));
+        generate_mipmaps(&mut layer, levels);
+        data.append(&mut layer.data);
+    }
+
+    // `Image::new` expects only the full-size level's data, so the mip levels are put in after.
+    let mut array = Image::new_fill(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: textures.len() as u32,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        TILE_TEXTURE_USAGE,
+    );
+
+    array.data = data;
+    array.texture_descriptor.mip_level_count = levels;
+    array
+}
+
+/// Stacks block-compressed textures into the layers of a 2D texture array as they are, since they
+/// can't be scaled or re-encoded. Every texture must share the first's format and size; layers get
+/// as many mip levels as the texture with the fewest has.
+pub fn build_compressed_array<'a>(textures: impl IntoIterator<Item = &'a Image>) -> Image {
+    let textures = textures.into_iter().collect::<Vec<_>>();
+    let first = textures[0].texture_descriptor.clone();
+    let levels = textures
+        .iter()
+        .map(|texture| texture.texture_descriptor.mip_level_count.max(1))
+        .min()
+        .unwrap_or(1);
+
+    let (format, size) = (first.format, UVec2::new(first.size.width, first.size.height));
+    let layer_len = (0..levels).map(|level| level_bytes(format, size, level)).sum::<usize>();
+
+    // Textures are stored a layer after another, each followed by its own mip levels.
+    let mut data = Vec::with_capacity(layer_len * textures.len());
+    for texture in &textures {
+        data.extend_from_slice(&texture.data[..layer_len]);
+    }
+
+    Image {
+        data,
+        texture_descriptor: TextureDescriptor {
+            size: Extent3d {
+                depth_or_array_layers: textures.len() as u32,
+                ..first.size
+            },
+            mip_level_count: levels,
+            dimension: TextureDimension::D2,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            ..first
+        },
+        asset_usage: TILE_TEXTURE_USAGE,
+        ..default()
+    }
+}
+
+/// Bytes a mip level of a texture's layer takes, counting whole blocks for compressed formats.
+#[inline]
+pub fn level_bytes(format: TextureFormat, size: UVec2, level: u32) -> usize {
+    let (width, height) = format.block_dimensions();
+    let size = (size >> level).max(UVec2::ONE);
+    let blocks = size.x.div_ceil(width) * size.y.div_ceil(height);
+
+    blocks as usize * format.block_copy_size(None).unwrap_or(4) as usize
+}
+
+/// Bytes of video memory a texture takes, over all its layers and mip levels.
+#[inline]
+pub fn texture_bytes(descriptor: &TextureDescriptor) -> u64 {
+    let size = UVec2::new(descriptor.size.width, descriptor.size.height);
+    let layer = (0..descriptor.mip_level_count.max(1))
+        .map(|level| level_bytes(descriptor.format, size, level) as u64)
+        .sum::<u64>();
+
+    layer * descriptor.size.depth_or_array_layers as u64
+}
+
+/// Appends box-filtered mip levels to an RGBA8 image, up to `levels` including the full-size one.
+/// Colors of sRGB images are averaged in linear space.
+#[allow(clippy::type_complexity)]
+pub fn generate_mipmaps(image: &mut Image, levels: u32) {
+    let (decode, encode): (fn(f32) -> f32, fn(f32) -> f32) = match image.texture_descriptor.format.is_srgb() {
+        true => (Srgba::gamma_function, Srgba::gamma_function_inverse),
+        false => (|value| value, |value| value),
+    };
+
+    let mut size = image.size();
+    let mut level_start = 0;
+    let mut count = 1;
+
+    let data = &mut image.data;
+    while count < levels && size.cmpgt(UVec2::ONE).any() {
+        let next = (size / 2).max(UVec2::ONE);
+        let start = data.len();
+        data.reserve((next.x * next.y * 4) as usize);
+
+        for y in 0..next.y {
+            for x in 0..next.x {
+                let mut sum = Vec4::ZERO;
+                for offset in [UVec2::ZERO, UVec2::X, UVec2::Y, UVec2::ONE] {
+                    let texel = (UVec2::new(x, y) * 2 + offset).min(size - 1);
+                    let index = level_start + (texel.y * size.x + texel.x) as usize * 4;
+                    let [r, g, b, a] = [0, 1, 2, 3].map(|channel| data[index + channel] as f32 / 255.0);
+                    sum += Vec4::new(decode(r), decode(g), decode(b), a);
+                }
+
+                let average = sum / 4.0;
+                data.extend(
+                    [encode(average.x), encode(average.y), encode(average.z), average.w]
+                        .map(|channel| (channel * 255.0).round() as u8),
+                );
+            }
+        }
+
+        level_start = start;
+        size = next;
+        count += 1;
+    }
+
+    image.texture_descriptor.mip_level_count = count;
+}
@@ -0,0 +1,328 @@
+use std::{fs, io::Error as IoError, path::Path};
+
+use bevy::{
+    input::common_conditions::input_just_pressed, prelude::*, render::render_resource::TextureFormat, utils::HashMap,
+};
+use image::{ImageError, RgbaImage};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    content::{TileTexture, Tiles},
+    obj::def::{MtlCollection, Obj},
+};
+
+/// Writes every tile atlas page to `debug/`.
+pub const DUMP_ATLAS_KEY: KeyCode = KeyCode::F12;
+/// Shows the next tile atlas page over the screen, then none once past the last.
+pub const VIEW_ATLAS_KEY: KeyCode = KeyCode::Insert;
+
+/// Tools for inspecting the tile atlas, for debugging tile UVs.
+pub struct AtlasDebugPlugin;
+impl Plugin for AtlasDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AtlasView>().add_systems(
+            Update,
+            (
+                dump_atlas.run_if(input_just_pressed(DUMP_ATLAS_KEY)),
+                cycle_atlas_view.run_if(input_just_pressed(VIEW_ATLAS_KEY)),
+            )
+                .run_if(resource_exists::<TileTexture>.and_then(resource_exists::<Tiles>)),
+        );
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AtlasDumpError {
+    #[error("Page {0} is no longer readable.")]
+    Unreadable(usize),
+    #[error("Couldn't convert a {0:?} atlas to RGBA8.")]
+    Format(TextureFormat),
+    #[error(transparent)]
+    Encode(#[from] ImageError),
+    #[error(transparent)]
+    Serialize(#[from] ron::Error),
+    #[error(transparent)]
+    Io(#[from] IoError),
+}
+
+/// Where each tile lies on a dumped page.
+#[derive(Serialize, Debug)]
+pub struct PageDump {
+    pub size: UVec2,
+    pub tiles: Vec<TileDump>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TileDump {
+    pub key: String,
+    /// The tile's texels on an atlas page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rect: Option<URect>,
+    /// The tile's layer of a texture array page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layer: Option<u32>,
+}
+
+/// The page currently shown over the screen, and the entity showing it.
+#[derive(Resource, Default)]
+pub struct AtlasView {
+    pub page: Option<usize>,
+    pub root: Option<Entity>,
+}
+
+/// The key of the tile each diffuse texture belongs to.
+pub fn texture_keys(
+    tiles: &Tiles,
+    objs: &Assets<Obj>,
+    materials: &Assets<MtlCollection>,
+) -> HashMap<AssetId<Image>, String> {
+    let mut keys = HashMap::new();
+    for (key, obj) in tiles.iter() {
+        let Some(obj) = objs.get(obj) else { continue };
+        let Some(mtl) = materials.get(&obj.material).and_then(|mtl| mtl.get(&obj.material_key)) else {
+            continue
+        };
+
+        if let Some(diffuse) = mtl.diffuse_texture_id {
+            keys.insert(diffuse, key.clone());
+        }
+    }
+
+    keys
+}
+
+/// Where each tile lies on a page, ordered by key.
+pub fn page_dump(
+    texture: &TileTexture,
+    index: usize,
+    keys: &HashMap<AssetId<Image>, String>,
+    size: UVec2,
+    layouts: &Assets<TextureAtlasLayout>,
+) -> PageDump {
+    let page = &texture.pages[index];
+    let layout = layouts.get(&page.layout);
+
+    let mut tiles = texture
+        .page_of
+        .iter()
+        .filter(|&(_, &page)| page == index)
+        .map(|(id, _)| TileDump {
+            key: keys.get(id).cloned().unwrap_or_else(|| format!("{id:?}")),
+            rect: layout.and_then(|layout| layout.get_texture_index(*id).map(|index| layout.textures[index])),
+            layer: page.layers.as_ref().and_then(|layers| layers.get(id).copied()),
+        })
+        .collect::<Vec<_>>();
+
+    tiles.sort_unstable_by(|a, b| a.key.cmp(&b.key));
+    PageDump { size, tiles }
+}
+
+/// The full-size level of every layer of an image, as RGBA8.
+pub fn rgba_layers(image: &Image) -> Result<Vec<RgbaImage>, AtlasDumpError> {
+    let format = image.texture_descriptor.format;
+    let converted;
+    let image = match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => image,
+        _ => {
+            converted = image
+                .convert(TextureFormat::Rgba8UnormSrgb)
+                .ok_or(AtlasDumpError::Format(format))?;
+            &converted
+        }
+    };
+
+    // Layers are stored one after another, each followed by its mip levels.
+    let size = image.size();
+    let layer_len = (0..image.texture_descriptor.mip_level_count.max(1))
+        .map(|level| ((size.x >> level).max(1) * (size.y >> level).max(1)) as usize * 4)
+        .sum::<usize>();
+
+    image
+        .data
+        .chunks(layer_len)
+        .take(image.texture_descriptor.array_layer_count() as usize)
+        .map(|layer| {
+            RgbaImage::from_raw(size.x, size.y, layer[..(size.x * size.y) as usize * 4].to_vec())
+                .ok_or(AtlasDumpError::Format(format))
+        })
+        .collect()
+}
+
+/// Writes an image as PNG to `{stem}.png`, or a `{stem}.layer{index}.png` per layer for texture
+/// arrays.
+fn write_png(image: &Image, stem: &str) -> Result<(), AtlasDumpError> {
+    let layers = rgba_layers(image)?;
+    let single = layers.len() == 1;
+    for (index, layer) in layers.into_iter().enumerate() {
+        match single {
+            true => layer.save(format!("{stem}.png"))?,
+            false => layer.save(format!("{stem}.layer{index}.png"))?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a page's textures as PNG and where its tiles lie as RON, named after `stem`.
+pub fn dump_page(
+    texture: &TileTexture,
+    index: usize,
+    keys: &HashMap<AssetId<Image>, String>,
+    stem: &str,
+    images: &Assets<Image>,
+    layouts: &Assets<TextureAtlasLayout>,
+) -> Result<(), AtlasDumpError> {
+    let page = &texture.pages[index];
+    let atlas = images.get(&page.atlas).ok_or(AtlasDumpError::Unreadable(index))?;
+    write_png(atlas, stem)?;
+
+    for (suffix, map) in [("normal", &page.normal_atlas), ("emissive", &page.emissive_atlas)] {
+        if let Some(map) = map.as_ref().and_then(|map| images.get(map)) {
+            write_png(map, &format!("{stem}.{suffix}"))?;
+        }
+    }
+
+    let dump = page_dump(texture, index, keys, atlas.size(), layouts);
+    fs::write(format!("{stem}.ron"), ron::ser::to_string_pretty(&dump, default())?)?;
+    Ok(())
+}
+
+/// Dumps every page to `debug/atlas.png`, then `debug/atlas-1.png` and so on, each alongside the
+/// RON of where its tiles lie.
+pub fn dump_atlas(
+    texture: Res<TileTexture>,
+    tiles: Res<Tiles>,
+    objs: Res<Assets<Obj>>,
+    materials: Res<Assets<MtlCollection>>,
+    images: Res<Assets<Image>>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+) {
+    let dir = Path::new("debug");
+    if let Err(e) = fs::create_dir_all(dir) {
+        warn!("Couldn't create `{}`: {e}", dir.display());
+        return
+    }
+
+    let keys = texture_keys(&tiles, &objs, &materials);
+    for index in 0..texture.pages.len() {
+        let stem = match index {
+            0 => dir.join("atlas"),
+            index => dir.join(format!("atlas-{index}")),
+        }
+        .display()
+        .to_string();
+
+        match dump_page(&texture, index, &keys, &stem, &images, &layouts) {
+            Ok(()) => info!("Dumped atlas page {index} to `{stem}.png`."),
+            Err(e) => warn!("Couldn't dump atlas page {index}: {e}"),
+        }
+    }
+}
+
+/// Shows the next atlas page with its tiles outlined and labeled.
+#[allow(clippy::too_many_arguments)]
+pub fn cycle_atlas_view(
+    mut commands: Commands,
+    mut view: ResMut<AtlasView>,
+    texture: Res<TileTexture>,
+    tiles: Res<Tiles>,
+    objs: Res<Assets<Obj>>,
+    materials: Res<Assets<MtlCollection>>,
+    images: Res<Assets<Image>>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+) {
+    if let Some(root) = view.root.take() {
+        commands.entity(root).despawn_recursive();
+    }
+
+    view.page = match view.page {
+        None => Some(0),
+        Some(page) => Some(page + 1),
+    }
+    .filter(|&page| page < texture.pages.len());
+
+    let Some(index) = view.page else { return };
+    let page = &texture.pages[index];
+    let size = images.get(&page.atlas).map_or(UVec2::ONE, Image::size);
+    let dump = page_dump(&texture, index, &texture_keys(&tiles, &objs, &materials), size, &layouts);
+
+    let label = |text: String| {
+        TextBundle::from_section(text, TextStyle {
+            font_size: 14.0,
+            ..default()
+        })
+    };
+
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.85).into(),
+            z_index: ZIndex::Global(i32::MAX - 1),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(label(format!(
+                "Page {} of {}, {}x{}",
+                index + 1,
+                texture.pages.len(),
+                size.x,
+                size.y
+            )));
+
+            // Layers of a texture array can't be shown as a UI image; they're listed instead.
+            if page.layers.is_some() {
+                for tile in &dump.tiles {
+                    parent.spawn(label(format!("{}: {}", tile.layer.unwrap_or_default(), tile.key)));
+                }
+
+                return
+            }
+
+            parent
+                .spawn(ImageBundle {
+                    style: Style {
+                        height: Val::Vh(90.0),
+                        max_width: Val::Vw(95.0),
+                        aspect_ratio: Some(size.x as f32 / size.y as f32),
+                        ..default()
+                    },
+                    image: page.atlas.clone_weak().into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    let percent = |value: u32, of: u32| Val::Percent(value as f32 / of as f32 * 100.0);
+                    for tile in &dump.tiles {
+                        let Some(rect) = tile.rect else { continue };
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    left: percent(rect.min.x, size.x),
+                                    top: percent(rect.min.y, size.y),
+                                    width: percent(rect.width(), size.x),
+                                    height: percent(rect.height(), size.y),
+                                    border: UiRect::all(Val::Px(1.0)),
+                                    overflow: Overflow::clip(),
+                                    ..default()
+                                },
+                                border_color: Color::srgb(1.0, 0.3, 0.2).into(),
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn(label(tile.key.clone()));
+                            });
+                    }
+                });
+        })
+        .id();
+
+    view.root = Some(root);
+}
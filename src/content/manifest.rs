@@ -0,0 +1,374 @@
+use std::{io::Error as IoError, path::Path};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemState,
+    prelude::*,
+    utils::HashMap,
+};
+use ron::error::SpannedError;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::content::{
+    names::TileKey,
+    properties::{AutoOrient, SurfaceTag, TilePropertyOverrides},
+    render::TileAlpha,
+    TileFolder, Tiles,
+};
+
+#[derive(Error, Debug)]
+pub enum TileManifestError {
+    #[error(transparent)]
+    Syntax(#[from] SpannedError),
+    #[error(transparent)]
+    Io(#[from] IoError),
+}
+
+/// Describes tiles for the editor, read from `tiles/manifest.ron`. Tiles it leaves out are still
+/// available, described from their paths.
+#[derive(Asset, TypePath, Deserialize, Clone, Default, Debug)]
+pub struct TileManifest {
+    pub tiles: Vec<TileEntry>,
+    /// Where renamed or moved tiles are found now, from their old key or asset path to their
+    /// current one, so maps saved before still open.
+    #[serde(default)]
+    pub redirects: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TileEntry {
+    /// Asset path of the tile.
+    pub path: String,
+    /// The key maps store the tile by, such as `liminal.floor`. Derived from the path if left out.
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Slash-separated, such as `Liminal/Floors`.
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Where the tile goes in the palette; tiles without one follow those with.
+    #[serde(default)]
+    pub order: Option<i32>,
+    /// Overrides the alpha mode the tile's material implies with `d`.
+    #[serde(default)]
+    pub alpha_mode: Option<TileAlpha>,
+    /// Scales the tile's emissive texture.
+    #[serde(default)]
+    pub emissive_boost: Option<f32>,
+    /// Overrides whether the tile is lit, which its material implies with `illum`.
+    #[serde(default)]
+    pub unlit: Option<bool>,
+    #[serde(default)]
+    pub double_sided: Option<bool>,
+    /// Overrides whether characters may stand on the tile.
+    #[serde(default)]
+    pub walkable: Option<bool>,
+    #[serde(default)]
+    pub friction: Option<f32>,
+    #[serde(default)]
+    pub move_cost: Option<f32>,
+    #[serde(default)]
+    pub surface: Option<SurfaceTag>,
+    /// Overrides whether the editor turns the tile to the placement rotation.
+    #[serde(default)]
+    pub rotatable: Option<bool>,
+    #[serde(default)]
+    pub auto_orient: Option<AutoOrient>,
+    /// Overrides whether the tile is a trigger volume.
+    #[serde(default)]
+    pub sensor: Option<bool>,
+}
+
+impl TileEntry {
+    /// The tile properties the entry overrides.
+    #[inline]
+    pub fn properties(&self) -> TilePropertyOverrides {
+        TilePropertyOverrides {
+            walkable: self.walkable,
+            friction: self.friction,
+            move_cost: self.move_cost,
+            surface: self.surface,
+            rotatable: self.rotatable,
+            auto_orient: self.auto_orient,
+            sensor: self.sensor,
+        }
+    }
+}
+
+pub struct TileManifestLoader;
+impl AssetLoader for TileManifestLoader {
+    type Asset = TileManifest;
+    type Settings = ();
+    type Error = TileManifestError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _: &'a Self::Settings,
+        _: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut file = String::new();
+        reader.read_to_string(&mut file).await?;
+
+        Ok(ron::from_str(&file)?)
+    }
+
+    #[inline]
+    fn extensions(&self) -> &[&str] {
+        // Loaded by type only, so the tile folder doesn't claim every RON file in it.
+        &[]
+    }
+}
+
+/// How a single tile is presented in the editor.
+#[derive(Clone, Default, Debug)]
+pub struct TileInfo {
+    pub name: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub order: Option<i32>,
+}
+
+impl TileInfo {
+    /// Describes a tile the manifest leaves out, named after its file and object and categorized by
+    /// its folder under `tiles/`.
+    pub fn from_path(path: &str) -> Self {
+        let (file, label) = path.split_once('#').unwrap_or((path, ""));
+        let file = Path::new(file);
+        let stem = file
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(file.to_str().unwrap_or(path));
+        let object = label.strip_prefix("obj:").unwrap_or(label);
+
+        let name = match object {
+            "" | "tile" => title(stem),
+            object if object == stem => title(stem),
+            object => format!("{} {object}", title(stem)),
+        };
+
+        let category = file
+            .parent()
+            .and_then(|dir| dir.strip_prefix("tiles").ok())
+            .into_iter()
+            .flat_map(Path::iter)
+            .filter_map(|dir| dir.to_str())
+            .map(title)
+            .collect::<Vec<_>>()
+            .join("/");
+
+        Self {
+            name,
+            category,
+            ..default()
+        }
+    }
+
+    /// The name prefixed with the category.
+    #[inline]
+    pub fn full_name(&self) -> String {
+        match self.category.is_empty() {
+            true => self.name.clone(),
+            false => format!("{}/{}", self.category, self.name),
+        }
+    }
+
+    #[inline]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Whether the tile is in the category or any under it.
+    #[inline]
+    pub fn in_category(&self, path: &str) -> bool {
+        path.is_empty() ||
+            self.category
+                .strip_prefix(path)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+}
+
+/// Capitalizes the first letter of a file name and spaces out its separators.
+fn title(name: &str) -> String {
+    let mut chars = name.chars().map(|c| if matches!(c, '_' | '-') { ' ' } else { c });
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Every tile in [`Tiles`] described by the manifest, in palette order.
+#[derive(Resource, Clone, Debug)]
+pub struct TileCatalog {
+    pub infos: HashMap<String, TileInfo>,
+    /// Tile keys by explicit order, then by category and name.
+    pub order: Vec<String>,
+}
+
+impl TileCatalog {
+    pub fn new(tiles: &Tiles, manifest: Option<&TileManifest>) -> Self {
+        let names = tiles.names.read();
+        let mut infos = tiles
+            .keys()
+            .map(|key| (key.clone(), TileInfo::from_path(names.path(key).unwrap_or(key))))
+            .collect::<HashMap<_, _>>();
+
+        for entry in manifest.into_iter().flat_map(|manifest| &manifest.tiles) {
+            let Some(info) = names.resolve(&entry.path).and_then(|key| infos.get_mut(key)) else {
+                warn!("The tile manifest lists `{}`, which isn't a tile.", entry.path);
+                continue
+            };
+
+            if let Some(ref name) = entry.name {
+                info.name.clone_from(name);
+            }
+
+            if let Some(ref category) = entry.category {
+                info.category.clone_from(category);
+            }
+
+            info.tags.clone_from(&entry.tags);
+            info.order = entry.order;
+        }
+
+        Self::from_infos(infos)
+    }
+
+    /// Orders already described tiles for the palette.
+    pub fn from_infos(infos: HashMap<String, TileInfo>) -> Self {
+        let mut order = infos.keys().cloned().collect::<Vec<_>>();
+        order.sort_unstable_by(|a, b| {
+            let (a_info, b_info) = (&infos[a], &infos[b]);
+            (a_info.order.is_none(), a_info.order, &a_info.category, &a_info.name, a).cmp(&(
+                b_info.order.is_none(),
+                b_info.order,
+                &b_info.category,
+                &b_info.name,
+                b,
+            ))
+        });
+
+        Self { infos, order }
+    }
+
+    #[inline]
+    pub fn info(&self, key: &str) -> Option<&TileInfo> {
+        self.infos.get(key)
+    }
+
+    /// The tile's category and name, or its key if it isn't in the catalog.
+    #[inline]
+    pub fn full_name(&self, key: &str) -> String {
+        self.info(key).map_or_else(|| key.into(), TileInfo::full_name)
+    }
+
+    /// Every category tiles are in, along with the categories containing them, sorted so each comes
+    /// right before those under it.
+    pub fn categories(&self) -> Vec<String> {
+        let mut categories = self
+            .infos
+            .values()
+            .filter(|info| !info.category.is_empty())
+            .flat_map(|info| {
+                info.category
+                    .match_indices('/')
+                    .map(|(end, _)| &info.category[..end])
+                    .chain([info.category.as_str()])
+            })
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        categories.sort_unstable_by(|a, b| a.split('/').cmp(b.split('/')));
+        categories.dedup();
+        categories
+    }
+
+    /// Keys of the tiles in a category or any under it, in palette order. The empty category holds
+    /// every tile.
+    pub fn iter_category<'a>(&'a self, path: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.order
+            .iter()
+            .filter(move |key| self.infos[key.as_str()].in_category(path))
+            .map(String::as_str)
+    }
+
+    /// Tiles whose key, name, or tags match the query, best matches first. Keys and names also
+    /// match fuzzily, by containing the query's characters in order. An empty query matches
+    /// every tile, in palette order.
+    pub fn search(&self, query: &str) -> Vec<TileKey> {
+        let query = query.trim().to_lowercase();
+        let mut matches = self
+            .order
+            .iter()
+            .enumerate()
+            .filter_map(|(position, key)| {
+                let rank = match query.is_empty() {
+                    true => 0,
+                    false => search_rank(key, &self.infos[key], &query)?,
+                };
+
+                Some((rank, position, key))
+            })
+            .collect::<Vec<_>>();
+
+        matches.sort_unstable_by_key(|&(rank, position, _)| (rank, position));
+        matches.into_iter().map(|(.., key)| TileKey(key.clone())).collect()
+    }
+}
+
+/// How well a tile matches a lowercase query, lower being better, or `None` if it doesn't.
+fn search_rank(key: &str, info: &TileInfo, query: &str) -> Option<u32> {
+    let (key, name) = (key.to_lowercase(), info.name.to_lowercase());
+    let texts = [key.as_str(), name.as_str()];
+
+    if texts.contains(&query) {
+        Some(0)
+    } else if texts
+        .iter()
+        .any(|&text| text.starts_with(query) || text.split(['.', ' ']).any(|word| word.starts_with(query)))
+    {
+        Some(1)
+    } else if texts.iter().any(|&text| text.contains(query)) {
+        Some(2)
+    } else if info.tags.iter().any(|tag| tag.to_lowercase() == query) {
+        Some(3)
+    } else if info.tags.iter().any(|tag| tag.to_lowercase().starts_with(query)) {
+        Some(4)
+    } else {
+        // Matches spread further apart rank lower.
+        texts
+            .iter()
+            .filter_map(|&text| fuzzy_gaps(text, query))
+            .min()
+            .map(|gaps| 5 + gaps)
+    }
+}
+
+/// How many characters lie between the query's characters in the text, if it contains them in
+/// order.
+fn fuzzy_gaps(text: &str, query: &str) -> Option<u32> {
+    let mut chars = text.chars();
+    let mut gaps = 0;
+    for (i, c) in query.chars().enumerate() {
+        let skipped = chars.by_ref().position(|t| t == c)?;
+        if i > 0 {
+            gaps += skipped as u32;
+        }
+    }
+
+    Some(gaps)
+}
+
+impl FromWorld for TileCatalog {
+    fn from_world(world: &mut World) -> Self {
+        world.init_resource::<Tiles>();
+        let (tiles, folder, manifests) =
+            SystemState::<(Res<Tiles>, Res<TileFolder>, Res<Assets<TileManifest>>)>::new(world).get(world);
+
+        Self::new(&tiles, manifests.get(&folder.manifest))
+    }
+}
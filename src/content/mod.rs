@@ -0,0 +1,1069 @@
+pub mod array;
+pub mod atlas;
+#[cfg(feature = "dev")]
+pub mod debug;
+pub mod manifest;
+pub mod names;
+pub mod properties;
+pub mod register;
+pub mod render;
+pub mod thumbnail;
+
+use std::{
+    cmp::Reverse,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use bevy::{
+    asset::embedded_asset,
+    ecs::{event::ManualEventReader, system::SystemState},
+    prelude::*,
+    render::{render_resource::TextureFormat, renderer::RenderDevice, settings::WgpuLimits, texture::ImageAddressMode},
+    sprite::TextureAtlasBuilderError,
+    utils::{Duration, HashMap, HashSet, Instant},
+};
+use bevy_asset_loader::prelude::*;
+use iyes_progress::prelude::*;
+use thiserror::Error;
+
+use crate::{
+    content::{
+        array::{MapMaterial, TileArrayExtension},
+        atlas::{
+            build_compressed_array, build_parallel_atlas, build_texture_array, extrude_edges, generate_mipmaps,
+            texture_bytes, TileAtlasSettings, TileBackend, TILE_TEXTURE_USAGE,
+        },
+        manifest::{TileCatalog, TileManifest, TileManifestLoader},
+        names::{TileKey, TileNames},
+        properties::{update_tile_properties, TilePropertyTable},
+        register::{resolve_prebuilt_tiles, update_tile_catalog, PrebuiltTiles, TileRegistered},
+        render::{update_tile_renders, TileRenders},
+        thumbnail::{draw_thumbnails, init_thumbnail_stage, queue_thumbnails, ThumbnailQueue, TileThumbnails},
+    },
+    loading::ReloadingAssets,
+    map::{tile::Tile, update_map_mesh, MapMaterials},
+    obj::def::{MtlCollection, Obj, ObjCollection},
+    GameState,
+};
+
+pub struct ContentPlugin;
+impl Plugin for ContentPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "tile_array.wgsl");
+
+        app.add_plugins(MaterialPlugin::<MapMaterial>::default())
+            .init_resource::<TileAtlasSettings>()
+            .init_resource::<TileNames>()
+            .init_asset::<TileManifest>()
+            .register_asset_loader(TileManifestLoader)
+            .add_event::<TileRegistered>()
+            .add_event::<RebuildTileTexture>()
+            .add_event::<TileTextureRebuilt>()
+            .init_resource::<TileTextureStats>()
+            .init_resource::<PrebuiltTiles>()
+            .init_resource::<TileRenders>()
+            .init_resource::<TilePropertyTable>()
+            .init_resource::<TileThumbnails>()
+            .init_resource::<ThumbnailQueue>()
+            .add_systems(Startup, init_thumbnail_stage)
+            .add_systems(
+                Update,
+                (
+                    build_tile_texture
+                        .track_progress()
+                        .run_if(in_state(GameState::Loading).and_then(not(resource_exists::<ReloadingAssets>))),
+                    (reload_tile_texture, service_tile_rebuilds)
+                        .chain()
+                        .run_if(resource_exists::<TileTexture>),
+                    update_tile_catalog.run_if(resource_exists::<TileCatalog>),
+                    (update_tile_renders, update_tile_properties)
+                        .after(service_tile_rebuilds)
+                        .run_if(resource_exists::<TileTexture>.and_then(resource_exists::<Tiles>)),
+                    (queue_thumbnails, draw_thumbnails)
+                        .chain()
+                        .after(service_tile_rebuilds)
+                        .run_if(resource_exists::<TileTexture>),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                resolve_prebuilt_tiles
+                    .before(update_map_mesh)
+                    .run_if(resource_exists::<Tiles>),
+            );
+    }
+}
+
+/// Every file under `tiles/`, recursively. Tiles are the objects in the OBJ files among them, and
+/// those generated from `.tile` files.
+#[derive(AssetCollection, Resource)]
+pub struct TileFolder {
+    #[asset(path = "tiles", collection(mapped))]
+    pub files: HashMap<String, UntypedHandle>,
+    #[asset(path = "tiles/manifest.ron")]
+    pub manifest: Handle<TileManifest>,
+}
+
+/// Every tile found in [`TileFolder`], keyed by [`TileKey`].
+#[derive(Resource, Deref)]
+pub struct Tiles {
+    #[deref]
+    pub tiles: HashMap<String, Handle<Obj>>,
+    pub names: TileNames,
+}
+
+impl Tiles {
+    /// The key of a tile named by its key, asset path, or a redirect.
+    #[inline]
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        self.names.read().resolve(name).map(Into::into)
+    }
+
+    /// The tile named by its key, asset path, or a redirect.
+    #[inline]
+    pub fn handle(&self, name: &str) -> Option<&Handle<Obj>> {
+        self.tiles.get(&self.resolve(name)?)
+    }
+}
+
+impl FromWorld for Tiles {
+    fn from_world(world: &mut World) -> Self {
+        world.init_resource::<TileNames>();
+        let (folder, collections, generated, manifests, names) = SystemState::<(
+            Res<TileFolder>,
+            Res<Assets<ObjCollection>>,
+            Res<Assets<Tile>>,
+            Res<Assets<TileManifest>>,
+            Res<TileNames>,
+        )>::new(world)
+        .get(world);
+
+        let manifest = manifests.get(&folder.manifest);
+        let keys = manifest
+            .into_iter()
+            .flat_map(|manifest| &manifest.tiles)
+            .filter_map(|entry| Some((entry.path.as_str(), entry.key.as_deref()?)))
+            .collect::<HashMap<_, _>>();
+
+        // Objects wrapped by `.tile` files are the same tiles as their wrappers, which take them over.
+        let mut wrapped = HashMap::new();
+        let mut paths = Vec::new();
+        for (path, file) in &folder.files {
+            // Generated tiles are loaded as their geometry, like any object.
+            if let Some(tile) = file.clone().try_typed::<Tile>().ok().and_then(|file| generated.get(&file)) {
+                let path = format!("{path}#obj:tile");
+                if let Tile::Model { source, .. } = tile {
+                    wrapped.insert(source.as_str(), path.clone());
+                }
+
+                paths.push((path, tile.obj().clone()));
+                continue
+            }
+
+            let Some(collection) = file
+                .clone()
+                .try_typed::<ObjCollection>()
+                .ok()
+                .and_then(|file| collections.get(&file))
+            else {
+                continue
+            };
+
+            paths.extend(
+                collection
+                    .iter()
+                    .map(|(name, obj)| (format!("{path}#obj:{name}"), obj.clone())),
+            );
+        }
+
+        paths.retain(|(path, _)| !wrapped.contains_key(path.as_str()));
+
+        // Manifest keys are claimed first, so generated keys can't take them.
+        paths.sort_unstable_by_key(|(path, _)| (!keys.contains_key(path.as_str()), path.clone()));
+
+        let mut table = names.write();
+        let mut tiles = HashMap::new();
+        for (path, obj) in paths {
+            let key = keys
+                .get(path.as_str())
+                .map_or_else(|| TileKey::from_path(&path).0, |&key| key.into());
+
+            let key = match table.insert(&key, Some(&path)) {
+                true => key,
+                false => {
+                    warn!("Tile `{path}` can't be keyed `{key}`, which is taken; keying it by path instead.");
+                    table.insert(&path, Some(&path));
+                    path
+                }
+            };
+
+            tiles.insert(key, obj);
+        }
+
+        // Maps naming the wrapped objects find their wrappers.
+        for (source, wrapper) in wrapped {
+            let Some(key) = table.keys.get(&wrapper).cloned() else {
+                continue
+            };
+            table.redirects.insert(TileKey::from_path(source).0, key.clone());
+            table.keys.insert(source.into(), key);
+        }
+
+        if let Some(manifest) = manifest {
+            table.redirects.extend(manifest.redirects.clone());
+        }
+
+        if tiles.is_empty() {
+            warn!("No tiles found in `tiles/`.");
+        }
+
+        drop(table);
+        Self {
+            tiles,
+            names: names.clone(),
+        }
+    }
+}
+
+/// One texture of the tile atlas. Tiles are split across pages when they don't all fit in a single
+/// texture of the maximum size.
+#[derive(Clone, Debug)]
+pub struct AtlasPage {
+    pub layout: Handle<TextureAtlasLayout>,
+    pub atlas: Handle<Image>,
+    /// The layer of each tile texture, if [`atlas`](Self::atlas) is a texture array rather than an
+    /// atlas. Its layout is then empty.
+    pub layers: Option<HashMap<AssetId<Image>, u32>>,
+    /// Normal maps at the same rectangles as [`atlas`](Self::atlas), if any tile on this page has
+    /// one.
+    pub normal_atlas: Option<Handle<Image>>,
+    /// Emissive maps at the same rectangles as [`atlas`](Self::atlas), if any tile on this page has
+    /// one.
+    pub emissive_atlas: Option<Handle<Image>>,
+}
+
+impl AtlasPage {
+    /// A copy of a map material showing this page instead.
+    #[inline]
+    pub fn material(&self, base: &MapMaterial) -> MapMaterial {
+        let atlas = Some(self.atlas.clone_weak());
+        let (atlas, array) = match self.layers {
+            Some(..) => (None, atlas),
+            None => (atlas, None),
+        };
+
+        MapMaterial {
+            base: StandardMaterial {
+                base_color_texture: atlas,
+                normal_map_texture: self.normal_atlas.as_ref().map(Handle::clone_weak),
+                emissive_texture: self.emissive_atlas.as_ref().map(Handle::clone_weak),
+                // The emissive texture is scaled by this, which is black by default.
+                emissive: match self.emissive_atlas {
+                    Some(..) => LinearRgba::WHITE,
+                    None => LinearRgba::BLACK,
+                },
+                ..base.base.clone()
+            },
+            extension: TileArrayExtension { array },
+        }
+    }
+}
+
+/// The auxiliary textures of a tile texture's material.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct TileMaps {
+    pub normal: Option<AssetId<Image>>,
+    pub emissive: Option<AssetId<Image>>,
+}
+
+/// An atlas page built from tile textures, before it's added to the assets.
+pub struct BuiltPage {
+    pub layout: TextureAtlasLayout,
+    pub atlas: Image,
+    pub normal_atlas: Option<Image>,
+    pub emissive_atlas: Option<Image>,
+    pub layers: Option<HashMap<AssetId<Image>, u32>>,
+}
+
+impl BuiltPage {
+    /// Bytes of video memory the page's textures take.
+    #[inline]
+    pub fn bytes(&self) -> u64 {
+        [Some(&self.atlas), self.normal_atlas.as_ref(), self.emissive_atlas.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|image| texture_bytes(&image.texture_descriptor))
+            .sum()
+    }
+}
+
+#[inline]
+fn report_duplicates(collapsed: usize) {
+    if collapsed > 0 {
+        info!("Collapsed {collapsed} duplicate tile texture(s) into the ones they copy.");
+    }
+}
+
+/// Logs how many pages tile textures took, and how much video memory.
+fn report_pages(pages: &[BuiltPage]) {
+    let bytes = pages.iter().map(BuiltPage::bytes).sum::<u64>();
+    info!(
+        "Built {} tile texture page(s), taking {:.2} MiB of video memory.",
+        pages.len(),
+        bytes as f64 / (1024.0 * 1024.0)
+    );
+}
+
+#[derive(Resource, Default)]
+pub struct TileTexture {
+    pub pages: Vec<AtlasPage>,
+    /// The page each tile texture was packed into.
+    pub page_of: HashMap<AssetId<Image>, usize>,
+    /// The auxiliary textures of every diffuse texture packed into the atlas.
+    pub maps: HashMap<AssetId<Image>, TileMaps>,
+    /// The textures packed into the atlas and its auxiliary atlases, taken out of `Assets<Image>`.
+    /// Their strong handles are held on to so they're still reloaded when they change on disk.
+    pub sources: HashMap<AssetId<Image>, (Handle<Image>, Image)>,
+    /// Textures moved out of [`sources`](Self::sources) by
+    /// [`release_sources`](Self::release_sources), whose data is dropped until they're needed to
+    /// repack the atlas again.
+    pub released: HashMap<AssetId<Image>, Handle<Image>>,
+    /// Diffuse textures identical to another, along with their auxiliary textures, and that other
+    /// texture packed in their place.
+    pub aliases: HashMap<AssetId<Image>, AssetId<Image>>,
+}
+
+impl TileTexture {
+    /// Drops the data of every source texture that can be loaded again from its path, so only the
+    /// atlas is kept in memory. Textures without a path, such as those of prebuilt tiles, are kept.
+    pub fn release_sources(&mut self) {
+        let released = self
+            .sources
+            .iter()
+            .filter(|(_, (handle, _))| handle.path().is_some())
+            .map(|(&id, _)| id)
+            .collect::<Vec<_>>();
+
+        for id in released {
+            let Some((handle, _)) = self.sources.remove(&id) else {
+                continue
+            };
+            self.released.insert(id, handle);
+        }
+    }
+
+    /// Finds diffuse textures with the same contents and auxiliary textures as another, so only one
+    /// of them is packed. Returns how many were collapsed.
+    pub fn dedupe(&mut self) -> usize {
+        fn content_hash(image: &Image) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            (&image.data, image.size(), image.texture_descriptor.format).hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let hash =
+            |id: Option<AssetId<Image>>| id.and_then(|id| self.sources.get(&id)).map(|(_, image)| content_hash(image));
+
+        let image = |id: Option<AssetId<Image>>| id.and_then(|id| self.sources.get(&id)).map(|(_, image)| image);
+        let same = |a: &(AssetId<Image>, TileMaps), b: &(AssetId<Image>, TileMaps)| {
+            [(Some(a.0), Some(b.0)), (a.1.normal, b.1.normal), (a.1.emissive, b.1.emissive)]
+                .into_iter()
+                .all(|(a, b)| {
+                    let (a, b) = (image(a), image(b));
+                    a.map(|a| (&a.data, a.size(), a.texture_descriptor.format)) ==
+                        b.map(|b| (&b.data, b.size(), b.texture_descriptor.format))
+                })
+        };
+
+        // Sorted by path, so the same texture stays canonical across rebuilds.
+        let mut textures = self
+            .maps
+            .iter()
+            .filter_map(|(&id, &maps)| Some((id, maps, &self.sources.get(&id)?.0)))
+            .collect::<Vec<_>>();
+        textures.sort_unstable_by_key(|&(id, _, handle)| source_name(&id, handle));
+
+        let mut canonical = HashMap::<_, Vec<(AssetId<Image>, TileMaps)>>::new();
+        let mut aliases = HashMap::new();
+        for (id, maps, _) in textures {
+            let candidates = canonical
+                .entry((hash(Some(id)), hash(maps.normal), hash(maps.emissive)))
+                .or_default();
+
+            // Hashes only narrow down the candidates, so contents are compared in full.
+            match candidates.iter().find(|&candidate| same(candidate, &(id, maps))) {
+                Some(&(original, _)) => {
+                    aliases.insert(id, original);
+                }
+                None => candidates.push((id, maps)),
+            }
+        }
+
+        let collapsed = aliases.len();
+        self.aliases = aliases;
+        collapsed
+    }
+
+    /// The texture packed in place of a tile texture, which is itself unless it's an alias.
+    #[inline]
+    pub fn canonical(&self, texture: AssetId<Image>) -> AssetId<Image> {
+        self.aliases.get(&texture).copied().unwrap_or(texture)
+    }
+
+    /// Loads released textures again, so the atlas can be repacked. They're taken back into
+    /// [`sources`](Self::sources) once loaded, keeping their IDs.
+    pub fn reload_released(&self, server: &AssetServer, images: &Assets<Image>) {
+        for (&id, handle) in &self.released {
+            if let (false, Some(path)) = (images.contains(id), handle.path()) {
+                server.reload(path.clone());
+            }
+        }
+    }
+
+    /// The page a tile texture was packed into, and its rectangle there in UV coordinates. Texture
+    /// arrays give each tile the whole of its layer.
+    #[inline]
+    pub fn locate(&self, layouts: &Assets<TextureAtlasLayout>, texture: AssetId<Image>) -> Option<(usize, Rect)> {
+        let texture = self.canonical(texture);
+        let page = *self.page_of.get(&texture)?;
+        let atlas_page = self.pages.get(page)?;
+        if atlas_page.layers.is_some() {
+            return Some((page, Rect::new(0.0, 0.0, 1.0, 1.0)))
+        }
+
+        let layout = layouts.get(&atlas_page.layout)?;
+        let rect = layout.textures.get(layout.get_texture_index(texture)?)?.as_rect();
+        let size = layout.size.as_vec2();
+
+        Some((page, Rect::from_corners(rect.min / size, rect.max / size)))
+    }
+
+    /// The texture array layer a tile texture was stacked into, if it isn't in an atlas.
+    #[inline]
+    pub fn layer(&self, texture: AssetId<Image>) -> Option<u32> {
+        let texture = self.canonical(texture);
+        let page = self.pages.get(*self.page_of.get(&texture)?)?;
+        page.layers.as_ref()?.get(&texture).copied()
+    }
+
+    /// Replaces the atlas pages, keeping the handles of those that remain so materials pointing
+    /// at them stay valid.
+    pub fn set_pages(
+        &mut self,
+        pages: Vec<BuiltPage>,
+        layouts: &mut Assets<TextureAtlasLayout>,
+        images: &mut Assets<Image>,
+    ) {
+        fn set_atlas(handle: &mut Option<Handle<Image>>, atlas: Option<Image>, images: &mut Assets<Image>) {
+            match (handle.as_ref(), atlas) {
+                (Some(handle), Some(atlas)) => images.insert(handle, atlas),
+                (None, Some(atlas)) => *handle = Some(images.add(atlas)),
+                (.., None) => *handle = None,
+            }
+        }
+
+        self.page_of.clear();
+        self.pages.truncate(pages.len());
+
+        for (index, page) in pages.into_iter().enumerate() {
+            let packed = self.maps.keys().filter(|&&id| match page.layers {
+                Some(ref layers) => layers.contains_key(&id),
+                None => page.layout.get_texture_index(id).is_some(),
+            });
+            self.page_of.extend(packed.map(|&id| (id, index)));
+
+            if index == self.pages.len() {
+                self.pages.push(AtlasPage {
+                    layout: layouts.reserve_handle(),
+                    atlas: images.reserve_handle(),
+                    layers: None,
+                    normal_atlas: None,
+                    emissive_atlas: None,
+                });
+            }
+
+            let current = &mut self.pages[index];
+            layouts.insert(&current.layout, page.layout);
+            images.insert(&current.atlas, page.atlas);
+            current.layers = page.layers;
+            set_atlas(&mut current.normal_atlas, page.normal_atlas, images);
+            set_atlas(&mut current.emissive_atlas, page.emissive_atlas, images);
+        }
+    }
+}
+
+impl TileTexture {
+    /// Takes the textures of every tile and packs them into atlas pages.
+    pub fn build(
+        tiles: &Tiles,
+        settings: TileAtlasSettings,
+        objs: &Assets<Obj>,
+        materials: &mut Assets<MtlCollection>,
+        images: &mut Assets<Image>,
+        layouts: &mut Assets<TextureAtlasLayout>,
+        render_device: &RenderDevice,
+    ) -> Result<Self, TileTextureError> {
+        let mut texture = Self::default();
+        texture.rebuild(tiles, settings, objs, materials, images, layouts, &render_device.limits())?;
+        Ok(texture)
+    }
+
+    /// Takes the textures of every tile, newly loaded ones replacing those taken before, and packs
+    /// them into the pages again.
+    #[allow(clippy::too_many_arguments)]
+    pub fn rebuild(
+        &mut self,
+        tiles: &Tiles,
+        settings: TileAtlasSettings,
+        objs: &Assets<Obj>,
+        materials: &mut Assets<MtlCollection>,
+        images: &mut Assets<Image>,
+        layouts: &mut Assets<TextureAtlasLayout>,
+        limits: &WgpuLimits,
+    ) -> Result<(), TileTextureError> {
+        take_tile_images(tiles, objs, materials, images, self)?;
+        report_duplicates(self.dedupe());
+
+        let pages = build_tile_pages(self, settings, limits)?;
+        report_pages(&pages);
+
+        self.set_pages(pages, layouts, images);
+        if !settings.keep_sources {
+            self.release_sources();
+        }
+
+        Ok(())
+    }
+}
+
+/// Asks for the tile texture to be rebuilt, once for however many are sent in a frame.
+#[derive(Event, Copy, Clone, Default, Debug)]
+pub struct RebuildTileTexture;
+
+/// Sent once the tile texture was rebuilt, its pages and tile UVs possibly changed.
+#[derive(Event, Copy, Clone, Default, Debug)]
+pub struct TileTextureRebuilt;
+
+/// Counters from rebuilding the tile texture, for judging atlas packing performance.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct TileTextureStats {
+    /// Times the tile texture was rebuilt.
+    pub rebuilds: usize,
+    /// Time the last rebuild took.
+    pub last_rebuild: Duration,
+}
+
+#[derive(Error, Debug)]
+pub enum TileTextureError {
+    #[error("Tile `{0}` didn't load.")]
+    Tile(String),
+    #[error("The material of tile `{0}` didn't load.")]
+    Material(String),
+    #[error("Texture `{image}` of tile `{tile}` didn't load.")]
+    Image { tile: String, image: String },
+    #[error("Couldn't build the tile atlas: {0}")]
+    Atlas(#[from] TileAtlasError),
+}
+
+/// Why loading failed, shown in [`GameState::LoadError`].
+#[derive(Resource, Clone, Debug)]
+pub struct LoadError {
+    /// The asset that failed, if it was a single one.
+    pub path: Option<String>,
+    pub message: String,
+}
+
+/// Builds the [`TileTexture`] once every tile has been gathered, holding the loading state until it
+/// has. Failing enters [`GameState::LoadError`] instead.
+#[allow(clippy::too_many_arguments)]
+pub fn build_tile_texture(
+    mut commands: Commands,
+    tiles: Option<Res<Tiles>>,
+    built: Option<Res<TileTexture>>,
+    settings: Res<TileAtlasSettings>,
+    objs: Res<Assets<Obj>>,
+    render_device: Res<RenderDevice>,
+    mut materials: ResMut<Assets<MtlCollection>>,
+    mut images: ResMut<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut map_materials: ResMut<Assets<MapMaterial>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) -> Progress {
+    if built.is_some() {
+        return true.into()
+    }
+
+    let Some(tiles) = tiles else { return false.into() };
+    match TileTexture::build(
+        &tiles,
+        *settings,
+        &objs,
+        &mut materials,
+        &mut images,
+        &mut layouts,
+        &render_device,
+    ) {
+        Ok(texture) => {
+            commands.insert_resource(MapMaterials::new(&texture, &mut map_materials));
+            commands.insert_resource(texture);
+            true.into()
+        }
+        Err(e) => {
+            error!("{e}");
+            commands.insert_resource(LoadError {
+                path: None,
+                message: e.to_string(),
+            });
+            next_state.set(GameState::LoadError);
+            false.into()
+        }
+    }
+}
+
+/// Moves the textures of every tile out of `Assets<Image>` into the tile texture's sources, leaving
+/// weak handles in their materials. Textures already taken are kept unless a newer one was loaded.
+/// Fails if some tile hasn't loaded yet.
+pub fn take_tile_images(
+    tiles: &Tiles,
+    objs: &Assets<Obj>,
+    materials: &mut Assets<MtlCollection>,
+    images: &mut Assets<Image>,
+    texture: &mut TileTexture,
+) -> Result<(), TileTextureError> {
+    let (sources, released) = (&mut texture.sources, &mut texture.released);
+    let mut take = |tile: &str, slot: &mut Handle<Image>| {
+        let id = slot.id();
+        if !images.contains(id) && !sources.contains_key(&id) {
+            return Err(TileTextureError::Image {
+                tile: tile.into(),
+                image: slot.path().map_or_else(|| format!("{id:?}"), ToString::to_string),
+            })
+        }
+
+        let handle = std::mem::replace(slot, slot.clone_weak());
+        match (images.remove(id), sources.get_mut(&id)) {
+            (Some(image), Some(source)) => source.1 = image,
+            (Some(image), None) => {
+                // Released textures come back under the handle that kept them loadable.
+                let handle = released.remove(&id).unwrap_or_else(|| handle.clone());
+                sources.insert(id, (handle, image));
+            }
+            _ => {}
+        }
+
+        // Materials taken from before only have a weak handle left.
+        if let (true, Some(source)) = (handle.is_strong(), sources.get_mut(&id)) {
+            source.0 = handle;
+        }
+
+        Ok(id)
+    };
+
+    let mut maps = HashMap::new();
+    for (key, obj) in tiles.iter() {
+        let obj = objs.get(obj).ok_or_else(|| TileTextureError::Tile(key.clone()))?;
+        let mtl = materials
+            .get_mut(&obj.material)
+            .ok_or_else(|| TileTextureError::Material(key.clone()))?;
+
+        for mtl in mtl.values_mut() {
+            let Some(diffuse) = mtl.diffuse_texture.as_mut() else {
+                continue
+            };
+
+            let diffuse = take(key, diffuse)?;
+            let tile_maps = TileMaps {
+                normal: mtl.normal_texture.as_mut().map(|slot| take(key, slot)).transpose()?,
+                emissive: mtl.emissive_texture.as_mut().map(|slot| take(key, slot)).transpose()?,
+            };
+
+            // Variants are packed like any other diffuse texture, sharing the material's maps.
+            maps.insert(diffuse, tile_maps);
+            for variant in &mut mtl.diffuse_variants {
+                maps.insert(take(key, &mut variant.texture)?, tile_maps);
+            }
+        }
+    }
+
+    let used = maps
+        .iter()
+        .flat_map(|(&diffuse, maps)| [Some(diffuse), maps.normal, maps.emissive])
+        .flatten()
+        .collect::<HashSet<_>>();
+
+    texture.sources.retain(|id, _| used.contains(id));
+    texture.released.retain(|id, _| used.contains(id));
+    texture.maps = maps;
+    Ok(())
+}
+
+#[derive(Error, Debug)]
+pub enum TileAtlasError {
+    #[error("`{path}` is {}x{} pixels, too large for a {max}x{max} atlas page.", .size.x, .size.y)]
+    TooLarge { path: String, size: UVec2, max: u32 },
+    #[error("{count} tile textures don't fit in a texture array of {max} layers.")]
+    TooManyLayers { count: usize, max: u32 },
+    #[error("Tile textures must either all be compressed or none be; these differ from the rest: {}", .offenders.join(", "))]
+    MixedCompression { offenders: Vec<String> },
+    #[error(
+        "Compressed tile textures can't be resized, so must all be {}x{} {format:?}; these aren't: {}",
+        .size.x, .size.y, .offenders.join(", ")
+    )]
+    CompressedMismatch {
+        format: TextureFormat,
+        size: UVec2,
+        offenders: Vec<String>,
+    },
+    #[error(transparent)]
+    Build(#[from] TextureAtlasBuilderError),
+}
+
+/// Packs tile textures into as few atlas pages as it takes, with as many mip levels as the
+/// settings and the textures allow.
+pub fn build_tile_pages(
+    texture: &TileTexture,
+    settings: TileAtlasSettings,
+    limits: &WgpuLimits,
+) -> Result<Vec<BuiltPage>, TileAtlasError> {
+    let mut textures = texture
+        .maps
+        .keys()
+        .filter(|&id| !texture.aliases.contains_key(id))
+        .filter_map(|id| texture.sources.get_key_value(id))
+        .collect::<Vec<_>>();
+
+    let max = limits.max_texture_dimension_2d;
+    let compressed = textures
+        .iter()
+        .filter(|(_, (_, image))| image.texture_descriptor.format.is_compressed())
+        .count();
+
+    if compressed > 0 {
+        // Compressed textures can't be packed into an atlas, so they're always stacked.
+        let mut page = build_compressed_tile_array(&textures, compressed, max, limits.max_texture_array_layers)?;
+        page.atlas.sampler = settings.sampler(ImageAddressMode::Repeat);
+        return Ok(vec![page])
+    }
+
+    let levels = settings.mip_levels(textures.iter().map(|(_, (_, image))| image));
+    let padding = settings.padding(levels);
+    if settings.backend == TileBackend::Array && !textures.is_empty() {
+        let mut page = build_tile_array(&textures, max, limits.max_texture_array_layers, levels)?;
+        // Tiles may repeat their textures across faces larger than a tile.
+        page.atlas.sampler = settings.sampler(ImageAddressMode::Repeat);
+        return Ok(vec![page])
+    }
+
+    let sampler = settings.sampler(ImageAddressMode::ClampToEdge);
+
+    // Largest first, so splitting a page in halves splits its area evenly.
+    textures.sort_by_key(|(_, (_, image))| Reverse(image.width() * image.height()));
+
+    let mut pages = Vec::new();
+    let mut groups = match textures.is_empty() {
+        true => Vec::new(),
+        false => vec![textures],
+    };
+
+    while let Some(group) = groups.pop() {
+        match build_tile_page(&group, max, padding, levels) {
+            Ok((layout, atlas)) => {
+                let auxiliary = |maps: fn(&TileMaps) -> Option<AssetId<Image>>, format, neutral| {
+                    let textures = group
+                        .iter()
+                        .filter_map(|&(&id, _)| Some((id, &texture.sources.get(&maps(texture.maps.get(&id)?)?)?.1)))
+                        .collect::<Vec<_>>();
+
+                    // Pages without any such texture go without, rather than sampling a blank one.
+                    (!textures.is_empty()).then(|| {
+                        let mut atlas = build_parallel_atlas(&layout, textures, format, neutral);
+                        extrude_edges(&mut atlas, &layout, padding / 2);
+                        generate_mipmaps(&mut atlas, levels);
+                        atlas.sampler = sampler.clone();
+                        atlas
+                    })
+                };
+
+                pages.push(BuiltPage {
+                    layers: None,
+                    normal_atlas: auxiliary(|maps| maps.normal, TextureFormat::Rgba8Unorm, [128, 128, 255, 255]),
+                    emissive_atlas: auxiliary(|maps| maps.emissive, TextureFormat::Rgba8UnormSrgb, [0, 0, 0, 255]),
+                    atlas: Image {
+                        sampler: sampler.clone(),
+                        ..atlas
+                    },
+                    layout,
+                });
+            }
+            Err(TextureAtlasBuilderError::NotEnoughSpace) if group.len() > 1 => {
+                let (mut even, mut odd) = (Vec::new(), Vec::new());
+                for (index, texture) in group.into_iter().enumerate() {
+                    match index % 2 {
+                        0 => even.push(texture),
+                        _ => odd.push(texture),
+                    }
+                }
+
+                groups.extend([odd, even]);
+            }
+            Err(TextureAtlasBuilderError::NotEnoughSpace) => {
+                let (id, (handle, image)) = group[0];
+                return Err(TileAtlasError::TooLarge {
+                    path: source_name(id, handle),
+                    size: image.size(),
+                    max,
+                })
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(pages)
+}
+
+/// The asset path of a source texture, or its ID if it has none.
+#[inline]
+fn source_name(id: &AssetId<Image>, handle: &Handle<Image>) -> String {
+    handle.path().map_or_else(|| format!("{id:?}"), ToString::to_string)
+}
+
+#[allow(clippy::type_complexity)]
+fn check_array_limits(
+    textures: &[(&AssetId<Image>, &(Handle<Image>, Image))],
+    max: u32,
+    max_layers: u32,
+) -> Result<(), TileAtlasError> {
+    if let Some(&(id, (handle, image))) = textures.iter().find(|(_, (_, image))| image.size().max_element() > max) {
+        return Err(TileAtlasError::TooLarge {
+            path: source_name(id, handle),
+            size: image.size(),
+            max,
+        })
+    }
+
+    if textures.len() > max_layers as usize {
+        return Err(TileAtlasError::TooManyLayers {
+            count: textures.len(),
+            max: max_layers,
+        })
+    }
+
+    Ok(())
+}
+
+/// Stacks compressed tile textures as they are, failing if some aren't compressed or don't share
+/// the format and size most of them have.
+#[allow(clippy::type_complexity)]
+fn build_compressed_tile_array(
+    textures: &[(&AssetId<Image>, &(Handle<Image>, Image))],
+    compressed: usize,
+    max: u32,
+    max_layers: u32,
+) -> Result<BuiltPage, TileAtlasError> {
+    let offenders = |offends: &dyn Fn(&Image) -> bool| {
+        let mut offenders = textures
+            .iter()
+            .filter(|(_, (_, image))| offends(image))
+            .map(|&(id, (handle, _))| source_name(id, handle))
+            .collect::<Vec<_>>();
+
+        offenders.sort_unstable();
+        offenders
+    };
+
+    if compressed < textures.len() {
+        // Neither kind can be converted into the other here, so whichever is fewer is listed.
+        let uncompressed = compressed * 2 >= textures.len();
+        return Err(TileAtlasError::MixedCompression {
+            offenders: offenders(&|image| image.texture_descriptor.format.is_compressed() != uncompressed),
+        })
+    }
+
+    let mut kinds = HashMap::<_, usize>::new();
+    for (_, (_, image)) in textures {
+        *kinds.entry((image.texture_descriptor.format, image.size())).or_default() += 1;
+    }
+
+    let (format, size) = kinds
+        .into_iter()
+        .max_by_key(|&((format, size), count)| (count, Reverse((size.x, size.y, format!("{format:?}")))))
+        .map(|(kind, _)| kind)
+        .unwrap_or((TextureFormat::Rgba8UnormSrgb, UVec2::ONE));
+
+    let mismatched = offenders(&|image| (image.texture_descriptor.format, image.size()) != (format, size));
+    if !mismatched.is_empty() {
+        return Err(TileAtlasError::CompressedMismatch {
+            format,
+            size,
+            offenders: mismatched,
+        })
+    }
+
+    check_array_limits(textures, max, max_layers)?;
+
+    let array = build_compressed_array(textures.iter().map(|(_, (_, image))| image));
+    Ok(BuiltPage {
+        layout: TextureAtlasLayout::new_empty(array.size()),
+        atlas: array,
+        normal_atlas: None,
+        emissive_atlas: None,
+        layers: Some(
+            textures
+                .iter()
+                .enumerate()
+                .map(|(layer, &(&id, _))| (id, layer as u32))
+                .collect(),
+        ),
+    })
+}
+
+#[allow(clippy::type_complexity)]
+fn build_tile_array(
+    textures: &[(&AssetId<Image>, &(Handle<Image>, Image))],
+    max: u32,
+    max_layers: u32,
+    levels: u32,
+) -> Result<BuiltPage, TileAtlasError> {
+    check_array_limits(textures, max, max_layers)?;
+
+    let array = build_texture_array(textures.iter().map(|(_, (_, image))| image), levels);
+    Ok(BuiltPage {
+        layout: TextureAtlasLayout::new_empty(array.size()),
+        atlas: array,
+        normal_atlas: None,
+        emissive_atlas: None,
+        layers: Some(
+            textures
+                .iter()
+                .enumerate()
+                .map(|(layer, &(&id, _))| (id, layer as u32))
+                .collect(),
+        ),
+    })
+}
+
+#[allow(clippy::type_complexity)]
+fn build_tile_page(
+    textures: &[(&AssetId<Image>, &(Handle<Image>, Image))],
+    max: u32,
+    padding: u32,
+    levels: u32,
+) -> Result<(TextureAtlasLayout, Image), TextureAtlasBuilderError> {
+    let mut builder = TextureAtlasBuilder::default();
+    builder
+        .max_size(UVec2::splat(max))
+        .format(TextureFormat::Rgba8UnormSrgb)
+        .auto_format_conversion(true)
+        .padding(UVec2::splat(padding));
+
+    for &(&id, (_, image)) in textures {
+        builder.add_texture(Some(id), image);
+    }
+
+    let (layout, mut atlas) = builder.build()?;
+    atlas.asset_usage = TILE_TEXTURE_USAGE;
+
+    extrude_edges(&mut atlas, &layout, padding / 2);
+    generate_mipmaps(&mut atlas, levels);
+
+    Ok((layout, atlas))
+}
+
+/// Requests the tile atlas be repacked when tiles are registered, or when a tile or its textures
+/// change on disk, which only happens with Bevy's file watcher.
+#[allow(clippy::type_complexity)]
+pub fn reload_tile_texture(
+    (mut obj_events, mut mtl_events, mut image_events, mut registered): (
+        EventReader<AssetEvent<Obj>>,
+        EventReader<AssetEvent<MtlCollection>>,
+        EventReader<AssetEvent<Image>>,
+        EventReader<TileRegistered>,
+    ),
+    tiles: Res<Tiles>,
+    objs: Res<Assets<Obj>>,
+    tile_texture: Res<TileTexture>,
+    mut rebuilds: EventWriter<RebuildTileTexture>,
+) {
+    let tile_objs = tiles.values().map(Handle::id).collect::<HashSet<_>>();
+    let tile_mtls = tiles
+        .values()
+        .filter_map(|obj| Some(objs.get(obj)?.material.id()))
+        .collect::<HashSet<_>>();
+
+    let mut stale = obj_events
+        .read()
+        .any(|&e| matches!(e, AssetEvent::Modified { id } if tile_objs.contains(&id)));
+    stale |= mtl_events
+        .read()
+        .any(|&e| matches!(e, AssetEvent::Modified { id } if tile_mtls.contains(&id)));
+    stale |= image_events.read().any(
+        |&e| matches!(e, AssetEvent::Added { id } | AssetEvent::Modified { id } if tile_texture.sources.contains_key(&id) || tile_texture.released.contains_key(&id)),
+    );
+    stale |= registered.read().count() > 0;
+
+    if stale {
+        rebuilds.send(RebuildTileTexture);
+    }
+}
+
+/// Repacks the tile atlas from every tile's textures, keeping the handles of its pages. Sends
+/// [`TileTextureRebuilt`] once done, for maps and materials to follow the new pages. Fails if some
+/// tile's textures aren't loaded, such as released ones.
+pub fn rebuild_tile_texture(world: &mut World) -> Result<(), TileTextureError> {
+    let start = Instant::now();
+    let limits = world
+        .get_resource::<RenderDevice>()
+        .map_or_else(WgpuLimits::default, RenderDevice::limits);
+
+    let mut state = SystemState::<(
+        Res<Tiles>,
+        Res<TileAtlasSettings>,
+        Res<Assets<Obj>>,
+        ResMut<TileTexture>,
+        ResMut<Assets<MtlCollection>>,
+        ResMut<Assets<Image>>,
+        ResMut<Assets<TextureAtlasLayout>>,
+    )>::new(world);
+
+    let (tiles, settings, objs, mut texture, mut materials, mut images, mut layouts) = state.get_mut(world);
+    texture.rebuild(&tiles, *settings, &objs, &mut materials, &mut images, &mut layouts, &limits)?;
+
+    let mut stats = world.get_resource_or_insert_with(TileTextureStats::default);
+    stats.rebuilds += 1;
+    stats.last_rebuild = start.elapsed();
+
+    world.send_event(TileTextureRebuilt);
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct TileReload {
+    requests: ManualEventReader<RebuildTileTexture>,
+    /// Whether a rebuild is waiting on tiles or their textures to load.
+    waiting: bool,
+}
+
+/// Rebuilds the tile texture once for all the [`RebuildTileTexture`] requests sent since last run.
+/// Rebuilds needing tiles or textures that aren't loaded, such as released ones, are retried every
+/// frame until they are.
+pub fn service_tile_rebuilds(world: &mut World, mut reload: Local<TileReload>) {
+    let requested = reload.requests.read(world.resource::<Events<RebuildTileTexture>>()).count() > 0;
+    if !requested && !reload.waiting {
+        return
+    }
+
+    match rebuild_tile_texture(world) {
+        Ok(()) => reload.waiting = false,
+        Err(TileTextureError::Tile(..) | TileTextureError::Material(..) | TileTextureError::Image { .. }) => {
+            if !std::mem::replace(&mut reload.waiting, true) {
+                world
+                    .resource::<TileTexture>()
+                    .reload_released(world.resource::<AssetServer>(), world.resource::<Assets<Image>>());
+            }
+        }
+        Err(e) => {
+            reload.waiting = false;
+            warn!("Couldn't rebuild the tile atlas: {e}");
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    path::Path,
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use bevy::{prelude::*, utils::HashMap};
+
+/// Identifies a tile by a logical name such as `liminal.floor`, which stays the same when its asset
+/// is moved or renamed. Maps store tiles by key.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Deref)]
+pub struct TileKey(pub String);
+
+impl TileKey {
+    /// The key a tile is given when the manifest doesn't name it: its folders under `tiles/`, file,
+    /// and object, separated by dots. The object is left out if it's the file's only one.
+    pub fn from_path(path: &str) -> Self {
+        let (file, label) = path.split_once('#').unwrap_or((path, ""));
+        let file = Path::new(file.strip_prefix("tiles/").unwrap_or(file));
+        let object = label.strip_prefix("obj:").unwrap_or(label);
+
+        let mut parts = file
+            .parent()
+            .into_iter()
+            .flat_map(Path::iter)
+            .filter_map(|dir| dir.to_str())
+            .collect::<Vec<_>>();
+
+        let stem = file.file_stem().and_then(|stem| stem.to_str()).unwrap_or(path);
+        parts.push(stem);
+        if !matches!(object, "" | "tile") && object != stem {
+            parts.push(object);
+        }
+
+        Self(parts.join("."))
+    }
+}
+
+/// Translates tile names found in maps and brushes into [`TileKey`]s and asset paths. Shared with
+/// the map loader, which resolves tiles off the main thread.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct TileNames(Arc<RwLock<TileNameTable>>);
+
+impl TileNames {
+    #[inline]
+    pub fn read(&self) -> RwLockReadGuard<'_, TileNameTable> {
+        self.0.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    #[inline]
+    pub fn write(&self) -> RwLockWriteGuard<'_, TileNameTable> {
+        self.0.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct TileNameTable {
+    /// The asset path of every tile key. Tiles registered from prebuilt assets have none.
+    pub paths: HashMap<String, Option<String>>,
+    /// The key of every tile asset path, for maps saved when tiles were stored by path.
+    pub keys: HashMap<String, String>,
+    /// Where renamed or moved tiles are found now, from their old key or path to their current key
+    /// or path.
+    pub redirects: HashMap<String, String>,
+}
+
+impl TileNameTable {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Adds a tile, returning `false` without changing anything if its key is taken.
+    pub fn insert(&mut self, key: &str, path: Option<&str>) -> bool {
+        if self.paths.contains_key(key) {
+            return false
+        }
+
+        self.paths.insert(key.into(), path.map(Into::into));
+        if let Some(path) = path {
+            self.keys.insert(path.into(), key.into());
+        }
+
+        true
+    }
+
+    /// The current key of a tile named by its key or asset path, following a redirect if it has
+    /// been renamed.
+    pub fn resolve<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        self.direct(name).or_else(|| self.direct(self.redirects.get(name)?))
+    }
+
+    /// The asset path of a tile key, if it has one.
+    #[inline]
+    pub fn path(&self, key: &str) -> Option<&str> {
+        self.paths.get(key)?.as_deref()
+    }
+
+    fn direct<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        match self.paths.get_key_value(name) {
+            Some((key, _)) => Some(key),
+            None => self.keys.get(name).map(String::as_str),
+        }
+    }
+}
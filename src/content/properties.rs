@@ -0,0 +1,213 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::Deserialize;
+
+use crate::{
+    content::{
+        manifest::{TileEntry, TileManifest},
+        register::TileRegistered,
+        TileFolder, TileTexture, TileTextureRebuilt, Tiles,
+    },
+    map::Map,
+    obj::def::Obj,
+};
+
+/// How a tile behaves in play and in the editor, from its `.tile` file and the manifest's
+/// overrides.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TileProperties {
+    /// Whether characters may stand on the tile.
+    pub walkable: bool,
+    /// Friction of the tile's collider.
+    pub friction: f32,
+    /// What crossing the tile costs, relative to other tiles.
+    pub move_cost: f32,
+    /// What the tile is made of, for footsteps and decals.
+    pub surface: SurfaceTag,
+    /// Whether the editor turns the tile to the placement rotation; unrotatable tiles are placed
+    /// unturned.
+    pub rotatable: bool,
+    /// How the tile turns itself to its neighbors.
+    pub auto_orient: AutoOrient,
+    /// Whether the tile is a trigger volume, sensing what enters its cells instead of being drawn
+    /// or solid.
+    pub sensor: bool,
+}
+
+impl Default for TileProperties {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            walkable: true,
+            friction: 0.5,
+            move_cost: 1.0,
+            surface: SurfaceTag::default(),
+            rotatable: true,
+            auto_orient: AutoOrient::default(),
+            sensor: false,
+        }
+    }
+}
+
+impl TileProperties {
+    /// Resolves a tile's properties from its `.tile` file, overridden by its manifest entry.
+    pub fn resolve(obj: Option<&Obj>, entry: Option<&TileEntry>) -> Self {
+        let mut properties = Self::default();
+        if let Some(obj) = obj {
+            obj.properties.apply(&mut properties);
+        }
+
+        if let Some(entry) = entry {
+            entry.properties().apply(&mut properties);
+        }
+
+        properties
+    }
+}
+
+/// What a tile is made of, such as `surface: Some(Metal)`.
+#[derive(Deserialize, Copy, Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub enum SurfaceTag {
+    #[default]
+    Concrete,
+    Metal,
+    Carpet,
+    Water,
+}
+
+/// How a tile turns itself as it or the cells beside it on its layer are edited, such as
+/// `auto_orient: Some(TowardEmpty)`. A tile faces along its `+Z` side; cells out of bounds count as
+/// solid.
+#[derive(Deserialize, Copy, Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub enum AutoOrient {
+    /// Keeps the orientation it's placed with.
+    #[default]
+    None,
+    /// Backs onto a solid neighbor, preferring to face an empty one, like a wall-mounted fixture.
+    AwayFromSolid,
+    /// Faces an empty neighbor, preferring to back onto a solid one, like a wall lining a room.
+    TowardEmpty,
+}
+
+impl AutoOrient {
+    /// The quarter turns a tile should be at, given whether the neighbor each number of quarter
+    /// turns faces is solid. Keeps the current turns if they're as good as any; returns `None` if
+    /// no turn suits the tile.
+    pub fn turns(self, solid: [bool; 4], current: i32) -> Option<i32> {
+        let score = |turns: usize| {
+            let (front, back) = (solid[turns], solid[(turns + 2) % 4]);
+            match self {
+                Self::None => None,
+                Self::AwayFromSolid => back.then_some(1 + !front as u8),
+                Self::TowardEmpty => (!front).then_some(1 + back as u8),
+            }
+        };
+
+        let best = (0..4).filter_map(score).max()?;
+        let current = current.rem_euclid(4);
+        match score(current as usize) == Some(best) {
+            true => Some(current),
+            false => (0..4).find(|&turns| score(turns) == Some(best)).map(|turns| turns as i32),
+        }
+    }
+}
+
+/// Properties given by a `.tile` file or manifest entry, over the ones before it.
+#[derive(Deserialize, Copy, Clone, Default, PartialEq, Debug)]
+#[serde(default)]
+pub struct TilePropertyOverrides {
+    pub walkable: Option<bool>,
+    pub friction: Option<f32>,
+    pub move_cost: Option<f32>,
+    pub surface: Option<SurfaceTag>,
+    pub rotatable: Option<bool>,
+    pub auto_orient: Option<AutoOrient>,
+    pub sensor: Option<bool>,
+}
+
+impl TilePropertyOverrides {
+    #[inline]
+    pub fn apply(self, properties: &mut TileProperties) {
+        if let Some(walkable) = self.walkable {
+            properties.walkable = walkable;
+        }
+
+        if let Some(friction) = self.friction {
+            properties.friction = friction;
+        }
+
+        if let Some(move_cost) = self.move_cost {
+            properties.move_cost = move_cost;
+        }
+
+        if let Some(surface) = self.surface {
+            properties.surface = surface;
+        }
+
+        if let Some(rotatable) = self.rotatable {
+            properties.rotatable = rotatable;
+        }
+
+        if let Some(auto_orient) = self.auto_orient {
+            properties.auto_orient = auto_orient;
+        }
+
+        if let Some(sensor) = self.sensor {
+            properties.sensor = sensor;
+        }
+    }
+}
+
+/// Each tile's [`TileProperties`], keyed by [`TileKey`](crate::content::names::TileKey). Tiles
+/// left out have the defaults.
+#[derive(Resource, Clone, Default, PartialEq, Debug)]
+pub struct TilePropertyTable(pub HashMap<String, TileProperties>);
+
+impl TilePropertyTable {
+    #[inline]
+    pub fn get(&self, key: &str) -> TileProperties {
+        self.0.get(key).copied().unwrap_or_default()
+    }
+
+    /// The properties of each tile a map uses, indexed as its cells' tiles are.
+    #[inline]
+    pub fn for_map(&self, map: &Map) -> Vec<TileProperties> {
+        map.tile_set.iter().map(|key| self.get(key)).collect()
+    }
+}
+
+/// Resolves tiles' properties once the tile texture is built or rebuilt, or tiles are registered.
+#[allow(clippy::too_many_arguments)]
+pub fn update_tile_properties(
+    mut rebuilt: EventReader<TileTextureRebuilt>,
+    mut registered: EventReader<TileRegistered>,
+    texture: Res<TileTexture>,
+    tiles: Res<Tiles>,
+    folder: Option<Res<TileFolder>>,
+    manifests: Res<Assets<TileManifest>>,
+    objs: Res<Assets<Obj>>,
+    mut table: ResMut<TilePropertyTable>,
+) {
+    if rebuilt.read().count() + registered.read().count() == 0 && !texture.is_added() {
+        return
+    }
+
+    let manifest = folder.and_then(|folder| manifests.get(&folder.manifest));
+    let entries = {
+        let names = tiles.names.read();
+        manifest
+            .into_iter()
+            .flat_map(|manifest| &manifest.tiles)
+            .filter_map(|entry| Some((names.resolve(&entry.path)?.to_string(), entry)))
+            .collect::<HashMap<_, _>>()
+    };
+
+    let properties = tiles
+        .iter()
+        .filter_map(|(key, handle)| {
+            let properties = TileProperties::resolve(objs.get(handle), entries.get(key).copied());
+            (properties != TileProperties::default()).then(|| (key.clone(), properties))
+        })
+        .collect();
+
+    table.set_if_neq(TilePropertyTable(properties));
+}
@@ -0,0 +1,165 @@
+use bevy::{ecs::world::Command, prelude::*, utils::HashSet};
+
+use crate::{
+    content::{
+        manifest::{TileCatalog, TileManifest},
+        names::TileKey,
+        TileFolder, Tiles,
+    },
+    map::Map,
+    obj::def::{Mtl, MtlCollection, Obj},
+};
+
+/// Sent once a tile is added to [`Tiles`] after loading. The tile atlas is repacked with it, and
+/// maps are remeshed for the new UVs.
+#[derive(Event, Clone, Debug)]
+pub struct TileRegistered(pub TileKey);
+
+/// Keys of tiles registered from prebuilt assets rather than loaded from a path.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct PrebuiltTiles(pub HashSet<String>);
+
+/// Adds tiles after the loading state, such as from mods or procedural content.
+pub trait ContentCommands {
+    /// Loads a tile from an asset path, such as `mods/rocks.obj#obj:boulder`, keyed as by
+    /// [`TileKey::from_path`].
+    fn register_tile(&mut self, path: impl Into<String>) -> TileKey;
+
+    /// Adds a tile from an object and its diffuse texture, keyed by a name that isn't an asset
+    /// path. Maps may use it only while it's registered, as it can't be loaded on its own.
+    fn register_prebuilt_tile(&mut self, name: impl Into<String>, obj: Obj, texture: Image) -> TileKey;
+}
+
+impl ContentCommands for Commands<'_, '_> {
+    fn register_tile(&mut self, path: impl Into<String>) -> TileKey {
+        let path = path.into();
+        let key = TileKey::from_path(&path);
+        self.add(RegisterTile {
+            key: key.clone(),
+            source: TileSource::Path(path),
+        });
+        key
+    }
+
+    fn register_prebuilt_tile(&mut self, name: impl Into<String>, obj: Obj, texture: Image) -> TileKey {
+        let key = TileKey(name.into());
+        self.add(RegisterTile {
+            key: key.clone(),
+            source: TileSource::Prebuilt(Box::new((obj, texture))),
+        });
+        key
+    }
+}
+
+enum TileSource {
+    Path(String),
+    Prebuilt(Box<(Obj, Image)>),
+}
+
+struct RegisterTile {
+    key: TileKey,
+    source: TileSource,
+}
+
+impl Command for RegisterTile {
+    fn apply(self, world: &mut World) {
+        let Self { key, source } = self;
+        let Some(tiles) = world.get_resource::<Tiles>() else {
+            warn!("Tile `{}` was registered before tiles were loaded.", *key);
+            return
+        };
+
+        {
+            let mut names = tiles.names.write();
+            let path = match source {
+                TileSource::Path(ref path) => Some(path.as_str()),
+                TileSource::Prebuilt(..) => None,
+            };
+
+            // Registering again keeps the tile as it is, rather than packing its texture twice. The
+            // key given for it still finds it if the manifest keyed it otherwise.
+            if let Some(existing) = path.and_then(|path| names.keys.get(path)).cloned() {
+                if existing != *key {
+                    names.redirects.insert(key.0, existing);
+                }
+                return
+            }
+
+            if !names.insert(&key, path) {
+                warn!("Tile `{}` can't be registered, as its key is taken.", *key);
+                return
+            }
+        }
+
+        let obj = match source {
+            TileSource::Path(path) => world.resource::<AssetServer>().load::<Obj>(path),
+            TileSource::Prebuilt(prebuilt) => {
+                let (mut obj, texture) = *prebuilt;
+                let texture = world.resource_mut::<Assets<Image>>().add(texture);
+                obj.material = world.resource_mut::<Assets<MtlCollection>>().add(MtlCollection {
+                    materials: [(key.0.clone(), Mtl {
+                        diffuse_texture_id: Some(texture.id()),
+                        diffuse_texture: Some(texture),
+                        ..default()
+                    })]
+                    .into_iter()
+                    .collect(),
+                });
+                obj.material_key.clone_from(&key.0);
+
+                world.resource_mut::<PrebuiltTiles>().insert(key.0.clone());
+                world.resource_mut::<Assets<Obj>>().add(obj)
+            }
+        };
+
+        world.resource_mut::<Tiles>().tiles.insert(key.0.clone(), obj);
+        world.send_event(TileRegistered(key));
+    }
+}
+
+/// Describes registered tiles in the palette, from the manifest if it lists them.
+pub fn update_tile_catalog(
+    mut events: EventReader<TileRegistered>,
+    tiles: Res<Tiles>,
+    folder: Res<TileFolder>,
+    manifests: Res<Assets<TileManifest>>,
+    mut catalog: ResMut<TileCatalog>,
+) {
+    if events.read().count() > 0 {
+        *catalog = TileCatalog::new(&tiles, manifests.get(&folder.manifest));
+    }
+}
+
+/// Points maps at prebuilt tiles, which they couldn't load by path themselves.
+pub fn resolve_prebuilt_tiles(prebuilt: Res<PrebuiltTiles>, tiles: Res<Tiles>, mut maps: ResMut<Assets<Map>>) {
+    if prebuilt.is_empty() {
+        return
+    }
+
+    let prebuilt_tile = |name: &str| {
+        tiles
+            .resolve(name)
+            .filter(|key| prebuilt.contains(key))
+            .and_then(|key| tiles.get(&key))
+    };
+
+    let stale = maps
+        .iter()
+        .filter(|(_, map)| {
+            map.tile_set
+                .iter()
+                .zip(&map.tile_handles)
+                .any(|(name, handle)| prebuilt_tile(name).is_some_and(|tile| tile != handle))
+        })
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+
+    for id in stale {
+        let Some(map) = maps.get_mut(id) else { continue };
+        for (name, handle) in map.tile_set.iter().zip(&mut map.tile_handles) {
+            if let Some(tile) = prebuilt_tile(name) {
+                handle.clone_from(tile);
+            }
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use std::hash::{Hash, Hasher};
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::Deserialize;
+
+use crate::{
+    content::{
+        array::MapMaterial,
+        manifest::{TileEntry, TileManifest},
+        properties::TileProperties,
+        register::TileRegistered,
+        TileFolder, TileTexture, TileTextureRebuilt, Tiles,
+    },
+    obj::def::{Mtl, MtlCollection, Obj},
+};
+
+/// How a tile's texture alpha is drawn.
+#[derive(Reflect, Deserialize, Copy, Clone, Default, Debug)]
+pub enum TileAlpha {
+    #[default]
+    Opaque,
+    /// Discards texels with alpha below the cutoff.
+    Mask(f32),
+    Blend,
+}
+
+impl TileAlpha {
+    #[inline]
+    fn bits(self) -> (u8, u32) {
+        match self {
+            Self::Opaque => (0, 0),
+            Self::Mask(cutoff) => (1, cutoff.to_bits()),
+            Self::Blend => (2, 0),
+        }
+    }
+}
+
+impl PartialEq for TileAlpha {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.bits() == other.bits()
+    }
+}
+
+impl Eq for TileAlpha {}
+
+impl From<TileAlpha> for AlphaMode {
+    #[inline]
+    fn from(alpha: TileAlpha) -> Self {
+        match alpha {
+            TileAlpha::Opaque => Self::Opaque,
+            TileAlpha::Mask(cutoff) => Self::Mask(cutoff),
+            TileAlpha::Blend => Self::Blend,
+        }
+    }
+}
+
+/// How a tile is drawn, from its material and the manifest's overrides. Maps mesh tiles drawn
+/// differently apart, each with its own material.
+#[derive(Reflect, Copy, Clone, Debug)]
+pub struct TileRenderFlags {
+    pub alpha_mode: TileAlpha,
+    /// Scales the tile's emissive texture.
+    pub emissive_boost: f32,
+    pub unlit: bool,
+    /// Draws back faces too, lit from their own side.
+    pub double_sided: bool,
+    /// Leaves the tile out of map meshes, as trigger volumes are.
+    pub hidden: bool,
+}
+
+impl Default for TileRenderFlags {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            alpha_mode: TileAlpha::Opaque,
+            emissive_boost: 1.0,
+            unlit: false,
+            double_sided: false,
+            hidden: false,
+        }
+    }
+}
+
+impl TileRenderFlags {
+    /// Resolves a tile's flags from its material, overridden by its manifest entry where they
+    /// disagree.
+    pub fn resolve(key: &str, mtl: Option<&Mtl>, entry: Option<&TileEntry>) -> Self {
+        let mut flags = Self::default();
+        let dissolve = mtl.and_then(|mtl| mtl.dissolve);
+        let illum = mtl.and_then(|mtl| mtl.illum);
+
+        if dissolve.is_some_and(|dissolve| dissolve < 1.0) {
+            flags.alpha_mode = TileAlpha::Blend;
+        }
+
+        if illum == Some(0) {
+            flags.unlit = true;
+        }
+
+        let Some(entry) = entry else { return flags };
+        if let Some(alpha_mode) = entry.alpha_mode {
+            if dissolve.is_some() && alpha_mode != flags.alpha_mode {
+                debug!("The manifest draws `{key}` as {alpha_mode:?}, overriding its material's `d`.");
+            }
+
+            flags.alpha_mode = alpha_mode;
+        }
+
+        if let Some(unlit) = entry.unlit {
+            if illum.is_some() && unlit != flags.unlit {
+                debug!(
+                    "The manifest draws `{key}` {}, overriding its material's `illum`.",
+                    if unlit { "unlit" } else { "lit" }
+                );
+            }
+
+            flags.unlit = unlit;
+        }
+
+        if let Some(emissive_boost) = entry.emissive_boost {
+            if mtl.is_some_and(|mtl| mtl.emissive_texture.is_none()) {
+                debug!("The manifest boosts `{key}`'s emission, but its material has no `map_Ke`.");
+            }
+
+            flags.emissive_boost = emissive_boost;
+        }
+
+        if let Some(double_sided) = entry.double_sided {
+            flags.double_sided = double_sided;
+        }
+
+        flags
+    }
+
+    /// Whether tiles with these flags can share the map's own material.
+    #[inline]
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Draws a map material with these flags.
+    pub fn apply(&self, material: &mut MapMaterial) {
+        let base = &mut material.base;
+        base.alpha_mode = self.alpha_mode.into();
+        base.unlit |= self.unlit;
+        base.emissive *= self.emissive_boost;
+        if self.double_sided {
+            base.double_sided = true;
+            base.cull_mode = None;
+        }
+    }
+
+    #[inline]
+    fn bits(&self) -> ((u8, u32), u32, bool, bool, bool) {
+        (
+            self.alpha_mode.bits(),
+            self.emissive_boost.to_bits(),
+            self.unlit,
+            self.double_sided,
+            self.hidden,
+        )
+    }
+}
+
+impl PartialEq for TileRenderFlags {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.bits() == other.bits()
+    }
+}
+
+impl Eq for TileRenderFlags {}
+
+impl Hash for TileRenderFlags {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bits().hash(state);
+    }
+}
+
+/// Each tile's [`TileRenderFlags`], keyed by [`TileKey`](crate::content::names::TileKey). Tiles
+/// left out are drawn with the defaults.
+#[derive(Resource, Clone, Default, PartialEq, Debug)]
+pub struct TileRenders(pub HashMap<String, TileRenderFlags>);
+
+impl TileRenders {
+    #[inline]
+    pub fn get(&self, key: &str) -> TileRenderFlags {
+        self.0.get(key).copied().unwrap_or_default()
+    }
+}
+
+/// Resolves tiles' render flags once the tile texture is built or rebuilt, or tiles are registered.
+#[allow(clippy::too_many_arguments)]
+pub fn update_tile_renders(
+    mut rebuilt: EventReader<TileTextureRebuilt>,
+    mut registered: EventReader<TileRegistered>,
+    texture: Res<TileTexture>,
+    tiles: Res<Tiles>,
+    folder: Option<Res<TileFolder>>,
+    manifests: Res<Assets<TileManifest>>,
+    objs: Res<Assets<Obj>>,
+    materials: Res<Assets<MtlCollection>>,
+    mut renders: ResMut<TileRenders>,
+) {
+    if rebuilt.read().count() + registered.read().count() == 0 && !texture.is_added() {
+        return
+    }
+
+    let manifest = folder.and_then(|folder| manifests.get(&folder.manifest));
+    let entries = {
+        let names = tiles.names.read();
+        manifest
+            .into_iter()
+            .flat_map(|manifest| &manifest.tiles)
+            .filter_map(|entry| Some((names.resolve(&entry.path)?.to_string(), entry)))
+            .collect::<HashMap<_, _>>()
+    };
+
+    let flags = tiles
+        .iter()
+        .filter_map(|(key, handle)| {
+            let obj = objs.get(handle);
+            let mtl = obj.and_then(|obj| materials.get(&obj.material)?.get(&obj.material_key));
+
+            let entry = entries.get(key).copied();
+            let flags = TileRenderFlags {
+                hidden: TileProperties::resolve(obj, entry).sensor,
+                ..TileRenderFlags::resolve(key, mtl, entry)
+            };
+            (!flags.is_default()).then(|| (key.clone(), flags))
+        })
+        .collect();
+
+    renders.set_if_neq(TileRenders(flags));
+}
@@ -0,0 +1,250 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    core_pipeline::tonemapping::Tonemapping,
+    prelude::*,
+    render::{
+        camera::{RenderTarget, ScalingMode},
+        mesh::PrimitiveTopology,
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+        view::RenderLayers,
+    },
+    utils::HashMap,
+};
+use nonmax::NonMaxU8;
+
+use crate::{
+    content::{array::MapMaterial, render::TileRenders, TileTexture, TileTextureRebuilt, Tiles},
+    map::{Map, MapPart},
+    obj::def::{MtlCollection, Obj},
+};
+
+/// Width and height of every tile thumbnail, in pixels.
+pub const THUMBNAIL_SIZE: u32 = 64;
+/// The render layer thumbnails are drawn on, away from every other camera.
+pub const THUMBNAIL_LAYER: usize = 31;
+/// Frames each thumbnail is drawn for, so a material whose pipeline is still compiling on the first
+/// still shows on the next.
+const THUMBNAIL_FRAMES: u32 = 2;
+/// Where thumbnails are viewed from, relative to the tile's center.
+const THUMBNAIL_VIEW: Vec3 = Vec3::new(1.0, 0.8, 1.2);
+
+/// A small rendered preview of every tile, keyed like [`Tiles`].
+#[derive(Resource, Default, Deref)]
+pub struct TileThumbnails(pub HashMap<String, Handle<Image>>);
+
+/// Tiles waiting for their thumbnail to be drawn, a tile at a time so drawing them doesn't hitch.
+#[derive(Resource, Default)]
+pub struct ThumbnailQueue {
+    pub pending: VecDeque<String>,
+    /// The tile being drawn, and the frames it's drawn for still.
+    pub current: Option<(String, u32)>,
+}
+
+impl ThumbnailQueue {
+    /// Draws a tile's thumbnail again, such as after it changed.
+    #[inline]
+    pub fn refresh(&mut self, key: &str) {
+        if !self.pending.iter().any(|pending| pending == key) {
+            self.pending.push_back(key.into());
+        }
+    }
+}
+
+#[derive(Component, Copy, Clone)]
+pub struct ThumbnailCamera;
+
+/// The tile being drawn into a thumbnail.
+#[derive(Component, Copy, Clone)]
+pub struct ThumbnailSubject;
+
+pub fn init_thumbnail_stage(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<MapMaterial>>,
+) {
+    let layer = RenderLayers::layer(THUMBNAIL_LAYER);
+    commands.spawn((
+        Camera3dBundle {
+            camera: Camera {
+                is_active: false,
+                order: -1,
+                clear_color: ClearColorConfig::Custom(Color::NONE),
+                ..default()
+            },
+            projection: OrthographicProjection::default().into(),
+            // Unlit tiles are shown in their texture's own colors.
+            tonemapping: Tonemapping::None,
+            ..default()
+        },
+        layer.clone(),
+        ThumbnailCamera,
+    ));
+
+    commands.spawn((
+        MaterialMeshBundle::<MapMaterial> {
+            mesh: meshes.add(Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD)),
+            material: materials.add(MapMaterial::default()),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        layer,
+        ThumbnailSubject,
+    ));
+}
+
+/// Redraws every thumbnail whenever the tile texture is rebuilt, as tiles or their textures may
+/// have changed with it.
+pub fn queue_thumbnails(
+    mut rebuilt: EventReader<TileTextureRebuilt>,
+    texture: Res<TileTexture>,
+    tiles: Res<Tiles>,
+    mut thumbnails: ResMut<TileThumbnails>,
+    mut queue: ResMut<ThumbnailQueue>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if rebuilt.read().count() == 0 && !texture.is_added() {
+        return
+    }
+
+    if thumbnails.keys().any(|key| !tiles.contains_key(key)) {
+        thumbnails.0.retain(|key, _| tiles.contains_key(key));
+    }
+
+    let mut keys = tiles.keys().cloned().collect::<Vec<_>>();
+    keys.sort_unstable();
+
+    for key in keys {
+        if !thumbnails.contains_key(&key) {
+            thumbnails.0.insert(key.clone(), images.add(thumbnail_target()));
+        }
+
+        queue.refresh(&key);
+    }
+}
+
+fn thumbnail_target() -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: THUMBNAIL_SIZE,
+            height: THUMBNAIL_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+
+    image.texture_descriptor.usage =
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+    image
+}
+
+/// Draws the next queued thumbnail, leaving the camera off once every one is drawn.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn draw_thumbnails(
+    mut queue: ResMut<ThumbnailQueue>,
+    thumbnails: Res<TileThumbnails>,
+    tiles: Res<Tiles>,
+    objs: Res<Assets<Obj>>,
+    materials: Res<Assets<MtlCollection>>,
+    texture: Res<TileTexture>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+    renders: Res<TileRenders>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut map_materials: ResMut<Assets<MapMaterial>>,
+    mut cameras: Query<(&mut Camera, &mut Transform, &mut Projection), With<ThumbnailCamera>>,
+    mut subjects: Query<(&Handle<Mesh>, &Handle<MapMaterial>, &mut Visibility), With<ThumbnailSubject>>,
+) {
+    let (Ok((mut camera, mut trns, mut projection)), Ok((mesh, material, mut visibility))) =
+        (cameras.get_single_mut(), subjects.get_single_mut())
+    else {
+        return
+    };
+
+    if let Some((_, frames)) = &mut queue.current {
+        *frames -= 1;
+        if *frames > 0 {
+            return
+        }
+    }
+
+    queue.current = None;
+    while let Some(key) = queue.pending.pop_front() {
+        let (Some(target), Some(tile)) = (thumbnails.get(&key), tiles.get(&key)) else {
+            continue
+        };
+
+        // Tiles not yet loaded or packed are drawn once the tile texture is rebuilt with them.
+        let Some(obj) = objs.get(tile) else { continue };
+        let Some((page, _)) = obj.diffuse_texture(&materials).and_then(|id| texture.locate(&layouts, id)) else {
+            continue
+        };
+
+        // A map of just the tile, so it's meshed with the same UVs as in any other map.
+        let mut map = Map::empty(UVec3::ONE);
+        map.tile_set.push(key.clone());
+        map.tile_handles.push(tile.clone());
+        map.tiles[0] = NonMaxU8::new(0);
+
+        meshes.insert(
+            mesh,
+            map.build_mesh(
+                Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD),
+                &objs,
+                &materials,
+                &texture,
+                &layouts,
+                page,
+                |_| true,
+            ),
+        );
+
+        // Drawn the tile's way, so transparent tiles look it in the palette.
+        let part = MapPart {
+            page,
+            render: renders.get(&key),
+        };
+        let base = MapMaterial {
+            base: StandardMaterial {
+                unlit: true,
+                cull_mode: None,
+                ..default()
+            },
+            extension: default(),
+        };
+
+        if let Some(tile_material) = part.material(&base, &texture) {
+            map_materials.insert(material, tile_material);
+        }
+
+        // Framed by the tile's bounds, so every tile fills its thumbnail alike.
+        let (min, max) = obj
+            .positions
+            .iter()
+            .fold((Vec3::MAX, Vec3::MIN), |(min, max), &pos| (min.min(pos), max.max(pos)));
+        let (center, radius) = ((min + max) * 0.5, ((max - min).length() * 0.5).max(0.01));
+
+        *trns = Transform::from_translation(center + THUMBNAIL_VIEW.normalize() * radius * 4.0).looking_at(center, Vec3::Y);
+        *projection = OrthographicProjection {
+            scaling_mode: ScalingMode::Fixed {
+                width: radius * 2.2,
+                height: radius * 2.2,
+            },
+            ..default()
+        }
+        .into();
+
+        camera.target = RenderTarget::Image(target.clone_weak());
+        camera.is_active = true;
+        visibility.set_if_neq(Visibility::Visible);
+
+        queue.current = Some((key, THUMBNAIL_FRAMES));
+        return
+    }
+
+    camera.is_active = false;
+    visibility.set_if_neq(Visibility::Hidden);
+}
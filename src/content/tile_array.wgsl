@@ -0,0 +1,32 @@
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing},
+    pbr_types::STANDARD_MATERIAL_FLAGS_UNLIT_BIT,
+}
+
+@group(2) @binding(100) var tile_array: texture_2d_array<f32>;
+@group(2) @binding(101) var tile_sampler: sampler;
+
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+
+#ifdef VERTEX_UVS_B
+    // The tile's layer is carried in the second UV channel, leaving the first free to repeat.
+    let layer = u32(in.uv_b.x + 0.5);
+    pbr_input.material.base_color *= textureSample(tile_array, tile_sampler, in.uv, layer);
+#endif
+
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+    var out: FragmentOutput;
+    if (pbr_input.material.flags & STANDARD_MATERIAL_FLAGS_UNLIT_BIT) == 0u {
+        out.color = apply_pbr_lighting(pbr_input);
+    } else {
+        out.color = pbr_input.material.base_color;
+    }
+
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    return out;
+}
@@ -0,0 +1,259 @@
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, Write},
+    panic,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard, PoisonError, TryLockError,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    asset::io::file::FileAssetReader,
+    log::{tracing_subscriber::fmt, BoxedLayer},
+    prelude::*,
+};
+
+use crate::{editor::autosave::rotate_autosaves, map::loader::MapFile};
+
+/// The directory under the base path logs and crash reports are written to.
+pub const LOGS_DIR: &str = "logs";
+/// Log lines kept in memory, written into crash reports.
+pub const RECENT_LOG_LINES: usize = 256;
+
+static RECENT_LOGS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static LOG_FILE: Mutex<Option<RotatingLog>> = Mutex::new(None);
+static CRASH_SNAPSHOT: Mutex<Option<CrashSnapshot>> = Mutex::new(None);
+static CRASHED: AtomicBool = AtomicBool::new(false);
+
+#[inline]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Locks without waiting, for panics that may have struck while the lock was held.
+#[inline]
+fn try_lock<T>(mutex: &Mutex<T>) -> Option<MutexGuard<T>> {
+    match mutex.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+#[inline]
+fn logs_dir() -> PathBuf {
+    FileAssetReader::get_base_path().join(LOGS_DIR)
+}
+
+/// Where log output is written to besides stdout, moving to older files as it grows.
+#[derive(Resource, Clone, Debug)]
+pub struct LogFileConfig {
+    /// The log file, which moves to `mnemonic.1.log` and so on once full.
+    pub path: PathBuf,
+    /// Bytes written to a log file before moving on to a new one.
+    pub max_size: u64,
+    /// Log files kept, including the current one.
+    pub keep: usize,
+}
+
+impl Default for LogFileConfig {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            path: logs_dir().join("mnemonic.log"),
+            max_size: 4 * 1024 * 1024,
+            keep: 3,
+        }
+    }
+}
+
+/// The open log file, and how much has been written to it.
+pub struct RotatingLog {
+    file: File,
+    size: u64,
+    config: LogFileConfig,
+}
+
+impl RotatingLog {
+    /// Opens the log file to append to, creating it and its directory if needed.
+    pub fn open(config: LogFileConfig) -> io::Result<Self> {
+        if let Some(dir) = config.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let file = File::options().create(true).append(true).open(&config.path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { file, size, config })
+    }
+
+    /// Appends to the log, moving on to a new file first if this would overfill it.
+    pub fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if self.size > 0 && self.size + bytes.len() as u64 > self.config.max_size {
+            self.file.flush()?;
+            rotate_files(&self.config.path, self.config.keep)?;
+            self.file = File::create(&self.config.path)?;
+            self.size = 0;
+        }
+
+        self.file.write_all(bytes)?;
+        self.size += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+/// Shifts `file.log` to `file.1.log` and so on, dropping the ones past `keep`.
+fn rotate_files(file: &Path, keep: usize) -> io::Result<()> {
+    let extension = file.extension().and_then(|ext| ext.to_str()).unwrap_or("log");
+    let older = |i: usize| file.with_extension(format!("{i}.{extension}"));
+    for i in (1..keep).rev() {
+        let from = if i == 1 { file.to_path_buf() } else { older(i - 1) };
+        if from.exists() {
+            fs::rename(from, older(i))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes formatted log output to the recent lines kept for crash reports, and to the log file if
+/// one is open.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct LogSink;
+impl Write for LogSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        let mut recent = lock(&RECENT_LOGS);
+        for line in text.lines() {
+            if recent.len() >= RECENT_LOG_LINES {
+                recent.pop_front();
+            }
+
+            recent.push_back(line.into());
+        }
+        drop(recent);
+
+        // Failing to log can't very well be logged.
+        if let Some(log) = lock(&LOG_FILE).as_mut() {
+            _ = log.write(buf);
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match lock(&LOG_FILE).as_mut() {
+            Some(log) => log.file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The last [`RECENT_LOG_LINES`] lines logged, oldest first.
+pub fn recent_logs() -> Vec<String> {
+    lock(&RECENT_LOGS).iter().cloned().collect()
+}
+
+/// Opens the log file if the app has a [`LogFileConfig`], and gives [`LogSink`] every log event.
+/// Meant for [`LogPlugin::custom_layer`](bevy::log::LogPlugin::custom_layer).
+pub fn log_layer(app: &mut App) -> Option<BoxedLayer> {
+    if let Some(config) = app.world().get_resource::<LogFileConfig>() {
+        match RotatingLog::open(config.clone()) {
+            Ok(log) => *lock(&LOG_FILE) = Some(log),
+            Err(e) => eprintln!("Couldn't open `{}`: {e}", config.path.display()),
+        }
+    }
+
+    Some(Box::new(fmt::layer().with_ansi(false).with_writer(|| LogSink)))
+}
+
+/// The editor map with unsaved changes, written to its autosave if the app panics.
+#[derive(Clone)]
+pub struct CrashSnapshot {
+    pub file: PathBuf,
+    pub map: MapFile,
+    /// Autosaves kept of the map, as its autosave is rotated.
+    pub keep: usize,
+}
+
+/// Sets what to save should the app panic, or `None` if there's nothing unsaved.
+pub fn set_crash_snapshot(snapshot: Option<CrashSnapshot>) {
+    *lock(&CRASH_SNAPSHOT) = snapshot;
+}
+
+/// Writes the [`CrashSnapshot`], if any, returning where it went.
+fn save_crash_snapshot() -> Option<io::Result<PathBuf>> {
+    let snapshot = try_lock(&CRASH_SNAPSHOT)?.take()?;
+    Some((|| {
+        let ron = snapshot.map.to_ron().map_err(io::Error::other)?;
+        if let Some(dir) = snapshot.file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        rotate_autosaves(&snapshot.file, snapshot.keep)?;
+        fs::write(&snapshot.file, ron)?;
+        Ok(snapshot.file)
+    })())
+}
+
+/// What a crash report says: the panic, where it happened, and what was logged before it.
+pub fn crash_report(message: &str, backtrace: &Backtrace, logs: &[String]) -> String {
+    let mut report = format!("{message}\n\nBacktrace:\n{backtrace}\n\nRecent log:\n");
+    for line in logs {
+        report.push_str(line);
+        report.push('\n');
+    }
+
+    report
+}
+
+/// Reports the first panic: writes a crash report to [`LOGS_DIR`], saves the editor map if it had
+/// unsaved changes, and tells the user where both went. Runs after the default hook, which prints
+/// the panic as usual.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if CRASHED.swap(true, Ordering::SeqCst) {
+            return
+        }
+
+        let logs = try_lock(&RECENT_LOGS).map(|logs| logs.iter().cloned().collect::<Vec<_>>());
+        let report = crash_report(
+            &info.to_string(),
+            &Backtrace::force_capture(),
+            logs.as_deref().unwrap_or_default(),
+        );
+
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+        let file = logs_dir().join(format!("crash-{secs}.txt"));
+        let written = fs::create_dir_all(logs_dir()).and_then(|_| fs::write(&file, report));
+
+        if let Some(log) = try_lock(&LOG_FILE).as_mut().and_then(|log| log.as_mut()) {
+            _ = log.file.flush();
+        }
+
+        let mut description = match written {
+            Ok(()) => format!("Mnemonic crashed. The crash report was written to\n{}", file.display()),
+            Err(e) => format!("Mnemonic crashed, and the crash report couldn't be written: {e}"),
+        };
+
+        match save_crash_snapshot() {
+            Some(Ok(map)) => description.push_str(&format!("\n\nUnsaved changes were kept in\n{}", map.display())),
+            Some(Err(e)) => description.push_str(&format!("\n\nUnsaved changes couldn't be kept: {e}")),
+            None => {}
+        }
+
+        eprintln!("{description}");
+        rfd::MessageDialog::new()
+            .set_level(rfd::MessageLevel::Error)
+            .set_title("Mnemonic crashed")
+            .set_description(description)
+            .set_buttons(rfd::MessageButtons::Ok)
+            .show();
+    }));
+}
@@ -0,0 +1,230 @@
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    prelude::*,
+    utils::Duration,
+};
+
+use crate::{content::TileTextureStats, map::MapStats, playtest::ColliderStats, EditorState};
+
+/// Milliseconds each map took to mesh, averaged over the maps meshed in a frame.
+pub const MAP_MESH_TIME: DiagnosticPath = DiagnosticPath::const_new("map/mesh_time");
+/// Playtest collider chunks rebuilt in a frame.
+pub const COLLIDER_CHUNKS: DiagnosticPath = DiagnosticPath::const_new("playtest/collider_chunks");
+/// Milliseconds the last playtest collider build took.
+pub const COLLIDER_TIME: DiagnosticPath = DiagnosticPath::const_new("playtest/collider_time");
+/// Milliseconds the last tile texture rebuild took.
+pub const ATLAS_TIME: DiagnosticPath = DiagnosticPath::const_new("content/atlas_time");
+
+/// Measurements kept by each diagnostic, and drawn by the graph.
+pub const HISTORY_LENGTH: usize = 120;
+
+/// Toggles the graph of build diagnostics, with the `dev` feature.
+#[cfg(feature = "dev")]
+pub const BUILD_GRAPH_KEY: KeyCode = KeyCode::ScrollLock;
+
+/// Diagnostics of how long meshes, colliders, and the tile atlas take to build, fed from
+/// [`MapStats`], [`ColliderStats`], and [`TileTextureStats`]. Builds are measured as they happen,
+/// so frames without any add no measurement, except for [`COLLIDER_CHUNKS`] while playtesting.
+pub struct BuildDiagnosticsPlugin;
+impl Plugin for BuildDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        for (path, suffix) in [
+            (MAP_MESH_TIME, "ms"),
+            (COLLIDER_CHUNKS, " chunks"),
+            (COLLIDER_TIME, "ms"),
+            (ATLAS_TIME, "ms"),
+        ] {
+            app.register_diagnostic(
+                Diagnostic::new(path)
+                    .with_suffix(suffix)
+                    .with_max_history_length(HISTORY_LENGTH),
+            );
+        }
+
+        app.add_systems(Last, measure_builds);
+
+        #[cfg(feature = "dev")]
+        app.add_systems(Startup, graph::init_build_graph).add_systems(
+            Last,
+            (
+                graph::toggle_build_graph.run_if(bevy::input::common_conditions::input_just_pressed(BUILD_GRAPH_KEY)),
+                graph::update_build_graph,
+            )
+                .chain()
+                .after(measure_builds),
+        );
+    }
+}
+
+#[inline]
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Adds a measurement for every build done since the last run.
+pub fn measure_builds(
+    mut diagnostics: Diagnostics,
+    state: Option<Res<State<EditorState>>>,
+    maps: Option<Res<MapStats>>,
+    colliders: Option<Res<ColliderStats>>,
+    atlas: Option<Res<TileTextureStats>>,
+    mut last: Local<(Duration, usize)>,
+) {
+    if let Some(maps) = maps.filter(|maps| maps.rebuilt > 0) {
+        diagnostics.add_measurement(&MAP_MESH_TIME, || millis(maps.elapsed) / maps.rebuilt as f64);
+    }
+
+    if let Some(colliders) = colliders {
+        if state.is_some_and(|state| *state.get() == EditorState::Playtest) {
+            diagnostics.add_measurement(&COLLIDER_CHUNKS, || colliders.rebuilt as f64);
+        }
+
+        // Builds seldom take exactly as long as the one before; those that did go unmeasured.
+        if colliders.last_build != last.0 {
+            last.0 = colliders.last_build;
+            diagnostics.add_measurement(&COLLIDER_TIME, || millis(colliders.last_build));
+        }
+    }
+
+    if let Some(atlas) = atlas.filter(|atlas| atlas.rebuilds != last.1) {
+        last.1 = atlas.rebuilds;
+        diagnostics.add_measurement(&ATLAS_TIME, || millis(atlas.last_rebuild));
+    }
+}
+
+#[cfg(feature = "dev")]
+mod graph {
+    use bevy::diagnostic::DiagnosticsStore;
+
+    use super::*;
+
+    const GRAPH_BACKGROUND: Color = Color::srgba(0.08, 0.08, 0.1, 0.85);
+    const GRAPH_BAR: Color = Color::srgb(0.45, 0.55, 0.85);
+
+    /// The graphed diagnostics, in order from the top.
+    const GRAPHED: [DiagnosticPath; 4] = [MAP_MESH_TIME, COLLIDER_CHUNKS, COLLIDER_TIME, ATLAS_TIME];
+
+    /// Holds a sparkline of each of [`GRAPHED`].
+    #[derive(Component, Copy, Clone, Default)]
+    pub struct BuildGraph;
+
+    /// One of [`GRAPHED`]'s sparklines, with its label.
+    #[derive(Component, Copy, Clone)]
+    pub struct BuildGraphRow(pub usize);
+
+    /// A bar of a sparkline; the last is the latest measurement.
+    #[derive(Component, Copy, Clone)]
+    pub struct BuildGraphBar(pub usize);
+
+    pub fn init_build_graph(mut commands: Commands) {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(8.0),
+                        right: Val::Px(8.0),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(6.0),
+                        padding: UiRect::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                    background_color: GRAPH_BACKGROUND.into(),
+                    visibility: Visibility::Hidden,
+                    z_index: ZIndex::Global(i32::MAX),
+                    ..default()
+                },
+                BuildGraph,
+            ))
+            .with_children(|parent| {
+                for row in 0..GRAPHED.len() {
+                    parent.spawn((
+                        TextBundle::from_section("", TextStyle {
+                            font_size: 12.0,
+                            color: Color::srgb(0.8, 0.8, 0.8),
+                            ..default()
+                        }),
+                        BuildGraphRow(row),
+                    ));
+
+                    parent
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Px(HISTORY_LENGTH as f32 * 2.0),
+                                    height: Val::Px(32.0),
+                                    align_items: AlignItems::FlexEnd,
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            BuildGraphRow(row),
+                        ))
+                        .with_children(|parent| {
+                            for bar in 0..HISTORY_LENGTH {
+                                parent.spawn((
+                                    NodeBundle {
+                                        style: Style {
+                                            width: Val::Px(2.0),
+                                            height: Val::Percent(0.0),
+                                            ..default()
+                                        },
+                                        background_color: GRAPH_BAR.into(),
+                                        ..default()
+                                    },
+                                    BuildGraphBar(bar),
+                                ));
+                            }
+                        });
+                }
+            });
+    }
+
+    pub fn toggle_build_graph(mut graphs: Query<&mut Visibility, With<BuildGraph>>) {
+        for mut visibility in &mut graphs {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+
+    /// Scales each sparkline's bars to the highest of its measurements, labeling it with the latest.
+    pub fn update_build_graph(
+        diagnostics: Res<DiagnosticsStore>,
+        graphs: Query<&Visibility, With<BuildGraph>>,
+        mut rows: Query<(&BuildGraphRow, Option<&mut Text>, Option<&Children>)>,
+        mut bars: Query<(&BuildGraphBar, &mut Style)>,
+    ) {
+        if graphs.iter().all(|&visibility| visibility == Visibility::Hidden) {
+            return
+        }
+
+        for (&BuildGraphRow(row), text, children) in &mut rows {
+            let Some(diagnostic) = diagnostics.get(&GRAPHED[row]) else { continue };
+            if let Some(mut text) = text {
+                text.sections[0].value = match diagnostic.value() {
+                    Some(value) => format!("{}: {value:.2}{}", diagnostic.path(), diagnostic.suffix),
+                    None => format!("{}: -", diagnostic.path()),
+                };
+            }
+
+            let Some(children) = children else { continue };
+            let values = diagnostic.values().copied().collect::<Vec<_>>();
+            let max = values.iter().copied().fold(0.0, f64::max);
+
+            // Measurements fill the graph from its right end.
+            let offset = HISTORY_LENGTH - values.len().min(HISTORY_LENGTH);
+            let mut bars = bars.iter_many_mut(children);
+            while let Some((&BuildGraphBar(bar), mut style)) = bars.fetch_next() {
+                let value = bar.checked_sub(offset).and_then(|index| values.get(index)).copied();
+                let height = match (value, max > 0.0) {
+                    (Some(value), true) => (value / max * 100.0) as f32,
+                    _ => 0.0,
+                };
+
+                style.height = Val::Percent(height);
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    prelude::*,
+    tasks::{block_on, poll_once, IoTaskPool, Task},
+};
+
+use crate::{
+    crash::{set_crash_snapshot, CrashSnapshot},
+    editor::{
+        prompt::{ActivePrompt, Notice, Prompt, PromptSubmit},
+        session::{watch_opened_map, EditorSession, SessionRequest, MAPS_DIR},
+        EditorMap, EditorSettings, CLOSE_EDITOR,
+    },
+    map::{loader::MapFile, Map},
+    AssetRoot, EditorUi,
+};
+
+pub struct AutosavePlugin;
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AutosaveSettings>()
+            .add_systems(CLOSE_EDITOR, clear_crash_snapshot)
+            .add_systems(
+                Update,
+                (autosave_map, snapshot_for_crash, offer_recovery, answer_recovery)
+                    .chain()
+                    .after(watch_opened_map)
+                    .run_if(in_state(EditorUi)),
+            );
+    }
+}
+
+/// The directory under [`MAPS_DIR`] autosaves are written to.
+pub const AUTOSAVE_DIR: &str = ".autosave";
+
+#[derive(Resource, Copy, Clone)]
+pub struct AutosaveSettings {
+    /// Seconds between autosaves of a map with unsaved changes.
+    pub interval: f32,
+    /// Autosaves kept per map, including the latest.
+    pub keep: usize,
+    /// Seconds between copies of a map with unsaved changes, kept to be autosaved should the app
+    /// panic.
+    pub crash_interval: f32,
+}
+
+impl Default for AutosaveSettings {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            interval: 300.0,
+            keep: 3,
+            crash_interval: 5.0,
+        }
+    }
+}
+
+/// The asset path of the latest autosave of the map saved at `path`, or of unsaved maps if `None`.
+pub fn autosave_path(path: Option<&str>) -> String {
+    let name = path
+        .and_then(|path| Path::new(path).file_stem())
+        .and_then(|name| name.to_str())
+        .unwrap_or("untitled");
+
+    format!("{MAPS_DIR}/{AUTOSAVE_DIR}/{name}.mnmap")
+}
+
+/// Shifts `file.mnmap` to `file.1.mnmap` and so on, dropping the ones past `keep`.
+pub fn rotate_autosaves(file: &Path, keep: usize) -> io::Result<()> {
+    let older = |i: usize| file.with_extension(format!("{i}.mnmap"));
+    for i in (1..keep).rev() {
+        let from = if i == 1 { file.to_path_buf() } else { older(i - 1) };
+        if from.exists() {
+            fs::rename(from, older(i))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct AutosaveState {
+    elapsed: f32,
+    /// The map file and revision last autosaved.
+    saved: Option<(Option<String>, u64)>,
+    task: Option<Task<io::Result<()>>>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn autosave_map(
+    time: Res<Time>,
+    root: Res<AssetRoot>,
+    settings: Res<AutosaveSettings>,
+    editor: Res<EditorSettings>,
+    session: Res<EditorSession>,
+    mut notice: ResMut<Notice>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    maps: Res<Assets<Map>>,
+    mut state: Local<AutosaveState>,
+) {
+    if let Some(task) = state.task.as_mut() {
+        if let Some(result) = block_on(poll_once(task)) {
+            if let Err(e) = result {
+                notice.show(format!("Couldn't autosave: {e}"));
+            }
+
+            state.task = None;
+        }
+    }
+
+    state.elapsed += time.delta_seconds();
+    if state.elapsed < settings.interval {
+        return
+    }
+
+    let revision = Some((session.path.clone(), session.revision));
+    if !session.is_dirty() || state.saved == revision || state.task.is_some() {
+        return
+    }
+
+    let Some(map) = editor_maps.get_single().ok().and_then(|map| maps.get(map)) else {
+        return
+    };
+
+    // Only copy the map here; serializing and writing it happens off the main thread.
+    let snapshot = MapFile {
+        lighting: editor.lighting,
+        ..MapFile::from(map)
+    };
+    let file = root.join(autosave_path(session.path.as_deref()));
+    let keep = settings.keep;
+
+    state.elapsed = 0.0;
+    state.saved = revision;
+    state.task = Some(IoTaskPool::get().spawn(async move {
+        let ron = snapshot.to_ron().map_err(io::Error::other)?;
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        rotate_autosaves(&file, keep)?;
+        fs::write(file, ron)
+    }));
+}
+
+/// Keeps a copy of the editor map for the panic hook while it has unsaved changes. Copies are only
+/// as recent as [`AutosaveSettings::crash_interval`].
+#[allow(clippy::too_many_arguments)]
+pub fn snapshot_for_crash(
+    time: Res<Time>,
+    root: Res<AssetRoot>,
+    settings: Res<AutosaveSettings>,
+    editor: Res<EditorSettings>,
+    session: Res<EditorSession>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    maps: Res<Assets<Map>>,
+    mut state: Local<AutosaveState>,
+) {
+    if !session.is_dirty() {
+        if state.saved.take().is_some() {
+            set_crash_snapshot(None);
+        }
+
+        return
+    }
+
+    state.elapsed += time.delta_seconds();
+    let revision = Some((session.path.clone(), session.revision));
+    if state.saved == revision || (state.saved.is_some() && state.elapsed < settings.crash_interval) {
+        return
+    }
+
+    let Some(map) = editor_maps.get_single().ok().and_then(|map| maps.get(map)) else {
+        return
+    };
+
+    state.elapsed = 0.0;
+    state.saved = revision;
+    set_crash_snapshot(Some(CrashSnapshot {
+        file: root.join(autosave_path(session.path.as_deref())),
+        map: MapFile {
+            lighting: editor.lighting,
+            ..MapFile::from(map)
+        },
+        keep: settings.keep,
+    }));
+}
+
+pub fn clear_crash_snapshot() {
+    set_crash_snapshot(None);
+}
+
+/// Whether the autosave at `autosave` was written after the map file at `path`, if any.
+fn is_newer(autosave: &Path, path: Option<&PathBuf>) -> bool {
+    let modified = |file: &Path| fs::metadata(file).and_then(|meta| meta.modified()).ok();
+    match (modified(autosave), path.and_then(|path| modified(path))) {
+        (Some(autosave), Some(saved)) => autosave > saved,
+        (Some(..), None) => true,
+        (None, ..) => false,
+    }
+}
+
+pub fn offer_recovery(
+    root: Res<AssetRoot>,
+    session: Res<EditorSession>,
+    mut prompt: ResMut<ActivePrompt>,
+    mut checked: Local<Option<Option<String>>>,
+) {
+    if session.opening.is_some() || prompt.is_some() || checked.as_ref() == Some(&session.path) {
+        return
+    }
+
+    *checked = Some(session.path.clone());
+
+    let autosave = autosave_path(session.path.as_deref());
+    if is_newer(
+        &root.join(&autosave),
+        session.path.as_ref().map(|path| root.join(path)).as_ref(),
+    ) {
+        **prompt = Some(Prompt::choice(
+            "recover",
+            format!("Found a newer autosave `{autosave}`"),
+            vec!["Recover".into(), "Ignore".into()],
+        ));
+    }
+}
+
+pub fn answer_recovery(
+    mut answers: EventReader<PromptSubmit>,
+    session: Res<EditorSession>,
+    mut requests: EventWriter<SessionRequest>,
+) {
+    for answer in answers.read() {
+        if answer.id == "recover" && answer.value == "Recover" {
+            requests.send(SessionRequest::Recover(autosave_path(session.path.as_deref())));
+        }
+    }
+}
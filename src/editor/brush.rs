@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+
+use crate::EditorState;
+
+pub struct BrushPlugin;
+impl Plugin for BrushPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Brush>()
+            .register_type::<Brush>()
+            .add_systems(Update, adjust_brush.run_if(in_state(EditorState::Editing)));
+    }
+}
+
+#[derive(Resource, Reflect, Copy, Clone, Eq, PartialEq, Debug)]
+#[reflect(Resource)]
+pub struct Brush {
+    /// Width of the footprint in cells, within [`Brush::MIN_SIZE`] and [`Brush::MAX_SIZE`].
+    pub size: u32,
+    pub shape: BrushShape,
+    pub plane: BrushPlane,
+}
+
+impl Default for Brush {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            size: 1,
+            shape: BrushShape::Square,
+            plane: BrushPlane::XZ,
+        }
+    }
+}
+
+#[derive(Reflect, Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum BrushShape {
+    /// Only the targeted cell, regardless of size.
+    Single,
+    #[default]
+    Square,
+    Circle,
+}
+
+#[derive(Reflect, Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum BrushPlane {
+    #[default]
+    XZ,
+    XY,
+    ZY,
+}
+
+impl BrushPlane {
+    /// The two axes spanning the plane.
+    #[inline]
+    pub fn axes(self) -> (IVec3, IVec3) {
+        match self {
+            Self::XZ => (IVec3::X, IVec3::Z),
+            Self::XY => (IVec3::X, IVec3::Y),
+            Self::ZY => (IVec3::Z, IVec3::Y),
+        }
+    }
+}
+
+impl Brush {
+    pub const MIN_SIZE: u32 = 1;
+    pub const MAX_SIZE: u32 = 16;
+
+    /// The inclusive range of offsets along each plane axis; even sizes lean towards positive
+    /// coordinates.
+    #[inline]
+    fn extent(self) -> (i32, i32) {
+        let size = match self.shape {
+            BrushShape::Single => 1,
+            _ => self.size.clamp(Self::MIN_SIZE, Self::MAX_SIZE) as i32,
+        };
+
+        (-(size - 1) / 2, size / 2)
+    }
+
+    /// The bounding region of the footprint centered on a cell, as an inclusive minimum and
+    /// exclusive maximum.
+    #[inline]
+    pub fn region(self, center: IVec3) -> (IVec3, IVec3) {
+        let (lower, upper) = self.extent();
+        let (u, v) = self.plane.axes();
+        (center + (u + v) * lower, center + (u + v) * upper + IVec3::ONE)
+    }
+
+    /// Every cell of the footprint centered on a cell, which may extend past the map bounds.
+    pub fn footprint(self, center: IVec3) -> impl Iterator<Item = IVec3> {
+        let (lower, upper) = self.extent();
+        let (u, v) = self.plane.axes();
+
+        // Circles are measured from the footprint's own center, which lies between cells for even sizes.
+        let mid = (lower + upper) as f32 * 0.5;
+        let radius = (upper - lower + 1) as f32 * 0.5;
+        let shape = self.shape;
+
+        (lower..=upper)
+            .flat_map(move |a| (lower..=upper).map(move |b| (a, b)))
+            .filter(move |&(a, b)| {
+                shape != BrushShape::Circle || Vec2::new(a as f32 - mid, b as f32 - mid).length() <= radius - 0.25
+            })
+            .map(move |(a, b)| center + u * a + v * b)
+    }
+}
+
+pub fn adjust_brush(keys: Res<ButtonInput<KeyCode>>, mut brush: ResMut<Brush>) {
+    let mut size = brush.size as i32;
+    if keys.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        size += 1;
+    }
+
+    if keys.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        size -= 1;
+    }
+
+    let size = (size.max(0) as u32).clamp(Brush::MIN_SIZE, Brush::MAX_SIZE);
+    if brush.size != size {
+        brush.size = size;
+    }
+
+    if keys.just_pressed(KeyCode::Backslash) {
+        brush.shape = match brush.shape {
+            BrushShape::Single => BrushShape::Square,
+            BrushShape::Square => BrushShape::Circle,
+            BrushShape::Circle => BrushShape::Single,
+        };
+    }
+
+    if keys.just_pressed(KeyCode::Quote) {
+        brush.plane = match brush.plane {
+            BrushPlane::XZ => BrushPlane::XY,
+            BrushPlane::XY => BrushPlane::ZY,
+            BrushPlane::ZY => BrushPlane::XZ,
+        };
+    }
+}
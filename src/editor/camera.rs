@@ -0,0 +1,558 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+    window::{CursorGrabMode, PrimaryWindow},
+};
+use bevy_mod_picking::{
+    picking_core::PickSet,
+    pointer::{InputMove, Location, PointerId},
+};
+
+use crate::{
+    editor::{
+        lighting::SUN_KEY,
+        tools::{select::EditorSelection, ToolMode},
+        EditorMap,
+    },
+    map::{EditMode, Map},
+    obj::def::Obj,
+    EditorUi,
+};
+
+pub struct EditorCameraPlugin;
+impl Plugin for EditorCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditorCameraSettings>()
+            .add_systems(
+                First,
+                aim_flying_pointer
+                    .after(PickSet::Input)
+                    .run_if(in_state(EditorUi)),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_fly_camera,
+                    pan_editor_camera,
+                    zoom_editor_camera,
+                    rotate_editor_camera,
+                    frame_editor_camera,
+                    fly_editor_camera,
+                    apply_editor_camera,
+                )
+                    .chain()
+                    .run_if(in_state(EditorUi)),
+            );
+    }
+}
+
+#[derive(Resource, Copy, Clone)]
+pub struct EditorCameraSettings {
+    /// Multiplier on the world-space distance panned per dragged pixel.
+    pub pan_sensitivity: f32,
+    /// Fractional scale change per scroll line.
+    pub zoom_sensitivity: f32,
+    pub min_scale: f32,
+    pub max_scale: f32,
+    /// Horizontal pixels alt-dragged per 90° rotation step.
+    pub rotate_drag_threshold: f32,
+    /// Seconds a 90° rotation step takes.
+    pub rotate_duration: f32,
+    pub fly_toggle: KeyCode,
+    /// World units per second moved in fly mode.
+    pub fly_speed: f32,
+    /// Speed multiplier while shift is held in fly mode.
+    pub fly_boost: f32,
+    /// Radians turned per moved pixel in fly mode.
+    pub look_sensitivity: f32,
+    pub frame_key: KeyCode,
+    /// Fraction of the view left around a framed region.
+    pub frame_margin: f32,
+    /// Seconds framing a region takes.
+    pub frame_duration: f32,
+}
+
+impl Default for EditorCameraSettings {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            pan_sensitivity: 1.0,
+            zoom_sensitivity: 0.1,
+            min_scale: 0.002,
+            max_scale: 0.5,
+            rotate_drag_threshold: 120.0,
+            rotate_duration: 0.2,
+            fly_toggle: KeyCode::KeyF,
+            fly_speed: 8.0,
+            fly_boost: 4.0,
+            look_sensitivity: 0.003,
+            frame_key: KeyCode::Home,
+            frame_margin: 0.1,
+            frame_duration: 0.2,
+        }
+    }
+}
+
+#[derive(Component, Clone)]
+pub struct EditorCamera {
+    pub focus: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+    pub rotation: Option<YawTween>,
+    pub framing: Option<FrameTween>,
+    pub mode: CameraMode,
+    /// Skips framing the next opened map, keeping a view restored from the last session.
+    pub keep_view: bool,
+    rotate_drag: f32,
+}
+
+#[derive(Clone)]
+pub enum CameraMode {
+    /// Orthographic rig orbiting [`EditorCamera::focus`].
+    Orbit,
+    /// Perspective free-fly, holding on to the orthographic projection to restore when toggled
+    /// back.
+    Fly {
+        translation: Vec3,
+        yaw: f32,
+        pitch: f32,
+        ortho: OrthographicProjection,
+    },
+}
+
+#[derive(Copy, Clone)]
+pub struct YawTween {
+    pub from: f32,
+    pub to: f32,
+    pub elapsed: f32,
+}
+
+#[derive(Copy, Clone)]
+pub struct FrameTween {
+    pub from: (Vec3, f32),
+    pub to: (Vec3, f32),
+    pub elapsed: f32,
+}
+
+impl EditorCamera {
+    #[inline]
+    pub fn looking_at(eye: Vec3, focus: Vec3) -> Self {
+        let offset = eye - focus;
+        Self {
+            focus,
+            yaw: offset.x.atan2(offset.z),
+            pitch: -offset.y.atan2(offset.xz().length()),
+            distance: offset.length(),
+            rotation: None,
+            framing: None,
+            mode: CameraMode::Orbit,
+            keep_view: false,
+            rotate_drag: 0.0,
+        }
+    }
+
+    #[inline]
+    pub fn is_flying(&self) -> bool {
+        matches!(self.mode, CameraMode::Fly { .. })
+    }
+
+    #[inline]
+    pub fn target_yaw(&self) -> f32 {
+        self.rotation.map_or(self.yaw, |tween| tween.to)
+    }
+
+    #[inline]
+    pub fn rotate_by(&mut self, angle: f32) {
+        self.rotation = Some(YawTween {
+            from: self.yaw,
+            to: self.target_yaw() + angle,
+            elapsed: 0.0,
+        });
+    }
+
+    #[inline]
+    pub fn transform(&self) -> Transform {
+        match self.mode {
+            CameraMode::Orbit => {
+                let rotation = Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0);
+                Transform::from_translation(self.focus + rotation * Vec3::new(0.0, 0.0, self.distance))
+                    .with_rotation(rotation)
+            }
+            CameraMode::Fly {
+                translation, yaw, pitch, ..
+            } => Transform::from_translation(translation).with_rotation(Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0)),
+        }
+    }
+
+    /// Starts moving the focus to the center of the world-space `corners` and zooming so that they
+    /// fit in a viewport of `size` logical pixels, with `margin` of it left on each side.
+    pub fn frame(&mut self, corners: &[Vec3], projection: &OrthographicProjection, size: Vec2, margin: f32) {
+        let (min, max) = corners.iter().fold((Vec3::MAX, Vec3::MIN), |(min, max), &corner| {
+            (min.min(corner), max.max(corner))
+        });
+        let center = (min + max) / 2.0;
+
+        // Measure the corners as seen once any rotation in progress has finished.
+        let view = Quat::from_euler(EulerRot::YXZ, self.target_yaw(), self.pitch, 0.0).inverse();
+        let extent = corners
+            .iter()
+            .map(|&corner| (view * (corner - center)).xy().abs())
+            .fold(Vec2::ZERO, Vec2::max) *
+            2.0;
+
+        let scale = (extent / (size * (1.0 - 2.0 * margin))).max_element();
+        self.framing = Some(FrameTween {
+            from: (self.focus, projection.scale),
+            to: (center, if scale > 0.0 { scale } else { projection.scale }),
+            elapsed: 0.0,
+        });
+    }
+
+    /// Where the pointer is in the viewport: the cursor when orbiting, or the center when flying.
+    #[inline]
+    pub fn pointer_position(&self, camera: &Camera, window: &Window) -> Option<Vec2> {
+        match self.mode {
+            CameraMode::Orbit => window.cursor_position(),
+            CameraMode::Fly { .. } => camera.logical_viewport_size().map(|size| size / 2.0),
+        }
+    }
+}
+
+/// Holds the mouse pointer at the viewport center while flying, where the cursor is grabbed and no
+/// longer moves it, so the picking stack aims where the camera looks.
+pub fn aim_flying_pointer(
+    window: Query<(Entity, &Window), With<PrimaryWindow>>,
+    cameras: Query<(&EditorCamera, &Camera)>,
+    mut moves: EventWriter<InputMove>,
+) {
+    let Ok((window_e, window)) = window.get_single() else {
+        return
+    };
+
+    for (camera, cam) in cameras.iter().filter(|(camera, _)| camera.is_flying()) {
+        let (Some(position), Some(target)) = (camera.pointer_position(cam, window), cam.target.normalize(Some(window_e)))
+        else {
+            continue
+        };
+
+        moves.send(InputMove::new(PointerId::Mouse, Location { target, position }, Vec2::ZERO));
+    }
+}
+
+pub fn toggle_fly_camera(
+    settings: Res<EditorCameraSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&mut EditorCamera, &mut Projection, &Transform)>,
+) {
+    if !keys.just_pressed(settings.fly_toggle) {
+        return
+    }
+
+    let mut flying = false;
+    for (mut camera, mut projection, trns) in &mut cameras {
+        camera.mode = match std::mem::replace(&mut camera.mode, CameraMode::Orbit) {
+            CameraMode::Orbit => {
+                let Projection::Orthographic(ref ortho) = *projection else {
+                    continue
+                };
+
+                // Back off from the focus so the perspective frustum roughly frames what the orthographic view did.
+                let ortho = ortho.clone();
+                let perspective = PerspectiveProjection::default();
+                let distance = ortho.area.height() * 0.5 / (perspective.fov * 0.5).tan();
+
+                *projection = Projection::Perspective(perspective);
+
+                let (yaw, pitch, ..) = trns.rotation.to_euler(EulerRot::YXZ);
+                CameraMode::Fly {
+                    translation: camera.focus - *trns.forward() * distance,
+                    yaw,
+                    pitch,
+                    ortho,
+                }
+            }
+            CameraMode::Fly { ortho, .. } => {
+                *projection = Projection::Orthographic(ortho);
+                CameraMode::Orbit
+            }
+        };
+
+        flying |= camera.is_flying();
+    }
+
+    if let Ok(mut window) = window.get_single_mut() {
+        window.cursor.grab_mode = if flying {
+            CursorGrabMode::Locked
+        } else {
+            CursorGrabMode::None
+        };
+        window.cursor.visible = !flying;
+    }
+}
+
+pub fn pan_editor_camera(
+    settings: Res<EditorCameraSettings>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut motion: EventReader<MouseMotion>,
+    mut cameras: Query<(&mut EditorCamera, &Transform, &Projection)>,
+) {
+    let delta = motion.read().map(|e| e.delta).sum::<Vec2>();
+    if delta == Vec2::ZERO ||
+        !(mouse.pressed(MouseButton::Middle) || (keys.pressed(KeyCode::Space) && mouse.pressed(MouseButton::Left)))
+    {
+        return
+    }
+
+    for (mut camera, trns, projection) in &mut cameras {
+        let Projection::Orthographic(projection) = projection else {
+            continue
+        };
+
+        let scale = projection.scale * settings.pan_sensitivity;
+        camera.framing = None;
+        camera.focus += (*trns.left() * delta.x + *trns.up() * delta.y) * scale;
+    }
+}
+
+pub fn zoom_editor_camera(
+    settings: Res<EditorCameraSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&mut EditorCamera, &mut Projection, &Camera, &GlobalTransform)>,
+) {
+    let lines = wheel
+        .read()
+        .map(|e| match e.unit {
+            MouseScrollUnit::Line => e.y,
+            MouseScrollUnit::Pixel => e.y / 16.0,
+        })
+        .sum::<f32>();
+
+    // Control-, shift-, and sun-scroll are reserved for other editor bindings.
+    if lines == 0.0 ||
+        keys.any_pressed([
+            KeyCode::ControlLeft,
+            KeyCode::ControlRight,
+            KeyCode::ShiftLeft,
+            KeyCode::ShiftRight,
+            SUN_KEY,
+        ])
+    {
+        return
+    }
+
+    let cursor = window.get_single().ok().and_then(Window::cursor_position);
+    for (mut camera, mut projection, cam, &global_trns) in &mut cameras {
+        let Projection::Orthographic(ref mut projection) = *projection else {
+            continue
+        };
+
+        let old_scale = projection.scale;
+        let new_scale =
+            (old_scale * (1.0 - settings.zoom_sensitivity).powf(lines)).clamp(settings.min_scale, settings.max_scale);
+        if new_scale == old_scale {
+            continue
+        }
+
+        projection.scale = new_scale;
+        camera.framing = None;
+
+        // Keep the world point under the cursor fixed by shifting the focus along the view plane.
+        if let Some(ray) = cursor.and_then(|cursor| cam.viewport_to_world(&global_trns, cursor)) {
+            let offset = ray.origin - global_trns.translation();
+            let offset = offset - *global_trns.forward() * offset.dot(*global_trns.forward());
+            camera.focus += offset * (1.0 - new_scale / old_scale);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn rotate_editor_camera(
+    time: Res<Time>,
+    settings: Res<EditorCameraSettings>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    tool: Res<State<ToolMode>>,
+    edit_mode: Res<State<EditMode>>,
+    mut motion: EventReader<MouseMotion>,
+    mut cameras: Query<&mut EditorCamera>,
+) {
+    // The select tool takes alt-drags for taking cells out of the selection.
+    let selecting = **tool == ToolMode::Select && **edit_mode == EditMode::Tile;
+    let dragging =
+        !selecting && keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]) && mouse.pressed(MouseButton::Left);
+    let drag = motion.read().map(|e| e.delta.x).sum::<f32>();
+
+    for mut camera in &mut cameras {
+        if camera.is_flying() {
+            continue
+        }
+
+        let mut steps = 0i32;
+        if keys.just_pressed(KeyCode::KeyQ) {
+            steps -= 1;
+        }
+
+        if keys.just_pressed(KeyCode::KeyE) {
+            steps += 1;
+        }
+
+        if !dragging {
+            if camera.rotate_drag != 0.0 {
+                camera.rotate_drag = 0.0;
+            }
+        } else {
+            camera.rotate_drag += drag;
+            while camera.rotate_drag.abs() >= settings.rotate_drag_threshold {
+                let step = camera.rotate_drag.signum();
+                camera.rotate_drag -= step * settings.rotate_drag_threshold;
+                steps -= step as i32;
+            }
+        }
+
+        if steps != 0 {
+            camera.rotate_by(steps as f32 * FRAC_PI_2);
+        }
+
+        if let Some(mut tween) = camera.rotation {
+            tween.elapsed += time.delta_seconds();
+
+            let t = (tween.elapsed / settings.rotate_duration).min(1.0);
+            let eased = t * t * (3.0 - 2.0 * t);
+
+            camera.yaw = tween.from + (tween.to - tween.from) * eased;
+            camera.rotation = (t < 1.0).then_some(tween);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn frame_editor_camera(
+    time: Res<Time>,
+    settings: Res<EditorCameraSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    maps: Res<Assets<Map>>,
+    objs: Res<Assets<Obj>>,
+    selection: Res<EditorSelection>,
+    editor_maps: Query<(Ref<Handle<Map>>, &GlobalTransform), With<EditorMap>>,
+    mut cameras: Query<(&mut EditorCamera, &mut Projection, &Camera)>,
+    mut pending: Local<bool>,
+) {
+    let Ok((handle, &map_trns)) = editor_maps.get_single() else {
+        return
+    };
+
+    // Newly opened maps get framed once they're loaded.
+    *pending |= handle.is_changed();
+    let pressed = keys.just_pressed(settings.frame_key);
+
+    if let Some(map) = maps.get(&*handle).filter(|_| pressed || *pending) {
+        *pending = false;
+
+        let cells = |(min, max)| (map.cell_min(min), map.cell_min(max));
+        let (min, max) = match selection.cells.bounds() {
+            Some(region) => cells(region),
+            None => map.aabb(&objs).unwrap_or_else(|| cells((IVec3::ZERO, IVec3::ONE))),
+        };
+
+        let corners = (0..8)
+            .map(|i| {
+                let pick = BVec3::new(i & 1 != 0, i & 2 != 0, i & 4 != 0);
+                map_trns.transform_point(Vec3::select(pick, max, min))
+            })
+            .collect::<Vec<_>>();
+
+        for (mut camera, projection, cam) in &mut cameras {
+            let (Projection::Orthographic(projection), Some(size)) = (&*projection, cam.logical_viewport_size()) else {
+                continue
+            };
+
+            if std::mem::take(&mut camera.keep_view) && !pressed {
+                continue
+            }
+
+            if !camera.is_flying() {
+                camera.frame(&corners, projection, size, settings.frame_margin);
+            }
+        }
+    }
+
+    for (mut camera, mut projection, ..) in &mut cameras {
+        let Some(mut tween) = camera.framing else { continue };
+        let Projection::Orthographic(ref mut projection) = *projection else {
+            continue
+        };
+
+        tween.elapsed += time.delta_seconds();
+
+        let t = (tween.elapsed / settings.frame_duration).min(1.0);
+        let eased = t * t * (3.0 - 2.0 * t);
+
+        camera.focus = tween.from.0.lerp(tween.to.0, eased);
+        projection.scale =
+            (tween.from.1 + (tween.to.1 - tween.from.1) * eased).clamp(settings.min_scale, settings.max_scale);
+        camera.framing = (t < 1.0).then_some(tween);
+    }
+}
+
+pub fn fly_editor_camera(
+    time: Res<Time>,
+    settings: Res<EditorCameraSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut motion: EventReader<MouseMotion>,
+    mut cameras: Query<&mut EditorCamera>,
+) {
+    let look = motion.read().map(|e| e.delta).sum::<Vec2>() * settings.look_sensitivity;
+    let speed = match keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        true => settings.fly_speed * settings.fly_boost,
+        false => settings.fly_speed,
+    };
+
+    let mut dir = Vec3::ZERO;
+    for (key, axis) in [
+        (KeyCode::KeyW, Vec3::NEG_Z),
+        (KeyCode::KeyS, Vec3::Z),
+        (KeyCode::KeyA, Vec3::NEG_X),
+        (KeyCode::KeyD, Vec3::X),
+        (KeyCode::KeyE, Vec3::Y),
+        (KeyCode::KeyQ, Vec3::NEG_Y),
+    ] {
+        if keys.pressed(key) {
+            dir += axis;
+        }
+    }
+
+    for mut camera in &mut cameras {
+        if !camera.is_flying() {
+            continue
+        }
+
+        let CameraMode::Fly {
+            ref mut translation,
+            ref mut yaw,
+            ref mut pitch,
+            ..
+        } = camera.mode
+        else {
+            continue
+        };
+
+        *yaw -= look.x;
+        *pitch = (*pitch - look.y).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+
+        let rotation = Quat::from_euler(EulerRot::YXZ, *yaw, *pitch, 0.0);
+        *translation += rotation * dir.normalize_or_zero() * speed * time.delta_seconds();
+    }
+}
+
+pub fn apply_editor_camera(mut cameras: Query<(&EditorCamera, &mut Transform), Changed<EditorCamera>>) {
+    for (camera, mut trns) in &mut cameras {
+        *trns = camera.transform();
+    }
+}
@@ -0,0 +1,108 @@
+use std::{fs, io, path::PathBuf};
+
+use bevy::prelude::*;
+use thiserror::Error;
+
+use crate::{
+    editor::{
+        history::MapCommands,
+        prompt::Notice,
+        tools::{
+            select::{EditorSelection, Floating, FloatingFragment},
+            ToolMode,
+        },
+    },
+    map::fragment::MapFragment,
+    EditorState,
+};
+
+pub struct ClipboardPlugin;
+impl Plugin for ClipboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Clipboard>()
+            .add_systems(Update, copy_paste.run_if(in_state(EditorState::Editing)));
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ClipboardError {
+    #[error("The clipboard is empty.")]
+    Empty,
+    #[error("The clipboard doesn't hold map cells: {0}")]
+    Syntax(#[from] ron::error::SpannedError),
+    #[error("The clipboard holds malformed map cells.")]
+    Invalid,
+    #[error(transparent)]
+    Serialize(#[from] ron::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Copied map cells as RON, also kept in a file so other editor sessions can paste them.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct Clipboard {
+    pub text: Option<String>,
+}
+
+impl Clipboard {
+    /// The file shared between editor sessions.
+    #[inline]
+    pub fn file() -> PathBuf {
+        std::env::temp_dir().join("mnemonic-clipboard.ron")
+    }
+
+    /// Stores a fragment. It's always kept for this session, even if sharing it fails.
+    pub fn copy(&mut self, fragment: &MapFragment) -> Result<(), ClipboardError> {
+        let text = ron::ser::to_string_pretty(fragment, default())?;
+        self.text = Some(text.clone());
+        fs::write(Self::file(), text)?;
+        Ok(())
+    }
+
+    /// Reads the latest fragment copied by any session.
+    pub fn paste(&mut self) -> Result<MapFragment, ClipboardError> {
+        if let Ok(text) = fs::read_to_string(Self::file()) {
+            self.text = Some(text);
+        }
+
+        let fragment = ron::from_str::<MapFragment>(self.text.as_deref().ok_or(ClipboardError::Empty)?)?;
+        match fragment.is_valid() {
+            true => Ok(fragment),
+            false => Err(ClipboardError::Invalid),
+        }
+    }
+}
+
+pub fn copy_paste(
+    keys: Res<ButtonInput<KeyCode>>,
+    selection: Res<EditorSelection>,
+    mut clipboard: ResMut<Clipboard>,
+    mut floating: ResMut<Floating>,
+    mut notice: ResMut<Notice>,
+    mut next_mode: ResMut<NextState<ToolMode>>,
+    commands: MapCommands,
+) {
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return
+    }
+
+    if keys.just_pressed(KeyCode::KeyC) {
+        let Some(fragment) = commands.map().and_then(|map| selection.cells.extract(map)) else {
+            return
+        };
+        let size = fragment.size;
+
+        match clipboard.copy(&fragment) {
+            Ok(()) => notice.show(format!("Copied {}x{}x{}.", size.x, size.y, size.z)),
+            Err(e) => notice.show(format!("Copied {}x{}x{}, but couldn't share it: {e}", size.x, size.y, size.z)),
+        }
+    } else if keys.just_pressed(KeyCode::KeyV) {
+        match clipboard.paste() {
+            Ok(fragment) => {
+                **floating = Some(FloatingFragment::new(fragment, false));
+                next_mode.set(ToolMode::Select);
+            }
+            Err(e) => notice.show(e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use std::{fs, io, path::Path, time::Duration};
+
+use bevy::{
+    app::AppExit,
+    prelude::*,
+    utils::Instant,
+    window::{MonitorSelection, PresentMode, PrimaryWindow, WindowMode, WindowMoved, WindowPosition, WindowResized},
+    winit::WinitWindows,
+};
+use ron::{error::SpannedError, Error as RonError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    editor::{prompt::Notice, session::maps_dir, EditorSettings},
+    AppConfig, AssetRoot, EditorUi,
+};
+
+pub struct DisplayPlugin;
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SavedWindow>()
+            .add_systems(
+                Update,
+                (
+                    (
+                        cycle_present_mode.run_if(in_state(EditorUi)),
+                        apply_present_mode.run_if(resource_changed::<EditorSettings>),
+                    )
+                        .chain(),
+                    (toggle_fullscreen, clamp_restored_window, track_window_state).chain(),
+                ),
+            )
+            .add_systems(Last, (save_display_settings, limit_frame_rate).chain());
+    }
+}
+
+/// The file in [`MAPS_DIR`](crate::editor::session::MAPS_DIR) display settings are kept in between
+/// runs.
+pub const SETTINGS_FILE: &str = ".settings.ron";
+
+/// Seconds display settings have to stay put before they're written, as dragging a window around
+/// moves it every frame.
+pub const DISPLAY_SAVE_DEBOUNCE: f32 = 0.5;
+/// Least and most logical size a restored window may have.
+pub const WINDOW_SIZE_LIMITS: (Vec2, Vec2) = (Vec2::new(320.0, 240.0), Vec2::new(16384.0, 16384.0));
+/// Least physical pixels of a restored window that must lie on a monitor along each axis for it to
+/// stay where it was, rather than be centered on the primary monitor.
+pub const WINDOW_MIN_VISIBLE: i32 = 64;
+
+/// The present modes cycled through.
+pub const PRESENT_MODES: [PresentMode; 3] = [PresentMode::AutoVsync, PresentMode::AutoNoVsync, PresentMode::Fifo];
+
+/// Name of a present mode for the status bar and menu.
+#[inline]
+pub fn present_mode_name(mode: PresentMode) -> &'static str {
+    match mode {
+        PresentMode::AutoVsync => "vsync",
+        PresentMode::AutoNoVsync => "no vsync",
+        PresentMode::Fifo => "FIFO vsync",
+        PresentMode::FifoRelaxed => "relaxed FIFO vsync",
+        PresentMode::Immediate => "immediate",
+        PresentMode::Mailbox => "mailbox",
+    }
+}
+
+/// The present mode after another in [`PRESENT_MODES`].
+#[inline]
+pub fn next_present_mode(mode: PresentMode) -> PresentMode {
+    let current = PRESENT_MODES.iter().position(|&other| other == mode);
+    PRESENT_MODES[current.map_or(0, |i| (i + 1) % PRESENT_MODES.len())]
+}
+
+#[derive(Error, Debug)]
+pub enum DisplaySettingsError {
+    #[error(transparent)]
+    Syntax(#[from] SpannedError),
+    #[error(transparent)]
+    Serialize(#[from] RonError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// The parts of [`EditorSettings`] kept between runs along with the window's state, applied before
+/// the window opens.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub struct DisplaySettings {
+    pub present_mode: PresentMode,
+    #[serde(default)]
+    pub frame_cap: Option<f32>,
+    #[serde(default)]
+    pub window: Option<WindowState>,
+}
+
+impl From<&EditorSettings> for DisplaySettings {
+    #[inline]
+    fn from(settings: &EditorSettings) -> Self {
+        Self {
+            present_mode: settings.present_mode,
+            frame_cap: settings.frame_cap,
+            window: None,
+        }
+    }
+}
+
+/// Where the primary window was and how it was shown. The size and position are those it had while
+/// last windowed, so leaving fullscreen or unmaximizing after a restore goes back to them.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub struct WindowState {
+    /// Logical size.
+    pub size: Vec2,
+    /// Physical position of the top-left corner, if the platform tells it.
+    #[serde(default)]
+    pub position: Option<IVec2>,
+    #[serde(default)]
+    pub maximized: bool,
+    #[serde(default)]
+    pub fullscreen: bool,
+}
+
+/// The primary window's state as last seen, kept for [`DisplaySettings`].
+#[derive(Resource, Copy, Clone, Default, Debug, Deref, DerefMut)]
+pub struct SavedWindow(pub Option<WindowState>);
+
+impl DisplaySettings {
+    pub fn load(root: &Path) -> Result<Self, DisplaySettingsError> {
+        Ok(ron::from_str(&fs::read_to_string(maps_dir(root).join(SETTINGS_FILE))?)?)
+    }
+
+    pub fn save(&self, root: &Path) -> Result<(), DisplaySettingsError> {
+        let dir = maps_dir(root);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(SETTINGS_FILE), ron::ser::to_string_pretty(self, default())?)?;
+        Ok(())
+    }
+
+    /// Sets the app up as these settings say. Window sizes are kept within [`WINDOW_SIZE_LIMITS`].
+    pub fn apply(self, config: &mut AppConfig) {
+        config.present_mode = self.present_mode;
+        config.frame_cap = self.frame_cap;
+        if let Some(window) = self.window {
+            let (min, max) = WINDOW_SIZE_LIMITS;
+            config.resolution = window.size.is_finite().then(|| window.size.clamp(min, max));
+            config.position = window.position;
+            config.maximized = window.maximized;
+            config.fullscreen = window.fullscreen;
+        }
+    }
+}
+
+pub fn cycle_present_mode(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<EditorSettings>,
+    mut notice: ResMut<Notice>,
+) {
+    if keys.just_pressed(settings.present_mode_cycle) {
+        settings.present_mode = next_present_mode(settings.present_mode);
+        notice.show(format!("Presenting with {}.", present_mode_name(settings.present_mode)));
+    }
+}
+
+pub fn apply_present_mode(settings: Res<EditorSettings>, mut windows: Query<&mut Window, With<PrimaryWindow>>) {
+    for mut window in &mut windows {
+        if window.present_mode != settings.present_mode {
+            window.present_mode = settings.present_mode;
+        }
+    }
+}
+
+pub fn toggle_fullscreen(
+    keys: Res<ButtonInput<KeyCode>>,
+    settings: Option<Res<EditorSettings>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if settings.is_some_and(|settings| keys.just_pressed(settings.fullscreen_toggle)) {
+        for mut window in &mut windows {
+            window.mode = toggled_fullscreen(window.mode);
+        }
+    }
+}
+
+/// Borderless fullscreen on the window's current monitor if windowed, or windowed otherwise.
+#[inline]
+pub fn toggled_fullscreen(mode: WindowMode) -> WindowMode {
+    match mode {
+        WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+        _ => WindowMode::Windowed,
+    }
+}
+
+/// Centers the primary window on the primary monitor if it was restored to where no monitor shows
+/// enough of it, such as on one since disconnected. Only checked once the window is created.
+pub fn clamp_restored_window(
+    winit_windows: NonSend<WinitWindows>,
+    mut windows: Query<(Entity, &mut Window), With<PrimaryWindow>>,
+    mut done: Local<bool>,
+) {
+    if *done {
+        return
+    }
+
+    let Ok((e, mut window)) = windows.get_single_mut() else { return };
+    let Some(winit_window) = winit_windows.get_window(e) else { return };
+    *done = true;
+
+    let WindowPosition::At(position) = window.position else { return };
+    let size = winit_window.outer_size();
+    let (min, max) = (position, position + IVec2::new(size.width as i32, size.height as i32));
+
+    let visible = winit_window.available_monitors().any(|monitor| {
+        let (at, size) = (monitor.position(), monitor.size());
+        let monitor_min = IVec2::new(at.x, at.y);
+        let monitor_max = monitor_min + IVec2::new(size.width as i32, size.height as i32);
+        (max.min(monitor_max) - min.max(monitor_min)).cmpge(IVec2::splat(WINDOW_MIN_VISIBLE)).all()
+    });
+
+    if !visible {
+        window.position = WindowPosition::Centered(MonitorSelection::Primary);
+    }
+}
+
+/// Follows the primary window as it's resized, moved, maximized, or made fullscreen.
+pub fn track_window_state(
+    mut resized: EventReader<WindowResized>,
+    mut moved: EventReader<WindowMoved>,
+    winit_windows: NonSend<WinitWindows>,
+    windows: Query<(Entity, &Window), With<PrimaryWindow>>,
+    mut saved: ResMut<SavedWindow>,
+) {
+    let Ok((e, window)) = windows.get_single() else { return };
+    let resized = resized.read().filter(|event| event.window == e).count() > 0;
+    let moved = moved.read().filter(|event| event.window == e).last().map(|event| event.position);
+
+    let maximized = winit_windows.get_window(e).is_some_and(|window| window.is_maximized());
+    let fullscreen = window.mode != WindowMode::Windowed;
+    let mut state = saved.unwrap_or(WindowState {
+        size: Vec2::new(window.resolution.width(), window.resolution.height()),
+        position: None,
+        maximized,
+        fullscreen,
+    });
+
+    // Maximized and fullscreen windows don't have a windowed size or position to keep.
+    if !maximized && !fullscreen {
+        if resized {
+            state.size = Vec2::new(window.resolution.width(), window.resolution.height());
+        }
+
+        if let Some(position) = moved {
+            state.position = Some(position);
+        }
+    }
+
+    state.maximized = maximized;
+    state.fullscreen = fullscreen;
+    if saved.0 != Some(state) {
+        saved.0 = Some(state);
+    }
+}
+
+/// Writes the display settings once they've stayed put for [`DISPLAY_SAVE_DEBOUNCE`] after changing,
+/// or right away as the app exits. They aren't written when first seen.
+pub fn save_display_settings(
+    time: Res<Time>,
+    root: Res<AssetRoot>,
+    settings: Option<Res<EditorSettings>>,
+    window: Res<SavedWindow>,
+    mut exit: EventReader<AppExit>,
+    mut saved: Local<Option<DisplaySettings>>,
+    mut pending: Local<Option<(DisplaySettings, f32)>>,
+) {
+    let Some(settings) = settings else { return };
+    let display = DisplaySettings {
+        window: **window,
+        ..DisplaySettings::from(&*settings)
+    };
+
+    let Some(last) = *saved else {
+        *saved = Some(display);
+        return
+    };
+
+    if last == display {
+        *pending = None;
+        return
+    }
+
+    let now = time.elapsed_seconds();
+    let since = match *pending {
+        Some((waiting, since)) if waiting == display => since,
+        _ => pending.insert((display, now)).1,
+    };
+
+    if exit.read().count() == 0 && now - since < DISPLAY_SAVE_DEBOUNCE {
+        return
+    }
+
+    *saved = Some(display);
+    *pending = None;
+    if let Err(e) = display.save(&root) {
+        warn!("Couldn't save display settings: {e}");
+    }
+}
+
+/// Sleeps off the rest of each frame to keep under [`EditorSettings::frame_cap`], so an idle editor
+/// without vsync doesn't spin a core.
+pub fn limit_frame_rate(settings: Option<Res<EditorSettings>>, mut last: Local<Option<Instant>>) {
+    let Some(cap) = settings.and_then(|settings| settings.frame_cap).filter(|&cap| cap > 0.0) else {
+        *last = None;
+        return
+    };
+
+    let frame = Duration::from_secs_f32(1.0 / cap);
+    if let Some(elapsed) = last.map(|last| last.elapsed()) {
+        if elapsed < frame {
+            std::thread::sleep(frame - elapsed);
+        }
+    }
+
+    *last = Some(Instant::now());
+}
@@ -0,0 +1,146 @@
+use bevy::{color::palettes::css, prelude::*, utils::HashMap};
+use bevy_mod_picking::pointer::PointerId;
+
+use crate::{
+    content::{properties::TilePropertyTable, Tiles},
+    editor::{
+        palette::{ActiveTile, PlacementRotation},
+        tools::{update_cursor_target, CursorTarget, ToolMode},
+        EditorMap, EditorSettings,
+    },
+    map::{picking::PointerOverUi, EditMode, Map},
+    obj::def::Obj,
+    EditorUi,
+};
+
+pub struct GhostPlugin;
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GhostMeshes>()
+            .init_resource::<GhostMaterial>()
+            .add_systems(
+                Update,
+                update_ghost.after(update_cursor_target).run_if(in_state(EditorUi)),
+            );
+    }
+}
+
+/// Standalone meshes of tiles previewed by the placement ghost, built once per tile.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct GhostMeshes(pub HashMap<AssetId<Obj>, Handle<Mesh>>);
+
+#[derive(Resource, Clone, Deref)]
+pub struct GhostMaterial(pub Handle<StandardMaterial>);
+impl FromWorld for GhostMaterial {
+    fn from_world(world: &mut World) -> Self {
+        Self(world.resource_mut::<Assets<StandardMaterial>>().add(StandardMaterial {
+            base_color: Color::WHITE.with_alpha(0.4),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            // Mirrored placements turn the mesh inside out.
+            cull_mode: None,
+            ..default()
+        }))
+    }
+}
+
+/// Previews the active tile where a left click would place it.
+#[derive(Component, Copy, Clone)]
+pub struct PlacementGhost;
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_ghost(
+    mut commands: Commands,
+    settings: Res<EditorSettings>,
+    edit_mode: Res<State<EditMode>>,
+    mode: Res<State<ToolMode>>,
+    target: Res<CursorTarget>,
+    active: Res<ActiveTile>,
+    rotation: Res<PlacementRotation>,
+    (tiles, properties): (Res<Tiles>, Res<TilePropertyTable>),
+    objs: Res<Assets<Obj>>,
+    maps: Res<Assets<Map>>,
+    material: Res<GhostMaterial>,
+    mut ghost_meshes: ResMut<GhostMeshes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    pointer_over_ui: Res<PointerOverUi>,
+    editor_maps: Query<(Entity, &Handle<Map>, &GlobalTransform), With<EditorMap>>,
+    mut ghosts: Query<(Entity, &mut Handle<Mesh>, &mut Transform, &mut Visibility), With<PlacementGhost>>,
+    mut gizmos: Gizmos,
+) {
+    let Ok((map_entity, map, map_trns)) = editor_maps.get_single() else {
+        return
+    };
+    let Some(map) = maps.get(map) else { return };
+
+    // Tiles are only placed in tile mode.
+    let tiling = **edit_mode == EditMode::Tile && !pointer_over_ui.over(PointerId::Mouse);
+    let placing = matches!(**mode, ToolMode::Place) && tiling;
+    let erasing = matches!(**mode, ToolMode::Place | ToolMode::Erase) && tiling;
+
+    // Outline the cell a right click would erase.
+    if let Some(hit) = target.hit.filter(|_| erasing) {
+        let (lower, upper) = (map.cell_min(hit.cell.as_ivec3()), map.cell_min(hit.cell.as_ivec3() + 1));
+        gizmos.cuboid(
+            *map_trns * Transform::from_translation((lower + upper) * 0.5).with_scale((upper - lower) * 1.02),
+            css::ORANGE_RED,
+        );
+    }
+
+    let cell = target
+        .click_cell(true)
+        .filter(|&cell| placing && (map.contains(cell) || settings.auto_grow));
+
+    let mesh = active
+        .path(map)
+        .and_then(|name| tiles.handle(name))
+        .and_then(|handle| Some((handle.id(), objs.get(handle)?)))
+        .map(|(id, obj)| {
+            ghost_meshes
+                .entry(id)
+                .or_insert_with(|| meshes.add(obj.to_mesh()))
+                .clone_weak()
+        });
+
+    let (Some(cell), Some(mesh)) = (cell, mesh) else {
+        for (.., mut visibility) in &mut ghosts {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+
+        return
+    };
+
+    // Unrotatable tiles are placed unturned; see `Map::orient_after`.
+    let rotatable = active
+        .path(map)
+        .and_then(|name| tiles.resolve(name))
+        .map_or(true, |key| properties.get(&key).rotatable);
+    let transform = match rotatable {
+        true => rotation.transform(),
+        false => Transform::IDENTITY,
+    }
+    .with_translation(cell.as_vec3() * map.tile_size);
+    match ghosts.get_single_mut() {
+        Ok((.., mut ghost_mesh, mut trns, mut visibility)) => {
+            if *ghost_mesh != mesh {
+                *ghost_mesh = mesh;
+            }
+
+            trns.set_if_neq(transform);
+            visibility.set_if_neq(Visibility::Inherited);
+        }
+        Err(..) => {
+            commands.entity(map_entity).with_children(|children| {
+                children.spawn((
+                    PbrBundle {
+                        mesh,
+                        material: material.clone_weak(),
+                        transform,
+                        ..default()
+                    },
+                    PlacementGhost,
+                ));
+            });
+        }
+    }
+}
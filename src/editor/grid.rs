@@ -0,0 +1,146 @@
+use bevy::{color::palettes::css, prelude::*};
+
+use crate::{
+    editor::{
+        brush::{Brush, BrushShape},
+        camera::EditorCamera,
+        layer::ActiveLayer,
+        tools::{update_cursor_target, CursorTarget, ToolMode},
+        EditorMap, EditorSettings,
+    },
+    map::Map,
+    EditorUi,
+};
+
+pub struct GridPlugin;
+impl Plugin for GridPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_gizmo_group::<GridGizmos>()
+            .insert_gizmo_config(BoundsGizmos, GizmoConfig {
+                line_width: 4.0,
+                ..default()
+            })
+            .add_systems(
+                Update,
+                (toggle_grid, draw_grid)
+                    .chain()
+                    .after(update_cursor_target)
+                    .run_if(in_state(EditorUi)),
+            );
+    }
+}
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct GridGizmos;
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct BoundsGizmos;
+
+/// The local-space corners of a cell face, given the face's outward normal.
+pub fn face_corners(map: &Map, cell: IVec3, normal: IVec3) -> [Vec3; 4] {
+    let axis = (0..3).max_by_key(|&axis| normal[axis].abs()).unwrap_or(1);
+    let (u, v) = (Vec3::AXES[(axis + 1) % 3], Vec3::AXES[(axis + 2) % 3]);
+    let (u, v) = (u * map.tile_size * 0.5, v * map.tile_size * 0.5);
+
+    let center = (cell.as_vec3() + normal.as_vec3() * 0.5) * map.tile_size;
+    [center - u - v, center + u - v, center + u + v, center - u + v]
+}
+
+pub fn toggle_grid(keys: Res<ButtonInput<KeyCode>>, mut settings: ResMut<EditorSettings>) {
+    if keys.just_pressed(settings.grid_toggle) {
+        settings.show_grid = !settings.show_grid;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn draw_grid(
+    settings: Res<EditorSettings>,
+    brush: Res<Brush>,
+    mode: Res<State<ToolMode>>,
+    maps: Res<Assets<Map>>,
+    target: Res<CursorTarget>,
+    active_layer: Res<ActiveLayer>,
+    cameras: Query<&Projection, With<EditorCamera>>,
+    editor_maps: Query<(&Handle<Map>, &GlobalTransform), With<EditorMap>>,
+    mut grid: Gizmos<GridGizmos>,
+    mut bounds: Gizmos<BoundsGizmos>,
+) {
+    if !settings.show_grid {
+        return
+    }
+
+    let Ok(projection) = cameras.get_single() else { return };
+
+    // Only one map is edited at a time, so the cursor target belongs to it.
+    let Ok((map, &map_trns)) = editor_maps.get_single() else {
+        return
+    };
+    let Some(map) = maps.get(map) else { return };
+
+    let layer = **active_layer as i32;
+
+    // Fade the grid out as cells shrink on screen, before the lines start to alias into moiré.
+    let alpha = match projection {
+        Projection::Orthographic(ortho) => {
+            let (from, to) = settings.grid_fade;
+            ((map.tile_size.x.min(map.tile_size.z) / ortho.scale - from) / (to - from)).clamp(0.0, 1.0)
+        }
+        Projection::Perspective(..) => 1.0,
+    };
+
+    let margin = settings.grid_margin as i32;
+    let (min, max) = (
+        IVec3::new(-margin, layer, -margin),
+        IVec3::new(map.size.x as i32 + margin, layer, map.size.z as i32 + margin),
+    );
+
+    if alpha > 0.0 {
+        let color = css::GRAY.with_alpha(0.5 * alpha);
+        for x in min.x..=max.x {
+            grid.line(
+                map_trns.transform_point(map.cell_min(IVec3::new(x, layer, min.z))),
+                map_trns.transform_point(map.cell_min(IVec3::new(x, layer, max.z))),
+                color,
+            );
+        }
+
+        for z in min.z..=max.z {
+            grid.line(
+                map_trns.transform_point(map.cell_min(IVec3::new(min.x, layer, z))),
+                map_trns.transform_point(map.cell_min(IVec3::new(max.x, layer, z))),
+                color,
+            );
+        }
+    }
+
+    let (lower, upper) = (map.cell_min(IVec3::ZERO), map.cell_min(map.size.as_ivec3()));
+    bounds.cuboid(
+        map_trns * Transform::from_translation((lower + upper) * 0.5).with_scale(upper - lower),
+        css::WHITE,
+    );
+
+    let target = match target.hit {
+        Some(hit) => Some((hit.cell.as_ivec3(), hit.normal)),
+        None => target
+            .layer_cell
+            .filter(|cell| cell.xz().cmpge(min.xz()).all() && cell.xz().cmplt(max.xz()).all())
+            .map(|cell| (cell, IVec3::NEG_Y)),
+    };
+
+    if let Some((cell, normal)) = target {
+        let normal = if normal == IVec3::ZERO { IVec3::Y } else { normal };
+        // Only the brush paints more than the targeted cell.
+        let brush = match **mode {
+            ToolMode::Place | ToolMode::Erase => *brush,
+            _ => Brush {
+                shape: BrushShape::Single,
+                ..*brush
+            },
+        };
+
+        for cell in brush.footprint(cell) {
+            let corners = face_corners(map, cell, normal).map(|corner| map_trns.transform_point(corner));
+            grid.linestrip([corners[0], corners[1], corners[2], corners[3], corners[0]], css::YELLOW);
+        }
+    }
+}
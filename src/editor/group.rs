@@ -0,0 +1,236 @@
+use std::{fs, io, path::Path};
+
+use bevy::prelude::*;
+use ron::{error::SpannedError, Error as RonError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    content::manifest::TileCatalog,
+    editor::{
+        palette::ActiveTile,
+        prompt::{ActivePrompt, Notice, Prompt, PromptSubmit},
+        EditorMap,
+    },
+    map::{pick_weighted, Map},
+    AssetRoot, EditorState, EditorUi,
+};
+
+pub struct GroupPlugin;
+impl Plugin for GroupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveGroup>().add_systems(
+            Update,
+            (group_shortcuts.run_if(in_state(EditorState::Editing)), answer_group_prompts)
+                .chain()
+                .run_if(in_state(EditorUi)),
+        );
+    }
+}
+
+/// The asset directory brush groups are kept in, independently of maps.
+pub const BRUSHES_DIR: &str = "brushes";
+
+#[derive(Error, Debug)]
+pub enum BrushGroupError {
+    #[error(transparent)]
+    Syntax(#[from] SpannedError),
+    #[error(transparent)]
+    Serialize(#[from] RonError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Tiles painted in place of the active tile while the group is enabled, each cell picking one at
+/// random in proportion to their weights.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct BrushGroup {
+    pub members: Vec<GroupMember>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct GroupMember {
+    pub tile: String,
+    pub weight: f32,
+}
+
+impl BrushGroup {
+    /// Adds weight to a tile, adding the tile as a member if it isn't one yet.
+    pub fn add(&mut self, tile: &str, weight: f32) {
+        match self.members.iter_mut().find(|member| member.tile == tile) {
+            Some(member) => member.weight += weight,
+            None => self.members.push(GroupMember {
+                tile: tile.into(),
+                weight,
+            }),
+        }
+    }
+
+    /// Whether any member can be picked at all.
+    #[inline]
+    pub fn is_pickable(&self) -> bool {
+        self.members.iter().any(|member| member.weight > 0.0)
+    }
+
+    /// Picks a member with a random number, typically from
+    /// [`cell_random`](crate::map::cell_random).
+    #[inline]
+    pub fn pick(&self, random: u64) -> Option<&GroupMember> {
+        pick_weighted(self.members.iter().map(|member| member.weight), random).map(|index| &self.members[index])
+    }
+
+    pub fn load(root: &Path, path: &str) -> Result<Self, BrushGroupError> {
+        Ok(ron::from_str(&fs::read_to_string(root.join(path))?)?)
+    }
+
+    pub fn save(&self, root: &Path, path: &str) -> Result<(), BrushGroupError> {
+        let path = root.join(path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, ron::ser::to_string_pretty(self, default())?)?;
+        Ok(())
+    }
+}
+
+/// The brush group tools paint from, and whether they currently do.
+#[derive(Resource, Clone, Debug)]
+pub struct ActiveGroup {
+    pub group: BrushGroup,
+    pub enabled: bool,
+    /// The asset path the group was loaded from or last saved to.
+    pub path: Option<String>,
+    pub toggle: KeyCode,
+}
+
+impl Default for ActiveGroup {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            group: default(),
+            enabled: false,
+            path: None,
+            toggle: KeyCode::KeyY,
+        }
+    }
+}
+
+impl ActiveGroup {
+    #[inline]
+    pub fn name(&self) -> &str {
+        self.path
+            .as_deref()
+            .map(|path| path.trim_start_matches(&format!("{BRUSHES_DIR}/")).trim_end_matches(".ron"))
+            .unwrap_or("untitled")
+    }
+}
+
+/// Every brush group file in [`BRUSHES_DIR`] under the asset directory `root` as asset paths,
+/// sorted.
+pub fn list_groups(root: &Path) -> io::Result<Vec<String>> {
+    let mut groups = fs::read_dir(root.join(BRUSHES_DIR))?
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            name.ends_with(".ron").then(|| format!("{BRUSHES_DIR}/{name}"))
+        })
+        .collect::<Vec<_>>();
+
+    groups.sort_unstable();
+    Ok(groups)
+}
+
+/// Turns a typed group name into an asset path in [`BRUSHES_DIR`], or `None` if it isn't a plain
+/// file name.
+pub fn group_path(name: &str) -> Option<String> {
+    let name = name.trim();
+    let name = name.strip_suffix(".ron").unwrap_or(name);
+    (!name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ' ')))
+        .then(|| format!("{BRUSHES_DIR}/{name}.ron"))
+}
+
+/// The group toggle enables painting from the group, and with shift adds the active tile to it;
+/// ctrl+G loads a group and ctrl+shift+G saves it.
+#[allow(clippy::too_many_arguments)]
+pub fn group_shortcuts(
+    root: Res<AssetRoot>,
+    keys: Res<ButtonInput<KeyCode>>,
+    active: Res<ActiveTile>,
+    catalog: Res<TileCatalog>,
+    maps: Res<Assets<Map>>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    mut group: ResMut<ActiveGroup>,
+    mut prompt: ResMut<ActivePrompt>,
+    mut notice: ResMut<Notice>,
+) {
+    if prompt.is_some() {
+        return
+    }
+
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if ctrl && keys.just_pressed(KeyCode::KeyG) {
+        if shift {
+            let name = group.name().to_string();
+            **prompt = Some(Prompt::text("save-group", "Save brush group as", name));
+        } else {
+            match list_groups(&root) {
+                Ok(groups) if !groups.is_empty() => {
+                    **prompt = Some(Prompt::choice("open-group", "Open brush group", groups))
+                }
+                Ok(..) => notice.show(format!("No brush groups in `{BRUSHES_DIR}/`.")),
+                Err(e) => notice.show(format!("Couldn't list brush groups: {e}")),
+            }
+        }
+    } else if !ctrl && keys.just_pressed(group.toggle) {
+        if shift {
+            let tile = editor_maps
+                .get_single()
+                .ok()
+                .and_then(|handle| maps.get(handle))
+                .and_then(|map| active.path(map).map(str::to_string));
+
+            if let Some(tile) = tile {
+                group.group.add(&tile, 1.0);
+                notice.show(format!("Added `{}` to the brush group.", catalog.full_name(&tile)));
+            }
+        } else if group.enabled || group.group.is_pickable() {
+            group.enabled = !group.enabled;
+        } else {
+            notice.show("The brush group is empty; add tiles with shift+Y.");
+        }
+    }
+}
+
+pub fn answer_group_prompts(
+    root: Res<AssetRoot>,
+    mut answers: EventReader<PromptSubmit>,
+    mut group: ResMut<ActiveGroup>,
+    mut notice: ResMut<Notice>,
+) {
+    for answer in answers.read() {
+        match answer.id {
+            "open-group" => match BrushGroup::load(&root, &answer.value) {
+                Ok(loaded) => {
+                    group.group = loaded;
+                    group.path = Some(answer.value.clone());
+                    group.enabled = group.group.is_pickable();
+                    notice.show(format!("Opened brush group `{}`.", group.name()));
+                }
+                Err(e) => notice.show(format!("Couldn't open `{}`: {e}", answer.value)),
+            },
+            "save-group" => match group_path(&answer.value) {
+                Some(path) => match group.group.save(&root, &path) {
+                    Ok(()) => {
+                        group.path = Some(path);
+                        notice.show(format!("Saved brush group `{}`.", group.name()));
+                    }
+                    Err(e) => notice.show(format!("Couldn't save `{path}`: {e}")),
+                },
+                None => notice.show(format!("Invalid brush group name `{}`.", answer.value)),
+            },
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    content::properties::TilePropertyTable,
+    editor::{session::EditorSession, EditorMap},
+    map::{
+        diff::{MapDiff, MapEdit},
+        Map, MapMeshThrottle,
+    },
+    EditorState, EditorUi,
+};
+
+pub struct HistoryPlugin;
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditorHistory>()
+            .add_systems(
+                Update,
+                (undo_redo.run_if(in_state(EditorState::Editing)), end_released_strokes).run_if(in_state(EditorUi)),
+            );
+    }
+}
+
+/// Least time between remeshes of the editor map while a stroke is painted on it.
+pub const STROKE_REMESH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Undoable steps taken on the editor map, oldest first.
+#[derive(Resource, Clone, Debug)]
+pub struct EditorHistory {
+    /// The most steps kept; older ones are forgotten first.
+    pub cap: usize,
+    undo: VecDeque<MapDiff>,
+    redo: Vec<MapDiff>,
+    /// Changes of the stroke still going, recorded as one step once it ends.
+    stroke: Option<MapDiff>,
+}
+
+impl Default for EditorHistory {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            cap: 256,
+            undo: default(),
+            redo: default(),
+            stroke: None,
+        }
+    }
+}
+
+impl EditorHistory {
+    /// Records a step, or folds it into the latest one if `merge` is set. Empty diffs are ignored;
+    /// returns whether the diff was recorded.
+    pub fn record(&mut self, diff: MapDiff, merge: bool) -> bool {
+        if diff.is_empty() {
+            return false
+        }
+
+        self.redo.clear();
+        match self.undo.back_mut() {
+            Some(last) if merge => last.extend(diff),
+            _ => {
+                self.undo.push_back(diff);
+                while self.undo.len() > self.cap.max(1) {
+                    self.undo.pop_front();
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Folds a diff into the stroke still going, starting one if there is none. Empty diffs are
+    /// ignored; returns whether the diff was kept.
+    pub fn stroke(&mut self, diff: MapDiff) -> bool {
+        if diff.is_empty() {
+            return false
+        }
+
+        self.redo.clear();
+        match &mut self.stroke {
+            Some(stroke) => stroke.extend(diff),
+            None => self.stroke = Some(diff),
+        }
+
+        true
+    }
+
+    /// Records the stroke still going as one step, returning whether there was one.
+    #[inline]
+    pub fn end_stroke(&mut self) -> bool {
+        self.stroke.take().is_some_and(|stroke| self.record(stroke, false))
+    }
+
+    #[inline]
+    pub fn is_stroking(&self) -> bool {
+        self.stroke.is_some()
+    }
+
+    /// How many steps may be undone, not counting a stroke still going.
+    #[inline]
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.stroke.is_some()
+    }
+
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.stroke = None;
+    }
+}
+
+/// The only way editor tools should change the editor map, so every change can be undone.
+#[derive(SystemParam)]
+pub struct MapCommands<'w, 's> {
+    maps: ResMut<'w, Assets<Map>>,
+    history: ResMut<'w, EditorHistory>,
+    session: ResMut<'w, EditorSession>,
+    properties: Res<'w, TilePropertyTable>,
+    throttle: ResMut<'w, MapMeshThrottle>,
+    editor_maps: Query<'w, 's, (&'static Handle<Map>, &'static mut Transform), With<EditorMap>>,
+}
+
+impl MapCommands<'_, '_> {
+    #[inline]
+    pub fn map(&self) -> Option<&Map> {
+        self.maps.get(self.editor_maps.get_single().ok()?.0)
+    }
+
+    /// Mutable access to the editor map that isn't recorded, for changes that needn't be undone,
+    /// such as growing the tile set.
+    #[inline]
+    pub fn map_mut_untracked(&mut self) -> Option<&mut Map> {
+        self.maps.get_mut(self.editor_maps.get_single().ok()?.0)
+    }
+
+    /// Runs an edit on the editor map and records its diff, folding it into the latest step if
+    /// `merge` is set. Returns whether anything changed. A stroke still going is ended first.
+    ///
+    /// Merely running the edit marks the map as modified, so callers should only edit when they
+    /// know something will change. Written cells and their neighbors then have their orientations
+    /// settled with [`Map::orient_after`], in the same step.
+    pub fn edit(&mut self, merge: bool, edit: impl FnOnce(&mut Map) -> MapDiff) -> bool {
+        self.end_stroke();
+        let Some(diff) = self.run(edit) else { return false };
+        if !self.history.record(diff, merge) {
+            return false
+        }
+
+        self.session.mark_changed();
+        true
+    }
+
+    /// Runs an edit on the editor map as part of a stroke, such as a drag of the paint brush, like
+    /// [`edit`](Self::edit). The stroke's changes are held back until [`end_stroke`](Self::end_stroke)
+    /// and recorded as one step, and the map is remeshed at most every [`STROKE_REMESH_INTERVAL`]
+    /// meanwhile.
+    pub fn stroke(&mut self, edit: impl FnOnce(&mut Map) -> MapDiff) -> bool {
+        let Some(diff) = self.run(edit) else { return false };
+        if !self.history.stroke(diff) {
+            return false
+        }
+
+        if let Ok((handle, _)) = self.editor_maps.get_single() {
+            self.throttle.intervals.insert(handle.id(), STROKE_REMESH_INTERVAL);
+        }
+
+        self.session.mark_changed();
+        true
+    }
+
+    /// Records the stroke still going as one step, and lets the editor map remesh as soon as it
+    /// changes again. Returns whether there was a stroke.
+    pub fn end_stroke(&mut self) -> bool {
+        if !self.history.is_stroking() {
+            return false
+        }
+
+        if let Ok((handle, _)) = self.editor_maps.get_single() {
+            self.throttle.intervals.remove(&handle.id());
+        }
+
+        self.history.end_stroke()
+    }
+
+    /// Runs an edit on the editor map, settling the orientations it touched and moving the map
+    /// entity against its resizes.
+    fn run(&mut self, edit: impl FnOnce(&mut Map) -> MapDiff) -> Option<MapDiff> {
+        let (map, _) = self.editor_maps.get_single().ok()?;
+        let map = self.maps.get_mut(map)?;
+        let mut diff = edit(map);
+        diff.extend(map.orient_after(&diff, &self.properties.for_map(map)));
+        self.shift(&diff, true);
+        Some(diff)
+    }
+
+    /// Resizes the editor map, moving its entity so the kept cells stay still in the world.
+    #[inline]
+    pub fn resize(&mut self, size: UVec3, offset: IVec3, merge: bool) -> bool {
+        self.edit(merge, |map| map.resize(size, offset))
+    }
+
+    pub fn undo(&mut self) -> bool {
+        self.end_stroke();
+        let Some(diff) = self.history.undo.pop_back() else {
+            return false
+        };
+        if let Some(map) = self.map_mut_untracked() {
+            diff.revert(map);
+            self.shift(&diff, false);
+            self.session.mark_changed();
+        }
+
+        self.history.redo.push(diff);
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.end_stroke();
+        let Some(diff) = self.history.redo.pop() else { return false };
+        if let Some(map) = self.map_mut_untracked() {
+            diff.apply(map);
+            self.shift(&diff, true);
+            self.session.mark_changed();
+        }
+
+        self.history.undo.push_back(diff);
+        true
+    }
+
+    /// Moves the editor map entity against the offsets of every resize in a diff, or along them
+    /// when reverting.
+    fn shift(&mut self, diff: &MapDiff, forward: bool) {
+        let Ok((handle, mut trns)) = self.editor_maps.get_single_mut() else {
+            return
+        };
+        let Some(map) = self.maps.get(handle) else { return };
+
+        let offset = diff
+            .edits
+            .iter()
+            .filter_map(|edit| match *edit {
+                MapEdit::Resize { offset, .. } => Some(offset),
+                _ => None,
+            })
+            .sum::<IVec3>();
+
+        if offset != IVec3::ZERO {
+            let shift = trns.rotation * (offset.as_vec3() * map.tile_size * trns.scale);
+            trns.translation += if forward { -shift } else { shift };
+        }
+    }
+}
+
+pub fn undo_redo(keys: Res<ButtonInput<KeyCode>>, mut commands: MapCommands) {
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return
+    }
+
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if keys.just_pressed(KeyCode::KeyY) || (shift && keys.just_pressed(KeyCode::KeyZ)) {
+        commands.redo();
+    } else if keys.just_pressed(KeyCode::KeyZ) {
+        commands.undo();
+    }
+}
+
+/// Ends strokes once every mouse button is let go, in case the tool painting them stopped running
+/// before it could.
+pub fn end_released_strokes(mouse: Res<ButtonInput<MouseButton>>, mut commands: MapCommands) {
+    if !mouse.any_pressed([MouseButton::Left, MouseButton::Right]) {
+        commands.end_stroke();
+    }
+}
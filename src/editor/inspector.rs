@@ -0,0 +1,381 @@
+use bevy::{input::InputSystem, prelude::*};
+use bevy_egui::{egui, EguiContexts, EguiPlugin, EguiSet};
+use bevy_mod_picking::pointer::PointerId;
+use nonmax::NonMaxU8;
+
+use crate::{
+    content::manifest::TileCatalog,
+    editor::{
+        group::ActiveGroup,
+        history::MapCommands,
+        palette::ActiveTile,
+        prompt::{ActivePrompt, Notice},
+        replace::{prompt_replace, PendingReplace},
+        session::EditorSession,
+        tools::select::EditorSelection,
+    },
+    map::{
+        orientation::TileOrientation,
+        picking::{update_pointer_over_ui, PointerOverUi},
+        Map, MapCell,
+    },
+    EditorUi,
+};
+
+pub struct InspectorPlugin;
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        // The world inspector of the `dev` feature may have added it already.
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin);
+        }
+
+        app.init_resource::<InspectorSettings>()
+            .add_systems(
+                PreUpdate,
+                take_panel_input
+                    .after(InputSystem)
+                    .after(EguiSet::BeginFrame)
+                    .after(update_pointer_over_ui)
+                    .run_if(in_state(EditorUi)),
+            )
+            .add_systems(
+                Update,
+                (toggle_inspector, update_inspector).chain().run_if(in_state(EditorUi)),
+            );
+    }
+}
+
+/// Tiles a prop moves per pixel its position fields are dragged.
+const MOVE_SPEED: f32 = 0.02;
+/// Scale a prop gains per pixel its scale field is dragged.
+const SCALE_SPEED: f32 = 0.01;
+
+#[derive(Resource, Copy, Clone)]
+pub struct InspectorSettings {
+    pub visible: bool,
+    pub toggle: KeyCode,
+}
+
+impl Default for InspectorSettings {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            visible: true,
+            toggle: KeyCode::Tab,
+        }
+    }
+}
+
+/// A change asked for through the inspector panel.
+#[derive(Clone, Debug)]
+pub enum InspectorEdit {
+    /// Resizes the map, keeping cells anchored at the origin.
+    Resize(UVec3),
+    CompactTileSet,
+    /// Asks what replaces every occurrence of a tile set entry, optionally only within the
+    /// selection.
+    ReplaceTile {
+        index: NonMaxU8,
+        within_selection: bool,
+    },
+    SetCell(IVec3, MapCell),
+    ToggleGroup,
+    /// Adds the active tile to the brush group, or another unit of weight if it's already a member.
+    AddToGroup,
+    GroupWeight {
+        index: usize,
+        weight: f32,
+    },
+    RemoveFromGroup(usize),
+    SetProp(usize, Transform),
+}
+
+pub fn toggle_inspector(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut contexts: EguiContexts,
+    mut settings: ResMut<InspectorSettings>,
+) {
+    let typing = contexts.try_ctx_mut().is_some_and(|ctx| ctx.wants_keyboard_input());
+    if keys.just_pressed(settings.toggle) && !typing {
+        settings.visible = !settings.visible;
+    }
+}
+
+/// Marks the mouse as over UI while it's over the panel, which the picking stack can't see, so
+/// placement tools leave it be. Keys typed into the panel's fields aren't taken as shortcuts.
+pub fn take_panel_input(
+    mut contexts: EguiContexts,
+    mut over_ui: ResMut<PointerOverUi>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else { return };
+    if ctx.is_pointer_over_area() || ctx.wants_pointer_input() {
+        over_ui.insert(PointerId::Mouse, true);
+    }
+
+    if ctx.wants_keyboard_input() {
+        keys.reset_all();
+    }
+}
+
+/// Whether a field's change carries on from last frame's, so a whole drag is undone at once.
+#[inline]
+fn dragging(response: &egui::Response) -> bool {
+    response.dragged() && !response.drag_started()
+}
+
+/// Draws the panel on the right side of the editor listing map, prop and cell properties, and makes
+/// the edits asked for through it.
+#[allow(clippy::too_many_arguments)]
+pub fn update_inspector(
+    mut contexts: EguiContexts,
+    settings: Res<InspectorSettings>,
+    session: Res<EditorSession>,
+    selection: Res<EditorSelection>,
+    active: Res<ActiveTile>,
+    catalog: Res<TileCatalog>,
+    mut group: ResMut<ActiveGroup>,
+    mut prompt: ResMut<ActivePrompt>,
+    mut pending: ResMut<PendingReplace>,
+    mut commands: MapCommands,
+    mut notice: ResMut<Notice>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else { return };
+    let Some(map) = commands.map().filter(|_| settings.visible) else {
+        return
+    };
+
+    let mut edits = Vec::new();
+    let mut merge = false;
+    egui::Window::new("Inspector")
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, 104.0))
+        .default_width(280.0)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.heading("Map");
+                ui.label(format!("File: {}", session.path.as_deref().unwrap_or("untitled")));
+
+                let mut size = map.size;
+                ui.horizontal(|ui| {
+                    ui.label("Size:");
+                    for (axis, name) in ["X ", "Y ", "Z "].into_iter().enumerate() {
+                        let response = ui.add(egui::DragValue::new(&mut size[axis]).prefix(name).clamp_range(1..=256));
+                        merge |= dragging(&response);
+                    }
+                });
+
+                if size != map.size {
+                    edits.push(InspectorEdit::Resize(size));
+                }
+
+                ui.label(format!(
+                    "Tile size: {} x {} x {}",
+                    map.tile_size.x, map.tile_size.y, map.tile_size.z
+                ));
+                ui.label(format!("Layers: {}", map.size.y));
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.heading("Tile set");
+                    if ui.button("Compact").clicked() {
+                        edits.push(InspectorEdit::CompactTileSet);
+                    }
+                });
+
+                for (index, (path, count)) in map.tile_set.iter().zip(map.tile_usage()).enumerate() {
+                    let Some(index) = NonMaxU8::new(index as u8) else { continue };
+
+                    ui.label(format!("{index}: {} ({count})", catalog.full_name(path)));
+                    ui.horizontal(|ui| {
+                        if ui.small_button("Replace all with...").clicked() {
+                            edits.push(InspectorEdit::ReplaceTile {
+                                index,
+                                within_selection: false,
+                            });
+                        }
+
+                        if !selection.cells.is_empty() && ui.small_button("In selection...").clicked() {
+                            edits.push(InspectorEdit::ReplaceTile {
+                                index,
+                                within_selection: true,
+                            });
+                        }
+                    });
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.heading(format!("Brush group: {}", group.name()));
+                    if ui.button(if group.enabled { "On" } else { "Off" }).clicked() {
+                        edits.push(InspectorEdit::ToggleGroup);
+                    }
+
+                    if ui.button("Add tile").clicked() {
+                        edits.push(InspectorEdit::AddToGroup);
+                    }
+                });
+
+                for (index, member) in group.group.members.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        let mut weight = member.weight;
+                        let drag = egui::DragValue::new(&mut weight).speed(0.1).clamp_range(0.0..=f32::MAX);
+                        if ui.add(drag).changed() {
+                            edits.push(InspectorEdit::GroupWeight { index, weight });
+                        }
+
+                        ui.label(catalog.full_name(&member.tile));
+                        if ui.small_button("x").clicked() {
+                            edits.push(InspectorEdit::RemoveFromGroup(index));
+                        }
+                    });
+                }
+
+                // Prop transforms are shown for the last prop selected.
+                if let Some((index, prop)) = selection.props.last().and_then(|&index| Some((index, map.props.get(index)?))) {
+                    ui.separator();
+                    ui.heading(format!("Prop #{index}"));
+                    ui.label(format!("Object: {}", prop.obj_path));
+
+                    let mut trns = prop.transform;
+                    ui.horizontal(|ui| {
+                        ui.label("Position:");
+                        for (axis, name) in ["X ", "Y ", "Z "].into_iter().enumerate() {
+                            let speed = MOVE_SPEED * map.tile_size[axis];
+                            let drag = egui::DragValue::new(&mut trns.translation[axis]).prefix(name).speed(speed);
+                            merge |= dragging(&ui.add(drag));
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Yaw:");
+                        let (mut yaw, ..) = trns.rotation.to_euler(EulerRot::YXZ);
+                        let response = ui.drag_angle(&mut yaw);
+                        if response.changed() {
+                            trns.rotation = Quat::from_rotation_y(yaw);
+                        }
+
+                        merge |= dragging(&response);
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Scale:");
+                        let mut scale = trns.scale.max_element();
+                        let drag = egui::DragValue::new(&mut scale)
+                            .speed(SCALE_SPEED)
+                            .clamp_range(0.01..=f32::MAX);
+                        let response = ui.add(drag);
+                        if response.changed() {
+                            trns.scale = Vec3::splat(scale);
+                        }
+
+                        merge |= dragging(&response);
+                    });
+
+                    if trns != prop.transform {
+                        edits.push(InspectorEdit::SetProp(index, trns));
+                    }
+                }
+
+                // Cell properties are shown for a selection of exactly one cell.
+                let Some((min, _)) = selection.cells.region().filter(|&(min, max)| max - min == IVec3::ONE) else {
+                    return
+                };
+                let Some(cell) = map.contains(min).then(|| map.get_cell(min.as_uvec3())).flatten() else {
+                    return
+                };
+
+                ui.separator();
+                ui.heading(format!("Cell {}, {}, {}", min.x, min.y, min.z));
+
+                let name = |tile: Option<NonMaxU8>| {
+                    tile.and_then(|tile| map.tile_set.get(tile.get() as usize))
+                        .map_or_else(|| "Empty".into(), |path| catalog.full_name(path))
+                };
+
+                let mut new = cell;
+                egui::ComboBox::from_label("Tile")
+                    .selected_text(name(cell.tile))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut new.tile, None, "Empty");
+                        for index in (0..map.tile_set.len()).filter_map(|index| NonMaxU8::new(index as u8)) {
+                            ui.selectable_value(&mut new.tile, Some(index), name(Some(index)));
+                        }
+                    });
+
+                if new.tile.is_some() {
+                    let mut turns = new.orientation.turns();
+                    let mut flipped = new.orientation.flipped();
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_label("Rotation")
+                            .selected_text(format!("{}°", turns * 90))
+                            .show_ui(ui, |ui| {
+                                for option in 0..4 {
+                                    ui.selectable_value(&mut turns, option, format!("{}°", option * 90));
+                                }
+                            });
+
+                        ui.checkbox(&mut flipped, "Flipped");
+                    });
+
+                    new.orientation = TileOrientation::new(turns, flipped);
+                } else {
+                    new.orientation = TileOrientation::IDENTITY;
+                }
+
+                if new != cell {
+                    edits.push(InspectorEdit::SetCell(min, new));
+                }
+            });
+        });
+
+    for edit in edits {
+        let Some(map) = commands.map() else { return };
+        match edit {
+            InspectorEdit::Resize(size) => {
+                commands.resize(size, IVec3::ZERO, merge);
+            }
+            InspectorEdit::CompactTileSet => {
+                let before = map.tile_set.len();
+                if commands.edit(false, Map::compact_tile_set) {
+                    let after = commands.map().map_or(before, |map| map.tile_set.len());
+                    notice.show(format!("Removed {} unused tile(s).", before - after));
+                } else {
+                    notice.show("Every tile in the tile set is in use.");
+                }
+            }
+            InspectorEdit::ReplaceTile { index, within_selection } => {
+                prompt_replace(index, within_selection, map, &catalog, &mut prompt, &mut pending)
+            }
+            InspectorEdit::SetCell(cell, value) => {
+                commands.edit(false, |map| map.fill_cells([cell], value));
+            }
+            InspectorEdit::ToggleGroup => match group.enabled || group.group.is_pickable() {
+                true => group.enabled = !group.enabled,
+                false => notice.show("The brush group is empty."),
+            },
+            InspectorEdit::AddToGroup => {
+                if let Some(tile) = active.path(map) {
+                    group.group.add(tile, 1.0);
+                }
+            }
+            InspectorEdit::GroupWeight { index, weight } => {
+                if let Some(member) = group.group.members.get_mut(index) {
+                    member.weight = weight;
+                }
+
+                group.enabled &= group.group.is_pickable();
+            }
+            InspectorEdit::RemoveFromGroup(index) => {
+                if index < group.group.members.len() {
+                    group.group.members.remove(index);
+                    group.enabled &= group.group.is_pickable();
+                }
+            }
+            InspectorEdit::SetProp(index, trns) => {
+                commands.edit(merge, |map| map.transform_props(&[index], |old| *old = trns));
+            }
+        }
+    }
+}
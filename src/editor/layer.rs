@@ -0,0 +1,286 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::PrimitiveTopology, render_asset::RenderAssetUsages},
+};
+
+use crate::{
+    content::{array::MapMaterial, render::TileRenders, TileTexture},
+    editor::EditorMap,
+    map::{
+        picking::{PickCeiling, PickGround},
+        update_map_mesh, Map, MapPage, MapPart,
+    },
+    obj::def::{MtlCollection, Obj},
+    EditorUi,
+};
+
+pub struct LayerPlugin;
+impl Plugin for LayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveLayer>()
+            .init_resource::<LayerView>()
+            .add_systems(
+                Update,
+                (select_layer, clamp_active_layer, update_pick_ceiling)
+                    .chain()
+                    .run_if(in_state(EditorUi)),
+            )
+            .add_systems(
+                PostUpdate,
+                isolate_layers.after(update_map_mesh).run_if(in_state(EditorUi)),
+            );
+    }
+}
+
+/// The horizontal layer of the editor map being worked on, where the grid lies and where placement
+/// lands when the cursor isn't over a tile.
+#[derive(Resource, Copy, Clone, Eq, PartialEq, Default, Debug, Deref, DerefMut)]
+pub struct ActiveLayer(pub u32);
+
+/// How tiles above the active layer are shown.
+#[derive(Resource, Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub enum LayerView {
+    #[default]
+    All,
+    /// Drawn translucent, and ignored by the cursor.
+    Ghost,
+    /// Not drawn, and ignored by the cursor.
+    Hide,
+}
+
+impl LayerView {
+    /// Whether the cursor may target a cell on the given layer.
+    #[inline]
+    pub fn targets(self, active: ActiveLayer, layer: u32) -> bool {
+        self == Self::All || layer <= *active
+    }
+}
+
+/// The translucent tiles above the active layer while [`LayerView::Ghost`] is on.
+#[derive(Component, Copy, Clone)]
+pub struct GhostLayers;
+
+pub fn select_layer(keys: Res<ButtonInput<KeyCode>>, mut layer: ResMut<ActiveLayer>, mut view: ResMut<LayerView>) {
+    if keys.just_pressed(KeyCode::PageUp) {
+        **layer = layer.saturating_add(1);
+    }
+
+    if keys.just_pressed(KeyCode::PageDown) && **layer > 0 {
+        **layer -= 1;
+    }
+
+    if keys.just_pressed(KeyCode::KeyL) {
+        *view = match *view {
+            LayerView::All => LayerView::Ghost,
+            LayerView::Ghost => LayerView::Hide,
+            LayerView::Hide => LayerView::All,
+        };
+    }
+}
+
+pub fn clamp_active_layer(
+    mut layer: ResMut<ActiveLayer>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    maps: Res<Assets<Map>>,
+) {
+    let Some(map) = editor_maps.get_single().ok().and_then(|map| maps.get(map)) else {
+        return
+    };
+
+    layer.set_if_neq(ActiveLayer((**layer).min(map.size.y.saturating_sub(1))));
+}
+
+/// Keeps pointers from striking cells the layer view has the cursor ignore, and has the active
+/// layer's floor catch pointers striking none.
+#[allow(clippy::type_complexity)]
+pub fn update_pick_ceiling(
+    mut commands: Commands,
+    layer: Res<ActiveLayer>,
+    view: Res<LayerView>,
+    editor_maps: Query<(Entity, Option<&PickCeiling>, Option<&PickGround>), With<EditorMap>>,
+) {
+    let ceiling = (*view != LayerView::All).then_some(PickCeiling(**layer));
+    for (e, current, ground) in &editor_maps {
+        if ground != Some(&PickGround(**layer)) {
+            commands.entity(e).insert(PickGround(**layer));
+        }
+
+        match (ceiling, current) {
+            (Some(ceiling), current) if current != Some(&ceiling) => {
+                commands.entity(e).insert(ceiling);
+            }
+            (None, Some(..)) => {
+                commands.entity(e).remove::<PickCeiling>();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Meshes the editor map in two parts split at the active layer for every map part, owned while
+/// isolation is on.
+#[derive(Default)]
+pub struct LayerIsolation {
+    parts: Vec<IsolatedPart>,
+}
+
+struct IsolatedPart {
+    part: MapPart,
+    below: Handle<Mesh>,
+    above: Handle<Mesh>,
+    ghost: Handle<MapMaterial>,
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn isolate_layers(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Map>>,
+    (layer, view): (Res<ActiveLayer>, Res<LayerView>),
+    maps: Res<Assets<Map>>,
+    tile_textures: Res<TileTexture>,
+    tile_assets: Res<Assets<Obj>>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+    mtls: Res<Assets<MtlCollection>>,
+    renders: Res<TileRenders>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<MapMaterial>>,
+    editor_maps: Query<(Entity, &Handle<Map>, &Handle<MapMaterial>, Option<&Children>), With<EditorMap>>,
+    pages: Query<&MapPage>,
+    ghosts: Query<Entity, With<GhostLayers>>,
+    mut isolation: Local<Option<LayerIsolation>>,
+    mut dirty: Local<bool>,
+) {
+    let Ok((e, handle, material, children)) = editor_maps.get_single() else {
+        return
+    };
+    *dirty |= layer.is_changed() || view.is_changed();
+    for e in events.read() {
+        *dirty |= e.is_modified(handle) || e.is_loaded_with_dependencies(handle) || e.is_added(handle);
+    }
+
+    if !*dirty {
+        return
+    }
+
+    let part_children = || {
+        children
+            .into_iter()
+            .flatten()
+            .filter_map(|&child| pages.get(child).ok().map(|&MapPage(part)| (child, part)))
+    };
+
+    if *view == LayerView::All {
+        *dirty = false;
+        for ghost in &ghosts {
+            commands.entity(ghost).despawn_recursive();
+        }
+
+        // Lets the map's own meshes be synchronized back onto it.
+        if isolation.take().is_some() {
+            commands.entity(e).remove::<Handle<Mesh>>();
+            for (child, _) in part_children() {
+                commands.entity(child).remove::<Handle<Mesh>>();
+            }
+        }
+
+        return
+    }
+
+    let Some(map) = maps.get(handle) else { return };
+    if !map.is_ready(&tile_assets, &mtls, &tile_textures, &layouts) {
+        return
+    }
+
+    *dirty = false;
+
+    let isolation = isolation.get_or_insert_with(default);
+    let base = materials.get(material).cloned().unwrap_or_default();
+
+    // Ghosts are spawned per part, so they're spawned again whenever the parts change.
+    let parts = map.parts(&tile_assets, &mtls, &tile_textures, &layouts, &renders);
+    let respawn = isolation.parts.iter().map(|isolated| isolated.part).ne(parts.iter().copied());
+    if respawn {
+        for ghost in &ghosts {
+            commands.entity(ghost).despawn_recursive();
+        }
+
+        isolation.parts = parts
+            .into_iter()
+            .filter_map(|part| {
+                let material = part.material(&base, &tile_textures)?;
+                let ghost = MapMaterial {
+                    base: StandardMaterial {
+                        base_color: base.base.base_color.with_alpha(0.2),
+                        alpha_mode: AlphaMode::Blend,
+                        ..material.base
+                    },
+                    ..material
+                };
+
+                Some(IsolatedPart {
+                    part,
+                    below: meshes.add(empty_mesh()),
+                    above: meshes.add(empty_mesh()),
+                    ghost: materials.add(ghost),
+                })
+            })
+            .collect();
+    }
+
+    let active = **layer;
+    for isolated in &isolation.parts {
+        let build = |include: &dyn Fn(UVec3) -> bool| {
+            map.build_part(
+                empty_mesh(),
+                &tile_assets,
+                &mtls,
+                &tile_textures,
+                &layouts,
+                &renders,
+                isolated.part,
+                include,
+            )
+        };
+
+        meshes.insert(&isolated.below, build(&|cell| cell.y <= active));
+        meshes.insert(&isolated.above, build(&|cell| cell.y > active));
+    }
+
+    if let Some(first) = isolation.parts.first() {
+        commands.entity(e).insert(first.below.clone_weak());
+    }
+
+    for (child, part) in part_children() {
+        if let Some(isolated) = isolation.parts.iter().find(|isolated| isolated.part == part) {
+            commands.entity(child).insert(isolated.below.clone_weak());
+        }
+    }
+
+    match *view {
+        LayerView::Ghost if ghosts.is_empty() || respawn => {
+            commands.entity(e).with_children(|children| {
+                for isolated in &isolation.parts {
+                    children.spawn((
+                        MaterialMeshBundle {
+                            mesh: isolated.above.clone_weak(),
+                            material: isolated.ghost.clone_weak(),
+                            ..default()
+                        },
+                        GhostLayers,
+                    ));
+                }
+            });
+        }
+        LayerView::Hide => {
+            for ghost in &ghosts {
+                commands.entity(ghost).despawn_recursive();
+            }
+        }
+        _ => {}
+    }
+}
+
+#[inline]
+fn empty_mesh() -> Mesh {
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD)
+}
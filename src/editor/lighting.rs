@@ -0,0 +1,135 @@
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+
+use crate::{
+    editor::{prompt::Notice, session::EditorSession, EditorMap, EditorSettings},
+    map::{lighting::MapLighting, Map},
+    EditorUi,
+};
+
+pub struct LightingPlugin;
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (sync_map_lighting, adjust_lighting, apply_lighting)
+                .chain()
+                .run_if(in_state(EditorUi)),
+        );
+    }
+}
+
+/// Held while dragging to move the sun, or scrolling to change its illuminance (or the ambient
+/// brightness with shift). `L` already cycles the layer view.
+pub const SUN_KEY: KeyCode = KeyCode::KeyO;
+
+/// Ambient colors cycled through with `C` while [`SUN_KEY`] is held.
+pub const AMBIENT_COLORS: [Vec3; 4] = [
+    Vec3::ONE,
+    Vec3::new(1.0, 0.85, 0.7),
+    Vec3::new(0.7, 0.8, 1.0),
+    Vec3::new(0.4, 0.4, 0.6),
+];
+
+/// Marks the directional light standing in for the map's sun.
+#[derive(Component, Copy, Clone, Default)]
+pub struct SunLight;
+
+/// Takes on the lighting saved with a map once it's opened.
+pub fn sync_map_lighting(
+    maps: Res<Assets<Map>>,
+    editor_maps: Query<Ref<Handle<Map>>, With<EditorMap>>,
+    mut settings: ResMut<EditorSettings>,
+    mut pending: Local<bool>,
+) {
+    let Ok(handle) = editor_maps.get_single() else { return };
+    *pending |= handle.is_changed();
+
+    if let Some(map) = maps.get(&*handle).filter(|_| *pending) {
+        *pending = false;
+        if settings.lighting != map.lighting {
+            settings.lighting = map.lighting;
+        }
+    }
+}
+
+pub fn adjust_lighting(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+    mut settings: ResMut<EditorSettings>,
+    mut session: ResMut<EditorSession>,
+    mut notice: ResMut<Notice>,
+) {
+    let drag = motion.read().map(|e| e.delta).sum::<Vec2>();
+    let lines = wheel
+        .read()
+        .map(|e| match e.unit {
+            MouseScrollUnit::Line => e.y,
+            MouseScrollUnit::Pixel => e.y / 16.0,
+        })
+        .sum::<f32>();
+
+    let mut lighting = settings.lighting;
+    if keys.just_pressed(settings.shadows_toggle) {
+        lighting.shadows = !lighting.shadows;
+    }
+
+    if keys.pressed(SUN_KEY) {
+        if mouse.pressed(MouseButton::Left) {
+            lighting.sun_azimuth = (lighting.sun_azimuth - drag.x * 0.5).rem_euclid(360.0);
+            lighting.sun_elevation = (lighting.sun_elevation - drag.y * 0.5).clamp(0.0, 90.0);
+        }
+
+        match keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            true => lighting.ambient_brightness = (lighting.ambient_brightness * 1.1f32.powf(lines)).clamp(0.0, 10000.0),
+            false => lighting.illuminance = (lighting.illuminance * 1.1f32.powf(lines)).clamp(1.0, 200000.0),
+        }
+
+        if keys.just_pressed(KeyCode::KeyC) {
+            let next = AMBIENT_COLORS
+                .iter()
+                .position(|&color| color == lighting.ambient_color)
+                .map_or(0, |i| (i + 1) % AMBIENT_COLORS.len());
+            lighting.ambient_color = AMBIENT_COLORS[next];
+        }
+    }
+
+    if lighting == settings.lighting {
+        return
+    }
+
+    settings.lighting = lighting;
+    session.mark_changed();
+    notice.show(format!(
+        "Sun {:.0}° at {:.0}°, {:.0} lx{}; ambient {:.0}",
+        lighting.sun_azimuth,
+        lighting.sun_elevation,
+        lighting.illuminance,
+        if lighting.shadows { ", shadows" } else { "" },
+        lighting.ambient_brightness,
+    ));
+}
+
+pub fn apply_lighting(
+    settings: Res<EditorSettings>,
+    mut ambient: ResMut<AmbientLight>,
+    mut suns: Query<(&mut DirectionalLight, &mut Transform), With<SunLight>>,
+    added: Query<(), Added<SunLight>>,
+) {
+    if !settings.is_changed() && added.is_empty() {
+        return
+    }
+
+    let lighting: MapLighting = settings.lighting;
+    for (mut light, mut trns) in &mut suns {
+        light.illuminance = lighting.illuminance;
+        light.shadows_enabled = lighting.shadows;
+        trns.rotation = lighting.sun_rotation();
+    }
+
+    *ambient = lighting.ambient_light();
+}
@@ -1,56 +1,298 @@
+pub mod autosave;
+pub mod brush;
+pub mod camera;
+pub mod clipboard;
+pub mod display;
+pub mod ghost;
+pub mod grid;
+pub mod group;
+pub mod history;
+#[cfg(feature = "inspector")]
+pub mod inspector;
+pub mod layer;
+pub mod lighting;
+pub mod object;
+pub mod palette;
+pub mod prompt;
+pub mod readout;
+pub mod reload;
+pub mod replace;
+pub mod resize;
+pub mod restore;
+pub mod session;
+pub mod status;
+pub mod symmetry;
+pub mod tools;
+pub mod trigger;
+pub mod view;
+
 use bevy::{
     core_pipeline::{bloom::BloomSettings, tonemapping::Tonemapping},
     prelude::*,
+    window::PresentMode,
 };
-use nonmax::NonMaxU8;
 
-use crate::{content::TileTexture, map::Map, GameState};
+use crate::{
+    content::Tiles,
+    editor::{
+        autosave::AutosavePlugin,
+        brush::BrushPlugin,
+        camera::{EditorCamera, EditorCameraPlugin},
+        clipboard::ClipboardPlugin,
+        display::DisplayPlugin,
+        ghost::GhostPlugin,
+        grid::GridPlugin,
+        group::GroupPlugin,
+        history::{EditorHistory, HistoryPlugin},
+        layer::{ActiveLayer, LayerPlugin},
+        lighting::{LightingPlugin, SunLight},
+        object::{ObjectPlugin, PropDrag},
+        palette::PalettePlugin,
+        prompt::{ActivePrompt, PromptPlugin},
+        readout::ReadoutPlugin,
+        reload::ReloadPlugin,
+        replace::ReplacePlugin,
+        resize::ResizePlugin,
+        restore::{RestorePlugin, SessionState},
+        session::{EditorSession, SessionPlugin, SessionRequest},
+        status::StatusPlugin,
+        symmetry::{SymmetryMode, SymmetryPlugin},
+        tools::{
+            select::{EditorSelection, Floating},
+            ToolsPlugin,
+        },
+        trigger::TriggerPlugin,
+        view::{bloom_settings, ViewPlugin},
+    },
+    map::{lighting::MapLighting, Map, MapMaterials},
+    AssetRoot, GameState,
+};
 
 pub struct EditorPlugin;
 impl Plugin for EditorPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Editor), init_editor_map);
+        app.init_resource::<EditorSettings>()
+            .register_type::<EditorSettings>()
+            .add_plugins((
+                (
+                    AutosavePlugin,
+                    BrushPlugin,
+                    EditorCameraPlugin,
+                    ClipboardPlugin,
+                    DisplayPlugin,
+                    GhostPlugin,
+                    GridPlugin,
+                    GroupPlugin,
+                    HistoryPlugin,
+                    LayerPlugin,
+                ),
+                (
+                    LightingPlugin,
+                    ObjectPlugin,
+                    PalettePlugin,
+                    PromptPlugin,
+                    ReadoutPlugin,
+                    ReloadPlugin,
+                    ReplacePlugin,
+                    ResizePlugin,
+                    RestorePlugin,
+                    SessionPlugin,
+                    StatusPlugin,
+                    SymmetryPlugin,
+                    ToolsPlugin,
+                    TriggerPlugin,
+                    ViewPlugin,
+                ),
+            ))
+            .add_systems(OPEN_EDITOR, init_editor_map)
+            .add_systems(CLOSE_EDITOR, cleanup_editor);
+
+        #[cfg(feature = "inspector")]
+        app.add_plugins(inspector::InspectorPlugin);
     }
 }
 
+#[derive(Resource, Reflect, Clone)]
+#[reflect(Resource)]
+pub struct EditorSettings {
+    pub show_grid: bool,
+    pub grid_toggle: KeyCode,
+    /// Cells the grid extends past the map bounds on each side.
+    pub grid_margin: u32,
+    /// On-screen cell sizes in pixels between which the grid fades from invisible to opaque.
+    pub grid_fade: (f32, f32),
+    /// Whether placing a tile outside the map bounds grows the map to contain it.
+    pub auto_grow: bool,
+    /// Whether the map is drawn with wireframe edges over it.
+    pub wireframe: bool,
+    pub wireframe_toggle: KeyCode,
+    /// Whether the map is drawn unlit, showing its textures as-is.
+    pub fullbright: bool,
+    pub fullbright_toggle: KeyCode,
+    /// Whether colliders are drawn over the map, as with the `dev` feature.
+    pub physics_debug: bool,
+    pub physics_debug_toggle: KeyCode,
+    /// The symmetry tools mirror their edits under, if any.
+    pub symmetry: Option<SymmetryMode>,
+    pub symmetry_toggle: KeyCode,
+    /// Cell coordinates on the XZ plane symmetry is anchored at, or `None` for the map's center.
+    pub symmetry_anchor: Option<Vec2>,
+    /// Lighting of the editor map, saved along with it.
+    pub lighting: MapLighting,
+    pub shadows_toggle: KeyCode,
+    pub tonemapping: Tonemapping,
+    /// Cycles through tonemapping operators, backwards with shift.
+    pub tonemapping_cycle: KeyCode,
+    pub bloom: bool,
+    pub bloom_intensity: f32,
+    pub bloom_toggle: KeyCode,
+    pub bloom_down: KeyCode,
+    pub bloom_up: KeyCode,
+    /// Size of the empty map the editor starts with when there's no map to reopen.
+    pub default_map_size: UVec3,
+    /// Whether the editor starts with the demo map instead, as with the `dev` feature or `--demo`.
+    pub demo: bool,
+    /// How the window waits on the display; see [`display::PRESENT_MODES`].
+    pub present_mode: PresentMode,
+    pub present_mode_cycle: KeyCode,
+    /// Toggles borderless fullscreen, anywhere in the app.
+    pub fullscreen_toggle: KeyCode,
+    /// Most frames drawn per second, or `None` for as many as the present mode allows.
+    pub frame_cap: Option<f32>,
+}
+
+impl Default for EditorSettings {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            show_grid: true,
+            grid_toggle: KeyCode::KeyH,
+            grid_margin: 4,
+            grid_fade: (4.0, 12.0),
+            auto_grow: false,
+            wireframe: false,
+            wireframe_toggle: KeyCode::F3,
+            fullbright: false,
+            fullbright_toggle: KeyCode::F4,
+            physics_debug: cfg!(feature = "dev"),
+            physics_debug_toggle: KeyCode::F2,
+            symmetry: None,
+            symmetry_toggle: KeyCode::KeyK,
+            symmetry_anchor: None,
+            lighting: default(),
+            shadows_toggle: KeyCode::F6,
+            tonemapping: Tonemapping::None,
+            tonemapping_cycle: KeyCode::F7,
+            bloom: true,
+            bloom_intensity: BloomSettings::NATURAL.intensity,
+            bloom_toggle: KeyCode::F8,
+            bloom_down: KeyCode::F9,
+            bloom_up: KeyCode::F10,
+            default_map_size: UVec3::new(16, 4, 16),
+            demo: cfg!(feature = "dev"),
+            present_mode: PresentMode::AutoNoVsync,
+            present_mode_cycle: KeyCode::F1,
+            fullscreen_toggle: KeyCode::F11,
+            frame_cap: None,
+        }
+    }
+}
+
+/// Runs when the editor is opened, but not when coming back from a playtest or dialog.
+pub const OPEN_EDITOR: OnEnter<GameState> = OnEnter(GameState::Editor);
+
+/// Runs when the editor is closed for the menu, but not when leaving for a playtest or dialog.
+pub const CLOSE_EDITOR: OnExit<GameState> = OnExit(GameState::Editor);
+
+/// Marks the map entity the editor is currently working on.
+#[derive(Component, Copy, Clone, Default)]
+pub struct EditorMap;
+
+#[allow(clippy::too_many_arguments)]
 fn init_editor_map(
     mut commands: Commands,
+    settings: Res<EditorSettings>,
+    root: Res<AssetRoot>,
+    mut session: ResMut<EditorSession>,
+    mut requests: EventWriter<SessionRequest>,
+    mut layer: ResMut<ActiveLayer>,
+    tiles: Res<Tiles>,
     mut maps: ResMut<Assets<Map>>,
-    tile_texture: Res<TileTexture>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    map_materials: Res<MapMaterials>,
 ) {
+    // Without a map to open, the last session's map is reopened as it was left.
+    let mut restored = None;
+    if session.pending.is_none() && !settings.demo {
+        let state = SessionState::load(&root).unwrap_or_default();
+        if let Some(path) = state.reopened(&root) {
+            session.pending = Some(SessionRequest::Open(path.into()));
+            restored = state.camera.map(|camera| camera.camera());
+            **layer = state.layer;
+        }
+    }
+
+    let map = match session.pending.is_none() && settings.demo {
+        true => {
+            let mut map = Map::empty(UVec3::new(2, 1, 1));
+            map.tiles[0] = map.ensure_tile("liminal.floor", &tiles);
+            maps.add(map)
+        }
+        // A map about to be opened has nothing to show until it loads.
+        false if restored.is_some() => Handle::default(),
+        false => maps.add(Map::empty(settings.default_map_size)),
+    };
+
     commands.spawn((
-        maps.add(Map {
-            tile_set: vec!["tiles/liminal/floor.obj".into()],
-            tiles: [0].into_iter().map(|id| NonMaxU8::new(id)).collect(),
-            size: UVec3::new(2, 1, 1),
-        }),
-        materials.add(StandardMaterial {
-            reflectance: 0.0,
-            base_color_texture: Some(tile_texture.atlas.clone_weak()),
-            ..default()
-        }),
+        map,
+        map_materials.opaque.clone(),
         TransformBundle::default(),
         VisibilityBundle::default(),
+        EditorMap,
+        StateScoped(GameState::Editor),
     ));
 
-    let cam_pos = Vec3::new(-20.0, 20.0, 20.0);
-    commands.spawn((
+    let (camera, scale) =
+        restored.unwrap_or_else(|| (EditorCamera::looking_at(Vec3::new(-20.0, 20.0, 20.0), Vec3::ZERO), 0.025));
+    let mut camera_entity = commands.spawn((
         Camera3dBundle {
             camera: Camera { hdr: true, ..default() },
-            projection: Projection::Orthographic(OrthographicProjection {
-                scale: 0.025,
-                ..default()
-            }),
-            transform: Transform::from_translation(cam_pos).looking_at(Vec3::ZERO, Vec3::Y),
-            tonemapping: Tonemapping::None,
+            projection: Projection::Orthographic(OrthographicProjection { scale, ..default() }),
+            transform: camera.transform(),
+            tonemapping: settings.tonemapping,
             ..default()
         },
-        BloomSettings::NATURAL,
+        camera,
+        StateScoped(GameState::Editor),
     ));
 
-    commands.spawn(DirectionalLightBundle {
-        transform: Transform::from_translation(cam_pos + Vec3::new(7.0, 10.0, 5.0)).looking_at(Vec3::ZERO, Vec3::Y),
-        ..default()
-    });
+    if let Some(bloom) = bloom_settings(&settings) {
+        camera_entity.insert(bloom);
+    }
+
+    commands.spawn((
+        DirectionalLightBundle {
+            transform: Transform::from_rotation(settings.lighting.sun_rotation()),
+            ..default()
+        },
+        SunLight,
+        StateScoped(GameState::Editor),
+    ));
+
+    if let Some(request) = session.pending.take() {
+        requests.send(request);
+    }
+}
+
+fn cleanup_editor(
+    mut history: ResMut<EditorHistory>,
+    mut prompt: ResMut<ActivePrompt>,
+    mut selection: ResMut<EditorSelection>,
+    mut floating: ResMut<Floating>,
+    mut prop_drag: ResMut<PropDrag>,
+) {
+    history.clear();
+    **prompt = None;
+    *selection = default();
+    **floating = None;
+    *prop_drag = default();
 }
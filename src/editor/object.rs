@@ -0,0 +1,378 @@
+use std::f32::consts::PI;
+
+use bevy::{color::palettes::css, prelude::*, utils::HashMap};
+use bevy_mod_picking::prelude::*;
+
+use crate::{
+    editor::{
+        history::MapCommands,
+        layer::ActiveLayer,
+        prompt::{ActivePrompt, Prompt, PromptSubmit},
+        tools::{navigating, select::EditorSelection, update_cursor_target, CursorTarget, ToolStatus},
+        EditorMap,
+    },
+    map::{
+        prop::{MapProp, Prop, PropBuilt},
+        EditMode,
+    },
+    obj::def::Obj,
+    EditorState, EditorUi, GameState,
+};
+
+/// Switches between editing tiles and props.
+pub const EDIT_MODE_KEY: KeyCode = KeyCode::Backquote;
+/// Asks for the object placed in object mode.
+pub const PROP_PATH_KEY: KeyCode = KeyCode::Enter;
+/// Radians selected props turn per press of comma or period.
+pub const PROP_TURN: f32 = PI / 12.0;
+
+pub struct ObjectPlugin;
+impl Plugin for ObjectPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveProp>()
+            .init_resource::<PropDrag>()
+            .init_resource::<HighlightMaterials>()
+            .add_systems(
+                Update,
+                (
+                    (
+                        switch_edit_mode.run_if(in_state(EditorState::Editing)),
+                        answer_prop_prompt,
+                        (
+                            (ask_prop_path, select_props).run_if(in_state(EditorState::Editing)),
+                            edit_props,
+                            draw_selected_props,
+                        )
+                            .chain()
+                            .run_if(in_state(EditMode::Object)),
+                    )
+                        .chain()
+                        .after(update_cursor_target)
+                        .run_if(in_state(EditorUi)),
+                    highlight_selected_props.run_if(in_state(GameState::Editor)),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Asset path of the object clicking empty space places in object mode, if any.
+#[derive(Resource, Clone, Default, Debug, Deref, DerefMut)]
+pub struct ActiveProp(pub Option<String>);
+
+/// The drag moving the selected props in object mode.
+#[derive(Resource, Copy, Clone, PartialEq, Default, Debug)]
+pub struct PropDrag {
+    /// While dragging, the prop the cursor grabbed and its offset from where the cursor grabbed it on its
+    /// ground plane.
+    pub grab: Option<(usize, Vec3)>,
+    /// Whether the drag has moved the props yet, so later moves fold into the same step.
+    pub moved: bool,
+}
+
+/// Tinted copies of prop materials, worn by selected props.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct HighlightMaterials(pub HashMap<AssetId<StandardMaterial>, Handle<StandardMaterial>>);
+
+/// Marks props wearing a highlight, with the material they wore before.
+#[derive(Component, Clone, Debug)]
+pub struct PropHighlight(pub Handle<StandardMaterial>);
+
+pub fn switch_edit_mode(
+    keys: Res<ButtonInput<KeyCode>>,
+    mode: Res<State<EditMode>>,
+    mut next: ResMut<NextState<EditMode>>,
+    mut selection: ResMut<EditorSelection>,
+    mut drag: ResMut<PropDrag>,
+    mut status: ResMut<ToolStatus>,
+) {
+    if !keys.just_pressed(EDIT_MODE_KEY) {
+        return
+    }
+
+    next.set(match **mode {
+        EditMode::Tile => EditMode::Object,
+        EditMode::Object => EditMode::Tile,
+    });
+
+    selection.props.clear();
+    *drag = default();
+    status.set("");
+}
+
+pub fn ask_prop_path(keys: Res<ButtonInput<KeyCode>>, active: Res<ActiveProp>, mut prompt: ResMut<ActivePrompt>) {
+    if prompt.is_none() && keys.just_pressed(PROP_PATH_KEY) {
+        **prompt = Some(Prompt::text(
+            "prop-path",
+            "Place object",
+            active.as_deref().unwrap_or("props/"),
+        ));
+    }
+}
+
+pub fn answer_prop_prompt(
+    mut answers: EventReader<PromptSubmit>,
+    mut active: ResMut<ActiveProp>,
+    mut status: ResMut<ToolStatus>,
+) {
+    for answer in answers.read() {
+        if answer.id != "prop-path" {
+            continue
+        }
+
+        let path = answer.value.trim();
+        **active = (!path.is_empty()).then(|| path.into());
+        status.set(path);
+    }
+}
+
+/// Where a local-space ray crosses the horizontal plane at `height`.
+#[inline]
+fn ground_point(ray: Ray3d, height: f32) -> Option<Vec3> {
+    let distance = ray.intersect_plane(Vec3::Y * height, InfinitePlane3d::new(Vec3::Y))?;
+    Some(ray.get_point(distance))
+}
+
+/// Left clicks select the picked prop and start dragging the selection, or place the active prop on the
+/// active layer where nothing is picked. Holding shift adds picked props to the selection or takes them
+/// back out, and keeps the selection when clicking empty space.
+#[allow(clippy::too_many_arguments)]
+pub fn select_props(
+    mut downs: EventReader<Pointer<Down>>,
+    props: Query<(&MapProp, &Parent)>,
+    editor_maps: Query<(), With<EditorMap>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    target: Res<CursorTarget>,
+    layer: Res<ActiveLayer>,
+    active: Res<ActiveProp>,
+    mut selection: ResMut<EditorSelection>,
+    mut drag: ResMut<PropDrag>,
+    mut commands: MapCommands,
+) {
+    let picked = downs
+        .read()
+        .filter(|e| e.button == PointerButton::Primary)
+        .find_map(|e| {
+            let (prop, parent) = props.get(e.target).ok()?;
+            editor_maps.contains(parent.get()).then_some(prop.index)
+        });
+
+    if keys.just_pressed(KeyCode::Escape) {
+        selection.reborrow().map_unchanged(|s| &mut s.props).set_if_neq(Vec::new());
+        *drag = default();
+    }
+
+    if !mouse.just_pressed(MouseButton::Left) || target.over_ui || navigating(&keys) {
+        return
+    }
+
+    let (Some(map), Some(ray)) = (commands.map(), target.ray) else {
+        return
+    };
+
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if let Some(index) = picked.filter(|&index| index < map.props.len()) {
+        match (shift, selection.props.iter().position(|&selected| selected == index)) {
+            (true, Some(at)) => {
+                selection.props.remove(at);
+                return
+            }
+            (true, None) => selection.props.push(index),
+            // Pressing on a selected prop drags the whole selection along.
+            (false, Some(..)) => {}
+            (false, None) => selection.props = vec![index],
+        }
+
+        let at = map.props[index].transform.translation;
+        *drag = PropDrag {
+            grab: ground_point(ray, at.y).map(|point| (index, at - point)),
+            moved: false,
+        };
+
+        return
+    }
+
+    if shift {
+        return
+    }
+
+    let floor = map.cell_min(IVec3::new(0, **layer as i32, 0)).y;
+    let Some((path, point)) = active.as_ref().zip(ground_point(ray, floor)) else {
+        selection.reborrow().map_unchanged(|s| &mut s.props).set_if_neq(Vec::new());
+        return
+    };
+
+    let prop = Prop::new(path, Transform::from_translation(point));
+    if commands.edit(false, |map| map.add_prop(prop)) {
+        selection.props = commands.map().map(|map| vec![map.props.len() - 1]).unwrap_or_default();
+    }
+}
+
+/// Drags the selected props along their ground planes, turns them with comma and period, and deletes
+/// them.
+#[allow(clippy::too_many_arguments)]
+pub fn edit_props(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    prompt: Res<ActivePrompt>,
+    target: Res<CursorTarget>,
+    mut selection: ResMut<EditorSelection>,
+    mut drag: ResMut<PropDrag>,
+    mut status: ResMut<ToolStatus>,
+    mut commands: MapCommands,
+) {
+    let Some(map) = commands.map() else { return };
+
+    // Undoing may take props away.
+    let count = map.props.len();
+    if selection.props.iter().any(|&index| index >= count) {
+        selection.props.retain(|&index| index < count);
+    }
+
+    match selection.props[..] {
+        [] => {
+            drag.set_if_neq(default());
+            return
+        }
+        [index] => status.set(format!("{} #{index}", map.props[index].obj_path)),
+        ref props => status.set(format!("{} props", props.len())),
+    }
+
+    if !mouse.pressed(MouseButton::Left) {
+        drag.set_if_neq(default());
+    }
+
+    if let (Some((index, grab)), Some(ray)) = (drag.grab, target.ray) {
+        let Some(at) = map.props.get(index).map(|prop| prop.transform.translation) else {
+            return
+        };
+
+        if let Some(point) = ground_point(ray, at.y) {
+            let delta = Vec3::new(point.x + grab.x - at.x, 0.0, point.z + grab.z - at.z);
+            if delta != Vec3::ZERO {
+                let merge = drag.moved;
+                drag.moved |= commands.edit(merge, |map| {
+                    map.transform_props(&selection.props, |trns| trns.translation += delta)
+                });
+            }
+        }
+
+        return
+    }
+
+    if prompt.is_some() {
+        return
+    }
+
+    let turn = match (keys.just_pressed(KeyCode::Comma), keys.just_pressed(KeyCode::Period)) {
+        (true, false) => PROP_TURN,
+        (false, true) => -PROP_TURN,
+        _ => 0.0,
+    };
+
+    if turn != 0.0 {
+        commands.edit(false, |map| map.transform_props(&selection.props, |trns| trns.rotate_y(turn)));
+    } else if keys.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        commands.edit(false, |map| map.remove_props(&selection.props));
+        selection.props.clear();
+        status.set("");
+    }
+}
+
+/// Outlines the selected props, each with a ring on its ground plane it may be dragged along.
+pub fn draw_selected_props(
+    selection: Res<EditorSelection>,
+    drag: Res<PropDrag>,
+    objs: Res<Assets<Obj>>,
+    editor_maps: Query<&Children, With<EditorMap>>,
+    props: Query<(&MapProp, &Handle<Obj>, &GlobalTransform)>,
+    mut gizmos: Gizmos,
+) {
+    if selection.props.is_empty() {
+        return
+    }
+
+    for (prop, handle, &trns) in editor_maps.iter().flatten().filter_map(|&child| props.get(child).ok()) {
+        if !selection.props.contains(&prop.index) {
+            continue
+        }
+
+        let (min, max) = objs.get(handle).map_or((Vec3::splat(-0.5), Vec3::splat(0.5)), Obj::aabb);
+        gizmos.cuboid(
+            trns * Transform::from_translation((min + max) * 0.5).with_scale(max - min),
+            css::GOLD,
+        );
+
+        let radius = (max - min).xz().length() * 0.5 * trns.compute_transform().scale.xz().max_element();
+        let color = match drag.grab {
+            Some(..) => css::ORANGE,
+            None => css::GOLD,
+        };
+        gizmos.circle(trns.translation(), Dir3::Y, radius.max(0.25), color);
+    }
+}
+
+/// Dresses the selected props of the editor map in tinted copies of their material, and gives deselected
+/// props theirs back. Nothing is highlighted while playtesting.
+#[allow(clippy::type_complexity)]
+pub fn highlight_selected_props(
+    mut commands: Commands,
+    state: Res<State<EditorState>>,
+    selection: Res<EditorSelection>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut highlights: ResMut<HighlightMaterials>,
+    editor_maps: Query<&Children, With<EditorMap>>,
+    mut props: Query<(
+        &MapProp,
+        Ref<PropBuilt>,
+        &mut Handle<StandardMaterial>,
+        Option<&mut PropHighlight>,
+    )>,
+) {
+    for &child in editor_maps.iter().flatten() {
+        let Ok((prop, built, mut material, highlight)) = props.get_mut(child) else {
+            continue
+        };
+
+        // Rebuilt props wear their object's new material.
+        let original = match &highlight {
+            Some(highlight) if !built.is_added() => highlight.0.clone(),
+            _ => material.clone(),
+        };
+
+        let selected = **state != EditorState::Playtest && selection.props.contains(&prop.index);
+        let wanted = match selected {
+            true => highlights
+                .entry(original.id())
+                .or_insert_with(|| {
+                    let base = materials.get(&original).cloned().unwrap_or_default();
+                    materials.add(StandardMaterial {
+                        emissive: LinearRgba::from(css::GOLD) * 0.35,
+                        ..base
+                    })
+                })
+                .clone(),
+            false => original.clone(),
+        };
+
+        if *material != wanted {
+            *material = wanted;
+        }
+
+        match (selected, highlight) {
+            (true, Some(mut highlight)) => {
+                if highlight.0 != original {
+                    highlight.0 = original;
+                }
+            }
+            (true, None) => {
+                commands.entity(child).insert(PropHighlight(original));
+            }
+            (false, Some(..)) => {
+                commands.entity(child).remove::<PropHighlight>();
+            }
+            (false, None) => {}
+        }
+    }
+}
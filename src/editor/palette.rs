@@ -0,0 +1,692 @@
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        mouse::{MouseScrollUnit, MouseWheel},
+        ButtonState, InputSystem,
+    },
+    prelude::*,
+    utils::HashSet,
+};
+use nonmax::NonMaxU8;
+
+use crate::{
+    content::{manifest::TileCatalog, thumbnail::TileThumbnails, Tiles},
+    editor::{
+        prompt::{edit_prompt, ActivePrompt},
+        EditorMap, OPEN_EDITOR,
+    },
+    map::{orientation::TileOrientation, Map},
+    EditorUi, GameState,
+};
+
+pub struct PalettePlugin;
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveTile>()
+            .register_type::<ActiveTile>()
+            .init_resource::<PlacementRotation>()
+            .init_resource::<PaletteFilter>()
+            .init_resource::<PaletteSearch>()
+            .add_systems(OPEN_EDITOR, (init_active_tile_text, init_palette_bar))
+            .add_systems(
+                PreUpdate,
+                edit_palette_search
+                    .after(InputSystem)
+                    .after(edit_prompt)
+                    .run_if(in_state(EditorUi)),
+            )
+            .add_systems(
+                Update,
+                (
+                    select_active_tile,
+                    rotate_placement,
+                    update_active_tile_text,
+                    press_category_buttons,
+                    press_palette_search,
+                    update_category_tree,
+                    update_palette_search_text,
+                    update_palette_tiles,
+                    press_palette_buttons,
+                    highlight_palette_buttons,
+                )
+                    .chain()
+                    .run_if(in_state(EditorUi)),
+            );
+    }
+}
+
+const PALETTE_COLOR: Color = Color::srgba(0.08, 0.08, 0.1, 0.85);
+const SLOT_IDLE: Color = Color::srgba(0.0, 0.0, 0.0, 0.0);
+const SLOT_HOVERED: Color = Color::srgb(0.5, 0.5, 0.6);
+const SLOT_ACTIVE: Color = Color::srgb(0.6, 0.75, 1.0);
+const CATEGORY_SELECTED: Color = Color::srgba(0.6, 0.75, 1.0, 0.3);
+
+/// The tile placement tools write.
+#[derive(Resource, Reflect, Clone, Eq, PartialEq, Debug)]
+#[reflect(Resource)]
+pub enum ActiveTile {
+    /// An entry of the active map's `tile_set`.
+    Index(#[reflect(ignore)] NonMaxU8),
+    /// A tile the active map doesn't use yet, added to its `tile_set` upon placement.
+    Path(String),
+}
+
+impl Default for ActiveTile {
+    #[inline]
+    fn default() -> Self {
+        Self::Index(NonMaxU8::ZERO)
+    }
+}
+
+impl ActiveTile {
+    #[inline]
+    pub fn path<'a>(&'a self, map: &'a Map) -> Option<&'a str> {
+        match self {
+            Self::Index(index) => map.tile_set.get(index.get() as usize).map(String::as_str),
+            Self::Path(path) => Some(path),
+        }
+    }
+
+    /// Selects a tile by path, referring to it by index if the map already uses it.
+    #[inline]
+    pub fn select(path: &str, map: &Map) -> Self {
+        match map
+            .tile_set
+            .iter()
+            .position(|tile| tile == path)
+            .and_then(|index| u8::try_from(index).ok().and_then(NonMaxU8::new))
+        {
+            Some(index) => Self::Index(index),
+            None => Self::Path(path.into()),
+        }
+    }
+
+    /// Resolves the tile into an index of the map's `tile_set`, adding it if necessary.
+    #[inline]
+    pub fn resolve(&mut self, map: &mut Map, tiles: &Tiles) -> Option<NonMaxU8> {
+        match self {
+            Self::Index(index) => Some(*index),
+            Self::Path(path) => {
+                let index = map.ensure_tile(path, tiles)?;
+                *self = Self::Index(index);
+                Some(index)
+            }
+        }
+    }
+}
+
+/// The orientation placed tiles are given, kept across tile selections.
+#[derive(Resource, Copy, Clone, Eq, PartialEq, Default, Debug, Deref, DerefMut)]
+pub struct PlacementRotation(pub TileOrientation);
+
+/// Every tile available to the editor, in the catalog's order.
+#[inline]
+pub fn palette(catalog: &TileCatalog) -> Vec<&str> {
+    catalog.order.iter().map(String::as_str).collect()
+}
+
+/// The category the palette bar shows, and which categories in its tree are expanded. Kept for the
+/// rest of the session, so the editor reopens on the last used category.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct PaletteFilter {
+    /// Slash-separated; empty for every tile.
+    pub category: String,
+    pub expanded: HashSet<String>,
+}
+
+/// Text the palette bar's tiles are searched by.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct PaletteSearch {
+    pub query: String,
+    /// Whether typing goes to the search box instead of editor bindings.
+    pub typing: bool,
+}
+
+/// The tiles shown in the palette bar: those in the filtered category matching the search, best
+/// matches first.
+pub fn filtered_palette(catalog: &TileCatalog, filter: &PaletteFilter, search: &PaletteSearch) -> Vec<String> {
+    match search.query.trim().is_empty() {
+        true => catalog.iter_category(&filter.category).map(String::from).collect(),
+        false => catalog
+            .search(&search.query)
+            .into_iter()
+            .map(|key| key.0)
+            .filter(|key| catalog.info(key).is_some_and(|info| info.in_category(&filter.category)))
+            .collect(),
+    }
+}
+
+#[derive(Component, Copy, Clone)]
+pub struct ActiveTileText;
+
+pub fn init_active_tile_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section("", TextStyle {
+            font_size: 18.0,
+            ..default()
+        })
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(8.0),
+            bottom: Val::Px(8.0),
+            ..default()
+        }),
+        ActiveTileText,
+        StateScoped(GameState::Editor),
+    ));
+}
+
+pub fn select_active_tile(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    catalog: Res<TileCatalog>,
+    maps: Res<Assets<Map>>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    mut active: ResMut<ActiveTile>,
+) {
+    let scroll = wheel
+        .read()
+        .map(|e| match e.unit {
+            MouseScrollUnit::Line => e.y,
+            MouseScrollUnit::Pixel => e.y / 16.0,
+        })
+        .sum::<f32>();
+
+    let Some(map) = editor_maps.get_single().ok().and_then(|map| maps.get(map)) else {
+        return
+    };
+
+    let palette = palette(&catalog);
+    if palette.is_empty() {
+        return
+    }
+
+    let current = active
+        .path(map)
+        .and_then(|path| palette.iter().position(|&tile| tile == path));
+
+    const DIGITS: [KeyCode; 9] = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+
+    let mut step = 0isize;
+    if keys.just_pressed(KeyCode::BracketLeft) {
+        step -= 1;
+    }
+
+    if keys.just_pressed(KeyCode::BracketRight) {
+        step += 1;
+    }
+
+    if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) && scroll != 0.0 {
+        step -= scroll.signum() as isize;
+    }
+
+    let selected = match DIGITS.iter().position(|&key| keys.just_pressed(key)) {
+        Some(digit) => (digit < palette.len()).then_some(digit),
+        None if step != 0 => Some(current.map_or(0, |current| {
+            (current as isize + step).rem_euclid(palette.len() as isize) as usize
+        })),
+        None => None,
+    };
+
+    if let Some(selected) = selected {
+        let selected = ActiveTile::select(palette[selected], map);
+        if *active != selected {
+            *active = selected;
+        }
+    }
+}
+
+pub fn rotate_placement(keys: Res<ButtonInput<KeyCode>>, mut rotation: ResMut<PlacementRotation>) {
+    if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return
+    }
+
+    let mut orientation = **rotation;
+    if keys.just_pressed(KeyCode::KeyR) {
+        let reverse = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        orientation = orientation.rotated(if reverse { -1 } else { 1 });
+    }
+
+    if keys.just_pressed(KeyCode::KeyT) {
+        orientation = orientation.toggled_flip();
+    }
+
+    rotation.set_if_neq(PlacementRotation(orientation));
+}
+
+pub fn update_active_tile_text(
+    active: Res<ActiveTile>,
+    rotation: Res<PlacementRotation>,
+    catalog: Res<TileCatalog>,
+    maps: Res<Assets<Map>>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    mut texts: Query<&mut Text, With<ActiveTileText>>,
+) {
+    let Some(map) = editor_maps.get_single().ok().and_then(|map| maps.get(map)) else {
+        return
+    };
+
+    let name = active
+        .path(map)
+        .map_or_else(|| "<none>".into(), |path| catalog.full_name(path));
+    let value = format!(
+        "Tile: {name} ({}°{})",
+        rotation.turns() * 90,
+        if rotation.flipped() { ", flipped" } else { "" }
+    );
+
+    for mut text in &mut texts {
+        if text.sections[0].value != value {
+            text.sections[0].value.clone_from(&value);
+        }
+    }
+}
+
+/// The tile palette along the bottom of the editor: a category tree beside a search box and the
+/// filtered tiles' thumbnails.
+#[derive(Component, Copy, Clone)]
+pub struct PaletteBar;
+
+#[derive(Component, Copy, Clone)]
+pub struct CategoryTree;
+
+/// Filters the palette by a category when clicked, expanding or collapsing it in the tree.
+#[derive(Component, Clone, Debug)]
+pub struct CategoryButton(pub String);
+
+/// Starts typing a search when clicked.
+#[derive(Component, Copy, Clone)]
+pub struct PaletteSearchBox;
+
+#[derive(Component, Copy, Clone)]
+pub struct PaletteSearchText;
+
+#[derive(Component, Copy, Clone)]
+pub struct PaletteTiles;
+
+/// Selects a tile by key when clicked.
+#[derive(Component, Clone, Debug)]
+pub struct PaletteButton(pub String);
+
+pub fn init_palette_bar(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(20.0),
+                    right: Val::Percent(20.0),
+                    bottom: Val::Px(8.0),
+                    align_items: AlignItems::FlexEnd,
+                    padding: UiRect::all(Val::Px(4.0)),
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                },
+                background_color: PALETTE_COLOR.into(),
+                ..default()
+            },
+            PaletteBar,
+            StateScoped(GameState::Editor),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        min_width: Val::Px(120.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                CategoryTree,
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        flex_grow: 1.0,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::axes(Val::Px(4.0), Val::Px(2.0)),
+                                    ..default()
+                                },
+                                background_color: Color::srgba(0.0, 0.0, 0.0, 0.4).into(),
+                                ..default()
+                            },
+                            PaletteSearchBox,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section("", TextStyle {
+                                    font_size: 14.0,
+                                    ..default()
+                                }),
+                                PaletteSearchText,
+                            ));
+                        });
+
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                flex_wrap: FlexWrap::Wrap,
+                                column_gap: Val::Px(2.0),
+                                row_gap: Val::Px(2.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        PaletteTiles,
+                    ));
+                });
+        });
+}
+
+/// Types into the palette search while it's focused, started with `/` or by clicking the search
+/// box. Enter selects the best match and escape clears the search; both stop typing.
+#[allow(clippy::too_many_arguments)]
+pub fn edit_palette_search(
+    mut events: EventReader<KeyboardInput>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    prompt: Res<ActivePrompt>,
+    catalog: Option<Res<TileCatalog>>,
+    filter: Res<PaletteFilter>,
+    mut search: ResMut<PaletteSearch>,
+    maps: Res<Assets<Map>>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    mut active: ResMut<ActiveTile>,
+) {
+    if !search.typing {
+        events.clear();
+        if prompt.is_none() && keys.just_pressed(KeyCode::Slash) {
+            search.typing = true;
+            keys.reset_all();
+        }
+
+        return
+    }
+
+    if prompt.is_some() {
+        search.typing = false;
+        return
+    }
+
+    for e in events.read() {
+        if e.state != ButtonState::Pressed {
+            continue
+        }
+
+        match &e.logical_key {
+            Key::Enter => {
+                search.typing = false;
+
+                let best = catalog
+                    .as_ref()
+                    .and_then(|catalog| filtered_palette(catalog, &filter, &search).into_iter().next());
+                let map = editor_maps.get_single().ok().and_then(|map| maps.get(map));
+                if let (Some(best), Some(map)) = (best, map) {
+                    let selected = ActiveTile::select(&best, map);
+                    if *active != selected {
+                        *active = selected;
+                    }
+                }
+            }
+            Key::Escape => {
+                search.typing = false;
+                search.query.clear();
+            }
+            Key::Backspace => {
+                search.query.pop();
+            }
+            Key::Space => search.query.push(' '),
+            Key::Character(text) => search.query.extend(text.chars().filter(|c| !c.is_control())),
+            _ => {}
+        }
+
+        if !search.typing {
+            break
+        }
+    }
+
+    // Keys typed into the search shouldn't also trigger editor bindings.
+    keys.reset_all();
+}
+
+pub fn press_palette_search(
+    boxes: Query<&Interaction, (With<PaletteSearchBox>, Changed<Interaction>)>,
+    prompt: Res<ActivePrompt>,
+    mut search: ResMut<PaletteSearch>,
+) {
+    if prompt.is_none() && boxes.iter().any(|&interaction| interaction == Interaction::Pressed) {
+        search.typing = true;
+    }
+}
+
+pub fn press_category_buttons(
+    buttons: Query<(&Interaction, &CategoryButton), Changed<Interaction>>,
+    mut filter: ResMut<PaletteFilter>,
+) {
+    for (&interaction, CategoryButton(category)) in &buttons {
+        if interaction != Interaction::Pressed {
+            continue
+        }
+
+        // Clicking the shown category again collapses it instead.
+        if filter.category == *category && filter.expanded.remove(category) {
+            continue
+        }
+
+        filter.category.clone_from(category);
+        filter.expanded.insert(category.clone());
+    }
+}
+
+/// Lists every category whose parents are expanded, indented by depth.
+pub fn update_category_tree(
+    mut commands: Commands,
+    catalog: Res<TileCatalog>,
+    filter: Res<PaletteFilter>,
+    trees: Query<Entity, Added<CategoryTree>>,
+    all_trees: Query<Entity, With<CategoryTree>>,
+) {
+    let trees = match catalog.is_changed() || filter.is_changed() {
+        true => all_trees.iter().collect::<Vec<_>>(),
+        false => trees.iter().collect(),
+    };
+
+    if trees.is_empty() {
+        return
+    }
+
+    let categories = catalog.categories();
+    let shown = categories.iter().filter(|category| {
+        category
+            .match_indices('/')
+            .all(|(end, _)| filter.expanded.contains(&category[..end]))
+    });
+
+    let rows = [(String::new(), "All".to_string(), 0)]
+        .into_iter()
+        .chain(shown.map(|category| {
+            let (depth, name) = (category.matches('/').count(), category.rsplit('/').next().unwrap_or(category));
+            let parent = categories.iter().any(|other| {
+                other
+                    .strip_prefix(category.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            });
+
+            let marker = match (parent, filter.expanded.contains(category)) {
+                (false, ..) => "  ",
+                (true, false) => "+ ",
+                (true, true) => "- ",
+            };
+
+            (category.clone(), format!("{marker}{name}"), depth)
+        }))
+        .collect::<Vec<_>>();
+
+    for tree in trees {
+        commands.entity(tree).despawn_descendants().with_children(|parent| {
+            for (category, label, depth) in &rows {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect {
+                                    left: Val::Px(4.0 + *depth as f32 * 10.0),
+                                    right: Val::Px(4.0),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            background_color: match filter.category == *category {
+                                true => CATEGORY_SELECTED,
+                                false => SLOT_IDLE,
+                            }
+                            .into(),
+                            ..default()
+                        },
+                        CategoryButton(category.clone()),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(label.clone(), TextStyle {
+                            font_size: 14.0,
+                            ..default()
+                        }));
+                    });
+            }
+        });
+    }
+}
+
+pub fn update_palette_search_text(
+    filter: Res<PaletteFilter>,
+    search: Res<PaletteSearch>,
+    mut texts: Query<&mut Text, With<PaletteSearchText>>,
+) {
+    let scope = match filter.category.is_empty() {
+        true => "all tiles",
+        false => filter.category.as_str(),
+    };
+
+    let value = match (search.typing, search.query.is_empty()) {
+        (true, ..) => format!("Search {scope}: {}_", search.query),
+        (false, true) => format!("/ to search {scope}"),
+        (false, false) => format!("Search {scope}: {}", search.query),
+    };
+
+    for mut text in &mut texts {
+        if text.sections[0].value != value {
+            text.sections[0].value.clone_from(&value);
+        }
+    }
+}
+
+/// Fills the palette bar with a thumbnail of every tile the filter and search leave, again whenever
+/// tiles are added or the filter changes.
+pub fn update_palette_tiles(
+    mut commands: Commands,
+    catalog: Res<TileCatalog>,
+    thumbnails: Res<TileThumbnails>,
+    filter: Res<PaletteFilter>,
+    search: Res<PaletteSearch>,
+    rows: Query<Entity, Added<PaletteTiles>>,
+    all_rows: Query<Entity, With<PaletteTiles>>,
+) {
+    let changed = catalog.is_changed() || thumbnails.is_changed() || filter.is_changed() || search.is_changed();
+    let rows = match changed {
+        true => all_rows.iter().collect::<Vec<_>>(),
+        false => rows.iter().collect(),
+    };
+
+    if rows.is_empty() {
+        return
+    }
+
+    let keys = filtered_palette(&catalog, &filter, &search);
+    for row in rows {
+        commands.entity(row).despawn_descendants().with_children(|parent| {
+            for key in &keys {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(44.0),
+                                height: Val::Px(44.0),
+                                border: UiRect::all(Val::Px(2.0)),
+                                ..default()
+                            },
+                            border_color: SLOT_IDLE.into(),
+                            image: thumbnails.get(key).cloned().unwrap_or_default().into(),
+                            ..default()
+                        },
+                        PaletteButton(key.clone()),
+                    ))
+                    .insert(Name::new(catalog.full_name(key)));
+            }
+        });
+    }
+}
+
+pub fn press_palette_buttons(
+    buttons: Query<(&Interaction, &PaletteButton), Changed<Interaction>>,
+    maps: Res<Assets<Map>>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    mut active: ResMut<ActiveTile>,
+) {
+    let Some(map) = editor_maps.get_single().ok().and_then(|map| maps.get(map)) else {
+        return
+    };
+
+    for (&interaction, PaletteButton(key)) in &buttons {
+        if interaction == Interaction::Pressed {
+            let selected = ActiveTile::select(key, map);
+            if *active != selected {
+                *active = selected;
+            }
+        }
+    }
+}
+
+/// Outlines the active tile's button, and whichever the cursor is over.
+pub fn highlight_palette_buttons(
+    active: Res<ActiveTile>,
+    maps: Res<Assets<Map>>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    mut buttons: Query<(&Interaction, &PaletteButton, &mut BorderColor)>,
+) {
+    let Some(map) = editor_maps.get_single().ok().and_then(|map| maps.get(map)) else {
+        return
+    };
+
+    let active = active.path(map);
+    for (&interaction, PaletteButton(key), mut border) in &mut buttons {
+        let color = match (active == Some(key), interaction) {
+            (true, ..) => SLOT_ACTIVE,
+            (false, Interaction::Hovered | Interaction::Pressed) => SLOT_HOVERED,
+            (false, Interaction::None) => SLOT_IDLE,
+        };
+
+        if border.0 != color {
+            border.0 = color;
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::time::Duration;
+
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState, InputSystem,
+    },
+    prelude::*,
+};
+
+use crate::{editor::OPEN_EDITOR, EditorState, EditorUi, GameState};
+
+pub struct PromptPlugin;
+impl Plugin for PromptPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActivePrompt>()
+            .init_resource::<Notice>()
+            .add_event::<PromptSubmit>()
+            .add_systems(OPEN_EDITOR, init_prompt_text)
+            .add_systems(
+                PreUpdate,
+                (edit_prompt.run_if(in_state(EditorUi)), sync_dialog_state)
+                    .chain()
+                    .after(InputSystem)
+                    .run_if(in_state(GameState::Editor)),
+            )
+            .add_systems(
+                Update,
+                (expire_notice, update_prompt_text)
+                    .chain()
+                    .run_if(in_state(EditorUi)),
+            );
+    }
+}
+
+/// A question waiting on the user, answered either with typed text or by picking one of `choices`.
+#[derive(Clone, Debug)]
+pub struct Prompt {
+    /// Tells apart what the answer is for in [`PromptSubmit`].
+    pub id: &'static str,
+    pub label: String,
+    pub input: String,
+    /// If non-empty, the prompt is answered by picking one of these instead of typing.
+    pub choices: Vec<String>,
+    pub selected: usize,
+}
+
+impl Prompt {
+    #[inline]
+    pub fn text(id: &'static str, label: impl Into<String>, input: impl Into<String>) -> Self {
+        Self {
+            id,
+            label: label.into(),
+            input: input.into(),
+            choices: Vec::new(),
+            selected: 0,
+        }
+    }
+
+    #[inline]
+    pub fn choice(id: &'static str, label: impl Into<String>, choices: Vec<String>) -> Self {
+        Self {
+            id,
+            label: label.into(),
+            input: String::new(),
+            choices,
+            selected: 0,
+        }
+    }
+}
+
+/// The open prompt, if any. While a prompt is open it takes all keyboard input.
+#[derive(Resource, Clone, Default, Debug, Deref, DerefMut)]
+pub struct ActivePrompt(pub Option<Prompt>);
+
+/// Sent when a prompt is confirmed with enter.
+#[derive(Event, Clone, Debug)]
+pub struct PromptSubmit {
+    pub id: &'static str,
+    pub value: String,
+}
+
+/// A short-lived message shown to the user.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct Notice {
+    pub text: String,
+    pub timer: Timer,
+}
+
+impl Notice {
+    #[inline]
+    pub fn show(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.timer = Timer::new(Duration::from_secs(4), TimerMode::Once);
+    }
+}
+
+#[derive(Component, Copy, Clone)]
+pub struct PromptText;
+
+pub fn init_prompt_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section("", TextStyle {
+            font_size: 18.0,
+            ..default()
+        })
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(8.0),
+            top: Val::Px(8.0),
+            ..default()
+        }),
+        PromptText,
+        StateScoped(GameState::Editor),
+    ));
+}
+
+pub fn edit_prompt(
+    mut events: EventReader<KeyboardInput>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut prompt: ResMut<ActivePrompt>,
+    mut submit: EventWriter<PromptSubmit>,
+) {
+    if prompt.is_none() {
+        events.clear();
+        return
+    }
+
+    let open = prompt.0.as_mut().unwrap();
+
+    let mut done = false;
+    for e in events.read() {
+        if e.state != ButtonState::Pressed {
+            continue
+        }
+
+        match &e.logical_key {
+            Key::Enter => {
+                let value = match open.choices.get(open.selected) {
+                    Some(choice) => choice.clone(),
+                    None => std::mem::take(&mut open.input),
+                };
+
+                submit.send(PromptSubmit { id: open.id, value });
+                done = true;
+            }
+            Key::Escape => done = true,
+            Key::Backspace => {
+                open.input.pop();
+            }
+            Key::ArrowUp if !open.choices.is_empty() => {
+                open.selected = (open.selected + open.choices.len() - 1) % open.choices.len();
+            }
+            Key::ArrowDown if !open.choices.is_empty() => {
+                open.selected = (open.selected + 1) % open.choices.len();
+            }
+            Key::Space if open.choices.is_empty() => open.input.push(' '),
+            Key::Character(text) if open.choices.is_empty() => {
+                open.input.extend(text.chars().filter(|c| !c.is_control()));
+            }
+            _ => {}
+        }
+
+        if done {
+            break
+        }
+    }
+
+    if done {
+        **prompt = None;
+    }
+
+    // Keys typed into the prompt shouldn't also trigger editor bindings.
+    keys.reset_all();
+}
+
+/// Enters [`EditorState::Dialog`] while a prompt is open, and goes back to editing once it's
+/// answered. Prompts opened during a frame take it over from the next.
+pub fn sync_dialog_state(
+    prompt: Res<ActivePrompt>,
+    state: Res<State<EditorState>>,
+    mut next_state: ResMut<NextState<EditorState>>,
+) {
+    match (prompt.is_some(), state.get()) {
+        (true, EditorState::Editing) => next_state.set(EditorState::Dialog),
+        (false, EditorState::Dialog) => next_state.set(EditorState::Editing),
+        _ => {}
+    }
+}
+
+pub fn expire_notice(time: Res<Time>, mut notice: ResMut<Notice>) {
+    if notice.text.is_empty() {
+        return
+    }
+
+    if notice.bypass_change_detection().timer.tick(time.delta()).finished() {
+        notice.text.clear();
+    }
+}
+
+pub fn update_prompt_text(prompt: Res<ActivePrompt>, notice: Res<Notice>, mut texts: Query<&mut Text, With<PromptText>>) {
+    if !prompt.is_changed() && !notice.is_changed() {
+        return
+    }
+
+    let mut text = String::new();
+    if let Some(prompt) = &prompt.0 {
+        text.push_str(&prompt.label);
+        match prompt.choices.is_empty() {
+            true => text.push_str(&format!(": {}_", prompt.input)),
+            false => {
+                for (i, choice) in prompt.choices.iter().enumerate() {
+                    text.push_str(&format!("\n{} {choice}", if i == prompt.selected { ">" } else { " " }));
+                }
+            }
+        }
+
+        text.push('\n');
+    }
+
+    text.push_str(&notice.text);
+    for mut value in &mut texts {
+        value.sections[0].value.clone_from(&text);
+    }
+}
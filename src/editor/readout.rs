@@ -0,0 +1,166 @@
+use std::fmt::Write;
+
+use bevy::{color::palettes::css, prelude::*, window::PrimaryWindow};
+
+use crate::{
+    editor::{
+        brush::Brush,
+        group::ActiveGroup,
+        layer::ActiveLayer,
+        tools::{update_cursor_target, CursorTarget, ToolMode},
+        EditorMap, OPEN_EDITOR,
+    },
+    map::Map,
+    EditorUi, GameState,
+};
+
+pub struct ReadoutPlugin;
+impl Plugin for ReadoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OPEN_EDITOR, init_readout_text).add_systems(
+            Update,
+            (update_readout, measure_cells)
+                .after(update_cursor_target)
+                .run_if(in_state(EditorUi)),
+        );
+    }
+}
+
+/// Held while dragging to measure between cells. `M` already switches to the select tool.
+pub const MEASURE_KEY: KeyCode = KeyCode::KeyJ;
+
+#[derive(Component, Copy, Clone, Default)]
+pub struct ReadoutText;
+
+#[derive(Component, Copy, Clone, Default)]
+pub struct MeasureText;
+
+pub fn init_readout_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section("", TextStyle {
+            font_size: 18.0,
+            ..default()
+        })
+        .with_text_justify(JustifyText::Right)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(8.0),
+            top: Val::Px(8.0),
+            ..default()
+        }),
+        ReadoutText,
+        StateScoped(GameState::Editor),
+    ));
+
+    commands.spawn((
+        TextBundle::from_section("", TextStyle {
+            font_size: 16.0,
+            color: css::YELLOW.into(),
+            ..default()
+        })
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            ..default()
+        }),
+        Visibility::Hidden,
+        MeasureText,
+        StateScoped(GameState::Editor),
+    ));
+}
+
+/// The cell under the cursor: the struck cell, or the layer cell when nothing is struck.
+#[inline]
+pub fn hovered_cell(target: &CursorTarget) -> Option<IVec3> {
+    target.hit.map(|hit| hit.cell.as_ivec3()).or(target.layer_cell)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_readout(
+    target: Res<CursorTarget>,
+    layer: Res<ActiveLayer>,
+    mode: Res<State<ToolMode>>,
+    brush: Res<Brush>,
+    group: Res<ActiveGroup>,
+    maps: Res<Assets<Map>>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    mut texts: Query<(&mut Text, &mut Visibility), With<ReadoutText>>,
+) {
+    let map = editor_maps.get_single().ok().and_then(|handle| maps.get(handle));
+    let cell = hovered_cell(&target);
+    for (mut text, mut visibility) in &mut texts {
+        let (Some(map), Some(cell)) = (map, cell) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue
+        };
+
+        visibility.set_if_neq(Visibility::Inherited);
+
+        let value = &mut text.sections[0].value;
+        value.clear();
+
+        let _ = writeln!(value, "Cell: {}, {}, {}", cell.x, cell.y, cell.z);
+        let _ = writeln!(value, "Layer: {}", **layer);
+        let _ = write!(value, "{}, brush {}", mode.name(), brush.size);
+        if group.enabled {
+            let _ = write!(value, ", group {}", group.name());
+        }
+
+        value.push('\n');
+        let _ = write!(value, "Map: {}x{}x{}", map.size.x, map.size.y, map.size.z);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn measure_cells(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    target: Res<CursorTarget>,
+    maps: Res<Assets<Map>>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    editor_maps: Query<(&Handle<Map>, &GlobalTransform), With<EditorMap>>,
+    mut texts: Query<(&mut Text, &mut Style, &mut Visibility), With<MeasureText>>,
+    mut gizmos: Gizmos,
+    mut anchor: Local<Option<IVec3>>,
+) {
+    let cell = hovered_cell(&target);
+    if !keys.pressed(MEASURE_KEY) || !mouse.pressed(MouseButton::Left) || cell.is_none() {
+        *anchor = None;
+    } else if mouse.just_pressed(MouseButton::Left) || keys.just_pressed(MEASURE_KEY) {
+        *anchor = cell;
+    }
+
+    let measured = anchor
+        .zip(cell)
+        .zip(editor_maps.get_single().ok())
+        .and_then(|((from, to), (handle, trns))| Some((from, to, maps.get(handle)?, trns)));
+
+    let Some((from, to, map, &map_trns)) = measured else {
+        for (.., mut visibility) in &mut texts {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+
+        return
+    };
+
+    let center = |cell: IVec3| map_trns.transform_point(cell.as_vec3() * map.tile_size);
+    gizmos.line(center(from), center(to), css::YELLOW);
+    gizmos.sphere(center(from), Quat::IDENTITY, map.tile_size.min_element() * 0.1, css::YELLOW);
+
+    let delta = to - from;
+    let cursor = window.get_single().ok().and_then(Window::cursor_position);
+    for (mut text, mut style, mut visibility) in &mut texts {
+        let Some(cursor) = cursor else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue
+        };
+
+        visibility.set_if_neq(Visibility::Inherited);
+        style.left = Val::Px(cursor.x + 16.0);
+        style.top = Val::Px(cursor.y + 16.0);
+        text.sections[0].value = format!(
+            "Manhattan: {}\nEuclidean: {:.2}",
+            delta.abs().element_sum(),
+            delta.as_vec3().length(),
+        );
+    }
+}
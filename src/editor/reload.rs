@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+
+use crate::{
+    editor::{
+        history::EditorHistory,
+        prompt::{ActivePrompt, Notice, Prompt, PromptSubmit},
+        session::{watch_opened_map, EditorSession},
+        tools::select::{EditorSelection, Floating},
+        EditorMap, EditorSettings,
+    },
+    map::{loader::MapFile, Map},
+    EditorUi,
+};
+
+pub struct ReloadPlugin;
+impl Plugin for ReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TakeExternalMap>().add_systems(
+            Update,
+            (watch_map_file, answer_conflict, take_external_map)
+                .chain()
+                .after(watch_opened_map)
+                .run_if(in_state(EditorUi)),
+        );
+    }
+}
+
+/// Replaces the edited map with the file as it is on disk now, dropping unsaved changes and
+/// history.
+#[derive(Event, Copy, Clone, Default, Debug)]
+pub struct TakeExternalMap;
+
+const KEEP_MINE: &str = "keep mine";
+const TAKE_THEIRS: &str = "take theirs";
+
+/// Follows changes made to the open map's file outside the editor, which only arrive with Bevy's
+/// file watcher. They're taken right away over a clean map, and asked about over unsaved changes.
+#[allow(clippy::too_many_arguments)]
+pub fn watch_map_file(
+    mut events: EventReader<AssetEvent<Map>>,
+    settings: Res<EditorSettings>,
+    session: Res<EditorSession>,
+    maps: Res<Assets<Map>>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    mut prompt: ResMut<ActivePrompt>,
+    mut notice: ResMut<Notice>,
+    mut takes: EventWriter<TakeExternalMap>,
+    mut conflict: Local<bool>,
+) {
+    let Some(source) = session.source.as_ref().filter(|_| session.opening.is_none()) else {
+        events.clear();
+        return
+    };
+
+    if events.read().any(|e| e.is_modified(source)) {
+        let (Some(theirs), Some(mine)) = (
+            maps.get(source),
+            editor_maps.get_single().ok().and_then(|handle| maps.get(handle)),
+        ) else {
+            return
+        };
+
+        // The editor's own saves come back as changes too.
+        let theirs = MapFile::from(theirs);
+        let mine = MapFile {
+            lighting: settings.lighting,
+            ..MapFile::from(mine)
+        };
+
+        if theirs == mine || session.written.as_ref() == Some(&theirs) {
+            return
+        }
+
+        match session.is_dirty() {
+            false => {
+                takes.send(TakeExternalMap);
+            }
+            true => {
+                notice.show(format!("`{}` changed on disk.", session.path.as_deref().unwrap_or_default()));
+                *conflict = true;
+            }
+        }
+    }
+
+    // Other prompts are let through first.
+    if *conflict && prompt.is_none() {
+        *conflict = false;
+        **prompt = Some(Prompt::choice(
+            "map-conflict",
+            format!(
+                "`{}` changed on disk; unsaved changes",
+                session.path.as_deref().unwrap_or_default()
+            ),
+            vec![KEEP_MINE.into(), TAKE_THEIRS.into()],
+        ));
+    }
+}
+
+pub fn answer_conflict(mut answers: EventReader<PromptSubmit>, mut takes: EventWriter<TakeExternalMap>) {
+    for answer in answers.read() {
+        if answer.id == "map-conflict" && answer.value == TAKE_THEIRS {
+            takes.send(TakeExternalMap);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn take_external_map(
+    mut takes: EventReader<TakeExternalMap>,
+    mut settings: ResMut<EditorSettings>,
+    mut session: ResMut<EditorSession>,
+    mut history: ResMut<EditorHistory>,
+    mut selection: ResMut<EditorSelection>,
+    mut floating: ResMut<Floating>,
+    mut notice: ResMut<Notice>,
+    mut maps: ResMut<Assets<Map>>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+) {
+    if takes.read().count() == 0 {
+        return
+    }
+
+    let (Some(source), Ok(handle)) = (session.source.clone(), editor_maps.get_single()) else {
+        return
+    };
+    let Some(theirs) = maps.get(&source).cloned() else { return };
+    let Some(mine) = maps.get_mut(handle) else { return };
+
+    settings.lighting = theirs.lighting;
+    *mine = theirs;
+
+    // Undoing past the external change would mix two versions of the map.
+    history.clear();
+    selection.set_if_neq(default());
+    **floating = None;
+
+    session.saved_revision = session.revision;
+    notice.show(format!(
+        "Reloaded `{}` from disk.",
+        session.path.as_deref().unwrap_or_default()
+    ));
+}
@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+use nonmax::NonMaxU8;
+
+use crate::{
+    content::{manifest::TileCatalog, Tiles},
+    editor::{
+        history::MapCommands,
+        palette::{palette, ActiveTile},
+        prompt::{ActivePrompt, Notice, Prompt, PromptSubmit},
+        tools::{select::EditorSelection, CursorTarget},
+    },
+    map::Map,
+    EditorState, EditorUi,
+};
+
+pub struct ReplacePlugin;
+impl Plugin for ReplacePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingReplace>()
+            .add_event::<ReplaceRequest>()
+            .add_systems(
+                Update,
+                (
+                    replace_shortcut.run_if(in_state(EditorState::Editing)),
+                    answer_replace_prompt,
+                    replace_tiles,
+                )
+                    .chain()
+                    .run_if(in_state(EditorUi)),
+            );
+    }
+}
+
+/// Asks to swap every occurrence of a tile set entry for another tile.
+#[derive(Event, Clone, Debug)]
+pub struct ReplaceRequest {
+    pub from: NonMaxU8,
+    /// The replacing tile's path, added to the tile set if the map doesn't use it yet.
+    pub to: String,
+    /// Whether only cells within the selection are replaced.
+    pub within_selection: bool,
+}
+
+/// The tile set entry an open "replace all with…" prompt replaces, and whether only within the
+/// selection.
+#[derive(Resource, Copy, Clone, Default, Debug)]
+pub struct PendingReplace(pub Option<(NonMaxU8, bool)>);
+
+/// Opens the prompt choosing what replaces a tile set entry: any tile in the palette or the tile
+/// set other than itself.
+pub fn prompt_replace(
+    from: NonMaxU8,
+    within_selection: bool,
+    map: &Map,
+    catalog: &TileCatalog,
+    prompt: &mut ActivePrompt,
+    pending: &mut PendingReplace,
+) {
+    let Some(path) = map.tile_set.get(from.get() as usize) else {
+        return
+    };
+
+    let mut choices = palette(catalog)
+        .into_iter()
+        .chain(map.tile_set.iter().map(String::as_str))
+        .filter(|&tile| tile != path)
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    choices.sort_unstable();
+    choices.dedup();
+
+    **prompt = Some(Prompt::choice(
+        "replace-tile",
+        format!(
+            "Replace `{path}`{} with",
+            if within_selection { " in the selection" } else { "" }
+        ),
+        choices,
+    ));
+    pending.0 = Some((from, within_selection));
+}
+
+/// Ctrl+R replaces the hovered tile with the active tile, within the selection if there is one.
+pub fn replace_shortcut(
+    keys: Res<ButtonInput<KeyCode>>,
+    target: Res<CursorTarget>,
+    selection: Res<EditorSelection>,
+    active: Res<ActiveTile>,
+    commands: MapCommands,
+    mut requests: EventWriter<ReplaceRequest>,
+) {
+    if !keys.just_pressed(KeyCode::KeyR) || !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return
+    }
+
+    let Some(map) = commands.map() else { return };
+    let (Some(hit), Some(to)) = (target.hit, active.path(map)) else {
+        return
+    };
+
+    if let Some(from) = map.get(hit.cell) {
+        requests.send(ReplaceRequest {
+            from,
+            to: to.into(),
+            within_selection: !selection.cells.is_empty(),
+        });
+    }
+}
+
+pub fn answer_replace_prompt(
+    mut answers: EventReader<PromptSubmit>,
+    mut pending: ResMut<PendingReplace>,
+    mut requests: EventWriter<ReplaceRequest>,
+) {
+    for answer in answers.read() {
+        if answer.id != "replace-tile" {
+            continue
+        }
+
+        if let Some((from, within_selection)) = pending.0.take() {
+            requests.send(ReplaceRequest {
+                from,
+                to: answer.value.clone(),
+                within_selection,
+            });
+        }
+    }
+}
+
+pub fn replace_tiles(
+    tiles: Res<Tiles>,
+    selection: Res<EditorSelection>,
+    mut requests: EventReader<ReplaceRequest>,
+    mut commands: MapCommands,
+    mut notice: ResMut<Notice>,
+) {
+    for request in requests.read() {
+        let Some(map) = commands.map() else { return };
+        let Some(from) = map.tile_set.get(request.from.get() as usize).cloned() else {
+            continue
+        };
+
+        let within_selection = request.within_selection && !selection.cells.is_empty();
+
+        let to = match map.tile_set.iter().position(|tile| *tile == request.to) {
+            Some(to) => NonMaxU8::new(to as u8),
+            None => commands
+                .map_mut_untracked()
+                .and_then(|map| map.ensure_tile(&request.to, &tiles)),
+        };
+
+        let Some(to) = to else {
+            notice.show(format!("Couldn't add `{}` to the tile set.", request.to));
+            continue
+        };
+
+        let mut count = 0;
+        commands.edit(false, |map| {
+            let diff = match within_selection {
+                true => map.replace_tile_cells(request.from, to, selection.cells.iter()),
+                false => map.replace_tile_region(request.from, to, IVec3::ZERO, map.size.as_ivec3()),
+            };
+            count = diff.edits.len();
+            diff
+        });
+
+        notice.show(format!(
+            "Replaced {count} cell{} of `{from}` with `{}`{}.",
+            if count == 1 { "" } else { "s" },
+            request.to,
+            if within_selection { " in the selection" } else { "" },
+        ));
+    }
+}
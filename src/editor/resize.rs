@@ -0,0 +1,284 @@
+use bevy::{color::palettes::css, prelude::*, window::PrimaryWindow};
+
+use crate::{
+    editor::{
+        camera::EditorCamera,
+        history::MapCommands,
+        prompt::{ActivePrompt, Notice, Prompt, PromptSubmit},
+        tools::{draw_region, navigating, CursorTarget},
+        EditorMap,
+    },
+    map::Map,
+    EditorUi,
+};
+
+pub struct ResizePlugin;
+impl Plugin for ResizePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ResizeSettings>()
+            .init_resource::<ResizeDrag>()
+            .init_resource::<PendingResize>()
+            .add_systems(Update, confirm_resize.run_if(in_state(EditorUi)));
+    }
+}
+
+#[derive(Resource, Copy, Clone)]
+pub struct ResizeSettings {
+    /// Gap between a face of the map bounds and its handle, in world units.
+    pub handle_gap: f32,
+    pub handle_length: f32,
+    /// How close to a handle on screen the pointer has to be to grab it, in pixels.
+    pub grab_distance: f32,
+}
+
+impl Default for ResizeSettings {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            handle_gap: 0.5,
+            handle_length: 1.5,
+            grab_distance: 10.0,
+        }
+    }
+}
+
+/// A face of the map bounds, given by the axis it faces along and whether it's the far side.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct BoundsFace {
+    pub axis: usize,
+    pub positive: bool,
+}
+
+impl BoundsFace {
+    pub const ALL: [Self; 6] = [
+        Self::new(0, false),
+        Self::new(0, true),
+        Self::new(1, false),
+        Self::new(1, true),
+        Self::new(2, false),
+        Self::new(2, true),
+    ];
+
+    #[inline]
+    pub const fn new(axis: usize, positive: bool) -> Self {
+        Self { axis, positive }
+    }
+
+    #[inline]
+    pub fn normal(self) -> Vec3 {
+        Vec3::AXES[self.axis] * if self.positive { 1.0 } else { -1.0 }
+    }
+
+    /// The local-space center of the face.
+    #[inline]
+    pub fn center(self, map: &Map) -> Vec3 {
+        let (min, max) = (map.cell_min(IVec3::ZERO), map.cell_min(map.size.as_ivec3()));
+        let mut center = (min + max) * 0.5;
+        center[self.axis] = if self.positive { max[self.axis] } else { min[self.axis] };
+        center
+    }
+
+    /// The size and cell offset that move this face outwards by some cells, or inwards if
+    /// negative, keeping the opposite face still. The map keeps at least one cell along the axis.
+    #[inline]
+    pub fn resized(self, map: &Map, cells: i32) -> (UVec3, IVec3) {
+        let mut size = map.size.as_ivec3();
+        let len = (size[self.axis] + cells).clamp(1, 256);
+        let grown = len - size[self.axis];
+        size[self.axis] = len;
+
+        let mut offset = IVec3::ZERO;
+        if !self.positive {
+            offset[self.axis] = grown;
+        }
+
+        (size.as_uvec3(), offset)
+    }
+}
+
+/// The bounds handle being dragged, if any.
+#[derive(Resource, Copy, Clone, Default, Debug)]
+pub struct ResizeDrag(pub Option<HandleDrag>);
+
+#[derive(Copy, Clone, Debug)]
+pub struct HandleDrag {
+    pub face: BoundsFace,
+    /// Where along the face normal the handle was grabbed, in local space.
+    pub grabbed: f32,
+    /// Whole cells the face has been dragged outwards.
+    pub cells: i32,
+}
+
+/// A resize that deletes tiles, waiting on confirmation.
+#[derive(Resource, Copy, Clone, Default, Debug)]
+pub struct PendingResize(pub Option<(UVec3, IVec3)>);
+
+/// How far along a line through `origin` in direction `dir` the point closest to a ray lies, or
+/// `None` if they're parallel.
+fn closest_along(origin: Vec3, dir: Vec3, ray: Ray3d) -> Option<f32> {
+    let (d, r) = (dir, *ray.direction);
+    let w = origin - ray.origin;
+    let (b, d_w, r_w) = (d.dot(r), d.dot(w), r.dot(w));
+    let denom = 1.0 - b * b;
+    (denom.abs() > 1e-5).then(|| (b * r_w - d_w) / denom)
+}
+
+/// Distance from a point to a line segment.
+fn segment_distance(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let t = ((point - a).dot(ab) / ab.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+    point.distance(a + ab * t)
+}
+
+/// How many tiles a resize would delete.
+pub fn deleted_tiles(map: &Map, size: UVec3, offset: IVec3) -> usize {
+    map.tiles
+        .iter()
+        .enumerate()
+        .filter(|&(index, tile)| {
+            let cell = map.cell(index).as_ivec3() + offset;
+            tile.is_some() && !(cell.cmpge(IVec3::ZERO).all() && cell.as_uvec3().cmplt(size).all())
+        })
+        .count()
+}
+
+/// Draws an arrow on each face of the map bounds; dragging one moves that face in whole cells. Runs
+/// before the tools, taking the cursor from them while a handle is hovered or dragged.
+#[allow(clippy::too_many_arguments)]
+pub fn drag_resize_handles(
+    settings: Res<ResizeSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&EditorCamera, &Camera, &GlobalTransform)>,
+    editor_maps: Query<&GlobalTransform, With<EditorMap>>,
+    mut target: ResMut<CursorTarget>,
+    mut drag: ResMut<ResizeDrag>,
+    mut pending: ResMut<PendingResize>,
+    mut prompt: ResMut<ActivePrompt>,
+    mut notice: ResMut<Notice>,
+    mut commands: MapCommands,
+    mut gizmos: Gizmos,
+) {
+    let (Ok(window), Ok((camera, cam, cam_trns)), Ok(&map_trns), Some(map)) = (
+        window.get_single(),
+        cameras.get_single(),
+        editor_maps.get_single(),
+        commands.map(),
+    ) else {
+        drag.0 = None;
+        return
+    };
+
+    if keys.just_pressed(KeyCode::Escape) {
+        drag.0 = None;
+    }
+
+    let pointer = camera.pointer_position(cam, window);
+    let ray = target.ray;
+
+    let handle = |face: BoundsFace| {
+        let start = face.center(map) + face.normal() * settings.handle_gap;
+        (start, start + face.normal() * settings.handle_length)
+    };
+
+    // Nothing is grabbed over UI or while the mouse is busy navigating.
+    let hovered = match (drag.0, pointer) {
+        (None, Some(pointer)) if !target.over_ui && !navigating(&keys) && prompt.is_none() => BoundsFace::ALL
+            .into_iter()
+            .filter_map(|face| {
+                let (start, end) = handle(face);
+                let start = cam.world_to_viewport(cam_trns, map_trns.transform_point(start))?;
+                let end = cam.world_to_viewport(cam_trns, map_trns.transform_point(end))?;
+                Some((face, segment_distance(pointer, start, end)))
+            })
+            .filter(|&(_, distance)| distance <= settings.grab_distance)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(face, _)| face),
+        (Some(drag), _) => Some(drag.face),
+        _ => None,
+    };
+
+    for face in BoundsFace::ALL {
+        let (start, end) = handle(face);
+        let color = match hovered == Some(face) {
+            true => css::YELLOW,
+            false => css::LIGHT_SLATE_GRAY,
+        };
+
+        gizmos.arrow(map_trns.transform_point(start), map_trns.transform_point(end), color);
+    }
+
+    let Some(face) = hovered else { return };
+    *target = CursorTarget {
+        over_ui: true,
+        ..default()
+    };
+
+    let along = ray.and_then(|ray| closest_along(face.center(map), face.normal(), ray));
+    if drag.0.is_none() && mouse.just_pressed(MouseButton::Left) {
+        if let Some(grabbed) = along {
+            drag.0 = Some(HandleDrag { face, grabbed, cells: 0 });
+        }
+    }
+
+    let Some(active) = drag.0.as_mut() else { return };
+    if let Some(along) = along {
+        active.cells = ((along - active.grabbed) / map.tile_size[face.axis]).round() as i32;
+    }
+
+    let (size, offset) = face.resized(map, active.cells);
+    draw_region(&mut gizmos, map, &map_trns, (-offset, size.as_ivec3() - offset), css::AQUA);
+    notice.show(format!(
+        "Resize {}x{}x{} to {}x{}x{}",
+        map.size.x, map.size.y, map.size.z, size.x, size.y, size.z
+    ));
+
+    if mouse.pressed(MouseButton::Left) {
+        return
+    }
+
+    drag.0 = None;
+    if size == map.size {
+        return
+    }
+
+    match deleted_tiles(map, size, offset) {
+        0 => {
+            commands.resize(size, offset, false);
+            notice.show(format!("Resized to {}x{}x{}.", size.x, size.y, size.z));
+        }
+        deleted => {
+            pending.0 = Some((size, offset));
+            **prompt = Some(Prompt::choice(
+                "resize",
+                format!(
+                    "Shrinking to {}x{}x{} deletes {deleted} tile{}",
+                    size.x,
+                    size.y,
+                    size.z,
+                    if deleted == 1 { "" } else { "s" }
+                ),
+                vec!["cancel".into(), "resize".into()],
+            ));
+        }
+    }
+}
+
+pub fn confirm_resize(
+    mut answers: EventReader<PromptSubmit>,
+    mut pending: ResMut<PendingResize>,
+    mut commands: MapCommands,
+    mut notice: ResMut<Notice>,
+) {
+    for answer in answers.read() {
+        if answer.id != "resize" {
+            continue
+        }
+
+        if let (Some((size, offset)), "resize") = (pending.0.take(), answer.value.as_str()) {
+            commands.resize(size, offset, false);
+            notice.show(format!("Resized to {}x{}x{}.", size.x, size.y, size.z));
+        }
+    }
+}
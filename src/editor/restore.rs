@@ -0,0 +1,123 @@
+use std::{fs, io, path::Path};
+
+use bevy::{app::AppExit, prelude::*};
+use ron::{error::SpannedError, Error as RonError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    editor::{
+        camera::{CameraMode, EditorCamera},
+        layer::ActiveLayer,
+        session::{maps_dir, EditorSession},
+        CLOSE_EDITOR,
+    },
+    AssetRoot, GameState,
+};
+
+pub struct RestorePlugin;
+impl Plugin for RestorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(CLOSE_EDITOR, save_session_state).add_systems(
+            Last,
+            save_session_state
+                .run_if(on_event::<AppExit>())
+                .run_if(in_state(GameState::Editor)),
+        );
+    }
+}
+
+/// The file in [`MAPS_DIR`](crate::editor::session::MAPS_DIR) the editor's state is kept in
+/// between runs.
+pub const SESSION_FILE: &str = ".session.ron";
+
+#[derive(Error, Debug)]
+pub enum SessionStateError {
+    #[error(transparent)]
+    Syntax(#[from] SpannedError),
+    #[error(transparent)]
+    Serialize(#[from] RonError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// What the editor was last looking at, reopened the next time it starts without a map to open.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct SessionState {
+    /// Asset path of the map that was open, if it was saved anywhere.
+    pub path: Option<String>,
+    pub camera: Option<CameraState>,
+    pub layer: u32,
+}
+
+/// The orbiting camera's framing. A flying camera is kept as the orbit it will return to.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct CameraState {
+    pub transform: Transform,
+    pub focus: Vec3,
+    pub scale: f32,
+}
+
+impl CameraState {
+    /// The camera rig and orthographic scale this state was saved from.
+    #[inline]
+    pub fn camera(self) -> (EditorCamera, f32) {
+        let mut camera = EditorCamera::looking_at(self.transform.translation, self.focus);
+        camera.keep_view = true;
+        (camera, self.scale)
+    }
+}
+
+impl SessionState {
+    pub fn load(root: &Path) -> Result<Self, SessionStateError> {
+        Ok(ron::from_str(&fs::read_to_string(maps_dir(root).join(SESSION_FILE))?)?)
+    }
+
+    pub fn save(&self, root: &Path) -> Result<(), SessionStateError> {
+        let dir = maps_dir(root);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(SESSION_FILE), ron::ser::to_string_pretty(self, default())?)?;
+        Ok(())
+    }
+
+    /// The map to reopen, if its file is still there in the asset directory `root`.
+    #[inline]
+    pub fn reopened(&self, root: &Path) -> Option<&str> {
+        self.path.as_deref().filter(|path| root.join(path).is_file())
+    }
+}
+
+pub fn save_session_state(
+    root: Res<AssetRoot>,
+    session: Res<EditorSession>,
+    layer: Res<ActiveLayer>,
+    cameras: Query<(&EditorCamera, &Projection)>,
+) {
+    let camera = cameras.get_single().ok().map(|(camera, projection)| {
+        let (transform, scale) = match (&camera.mode, projection) {
+            (CameraMode::Fly { ortho, .. }, _) => {
+                let mut orbit = camera.clone();
+                orbit.mode = CameraMode::Orbit;
+                (orbit.transform(), ortho.scale)
+            }
+            (CameraMode::Orbit, Projection::Orthographic(ortho)) => (camera.transform(), ortho.scale),
+            (CameraMode::Orbit, ..) => (camera.transform(), 1.0),
+        };
+
+        CameraState {
+            transform,
+            focus: camera.focus,
+            scale,
+        }
+    });
+
+    let state = SessionState {
+        path: session.path.clone(),
+        camera,
+        layer: **layer,
+    };
+
+    if let Err(e) = state.save(&root) {
+        warn!("Couldn't save the editor session: {e}");
+    }
+}
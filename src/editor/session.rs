@@ -0,0 +1,495 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    asset::LoadState,
+    prelude::*,
+    tasks::{block_on, poll_once, IoTaskPool, Task},
+    window::PrimaryWindow,
+};
+
+use crate::{
+    editor::{
+        history::EditorHistory,
+        palette::ActiveTile,
+        prompt::{ActivePrompt, Notice, Prompt, PromptSubmit},
+        resize::ResizeDrag,
+        tools::{
+            select::{EditorSelection, Floating},
+            switch_tool, ToolStatus,
+        },
+        EditorMap, EditorSettings, CLOSE_EDITOR,
+    },
+    map::{loader::MapFile, Map},
+    AssetRoot, EditorState, EditorUi, GameState,
+};
+
+pub struct SessionPlugin;
+impl Plugin for SessionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditorSession>()
+            .register_type::<EditorSession>()
+            .add_event::<SessionRequest>()
+            .add_systems(CLOSE_EDITOR, reset_window_title)
+            .add_systems(Update, return_to_menu.before(switch_tool).run_if(in_state(EditorState::Editing)))
+            .add_systems(
+                Update,
+                (
+                    session_shortcuts.run_if(in_state(EditorState::Editing)),
+                    answer_prompts,
+                    save_map,
+                    replace_map,
+                    watch_opened_map,
+                    update_window_title,
+                )
+                    .chain()
+                    .run_if(in_state(EditorUi)),
+            );
+    }
+}
+
+/// The directory under the asset root that maps are saved to and opened from.
+pub const MAPS_DIR: &str = "maps";
+/// The window title outside of any map.
+pub const TITLE: &str = "Mnemonic";
+/// How long a second press has to confirm discarding unsaved changes, in seconds.
+pub const CONFIRM_WINDOW: f64 = 3.0;
+
+/// The file behind the editor map, and whether it has unsaved changes.
+#[derive(Resource, Reflect, Clone, Default, Debug)]
+#[reflect(Resource)]
+pub struct EditorSession {
+    /// Asset path of the file the map was opened from or last saved to.
+    pub path: Option<String>,
+    /// Bumped on every change to the map.
+    pub revision: u64,
+    /// The revision last written to `path`.
+    pub saved_revision: u64,
+    /// A request waiting on a second press to discard unsaved changes, and when it was made.
+    #[reflect(ignore)]
+    pub confirm: Option<(&'static str, f64)>,
+    /// A map being opened, watched so failures can be reported.
+    pub opening: Option<Handle<Map>>,
+    /// The map as loaded from `path`. Edits go to a copy of it, so changes to the file on disk can
+    /// be told apart from the editor's own.
+    pub source: Option<Handle<Map>>,
+    /// What the editor last wrote to disk, so the file changing to it isn't taken for an outside
+    /// edit.
+    #[reflect(ignore)]
+    pub written: Option<MapFile>,
+    /// What to open once the editor starts.
+    #[reflect(ignore)]
+    pub pending: Option<SessionRequest>,
+}
+
+impl EditorSession {
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.revision != self.saved_revision
+    }
+
+    /// Records a change to the map, making it dirty.
+    #[inline]
+    pub fn mark_changed(&mut self) {
+        self.revision += 1;
+    }
+
+    /// Records that `revision` was written to `path`, which clears the dirty flag unless the map
+    /// changed since.
+    #[inline]
+    pub fn mark_saved(&mut self, path: String, revision: u64) {
+        self.path = Some(path);
+        self.saved_revision = revision;
+    }
+
+    /// The file name shown for the map, with an asterisk if it has unsaved changes.
+    pub fn title(&self) -> String {
+        let name = self
+            .path
+            .as_deref()
+            .and_then(|path| path.rsplit('/').next())
+            .unwrap_or("untitled");
+
+        match self.is_dirty() {
+            true => format!("{name}*"),
+            false => name.into(),
+        }
+    }
+
+    /// Whether a request may throw away unsaved changes. Asks for the request to be repeated first
+    /// if there are any.
+    pub fn confirm_discard(&mut self, request: &'static str, now: f64, notice: &mut Notice) -> bool {
+        if !self.is_dirty() {
+            return true
+        }
+
+        match self.confirm.take() {
+            Some((confirmed, at)) if confirmed == request && now - at <= CONFIRM_WINDOW => true,
+            _ => {
+                self.confirm = Some((request, now));
+                notice.show("Unsaved changes; press again to discard them.");
+                false
+            }
+        }
+    }
+}
+
+#[derive(Event, Clone, Debug)]
+pub enum SessionRequest {
+    /// Saves the editor map to an asset path.
+    Save(String),
+    /// Replaces the editor map with the one at an asset path.
+    Open(String),
+    /// Replaces the editor map with an empty one of the given size.
+    New(UVec3),
+    /// Replaces the editor map with an autosave at an asset path, keeping the file it belongs to.
+    Recover(String),
+}
+
+/// The directory on disk maps are saved into, under the asset directory `root`.
+#[inline]
+pub fn maps_dir(root: &Path) -> PathBuf {
+    root.join(MAPS_DIR)
+}
+
+/// Every map file in [`MAPS_DIR`] under the asset directory `root` as asset paths, sorted.
+pub fn list_maps(root: &Path) -> io::Result<Vec<String>> {
+    let mut maps = fs::read_dir(maps_dir(root))?
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            name.ends_with(".mnmap").then(|| format!("{MAPS_DIR}/{name}"))
+        })
+        .collect::<Vec<_>>();
+
+    maps.sort_unstable();
+    Ok(maps)
+}
+
+/// Turns a typed file name into an asset path in [`MAPS_DIR`], or `None` if it isn't a plain file
+/// name.
+pub fn map_path(name: &str) -> Option<String> {
+    let name = name.trim();
+    let name = name.strip_suffix(".mnmap").unwrap_or(name);
+    (!name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ' ')))
+        .then(|| format!("{MAPS_DIR}/{name}.mnmap"))
+}
+
+pub fn session_shortcuts(
+    time: Res<Time>,
+    root: Res<AssetRoot>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut session: ResMut<EditorSession>,
+    mut prompt: ResMut<ActivePrompt>,
+    mut notice: ResMut<Notice>,
+    mut requests: EventWriter<SessionRequest>,
+) {
+    if prompt.is_some() || !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return
+    }
+
+    let now = time.elapsed_seconds_f64();
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if keys.just_pressed(KeyCode::KeyS) {
+        match session.path.clone() {
+            Some(path) if !shift => {
+                requests.send(SessionRequest::Save(path));
+            }
+            path => {
+                let name = match path {
+                    Some(path) => path
+                        .trim_start_matches(&format!("{MAPS_DIR}/"))
+                        .trim_end_matches(".mnmap")
+                        .into(),
+                    None => format!(
+                        "map-{}",
+                        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs())
+                    ),
+                };
+
+                **prompt = Some(Prompt::text("save-as", "Save as", name));
+            }
+        }
+    } else if keys.just_pressed(KeyCode::KeyO) && session.confirm_discard("open", now, &mut notice) {
+        match list_maps(&root) {
+            Ok(maps) if !maps.is_empty() => **prompt = Some(Prompt::choice("open", "Open map", maps)),
+            Ok(..) => notice.show(format!("No maps in `{MAPS_DIR}/`.")),
+            Err(e) => notice.show(format!("Couldn't list maps: {e}")),
+        }
+    } else if keys.just_pressed(KeyCode::KeyN) && session.confirm_discard("new", now, &mut notice) {
+        **prompt = Some(Prompt::text("new", "New map size (x y z)", "16 4 16"));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn return_to_menu(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    selection: Res<EditorSelection>,
+    floating: Res<Floating>,
+    status: Res<ToolStatus>,
+    resize: Res<ResizeDrag>,
+    mut session: ResMut<EditorSession>,
+    mut notice: ResMut<Notice>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // Escape first backs out of whatever the active tool is doing.
+    let busy = !selection.cells.is_empty() ||
+        !selection.props.is_empty() ||
+        floating.is_some() ||
+        !status.0.is_empty() ||
+        resize.0.is_some();
+    if !keys.just_pressed(KeyCode::Escape) || busy {
+        return
+    }
+
+    if session.confirm_discard("menu", time.elapsed_seconds_f64(), &mut notice) {
+        next_state.set(GameState::Menu);
+    }
+}
+
+pub fn answer_prompts(
+    mut answers: EventReader<PromptSubmit>,
+    mut notice: ResMut<Notice>,
+    mut requests: EventWriter<SessionRequest>,
+) {
+    for answer in answers.read() {
+        match answer.id {
+            "save-as" => match map_path(&answer.value) {
+                Some(path) => {
+                    requests.send(SessionRequest::Save(path));
+                }
+                None => notice.show(format!("Invalid map name `{}`.", answer.value)),
+            },
+            "open" => {
+                requests.send(SessionRequest::Open(answer.value.clone()));
+            }
+            "new" => {
+                let size = answer
+                    .value
+                    .split_whitespace()
+                    .map(str::parse::<u32>)
+                    .collect::<Result<Vec<_>, _>>()
+                    .ok()
+                    .and_then(|size| <[u32; 3]>::try_from(size).ok())
+                    .map(UVec3::from_array)
+                    .filter(|size| size.cmpge(UVec3::ONE).all() && size.cmple(UVec3::splat(256)).all());
+
+                match size {
+                    Some(size) => {
+                        requests.send(SessionRequest::New(size));
+                    }
+                    None => notice.show("Map size must be three numbers from 1 to 256."),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// An in-flight write of a map file, with the asset path and revision it saves.
+pub struct SaveTask(Task<io::Result<()>>, String, u64);
+
+#[allow(clippy::too_many_arguments)]
+pub fn save_map(
+    mut requests: EventReader<SessionRequest>,
+    settings: Res<EditorSettings>,
+    root: Res<AssetRoot>,
+    server: Res<AssetServer>,
+    mut session: ResMut<EditorSession>,
+    mut notice: ResMut<Notice>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    maps: Res<Assets<Map>>,
+    mut task: Local<Option<SaveTask>>,
+) {
+    if let Some(SaveTask(pending, path, revision)) = task.as_mut() {
+        if let Some(result) = block_on(poll_once(pending)) {
+            match result {
+                Ok(()) => {
+                    notice.show(format!("Saved `{path}`."));
+                    session.mark_saved(path.clone(), *revision);
+                    session.source = Some(server.load(path.clone()));
+                }
+                Err(e) => notice.show(format!("Couldn't save `{path}`: {e}")),
+            }
+
+            *task = None;
+        }
+    }
+
+    for request in requests.read() {
+        let SessionRequest::Save(path) = request else { continue };
+        if task.is_some() {
+            notice.show("Still saving; try again in a moment.");
+            continue
+        }
+
+        let Some(map) = editor_maps.get_single().ok().and_then(|map| maps.get(map)) else {
+            continue
+        };
+
+        // Lighting is edited live in the settings rather than on the map asset.
+        let file = MapFile {
+            lighting: settings.lighting,
+            ..MapFile::from(map)
+        };
+
+        let ron = match file.to_ron() {
+            Ok(ron) => ron,
+            Err(e) => {
+                notice.show(format!("Couldn't save `{path}`: {e}"));
+                continue
+            }
+        };
+
+        session.written = Some(file);
+        let file = root.join(path);
+        *task = Some(SaveTask(
+            IoTaskPool::get().spawn(async move {
+                if let Some(dir) = file.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+
+                fs::write(file, ron)
+            }),
+            path.clone(),
+            session.revision,
+        ));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn replace_map(
+    mut requests: EventReader<SessionRequest>,
+    server: Res<AssetServer>,
+    mut session: ResMut<EditorSession>,
+    mut history: ResMut<EditorHistory>,
+    mut active: ResMut<ActiveTile>,
+    mut selection: ResMut<EditorSelection>,
+    mut floating: ResMut<Floating>,
+    mut commands: Commands,
+    mut editor_maps: Query<(Entity, &mut Handle<Map>, &mut Transform), With<EditorMap>>,
+    mut maps: ResMut<Assets<Map>>,
+) {
+    for request in requests.read() {
+        let Ok((e, mut handle, mut trns)) = editor_maps.get_single_mut() else {
+            return
+        };
+        let new_handle = match request {
+            SessionRequest::Save(..) => continue,
+            SessionRequest::Open(path) | SessionRequest::Recover(path) => {
+                let reload = server.get_handle::<Map>(path).is_some();
+                let new_handle = server.load::<Map>(path);
+                if reload {
+                    server.reload(path);
+                }
+
+                if let SessionRequest::Open(..) = request {
+                    session.path = Some(path.clone());
+                }
+
+                session.opening = Some(new_handle.clone());
+                session.source = None;
+                new_handle
+            }
+            &SessionRequest::New(size) => {
+                let Some(map) = maps.get(&*handle) else { continue };
+                let new_map = Map {
+                    tile_set: map.tile_set.clone(),
+                    tile_handles: map.tile_handles.clone(),
+                    tile_size: map.tile_size,
+                    ..Map::empty(size)
+                };
+
+                session.path = None;
+                session.opening = None;
+                session.source = None;
+                maps.add(new_map)
+            }
+        };
+
+        // Tile set indices differ between maps, so keep the active tile by path.
+        if let Some(path) = maps.get(&*handle).and_then(|map| active.path(map)) {
+            *active = ActiveTile::Path(path.into());
+        }
+
+        *handle = new_handle;
+        *trns = Transform::IDENTITY;
+        commands.entity(e).remove::<Handle<Mesh>>();
+
+        session.revision = 0;
+        session.saved_revision = 0;
+        session.confirm = None;
+        // Recovered changes were never written to the map's own file.
+        if let SessionRequest::Recover(..) = request {
+            session.mark_changed();
+        }
+
+        history.clear();
+        selection.set_if_neq(default());
+        **floating = None;
+    }
+}
+
+pub fn watch_opened_map(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    mut events: EventReader<AssetEvent<Map>>,
+    mut session: ResMut<EditorSession>,
+    mut notice: ResMut<Notice>,
+    mut maps: ResMut<Assets<Map>>,
+    mut editor_maps: Query<(Entity, &mut Handle<Map>), With<EditorMap>>,
+) {
+    let Some(handle) = session.opening.clone() else {
+        events.clear();
+        return
+    };
+
+    // Reopened maps are reloaded, so wait for the load to finish rather than trusting the load state.
+    if events
+        .read()
+        .fold(false, |loaded, e| loaded | e.is_loaded_with_dependencies(&handle))
+    {
+        // Edits go to a copy, leaving the loaded map to follow the file on disk.
+        if let (Some(map), Ok((e, mut editor_handle))) = (maps.get(&handle).cloned(), editor_maps.get_single_mut()) {
+            if *editor_handle == handle {
+                *editor_handle = maps.add(map);
+                commands.entity(e).remove::<Handle<Mesh>>();
+            }
+        }
+
+        // Recovered autosaves aren't the map's own file, so there's nothing to follow.
+        let own_file = server.get_path(&handle).map(|path| path.to_string()) == session.path;
+        session.source = own_file.then(|| handle.clone());
+
+        notice.show(format!("Opened `{}`.", session.path.as_deref().unwrap_or_default()));
+        session.opening = None;
+        return
+    }
+
+    if let Some(LoadState::Failed(e)) = server.get_load_state(&handle) {
+        notice.show(format!(
+            "Couldn't open `{}`: {e}",
+            session.path.as_deref().unwrap_or_default()
+        ));
+        session.opening = None;
+        session.path = None;
+    }
+}
+
+pub fn update_window_title(session: Res<EditorSession>, mut window: Query<&mut Window, With<PrimaryWindow>>) {
+    let Ok(mut window) = window.get_single_mut() else { return };
+    let title = format!("{TITLE} — {}", session.title());
+    if window.title != title {
+        window.title = title;
+    }
+}
+
+pub fn reset_window_title(mut window: Query<&mut Window, With<PrimaryWindow>>) {
+    if let Ok(mut window) = window.get_single_mut() {
+        window.title = TITLE.into();
+    }
+}
@@ -0,0 +1,150 @@
+use std::fmt::Write;
+
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+
+use crate::{
+    editor::{
+        readout::hovered_cell,
+        tools::{CursorTarget, ToolMode},
+        EditorMap, OPEN_EDITOR,
+    },
+    map::{Map, MapStats},
+    playtest::ColliderStats,
+    GameState,
+};
+
+pub struct StatusPlugin;
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StatusSettings>()
+            .add_systems(OPEN_EDITOR, init_status_bar)
+            .add_systems(
+                Update,
+                update_status_bar.run_if(in_state(GameState::Editor)),
+            );
+    }
+}
+
+#[derive(Resource, Copy, Clone)]
+pub struct StatusSettings {
+    /// Seconds between refreshes of the status bar.
+    pub refresh: f32,
+}
+
+impl Default for StatusSettings {
+    #[inline]
+    fn default() -> Self {
+        Self { refresh: 0.25 }
+    }
+}
+
+/// Marks the status bar, which stays up while playtesting to show what the map's colliders cost.
+#[derive(Component, Copy, Clone, Default)]
+pub struct StatusBar;
+
+/// Meshes rebuilt since the status bar last refreshed, and when that was.
+#[derive(Default)]
+pub struct StatusCounters {
+    pub rebuilt: usize,
+    pub elapsed: f32,
+}
+
+pub fn init_status_bar(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section("", TextStyle {
+            font_size: 16.0,
+            color: Color::srgb(0.8, 0.8, 0.8),
+            ..default()
+        })
+        .with_text_justify(JustifyText::Right)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(8.0),
+            bottom: Val::Px(8.0),
+            ..default()
+        }),
+        StatusBar,
+        StateScoped(GameState::Editor),
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_status_bar(
+    time: Res<Time>,
+    settings: Res<StatusSettings>,
+    diagnostics: Res<DiagnosticsStore>,
+    stats: Res<MapStats>,
+    colliders: Res<ColliderStats>,
+    target: Res<CursorTarget>,
+    // Only there in the editor, not while playtesting.
+    mode: Option<Res<State<ToolMode>>>,
+    maps: Res<Assets<Map>>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    mut texts: Query<&mut Text, With<StatusBar>>,
+    mut counters: Local<StatusCounters>,
+) {
+    counters.rebuilt += stats.rebuilt;
+    counters.elapsed += time.delta_seconds();
+    if counters.elapsed < settings.refresh {
+        return
+    }
+
+    let measure = |diagnostic| {
+        diagnostics
+            .get(&diagnostic)
+            .and_then(|diagnostic| diagnostic.smoothed())
+            .unwrap_or_default()
+    };
+
+    let mut text = format!(
+        "{:.0} FPS, {:.2} ms",
+        measure(FrameTimeDiagnosticsPlugin::FPS),
+        measure(FrameTimeDiagnosticsPlugin::FRAME_TIME)
+    );
+
+    if let Some((id, map)) = editor_maps
+        .get_single()
+        .ok()
+        .and_then(|handle| Some((handle.id(), maps.get(handle)?)))
+    {
+        let mesh = stats.meshes.get(&id).copied().unwrap_or_default();
+        let _ = write!(
+            text,
+            " | {} tiles, {} vertices, {} triangles",
+            map.tiles.iter().flatten().count(),
+            mesh.vertices,
+            mesh.triangles
+        );
+    }
+
+    let _ = write!(
+        text,
+        " | rebuilt {} last frame, {} in {:.2}s",
+        stats.rebuilt, counters.rebuilt, counters.elapsed
+    );
+
+    if colliders.cost.shapes > 0 {
+        let _ = write!(
+            text,
+            " | {} collider shapes, {} triangles, built in {:.2} ms",
+            colliders.cost.shapes,
+            colliders.cost.triangles,
+            colliders.last_build.as_secs_f64() * 1000.0
+        );
+    }
+
+    if let Some(mode) = mode {
+        let _ = write!(text, " | {}", mode.name());
+        if let Some(cell) = hovered_cell(&target) {
+            let _ = write!(text, " at {}, {}, {}", cell.x, cell.y, cell.z);
+        }
+    }
+
+    *counters = default();
+    for mut value in &mut texts {
+        value.sections[0].value.clone_from(&text);
+    }
+}
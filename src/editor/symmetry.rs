@@ -0,0 +1,203 @@
+use bevy::{color::palettes::css, prelude::*};
+
+use crate::{
+    editor::{layer::ActiveLayer, EditorMap, EditorSettings},
+    map::{diff::MapDiff, Map, MapCell},
+    EditorUi,
+};
+
+pub struct SymmetryPlugin;
+impl Plugin for SymmetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (toggle_symmetry, draw_symmetry).chain().run_if(in_state(EditorUi)),
+        );
+    }
+}
+
+#[derive(Reflect, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SymmetryMode {
+    MirrorX,
+    MirrorZ,
+    MirrorXZ,
+    Rotational180,
+}
+
+impl SymmetryMode {
+    /// The order the symmetry toggle cycles through, starting after no symmetry.
+    pub const CYCLE: [Self; 4] = [Self::MirrorX, Self::MirrorZ, Self::MirrorXZ, Self::Rotational180];
+
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::MirrorX => "mirror X",
+            Self::MirrorZ => "mirror Z",
+            Self::MirrorXZ => "mirror XZ",
+            Self::Rotational180 => "rotate 180°",
+        }
+    }
+
+    /// Every reflection an edit is repeated under, starting with the edit itself.
+    pub fn reflections(self, anchor: Vec2) -> Vec<Reflection> {
+        let reflection = |mirror_x, mirror_z| Reflection {
+            mirror_x,
+            mirror_z,
+            anchor,
+        };
+
+        match self {
+            Self::MirrorX => vec![Reflection::IDENTITY, reflection(true, false)],
+            Self::MirrorZ => vec![Reflection::IDENTITY, reflection(false, true)],
+            Self::MirrorXZ => vec![
+                Reflection::IDENTITY,
+                reflection(true, false),
+                reflection(false, true),
+                reflection(true, true),
+            ],
+            Self::Rotational180 => vec![Reflection::IDENTITY, reflection(true, true)],
+        }
+    }
+}
+
+/// Mirrors cells across vertical planes through `anchor`, given in cell coordinates on the XZ
+/// plane. Mirroring across both is a half turn about the anchor.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Reflection {
+    pub mirror_x: bool,
+    pub mirror_z: bool,
+    pub anchor: Vec2,
+}
+
+impl Reflection {
+    pub const IDENTITY: Self = Self {
+        mirror_x: false,
+        mirror_z: false,
+        anchor: Vec2::ZERO,
+    };
+
+    #[inline]
+    pub fn cell(self, cell: IVec3) -> IVec3 {
+        let mirror = |c: i32, anchor: f32| (2.0 * anchor - c as f32).round() as i32;
+        IVec3::new(
+            if self.mirror_x {
+                mirror(cell.x, self.anchor.x)
+            } else {
+                cell.x
+            },
+            cell.y,
+            if self.mirror_z {
+                mirror(cell.z, self.anchor.y)
+            } else {
+                cell.z
+            },
+        )
+    }
+
+    /// Reflects a region given by an inclusive minimum and exclusive maximum.
+    #[inline]
+    pub fn region(self, min: IVec3, max: IVec3) -> (IVec3, IVec3) {
+        let (a, b) = (self.cell(min), self.cell(max - 1));
+        (a.min(b), a.max(b) + 1)
+    }
+
+    /// Reflects a cell's contents, turning its orientation so the tile looks mirrored too.
+    #[inline]
+    pub fn value(self, mut value: MapCell) -> MapCell {
+        if self.mirror_x {
+            value.orientation = value.orientation.mirrored_x();
+        }
+
+        if self.mirror_z {
+            value.orientation = value.orientation.mirrored_z();
+        }
+
+        value
+    }
+}
+
+/// The reflections tools repeat their edits under for the current symmetry setting, which is just
+/// the identity when symmetry is off.
+pub fn reflections(settings: &EditorSettings, map: &Map) -> Vec<Reflection> {
+    match settings.symmetry {
+        Some(mode) => mode.reflections(symmetry_anchor(settings, map)),
+        None => vec![Reflection::IDENTITY],
+    }
+}
+
+/// Repeats an edit under every reflection, gathering the changes into one diff so they're undone
+/// together.
+pub fn edit_reflected(
+    map: &mut Map,
+    reflections: &[Reflection],
+    mut edit: impl FnMut(&mut Map, Reflection) -> MapDiff,
+) -> MapDiff {
+    let mut diff = MapDiff::default();
+    for &reflection in reflections {
+        diff.extend(edit(map, reflection));
+    }
+
+    diff
+}
+
+/// The configured symmetry anchor, or the map's center.
+#[inline]
+pub fn symmetry_anchor(settings: &EditorSettings, map: &Map) -> Vec2 {
+    settings
+        .symmetry_anchor
+        .unwrap_or_else(|| (map.size.xz().as_vec2() - 1.0) / 2.0)
+}
+
+pub fn toggle_symmetry(keys: Res<ButtonInput<KeyCode>>, mut settings: ResMut<EditorSettings>) {
+    if !keys.just_pressed(settings.symmetry_toggle) || keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return
+    }
+
+    settings.symmetry = match settings.symmetry {
+        None => Some(SymmetryMode::CYCLE[0]),
+        Some(mode) => SymmetryMode::CYCLE
+            .iter()
+            .position(|&other| other == mode)
+            .and_then(|i| SymmetryMode::CYCLE.get(i + 1).copied()),
+    };
+}
+
+pub fn draw_symmetry(
+    settings: Res<EditorSettings>,
+    layer: Res<ActiveLayer>,
+    maps: Res<Assets<Map>>,
+    editor_maps: Query<(&Handle<Map>, &GlobalTransform), With<EditorMap>>,
+    mut gizmos: Gizmos,
+) {
+    let Some(mode) = settings.symmetry else { return };
+    let Ok((handle, &map_trns)) = editor_maps.get_single() else {
+        return
+    };
+    let Some(map) = maps.get(handle) else { return };
+
+    let anchor = symmetry_anchor(&settings, map) * map.tile_size.xz();
+    let (min, max) = (map.cell_min(IVec3::ZERO), map.cell_min(map.size.as_ivec3()));
+    let y = min.y + **layer as f32 * map.tile_size.y;
+
+    let mut line =
+        |from: Vec3, to: Vec3| gizmos.line(map_trns.transform_point(from), map_trns.transform_point(to), css::FUCHSIA);
+
+    match mode {
+        SymmetryMode::MirrorX => line(Vec3::new(anchor.x, y, min.z), Vec3::new(anchor.x, y, max.z)),
+        SymmetryMode::MirrorZ => line(Vec3::new(min.x, y, anchor.y), Vec3::new(max.x, y, anchor.y)),
+        SymmetryMode::MirrorXZ => {
+            line(Vec3::new(anchor.x, y, min.z), Vec3::new(anchor.x, y, max.z));
+            line(Vec3::new(min.x, y, anchor.y), Vec3::new(max.x, y, anchor.y));
+        }
+        SymmetryMode::Rotational180 => {
+            let center = Vec3::new(anchor.x, y, anchor.y);
+            let arm = map.tile_size * Vec3::new(0.5, 0.0, 0.5);
+
+            line(center - arm, center + arm);
+            line(
+                center - arm * Vec3::new(1.0, 0.0, -1.0),
+                center + arm * Vec3::new(1.0, 0.0, -1.0),
+            );
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use bevy::{color::palettes::css, input::mouse::MouseWheel, prelude::*};
+
+use crate::{
+    content::Tiles,
+    editor::{
+        group::ActiveGroup,
+        history::MapCommands,
+        palette::{ActiveTile, PlacementRotation},
+        symmetry::{edit_reflected, reflections},
+        tools::{
+            boxing, draw_region, navigating, previewed_cell, scrolled_lines, written_paint, CursorTarget, Paint, RegionDrag,
+            ToolStatus,
+        },
+        EditorMap, EditorSettings,
+    },
+    map::{Map, MapCell},
+};
+
+#[allow(clippy::too_many_arguments)]
+pub fn rect_fill(
+    settings: Res<EditorSettings>,
+    tiles: Res<Tiles>,
+    target: Res<CursorTarget>,
+    mut active: ResMut<ActiveTile>,
+    group: Res<ActiveGroup>,
+    rotation: Res<PlacementRotation>,
+    mut status: ResMut<ToolStatus>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    editor_maps: Query<&GlobalTransform, With<EditorMap>>,
+    mut commands: MapCommands,
+    mut gizmos: Gizmos,
+    mut drag: Local<Option<RegionDrag>>,
+) {
+    let scroll = scrolled_lines(&mut wheel);
+    if keys.just_pressed(KeyCode::Escape) {
+        *drag = None;
+    }
+
+    let (Ok(map_trns), Some(map)) = (editor_maps.get_single(), commands.map()) else {
+        *drag = None;
+        return status.set("")
+    };
+
+    if drag.is_none() && !navigating(&keys) {
+        *drag = [MouseButton::Left, MouseButton::Right]
+            .into_iter()
+            .find(|&button| mouse.just_pressed(button))
+            .and_then(|button| Some(RegionDrag::new(target.click_cell(button == MouseButton::Left)?, button)));
+    }
+
+    let Some(pending) = drag.as_mut() else { return status.set("") };
+    let boxed = boxing(&keys);
+    pending.update(map, &target, if boxed { scroll } else { 0.0 });
+
+    let pending = *pending;
+    let place = pending.button == MouseButton::Left;
+    let region @ (min, max) = pending.region(boxed);
+
+    if !mouse.pressed(pending.button) {
+        *drag = None;
+        status.set("");
+
+        let Some(paint) = written_paint(place, &mut active, &group, *rotation, &mut commands, &tiles) else {
+            return
+        };
+
+        let map = commands.map().unwrap();
+        let reflections = reflections(&settings, map);
+        if reflections.iter().any(|&reflection| {
+            let (min, max) = reflection.region(min, max);
+            region_changes(map, min, max, |cell| paint.reflected(reflection, cell)) > 0
+        }) {
+            commands.edit(false, |map| {
+                edit_reflected(map, &reflections, |map, reflection| {
+                    let (min, max) = reflection.region(min, max);
+                    map.fill_region_with(min, max, |cell| paint.reflected(reflection, cell))
+                })
+            });
+        }
+
+        return
+    }
+
+    // The active tile may not be in the tile set yet, in which case every filled cell changes. Brush
+    // groups are counted the same way, since their picks aren't known before painting.
+    let count = match previewed_cell(place, &active, *rotation, map).filter(|_| !(place && group.enabled)) {
+        Some(value) => region_changes(map, min, max, |_| value),
+        None => {
+            let (min, max) = (min.max(IVec3::ZERO), max.min(map.size.as_ivec3()));
+            (max - min).max(IVec3::ZERO).element_product() as usize
+        }
+    };
+
+    draw_region(&mut gizmos, map, map_trns, region, if place { css::LIME } else { css::RED });
+
+    let size = max - min;
+    status.set(format!(
+        "{} {}x{}x{}: {count} cell{}",
+        if place { "fill" } else { "clear" },
+        size.x,
+        size.y,
+        size.z,
+        if count == 1 { "" } else { "s" },
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn flood_fill(
+    settings: Res<EditorSettings>,
+    tiles: Res<Tiles>,
+    target: Res<CursorTarget>,
+    mut active: ResMut<ActiveTile>,
+    group: Res<ActiveGroup>,
+    rotation: Res<PlacementRotation>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut commands: MapCommands,
+) {
+    let place = match (mouse.just_pressed(MouseButton::Left), mouse.just_pressed(MouseButton::Right)) {
+        (true, false) => true,
+        (false, true) => false,
+        _ => return,
+    };
+
+    if navigating(&keys) {
+        return
+    }
+
+    // Floods start from the clicked tile, or the empty layer cell when nothing is struck.
+    let Some(start) = target.hit.map(|hit| hit.cell.as_ivec3()).or(target.layer_cell) else {
+        return
+    };
+    let Some(map) = commands.map() else { return };
+    if !map.contains(start) ||
+        (!(place && group.enabled) &&
+            previewed_cell(place, &active, *rotation, map).map(|value| value.tile) == Some(map.get(start.as_uvec3())))
+    {
+        return
+    }
+
+    let reflections = reflections(&settings, map);
+    if let Some(paint) = written_paint(place, &mut active, &group, *rotation, &mut commands, &tiles) {
+        commands.edit(false, |map| {
+            edit_reflected(map, &reflections, |map, reflection| {
+                let start = reflection.cell(start);
+                match (map.contains(start), &paint) {
+                    (false, ..) => default(),
+                    (true, &Paint::Cell(value)) => map.flood_layer(start.as_uvec3(), reflection.value(value)),
+                    (true, paint) => map.flood_layer_with(start.as_uvec3(), |cell| paint.reflected(reflection, cell)),
+                }
+            })
+        });
+    }
+}
+
+/// Counts the in-bounds cells of a region that differ from what each would be given.
+pub fn region_changes(map: &Map, min: IVec3, max: IVec3, value: impl Fn(IVec3) -> MapCell) -> usize {
+    let (min, max) = (min.max(IVec3::ZERO), max.min(map.size.as_ivec3()));
+    let mut count = 0;
+    for z in min.z..max.z {
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                let cell = IVec3::new(x, y, z);
+                count += (map.get_cell(cell.as_uvec3()) != Some(value(cell))) as usize;
+            }
+        }
+    }
+
+    count
+}
@@ -0,0 +1,519 @@
+pub mod fill;
+pub mod paint;
+pub mod pick;
+pub mod select;
+
+use bevy::{
+    core_pipeline::{bloom::BloomSettings, tonemapping::Tonemapping},
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+use bevy_mod_picking::pointer::{PointerButton, PointerId};
+use nonmax::NonMaxU8;
+
+use crate::{
+    content::Tiles,
+    editor::{
+        group::ActiveGroup,
+        history::MapCommands,
+        layer::ActiveLayer,
+        lighting::SUN_KEY,
+        palette::{ActiveTile, PlacementRotation},
+        readout::MEASURE_KEY,
+        resize::drag_resize_handles,
+        symmetry::Reflection,
+        tools::{
+            fill::{flood_fill, rect_fill},
+            paint::paint_tiles,
+            pick::pick_tile,
+            select::{draw_selection, edit_selection, place_floating, select_region, EditorSelection, Floating, SelectDrag},
+        },
+        view::tonemapper_name,
+        EditorMap, EditorSettings, OPEN_EDITOR,
+    },
+    map::{
+        cell_random,
+        orientation::TileOrientation,
+        pick_weighted,
+        picking::{MapPicks, PointerOverUi, TilePointerEvent, TilePointerKind},
+        EditMode, GridHit, Map, MapCell,
+    },
+    EditorState, EditorUi, GameState,
+};
+
+pub struct ToolsPlugin;
+impl Plugin for ToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<ToolMode>()
+            .init_resource::<CursorTarget>()
+            .init_resource::<ToolStatus>()
+            .init_resource::<EditorSelection>()
+            .init_resource::<Floating>()
+            .init_resource::<SelectDrag>()
+            .add_systems(OPEN_EDITOR, init_tool_text)
+            .add_systems(
+                Update,
+                (
+                    (switch_tool.run_if(in_state(EditorState::Editing)), update_cursor_target),
+                    drag_resize_handles.run_if(in_state(EditorState::Editing)),
+                    (
+                        paint_tiles.run_if(in_state(ToolMode::Place).or_else(in_state(ToolMode::Erase))),
+                        flood_fill.run_if(in_state(ToolMode::FloodFill)),
+                        rect_fill.run_if(in_state(ToolMode::RectFill)),
+                        (select_region, edit_selection, place_floating)
+                            .chain()
+                            .run_if(in_state(ToolMode::Select)),
+                        pick_tile.run_if(in_state(ToolMode::Eyedropper)),
+                    )
+                        .run_if(in_state(EditMode::Tile).and_then(in_state(EditorState::Editing))),
+                    (update_tool_text, draw_selection),
+                )
+                    .chain()
+                    .run_if(in_state(EditorUi)),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default, SubStates)]
+#[source(GameState = GameState::Editor)]
+pub enum ToolMode {
+    /// Paints the brush footprint under the cursor while a button is held; the right button erases.
+    #[default]
+    Place,
+    /// Erases the brush footprint under the cursor while a button is held.
+    Erase,
+    /// Replaces the connected region of like cells on the clicked layer.
+    FloodFill,
+    /// Fills the rectangle, or box, between the press and release cells.
+    RectFill,
+    /// Marks a region of cells for later operations.
+    Select,
+    /// Makes the clicked tile the active tile.
+    Eyedropper,
+}
+
+impl ToolMode {
+    pub const KEYS: [(KeyCode, Self); 6] = [
+        (KeyCode::KeyB, Self::Place),
+        (KeyCode::KeyX, Self::Erase),
+        (KeyCode::KeyG, Self::FloodFill),
+        (KeyCode::KeyU, Self::RectFill),
+        (KeyCode::KeyM, Self::Select),
+        (KeyCode::KeyI, Self::Eyedropper),
+    ];
+
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Place => "Place",
+            Self::Erase => "Erase",
+            Self::FloodFill => "Flood Fill",
+            Self::RectFill => "Rect Fill",
+            Self::Select => "Select",
+            Self::Eyedropper => "Eyedropper",
+        }
+    }
+}
+
+/// What the cursor points at in the editor map, computed once per frame for every tool.
+#[derive(Resource, Copy, Clone, Default, Debug)]
+pub struct CursorTarget {
+    /// The pointer ray in the map's local space.
+    pub ray: Option<Ray3d>,
+    /// The first solid cell the ray strikes.
+    pub hit: Option<GridHit>,
+    /// The cell on the layer the ray passes through, which may lie outside the map bounds.
+    pub layer_cell: Option<IVec3>,
+    /// Whether the cursor is over UI taking it, by [`PointerOverUi`], outside of drags that started
+    /// in the map. It then targets nothing in the map.
+    pub over_ui: bool,
+}
+
+impl CursorTarget {
+    /// The cell a fresh click targets: the empty cell in front of the struck face when placing, the
+    /// struck cell itself when erasing, or the layer cell when placing into empty space.
+    #[inline]
+    pub fn click_cell(&self, place: bool) -> Option<IVec3> {
+        match (place, self.hit) {
+            (true, Some(hit)) => (hit.normal != IVec3::ZERO).then(|| hit.cell.as_ivec3() + hit.normal),
+            (false, Some(hit)) => Some(hit.cell.as_ivec3()),
+            (true, None) => self.layer_cell,
+            (false, None) => None,
+        }
+    }
+}
+
+/// A line of feedback from the active tool, shown next to its name.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct ToolStatus(pub String);
+
+impl ToolStatus {
+    #[inline]
+    pub fn set(&mut self, status: impl AsRef<str>) {
+        let status = status.as_ref();
+        if self.0 != status {
+            self.0 = status.into();
+        }
+    }
+}
+
+#[derive(Component, Copy, Clone)]
+pub struct ToolText;
+
+pub fn init_tool_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section("", TextStyle {
+            font_size: 18.0,
+            ..default()
+        })
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(8.0),
+            bottom: Val::Px(32.0),
+            ..default()
+        }),
+        ToolText,
+        StateScoped(GameState::Editor),
+    ));
+}
+
+pub fn switch_tool(
+    keys: Res<ButtonInput<KeyCode>>,
+    mode: Res<State<ToolMode>>,
+    mut next: ResMut<NextState<ToolMode>>,
+    mut status: ResMut<ToolStatus>,
+) {
+    // Control combinations are left to other editor bindings.
+    if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return
+    }
+
+    if let Some(&(.., tool)) = ToolMode::KEYS.iter().find(|&&(key, ..)| keys.just_pressed(key)) {
+        if **mode != tool {
+            next.set(tool);
+            status.set("");
+        }
+    }
+}
+
+/// Takes the mouse's ray through the editor map from the picking stack, unless the mouse is over UI.
+/// Drags that started in the map carry on over UI.
+#[allow(clippy::too_many_arguments)]
+pub fn update_cursor_target(
+    picks: Res<MapPicks>,
+    pointer_over_ui: Res<PointerOverUi>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    editor_maps: Query<(Entity, &Handle<Map>), With<EditorMap>>,
+    maps: Res<Assets<Map>>,
+    layer: Res<ActiveLayer>,
+    mut target: ResMut<CursorTarget>,
+    mut dragging: Local<bool>,
+) {
+    let over_ui = pointer_over_ui.over(PointerId::Mouse);
+    let buttons = [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+    if !mouse.any_pressed(buttons) {
+        *dragging = false;
+    } else if mouse.any_just_pressed(buttons) {
+        *dragging = !over_ui;
+    }
+
+    let over_ui = over_ui && !*dragging;
+    let mut new_target = CursorTarget { over_ui, ..default() };
+
+    if let (false, Ok((e, handle))) = (over_ui, editor_maps.get_single()) {
+        if let (Some(map), Some(pick)) = (maps.get(handle), picks.get(&(PointerId::Mouse, e))) {
+            new_target = CursorTarget {
+                ray: Some(pick.ray),
+                hit: pick.hit,
+                layer_cell: map.layer_cell(pick.ray, **layer as i32),
+                over_ui,
+            };
+        }
+    }
+
+    *target = new_target;
+}
+
+pub fn update_tool_text(
+    edit_mode: Res<State<EditMode>>,
+    mode: Res<State<ToolMode>>,
+    status: Res<ToolStatus>,
+    settings: Res<EditorSettings>,
+    mut texts: Query<&mut Text, With<ToolText>>,
+) {
+    if !edit_mode.is_changed() && !mode.is_changed() && !status.is_changed() && !settings.is_changed() {
+        return
+    }
+
+    let name = match **edit_mode {
+        EditMode::Tile => mode.name(),
+        EditMode::Object => "Object",
+    };
+    let mut text = match status.0.is_empty() {
+        true => format!("Tool: {name}"),
+        false => format!("Tool: {name} ({})", status.0),
+    };
+
+    let bloom = match settings.bloom {
+        false => Some("no bloom".into()),
+        true => (settings.bloom_intensity != BloomSettings::NATURAL.intensity)
+            .then(|| format!("bloom {:.2}", settings.bloom_intensity)),
+    };
+
+    let views = [
+        settings.wireframe.then(|| "wireframe".into()),
+        settings.fullbright.then(|| "fullbright".into()),
+        settings.physics_debug.then(|| "physics debug".into()),
+        settings.symmetry.map(|mode| mode.name().into()),
+        (settings.tonemapping != Tonemapping::None).then(|| tonemapper_name(settings.tonemapping).into()),
+        bloom,
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<String>>();
+    if !views.is_empty() {
+        text += &format!(" [{}]", views.join(", "));
+    }
+
+    for mut value in &mut texts {
+        value.sections[0].value.clone_from(&text);
+    }
+}
+
+/// The cell a tool writes: the active tile in the placement orientation when placing, or an
+/// empty cell when erasing. Adds the active tile to the map's tile set if needed; returns `None` if
+/// it can't be.
+pub fn written_cell(
+    place: bool,
+    active: &mut ActiveTile,
+    rotation: PlacementRotation,
+    commands: &mut MapCommands,
+    tiles: &Tiles,
+) -> Option<MapCell> {
+    let tile = match (place, &*active) {
+        (false, ..) => return Some(MapCell::EMPTY),
+        (true, &ActiveTile::Index(index)) => index,
+        (true, ActiveTile::Path(..)) => active.resolve(commands.map_mut_untracked()?, tiles)?,
+    };
+
+    Some(MapCell::new(Some(tile), *rotation))
+}
+
+/// What a tool writes into each cell it edits.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Paint {
+    Cell(MapCell),
+    /// A member of the active brush group per cell, picked by the map seed and the cell
+    /// coordinate.
+    Group {
+        tiles: Vec<(NonMaxU8, f32)>,
+        orientation: TileOrientation,
+        seed: u64,
+    },
+}
+
+impl Paint {
+    #[inline]
+    pub fn at(&self, cell: IVec3) -> MapCell {
+        match self {
+            &Self::Cell(value) => value,
+            Self::Group {
+                tiles,
+                orientation,
+                seed,
+            } => {
+                let index = pick_weighted(tiles.iter().map(|&(_, weight)| weight), cell_random(*seed, cell));
+                MapCell::new(index.map(|index| tiles[index].0), *orientation)
+            }
+        }
+    }
+
+    /// What gets written into a cell under a reflection, mirroring what its source cell gets so
+    /// reflected brush group strokes match the original.
+    #[inline]
+    pub fn reflected(&self, reflection: Reflection, cell: IVec3) -> MapCell {
+        reflection.value(self.at(reflection.cell(cell)))
+    }
+}
+
+/// Like [`written_cell`], but paints from the active brush group instead while it's enabled,
+/// adding its tiles to the map's tile set if needed.
+pub fn written_paint(
+    place: bool,
+    active: &mut ActiveTile,
+    group: &ActiveGroup,
+    rotation: PlacementRotation,
+    commands: &mut MapCommands,
+    tiles: &Tiles,
+) -> Option<Paint> {
+    if !place || !group.enabled {
+        return written_cell(place, active, rotation, commands, tiles).map(Paint::Cell)
+    }
+
+    let members = group
+        .group
+        .members
+        .iter()
+        .filter(|member| member.weight > 0.0)
+        .map(|member| Some((tiles.resolve(&member.tile)?, member.weight)))
+        .collect::<Option<Vec<_>>>()?;
+
+    let map = commands.map()?;
+    let resolve = |map: &Map| {
+        members
+            .iter()
+            .map(|(key, weight)| {
+                let index = map.tile_set.iter().position(|tile| tile == key)?;
+                Some((NonMaxU8::new(index as u8)?, *weight))
+            })
+            .collect::<Option<Vec<_>>>()
+    };
+
+    // Only touch the map when a member isn't in its tile set yet.
+    let tiles = match resolve(map) {
+        Some(tiles) => tiles,
+        None => {
+            let map = commands.map_mut_untracked()?;
+            for (key, _) in &members {
+                map.ensure_tile(key, tiles)?;
+            }
+
+            resolve(map)?
+        }
+    };
+
+    let seed = commands.map()?.seed;
+    (!tiles.is_empty()).then_some(Paint::Group {
+        tiles,
+        orientation: *rotation,
+        seed,
+    })
+}
+
+/// The cell a tool would write without touching the map, or `None` if the active tile isn't in
+/// the tile set yet.
+pub fn previewed_cell(place: bool, active: &ActiveTile, rotation: PlacementRotation, map: &Map) -> Option<MapCell> {
+    match place {
+        true => active
+            .path(map)
+            .and_then(|path| map.tile_set.iter().position(|tile| tile == path))
+            .map(|index| MapCell::new(NonMaxU8::new(index as u8), *rotation)),
+        false => Some(MapCell::EMPTY),
+    }
+}
+
+/// Whether space or alt is held, turning mouse buttons into camera navigation, or the measure or
+/// sun key, turning them into measuring or moving the sun.
+#[inline]
+pub fn navigating(keys: &ButtonInput<KeyCode>) -> bool {
+    navigating_without_alt(keys) || keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+}
+
+/// Like [`navigating`], for tools taking alt-clicks for themselves rather than leaving them to turn
+/// the camera.
+#[inline]
+pub fn navigating_without_alt(keys: &ButtonInput<KeyCode>) -> bool {
+    keys.any_pressed([KeyCode::Space, MEASURE_KEY, SUN_KEY])
+}
+
+/// The tile faces the mouse was pressed on this frame, with the buttons pressed, from the editor
+/// map's [`TilePointerEvent`]s.
+pub fn tile_presses(events: &mut EventReader<TilePointerEvent>) -> Vec<(PointerButton, TilePointerEvent)> {
+    events
+        .read()
+        .filter(|e| e.pointer == PointerId::Mouse)
+        .filter_map(|&e| match e.kind {
+            TilePointerKind::Down(button) => Some((button, e)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A click-drag spanning a rectangle of cells on the layer it started on, or a box while shift is
+/// held.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct RegionDrag {
+    pub start: IVec3,
+    pub end: IVec3,
+    pub button: MouseButton,
+    /// Layers the box spans upwards from the start cell.
+    pub height: u32,
+}
+
+impl RegionDrag {
+    #[inline]
+    pub fn new(start: IVec3, button: MouseButton) -> Self {
+        Self {
+            start,
+            end: start,
+            button,
+            height: 1,
+        }
+    }
+
+    /// Follows the cursor along the drag's layer, and adjusts the box height by scrolled lines.
+    #[inline]
+    pub fn update(&mut self, map: &Map, target: &CursorTarget, scroll: f32) {
+        self.follow(map, target);
+        self.raise(scroll);
+    }
+
+    /// Follows the cursor along the drag's layer, if it points at it.
+    #[inline]
+    pub fn follow(&mut self, map: &Map, target: &CursorTarget) {
+        if let Some(end) = target.ray.and_then(|ray| map.layer_cell(ray, self.start.y)) {
+            self.end = end;
+        }
+    }
+
+    /// Adjusts the box height by scrolled lines.
+    #[inline]
+    pub fn raise(&mut self, scroll: f32) {
+        if scroll != 0.0 {
+            self.height = (self.height as i32 + scroll.signum() as i32).max(1) as u32;
+        }
+    }
+
+    /// The dragged region as an inclusive minimum and exclusive maximum.
+    #[inline]
+    pub fn region(&self, boxed: bool) -> (IVec3, IVec3) {
+        let min = self.start.min(self.end);
+        let mut max = self.start.max(self.end) + 1;
+        max.y = min.y + if boxed { self.height as i32 } else { 1 };
+        (min, max)
+    }
+}
+
+/// Whether shift is held, extending region drags into boxes.
+#[inline]
+pub fn boxing(keys: &ButtonInput<KeyCode>) -> bool {
+    keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+}
+
+/// Lines scrolled this frame.
+#[inline]
+pub fn scrolled_lines(wheel: &mut EventReader<MouseWheel>) -> f32 {
+    wheel
+        .read()
+        .map(|e| match e.unit {
+            MouseScrollUnit::Line => e.y,
+            MouseScrollUnit::Pixel => e.y / 16.0,
+        })
+        .sum()
+}
+
+/// Draws the box of a local-space region of the map.
+#[inline]
+pub fn draw_region(
+    gizmos: &mut Gizmos,
+    map: &Map,
+    map_trns: &GlobalTransform,
+    (min, max): (IVec3, IVec3),
+    color: impl Into<Color>,
+) {
+    let (lower, upper) = (map.cell_min(min), map.cell_min(max));
+    gizmos.cuboid(
+        *map_trns * Transform::from_translation((lower + upper) * 0.5).with_scale(upper - lower),
+        color,
+    );
+}
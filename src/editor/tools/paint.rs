@@ -0,0 +1,114 @@
+use bevy::{prelude::*, utils::HashSet};
+use bevy_mod_picking::pointer::PointerButton;
+
+use crate::{
+    content::Tiles,
+    editor::{
+        brush::{Brush, BrushShape},
+        group::ActiveGroup,
+        history::MapCommands,
+        palette::{ActiveTile, PlacementRotation},
+        symmetry::{edit_reflected, reflections},
+        tools::{navigating, tile_presses, written_paint, CursorTarget, ToolMode},
+        EditorSettings,
+    },
+    map::picking::TilePointerEvent,
+};
+
+/// Cells touched by the current click-drag, so a held button writes to each cell once. The stroke's
+/// changes are recorded as one step once the button is let go.
+#[derive(Default)]
+pub struct PaintStroke {
+    /// The layer the stroke started on; dragging stays on it instead of climbing onto freshly
+    /// placed tiles.
+    pub layer: Option<i32>,
+    pub visited: HashSet<IVec3>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn paint_tiles(
+    mut events: EventReader<TilePointerEvent>,
+    settings: Res<EditorSettings>,
+    tiles: Res<Tiles>,
+    mode: Res<State<ToolMode>>,
+    target: Res<CursorTarget>,
+    mut active: ResMut<ActiveTile>,
+    group: Res<ActiveGroup>,
+    rotation: Res<PlacementRotation>,
+    brush: Res<Brush>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut commands: MapCommands,
+    mut stroke: Local<PaintStroke>,
+) {
+    let presses = tile_presses(&mut events);
+    let (button, place) = match (mouse.pressed(MouseButton::Left), mouse.pressed(MouseButton::Right)) {
+        (true, false) => (PointerButton::Primary, **mode == ToolMode::Place),
+        (false, true) => (PointerButton::Secondary, false),
+        _ => {
+            *stroke = default();
+            commands.end_stroke();
+            return
+        }
+    };
+
+    if navigating(&keys) {
+        return
+    }
+
+    let (Some(map), Some(ray)) = (commands.map(), target.ray) else {
+        return
+    };
+
+    // Strokes start on the pressed tile face, or in the cursor's layer when pressed on no tile.
+    let pressed = presses.into_iter().rev().find(|&(other, _)| other == button);
+    let target = match stroke.layer {
+        None => pressed.map_or_else(|| target.click_cell(place), |(_, e)| e.click_cell(place)),
+        Some(layer) => map.layer_cell(ray, layer),
+    };
+
+    let Some(mut cell) = target else { return };
+    if !stroke.visited.insert(cell) {
+        return
+    }
+
+    stroke.layer = Some(cell.y);
+
+    if !map.contains(cell) && place && settings.auto_grow {
+        let min = cell.min(IVec3::ZERO);
+        let max = (cell + 1).max(map.size.as_ivec3());
+        let offset = -min;
+
+        commands.stroke(|map| map.resize((max - min).as_uvec3(), offset));
+        cell += offset;
+        stroke.layer = Some(cell.y);
+        stroke.visited = stroke.visited.drain().map(|visited| visited + offset).collect();
+    }
+
+    let Some(paint) = written_paint(place, &mut active, &group, *rotation, &mut commands, &tiles) else {
+        return
+    };
+
+    // Footprints are clipped to the map bounds; only edit the map if something changes.
+    let map = commands.map().unwrap();
+    let reflections = reflections(&settings, map);
+    if reflections.iter().any(|&reflection| {
+        brush
+            .footprint(cell)
+            .map(|cell| reflection.cell(cell))
+            .any(|cell| map.contains(cell) && map.get_cell(cell.as_uvec3()) != Some(paint.reflected(reflection, cell)))
+    }) {
+        commands.stroke(|map| {
+            edit_reflected(map, &reflections, |map, reflection| match brush.shape {
+                BrushShape::Circle => map.fill_cells_with(brush.footprint(cell).map(|cell| reflection.cell(cell)), |cell| {
+                    paint.reflected(reflection, cell)
+                }),
+                BrushShape::Single | BrushShape::Square => {
+                    let (min, max) = brush.region(cell);
+                    let (min, max) = reflection.region(min, max);
+                    map.fill_region_with(min, max, |cell| paint.reflected(reflection, cell))
+                }
+            })
+        });
+    }
+}
@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+use bevy_mod_picking::pointer::PointerButton;
+
+use crate::{
+    editor::{
+        palette::{ActiveTile, PlacementRotation},
+        tools::{navigating, tile_presses},
+        EditorMap,
+    },
+    map::{picking::TilePointerEvent, Map},
+};
+
+pub fn pick_tile(
+    mut events: EventReader<TilePointerEvent>,
+    mut active: ResMut<ActiveTile>,
+    mut rotation: ResMut<PlacementRotation>,
+    keys: Res<ButtonInput<KeyCode>>,
+    editor_maps: Query<&Handle<Map>, With<EditorMap>>,
+    maps: Res<Assets<Map>>,
+) {
+    let pressed = tile_presses(&mut events)
+        .into_iter()
+        .filter_map(|(button, e)| (button == PointerButton::Primary).then_some(e))
+        .last();
+
+    let (false, Some(e)) = (navigating(&keys), pressed) else {
+        return
+    };
+
+    let Some(map) = editor_maps
+        .get_single()
+        .ok()
+        .filter(|handle| handle.id() == e.map)
+        .and_then(|handle| maps.get(handle))
+    else {
+        return
+    };
+
+    if let Some(tile) = map.get(e.cell) {
+        active.set_if_neq(ActiveTile::Index(tile));
+        rotation.set_if_neq(PlacementRotation(map.orientation(e.cell)));
+    }
+}
@@ -0,0 +1,459 @@
+use bevy::{color::palettes::css, input::mouse::MouseWheel, prelude::*};
+use bevy_mod_picking::pointer::PointerId;
+
+use crate::{
+    content::Tiles,
+    editor::{
+        history::MapCommands,
+        tools::{
+            boxing, draw_region, navigating, navigating_without_alt, scrolled_lines, CursorTarget, RegionDrag,
+            ToolStatus,
+        },
+        EditorMap,
+    },
+    map::{
+        fragment::MapFragment,
+        picking::{TilePointerEvent, TilePointerKind},
+        Map, MapCell,
+    },
+};
+
+/// What is selected in the editor map: cells in tile mode, and props in object mode.
+#[derive(Resource, Clone, Eq, PartialEq, Default, Debug)]
+pub struct EditorSelection {
+    pub cells: CellSelection,
+    /// Where the selected props are in [`Map::props`](crate::map::Map::props), in the order they were
+    /// selected.
+    pub props: Vec<usize>,
+}
+
+/// Selected cells, as boxes each adding cells to the selection or taking them out, in the order they
+/// were dragged. Later boxes win over earlier ones where they overlap.
+#[derive(Clone, Eq, PartialEq, Default, Debug)]
+pub struct CellSelection {
+    /// Each box as an inclusive minimum and exclusive maximum, and whether it takes cells out.
+    boxes: Vec<(IVec3, IVec3, bool)>,
+}
+
+impl CellSelection {
+    /// Selects the cells of a region given by an inclusive minimum and exclusive maximum.
+    #[inline]
+    pub fn new((min, max): (IVec3, IVec3)) -> Self {
+        let mut selection = Self::default();
+        selection.apply((min, max), SelectOp::Add);
+        selection
+    }
+
+    /// Changes the selection by a region given by an inclusive minimum and exclusive maximum.
+    pub fn apply(&mut self, (min, max): (IVec3, IVec3), op: SelectOp) {
+        if !min.cmplt(max).all() {
+            return
+        }
+
+        match op {
+            SelectOp::Replace => self.boxes = vec![(min, max, false)],
+            SelectOp::Add => self.boxes.push((min, max, false)),
+            SelectOp::Subtract => {
+                // Boxes taking out every cell there is leave nothing to keep track of.
+                match self.bounds() {
+                    Some((lower, upper)) if min.cmple(lower).all() && max.cmpge(upper).all() => self.boxes.clear(),
+                    Some(..) => self.boxes.push((min, max, true)),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    /// Changes the selection by any cells at all, boxed up in runs along the X axis.
+    pub fn apply_cells(&mut self, cells: impl IntoIterator<Item = IVec3>, op: SelectOp) {
+        let mut cells = cells.into_iter().collect::<Vec<_>>();
+        cells.sort_unstable_by_key(|cell| (cell.z, cell.y, cell.x));
+        cells.dedup();
+
+        let mut runs = Vec::<(IVec3, IVec3)>::new();
+        for cell in cells {
+            match runs.last_mut() {
+                Some((_, max)) if cell == *max - IVec3::new(0, 1, 1) => max.x += 1,
+                _ => runs.push((cell, cell + 1)),
+            }
+        }
+
+        if op == SelectOp::Replace {
+            self.boxes.clear();
+        }
+
+        let op = match op {
+            SelectOp::Subtract => SelectOp::Subtract,
+            SelectOp::Replace | SelectOp::Add => SelectOp::Add,
+        };
+
+        for run in runs {
+            self.apply(run, op);
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    /// The boxes making up the selection, and whether each takes cells out.
+    #[inline]
+    pub fn boxes(&self) -> &[(IVec3, IVec3, bool)] {
+        &self.boxes
+    }
+
+    #[inline]
+    pub fn contains(&self, cell: IVec3) -> bool {
+        self.boxes
+            .iter()
+            .rev()
+            .find(|&&(min, max, _)| cell.cmpge(min).all() && cell.cmplt(max).all())
+            .is_some_and(|&(.., subtract)| !subtract)
+    }
+
+    /// The smallest region holding every selected cell, as an inclusive minimum and exclusive maximum.
+    pub fn bounds(&self) -> Option<(IVec3, IVec3)> {
+        self.boxes
+            .iter()
+            .filter(|&&(.., subtract)| !subtract)
+            .map(|&(min, max, _)| (min, max))
+            .reduce(|(a_min, a_max), (b_min, b_max)| (a_min.min(b_min), a_max.max(b_max)))
+    }
+
+    /// The selected region, if the selection is exactly one box.
+    #[inline]
+    pub fn region(&self) -> Option<(IVec3, IVec3)> {
+        match self.boxes[..] {
+            [(min, max, false)] => Some((min, max)),
+            _ => None,
+        }
+    }
+
+    /// Every selected cell.
+    pub fn iter(&self) -> impl Iterator<Item = IVec3> + '_ {
+        let (min, max) = self.bounds().unwrap_or_default();
+        (min.z..max.z)
+            .flat_map(move |z| (min.y..max.y).flat_map(move |y| (min.x..max.x).map(move |x| IVec3::new(x, y, z))))
+            .filter(|&cell| self.contains(cell))
+    }
+
+    /// Copies the selected cells of a map, in a fragment spanning the selection's bounds.
+    #[inline]
+    pub fn extract(&self, map: &Map) -> Option<MapFragment> {
+        let (min, max) = self.bounds()?;
+        Some(map.extract_where(min, max, |cell| self.contains(cell)))
+    }
+}
+
+/// How a dragged box changes the selection, by the modifiers held as the drag started.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub enum SelectOp {
+    /// Plain drags select only the dragged cells.
+    #[default]
+    Replace,
+    /// Ctrl-drags add the dragged cells to the selection.
+    Add,
+    /// Alt-drags take the dragged cells out of the selection.
+    Subtract,
+}
+
+impl SelectOp {
+    #[inline]
+    pub fn held(keys: &ButtonInput<KeyCode>) -> Self {
+        match (
+            keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]),
+            keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]),
+        ) {
+            (_, true) => Self::Subtract,
+            (true, false) => Self::Add,
+            (false, false) => Self::Replace,
+        }
+    }
+}
+
+/// Longest time in seconds between clicks on the same cell for them to count as one double- or
+/// triple-click.
+pub const MULTI_CLICK_INTERVAL: f32 = 0.4;
+
+/// The box being dragged with the select tool, and how it will change the selection once let go.
+#[derive(Resource, Copy, Clone, Default, Debug, Deref, DerefMut)]
+pub struct SelectDrag(pub Option<(RegionDrag, SelectOp)>);
+
+/// Cells lifted off the map, following the cursor until they are stamped back down.
+#[derive(Resource, Clone, Default, Debug, Deref, DerefMut)]
+pub struct Floating(pub Option<FloatingFragment>);
+
+#[derive(Clone, Debug)]
+pub struct FloatingFragment {
+    pub fragment: MapFragment,
+    /// Cells the fragment was nudged by from where the cursor puts it.
+    pub nudge: IVec3,
+    /// Where the fragment's minimum corner was last put.
+    pub at: Option<IVec3>,
+    /// Whether stamping completes the step that lifted the fragment, so a move undoes in one go.
+    pub merge: bool,
+}
+
+impl FloatingFragment {
+    #[inline]
+    pub fn new(fragment: MapFragment, merge: bool) -> Self {
+        Self {
+            fragment,
+            nudge: IVec3::ZERO,
+            at: None,
+            merge,
+        }
+    }
+}
+
+/// Drags of the editor map's tiles box cells on the layer of the tile the drag started on, or in
+/// several layers while shift is held and scrolled. The box changes the selection once let go, as
+/// [`SelectOp::held`] says. Clicking a tile without dragging selects it alone, double-clicking
+/// selects the cells connected to it on its layer that hold the same tile, and triple- or
+/// ctrl+double-clicking selects every cell holding it.
+#[allow(clippy::too_many_arguments)]
+pub fn select_region(
+    time: Res<Time>,
+    mut clicks: Local<Option<(UVec3, f32, u32)>>,
+    mut events: EventReader<TilePointerEvent>,
+    target: Res<CursorTarget>,
+    floating: Res<Floating>,
+    mut selection: ResMut<EditorSelection>,
+    mut drag: ResMut<SelectDrag>,
+    mut status: ResMut<ToolStatus>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    commands: MapCommands,
+) {
+    let scroll = scrolled_lines(&mut wheel);
+    let events = events
+        .read()
+        .filter(|e| e.pointer == PointerId::Mouse)
+        .copied()
+        .collect::<Vec<_>>();
+
+    if floating.is_some() {
+        drag.set_if_neq(SelectDrag(None));
+        return
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        match **drag {
+            Some(..) => **drag = None,
+            None => {
+                selection.reborrow().map_unchanged(|s| &mut s.cells).set_if_neq(default());
+                status.set("");
+            }
+        }
+    }
+
+    let Some(map) = commands.map() else {
+        drag.set_if_neq(SelectDrag(None));
+        return
+    };
+
+    // Regions never reach past the map bounds.
+    let clip = |(min, max): (IVec3, IVec3)| (min.max(IVec3::ZERO), max.min(map.size.as_ivec3()));
+    let boxed = boxing(&keys);
+
+    let mut released = None;
+    for e in events {
+        match e.kind {
+            // Alt-drags take cells out here, rather than turning the camera.
+            TilePointerKind::DragStart if mouse.pressed(MouseButton::Left) && !navigating_without_alt(&keys) => {
+                **drag = Some((RegionDrag::new(e.cell.as_ivec3(), MouseButton::Left), SelectOp::held(&keys)));
+            }
+            TilePointerKind::Click
+                if drag.is_none() && mouse.just_released(MouseButton::Left) && !navigating_without_alt(&keys) =>
+            {
+                let now = time.elapsed_seconds();
+                let count = match *clicks {
+                    Some((cell, at, count)) if cell == e.cell && now - at <= MULTI_CLICK_INTERVAL => count + 1,
+                    _ => 1,
+                };
+                *clicks = Some((e.cell, now, count));
+
+                let (op, cell) = (SelectOp::held(&keys), e.cell.as_ivec3());
+                match count {
+                    1 => selection.cells.apply(clip((cell, cell + 1)), op),
+                    2 if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) => {
+                        selection.cells.apply_cells(map.flood_layer_cells(e.cell), op)
+                    }
+                    _ => selection.cells.apply_cells(map.tile_cells(map.get(e.cell)), op),
+                }
+            }
+            TilePointerKind::Drag | TilePointerKind::DragEnd => {
+                let Some((pending, op)) = drag.as_mut() else { continue };
+
+                // Drags follow the cursor along the layer they started on, or the cell it's over if it
+                // points past the layer.
+                pending.end = IVec3::new(e.cell.x as i32, pending.start.y, e.cell.z as i32);
+                pending.follow(map, &target);
+                if e.kind == TilePointerKind::DragEnd {
+                    released = Some((pending.region(boxed), *op));
+                    **drag = None;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((pending, _)) = drag.as_mut().filter(|_| boxed && scroll != 0.0) {
+        pending.raise(scroll);
+    }
+
+    if let Some((region, op)) = released {
+        selection.cells.apply(clip(region), op);
+    }
+
+    let size = match (**drag, selection.cells.bounds()) {
+        (Some((pending, _)), _) => {
+            let (min, max) = clip(pending.region(boxed));
+            (max - min).max(IVec3::ZERO)
+        }
+        (None, Some((min, max))) => max - min,
+        (None, None) => return,
+    };
+
+    status.set(format!("{}x{}x{}", size.x, size.y, size.z));
+}
+
+pub fn edit_selection(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut selection: ResMut<EditorSelection>,
+    mut floating: ResMut<Floating>,
+    mut commands: MapCommands,
+) {
+    if selection.cells.is_empty() || floating.is_some() {
+        return
+    }
+
+    let cells = &selection.cells;
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if keys.just_pressed(KeyCode::Delete) {
+        if commands.map().and_then(|map| cells.extract(map)).is_some_and(|fragment| has_tiles(&fragment)) {
+            commands.edit(false, |map| map.fill_cells(cells.iter(), MapCell::EMPTY));
+        }
+    } else if ctrl && keys.just_pressed(KeyCode::KeyD) {
+        if let Some(fragment) = commands.map().and_then(|map| cells.extract(map)) {
+            **floating = Some(FloatingFragment::new(fragment, false));
+        }
+    } else if ctrl && keys.just_pressed(KeyCode::KeyX) {
+        // Moving lifts the cells off the map, to be stamped back down elsewhere.
+        let Some(fragment) = commands.map().and_then(|map| cells.extract(map)) else {
+            return
+        };
+        let merge = commands.edit(false, |map| map.fill_cells(cells.iter(), MapCell::EMPTY));
+
+        **floating = Some(FloatingFragment::new(fragment, merge));
+        selection.reborrow().map_unchanged(|s| &mut s.cells).set_if_neq(default());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn place_floating(
+    tiles: Res<Tiles>,
+    target: Res<CursorTarget>,
+    mut status: ResMut<ToolStatus>,
+    mut floating: ResMut<Floating>,
+    mut selection: ResMut<EditorSelection>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    editor_maps: Query<&GlobalTransform, With<EditorMap>>,
+    mut commands: MapCommands,
+    mut gizmos: Gizmos,
+) {
+    if floating.is_none() {
+        return
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        **floating = None;
+        return status.set("")
+    }
+
+    let (Ok(map_trns), Some(map)) = (editor_maps.get_single(), commands.map()) else {
+        return
+    };
+
+    let pending = floating.0.as_mut().unwrap();
+    for (key, dir) in [
+        (KeyCode::ArrowLeft, IVec3::NEG_X),
+        (KeyCode::ArrowRight, IVec3::X),
+        (KeyCode::ArrowUp, IVec3::NEG_Z),
+        (KeyCode::ArrowDown, IVec3::Z),
+    ] {
+        if keys.just_pressed(key) {
+            pending.nudge += dir;
+        }
+    }
+
+    // Hold the fragment centered on the cursor, sitting on whatever it points at.
+    let size = pending.fragment.size.as_ivec3();
+    if let Some(cell) = target.click_cell(true) {
+        pending.at = Some(cell - IVec3::new(size.x / 2, 0, size.z / 2) + pending.nudge);
+    }
+
+    let Some(at) = pending.at else { return };
+    draw_region(&mut gizmos, map, map_trns, (at, at + size), css::FUCHSIA);
+    for (cell, ..) in pending.fragment.iter_cells() {
+        let cell = at + cell.as_ivec3();
+        draw_region(&mut gizmos, map, map_trns, (cell, cell + 1), css::PLUM);
+    }
+
+    status.set(format!(
+        "floating {}x{}x{}, enter to stamp, esc to drop",
+        size.x, size.y, size.z
+    ));
+
+    let commit = keys.just_pressed(KeyCode::Enter) || (mouse.just_pressed(MouseButton::Left) && !navigating(&keys));
+    if !commit {
+        return
+    }
+
+    let FloatingFragment { fragment, merge, .. } = floating.0.take().unwrap();
+    commands.edit(merge, |map| map.stamp(&fragment, at, &tiles));
+
+    selection
+        .reborrow()
+        .map_unchanged(|s| &mut s.cells)
+        .set_if_neq(CellSelection::new((at, at + size)));
+    status.set("");
+}
+
+/// Draws the selection's boxes, those taking cells out in red, and the box being dragged in the
+/// color of what it will do.
+pub fn draw_selection(
+    selection: Res<EditorSelection>,
+    drag: Res<SelectDrag>,
+    floating: Res<Floating>,
+    keys: Res<ButtonInput<KeyCode>>,
+    editor_maps: Query<&GlobalTransform, With<EditorMap>>,
+    commands: MapCommands,
+    mut gizmos: Gizmos,
+) {
+    let (Ok(map_trns), Some(map), None) = (editor_maps.get_single(), commands.map(), &floating.0) else {
+        return
+    };
+
+    for &(min, max, subtract) in selection.cells.boxes() {
+        let color = if subtract { css::CRIMSON } else { css::AQUA };
+        draw_region(&mut gizmos, map, map_trns, (min, max), color);
+    }
+
+    if let Some((pending, op)) = **drag {
+        let color = match op {
+            SelectOp::Replace => css::YELLOW,
+            SelectOp::Add => css::LIME,
+            SelectOp::Subtract => css::ORANGE_RED,
+        };
+        draw_region(&mut gizmos, map, map_trns, pending.region(boxing(&keys)), color);
+    }
+}
+
+#[inline]
+fn has_tiles(fragment: &MapFragment) -> bool {
+    fragment.tiles.iter().any(Option::is_some)
+}
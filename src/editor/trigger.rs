@@ -0,0 +1,26 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use bevy::prelude::*;
+
+use crate::{map::trigger::MapTrigger, EditorUi};
+
+pub struct TriggerPlugin;
+impl Plugin for TriggerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, draw_triggers.run_if(in_state(EditorUi)));
+    }
+}
+
+/// The color trigger cells of an id are outlined with, so triggers of different ids tell apart.
+pub fn trigger_color(id: &str) -> Color {
+    let mut hasher = DefaultHasher::new();
+    id.hash(&mut hasher);
+    Color::hsla((hasher.finish() % 360) as f32, 0.9, 0.6, 0.6)
+}
+
+/// Outlines the cells of map triggers, which aren't drawn otherwise.
+pub fn draw_triggers(triggers: Query<(&MapTrigger, &GlobalTransform)>, mut gizmos: Gizmos) {
+    for (trigger, &trns) in &triggers {
+        gizmos.cuboid(trns, trigger_color(&trigger.id));
+    }
+}
@@ -0,0 +1,228 @@
+use avian3d::prelude::*;
+use bevy::{
+    core_pipeline::{bloom::BloomSettings, tonemapping::Tonemapping},
+    pbr::wireframe::Wireframe,
+    prelude::*,
+    render::{renderer::RenderDevice, settings::WgpuFeatures},
+};
+
+use crate::{
+    content::array::MapMaterial,
+    editor::{camera::EditorCamera, prompt::Notice, EditorMap, EditorSettings},
+    map::MapMaterials,
+    EditorUi, GameState,
+};
+
+pub struct ViewPlugin;
+impl Plugin for ViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                toggle_views,
+                (apply_wireframe, apply_fullbright, apply_tonemapping, apply_bloom),
+            )
+                .chain()
+                .run_if(in_state(EditorUi)),
+        )
+        .add_systems(
+            Update,
+            (
+                toggle_physics_debug.run_if(in_state(GameState::Editor)),
+                apply_physics_debug.run_if(resource_changed::<EditorSettings>),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The tonemapping operators cycled through, leaving out those that need lookup textures this
+/// build doesn't ship with.
+pub const TONEMAPPERS: [Tonemapping; 5] = [
+    Tonemapping::None,
+    Tonemapping::Reinhard,
+    Tonemapping::ReinhardLuminance,
+    Tonemapping::AcesFitted,
+    Tonemapping::SomewhatBoringDisplayTransform,
+];
+
+/// Seconds the tonemapper has to stay put before it's applied, as each switch recompiles
+/// pipelines.
+pub const TONEMAPPING_DEBOUNCE: f32 = 0.3;
+
+/// Name of a tonemapping operator for the status bar.
+#[inline]
+pub fn tonemapper_name(tonemapping: Tonemapping) -> &'static str {
+    match tonemapping {
+        Tonemapping::None => "no tonemapping",
+        Tonemapping::Reinhard => "Reinhard",
+        Tonemapping::ReinhardLuminance => "Reinhard luminance",
+        Tonemapping::AcesFitted => "ACES",
+        Tonemapping::AgX => "AgX",
+        Tonemapping::SomewhatBoringDisplayTransform => "somewhat boring",
+        Tonemapping::TonyMcMapface => "TonyMcMapface",
+        Tonemapping::BlenderFilmic => "Blender filmic",
+    }
+}
+
+/// The bloom the editor settings ask for, if any.
+#[inline]
+pub fn bloom_settings(settings: &EditorSettings) -> Option<BloomSettings> {
+    settings.bloom.then_some(BloomSettings {
+        intensity: settings.bloom_intensity,
+        ..BloomSettings::NATURAL
+    })
+}
+
+/// Whether the GPU draws lines as polygons, which the wireframe view needs. Without a renderer, as
+/// in headless apps, nothing is drawn anyway.
+#[inline]
+pub fn supports_wireframe(device: Option<&RenderDevice>) -> bool {
+    device.map_or(true, |device| device.features().contains(WgpuFeatures::POLYGON_MODE_LINE))
+}
+
+pub fn toggle_views(
+    keys: Res<ButtonInput<KeyCode>>,
+    device: Option<Res<RenderDevice>>,
+    mut settings: ResMut<EditorSettings>,
+    mut notice: ResMut<Notice>,
+) {
+    if keys.just_pressed(settings.wireframe_toggle) {
+        match supports_wireframe(device.as_deref()) {
+            true => settings.wireframe = !settings.wireframe,
+            false => notice.show("Wireframes aren't supported by this GPU."),
+        }
+    }
+
+    if keys.just_pressed(settings.fullbright_toggle) {
+        settings.fullbright = !settings.fullbright;
+    }
+
+    if keys.just_pressed(settings.tonemapping_cycle) {
+        let step = match keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            true => TONEMAPPERS.len() - 1,
+            false => 1,
+        };
+
+        let current = TONEMAPPERS.iter().position(|&other| other == settings.tonemapping);
+        settings.tonemapping = TONEMAPPERS[current.map_or(0, |i| (i + step) % TONEMAPPERS.len())];
+    }
+
+    if keys.just_pressed(settings.bloom_toggle) {
+        settings.bloom = !settings.bloom;
+    }
+
+    for (key, scale) in [(settings.bloom_down, 0.8), (settings.bloom_up, 1.25)] {
+        if keys.just_pressed(key) {
+            settings.bloom = true;
+            settings.bloom_intensity = (settings.bloom_intensity * scale).clamp(0.01, 1.0);
+        }
+    }
+}
+
+/// Kept apart from [`toggle_views`], as colliders are worth seeing while playtesting too.
+pub fn toggle_physics_debug(keys: Res<ButtonInput<KeyCode>>, mut settings: ResMut<EditorSettings>) {
+    if keys.just_pressed(settings.physics_debug_toggle) {
+        settings.physics_debug = !settings.physics_debug;
+    }
+}
+
+pub fn apply_physics_debug(settings: Res<EditorSettings>, mut gizmos: ResMut<GizmoConfigStore>) {
+    let (config, _) = gizmos.config_mut::<PhysicsGizmos>();
+    if config.enabled != settings.physics_debug {
+        config.enabled = settings.physics_debug;
+    }
+}
+
+pub fn apply_wireframe(
+    mut commands: Commands,
+    settings: Res<EditorSettings>,
+    editor_maps: Query<(Entity, Ref<EditorMap>, Has<Wireframe>)>,
+) {
+    for (e, marker, wireframe) in &editor_maps {
+        if !settings.is_changed() && !marker.is_added() {
+            continue
+        }
+
+        match (settings.wireframe, wireframe) {
+            (true, false) => {
+                commands.entity(e).insert(Wireframe);
+            }
+            (false, true) => {
+                commands.entity(e).remove::<Wireframe>();
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn apply_fullbright(
+    settings: Res<EditorSettings>,
+    shared: Res<MapMaterials>,
+    mut editor_maps: Query<(Ref<EditorMap>, &mut Handle<MapMaterial>)>,
+) {
+    for (marker, mut material) in &mut editor_maps {
+        if !settings.is_changed() && !marker.is_added() {
+            continue
+        }
+
+        let target = match settings.fullbright {
+            true => &shared.unlit,
+            false => &shared.opaque,
+        };
+
+        if *material != *target {
+            *material = target.clone();
+        }
+    }
+}
+
+pub fn apply_tonemapping(
+    time: Res<Time>,
+    settings: Res<EditorSettings>,
+    mut cameras: Query<&mut Tonemapping, With<EditorCamera>>,
+    mut pending: Local<(Option<Tonemapping>, f32)>,
+) {
+    let (requested, elapsed) = &mut *pending;
+    if *requested != Some(settings.tonemapping) {
+        *requested = Some(settings.tonemapping);
+        *elapsed = 0.0;
+        return
+    }
+
+    *elapsed += time.delta_seconds();
+    if *elapsed < TONEMAPPING_DEBOUNCE {
+        return
+    }
+
+    for mut tonemapping in &mut cameras {
+        tonemapping.set_if_neq(settings.tonemapping);
+    }
+}
+
+pub fn apply_bloom(
+    mut commands: Commands,
+    settings: Res<EditorSettings>,
+    mut cameras: Query<(Entity, Option<&mut BloomSettings>), With<EditorCamera>>,
+) {
+    if !settings.is_changed() {
+        return
+    }
+
+    for (e, bloom) in &mut cameras {
+        match (bloom_settings(&settings), bloom) {
+            (Some(wanted), Some(mut bloom)) => {
+                if bloom.intensity != wanted.intensity {
+                    bloom.intensity = wanted.intensity;
+                }
+            }
+            (Some(wanted), None) => {
+                commands.entity(e).insert(wanted);
+            }
+            (None, Some(..)) => {
+                commands.entity(e).remove::<BloomSettings>();
+            }
+            (None, None) => {}
+        }
+    }
+}
@@ -0,0 +1,454 @@
+use std::{
+    fs,
+    io::{Cursor, Error as IoError},
+    path::PathBuf,
+    time::Duration,
+};
+
+use bevy::{
+    app::ScheduleRunnerPlugin,
+    asset::{LoadedFolder, UntypedAssetLoadFailedEvent},
+    ecs::system::RunSystemOnce,
+    prelude::*,
+    render::{mesh::VertexAttributeValues, render_resource::TextureFormat},
+    state::app::StatesPlugin,
+    utils::HashMap,
+};
+use bevy_asset_loader::prelude::MapKey;
+use image::{ImageError, ImageFormat, RgbaImage};
+use thiserror::Error;
+
+use crate::{
+    cli::EXIT_FAILURE,
+    content::{
+        array::MapMaterial,
+        atlas::{TileAtlasSettings, TileFilter},
+        manifest::{TileManifest, TileManifestLoader},
+        rebuild_tile_texture,
+        register::TileRegistered,
+        render::{update_tile_renders, TileAlpha, TileRenderFlags},
+        TileFolder, TileTexture, TileTextureError, Tiles,
+    },
+    map::{Map, MapMeshes, MapPart, MapPlugin},
+    obj::{
+        def::{MtlCollection, Obj},
+        ObjPlugin,
+    },
+};
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("`{path}` failed to load: {message}")]
+    Load { path: String, message: String },
+    #[error(transparent)]
+    TileTexture(#[from] TileTextureError),
+    #[error("`{0}` uses tiles without a packed texture, so it can't be meshed.")]
+    Unmeshable(String),
+    #[error("Atlas page {0} is gone.")]
+    MissingPage(usize),
+    #[error("Couldn't convert a {0:?} atlas page to RGBA8.")]
+    Format(TextureFormat),
+    #[error(transparent)]
+    Encode(#[from] ImageError),
+    #[error(transparent)]
+    Io(#[from] IoError),
+}
+
+/// Loads the map at the asset path `map` without a window or renderer, and writes it as binary glTF
+/// to `out`. Failures are written to stderr and told by the exit code.
+pub fn export_gltf(map: String, out: PathBuf) -> AppExit {
+    App::new()
+        .add_plugins((
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_millis(1))),
+            AssetPlugin::default(),
+            ExportPlugin { map, out },
+        ))
+        .run()
+}
+
+/// Loads the tile folder and a map as the editor would, packs the tile atlas, and writes the meshed
+/// map with [`map_glb`] before exiting. Exits with [`EXIT_FAILURE`] as soon as any asset fails to
+/// load, or the map can't be meshed or written. Needs [`MinimalPlugins`] and an [`AssetPlugin`].
+pub struct ExportPlugin {
+    /// Asset path of the map to export.
+    pub map: String,
+    pub out: PathBuf,
+}
+
+impl Plugin for ExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((ImagePlugin::default_nearest(), StatesPlugin, ObjPlugin, MapPlugin))
+            .init_asset::<Mesh>()
+            .init_asset::<TextureAtlasLayout>()
+            .init_asset::<MapMaterial>()
+            .init_asset::<TileManifest>()
+            .register_asset_loader(TileManifestLoader)
+            .init_resource::<TileAtlasSettings>()
+            .add_event::<TileRegistered>()
+            .insert_resource(ExportTask {
+                map: self.map.clone(),
+                out: self.out.clone(),
+                folder: None,
+                manifest: None,
+                handle: None,
+            })
+            .add_systems(Startup, start_export)
+            .add_systems(Update, export_map);
+    }
+}
+
+/// The map being exported, and the files it's waiting on.
+#[derive(Resource)]
+pub struct ExportTask {
+    pub map: String,
+    pub out: PathBuf,
+    pub folder: Option<Handle<LoadedFolder>>,
+    pub manifest: Option<Handle<TileManifest>>,
+    /// The map, loaded once the tiles it names are.
+    pub handle: Option<Handle<Map>>,
+}
+
+pub fn start_export(server: Res<AssetServer>, mut task: ResMut<ExportTask>) {
+    task.folder = Some(server.load_folder("tiles"));
+    task.manifest = Some(server.load("tiles/manifest.ron"));
+}
+
+/// Moves the export along, exiting once the map is written or something fails.
+pub fn export_map(world: &mut World) {
+    let exit = match advance_export(world) {
+        Ok(false) => return,
+        Ok(true) => AppExit::Success,
+        Err(e) => {
+            eprintln!("{e}");
+            AppExit::from_code(EXIT_FAILURE)
+        }
+    };
+
+    world.send_event(exit);
+}
+
+/// Takes the export as far as loaded assets let it, returning whether the map was written.
+fn advance_export(world: &mut World) -> Result<bool, ExportError> {
+    if let Some(e) = world.resource_mut::<Events<UntypedAssetLoadFailedEvent>>().drain().next() {
+        return Err(ExportError::Load {
+            path: e.path.to_string(),
+            message: e.error.to_string(),
+        })
+    }
+
+    let task = world.resource::<ExportTask>();
+    let (Some(folder), Some(manifest)) = (task.folder.clone(), task.manifest.clone()) else {
+        return Ok(false)
+    };
+
+    let Some(handle) = task.handle.clone() else {
+        let server = world.resource::<AssetServer>();
+        if !server.is_loaded_with_dependencies(&folder) || !server.is_loaded_with_dependencies(&manifest) {
+            return Ok(false)
+        }
+
+        // Keyed as the editor's loading state keys them.
+        let files = world
+            .resource::<Assets<LoadedFolder>>()
+            .get(&folder)
+            .into_iter()
+            .flat_map(|folder| &folder.handles)
+            .filter_map(|file| Some((String::from_asset_path(file.path()?), file.clone())))
+            .collect();
+
+        world.insert_resource(TileFolder { files, manifest });
+        world.init_resource::<Tiles>();
+        world.init_resource::<TileTexture>();
+        rebuild_tile_texture(world)?;
+        world.run_system_once(update_tile_renders);
+
+        // Maps resolve their tiles' names as they load, so they're only loaded once the tiles are named.
+        let map = world.resource::<ExportTask>().map.clone();
+        let handle = world.resource::<AssetServer>().load(map);
+        world.resource_mut::<ExportTask>().handle = Some(handle);
+        return Ok(false)
+    };
+
+    let Some(parts) = world.resource::<MapMeshes>().get(&handle.id()) else {
+        let loaded = world.resource::<AssetServer>().is_loaded_with_dependencies(&handle);
+        let ready = world.resource::<Assets<Map>>().get(&handle).map(|map| {
+            map.is_ready(
+                world.resource::<Assets<Obj>>(),
+                world.resource::<Assets<MtlCollection>>(),
+                world.resource::<TileTexture>(),
+                world.resource::<Assets<TextureAtlasLayout>>(),
+            )
+        });
+
+        // Ready maps are meshed after this update; those that aren't never will be.
+        return match (loaded, ready) {
+            (true, Some(false)) => Err(ExportError::Unmeshable(task.map.clone())),
+            _ => Ok(false),
+        }
+    };
+
+    let meshes = world.resource::<Assets<Mesh>>();
+    let parts = parts
+        .iter()
+        .filter_map(|(part, mesh)| Some((*part, meshes.get(mesh)?)))
+        .collect::<Vec<_>>();
+
+    let images = world.resource::<Assets<Image>>();
+    let pages = world
+        .resource::<TileTexture>()
+        .pages
+        .iter()
+        .enumerate()
+        .map(|(index, page)| images.get(&page.atlas).ok_or(ExportError::MissingPage(index)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let glb = map_glb(&parts, &pages, world.resource::<TileAtlasSettings>().filter)?;
+    fs::write(&task.out, glb)?;
+    Ok(true)
+}
+
+/// Writes map meshes as binary glTF: a single node whose mesh has a primitive per part, textured
+/// with its atlas page embedded as PNG. Only the pages' base color is kept; normal and emissive
+/// atlases are left out.
+pub fn map_glb(parts: &[(MapPart, &Mesh)], pages: &[&Image], filter: TileFilter) -> Result<Vec<u8>, ExportError> {
+    let mut glb = GlbBuilder::default();
+    let (mut primitives, mut materials, mut textures, mut images) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    let mut page_textures = HashMap::new();
+    let mut unlit = false;
+
+    for &(part, mesh) in parts {
+        let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(positions)) if !positions.is_empty() && !part.render.hidden => positions,
+            _ => continue,
+        };
+
+        let texture = match page_textures.get(&part.page) {
+            Some(&texture) => texture,
+            None => {
+                let page = pages.get(part.page).ok_or(ExportError::MissingPage(part.page))?;
+                let view = glb.view(&page_png(page)?, None);
+                images.push(format!(r#"{{"bufferView":{view},"mimeType":"image/png"}}"#));
+                textures.push(format!(r#"{{"sampler":0,"source":{}}}"#, images.len() - 1));
+
+                page_textures.insert(part.page, textures.len() - 1);
+                textures.len() - 1
+            }
+        };
+
+        let (min, max) = positions
+            .iter()
+            .fold(([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]), |(min, max), &pos| {
+                (Vec3::from(min).min(pos.into()).into(), Vec3::from(max).max(pos.into()).into())
+            });
+
+        let count = positions.len();
+        let position = glb.accessor(
+            ARRAY_BUFFER,
+            positions.iter().flatten().flat_map(|f| f.to_le_bytes()),
+            count,
+            FLOAT,
+            "VEC3",
+            &format!(
+                r#","min":[{},{},{}],"max":[{},{},{}]"#,
+                min[0], min[1], min[2], max[0], max[1], max[2]
+            ),
+        );
+
+        let mut attributes = format!(r#""POSITION":{position}"#);
+        if let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            let normal = glb.accessor(
+                ARRAY_BUFFER,
+                normals.iter().flatten().flat_map(|f| f.to_le_bytes()),
+                normals.len(),
+                FLOAT,
+                "VEC3",
+                "",
+            );
+            attributes.push_str(&format!(r#","NORMAL":{normal}"#));
+        }
+
+        if let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            let uv = glb.accessor(
+                ARRAY_BUFFER,
+                uvs.iter().flatten().flat_map(|f| f.to_le_bytes()),
+                uvs.len(),
+                FLOAT,
+                "VEC2",
+                "",
+            );
+            attributes.push_str(&format!(r#","TEXCOORD_0":{uv}"#));
+        }
+
+        let indices = match mesh.indices() {
+            Some(indices) => {
+                let index = glb.accessor(
+                    ELEMENT_ARRAY_BUFFER,
+                    indices.iter().flat_map(|i| (i as u32).to_le_bytes()),
+                    indices.len(),
+                    UNSIGNED_INT,
+                    "SCALAR",
+                    "",
+                );
+                format!(r#","indices":{index}"#)
+            }
+            None => String::new(),
+        };
+
+        unlit |= part.render.unlit;
+        materials.push(material(part.render, texture));
+        primitives.push(format!(
+            r#"{{"attributes":{{{attributes}}}{indices},"material":{}}}"#,
+            materials.len() - 1
+        ));
+    }
+
+    let filter = match filter {
+        TileFilter::Nearest => r#""magFilter":9728,"minFilter":9984"#,
+        TileFilter::Linear => r#""magFilter":9729,"minFilter":9987"#,
+    };
+
+    let any = !primitives.is_empty();
+    let fields = [
+        Some(r#""asset":{"version":"2.0","generator":"mnemonic"}"#.to_string()),
+        unlit.then(|| r#""extensionsUsed":["KHR_materials_unlit"]"#.to_string()),
+        Some(r#""scene":0"#.to_string()),
+        Some(format!(r#""scenes":[{{{}}}]"#, if any { r#""nodes":[0]"# } else { "" })),
+        any.then(|| r#""nodes":[{"mesh":0}]"#.to_string()),
+        any.then(|| format!(r#""meshes":[{{"primitives":[{}]}}]"#, primitives.join(","))),
+        json_array("materials", &materials),
+        json_array("textures", &textures),
+        any.then(|| format!(r#""samplers":[{{{filter}}}]"#)),
+        json_array("images", &images),
+        json_array("accessors", &glb.accessors),
+        json_array("bufferViews", &glb.views),
+        (!glb.bin.is_empty()).then(|| format!(r#""buffers":[{{"byteLength":{}}}]"#, glb.bin.len())),
+    ];
+
+    let json = format!("{{{}}}", fields.into_iter().flatten().collect::<Vec<_>>().join(","));
+    Ok(glb.finish(json))
+}
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// The binary chunk of a glTF file, and the views and accessors into it.
+#[derive(Default)]
+struct GlbBuilder {
+    bin: Vec<u8>,
+    views: Vec<String>,
+    accessors: Vec<String>,
+}
+
+impl GlbBuilder {
+    /// Appends a buffer view of `bytes`, returning its index.
+    fn view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        let offset = self.bin.len();
+        self.bin.extend_from_slice(bytes);
+        // Accessors read from 4-byte aligned offsets.
+        self.bin.resize(self.bin.len().next_multiple_of(4), 0);
+
+        let target = target.map_or_else(String::new, |target| format!(r#","target":{target}"#));
+        self.views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{offset},"byteLength":{}{target}}}"#,
+            bytes.len()
+        ));
+        self.views.len() - 1
+    }
+
+    /// Appends a view of `bytes` and an accessor reading `count` elements of it, returning the
+    /// accessor's index. `extra` is added to the accessor as is.
+    fn accessor(
+        &mut self,
+        target: u32,
+        bytes: impl IntoIterator<Item = u8>,
+        count: usize,
+        component: u32,
+        kind: &str,
+        extra: &str,
+    ) -> usize {
+        let view = self.view(&bytes.into_iter().collect::<Vec<_>>(), Some(target));
+        self.accessors.push(format!(
+            r#"{{"bufferView":{view},"componentType":{component},"count":{count},"type":"{kind}"{extra}}}"#
+        ));
+        self.accessors.len() - 1
+    }
+
+    /// Puts the JSON and binary chunks together under the GLB header.
+    fn finish(self, json: String) -> Vec<u8> {
+        let mut json = json.into_bytes();
+        // Chunks are 4-byte aligned; JSON by spaces, and binary by zeroes.
+        json.resize(json.len().next_multiple_of(4), b' ');
+
+        let mut chunks = vec![(0x4E4F534Au32, json)];
+        if !self.bin.is_empty() {
+            chunks.push((0x004E4942, self.bin));
+        }
+
+        let len = 12 + chunks.iter().map(|(_, chunk)| 8 + chunk.len()).sum::<usize>();
+        let mut glb = Vec::with_capacity(len);
+        glb.extend_from_slice(b"glTF");
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(len as u32).to_le_bytes());
+
+        for (kind, chunk) in chunks {
+            glb.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+            glb.extend_from_slice(&kind.to_le_bytes());
+            glb.extend_from_slice(&chunk);
+        }
+
+        glb
+    }
+}
+
+/// A top-level glTF array, left out if empty as the format asks.
+fn json_array(name: &str, items: &[String]) -> Option<String> {
+    (!items.is_empty()).then(|| format!(r#""{name}":[{}]"#, items.join(",")))
+}
+
+/// A glTF material drawing a part as the map material would, with the default roughness.
+fn material(render: TileRenderFlags, texture: usize) -> String {
+    let alpha = match render.alpha_mode {
+        TileAlpha::Opaque => r#""alphaMode":"OPAQUE""#.to_string(),
+        TileAlpha::Mask(cutoff) => format!(r#""alphaMode":"MASK","alphaCutoff":{cutoff}"#),
+        TileAlpha::Blend => r#""alphaMode":"BLEND""#.to_string(),
+    };
+
+    let unlit = match render.unlit {
+        true => r#","extensions":{"KHR_materials_unlit":{}}"#,
+        false => "",
+    };
+
+    format!(
+        r#"{{"pbrMetallicRoughness":{{"baseColorTexture":{{"index":{texture}}},"metallicFactor":0,"roughnessFactor":0.5}},{alpha},"doubleSided":{}{unlit}}}"#,
+        render.double_sided
+    )
+}
+
+/// The full-size level of an atlas page, encoded as PNG.
+fn page_png(page: &Image) -> Result<Vec<u8>, ExportError> {
+    let format = page.texture_descriptor.format;
+    let converted;
+    let page = match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => page,
+        _ => {
+            converted = page
+                .convert(TextureFormat::Rgba8UnormSrgb)
+                .ok_or(ExportError::Format(format))?;
+            &converted
+        }
+    };
+
+    // Mip levels follow the full-size one.
+    let size = page.size();
+    let rgba = page
+        .data
+        .get(..(size.x * size.y) as usize * 4)
+        .and_then(|data| RgbaImage::from_raw(size.x, size.y, data.to_vec()))
+        .ok_or(ExportError::Format(format))?;
+
+    let mut png = Cursor::new(Vec::new());
+    rgba.write_to(&mut png, ImageFormat::Png)?;
+    Ok(png.into_inner())
+}
@@ -0,0 +1,176 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bevy::{
+    asset::io::{
+        gated::{GateOpener, GatedReader},
+        memory::{Dir, MemoryAssetReader},
+        AssetSource, AssetSourceBuilder, AssetSourceEvent, AssetSourceId, AssetWatcher,
+    },
+    prelude::*,
+    state::app::StatesPlugin,
+};
+
+use crate::{
+    content::{array::MapMaterial, AtlasPage, TileTexture},
+    map::MapPlugin,
+    obj::{def::MtlCollection, ObjPlugin},
+};
+
+/// Longest [`update_until`] waits on its condition, in updates.
+pub const MAX_UPDATES: usize = 1000;
+
+/// Puts the files of the `liminal.floor` tile in a directory, as `floor.obj`, `floor.mtl`, and
+/// `floor.png`. Its object is `floor.obj#obj:tile`.
+pub fn insert_floor_tile(dir: &Dir) {
+    dir.insert_asset(
+        Path::new("floor.obj"),
+        include_bytes!("../assets/tiles/liminal/floor.obj").to_vec(),
+    );
+    dir.insert_asset(
+        Path::new("floor.mtl"),
+        include_bytes!("../assets/tiles/liminal/floor.mtl").to_vec(),
+    );
+    dir.insert_asset(
+        Path::new("floor.png"),
+        include_bytes!("../assets/tiles/liminal/floor.png").to_vec(),
+    );
+}
+
+/// An app without a window or renderer, loading its assets from memory. Objects, tiles, and maps
+/// load as they would in the editor, and maps are meshed once their tiles' textures are packed into
+/// the single page of a stand-in [`TileTexture`] by [`pack_test_atlas`].
+pub fn test_app(dir: Dir) -> App {
+    let reader = MemoryAssetReader { root: dir };
+    build(AssetSource::build().with_reader(move || Box::new(reader.clone())), false)
+}
+
+/// Like [`test_app`], with every file held back until its gate is opened, to control the order
+/// assets finish loading in.
+pub fn gated_test_app(dir: Dir) -> (App, GateOpener) {
+    let (reader, opener) = GatedReader::new(MemoryAssetReader { root: dir });
+    (build(AssetSource::build().with_reader(move || Box::new(reader.clone())), false), opener)
+}
+
+/// Like [`test_app`], watching for changes to files as they're told through the [`AssetNotifier`].
+pub fn watched_test_app(dir: Dir) -> (App, AssetNotifier) {
+    let notifier = AssetNotifier::default();
+    let source = AssetSource::build()
+        .with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() }))
+        .with_watcher({
+            let notifier = notifier.clone();
+            move |sender| {
+                *notifier.0.lock().unwrap() = Some(Box::new(move |e| sender.send(e).unwrap()));
+                Some(Box::new(ManualWatcher))
+            }
+        });
+
+    (build(source, true), notifier)
+}
+
+/// Tells the asset server of a [`watched_test_app`] that files changed, as a file watcher would.
+#[derive(Clone, Default)]
+pub struct AssetNotifier(Arc<Mutex<Option<Notify>>>);
+impl AssetNotifier {
+    pub fn modified(&self, path: impl Into<PathBuf>) {
+        let notify = self.0.lock().unwrap();
+        notify.as_ref().expect("the watcher was made")(AssetSourceEvent::ModifiedAsset(path.into()));
+    }
+}
+
+type Notify = Box<dyn Fn(AssetSourceEvent) + Send>;
+
+struct ManualWatcher;
+impl AssetWatcher for ManualWatcher {}
+
+fn build(source: AssetSourceBuilder, watch: bool) -> App {
+    let mut app = App::new();
+    app.register_asset_source(AssetSourceId::Default, source)
+        .add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                watch_for_changes_override: watch.then_some(true),
+                ..default()
+            },
+            ImagePlugin::default_nearest(),
+            StatesPlugin,
+            ObjPlugin,
+            MapPlugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<TextureAtlasLayout>()
+        .init_asset::<MapMaterial>()
+        .add_systems(Update, pack_test_atlas);
+
+    app.finish();
+    app.cleanup();
+
+    // The layout is filled in by `pack_test_atlas`, without ever reaching a render device.
+    let layout = app
+        .world_mut()
+        .resource_mut::<Assets<TextureAtlasLayout>>()
+        .add(TextureAtlasLayout::new_empty(UVec2::ONE));
+    app.insert_resource(TileTexture {
+        pages: vec![AtlasPage {
+            layout,
+            atlas: Handle::default(),
+            layers: None,
+            normal_atlas: None,
+            emissive_atlas: None,
+        }],
+        page_of: default(),
+        maps: default(),
+        sources: default(),
+        released: default(),
+        aliases: default(),
+    });
+
+    app
+}
+
+/// Packs the diffuse texture of every loaded material into the first page of the [`TileTexture`]'s
+/// layout, once they've all loaded. Only textures known by then are packed.
+pub fn pack_test_atlas(
+    materials: Res<Assets<MtlCollection>>,
+    images: Res<Assets<Image>>,
+    mut tile_texture: ResMut<TileTexture>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut done: Local<bool>,
+) {
+    if *done || materials.is_empty() {
+        return
+    }
+
+    let mut builder = TextureAtlasBuilder::default();
+    for (.., mtl) in materials.iter() {
+        for mtl in mtl.values() {
+            let Some(texture) = &mtl.diffuse_texture else { continue };
+            let Some(image) = images.get(texture) else { return };
+            builder.add_texture(Some(texture.id()), image);
+            tile_texture.page_of.insert(texture.id(), 0);
+        }
+    }
+
+    if let Ok((layout, ..)) = builder.build() {
+        layouts.insert(&tile_texture.pages[0].layout, layout);
+        *done = true;
+    }
+}
+
+/// Updates the app until `condition` holds, sleeping in between so assets may load. Panics if it
+/// takes more than [`MAX_UPDATES`].
+pub fn update_until(app: &mut App, mut condition: impl FnMut(&mut App) -> bool) {
+    for _ in 0..MAX_UPDATES {
+        app.update();
+        if condition(app) {
+            return
+        }
+
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    panic!("Condition never met.");
+}
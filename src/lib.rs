@@ -1,54 +1,287 @@
+pub mod character;
+pub mod cli;
 pub mod content;
+pub mod crash;
+pub mod diagnostics;
 pub mod editor;
+pub mod export;
+#[cfg(feature = "test-harness")]
+pub mod harness;
+pub mod loading;
 pub mod map;
+pub mod menu;
 pub mod obj;
+pub mod physics;
+pub mod playtest;
+
+use std::{path::PathBuf, process::ExitCode};
 
 use avian3d::prelude::*;
-use bevy::{prelude::*, window::PresentMode};
+use bevy::{
+    asset::io::file::FileAssetReader,
+    diagnostic::FrameTimeDiagnosticsPlugin,
+    log::LogPlugin,
+    pbr::wireframe::WireframePlugin,
+    prelude::*,
+    render::{
+        settings::{WgpuSettings, WgpuSettingsPriority},
+        RenderPlugin,
+    },
+    window::{PresentMode, WindowMode, WindowPosition, WindowResolution},
+};
 use bevy_asset_loader::prelude::*;
 use bevy_mod_picking::prelude::*;
-use content::{TileTexture, Tiles};
-use editor::EditorPlugin;
+use character::CharacterPlugin;
+use content::{
+    atlas::TileAtlasSettings,
+    manifest::TileCatalog,
+    ContentPlugin, TileFolder,
+};
+use crash::LogFileConfig;
+use diagnostics::BuildDiagnosticsPlugin;
+use editor::{
+    session::{EditorSession, SessionRequest, TITLE},
+    EditorPlugin, EditorSettings,
+};
 use iyes_progress::prelude::*;
-use map::MapPlugin;
+use loading::LoadingPlugin;
+use map::{picking::MapPickingPlugin, MapPlugin};
+use menu::MenuPlugin;
 use obj::ObjPlugin;
+use playtest::PlaytestPlugin;
+
+/// Toggles the world inspector, with the `dev` feature. Every function key is taken already.
+#[cfg(feature = "dev")]
+pub const WORLD_INSPECTOR_KEY: KeyCode = KeyCode::Pause;
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash, Default, States)]
 pub enum GameState {
     #[default]
     Loading,
     Menu,
+    /// The editor is open, doing whatever [`EditorState`] says.
     Editor,
+    /// Content failed to load, with the reason on screen.
+    LoadError,
+}
+
+/// What the editor is doing while it's open. Systems taking editing input run in
+/// [`Editing`](Self::Editing) alone, and are left alone during dialogs and playtests.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default, SubStates)]
+#[source(GameState = GameState::Editor)]
+pub enum EditorState {
+    #[default]
+    Editing,
+    /// The map is being played in.
+    Playtest,
+    /// A [prompt](editor::prompt::ActivePrompt) is waiting on the user, taking all keyboard input.
+    Dialog,
+}
+
+/// The editor's own view is up: editing, or with a dialog over it, but not playtesting.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct EditorUi;
+impl ComputedStates for EditorUi {
+    type SourceStates = EditorState;
+
+    #[inline]
+    fn compute(state: EditorState) -> Option<Self> {
+        (state != EditorState::Playtest).then_some(Self)
+    }
+}
+
+/// Installs [`GameState`] and the states under it. Entities spawned with a [`StateScoped`] of
+/// either [`GameState`] or [`EditorState`] are despawned as their state is left.
+pub struct GameStatePlugin;
+impl Plugin for GameStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GameState>()
+            .add_sub_state::<EditorState>()
+            .add_computed_state::<EditorUi>()
+            .enable_state_scoped_entities::<GameState>()
+            .enable_state_scoped_entities::<EditorState>();
+    }
+}
+
+/// The directory on disk assets are read from, and maps, brush groups, and editor state are written
+/// into. Inserted by [`build_app`] from [`AppConfig::asset_root`].
+#[derive(Resource, Clone, Debug, Deref)]
+pub struct AssetRoot(pub PathBuf);
+impl AssetRoot {
+    /// The directory an [`AssetPlugin::file_path`] of `file_path` reads from.
+    #[inline]
+    pub fn new(file_path: &str) -> Self {
+        Self(FileAssetReader::get_base_path().join(file_path))
+    }
+}
+
+impl Default for AssetRoot {
+    #[inline]
+    fn default() -> Self {
+        Self::new(&AssetPlugin::default().file_path)
+    }
+}
+
+/// How [`build_app`] sets the app up.
+#[derive(Clone)]
+pub struct AppConfig {
+    pub title: String,
+    pub present_mode: PresentMode,
+    /// Most frames drawn per second, or `None` for as many as the present mode allows. Only kept
+    /// with the editor.
+    pub frame_cap: Option<f32>,
+    /// Logical size of the window, or `None` for the platform's default.
+    pub resolution: Option<Vec2>,
+    /// Physical position of the window's top-left corner, or `None` for the platform's default.
+    pub position: Option<IVec2>,
+    pub maximized: bool,
+    /// Whether the window covers its monitor, borderless.
+    pub fullscreen: bool,
+    /// What to enter once content has loaded: [`GameState::Menu`], or [`GameState::Editor`] to skip
+    /// it.
+    pub after_loading: GameState,
+    /// Directory assets are read from, or `None` for Bevy's default `assets`.
+    pub asset_root: Option<String>,
+    /// Units per meter the physics engine scales its tolerances by.
+    pub length_unit: f32,
+    pub atlas: TileAtlasSettings,
+    /// Settings of the editor, or `None` to leave out the editor along with the menu and playtests
+    /// leading in and out of it.
+    pub editor: Option<EditorSettings>,
+    /// What the editor opens once it starts, rather than reopening the last session's map.
+    pub open: Option<SessionRequest>,
+    /// Where log output is kept besides stdout, or `None` for nowhere.
+    pub log_file: Option<LogFileConfig>,
+}
+
+impl Default for AppConfig {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            title: TITLE.into(),
+            present_mode: PresentMode::AutoNoVsync,
+            frame_cap: None,
+            resolution: None,
+            position: None,
+            maximized: false,
+            fullscreen: false,
+            after_loading: GameState::Menu,
+            asset_root: None,
+            length_unit: 2.0,
+            atlas: default(),
+            editor: Some(default()),
+            open: None,
+            log_file: Some(default()),
+        }
+    }
 }
 
+/// Runs the app as configured by command-line arguments, reporting panics to a crash report.
 #[inline]
-pub fn run() {
+pub fn run() -> ExitCode {
+    crash::install_panic_hook();
+    cli::run_args(std::env::args().skip(1))
+}
+
+#[inline]
+pub fn run_with(config: AppConfig) -> AppExit {
+    build_app(config).run()
+}
+
+/// Sets the app up without running it, so it may be extended or updated by hand.
+pub fn build_app(config: AppConfig) -> App {
+    let mut window = Window {
+        present_mode: config.present_mode,
+        title: config.title,
+        ..default()
+    };
+
+    if let Some(resolution) = config.resolution {
+        window.resolution = WindowResolution::new(resolution.x, resolution.y);
+    }
+
+    if let Some(position) = config.position {
+        window.position = WindowPosition::At(position);
+    }
+
+    // Set before the window is created, so it doesn't visibly jump once restored.
+    if config.maximized {
+        window.set_maximized(true);
+    }
+
+    if config.fullscreen {
+        window.mode = WindowMode::BorderlessFullscreen;
+    }
+
+    let file_path = config.asset_root.unwrap_or_else(|| AssetPlugin::default().file_path);
     let mut app = App::new();
-    app.add_plugins((
-        DefaultPlugins.set(ImagePlugin::default_nearest()).set(WindowPlugin {
-            primary_window: Some(Window {
-                present_mode: PresentMode::AutoNoVsync,
-                title: "Mnemonic".into(),
+    app.insert_resource(AssetRoot::new(&file_path));
+    if let Some(log_file) = config.log_file {
+        app.insert_resource(log_file);
+    }
+
+    app.insert_resource(config.atlas).add_plugins((
+        DefaultPlugins
+            .set(ImagePlugin::default_nearest())
+            .set(LogPlugin {
+                custom_layer: crash::log_layer,
+                ..default()
+            })
+            .set(AssetPlugin {
+                file_path,
+                ..default()
+            })
+            .set(WindowPlugin {
+                primary_window: Some(window),
+                ..default()
+            })
+            .set(RenderPlugin {
+                // Takes whatever the adapter supports, including the line polygon mode of the editor's
+                // wireframe view where there is one. Requiring it fails outright on WebGPU, GLES, and
+                // Android.
+                render_creation: WgpuSettings {
+                    priority: WgpuSettingsPriority::Functionality,
+                    ..default()
+                }
+                .into(),
                 ..default()
             }),
-            ..default()
-        }),
-        PhysicsPlugins::default().with_length_unit(2.0),
-        #[cfg(feature = "dev")]
+        WireframePlugin,
+        FrameTimeDiagnosticsPlugin,
+        PhysicsPlugins::default().with_length_unit(config.length_unit),
         PhysicsDebugPlugin::default(),
+        #[cfg(feature = "dev")]
+        content::debug::AtlasDebugPlugin,
         DefaultPickingPlugins,
+        ContentPlugin,
+        BuildDiagnosticsPlugin,
+        LoadingPlugin,
         MapPlugin,
+        MapPickingPlugin,
         ObjPlugin,
-        EditorPlugin,
+        CharacterPlugin,
     ))
-    .init_state::<GameState>()
-    .add_plugins(ProgressPlugin::new(GameState::Loading).continue_to(GameState::Editor))
+    .add_plugins(GameStatePlugin)
+    .add_plugins(ProgressPlugin::new(GameState::Loading).continue_to(config.after_loading))
+    // Failures are caught by `LoadingPlugin` rather than the loader, whose own failure handling
+    // can't be retried.
     .add_loading_state(
         LoadingState::new(GameState::Loading)
-            .load_collection::<Tiles>()
-            .init_resource::<TileTexture>(),
+            .load_collection::<TileFolder>()
+            .init_resource::<TileCatalog>(),
     );
 
+    if let Some(mut editor) = config.editor {
+        editor.present_mode = config.present_mode;
+        editor.frame_cap = config.frame_cap;
+        app.insert_resource(editor)
+            .insert_resource(EditorSession {
+                pending: config.open,
+                ..default()
+            })
+            .add_plugins((EditorPlugin, MenuPlugin, PlaytestPlugin));
+    }
+
     #[cfg(feature = "dev")]
     {
         fn toggle_debug(mut mode: ResMut<DebugPickingMode>) {
@@ -58,11 +291,16 @@ pub fn run() {
             }
         }
 
-        app.insert_resource(DebugPickingMode::Disabled).add_systems(
-            PreUpdate,
-            toggle_debug.run_if(bevy::input::common_conditions::input_just_pressed(KeyCode::F5)),
-        );
+        app.insert_resource(DebugPickingMode::Disabled)
+            .add_systems(
+                PreUpdate,
+                toggle_debug.run_if(bevy::input::common_conditions::input_just_pressed(KeyCode::F5)),
+            )
+            .add_plugins(
+                bevy_inspector_egui::quick::WorldInspectorPlugin::new()
+                    .run_if(bevy::input::common_conditions::input_toggle_active(false, WORLD_INSPECTOR_KEY)),
+            );
     }
 
-    app.run();
+    app
 }
@@ -0,0 +1,299 @@
+use std::any::TypeId;
+
+use bevy::{
+    app::AppExit,
+    asset::{AssetPath, LoadedFolder, RecursiveDependencyLoadState, UntypedAssetLoadFailedEvent},
+    prelude::*,
+};
+use iyes_progress::prelude::*;
+
+use crate::{
+    content::{manifest::TileCatalog, LoadError, TileFolder, TileTexture, Tiles},
+    menu::{highlight_buttons, spawn_button, MenuButton},
+    GameState,
+};
+
+const BAR_BACKGROUND: Color = Color::srgb(0.15, 0.15, 0.18);
+const BAR_FILL: Color = Color::srgb(0.45, 0.55, 0.85);
+
+/// Shows how far along loading is while in [`GameState::Loading`]. Any asset failing to load
+/// leaves for [`GameState::LoadError`], whose panel tells why. Leaving it retries the assets that
+/// failed.
+pub struct LoadingPlugin;
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FailedAssets>()
+            .add_systems(OnEnter(GameState::Loading), init_loading_screen)
+            .add_systems(OnEnter(GameState::LoadError), init_load_error)
+            .add_systems(OnExit(GameState::LoadError), retry_failed_assets)
+            .add_systems(
+                Update,
+                watch_asset_failures
+                    .track_progress()
+                    .run_if(in_state(GameState::Loading)),
+            )
+            .add_systems(
+                Update,
+                (press_load_error_buttons, highlight_buttons).run_if(in_state(GameState::LoadError)),
+            )
+            .add_systems(
+                Last,
+                update_loading_screen
+                    .after(TrackedProgressSet)
+                    .run_if(in_state(GameState::Loading).and_then(resource_exists::<ProgressCounter>)),
+            );
+    }
+}
+
+/// The fill of the progress bar, as wide as the share of work done.
+#[derive(Component, Copy, Clone, Default)]
+pub struct LoadingBar;
+
+/// What's being loaded, and how many of its parts are done.
+#[derive(Component, Copy, Clone, Default)]
+pub struct LoadingText;
+
+/// Assets that failed to load, reloaded by [`retry_failed_assets`].
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct FailedAssets(pub Vec<(UntypedAssetId, AssetPath<'static>)>);
+
+/// Assets being loaded again after failing, holding the tile texture from being built until they
+/// all have.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct ReloadingAssets(pub Vec<UntypedAssetId>);
+
+/// What's being loaded, told by how far loading got.
+#[inline]
+pub fn loading_stage(folder_loaded: bool, texture_built: bool) -> &'static str {
+    match (folder_loaded, texture_built) {
+        (false, _) => "Loading tile files",
+        (true, false) => "Packing the tile atlas",
+        (true, true) => "Starting",
+    }
+}
+
+fn init_loading_screen(mut commands: Commands) {
+    commands.spawn((Camera2dBundle::default(), StateScoped(GameState::Loading)));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            },
+            StateScoped(GameState::Loading),
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section("Mnemonic", TextStyle {
+                    font_size: 48.0,
+                    ..default()
+                })
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(16.0)),
+                    ..default()
+                }),
+            );
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(320.0),
+                        height: Val::Px(12.0),
+                        ..default()
+                    },
+                    background_color: BAR_BACKGROUND.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: BAR_FILL.into(),
+                            ..default()
+                        },
+                        LoadingBar,
+                    ));
+                });
+
+            parent.spawn((
+                TextBundle::from_section("", TextStyle {
+                    font_size: 16.0,
+                    color: Color::srgb(0.6, 0.6, 0.6),
+                    ..default()
+                }),
+                LoadingText,
+            ));
+        });
+}
+
+fn update_loading_screen(
+    progress: Res<ProgressCounter>,
+    folder: Option<Res<TileFolder>>,
+    texture: Option<Res<TileTexture>>,
+    mut bars: Query<&mut Style, With<LoadingBar>>,
+    mut texts: Query<&mut Text, With<LoadingText>>,
+) {
+    let Progress { done, total } = progress.progress();
+    let share = match total {
+        0 => 0.0,
+        _ => done.min(total) as f32 / total as f32,
+    };
+
+    for mut style in &mut bars {
+        style.width = Val::Percent(share * 100.0);
+    }
+
+    let stage = loading_stage(folder.is_some(), texture.is_some());
+    for mut text in &mut texts {
+        let value = format!("{stage}... ({done}/{total})");
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+/// Shows why loading failed, with the choice to retry or quit.
+fn init_load_error(mut commands: Commands, error: Option<Res<LoadError>>) {
+    let (path, message) = error.map_or_else(
+        || (None, "An asset failed to load; see the log for details.".into()),
+        |error| (error.path.clone(), error.message.clone()),
+    );
+
+    commands.spawn((Camera2dBundle::default(), StateScoped(GameState::LoadError)));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            },
+            StateScoped(GameState::LoadError),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Couldn't load the game", TextStyle {
+                font_size: 32.0,
+                ..default()
+            }));
+            if let Some(path) = path {
+                parent.spawn(TextBundle::from_section(path, TextStyle {
+                    font_size: 18.0,
+                    ..default()
+                }));
+            }
+            parent.spawn(
+                TextBundle::from_section(message, TextStyle {
+                    font_size: 18.0,
+                    color: Color::srgb(1.0, 0.5, 0.5),
+                    ..default()
+                })
+                .with_style(Style {
+                    max_width: Val::Percent(80.0),
+                    margin: UiRect::bottom(Val::Px(16.0)),
+                    ..default()
+                }),
+            );
+
+            spawn_button(parent, MenuButton::Retry);
+            spawn_button(parent, MenuButton::Quit);
+        });
+}
+
+fn press_load_error_buttons(
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for (&interaction, button) in &buttons {
+        match (interaction, button) {
+            (Interaction::Pressed, MenuButton::Retry) => next_state.set(GameState::Loading),
+            (Interaction::Pressed, MenuButton::Quit) => {
+                exit.send(AppExit::Success);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Leaves for [`GameState::LoadError`] as soon as any asset fails to load, naming it. Until then,
+/// holds loading while assets being retried are still loading.
+pub fn watch_asset_failures(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    mut failures: EventReader<UntypedAssetLoadFailedEvent>,
+    mut failed: ResMut<FailedAssets>,
+    reloading: Option<ResMut<ReloadingAssets>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) -> Progress {
+    let mut first = None;
+    for e in failures.read() {
+        error!("Couldn't load `{}`: {}", e.path, e.error);
+        first.get_or_insert(LoadError {
+            path: Some(e.path.to_string()),
+            message: e.error.to_string(),
+        });
+
+        // Folders are loaded again by the loading state itself.
+        if e.id.type_id() != TypeId::of::<LoadedFolder>() {
+            failed.push((e.id, e.path.clone()));
+        }
+    }
+
+    if let Some(error) = first {
+        commands.insert_resource(error);
+        next_state.set(GameState::LoadError);
+        return false.into()
+    }
+
+    let Some(mut reloading) = reloading else { return true.into() };
+    reloading.retain(|&id| {
+        !matches!(
+            server.get_recursive_dependency_load_state(id),
+            Some(RecursiveDependencyLoadState::Loaded)
+        )
+    });
+
+    if reloading.is_empty() {
+        commands.remove_resource::<ReloadingAssets>();
+    }
+
+    false.into()
+}
+
+/// Loads again every asset that failed, before loading starts over. Tiles gathered from before are
+/// dropped, to be gathered again once the tile folder has loaded.
+pub fn retry_failed_assets(mut commands: Commands, server: Res<AssetServer>, mut failed: ResMut<FailedAssets>) {
+    commands.remove_resource::<LoadError>();
+    commands.remove_resource::<TileCatalog>();
+    commands.remove_resource::<Tiles>();
+
+    let mut reloading = Vec::with_capacity(failed.len());
+    for (id, path) in failed.drain(..) {
+        if !reloading.contains(&id) {
+            server.reload(path);
+            reloading.push(id);
+        }
+    }
+
+    if !reloading.is_empty() {
+        commands.insert_resource(ReloadingAssets(reloading));
+    }
+}
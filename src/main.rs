@@ -1,9 +1,11 @@
+use std::process::ExitCode;
+
 use bevy::prelude::*;
 
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
 #[bevy_main]
-fn main() {
-    mnemonic::run();
+fn main() -> ExitCode {
+    mnemonic::run()
 }
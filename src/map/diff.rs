@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+use nonmax::NonMaxU8;
+
+use super::{orientation::TileOrientation, prop::Prop, Map, MapCell};
+use crate::obj::def::Obj;
+
+/// A single reversible change to a map.
+#[derive(Clone, PartialEq, Debug)]
+pub enum MapEdit {
+    /// A cell changed.
+    Set { cell: UVec3, from: MapCell, to: MapCell },
+    /// The map was resized; keeps the whole previous layout, as shrinking discards cells.
+    Resize {
+        from: UVec3,
+        to: UVec3,
+        offset: IVec3,
+        tiles: Vec<Option<NonMaxU8>>,
+        orientations: Vec<TileOrientation>,
+    },
+    /// The tile set changed, along with the handles keeping its tiles loaded.
+    TileSet {
+        from: Vec<String>,
+        from_handles: Vec<Handle<Obj>>,
+        to: Vec<String>,
+        to_handles: Vec<Handle<Obj>>,
+    },
+    /// The props changed; keeps them all, as there are few.
+    Props { from: Vec<Prop>, to: Vec<Prop> },
+}
+
+/// An ordered list of changes made to a map, which can be applied again or reverted.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct MapDiff {
+    pub edits: Vec<MapEdit>,
+}
+
+impl MapDiff {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// The number of cell writes in the diff.
+    #[inline]
+    pub fn changed_cells(&self) -> usize {
+        self.edits.iter().filter(|edit| matches!(edit, MapEdit::Set { .. })).count()
+    }
+
+    /// Appends the edits of a diff made after this one.
+    #[inline]
+    pub fn extend(&mut self, other: MapDiff) {
+        self.edits.extend(other.edits);
+    }
+
+    /// Redoes the diff on the map it was recorded from, as it was before the diff.
+    pub fn apply(&self, map: &mut Map) {
+        for edit in &self.edits {
+            match *edit {
+                MapEdit::Set { cell, to, .. } => {
+                    map.set_cell(cell, to);
+                }
+                MapEdit::Resize { to, offset, .. } => {
+                    map.resize(to, offset);
+                }
+                MapEdit::TileSet {
+                    ref to, ref to_handles, ..
+                } => {
+                    map.tile_set.clone_from(to);
+                    map.tile_handles.clone_from(to_handles);
+                }
+                MapEdit::Props { ref to, .. } => {
+                    map.props.clone_from(to);
+                }
+            }
+        }
+    }
+
+    /// Undoes the diff on the map it was recorded from, as it was after the diff.
+    pub fn revert(&self, map: &mut Map) {
+        for edit in self.edits.iter().rev() {
+            match edit {
+                &MapEdit::Set { cell, from, .. } => {
+                    map.set_cell(cell, from);
+                }
+                MapEdit::Resize {
+                    from,
+                    offset,
+                    tiles,
+                    orientations,
+                    ..
+                } => {
+                    map.size = *from;
+                    map.tiles.0.clone_from(tiles);
+                    map.orientations.clone_from(orientations);
+
+                    let shift = offset.as_vec3() * map.tile_size;
+                    for prop in &mut map.props {
+                        prop.transform.translation -= shift;
+                    }
+                }
+                MapEdit::TileSet { from, from_handles, .. } => {
+                    map.tile_set.clone_from(from);
+                    map.tile_handles.clone_from(from_handles);
+                }
+                MapEdit::Props { from, .. } => {
+                    map.props.clone_from(from);
+                }
+            }
+        }
+    }
+}
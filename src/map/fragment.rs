@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use nonmax::NonMaxU8;
+use serde::{Deserialize, Serialize};
+
+use super::{cell_of, diff::MapDiff, orientation::TileOrientation, Map, MapCell};
+use crate::content::Tiles;
+
+/// A box of cells cut out of a map, referring to tiles by key so it can be stamped into any map.
+#[derive(Clone, Eq, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub struct MapFragment {
+    pub tile_set: Vec<String>,
+    pub tiles: Vec<Option<NonMaxU8>>,
+    pub orientations: Vec<TileOrientation>,
+    pub size: UVec3,
+}
+
+impl MapFragment {
+    #[inline]
+    pub fn index(&self, cell: UVec3) -> Option<usize> {
+        cell.cmplt(self.size)
+            .all()
+            .then(|| (cell.x + (cell.y + cell.z * self.size.y) * self.size.x) as usize)
+    }
+
+    /// Every occupied cell relative to the fragment's minimum corner, with its index into the
+    /// fragment's tile set.
+    pub fn iter_cells(&self) -> impl Iterator<Item = (UVec3, NonMaxU8, TileOrientation)> + '_ {
+        self.tiles.iter().enumerate().filter_map(|(index, &tile)| {
+            Some((
+                cell_of(self.size, index),
+                tile?,
+                self.orientations.get(index).copied().unwrap_or_default(),
+            ))
+        })
+    }
+
+    /// Whether the fragment's lists agree with its size and tile set.
+    pub fn is_valid(&self) -> bool {
+        let len = self.size.x as usize * self.size.y as usize * self.size.z as usize;
+        self.tiles.len() == len &&
+            self.orientations.len() == len &&
+            self.tiles
+                .iter()
+                .flatten()
+                .all(|tile| (tile.get() as usize) < self.tile_set.len())
+    }
+}
+
+impl Map {
+    /// Copies the cells of a region, given by an inclusive minimum and exclusive maximum, clipped
+    /// to the map bounds.
+    #[inline]
+    pub fn extract(&self, min: IVec3, max: IVec3) -> MapFragment {
+        self.extract_where(min, max, |_| true)
+    }
+
+    /// Like [`Map::extract`], leaving the cells `keep` turns down empty.
+    pub fn extract_where(&self, min: IVec3, max: IVec3, mut keep: impl FnMut(IVec3) -> bool) -> MapFragment {
+        let (min, max) = (min.max(IVec3::ZERO), max.min(self.size.as_ivec3()));
+        let size = (max - min).max(IVec3::ZERO).as_uvec3();
+
+        let mut fragment = MapFragment { size, ..default() };
+
+        for z in min.z..max.z {
+            for y in min.y..max.y {
+                for x in min.x..max.x {
+                    let cell = match keep(IVec3::new(x, y, z)) {
+                        true => self.get_cell(UVec3::new(x as u32, y as u32, z as u32)).unwrap_or_default(),
+                        false => MapCell::EMPTY,
+                    };
+                    let tile = cell.tile.and_then(|tile| {
+                        let path = &self.tile_set[tile.get() as usize];
+                        let index = match fragment.tile_set.iter().position(|known| known == path) {
+                            Some(index) => index,
+                            None => {
+                                fragment.tile_set.push(path.clone());
+                                fragment.tile_set.len() - 1
+                            }
+                        };
+
+                        NonMaxU8::new(index as u8)
+                    });
+
+                    fragment.tiles.push(tile);
+                    fragment
+                        .orientations
+                        .push(if tile.is_some() { cell.orientation } else { default() });
+                }
+            }
+        }
+
+        fragment
+    }
+
+    /// Writes the occupied cells of a fragment with its minimum corner at a cell, clipped to the
+    /// map bounds. Tiles the map doesn't use yet are added to its tile set; those that don't
+    /// fit are skipped.
+    pub fn stamp(&mut self, fragment: &MapFragment, at: IVec3, tiles: &Tiles) -> MapDiff {
+        let indices = fragment
+            .tile_set
+            .iter()
+            .map(|name| self.ensure_tile(name, tiles))
+            .collect::<Vec<_>>();
+
+        let mut diff = MapDiff::default();
+        for (cell, tile, orientation) in fragment.iter_cells() {
+            let cell = at + cell.as_ivec3();
+            let Some(tile) = indices.get(tile.get() as usize).copied().flatten() else {
+                continue
+            };
+
+            if self.contains(cell) {
+                self.write(cell.as_uvec3(), MapCell::new(Some(tile), orientation), &mut diff);
+            }
+        }
+
+        diff
+    }
+}
@@ -0,0 +1,102 @@
+use avian3d::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{map::Map, obj::def::Cull, physics::GameLayer};
+
+/// Where a physics query struck a map's collider, in terms of the map's cells.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct MapHit {
+    /// The entity of the map that was hit.
+    pub map: Entity,
+    pub cell: UVec3,
+    /// The face of the cell that was hit; faces that aren't along an axis, such as a ramp's slope,
+    /// count as the face they lean towards most.
+    pub face: Cull,
+    /// World-space point of the hit.
+    pub point: Vec3,
+    /// World-space normal at the hit.
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl MapHit {
+    /// The cell and face of a map struck at a world-space point with the given normal, or `None`
+    /// if it's out of the map's bounds. Points exactly on the boundary between cells, as hits on
+    /// flush tile faces are, belong to the cell the normal points out of.
+    pub fn from_ray_hit(map_trns: &GlobalTransform, map: &Map, hit_point: Vec3, hit_normal: Vec3) -> Option<(UVec3, Cull)> {
+        let affine = map_trns.affine();
+        let local = affine.inverse().transform_point3(hit_point);
+        // Normals transform by the inverse transpose, so back by the transpose.
+        let normal = affine.matrix3.transpose().mul_vec3(hit_normal).normalize_or_zero();
+
+        // Nudged into the struck cell by a sliver of its size, far less than any tile's thickness.
+        let cell = map.cell_at(local - normal * map.tile_size.min_element() * 1e-3);
+        map.contains(cell)
+            .then(|| (cell.as_uvec3(), Self::face(normal * map.tile_size)))
+    }
+
+    /// The outward normal of a single cell face, or zero for anything else.
+    #[inline]
+    pub fn face_normal(face: Cull) -> IVec3 {
+        [
+            (Cull::UP, IVec3::Y),
+            (Cull::DOWN, IVec3::NEG_Y),
+            (Cull::X, IVec3::X),
+            (Cull::NEG_X, IVec3::NEG_X),
+            (Cull::Z, IVec3::Z),
+            (Cull::NEG_Z, IVec3::NEG_Z),
+        ]
+        .into_iter()
+        .find_map(|(other, normal)| (other == face).then_some(normal))
+        .unwrap_or(IVec3::ZERO)
+    }
+
+    /// The cell face a normal faces most, in the space where cells are cubes.
+    pub fn face(normal: Vec3) -> Cull {
+        let abs = normal.abs();
+        match (abs.x >= abs.y && abs.x >= abs.z, abs.y >= abs.z) {
+            (true, _) if normal.x >= 0.0 => Cull::X,
+            (true, _) => Cull::NEG_X,
+            (false, true) if normal.y >= 0.0 => Cull::UP,
+            (false, true) => Cull::DOWN,
+            (false, false) if normal.z >= 0.0 => Cull::Z,
+            (false, false) => Cull::NEG_Z,
+        }
+    }
+}
+
+/// Casts physics queries against the colliders of maps, such as those built for the playtest, and
+/// tells which cells they hit.
+#[derive(SystemParam)]
+pub struct MapSpatialQuery<'w, 's> {
+    spatial: SpatialQuery<'w, 's>,
+    map_assets: Res<'w, Assets<Map>>,
+    maps: Query<'w, 's, (&'static Handle<Map>, &'static GlobalTransform)>,
+    colliders: Query<'w, 's, &'static ColliderParent>,
+}
+
+impl MapSpatialQuery<'_, '_> {
+    /// Casts a world-space ray, returning the first map cell it hits. Only colliders on
+    /// [`GameLayer::Map`] that `filter` also accepts are hit.
+    pub fn raycast_map(&self, ray: Ray3d, max_distance: f32, filter: SpatialQueryFilter) -> Option<MapHit> {
+        let filter = SpatialQueryFilter {
+            mask: filter.mask & LayerMask::from(GameLayer::Map),
+            ..filter
+        };
+
+        let hit = self.spatial.cast_ray(ray.origin, ray.direction, max_distance, true, filter)?;
+        let map = self.colliders.get(hit.entity).map_or(hit.entity, ColliderParent::get);
+        let (handle, map_trns) = self.maps.get(map).ok()?;
+
+        let point = ray.get_point(hit.time_of_impact);
+        let (cell, face) = MapHit::from_ray_hit(map_trns, self.map_assets.get(handle)?, point, hit.normal)?;
+        Some(MapHit {
+            map,
+            cell,
+            face,
+            point,
+            normal: hit.normal,
+            distance: hit.time_of_impact,
+        })
+    }
+}
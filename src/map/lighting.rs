@@ -0,0 +1,300 @@
+use bevy::{
+    prelude::*,
+    render::camera::RenderTarget,
+    utils::{HashMap, HashSet},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    map::{orientation::TileOrientation, Map},
+    obj::def::Obj,
+};
+
+/// How a map is lit: a single sun and ambient light.
+#[derive(Reflect, Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MapLighting {
+    /// Compass direction the sunlight comes from, in degrees counter-clockwise from +Z.
+    pub sun_azimuth: f32,
+    /// Height of the sun above the horizon, in degrees.
+    pub sun_elevation: f32,
+    /// Sun illuminance in lux.
+    pub illuminance: f32,
+    pub shadows: bool,
+    /// Ambient light color in sRGB.
+    pub ambient_color: Vec3,
+    pub ambient_brightness: f32,
+}
+
+impl Default for MapLighting {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            sun_azimuth: -27.5,
+            sun_elevation: 46.8,
+            illuminance: light_consts::lux::AMBIENT_DAYLIGHT,
+            shadows: false,
+            ambient_color: Vec3::ONE,
+            ambient_brightness: 80.0,
+        }
+    }
+}
+
+impl MapLighting {
+    /// The rotation of a directional light shining from the sun toward the map.
+    #[inline]
+    pub fn sun_rotation(&self) -> Quat {
+        Quat::from_euler(
+            EulerRot::YXZ,
+            self.sun_azimuth.to_radians(),
+            -self.sun_elevation.to_radians(),
+            0.0,
+        )
+    }
+
+    #[inline]
+    pub fn ambient_light(&self) -> AmbientLight {
+        AmbientLight {
+            color: Color::srgb(self.ambient_color.x, self.ambient_color.y, self.ambient_color.z),
+            brightness: self.ambient_brightness,
+        }
+    }
+}
+
+/// A light a tile shines from every cell it's placed in, such as a ceiling lamp.
+#[derive(Copy, Clone, PartialEq, Debug, Deserialize)]
+#[serde(default)]
+pub struct TileLight {
+    /// Color in sRGB.
+    pub color: Vec3,
+    /// Luminous power in lumens.
+    pub intensity: f32,
+    pub range: f32,
+    /// Where the light is from the cell's center, turned with the tile.
+    pub offset: Vec3,
+    pub kind: TileLightKind,
+}
+
+impl Default for TileLight {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            color: Vec3::ONE,
+            intensity: 1_000_000.0,
+            range: 20.0,
+            offset: Vec3::ZERO,
+            kind: TileLightKind::Point,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Default, Deserialize)]
+pub enum TileLightKind {
+    #[default]
+    Point,
+    /// A cone along `direction`, turned with the tile, spreading `angle` degrees from it.
+    Spot {
+        #[serde(default = "TileLightKind::down")]
+        direction: Vec3,
+        #[serde(default = "TileLightKind::spread")]
+        angle: f32,
+    },
+}
+
+impl TileLightKind {
+    #[inline]
+    fn down() -> Vec3 {
+        Vec3::NEG_Y
+    }
+
+    #[inline]
+    fn spread() -> f32 {
+        45.0
+    }
+}
+
+#[derive(Resource, Copy, Clone, Debug)]
+pub struct TileLightSettings {
+    /// Most tile lights shining at once, over every map. Those nearest the camera are kept.
+    pub max_lights: usize,
+}
+
+impl Default for TileLightSettings {
+    #[inline]
+    fn default() -> Self {
+        Self { max_lights: 32 }
+    }
+}
+
+/// A light shone by the tile in a cell of its parent map.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct MapLight {
+    pub cell: UVec3,
+    pub orientation: TileOrientation,
+    pub light: TileLight,
+}
+
+impl MapLight {
+    /// Where the light is and faces in its map's local space.
+    pub fn transform(&self, tile_size: Vec3) -> Transform {
+        let trns = Transform::from_translation(self.orientation.apply(self.light.offset) + self.cell.as_vec3() * tile_size);
+        match self.light.kind {
+            TileLightKind::Point => trns,
+            TileLightKind::Spot { direction, .. } => {
+                let direction = self.orientation.apply(direction);
+                let up = match direction.cross(Vec3::Y).length_squared() > 1e-6 {
+                    true => Vec3::Y,
+                    false => Vec3::Z,
+                };
+
+                trns.looking_to(direction, up)
+            }
+        }
+    }
+}
+
+/// Each map's tiles that shine lights, gathered again whenever the map or a tile changes.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct MapLightSources(pub HashMap<AssetId<Map>, Vec<MapLight>>);
+
+pub fn gather_map_lights(
+    mut map_events: EventReader<AssetEvent<Map>>,
+    mut obj_events: EventReader<AssetEvent<Obj>>,
+    maps: Res<Assets<Map>>,
+    objs: Res<Assets<Obj>>,
+    mut sources: ResMut<MapLightSources>,
+) {
+    let mut stale = HashSet::new();
+    for &e in map_events.read() {
+        match e {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } | AssetEvent::LoadedWithDependencies { id } => {
+                stale.insert(id);
+            }
+            AssetEvent::Unused { id } | AssetEvent::Removed { id } => {
+                sources.remove(&id);
+            }
+        }
+    }
+
+    if obj_events.read().count() > 0 {
+        stale.extend(maps.ids());
+    }
+
+    for id in stale {
+        let Some(map) = maps.get(id) else { continue };
+        let lights = map
+            .iter_tiles(&objs)
+            .filter_map(|(cell, orientation, tile)| {
+                Some(MapLight {
+                    cell,
+                    orientation,
+                    light: tile.light?,
+                })
+            })
+            .collect();
+
+        sources.insert(id, lights);
+    }
+}
+
+/// Keeps a light under each map entity for every tile shining one, up to
+/// [`max_lights`](TileLightSettings::max_lights) of those nearest the camera.
+pub fn update_map_lights(
+    mut commands: Commands,
+    settings: Res<TileLightSettings>,
+    sources: Res<MapLightSources>,
+    map_assets: Res<Assets<Map>>,
+    maps: Query<(Entity, &Handle<Map>, &GlobalTransform, Option<&Children>)>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    mut lights: Query<(&MapLight, &mut Transform)>,
+) {
+    let eye = cameras
+        .iter()
+        .find(|(camera, _)| camera.is_active && matches!(camera.target, RenderTarget::Window(..)))
+        .map(|(_, trns)| trns.translation());
+
+    let mut wanted = maps
+        .iter()
+        .filter_map(|(e, handle, trns, _)| Some((e, map_assets.get(handle)?, trns, sources.get(&handle.id())?)))
+        .flat_map(|(e, map, trns, lights)| {
+            lights.iter().map(move |&light| {
+                let local = light.transform(map.tile_size);
+                let distance = eye.map_or(0.0, |eye| trns.transform_point(local.translation).distance_squared(eye));
+                (distance, e, light, local)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if wanted.len() > settings.max_lights {
+        wanted.select_nth_unstable_by(settings.max_lights, |(a, ..), (b, ..)| a.total_cmp(b));
+        wanted.truncate(settings.max_lights);
+    }
+
+    let mut wanted = wanted
+        .into_iter()
+        .map(|(_, e, light, local)| ((e, light.cell), (light, local)))
+        .collect::<HashMap<_, _>>();
+
+    // Lights still wanted as they are stay, following their map's tile size; the rest go.
+    for (e, .., children) in &maps {
+        for &child in children.into_iter().flatten() {
+            let Ok((&current, mut trns)) = lights.get_mut(child) else {
+                continue
+            };
+
+            match wanted.remove(&(e, current.cell)) {
+                Some((light, local)) if light == current => {
+                    if *trns != local {
+                        *trns = local;
+                    }
+                }
+                _ => commands.entity(child).despawn_recursive(),
+            }
+        }
+    }
+
+    for ((e, _), (light, local)) in wanted {
+        let TileLight {
+            color,
+            intensity,
+            range,
+            kind,
+            ..
+        } = light.light;
+
+        let color = Color::srgb(color.x, color.y, color.z);
+        let child = match kind {
+            TileLightKind::Point => commands.spawn((
+                PointLightBundle {
+                    point_light: PointLight {
+                        color,
+                        intensity,
+                        range,
+                        ..default()
+                    },
+                    transform: local,
+                    ..default()
+                },
+                light,
+            )),
+            TileLightKind::Spot { angle, .. } => commands.spawn((
+                SpotLightBundle {
+                    spot_light: SpotLight {
+                        color,
+                        intensity,
+                        range,
+                        outer_angle: angle.to_radians(),
+                        inner_angle: angle.to_radians() * 0.8,
+                        ..default()
+                    },
+                    transform: local,
+                    ..default()
+                },
+                light,
+            )),
+        }
+        .id();
+
+        commands.entity(e).add_child(child);
+    }
+}
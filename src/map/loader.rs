@@ -0,0 +1,240 @@
+use std::io::Error as IoError;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use nonmax::NonMaxU8;
+use ron::{error::SpannedError, Error as RonError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{lighting::MapLighting, orientation::TileOrientation, prop::Prop, Map};
+use crate::content::names::{TileNameTable, TileNames};
+
+#[derive(Error, Debug)]
+pub enum MapError {
+    #[error("Tile count mismatch: expected {expected} for the map size, found {found}.")]
+    SizeMismatch { expected: usize, found: usize },
+    #[error("Tile index out of range: {index} >= {max}.")]
+    OutOfRangeTile { index: usize, max: usize },
+    #[error("Invalid tile orientation: {bits:#05b}.")]
+    InvalidOrientation { bits: u8 },
+    #[error("The tile set is full.")]
+    TileSetFull,
+    #[error("Too many tiles in the tile set: {count} > {}.", u8::MAX)]
+    TooManyTiles { count: usize },
+    #[error("Unknown tiles: {}.", .0.join(", "))]
+    UnresolvedTiles(Vec<String>),
+    #[error(transparent)]
+    Syntax(#[from] SpannedError),
+    #[error(transparent)]
+    Serialize(#[from] RonError),
+    #[error(transparent)]
+    Io(#[from] IoError),
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct MapFile {
+    pub tile_set: Vec<String>,
+    pub tiles: Vec<Option<NonMaxU8>>,
+    /// Orientation of each cell's tile, or empty if none are turned.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub orientations: Vec<TileOrientation>,
+    pub size: UVec3,
+    #[serde(default = "MapFile::default_tile_size")]
+    pub tile_size: Vec3,
+    #[serde(default)]
+    pub lighting: MapLighting,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub props: Vec<Prop>,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl From<&Map> for MapFile {
+    #[inline]
+    fn from(map: &Map) -> Self {
+        Self {
+            tile_set: map.tile_set.clone(),
+            tiles: map.tiles.0.clone(),
+            orientations: match map.orientations.iter().all(|&orientation| orientation == default()) {
+                true => Vec::new(),
+                false => map.orientations.clone(),
+            },
+            size: map.size,
+            tile_size: map.tile_size,
+            lighting: map.lighting,
+            props: map.props.clone(),
+            seed: map.seed,
+        }
+    }
+}
+
+impl MapFile {
+    #[inline]
+    fn default_tile_size() -> Vec3 {
+        Vec3::ONE
+    }
+
+    #[inline]
+    pub fn to_ron(&self) -> Result<String, MapError> {
+        Ok(ron::ser::to_string_pretty(self, default())?)
+    }
+
+    /// Parses and validates a map file.
+    #[inline]
+    pub fn from_ron(text: &str) -> Result<Self, MapError> {
+        let file = ron::from_str::<Self>(text)?;
+        file.validate()?;
+        Ok(file)
+    }
+
+    /// Swaps every occurrence of one tile path for another, adding the new path to the tile set if
+    /// needed. Returns how many cells changed. Works without a running app, for batch migrations.
+    pub fn replace_tile(&mut self, from: &str, to: &str) -> Result<usize, MapError> {
+        let Some(from) = self.tile_set.iter().position(|tile| tile == from) else {
+            return Ok(0)
+        };
+
+        let to = match self.tile_set.iter().position(|tile| tile == to) {
+            Some(to) => to,
+            None if self.tile_set.len() < u8::MAX as usize => {
+                self.tile_set.push(to.into());
+                self.tile_set.len() - 1
+            }
+            None => return Err(MapError::TileSetFull),
+        };
+
+        let (from, to) = (NonMaxU8::new(from as u8), NonMaxU8::new(to as u8));
+        let mut count = 0;
+        for tile in self.tiles.iter_mut().filter(|tile| tile.is_some() && **tile == from) {
+            *tile = to;
+            count += (from != to) as usize;
+        }
+
+        Ok(count)
+    }
+
+    /// Replaces tile asset paths and renamed keys in the tile set with current keys, for maps saved
+    /// before tiles were keyed or renamed since. Returns how many tiles changed.
+    pub fn migrate_tiles(&mut self, names: &TileNameTable) -> usize {
+        let mut count = 0;
+        for tile in &mut self.tile_set {
+            let Some(key) = names.resolve(tile).filter(|&key| key != tile) else {
+                continue
+            };
+
+            *tile = key.into();
+            count += 1;
+        }
+
+        count
+    }
+
+    pub fn validate(&self) -> Result<(), MapError> {
+        let expected = self.size.x as usize * self.size.y as usize * self.size.z as usize;
+        if self.tiles.len() != expected {
+            return Err(MapError::SizeMismatch {
+                expected,
+                found: self.tiles.len(),
+            })
+        }
+
+        if self.tile_set.len() > u8::MAX as usize {
+            return Err(MapError::TooManyTiles {
+                count: self.tile_set.len(),
+            })
+        }
+
+        if !self.orientations.is_empty() && self.orientations.len() != expected {
+            return Err(MapError::SizeMismatch {
+                expected,
+                found: self.orientations.len(),
+            })
+        }
+
+        if let Some(orientation) = self
+            .orientations
+            .iter()
+            .find(|orientation| TileOrientation::from_bits(orientation.bits()).is_none())
+        {
+            return Err(MapError::InvalidOrientation {
+                bits: orientation.bits(),
+            })
+        }
+
+        for &tile in self.tiles.iter().flatten() {
+            let index = tile.get() as usize;
+            if index >= self.tile_set.len() {
+                return Err(MapError::OutOfRangeTile {
+                    index,
+                    max: self.tile_set.len(),
+                })
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Loads maps, resolving their tiles through the shared [`TileNames`]. Until tiles are known, tile
+/// names are loaded as asset paths.
+pub struct MapLoader {
+    pub names: TileNames,
+}
+
+impl AssetLoader for MapLoader {
+    type Asset = Map;
+    type Settings = ();
+    type Error = MapError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _: &'a Self::Settings,
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut file = String::new();
+        reader.read_to_string(&mut file).await?;
+
+        let mut file = MapFile::from_ron(&file)?;
+        let names = self.names.read();
+        if names.is_empty() {
+            let tile_handles = file.tile_set.iter().map(|path| load_context.load(path)).collect();
+            return Ok(Map::from_file(file, tile_handles))
+        }
+
+        let migrated = file.migrate_tiles(&names);
+        if migrated > 0 {
+            info!(
+                "Migrated {migrated} tiles of `{}` to their current keys.",
+                load_context.path().display()
+            );
+        }
+
+        // Prebuilt and unknown tiles can't be loaded by path; the former are filled in once
+        // registered.
+        let tile_handles = file
+            .tile_set
+            .iter()
+            .map(|key| {
+                names
+                    .path(key)
+                    .map_or_else(Handle::default, |path| load_context.load(path.to_string()))
+            })
+            .collect();
+
+        let map = Map::from_file(file, tile_handles);
+        if let Err(e) = map.validate(&names) {
+            warn!("`{}`: {e}", load_context.path().display());
+        }
+
+        Ok(map)
+    }
+
+    #[inline]
+    fn extensions(&self) -> &[&str] {
+        &["mnmap"]
+    }
+}
@@ -1,80 +1,1267 @@
+pub mod diff;
+pub mod fragment;
+pub mod hit;
+pub mod lighting;
+pub mod loader;
+pub mod orientation;
+pub mod picking;
+pub mod prop;
+pub mod tile;
+pub mod trigger;
+
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use avian3d::prelude::*;
 use bevy::{
+    pbr::wireframe::Wireframe,
     prelude::*,
     render::{
         mesh::{Indices, PrimitiveTopology},
         render_asset::RenderAssetUsages,
     },
-    utils::HashMap,
+    utils::{HashMap, HashSet, Instant},
 };
 use nonmax::NonMaxU8;
 
 use crate::{
-    content::{TileTexture, Tiles},
+    content::{
+        array::MapMaterial,
+        names::{TileNameTable, TileNames},
+        properties::{SurfaceTag, TileProperties, TilePropertyTable},
+        render::{TileRenderFlags, TileRenders},
+        TileTexture, TileTextureRebuilt, Tiles,
+    },
+    map::{
+        diff::{MapDiff, MapEdit},
+        lighting::{gather_map_lights, update_map_lights, MapLightSources, MapLighting, TileLightSettings},
+        loader::{MapError, MapFile, MapLoader},
+        orientation::TileOrientation,
+        prop::{build_map_props, sync_map_props, Prop, PropMeshes},
+        tile::{Tile, TileCollider, TileLoader, RAMP_FACES},
+        trigger::{gather_map_triggers, update_map_triggers, MapTriggers},
+    },
     obj::def::{MtlCollection, Obj},
-    GameState,
 };
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash, Default, States)]
 pub enum EditMode {
     #[default]
     Tile,
+    /// Places, moves, and removes props.
+    Object,
 }
 
 pub struct MapPlugin;
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<TileNames>();
+        let names = app.world().resource::<TileNames>().clone();
+
         app.init_state::<EditMode>()
             .init_asset::<Map>()
+            .register_asset_reflect::<Map>()
+            .register_asset_loader(MapLoader { names })
+            .init_asset::<Tile>()
+            .register_asset_loader(TileLoader)
             .init_resource::<MapMeshes>()
+            .register_type::<MapMeshes>()
+            .init_resource::<PendingMapMeshes>()
+            .init_resource::<MapMeshThrottle>()
+            .init_resource::<PageMaterials>()
+            .init_resource::<TileRenders>()
+            .init_resource::<MapStats>()
+            .init_resource::<TileLightSettings>()
+            .init_resource::<MapLightSources>()
+            .init_resource::<MapTriggers>()
+            .init_resource::<TilePropertyTable>()
+            .init_resource::<PropMeshes>()
+            .add_event::<TileTextureRebuilt>()
             .add_systems(
                 PostUpdate,
-                (update_map_mesh, sync_map_mesh)
+                (
+                    (gather_map_lights, update_map_lights).chain(),
+                    (gather_map_triggers, update_map_triggers).chain(),
+                    (sync_map_props, build_map_props).chain(),
+                )
+                    .before(TransformSystem::TransformPropagate),
+            )
+            .add_systems(
+                PostUpdate,
+                (refresh_map_materials, update_map_mesh, sync_map_mesh, sync_page_materials)
                     .chain_ignore_deferred()
-                    .run_if(not(in_state(GameState::Loading))),
+                    .run_if(resource_exists::<TileTexture>),
             );
     }
 }
 
-#[derive(Asset, TypePath)]
+#[derive(Asset, Reflect, Clone)]
 pub struct Map {
+    /// The [`TileKey`](crate::content::names::TileKey) of each tile the map uses.
     pub tile_set: Vec<String>,
-    pub tiles: Vec<Option<NonMaxU8>>,
+    #[dependency]
+    pub tile_handles: Vec<Handle<Obj>>,
+    pub tiles: MapTiles,
+    /// Orientation of each cell's tile, parallel to `tiles`.
+    pub orientations: Vec<TileOrientation>,
     pub size: UVec3,
+    pub tile_size: Vec3,
+    /// Lighting the map was saved with.
+    pub lighting: MapLighting,
+    /// Objects placed off the grid, in the map's local space.
+    pub props: Vec<Prop>,
+    /// Seeds random choices made while editing, such as brush group variants, so they're stable
+    /// per map.
+    pub seed: u64,
+}
+
+/// The tile of each cell as an index into the map's `tile_set`, or `None` if empty. Reflected as a
+/// whole, as [`NonMaxU8`] can't be reflected itself.
+#[derive(Reflect, Clone, Eq, PartialEq, Default, Debug, Deref, DerefMut)]
+#[reflect_value(PartialEq, Default, Debug)]
+pub struct MapTiles(pub Vec<Option<NonMaxU8>>);
+
+/// Everything stored for a single cell.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct MapCell {
+    pub tile: Option<NonMaxU8>,
+    pub orientation: TileOrientation,
+}
+
+impl MapCell {
+    pub const EMPTY: Self = Self {
+        tile: None,
+        orientation: TileOrientation::IDENTITY,
+    };
+
+    #[inline]
+    pub fn new(tile: Option<NonMaxU8>, orientation: TileOrientation) -> Self {
+        Self { tile, orientation }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct GridHit {
+    pub cell: UVec3,
+    /// Outward normal of the struck cell face, or zero if the ray started inside the cell.
+    pub normal: IVec3,
 }
 
 impl Map {
+    /// A map of the given size with no tiles in it.
+    pub fn empty(size: UVec3) -> Self {
+        let len = size.x as usize * size.y as usize * size.z as usize;
+        Self {
+            tile_set: Vec::new(),
+            tile_handles: Vec::new(),
+            tiles: MapTiles(vec![None; len]),
+            orientations: vec![default(); len],
+            size,
+            tile_size: Vec3::ONE,
+            lighting: default(),
+            props: Vec::new(),
+            seed: Self::fresh_seed(),
+        }
+    }
+
+    /// A map read from a file, given a handle for each tile in its tile set.
+    pub fn from_file(file: MapFile, tile_handles: Vec<Handle<Obj>>) -> Self {
+        let MapFile {
+            tile_set,
+            tiles,
+            mut orientations,
+            size,
+            tile_size,
+            lighting,
+            props,
+            seed,
+        } = file;
+
+        orientations.resize(tiles.len(), default());
+        Self {
+            tile_set,
+            tile_handles,
+            tiles: MapTiles(tiles),
+            orientations,
+            size,
+            tile_size,
+            lighting,
+            props,
+            seed,
+        }
+    }
+
+    /// A seed for a newly created map, differing between maps created at different times.
+    #[inline]
+    pub fn fresh_seed() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64)
+    }
+
+    #[inline]
+    pub fn cell(&self, index: usize) -> UVec3 {
+        cell_of(self.size, index)
+    }
+
+    #[inline]
+    pub fn index(&self, cell: UVec3) -> Option<usize> {
+        (cell.x < self.size.x && cell.y < self.size.y && cell.z < self.size.z)
+            .then(|| (cell.x + (cell.y + cell.z * self.size.y) * self.size.x) as usize)
+    }
+
+    #[inline]
+    pub fn contains(&self, cell: IVec3) -> bool {
+        cell.cmpge(IVec3::ZERO).all() && cell.as_uvec3().cmplt(self.size).all()
+    }
+
+    #[inline]
+    pub fn get(&self, cell: UVec3) -> Option<NonMaxU8> {
+        self.tiles.get(self.index(cell)?).copied().flatten()
+    }
+
+    #[inline]
+    pub fn orientation(&self, cell: UVec3) -> TileOrientation {
+        self.index(cell)
+            .and_then(|index| self.orientations.get(index))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn get_cell(&self, cell: UVec3) -> Option<MapCell> {
+        let index = self.index(cell)?;
+        Some(MapCell::new(
+            self.tiles.get(index).copied().flatten(),
+            self.orientations.get(index).copied().unwrap_or_default(),
+        ))
+    }
+
+    /// What the tile in a cell is made of, or `None` if the cell is empty or out of bounds.
+    #[inline]
+    pub fn surface_at(&self, cell: UVec3, properties: &TilePropertyTable) -> Option<SurfaceTag> {
+        let tile = self.get(cell)?;
+        Some(properties.get(self.tile_set.get(tile.get() as usize)?).surface)
+    }
+
+    /// Writes a tile into a cell, keeping its orientation. Returns the previous tile, or `None` if
+    /// the cell is out of bounds.
+    #[inline]
+    pub fn set(&mut self, cell: UVec3, tile: Option<NonMaxU8>) -> Option<Option<NonMaxU8>> {
+        let index = self.index(cell)?;
+        self.tiles.get_mut(index).map(|prev| std::mem::replace(prev, tile))
+    }
+
+    /// Writes everything stored for a cell, returning what it held before, or `None` if the cell is
+    /// out of bounds.
+    #[inline]
+    pub fn set_cell(&mut self, cell: UVec3, value: MapCell) -> Option<MapCell> {
+        let index = self.index(cell)?;
+        let prev = self.get_cell(cell)?;
+
+        self.tiles[index] = value.tile;
+        if let Some(orientation) = self.orientations.get_mut(index) {
+            *orientation = value.orientation;
+        }
+
+        Some(prev)
+    }
+
+    /// Writes a cell, recording the change if there is any. Empty cells are always written
+    /// unoriented.
+    #[inline]
+    pub(crate) fn write(&mut self, cell: UVec3, value: MapCell, diff: &mut MapDiff) {
+        let value = if value.tile.is_none() { MapCell::EMPTY } else { value };
+        match self.set_cell(cell, value) {
+            Some(from) if from != value => diff.edits.push(MapEdit::Set { cell, from, to: value }),
+            _ => {}
+        }
+    }
+
+    /// Writes a tile into every cell of a region given by an inclusive minimum and exclusive
+    /// maximum, clipped to the map bounds.
+    #[inline]
+    pub fn fill_region(&mut self, min: IVec3, max: IVec3, value: MapCell) -> MapDiff {
+        self.fill_region_with(min, max, |_| value)
+    }
+
+    /// Like [`Map::fill_region`], with each cell's value chosen by its coordinate.
+    pub fn fill_region_with(&mut self, min: IVec3, max: IVec3, mut value: impl FnMut(IVec3) -> MapCell) -> MapDiff {
+        let mut diff = MapDiff::default();
+        let (min, max) = (min.max(IVec3::ZERO), max.min(self.size.as_ivec3()));
+        for z in min.z..max.z {
+            for y in min.y..max.y {
+                for x in min.x..max.x {
+                    let cell = IVec3::new(x, y, z);
+                    self.write(cell.as_uvec3(), value(cell), &mut diff);
+                }
+            }
+        }
+
+        diff
+    }
+
+    /// Writes a tile into every given cell, skipping those out of bounds.
+    #[inline]
+    pub fn fill_cells(&mut self, cells: impl IntoIterator<Item = IVec3>, value: MapCell) -> MapDiff {
+        self.fill_cells_with(cells, |_| value)
+    }
+
+    /// Like [`Map::fill_cells`], with each cell's value chosen by its coordinate.
+    pub fn fill_cells_with(
+        &mut self,
+        cells: impl IntoIterator<Item = IVec3>,
+        mut value: impl FnMut(IVec3) -> MapCell,
+    ) -> MapDiff {
+        let mut diff = MapDiff::default();
+        for cell in cells {
+            if self.contains(cell) {
+                self.write(cell.as_uvec3(), value(cell), &mut diff);
+            }
+        }
+
+        diff
+    }
+
+    /// Writes a cell into the region of cells connected to a starting cell along its horizontal
+    /// layer that hold the same tile as it.
+    #[inline]
+    pub fn flood_layer(&mut self, start: UVec3, value: MapCell) -> MapDiff {
+        match self.get(start) == value.tile {
+            true => MapDiff::default(),
+            false => self.flood_layer_with(start, |_| value),
+        }
+    }
+
+    /// Like [`Map::flood_layer`], with each cell's value chosen by its coordinate.
+    pub fn flood_layer_with(&mut self, start: UVec3, mut value: impl FnMut(IVec3) -> MapCell) -> MapDiff {
+        let mut diff = MapDiff::default();
+        for cell in self.flood_layer_cells(start) {
+            self.write(cell.as_uvec3(), value(cell), &mut diff);
+        }
+
+        diff
+    }
+
+    /// The region of cells connected to a starting cell along its horizontal layer that hold the same
+    /// tile as it, which [`Map::flood_layer`] writes into.
+    pub fn flood_layer_cells(&self, start: UVec3) -> Vec<IVec3> {
+        let mut cells = Vec::new();
+        let from = self.get(start);
+        if !self.contains(start.as_ivec3()) {
+            return cells
+        }
+
+        let mut visited = HashSet::new();
+        let mut open = vec![start.as_ivec3()];
+        while let Some(cell) = open.pop() {
+            if !self.contains(cell) || self.get(cell.as_uvec3()) != from || !visited.insert(cell) {
+                continue
+            }
+
+            cells.push(cell);
+            open.extend([IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z].map(|dir| cell + dir));
+        }
+
+        cells
+    }
+
+    /// Every cell holding a tile, or every empty cell if `tile` is `None`.
+    pub fn tile_cells(&self, tile: Option<NonMaxU8>) -> impl Iterator<Item = IVec3> + '_ {
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(move |&(_, &at)| at == tile)
+            .map(|(index, _)| self.cell(index).as_ivec3())
+    }
+
+    /// Swaps one tile for another in every cell of a region given by an inclusive minimum and
+    /// exclusive maximum, keeping their orientations.
+    pub fn replace_tile_region(&mut self, from: NonMaxU8, to: NonMaxU8, min: IVec3, max: IVec3) -> MapDiff {
+        let mut diff = MapDiff::default();
+        let (min, max) = (min.max(IVec3::ZERO), max.min(self.size.as_ivec3()));
+        for z in min.z..max.z {
+            for y in min.y..max.y {
+                for x in min.x..max.x {
+                    let cell = UVec3::new(x as u32, y as u32, z as u32);
+                    if self.get(cell) == Some(from) {
+                        let orientation = self.orientation(cell);
+                        self.write(cell, MapCell::new(Some(to), orientation), &mut diff);
+                    }
+                }
+            }
+        }
+
+        diff
+    }
+
+    /// Swaps one tile for another in every given cell, keeping their orientations and skipping
+    /// those out of bounds.
+    pub fn replace_tile_cells(&mut self, from: NonMaxU8, to: NonMaxU8, cells: impl IntoIterator<Item = IVec3>) -> MapDiff {
+        let mut diff = MapDiff::default();
+        for cell in cells.into_iter().filter(|&cell| self.contains(cell)).map(IVec3::as_uvec3) {
+            if self.get(cell) == Some(from) {
+                let orientation = self.orientation(cell);
+                self.write(cell, MapCell::new(Some(to), orientation), &mut diff);
+            }
+        }
+
+        diff
+    }
+
+    /// Swaps one tile for another in every cell of the map, returning how many cells changed.
+    #[inline]
+    pub fn replace_tile(&mut self, from: NonMaxU8, to: NonMaxU8) -> usize {
+        self.replace_tile_region(from, to, IVec3::ZERO, self.size.as_ivec3())
+            .edits
+            .len()
+    }
+
+    /// Settles the orientations of the cells a diff wrote and the cells beside them on their
+    /// layers: written unrotatable tiles are unturned, and tiles that
+    /// [`AutoOrient`](crate::content::properties::AutoOrient) are turned to their
+    /// neighbors. `properties` are indexed as the tile set is; see [`TilePropertyTable::for_map`].
+    pub fn orient_after(&mut self, diff: &MapDiff, properties: &[TileProperties]) -> MapDiff {
+        let mut written = Vec::new();
+        let mut seen = HashSet::new();
+        for edit in &diff.edits {
+            let &MapEdit::Set { cell, .. } = edit else { continue };
+            written.extend(
+                [IVec3::ZERO, IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z]
+                    .map(|dir| (cell.as_ivec3() + dir, dir == IVec3::ZERO))
+                    .into_iter()
+                    .filter(|&(cell, _)| self.contains(cell) && seen.insert(cell)),
+            );
+        }
+
+        let mut oriented = MapDiff::default();
+        for (cell, direct) in written {
+            let cell = cell.as_uvec3();
+            let Some(tile) = self.get(cell) else { continue };
+            let Some(&properties) = properties.get(tile.get() as usize) else {
+                continue
+            };
+
+            let orientation = self.orientation(cell);
+            let solid = [0, 1, 2, 3].map(|turns| {
+                let neighbor = cell.as_ivec3() + TileOrientation::facing(turns);
+                !self.contains(neighbor) || self.get(neighbor.as_uvec3()).is_some()
+            });
+
+            let orientation = match properties.auto_orient.turns(solid, orientation.turns()) {
+                Some(turns) => orientation.with_turns(turns),
+                None if direct && !properties.rotatable => TileOrientation::IDENTITY,
+                None => continue,
+            };
+
+            self.write(cell, MapCell::new(Some(tile), orientation), &mut oriented);
+        }
+
+        oriented
+    }
+
+    /// Finds a tile in the tile set by its key, asset path, or a redirect, appending it if it isn't
+    /// there yet. Returns `None` if the tile is unknown or the tile set is full.
+    pub fn ensure_tile(&mut self, name: &str, tiles: &Tiles) -> Option<NonMaxU8> {
+        let key = tiles.resolve(name)?;
+        let index = match self.tile_set.iter().position(|tile| *tile == key) {
+            Some(index) => index,
+            None => {
+                if self.tile_set.len() > u8::MAX as usize - 1 {
+                    return None
+                }
+
+                self.tile_handles.push(tiles.get(&key)?.clone());
+                self.tile_set.push(key);
+                self.tile_set.len() - 1
+            }
+        };
+
+        NonMaxU8::new(index as u8)
+    }
+
+    /// Checks that every tile in the tile set is known, naming those that aren't.
+    pub fn validate(&self, names: &TileNameTable) -> Result<(), MapError> {
+        let unresolved = self
+            .tile_set
+            .iter()
+            .filter(|tile| names.resolve(tile).is_none())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        match unresolved.is_empty() {
+            true => Ok(()),
+            false => Err(MapError::UnresolvedTiles(unresolved)),
+        }
+    }
+
+    /// Resizes the map, moving the old cell `(0, 0, 0)` to `offset`. Cells that fall out of the new
+    /// bounds are discarded, and new cells are left empty. Props move along with the cells.
+    pub fn resize(&mut self, size: UVec3, offset: IVec3) -> MapDiff {
+        let len = size.x as usize * size.y as usize * size.z as usize;
+        let old_size = std::mem::replace(&mut self.size, size);
+        let old_tiles = std::mem::replace(&mut self.tiles.0, vec![None; len]);
+        let old_orientations = std::mem::replace(&mut self.orientations, vec![default(); len]);
+
+        for (index, &tile) in old_tiles.iter().enumerate() {
+            if tile.is_none() {
+                continue
+            }
+
+            let cell = cell_of(old_size, index).as_ivec3() + offset;
+            if let Some(new_index) = self.contains(cell).then(|| self.index(cell.as_uvec3())).flatten() {
+                self.tiles[new_index] = tile;
+                self.orientations[new_index] = old_orientations.get(index).copied().unwrap_or_default();
+            }
+        }
+
+        let shift = offset.as_vec3() * self.tile_size;
+        for prop in &mut self.props {
+            prop.transform.translation += shift;
+        }
+
+        MapDiff {
+            edits: vec![MapEdit::Resize {
+                from: old_size,
+                to: size,
+                offset,
+                tiles: old_tiles,
+                orientations: old_orientations,
+            }],
+        }
+    }
+
+    /// How many cells hold each tile of the tile set.
+    pub fn tile_usage(&self) -> Vec<usize> {
+        let mut usage = vec![0; self.tile_set.len()];
+        for tile in self.tiles.iter().flatten() {
+            if let Some(count) = usage.get_mut(tile.get() as usize) {
+                *count += 1;
+            }
+        }
+
+        usage
+    }
+
+    /// Drops tiles no cell uses from the tile set, renumbering the cells that hold the rest.
+    pub fn compact_tile_set(&mut self) -> MapDiff {
+        let usage = self.tile_usage();
+        if usage.iter().all(|&count| count > 0) {
+            return default()
+        }
+
+        let mut remap = Vec::with_capacity(usage.len());
+        let mut next = 0u8;
+        for &count in &usage {
+            remap.push(next);
+            next += (count > 0) as u8;
+        }
+
+        let (to, to_handles) = self
+            .tile_set
+            .iter()
+            .zip(&self.tile_handles)
+            .zip(&usage)
+            .filter(|(_, &count)| count > 0)
+            .map(|((path, handle), _)| (path.clone(), handle.clone()))
+            .unzip::<_, _, Vec<_>, Vec<_>>();
+
+        let mut diff = MapDiff {
+            edits: vec![MapEdit::TileSet {
+                from: std::mem::replace(&mut self.tile_set, to.clone()),
+                from_handles: std::mem::replace(&mut self.tile_handles, to_handles.clone()),
+                to,
+                to_handles,
+            }],
+        };
+
+        for index in 0..self.tiles.len() {
+            let Some(tile) = self.tiles[index] else { continue };
+            let renumbered = NonMaxU8::new(remap[tile.get() as usize]);
+            if renumbered != Some(tile) {
+                let cell = MapCell::new(renumbered, self.orientations[index]);
+                self.write(self.cell(index), cell, &mut diff);
+            }
+        }
+
+        diff
+    }
+
+    /// The cell containing a point in the map's local space. Cells are centered on `cell *
+    /// tile_size`.
+    #[inline]
+    pub fn cell_at(&self, local: Vec3) -> IVec3 {
+        (local / self.tile_size + 0.5).floor().as_ivec3()
+    }
+
+    /// The local-space position of a cell's corner with the least coordinates.
+    #[inline]
+    pub fn cell_min(&self, cell: IVec3) -> Vec3 {
+        (cell.as_vec3() - 0.5) * self.tile_size
+    }
+
+    /// The local-space minimum and maximum corners bounding every tile in the map by their
+    /// [`Obj::aabb`], or `None` if the map is empty. Tiles yet to load are bounded by their cells.
+    pub fn aabb(&self, tile_assets: &Assets<Obj>) -> Option<(Vec3, Vec3)> {
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &tile)| {
+                let cell = self.cell(index);
+                let obj = self
+                    .tile_handles
+                    .get(tile?.get() as usize)
+                    .and_then(|handle| tile_assets.get(handle));
+
+                Some(match obj {
+                    // Orientations only swap and negate axes, so the bounds stay axis-aligned.
+                    Some(obj) => {
+                        let (orientation, center) = (self.orientation(cell), cell.as_vec3() * self.tile_size);
+                        let (min, max) = obj.aabb();
+                        let (min, max) = (orientation.apply(min), orientation.apply(max));
+                        (center + min.min(max), center + min.max(max))
+                    }
+                    None => (self.cell_min(cell.as_ivec3()), self.cell_min(cell.as_ivec3() + 1)),
+                })
+            })
+            .reduce(|(min, max), (tile_min, tile_max)| (min.min(tile_min), max.max(tile_max)))
+    }
+
+    /// Walks the cells a local-space ray passes through within the map bounds, returning the first
+    /// occupied one.
+    #[inline]
+    pub fn raycast(&self, ray: Ray3d, max_distance: f32) -> Option<GridHit> {
+        self.raycast_where(ray, max_distance, |_| true)
+    }
+
+    /// Like [`Map::raycast`], but only stops at occupied cells accepted by `solid`.
+    pub fn raycast_where(&self, ray: Ray3d, max_distance: f32, solid: impl Fn(UVec3) -> bool) -> Option<GridHit> {
+        // Work in grid space, where cell `c` spans `[c, c + 1)`.
+        let origin = ray.origin / self.tile_size + 0.5;
+        let dir = *ray.direction / self.tile_size;
+        let size = self.size.as_vec3();
+
+        let mut enter = f32::NEG_INFINITY;
+        let mut enter_axis = None;
+        // Grid space scales the direction along with positions, so `t` still measures local distance.
+        let mut exit = max_distance;
+        for axis in 0..3 {
+            if dir[axis] == 0.0 {
+                if origin[axis] < 0.0 || origin[axis] >= size[axis] {
+                    return None
+                }
+
+                continue
+            }
+
+            let (a, b) = (-origin[axis] / dir[axis], (size[axis] - origin[axis]) / dir[axis]);
+            if a.min(b) > enter {
+                enter = a.min(b);
+                enter_axis = Some(axis);
+            }
+
+            exit = exit.min(a.max(b));
+        }
+
+        if enter > exit || exit < 0.0 {
+            return None
+        }
+
+        let step = dir.signum().as_ivec3();
+        let mut normal = IVec3::ZERO;
+        if let (true, Some(axis)) = (enter > 0.0, enter_axis) {
+            normal[axis] = -step[axis];
+        }
+
+        let mut t = enter.max(0.0);
+        let mut cell = (origin + dir * t)
+            .floor()
+            .as_ivec3()
+            .clamp(IVec3::ZERO, self.size.as_ivec3() - 1);
+
+        let next = (cell + step.max(IVec3::ZERO)).as_vec3();
+        let mut t_max = Vec3::select(dir.cmpeq(Vec3::ZERO), Vec3::INFINITY, (next - origin) / dir);
+        let t_delta = dir.recip().abs();
+
+        while t <= exit && self.contains(cell) {
+            if self.get(cell.as_uvec3()).is_some() && solid(cell.as_uvec3()) {
+                return Some(GridHit {
+                    cell: cell.as_uvec3(),
+                    normal,
+                })
+            }
+
+            let axis = match (t_max.x <= t_max.y, t_max.x <= t_max.z, t_max.y <= t_max.z) {
+                (true, true, _) => 0,
+                (false, _, true) => 1,
+                _ => 2,
+            };
+
+            t = t_max[axis];
+            t_max[axis] += t_delta[axis];
+            cell[axis] += step[axis];
+
+            normal = IVec3::ZERO;
+            normal[axis] = -step[axis];
+        }
+
+        None
+    }
+
+    /// Intersects a local-space ray with the floor plane of a horizontal layer, returning the cell
+    /// it lands in, which may lie outside the map bounds.
+    pub fn layer_cell(&self, ray: Ray3d, layer: i32) -> Option<IVec3> {
+        let floor = self.cell_min(IVec3::new(0, layer, 0)).y;
+        let distance = ray.intersect_plane(Vec3::new(0.0, floor, 0.0), InfinitePlane3d::new(Vec3::Y))?;
+
+        let mut cell = self.cell_at(ray.get_point(distance));
+        cell.y = layer;
+        Some(cell)
+    }
+
     #[inline]
     pub fn iter_tiles<'a>(
         &'a self,
-        tiles: &'a Tiles,
         tile_assets: &'a Assets<Obj>,
-    ) -> impl Iterator<Item = (UVec3, &'a Obj)> {
-        let [width, length, ..] = self.size.to_array();
-        self.tiles.iter().enumerate().filter_map(move |(pos, &tile)| {
+    ) -> impl Iterator<Item = (UVec3, TileOrientation, &'a Obj)> {
+        self.tiles.iter().enumerate().filter_map(move |(index, &tile)| {
             Some((
-                UVec3::new(
-                    pos as u32 % width,
-                    (pos as u32 / width) % length,
-                    pos as u32 / (width * length),
-                ),
-                tile_assets.get(tiles.get(self.tile_set.get(tile?.get() as usize)?)?)?,
+                self.cell(index),
+                self.orientations.get(index).copied().unwrap_or_default(),
+                tile_assets.get(self.tile_handles.get(tile?.get() as usize)?)?,
             ))
         })
     }
+
+    /// Writes the geometry of every ready tile packed into an atlas page, in the cells accepted by
+    /// `include`, into a mesh.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_mesh(
+        &self,
+        mesh: Mesh,
+        tile_assets: &Assets<Obj>,
+        materials: &Assets<MtlCollection>,
+        texture: &TileTexture,
+        layouts: &Assets<TextureAtlasLayout>,
+        page: usize,
+        include: impl Fn(UVec3) -> bool,
+    ) -> Mesh {
+        let tiles = self
+            .iter_tiles(tile_assets)
+            .filter(|&(cell, ..)| include(cell))
+            .filter_map(|(cell, orientation, tile)| {
+                let diffuse = tile.diffuse_texture_at(materials, self.seed, cell)?;
+                let (tile_page, rect) = texture.locate(layouts, diffuse)?;
+                (tile_page == page).then_some((cell, orientation, tile, diffuse, rect))
+            })
+            .collect::<Vec<_>>();
+
+        let mut offsets = Vec::with_capacity(tiles.len());
+        let mut offset = 0u32;
+
+        let mut mesh = mesh
+            .with_inserted_attribute(
+                Mesh::ATTRIBUTE_POSITION,
+                tiles
+                    .iter()
+                    .flat_map(|&(tile_pos, orientation, tile, ..)| {
+                        offsets.push(offset);
+                        offset += tile.positions.len() as u32;
+
+                        tile.positions
+                            .iter()
+                            .map(move |&pos| orientation.apply(pos) + tile_pos.as_vec3() * self.tile_size)
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .with_inserted_attribute(
+                Mesh::ATTRIBUTE_UV_0,
+                tiles
+                    .iter()
+                    .flat_map(|&(.., tile, _, rect)| tile.uvs.iter().map(move |&uv| rect.min + uv * rect.size()))
+                    .collect::<Vec<_>>(),
+            )
+            .with_inserted_attribute(
+                Mesh::ATTRIBUTE_NORMAL,
+                tiles
+                    .iter()
+                    .flat_map(|&(_, orientation, tile, ..)| {
+                        tile.normals.iter().map(move |&normal| orientation.apply(normal))
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .with_inserted_indices(Indices::U32(
+                tiles
+                    .iter()
+                    .zip(offsets)
+                    .flat_map(|(&(_, orientation, tile, ..), offset)| {
+                        // Mirroring turns the faces inside out, so flip their winding back.
+                        let flipped = orientation.flipped();
+                        tile.faces.iter().flat_map(move |&[a, b, c]| {
+                            let [a, b, c] = [a + offset, b + offset, c + offset];
+                            if flipped {
+                                [a, c, b]
+                            } else {
+                                [a, b, c]
+                            }
+                        })
+                    })
+                    .collect(),
+            ));
+
+        // Texture arrays find each tile's layer in the second UV channel.
+        if texture.pages.get(page).is_some_and(|page| page.layers.is_some()) {
+            mesh.insert_attribute(
+                Mesh::ATTRIBUTE_UV_1,
+                tiles
+                    .iter()
+                    .flat_map(|&(.., tile, diffuse, _)| {
+                        let layer = texture.layer(diffuse).unwrap_or(0);
+                        tile.uvs.iter().map(move |_| Vec2::new(layer as f32, 0.0))
+                    })
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        // Normal maps are only sampled along tangents, which pages without them can go without.
+        if !tiles.is_empty() && texture.pages.get(page).is_some_and(|page| page.normal_atlas.is_some()) {
+            if let Err(e) = mesh.generate_tangents() {
+                warn!("Couldn't generate map tangents: {e}");
+            }
+        }
+
+        mesh
+    }
+
+    /// Writes the geometry of every ready tile in a part, in the cells accepted by `include`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_part(
+        &self,
+        mesh: Mesh,
+        tile_assets: &Assets<Obj>,
+        materials: &Assets<MtlCollection>,
+        texture: &TileTexture,
+        layouts: &Assets<TextureAtlasLayout>,
+        renders: &TileRenders,
+        part: MapPart,
+        include: impl Fn(UVec3) -> bool,
+    ) -> Mesh {
+        self.build_mesh(mesh, tile_assets, materials, texture, layouts, part.page, |cell| {
+            let render = self
+                .get(cell)
+                .and_then(|tile| self.tile_set.get(tile.get() as usize))
+                .map(|key| renders.get(key))
+                .unwrap_or_default();
+
+            render == part.render && include(cell)
+        })
+    }
+
+    /// The parts ready tiles are meshed in: every atlas page drawn with the map's own material,
+    /// then the pages of tiles drawn differently, in the order the tile set first uses them.
+    /// Hidden tiles are in none.
+    pub fn parts(
+        &self,
+        tile_assets: &Assets<Obj>,
+        materials: &Assets<MtlCollection>,
+        texture: &TileTexture,
+        layouts: &Assets<TextureAtlasLayout>,
+        renders: &TileRenders,
+    ) -> Vec<MapPart> {
+        let mut parts = (0..texture.pages.len()).map(MapPart::page).collect::<Vec<_>>();
+        for (key, tile) in self.tile_set.iter().zip(&self.tile_handles) {
+            let render = renders.get(key);
+            if render.is_default() || render.hidden {
+                continue
+            }
+
+            // Variants may have been packed into other pages than the tile's own texture.
+            let Some(tile) = tile_assets.get(tile) else { continue };
+            for id in tile.diffuse_textures(materials) {
+                let Some((page, ..)) = texture.locate(layouts, id) else {
+                    continue
+                };
+
+                let part = MapPart { page, render };
+                if !parts.contains(&part) {
+                    parts.push(part);
+                }
+            }
+        }
+
+        parts
+    }
+
+    pub fn is_ready(
+        &self,
+        tile_assets: &Assets<Obj>,
+        materials: &Assets<MtlCollection>,
+        texture: &TileTexture,
+        layouts: &Assets<TextureAtlasLayout>,
+    ) -> bool {
+        self.tile_handles.iter().all(|tile| {
+            tile_assets.get(tile).is_some_and(|tile| {
+                let textures = tile.diffuse_textures(materials);
+                !textures.is_empty() && textures.into_iter().all(|id| texture.locate(layouts, id).is_some())
+            })
+        })
+    }
+
+    /// Builds a compound of the [`TileCollider`] of every loaded tile in the cells accepted by
+    /// `include`, or `None` if none of them is solid. Compounds can't nest triangle meshes, so
+    /// tiles colliding as their triangles add each triangle on its own.
+    #[inline]
+    pub fn build_collider(&self, tile_assets: &Assets<Obj>, include: impl Fn(UVec3) -> bool) -> Option<Collider> {
+        self.collider_of(self.iter_tiles(tile_assets).filter(|&(cell, ..)| include(cell)))
+    }
+
+    fn collider_of<'a>(&self, tiles: impl IntoIterator<Item = (UVec3, TileOrientation, &'a Obj)>) -> Option<Collider> {
+        let mut parts = Vec::new();
+        for (cell, orientation, tile) in tiles {
+            let center = cell.as_vec3() * self.tile_size;
+            // Every shape but the triangles is symmetric across X, so only turns change them.
+            let rotation = orientation.rotation();
+
+            match tile.collider {
+                TileCollider::None => {}
+                TileCollider::Full => parts.push((center, rotation, Collider::cuboid(1.0, 1.0, 1.0))),
+                TileCollider::Slab { height } => parts.push((
+                    center + Vec3::Y * (height - 1.0) / 2.0,
+                    rotation,
+                    Collider::cuboid(1.0, height, 1.0),
+                )),
+                TileCollider::Ramp => {
+                    let corners = RAMP_FACES
+                        .iter()
+                        .flat_map(|(_, corners)| corners.iter().map(|&(pos, _)| pos))
+                        .collect();
+
+                    if let Some(ramp) = Collider::convex_hull(corners) {
+                        parts.push((center, rotation, ramp));
+                    }
+                }
+                TileCollider::Trimesh => parts.extend(tile.face_iter().map(|face| {
+                    let [a, b, c] = face.map(|index| orientation.apply(tile.positions[index]));
+                    (center, Quat::IDENTITY, Collider::triangle(a, b, c))
+                })),
+            }
+        }
+
+        (!parts.is_empty()).then(|| Collider::compound(parts))
+    }
+
+    /// A hash of what each chunk of cells `size` along each side holds, keyed by the chunk's
+    /// coordinate. Chunks holding no tiles are left out. Chunks whose hashes differ between two
+    /// versions of a map changed in between.
+    pub fn chunk_signatures(&self, size: u32) -> HashMap<UVec3, u64> {
+        let mut hashers = HashMap::<UVec3, DefaultHasher>::new();
+        for (index, (tile, orientation)) in self.tiles.iter().zip(&self.orientations).enumerate() {
+            let Some(tile) = tile else { continue };
+            let cell = self.cell(index);
+            let hasher = hashers.entry(cell / size).or_default();
+            (cell, tile.get(), orientation.bits()).hash(hasher);
+        }
+
+        hashers.into_iter().map(|(chunk, hasher)| (chunk, hasher.finish())).collect()
+    }
+
+    /// Builds a collider for every friction among the map's tiles, of the tiles with it. Sensor
+    /// tiles are left out, being [`MapTrigger`](trigger::MapTrigger)s instead.
+    #[inline]
+    pub fn build_colliders(&self, tile_assets: &Assets<Obj>, properties: &TilePropertyTable) -> Vec<(Collider, Friction)> {
+        self.build_colliders_in(tile_assets, properties, UVec3::ZERO, self.size)
+    }
+
+    /// Like [`Map::build_colliders`], of the tiles in a region given by an inclusive minimum and
+    /// exclusive maximum, clipped to the map bounds. Only visits the region's cells, so maps may be
+    /// split into many regions cheaply.
+    pub fn build_colliders_in(
+        &self,
+        tile_assets: &Assets<Obj>,
+        properties: &TilePropertyTable,
+        min: UVec3,
+        max: UVec3,
+    ) -> Vec<(Collider, Friction)> {
+        let frictions = properties
+            .for_map(self)
+            .into_iter()
+            .map(|properties| (!properties.sensor).then_some(properties.friction))
+            .collect::<Vec<_>>();
+
+        let mut groups = Vec::<(f32, Vec<_>)>::new();
+        let max = max.min(self.size);
+        for z in min.z..max.z {
+            for y in min.y..max.y {
+                for x in min.x..max.x {
+                    let cell = UVec3::new(x, y, z);
+                    let Some(tile) = self.get(cell) else { continue };
+                    let (Some(&Some(friction)), Some(obj)) = (
+                        frictions.get(tile.get() as usize),
+                        self.tile_handles
+                            .get(tile.get() as usize)
+                            .and_then(|handle| tile_assets.get(handle)),
+                    ) else {
+                        continue
+                    };
+
+                    let tile = (cell, self.orientation(cell), obj);
+                    match groups.iter_mut().find(|(other, _)| *other == friction) {
+                        Some((_, tiles)) => tiles.push(tile),
+                        None => groups.push((friction, vec![tile])),
+                    }
+                }
+            }
+        }
+
+        groups.sort_unstable_by(|(a, _), (b, _)| a.total_cmp(b));
+        groups
+            .into_iter()
+            .filter_map(|(friction, tiles)| Some((self.collider_of(tiles)?, Friction::new(friction))))
+            .collect()
+    }
 }
 
+#[inline]
+pub(crate) fn cell_of(size: UVec3, index: usize) -> UVec3 {
+    let index = index as u32;
+    UVec3::new(index % size.x, (index / size.x) % size.y, index / (size.x * size.y))
+}
+
+/// A pseudo-random number fixed by a map seed and a cell coordinate, so choices derived from it
+/// come out the same every time the cell is edited.
+#[inline]
+pub fn cell_random(seed: u64, cell: IVec3) -> u64 {
+    // SplitMix64's finalizer over the seed and each coordinate.
+    let mix = |mut x: u64| {
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+        x ^ (x >> 31)
+    };
+
+    [cell.x, cell.y, cell.z].into_iter().fold(mix(seed), |hash, c| {
+        mix(hash.wrapping_add(0x9e3779b97f4a7c15) ^ c as u32 as u64)
+    })
+}
+
+/// Picks an index in proportion to the given weights, ignoring those that aren't positive. The
+/// same random number always picks the same index.
+pub fn pick_weighted(weights: impl Iterator<Item = f32> + Clone, random: u64) -> Option<usize> {
+    let total = weights.clone().filter(|&weight| weight > 0.0).sum::<f32>();
+    if total <= 0.0 {
+        return None
+    }
+
+    // The top 24 bits fill an `f32` mantissa exactly.
+    let mut left = (random >> 40) as f32 / (1u64 << 24) as f32 * total;
+    let mut last = None;
+    for (index, weight) in weights.enumerate().filter(|&(_, weight)| weight > 0.0) {
+        if left < weight {
+            return Some(index)
+        }
+
+        left -= weight;
+        last = Some(index);
+    }
+
+    // Rounding may leave a sliver past the last member.
+    last
+}
+
+/// Transforms a world-space ray into the local space of a map entity.
+#[inline]
+pub fn local_ray(map_trns: &GlobalTransform, ray: Ray3d) -> Option<Ray3d> {
+    let inverse = map_trns.affine().inverse();
+    Some(Ray3d {
+        origin: inverse.transform_point3(ray.origin),
+        direction: Dir3::new(inverse.transform_vector3(*ray.direction)).ok()?,
+    })
+}
+
+/// Each map's meshes, one per [`MapPart`], starting with the first page drawn with the map's own
+/// material.
+#[derive(Resource, Reflect, Default, Deref, DerefMut)]
+#[reflect(Resource)]
+pub struct MapMeshes(pub HashMap<AssetId<Map>, Vec<(MapPart, Handle<Mesh>)>>);
+
 #[derive(Resource, Default, Deref, DerefMut)]
-pub struct MapMeshes(pub HashMap<AssetId<Map>, Handle<Mesh>>);
+pub struct PendingMapMeshes(pub HashSet<AssetId<Map>>);
+
+/// Least time between rebuilds of some maps' meshes, for maps edited faster than they're worth
+/// remeshing. Their changes in between are built together once the time has passed; other maps are
+/// rebuilt as soon as they change.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct MapMeshThrottle {
+    pub intervals: HashMap<AssetId<Map>, Duration>,
+    /// When each map was last built, as of [`Time::elapsed`].
+    built: HashMap<AssetId<Map>, Duration>,
+}
 
+impl MapMeshThrottle {
+    /// Whether a map's meshes must wait before being rebuilt at `now`.
+    #[inline]
+    pub fn holds(&self, id: AssetId<Map>, now: Duration) -> bool {
+        match (self.intervals.get(&id), self.built.get(&id)) {
+            (Some(&interval), Some(&built)) => now < built + interval,
+            _ => false,
+        }
+    }
+
+    #[inline]
+    pub fn mark_built(&mut self, id: AssetId<Map>, now: Duration) {
+        self.built.insert(id, now);
+    }
+}
+
+/// Materials of map parts past the first, each a map's own material showing another atlas page or
+/// drawing tiles differently, keyed by the map's material and the part.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct PageMaterials(pub HashMap<(AssetId<MapMaterial>, MapPart), Handle<MapMaterial>>);
+
+/// The materials every map shares, each showing the first atlas page. Maps spawned without a
+/// material are given the opaque one.
+#[derive(Resource, Clone, Debug)]
+pub struct MapMaterials {
+    pub opaque: Handle<MapMaterial>,
+    /// Shows tiles in their texture's own colors, regardless of lighting.
+    pub unlit: Handle<MapMaterial>,
+}
+
+impl MapMaterials {
+    pub fn new(texture: &TileTexture, materials: &mut Assets<MapMaterial>) -> Self {
+        let [opaque, unlit] = Self::variants(texture).map(|material| materials.add(material));
+        Self { opaque, unlit }
+    }
+
+    /// Points every shared material at the rebuilt atlas in place, so every map shows it at once.
+    pub fn refresh(&self, texture: &TileTexture, materials: &mut Assets<MapMaterial>) {
+        for (handle, material) in [&self.opaque, &self.unlit].into_iter().zip(Self::variants(texture)) {
+            materials.insert(handle, material);
+        }
+    }
+
+    fn variants(texture: &TileTexture) -> [MapMaterial; 2] {
+        let opaque = MapMaterial {
+            base: StandardMaterial {
+                reflectance: 0.0,
+                ..default()
+            },
+            extension: default(),
+        };
+
+        let opaque = match texture.pages.first() {
+            Some(page) => page.material(&opaque),
+            None => opaque,
+        };
+
+        let unlit = MapMaterial {
+            base: StandardMaterial {
+                unlit: true,
+                ..opaque.base.clone()
+            },
+            extension: opaque.extension.clone(),
+        };
+
+        [opaque, unlit]
+    }
+}
+
+/// The tiles of a map on one atlas page that are drawn alike.
+#[derive(Reflect, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct MapPart {
+    pub page: usize,
+    pub render: TileRenderFlags,
+}
+
+impl MapPart {
+    /// The tiles on a page drawn with the map's own material.
+    #[inline]
+    pub fn page(page: usize) -> Self {
+        Self { page, render: default() }
+    }
+
+    /// A copy of a map material drawing this part.
+    pub fn material(&self, base: &MapMaterial, texture: &TileTexture) -> Option<MapMaterial> {
+        let mut material = texture.pages.get(self.page)?.material(base);
+        self.render.apply(&mut material);
+        Some(material)
+    }
+}
+
+/// Draws a part of its parent map past the first, which the map entity draws itself.
+#[derive(Component, Copy, Clone, Debug)]
+pub struct MapPage(pub MapPart);
+
+/// Counters from building map meshes, for judging meshing performance.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct MapStats {
+    /// Sizes of each map's built meshes, over all pages.
+    pub meshes: HashMap<AssetId<Map>, MeshStats>,
+    /// Maps meshed by the last run of [`update_map_mesh`].
+    pub rebuilt: usize,
+    /// Time the last run of [`update_map_mesh`] spent meshing them.
+    pub elapsed: Duration,
+}
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct MeshStats {
+    pub vertices: usize,
+    pub triangles: usize,
+}
+
+/// Gives map entities the mesh of their first part, and a [`MapPage`] child for each other part.
+/// Maps without a material are given the shared opaque one.
+#[allow(clippy::type_complexity)]
 pub fn sync_map_mesh(
     mut commands: Commands,
-    maps: Query<(Entity, &Handle<Map>), Or<(Changed<Handle<Map>>, Without<Handle<Mesh>>)>>,
+    maps: Query<(
+        Entity,
+        Ref<Handle<Map>>,
+        Has<Handle<Mesh>>,
+        Has<Handle<MapMaterial>>,
+        Option<&Children>,
+    )>,
+    pages: Query<(&MapPage, Has<Handle<Mesh>>)>,
     mut removed: RemovedComponents<Handle<Map>>,
     map_meshes: Res<MapMeshes>,
+    shared: Option<Res<MapMaterials>>,
 ) {
-    for (e, map) in &maps {
-        let Some(mesh) = map_meshes.get(&map.id()) else { continue };
-        commands.entity(e).insert(mesh.clone_weak());
+    for (e, map, has_mesh, has_material, children) in &maps {
+        if let Some(shared) = shared.as_ref().filter(|_| !has_material) {
+            commands.entity(e).insert(shared.opaque.clone());
+        }
+
+        let Some(meshes) = map_meshes.get(&map.id()) else { continue };
+        if let Some((_, mesh)) = meshes.first().filter(|_| map.is_changed() || !has_mesh) {
+            commands.entity(e).insert(mesh.clone_weak());
+        }
+
+        let mut missing = meshes.iter().skip(1).map(|&(part, _)| part).collect::<HashSet<_>>();
+        for &child in children.into_iter().flatten() {
+            let Ok((&MapPage(part), has_mesh)) = pages.get(child) else {
+                continue
+            };
+
+            match meshes
+                .iter()
+                .find(|&&(other, _)| other == part)
+                .filter(|_| missing.remove(&part))
+            {
+                None => commands.entity(child).despawn_recursive(),
+                Some((_, mesh)) if map.is_changed() || !has_mesh => {
+                    commands.entity(child).insert(mesh.clone_weak());
+                }
+                Some(..) => {}
+            }
+        }
+
+        for (part, mesh) in meshes.iter().filter(|(part, _)| missing.contains(part)) {
+            let child = commands
+                .spawn((SpatialBundle::default(), mesh.clone_weak(), MapPage(*part)))
+                .id();
+            commands.entity(e).add_child(child);
+        }
     }
 
     for e in removed.read() {
@@ -82,98 +1269,215 @@ pub fn sync_map_mesh(
     }
 }
 
+/// Draws map parts with their map's material and wireframe, showing their own atlas page and
+/// drawing their tiles' way.
+#[allow(clippy::type_complexity)]
+pub fn sync_page_materials(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<MapMaterial>>,
+    tile_texture: Res<TileTexture>,
+    mut page_materials: ResMut<PageMaterials>,
+    mut materials: ResMut<Assets<MapMaterial>>,
+    maps: Query<(&Handle<MapMaterial>, Has<Wireframe>, &Children), With<Handle<Map>>>,
+    pages: Query<(&MapPage, Option<&Handle<MapMaterial>>, Has<Wireframe>)>,
+) {
+    for &e in events.read() {
+        let AssetEvent::Modified { id } = e else { continue };
+        for (&(base, part), derived) in page_materials.iter() {
+            let Some(material) = materials
+                .get(base)
+                .filter(|_| base == id)
+                .and_then(|base| part.material(base, &tile_texture))
+            else {
+                continue
+            };
+
+            materials.insert(derived, material);
+        }
+    }
+
+    for (base, wireframe, children) in &maps {
+        for &child in children {
+            let Ok((&MapPage(part), current, child_wireframe)) = pages.get(child) else {
+                continue
+            };
+
+            let derived = match page_materials.get(&(base.id(), part)) {
+                Some(derived) => derived.clone_weak(),
+                None => {
+                    let Some(material) = materials.get(base).and_then(|base| part.material(base, &tile_texture)) else {
+                        continue
+                    };
+
+                    let derived = materials.add(material);
+                    page_materials.insert((base.id(), part), derived.clone());
+                    derived.clone_weak()
+                }
+            };
+
+            if current != Some(&derived) {
+                commands.entity(child).insert(derived);
+            }
+
+            match (wireframe, child_wireframe) {
+                (true, false) => {
+                    commands.entity(child).insert(Wireframe);
+                }
+                (false, true) => {
+                    commands.entity(child).remove::<Wireframe>();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Points materials showing an atlas page at the rebuilt pages, in place, as auxiliary atlases may
+/// have come or gone.
+pub fn refresh_map_materials(
+    mut events: EventReader<TileTextureRebuilt>,
+    tile_texture: Res<TileTexture>,
+    shared: Option<Res<MapMaterials>>,
+    page_materials: Res<PageMaterials>,
+    mut materials: ResMut<Assets<MapMaterial>>,
+) {
+    if events.read().count() == 0 {
+        return
+    }
+
+    let refreshed = materials
+        .iter()
+        .filter_map(|(id, material)| {
+            let atlas = material
+                .base
+                .base_color_texture
+                .as_ref()
+                .or(material.extension.array.as_ref())?
+                .id();
+            let page = tile_texture.pages.iter().find(|page| page.atlas.id() == atlas)?;
+            Some((id, page.material(material)))
+        })
+        .collect::<Vec<_>>();
+
+    for (id, material) in refreshed {
+        materials.insert(id, material);
+    }
+
+    // The shared materials may not have shown a page yet, if there were no tiles before.
+    if let Some(shared) = shared {
+        shared.refresh(&tile_texture, &mut materials);
+    }
+
+    // Derived again from their refreshed maps' materials, so they keep drawing their tiles' way.
+    for (&(base, part), derived) in page_materials.iter() {
+        if let Some(material) = materials.get(base).and_then(|base| part.material(base, &tile_texture)) {
+            materials.insert(derived, material);
+        }
+    }
+}
+
+/// Meshes maps once they and their tiles are loaded, and again whenever they change, the tile
+/// texture is rebuilt, or tiles are drawn differently. Every change a map went through since it was
+/// last built is built at once, at most once per frame or as often as its [`MapMeshThrottle`] lets it.
+#[allow(clippy::too_many_arguments)]
 pub fn update_map_mesh(
+    time: Res<Time>,
     mut events: EventReader<AssetEvent<Map>>,
+    mut rebuilt: EventReader<TileTextureRebuilt>,
+    server: Res<AssetServer>,
     maps: Res<Assets<Map>>,
-    tiles: Res<Tiles>,
     tile_textures: Res<TileTexture>,
     tile_assets: Res<Assets<Obj>>,
     layouts: Res<Assets<TextureAtlasLayout>>,
     materials: Res<Assets<MtlCollection>>,
+    renders: Res<TileRenders>,
     mut map_meshes: ResMut<MapMeshes>,
+    mut pending: ResMut<PendingMapMeshes>,
+    mut throttle: ResMut<MapMeshThrottle>,
+    mut stats: ResMut<MapStats>,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
-    let layout = layouts.get(&tile_textures.layout).unwrap();
+    stats.rebuilt = 0;
+    if rebuilt.read().count() > 0 || renders.is_changed() {
+        pending.extend(maps.ids());
+    }
+
     for &e in events.read() {
         match e {
+            AssetEvent::Added { id } => {
+                // Maps loaded by the asset server are built once `LoadedWithDependencies` arrives; maps constructed
+                // in code are built as soon as their tiles are ready.
+                if server.get_load_state(id).is_none() {
+                    pending.insert(id);
+                }
+            }
+            AssetEvent::Modified { id } | AssetEvent::LoadedWithDependencies { id } => {
+                pending.insert(id);
+            }
             AssetEvent::Unused { id } | AssetEvent::Removed { id } => {
+                pending.remove(&id);
                 map_meshes.remove(&id);
+                stats.meshes.remove(&id);
+                throttle.built.remove(&id);
             }
-            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
-                let Some(map) = maps.get(id) else { continue };
-                let (handle, mesh) = match map_meshes.remove(&id) {
-                    None => (
-                        None,
-                        Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD),
-                    ),
-                    Some(handle) => match meshes.remove(&handle) {
-                        None => (
-                            None,
-                            Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD),
-                        ),
-                        Some(mesh) => (Some(handle), mesh),
-                    },
-                };
-
-                let mut offsets = Vec::new();
-                let mut offset = 0u32;
-
-                let mesh = mesh
-                    .with_inserted_attribute(
-                        Mesh::ATTRIBUTE_POSITION,
-                        map.iter_tiles(&tiles, &tile_assets)
-                            .flat_map(|(tile_pos, tile)| {
-                                offsets.push(offset);
-                                offset += tile.positions.len() as u32;
-
-                                tile.positions.iter().map(move |&pos| pos + tile_pos.as_vec3())
-                            })
-                            .collect::<Vec<_>>(),
-                    )
-                    .with_inserted_attribute(
-                        Mesh::ATTRIBUTE_UV_0,
-                        map.iter_tiles(&tiles, &tile_assets)
-                            .flat_map(|(.., tile)| {
-                                let material = materials.get(&tile.material).unwrap();
-                                let rect = layout.textures[layout
-                                    .get_texture_index(material[&tile.material_key].diffuse_texture.as_ref().unwrap().id())
-                                    .unwrap()]
-                                .as_rect();
-
-                                let min = rect.min / layout.size.as_vec2();
-                                let scl = rect.max / layout.size.as_vec2() - min;
-
-                                tile.uvs.iter().map(move |&uv| min + uv * scl)
-                            })
-                            .collect::<Vec<_>>(),
-                    )
-                    .with_inserted_attribute(
-                        Mesh::ATTRIBUTE_NORMAL,
-                        map.iter_tiles(&tiles, &tile_assets)
-                            .flat_map(|(.., tile)| tile.normals.iter().copied())
-                            .collect::<Vec<_>>(),
-                    )
-                    .with_inserted_indices(Indices::U32(
-                        map.iter_tiles(&tiles, &tile_assets)
-                            .enumerate()
-                            .flat_map(|(id, (.., tile))| {
-                                let offset = offsets[id];
-                                tile.faces
-                                    .iter()
-                                    .flat_map(move |&[a, b, c]| [a as u32 + offset, b as u32 + offset, c as u32 + offset])
-                            })
-                            .collect(),
-                    ));
-
-                map_meshes.insert_unique_unchecked(id, match handle {
-                    None => meshes.add(mesh),
-                    Some(handle) => {
-                        meshes.insert(&handle, mesh);
-                        handle
-                    }
-                });
-            }
-            _ => {}
         }
     }
+
+    let now = time.elapsed();
+    let start = Instant::now();
+    pending.retain(|&id| {
+        let Some(map) = maps.get(id) else { return false };
+        if throttle.holds(id, now) || !map.is_ready(&tile_assets, &materials, &tile_textures, &layouts) {
+            return true
+        }
+
+        // Parts keep their meshes across rebuilds, so their entities needn't be given new ones.
+        let mut old = map_meshes.remove(&id).unwrap_or_default();
+        let parts = map.parts(&tile_assets, &materials, &tile_textures, &layouts, &renders);
+        let mut handles = Vec::with_capacity(parts.len());
+
+        let mut total = MeshStats::default();
+        for part in parts {
+            let handle = old
+                .iter()
+                .position(|&(other, _)| other == part)
+                .map(|index| old.swap_remove(index).1);
+            let mesh = handle
+                .as_ref()
+                .and_then(|handle| meshes.remove(handle))
+                .unwrap_or_else(|| Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD));
+
+            let mesh = map.build_part(
+                mesh,
+                &tile_assets,
+                &materials,
+                &tile_textures,
+                &layouts,
+                &renders,
+                part,
+                |_| true,
+            );
+            let vertices = mesh.count_vertices();
+            total.vertices += vertices;
+            total.triangles += mesh.indices().map_or(vertices, Indices::len) / 3;
+
+            let handle = match handle {
+                Some(handle) => {
+                    meshes.insert(&handle, mesh);
+                    handle
+                }
+                None => meshes.add(mesh),
+            };
+            handles.push((part, handle));
+        }
+
+        stats.rebuilt += 1;
+        stats.meshes.insert(id, total);
+        throttle.mark_built(id, now);
+        map_meshes.insert_unique_unchecked(id, handles);
+
+        false
+    });
+
+    stats.elapsed = start.elapsed();
 }
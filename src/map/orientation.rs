@@ -0,0 +1,100 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How a tile is turned in its cell: a number of quarter turns counter-clockwise about the up axis,
+/// optionally after mirroring along the X axis.
+#[derive(Reflect, Copy, Clone, Eq, PartialEq, Hash, Default, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TileOrientation(u8);
+
+impl TileOrientation {
+    const TURNS: u8 = 0b011;
+    const FLIP: u8 = 0b100;
+
+    pub const IDENTITY: Self = Self(0);
+
+    #[inline]
+    pub fn new(turns: i32, flipped: bool) -> Self {
+        Self(turns.rem_euclid(4) as u8 | if flipped { Self::FLIP } else { 0 })
+    }
+
+    /// Reads a serialized orientation, or `None` if it has unknown bits set.
+    #[inline]
+    pub fn from_bits(bits: u8) -> Option<Self> {
+        (bits & !(Self::TURNS | Self::FLIP) == 0).then_some(Self(bits))
+    }
+
+    #[inline]
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    #[inline]
+    pub fn turns(self) -> i32 {
+        (self.0 & Self::TURNS) as i32
+    }
+
+    #[inline]
+    pub fn flipped(self) -> bool {
+        self.0 & Self::FLIP != 0
+    }
+
+    /// Turns by a number of further quarter turns, negative for clockwise.
+    #[inline]
+    pub fn rotated(self, turns: i32) -> Self {
+        Self::new(self.turns() + turns, self.flipped())
+    }
+
+    #[inline]
+    pub fn toggled_flip(self) -> Self {
+        Self::new(self.turns(), !self.flipped())
+    }
+
+    /// The orientation seen in a mirror across the map's X axis, where `x` becomes `-x`.
+    #[inline]
+    pub fn mirrored_x(self) -> Self {
+        Self::new(-self.turns(), !self.flipped())
+    }
+
+    /// The orientation seen in a mirror across the map's Z axis, where `z` becomes `-z`.
+    #[inline]
+    pub fn mirrored_z(self) -> Self {
+        Self::new(2 - self.turns(), !self.flipped())
+    }
+
+    /// The orientation with its quarter turns replaced, keeping whether it's flipped.
+    #[inline]
+    pub fn with_turns(self, turns: i32) -> Self {
+        Self::new(turns, self.flipped())
+    }
+
+    /// The direction a number of quarter turns from unturned faces, which is `+Z`.
+    #[inline]
+    pub fn facing(turns: i32) -> IVec3 {
+        Self::new(turns, false).apply(Vec3::Z).round().as_ivec3()
+    }
+
+    #[inline]
+    pub fn rotation(self) -> Quat {
+        Quat::from_rotation_y(self.turns() as f32 * FRAC_PI_2)
+    }
+
+    /// Applies the orientation to a point or direction relative to the tile's center.
+    #[inline]
+    pub fn apply(self, point: Vec3) -> Vec3 {
+        let point = match self.flipped() {
+            true => Vec3::new(-point.x, point.y, point.z),
+            false => point,
+        };
+
+        self.rotation() * point
+    }
+
+    /// The orientation as a transform about the tile's center.
+    #[inline]
+    pub fn transform(self) -> Transform {
+        Transform::from_rotation(self.rotation()).with_scale(Vec3::new(if self.flipped() { -1.0 } else { 1.0 }, 1.0, 1.0))
+    }
+}
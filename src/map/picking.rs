@@ -0,0 +1,452 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_mod_picking::{
+    backend::{prelude::*, ray::RayId},
+    events::{Click, Down, Drag, DragEnd, DragStart, Move, Out, Over, Pointer, Up},
+    focus::HoverMap,
+    pointer::PointerButton,
+};
+
+use crate::{
+    map::{hit::MapHit, local_ray, GridHit, Map},
+    obj::def::Cull,
+};
+
+/// A picking backend striking maps through their grid rather than their meshes, so every hit leads
+/// back to the cell it struck.
+pub struct MapPickingPlugin;
+impl Plugin for MapPickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MapPicks>()
+            .init_resource::<TilePointers>()
+            .init_resource::<PointerOverUi>()
+            .add_event::<TilePointerEvent>()
+            .add_systems(PreUpdate, update_map_hits.in_set(PickSet::Backend))
+            .add_systems(
+                PreUpdate,
+                (update_pointer_over_ui, send_tile_pointer_events)
+                    .chain()
+                    .in_set(PickSet::PostFocus),
+            );
+    }
+}
+
+/// Cells past the map bounds a [`PickGround`] still catches pointers within.
+pub const GROUND_MARGIN: i32 = 4;
+/// Least vertical part of a ray's local-space direction for a [`PickGround`] to catch it; flatter
+/// rays would meet the floor too far off to point at anything.
+pub const GROUND_MIN_SLOPE: f32 = 0.02;
+
+/// Highest layer of a map pointers may strike cells on; cells above it are passed through.
+#[derive(Component, Copy, Clone, Eq, PartialEq, Default, Debug, Deref, DerefMut)]
+pub struct PickCeiling(pub u32);
+
+/// Layer of a map whose floor catches pointers striking no cell, so even empty maps may be pointed
+/// at. Pointers over UI aren't caught.
+#[derive(Component, Copy, Clone, Eq, PartialEq, Default, Debug, Deref, DerefMut)]
+pub struct PickGround(pub u32);
+
+/// A pointer's ray through a map entity.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct MapPick {
+    /// The camera the ray was cast from.
+    pub camera: Entity,
+    /// The pointer ray in the map's local space.
+    pub ray: Ray3d,
+    /// The first cell the ray strikes, if any.
+    pub hit: Option<GridHit>,
+    /// If the ray strikes no cell, the cell above the [`PickGround`] floor it meets instead. It may
+    /// lie outside the map bounds, by up to [`GROUND_MARGIN`] cells.
+    pub ground: Option<IVec3>,
+}
+
+impl MapPick {
+    /// Casts a world-space ray from `camera` through a map, along with the hit data of the face it
+    /// strikes for the picking stack, or of the `ground` floor if it strikes none. Returns `None` if
+    /// the map's transform can't be inverted.
+    pub fn cast(
+        camera: Entity,
+        map: &Map,
+        map_trns: &GlobalTransform,
+        ray: Ray3d,
+        ceiling: Option<PickCeiling>,
+        ground: Option<PickGround>,
+    ) -> Option<(Self, Option<HitData>)> {
+        let local = local_ray(map_trns, ray)?;
+        let hit = map.raycast_where(local, f32::INFINITY, |cell| {
+            ceiling.map_or(true, |PickCeiling(top)| cell.y <= top)
+        });
+
+        let ground = ground
+            .filter(|_| hit.is_none())
+            .and_then(|PickGround(layer)| map.ground_cell(local, layer));
+
+        let face = match (hit, ground) {
+            (Some(hit), _) => Some((map.face_point(local, hit), hit.normal.as_vec3())),
+            (None, Some(cell)) => local
+                .intersect_plane(map.cell_min(cell), InfinitePlane3d::new(Vec3::Y))
+                .map(|distance| (local.get_point(distance), Vec3::Y)),
+            (None, None) => None,
+        };
+
+        let data = face.map(|(point, normal)| {
+            let affine = map_trns.affine();
+            let position = affine.transform_point3(point);
+            // Normals transform by the inverse transpose.
+            let normal = affine.matrix3.inverse().transpose().mul_vec3(normal).normalize_or_zero();
+
+            HitData::new(
+                camera,
+                (position - ray.origin).dot(*ray.direction),
+                Some(position),
+                (normal != Vec3::ZERO).then_some(normal),
+            )
+        });
+
+        Some((
+            Self {
+                camera,
+                ray: local,
+                hit,
+                ground,
+            },
+            data,
+        ))
+    }
+
+    /// The cell face the ray strikes, or the floor of the ground cell it meets within the map bounds.
+    #[inline]
+    pub fn face(&self, map: &Map) -> Option<TileFace> {
+        match (self.hit, self.ground) {
+            (Some(hit), _) => Some(TileFace {
+                cell: hit.cell,
+                face: MapHit::face(hit.normal.as_vec3()),
+                ground: false,
+            }),
+            (None, Some(cell)) => TileFace::ground(map, cell),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Every pointer's ray through every map entity this frame, keyed by the pointer and the map
+/// entity. Pointers over several cameras keep the ray of the last one.
+#[derive(Resource, Clone, Default, Debug, Deref, DerefMut)]
+pub struct MapPicks(pub HashMap<(PointerId, Entity), MapPick>);
+
+impl MapPicks {
+    /// The cell a pointer strikes in a map entity, if any.
+    #[inline]
+    pub fn hit(&self, pointer: PointerId, map: Entity) -> Option<GridHit> {
+        self.get(&(pointer, map)).and_then(|pick| pick.hit)
+    }
+}
+
+impl Map {
+    /// The cell above the floor of a layer that a local-space ray meets, within [`GROUND_MARGIN`]
+    /// cells of the map bounds. Rays running nearly along the floor meet none.
+    pub fn ground_cell(&self, ray: Ray3d, layer: u32) -> Option<IVec3> {
+        if ray.direction.y.abs() < GROUND_MIN_SLOPE {
+            return None
+        }
+
+        let cell = self.layer_cell(ray, layer as i32)?;
+        let margin = IVec3::new(GROUND_MARGIN, 0, GROUND_MARGIN);
+        (cell.cmpge(-margin).all() && cell.cmplt(self.size.as_ivec3() + margin).all()).then_some(cell)
+    }
+
+    /// Where a local-space ray meets the struck face of a cell, in local space. Rays starting inside
+    /// the cell meet it at their origin.
+    pub fn face_point(&self, ray: Ray3d, hit: GridHit) -> Vec3 {
+        let Ok(normal) = Dir3::new(hit.normal.as_vec3()) else {
+            return ray.origin
+        };
+
+        let face = (hit.cell.as_vec3() + *normal * 0.5) * self.tile_size;
+        ray.intersect_plane(face, InfinitePlane3d { normal })
+            .map_or(ray.origin, |distance| ray.get_point(distance))
+    }
+}
+
+/// Casts every pointer's rays through every map entity, recording them in [`MapPicks`] and
+/// reporting the struck cells' faces to the picking stack. Whether pointers are over UI is told by
+/// the last frame's [`PointerOverUi`], as it's only known once every backend has reported.
+#[allow(clippy::type_complexity)]
+pub fn update_map_hits(
+    rays: Res<RayMap>,
+    cameras: Query<&Camera>,
+    maps: Res<Assets<Map>>,
+    map_entities: Query<(
+        Entity,
+        &Handle<Map>,
+        &GlobalTransform,
+        Option<&PickCeiling>,
+        Option<&PickGround>,
+    )>,
+    over_ui: Res<PointerOverUi>,
+    mut picks: ResMut<MapPicks>,
+    mut output: EventWriter<PointerHits>,
+) {
+    picks.clear();
+    for (&RayId { camera, pointer }, &ray) in rays.iter() {
+        let Ok(cam) = cameras.get(camera) else { continue };
+
+        let mut hits = Vec::new();
+        for (e, handle, map_trns, ceiling, ground) in &map_entities {
+            let ground = ground.copied().filter(|_| !over_ui.over(pointer));
+            let Some((pick, data)) = maps
+                .get(handle)
+                .and_then(|map| MapPick::cast(camera, map, map_trns, ray, ceiling.copied(), ground))
+            else {
+                continue
+            };
+
+            picks.insert((pointer, e), pick);
+            hits.extend(data.map(|data| (e, data)));
+        }
+
+        if !hits.is_empty() {
+            output.send(PointerHits::new(pointer, hits, cam.order as f32));
+        }
+    }
+}
+
+/// Whether each pointer is over UI that takes it, such as buttons and panels, rather than the world
+/// behind it.
+#[derive(Resource, Clone, Default, Debug, Deref, DerefMut)]
+pub struct PointerOverUi(pub HashMap<PointerId, bool>);
+
+impl PointerOverUi {
+    #[inline]
+    pub fn over(&self, pointer: PointerId) -> bool {
+        self.get(&pointer).copied().unwrap_or(false)
+    }
+}
+
+/// UI nodes take the pointer if they're interactive or have a visible background; bare text lets
+/// it through to the world.
+pub fn update_pointer_over_ui(
+    hovers: Res<HoverMap>,
+    nodes: Query<(Has<Interaction>, Option<&BackgroundColor>), With<Node>>,
+    mut over_ui: ResMut<PointerOverUi>,
+) {
+    over_ui.clear();
+    for (&pointer, hovered) in hovers.iter() {
+        let over = hovered.keys().any(|&e| {
+            nodes.get(e).is_ok_and(|(interactive, background)| {
+                interactive || background.is_some_and(|background| !background.0.is_fully_transparent())
+            })
+        });
+
+        over_ui.insert(pointer, over);
+    }
+}
+
+/// What a pointer did to a cell of a map.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum TilePointerKind {
+    Over,
+    Out,
+    Down(PointerButton),
+    Up,
+    Click,
+    DragStart,
+    Drag,
+    DragEnd,
+}
+
+/// A cell face struck by a pointer.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct TileFace {
+    pub cell: UVec3,
+    pub face: Cull,
+    /// Whether the pointer struck no cell, but the [`PickGround`] floor of this one.
+    pub ground: bool,
+}
+
+impl TileFace {
+    /// The floor of a ground cell, if it's within the map bounds.
+    #[inline]
+    pub fn ground(map: &Map, cell: IVec3) -> Option<Self> {
+        map.contains(cell).then(|| Self {
+            cell: cell.as_uvec3(),
+            face: Cull::UP,
+            ground: true,
+        })
+    }
+}
+
+/// A pointer event on a map entity, told in terms of the cell face it struck. Pointers sliding
+/// between cells without leaving the map go [`Out`](TilePointerKind::Out) of one before going
+/// [`Over`](TilePointerKind::Over) the next.
+#[derive(Event, Copy, Clone, PartialEq, Debug)]
+pub struct TilePointerEvent {
+    pub pointer: PointerId,
+    /// The map entity.
+    pub entity: Entity,
+    pub map: AssetId<Map>,
+    pub cell: UVec3,
+    pub face: Cull,
+    /// Whether the pointer struck no cell, but the [`PickGround`] floor of `cell`.
+    pub ground: bool,
+    pub kind: TilePointerKind,
+}
+
+impl TilePointerEvent {
+    /// The cell a click on this face targets: the cell in front of it when placing, or the struck
+    /// cell itself otherwise. Faces with no direction, struck from inside their cell, have no cell
+    /// in front. Clicks on the ground target the cell above it either way.
+    #[inline]
+    pub fn click_cell(&self, place: bool) -> Option<IVec3> {
+        let normal = MapHit::face_normal(self.face);
+        match place && !self.ground {
+            true => (normal != IVec3::ZERO).then(|| self.cell.as_ivec3() + normal),
+            false => Some(self.cell.as_ivec3()),
+        }
+    }
+}
+
+/// The cell face each pointer is over in each map entity, and where each of their drags on a map
+/// entity last was.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct TilePointers {
+    pub hovered: HashMap<(PointerId, Entity), TileFace>,
+    pub dragged: HashMap<(PointerId, Entity), TileFace>,
+}
+
+/// Translates pointer events on map entities into [`TilePointerEvent`]s through the cell faces
+/// their hits lie on, so they may as well be synthesized as come from [`update_map_hits`].
+/// Pointers over UI reach no cells, but drags that started on a map keep following the cells under
+/// them.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn send_tile_pointer_events(
+    (mut overs, mut moves, mut outs): (
+        EventReader<Pointer<Over>>,
+        EventReader<Pointer<Move>>,
+        EventReader<Pointer<Out>>,
+    ),
+    (mut downs, mut ups, mut clicks): (
+        EventReader<Pointer<Down>>,
+        EventReader<Pointer<Up>>,
+        EventReader<Pointer<Click>>,
+    ),
+    (mut drag_starts, mut drags, mut drag_ends): (
+        EventReader<Pointer<DragStart>>,
+        EventReader<Pointer<Drag>>,
+        EventReader<Pointer<DragEnd>>,
+    ),
+    maps: Res<Assets<Map>>,
+    map_entities: Query<(&Handle<Map>, &GlobalTransform)>,
+    (picks, over_ui): (Res<MapPicks>, Res<PointerOverUi>),
+    mut pointers: ResMut<TilePointers>,
+    mut output: EventWriter<TilePointerEvent>,
+) {
+    let map_of = |target: Entity| {
+        let (handle, map_trns) = map_entities.get(target).ok()?;
+        Some((handle.id(), maps.get(handle)?, map_trns))
+    };
+
+    // Pointers striking no cell may still meet the ground, which the hit lies on.
+    let face_of = |pointer, target: Entity, hit: &HitData| {
+        let (id, map, map_trns) = map_of(target)?;
+        let face = match picks.get(&(pointer, target)).filter(|pick| pick.hit.is_none()) {
+            Some(&MapPick { ground: Some(cell), .. }) => TileFace::ground(map, cell)?,
+            _ => {
+                let (cell, face) =
+                    MapHit::from_ray_hit(map_trns, map, hit.position?, hit.normal.unwrap_or(Vec3::ZERO))?;
+                TileFace {
+                    cell,
+                    face,
+                    ground: false,
+                }
+            }
+        };
+
+        Some((id, face))
+    };
+
+    let mut send = |pointer, entity, map, face: TileFace, kind| {
+        output.send(TilePointerEvent {
+            pointer,
+            entity,
+            map,
+            cell: face.cell,
+            face: face.face,
+            ground: face.ground,
+            kind,
+        });
+    };
+
+    // Moving over a map may land on another cell without the pointer leaving it.
+    let hovers = overs
+        .read()
+        .map(|e| (e.pointer_id, e.target, e.hit.clone()))
+        .chain(moves.read().map(|e| (e.pointer_id, e.target, e.hit.clone())))
+        .filter(|&(pointer, ..)| !over_ui.over(pointer));
+    for (pointer, target, hit) in hovers {
+        let Some((map, face)) = face_of(pointer, target, &hit) else { continue };
+        match pointers.hovered.insert((pointer, target), face) {
+            Some(old) if old == face => continue,
+            Some(old) => send(pointer, target, map, old, TilePointerKind::Out),
+            None => {}
+        }
+
+        send(pointer, target, map, face, TilePointerKind::Over);
+    }
+
+    for e in downs.read().filter(|e| !over_ui.over(e.pointer_id)) {
+        if let Some((map, face)) = face_of(e.pointer_id, e.target, &e.hit) {
+            send(e.pointer_id, e.target, map, face, TilePointerKind::Down(e.button));
+        }
+    }
+
+    for e in drag_starts.read().filter(|e| !over_ui.over(e.pointer_id)) {
+        if let Some((map, face)) = face_of(e.pointer_id, e.target, &e.hit) {
+            pointers.dragged.insert((e.pointer_id, e.target), face);
+            send(e.pointer_id, e.target, map, face, TilePointerKind::DragStart);
+        }
+    }
+
+    // Drags carry no hit, so they follow the cell the pointer is over, or the cell it would strike
+    // if UI weren't in the way.
+    for e in drags.read() {
+        let key = (e.pointer_id, e.target);
+        let Some((map, map_asset, _)) = map_of(e.target) else { continue };
+        let behind_ui = || picks.get(&key)?.face(map_asset);
+
+        let Some(at) = pointers.hovered.get(&key).copied().or_else(behind_ui) else { continue };
+        pointers.dragged.insert(key, at);
+        send(e.pointer_id, e.target, map, at, TilePointerKind::Drag);
+    }
+
+    for e in ups.read().filter(|e| !over_ui.over(e.pointer_id)) {
+        if let Some((map, face)) = face_of(e.pointer_id, e.target, &e.hit) {
+            send(e.pointer_id, e.target, map, face, TilePointerKind::Up);
+        }
+    }
+
+    for e in clicks.read().filter(|e| !over_ui.over(e.pointer_id)) {
+        if let Some((map, face)) = face_of(e.pointer_id, e.target, &e.hit) {
+            send(e.pointer_id, e.target, map, face, TilePointerKind::Click);
+        }
+    }
+
+    let id_of = |target: Entity| map_entities.get(target).ok().map(|(handle, _)| handle.id());
+    for e in drag_ends.read() {
+        let key = (e.pointer_id, e.target);
+        let at = pointers.dragged.remove(&key);
+        if let (Some(map), Some(at)) = (id_of(e.target), pointers.hovered.get(&key).copied().or(at)) {
+            send(e.pointer_id, e.target, map, at, TilePointerKind::DragEnd);
+        }
+    }
+
+    for e in outs.read() {
+        let key = (e.pointer_id, e.target);
+        let at = pointers
+            .hovered
+            .remove(&key)
+            .or_else(|| face_of(e.pointer_id, e.target, &e.hit).map(|(_, face)| face));
+        if let (Some(map), Some(at)) = (id_of(e.target), at) {
+            send(e.pointer_id, e.target, map, at, TilePointerKind::Out);
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use avian3d::prelude::*;
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    map::{
+        diff::{MapDiff, MapEdit},
+        Map,
+    },
+    obj::def::{ColliderData, MtlCollection, Obj},
+    physics::GameLayer,
+};
+
+/// An object placed freely in a map rather than on its grid, such as furniture or debris.
+#[derive(Reflect, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Prop {
+    /// Asset path of the object, labeled within its `.obj` file, as in `props/chair.obj#obj:chair`.
+    pub obj_path: String,
+    /// Where it sits in its map's local space.
+    pub transform: Transform,
+    /// Whether it collides, with its object's
+    /// [`collider_data`](crate::obj::def::Obj::collider_data).
+    #[serde(default)]
+    pub collider: bool,
+}
+
+impl Prop {
+    #[inline]
+    pub fn new(obj_path: impl Into<String>, transform: Transform) -> Self {
+        Self {
+            obj_path: obj_path.into(),
+            transform,
+            collider: true,
+        }
+    }
+}
+
+impl Map {
+    /// Replaces every prop at once, recording the change if there is any.
+    pub fn set_props(&mut self, props: Vec<Prop>) -> MapDiff {
+        if self.props == props {
+            return default()
+        }
+
+        MapDiff {
+            edits: vec![MapEdit::Props {
+                from: std::mem::replace(&mut self.props, props.clone()),
+                to: props,
+            }],
+        }
+    }
+
+    /// Changes the prop at `index`, or removes it if `prop` is `None`.
+    pub fn set_prop(&mut self, index: usize, prop: Option<Prop>) -> MapDiff {
+        let mut props = self.props.clone();
+        match (index < props.len(), prop) {
+            (false, _) => return default(),
+            (true, Some(prop)) => props[index] = prop,
+            (true, None) => {
+                props.remove(index);
+            }
+        }
+
+        self.set_props(props)
+    }
+
+    /// Places a prop after every other.
+    #[inline]
+    pub fn add_prop(&mut self, prop: Prop) -> MapDiff {
+        let mut props = self.props.clone();
+        props.push(prop);
+        self.set_props(props)
+    }
+
+    /// Changes the transform of every prop at `indices` in one step.
+    pub fn transform_props(&mut self, indices: &[usize], mut edit: impl FnMut(&mut Transform)) -> MapDiff {
+        let mut props = self.props.clone();
+        for &index in indices {
+            if let Some(prop) = props.get_mut(index) {
+                edit(&mut prop.transform);
+            }
+        }
+
+        self.set_props(props)
+    }
+
+    /// Removes every prop at `indices` in one step. Props after them move down to fill their places.
+    pub fn remove_props(&mut self, indices: &[usize]) -> MapDiff {
+        let props = self
+            .props
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !indices.contains(index))
+            .map(|(_, prop)| prop.clone())
+            .collect();
+
+        self.set_props(props)
+    }
+}
+
+/// The entity of a map's prop, kept under the map by [`sync_map_props`].
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct MapProp {
+    /// Where the prop is in [`Map::props`].
+    pub index: usize,
+    pub prop: Prop,
+}
+
+/// Marks prop entities whose object's mesh and colliders are added, once it has loaded.
+#[derive(Component, Copy, Clone, Default)]
+pub struct PropBuilt;
+
+/// Standalone meshes and materials of objects placed as props, built once per object.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct PropMeshes(pub HashMap<AssetId<Obj>, (Handle<Mesh>, Handle<StandardMaterial>)>);
+
+/// Keeps an entity under each map entity for every one of its props, as they change. Props that
+/// merely moved keep their entity.
+#[allow(clippy::type_complexity)]
+pub fn sync_map_props(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    mut map_events: EventReader<AssetEvent<Map>>,
+    map_assets: Res<Assets<Map>>,
+    maps: Query<(Entity, Ref<Handle<Map>>, Option<&Children>)>,
+    mut props: Query<(&mut MapProp, &mut Transform)>,
+) {
+    let stale = map_events
+        .read()
+        .filter_map(|&e| match e {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } | AssetEvent::LoadedWithDependencies { id } => {
+                Some(id)
+            }
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    for (e, handle, children) in &maps {
+        if !handle.is_changed() && !stale.contains(&handle.id()) {
+            continue
+        }
+
+        let wanted = map_assets.get(&*handle).map_or(&[][..], |map| map.props.as_slice());
+        let mut missing = (0..wanted.len()).collect::<HashSet<_>>();
+        for &child in children.into_iter().flatten() {
+            let Ok((mut current, mut trns)) = props.get_mut(child) else {
+                continue
+            };
+
+            match wanted.get(current.index) {
+                Some(prop) if prop.obj_path == current.prop.obj_path && prop.collider == current.prop.collider => {
+                    missing.remove(&current.index);
+                    if current.prop != *prop {
+                        current.prop.clone_from(prop);
+                        *trns = prop.transform;
+                    }
+                }
+                _ => commands.entity(child).despawn_recursive(),
+            }
+        }
+
+        for index in missing {
+            let prop = wanted[index].clone();
+            let child = commands
+                .spawn((
+                    SpatialBundle::from_transform(prop.transform),
+                    server.load::<Obj>(&prop.obj_path),
+                    MapProp { index, prop },
+                ))
+                .id();
+
+            commands.entity(e).add_child(child);
+        }
+    }
+}
+
+/// Gives props their object's mesh once it has loaded, and its collider if they collide. Props
+/// without one get a sensor bounding them instead, so the editor may still pick them.
+#[allow(clippy::too_many_arguments)]
+pub fn build_map_props(
+    mut commands: Commands,
+    mut obj_events: EventReader<AssetEvent<Obj>>,
+    objs: Res<Assets<Obj>>,
+    mtls: Res<Assets<MtlCollection>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut prop_meshes: ResMut<PropMeshes>,
+    unbuilt: Query<(Entity, &MapProp, &Handle<Obj>), Without<PropBuilt>>,
+    built: Query<(Entity, &Handle<Obj>), With<PropBuilt>>,
+) {
+    // Reloaded objects are built again.
+    for &e in obj_events.read() {
+        if let AssetEvent::Modified { id } | AssetEvent::Removed { id } = e {
+            prop_meshes.remove(&id);
+            for (prop, _) in built.iter().filter(|(_, handle)| handle.id() == id) {
+                commands
+                    .entity(prop)
+                    .remove::<(PropBuilt, Handle<Mesh>, Handle<StandardMaterial>, Collider, Sensor, CollisionLayers)>();
+            }
+        }
+    }
+
+    for (e, prop, handle) in &unbuilt {
+        let Some(obj) = objs.get(handle) else { continue };
+        let Some(mtl) = mtls.get(&obj.material) else { continue };
+
+        let (mesh, material) = prop_meshes
+            .entry(handle.id())
+            .or_insert_with(|| {
+                let texture = mtl.get(&obj.material_key).and_then(|mtl| mtl.diffuse_texture.clone());
+                (
+                    meshes.add(obj.to_mesh()),
+                    materials.add(StandardMaterial {
+                        base_color_texture: texture,
+                        perceptual_roughness: 1.0,
+                        ..default()
+                    }),
+                )
+            })
+            .clone();
+
+        let mut entity = commands.entity(e);
+        entity.insert((mesh, material, PropBuilt));
+
+        let collider = obj
+            .collider_data
+            .as_ref()
+            .filter(|_| prop.prop.collider)
+            .and_then(ColliderData::collider);
+
+        match collider {
+            Some(collider) => {
+                entity.insert((collider, GameLayer::prop()));
+            }
+            None => {
+                let (min, max) = obj.aabb();
+                let size = (max - min).max(Vec3::splat(0.01));
+                entity.insert((
+                    Collider::compound(vec![(
+                        (min + max) / 2.0,
+                        Quat::IDENTITY,
+                        Collider::cuboid(size.x, size.y, size.z),
+                    )]),
+                    Sensor,
+                    GameLayer::pick_only(),
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,981 @@
+pub mod v1;
+
+use std::{
+    f32::consts::{FRAC_1_SQRT_2, TAU},
+    io::Error as IoError,
+};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, LoadDirectError, ParseAssetPathError},
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+    utils::HashMap,
+};
+use ron::error::SpannedError;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    content::properties::{AutoOrient, SurfaceTag, TilePropertyOverrides},
+    map::{lighting::TileLight, tile::v1::TileFileV1},
+    obj::{
+        def::{Cull, Mtl, MtlCollection, Obj, ObjCollection, TextureVariant},
+        loader::{premultiply_alpha, TextureSettings},
+    },
+};
+
+#[derive(Error, Debug)]
+pub enum TileError {
+    #[error(transparent)]
+    Syntax(#[from] SpannedError),
+    #[error(transparent)]
+    InvalidImage(#[from] Box<LoadDirectError>),
+    #[error(transparent)]
+    InvalidPath(#[from] ParseAssetPathError),
+    #[error("No texture for the cube's `{0}` face.")]
+    MissingFace(&'static str),
+    #[error("Can't convert {0:?} textures.")]
+    UnsupportedFormat(TextureFormat),
+    #[error("`{field}` of {value} doesn't fit in a cell.")]
+    OutOfRange { field: &'static str, value: f32 },
+    #[error("`{field}` can't be negative, but is {value}.")]
+    Negative { field: &'static str, value: f32 },
+    #[error("Cubes textured per face can't have variants.")]
+    FacedVariants,
+    #[error("Tile file version {0} isn't understood; this build reads versions 1 through {TILE_FILE_VERSION}.")]
+    UnsupportedVersion(u32),
+    #[error("No object `{0}`.")]
+    MissingObject(String),
+    #[error(transparent)]
+    Io(#[from] IoError),
+}
+
+/// A tile defined by a `.tile` file, generated or wrapping a modelled object. Its geometry is
+/// labeled `obj:tile`, so maps and the tile atlas take it as they would a modelled tile.
+#[derive(Asset, TypePath, Clone, Debug)]
+pub enum Tile {
+    /// A unit cube showing its texture as a 3x2 net, laid out as modelling tools export cubes.
+    /// Textures given per face are baked into such a net.
+    Cube {
+        /// Taken into the atlas like any other tile texture, so only its ID is kept.
+        texture: AssetId<Image>,
+        obj: Handle<Obj>,
+    },
+    /// A 45° wedge on a square base, ascending towards -Z before the cell's orientation turns it.
+    /// Textured as the cube it's cut from.
+    Ramp { texture: AssetId<Image>, obj: Handle<Obj> },
+    /// The bottom of a cube, `height` tall.
+    Slab {
+        texture: AssetId<Image>,
+        obj: Handle<Obj>,
+        height: f32,
+    },
+    /// An upright cylinder through the cell's center, as tall as the cell.
+    Pillar {
+        texture: AssetId<Image>,
+        obj: Handle<Obj>,
+        radius: f32,
+    },
+    /// An object of an OBJ file, with the `.tile` file's metadata over it. Reloaded along with
+    /// the OBJ file.
+    Model {
+        /// The asset path of the wrapped object, which tile discovery leaves out for this.
+        source: String,
+        obj: Handle<Obj>,
+    },
+}
+
+impl Tile {
+    /// The generated geometry.
+    #[inline]
+    pub fn obj(&self) -> &Handle<Obj> {
+        match self {
+            Self::Cube { obj, .. } |
+            Self::Ramp { obj, .. } |
+            Self::Slab { obj, .. } |
+            Self::Pillar { obj, .. } |
+            Self::Model { obj, .. } => obj,
+        }
+    }
+}
+
+/// The shape a tile collides as, in its cell's space before orientation. Generated tiles collide
+/// as their visual shape unless their `.tile` file says otherwise; modelled tiles collide as their
+/// triangles.
+#[derive(Deserialize, Copy, Clone, PartialEq, Default, Debug)]
+pub enum TileCollider {
+    /// Passed through.
+    None,
+    /// The whole cell.
+    Full,
+    /// The bottom of the cell, `height` tall as a fraction of it.
+    Slab { height: f32 },
+    /// A 45° wedge ascending towards -Z, as [`Tile::Ramp`].
+    Ramp,
+    /// The tile's own triangles.
+    #[default]
+    Trimesh,
+}
+
+/// The version of [`TileFile`] this build writes and reads without migrating.
+pub const TILE_FILE_VERSION: u32 = 2;
+
+/// A `.tile` file, with paths relative to it, written as `(version: 2, tile: Cube(texture:
+/// "stone.png"))`. Files of older versions are migrated as they're read; see [`TileFile::parse`].
+///
+/// Each shape may override its [`TileCollider`] and
+/// [`TileProperties`](crate::content::properties::TileProperties), such as `Ramp(texture:
+/// "stone.png", collider: Some(Full), friction: Some(0.1))`, and shine a [`TileLight`], such as
+/// `Pillar(texture: "lamp.png", radius: 0.2, light: Some((intensity: 800.0)))`. Walls and fixtures
+/// may turn themselves to their neighbors with `auto_orient: Some(TowardEmpty)`, or refuse the
+/// editor's placement rotation with `rotatable: Some(false)`. Trigger volumes are marked with
+/// `sensor: Some(true)`, and sense what enters their cells instead of being drawn or solid.
+/// Textures may have `variants` that map cells show in their place, such as `Slab(texture:
+/// "floor_a.png", height: 0.5, variants: ["floor_b.png", ("floor_c.png", 0.5)])`.
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub enum TileFile {
+    /// `Cube(texture: "stone.png")`, or `Cube(texture: (top: "grass.png", sides:
+    /// "grass_side.png", bottom: "dirt.png"))`.
+    Cube {
+        texture: CubeTexture,
+        #[serde(default)]
+        variants: Vec<TileVariant>,
+        #[serde(default)]
+        collider: Option<TileCollider>,
+        #[serde(default)]
+        walkable: Option<bool>,
+        #[serde(default)]
+        friction: Option<f32>,
+        #[serde(default)]
+        move_cost: Option<f32>,
+        #[serde(default)]
+        surface: Option<SurfaceTag>,
+        #[serde(default)]
+        rotatable: Option<bool>,
+        #[serde(default)]
+        auto_orient: Option<AutoOrient>,
+        #[serde(default)]
+        sensor: Option<bool>,
+        #[serde(default)]
+        light: Option<TileLight>,
+    },
+    Ramp {
+        texture: String,
+        #[serde(default)]
+        variants: Vec<TileVariant>,
+        #[serde(default)]
+        collider: Option<TileCollider>,
+        #[serde(default)]
+        walkable: Option<bool>,
+        #[serde(default)]
+        friction: Option<f32>,
+        #[serde(default)]
+        move_cost: Option<f32>,
+        #[serde(default)]
+        surface: Option<SurfaceTag>,
+        #[serde(default)]
+        rotatable: Option<bool>,
+        #[serde(default)]
+        auto_orient: Option<AutoOrient>,
+        #[serde(default)]
+        sensor: Option<bool>,
+        #[serde(default)]
+        light: Option<TileLight>,
+    },
+    /// `height` is a fraction of the cell's, in `(0, 1]`.
+    Slab {
+        texture: String,
+        height: f32,
+        #[serde(default)]
+        variants: Vec<TileVariant>,
+        #[serde(default)]
+        collider: Option<TileCollider>,
+        #[serde(default)]
+        walkable: Option<bool>,
+        #[serde(default)]
+        friction: Option<f32>,
+        #[serde(default)]
+        move_cost: Option<f32>,
+        #[serde(default)]
+        surface: Option<SurfaceTag>,
+        #[serde(default)]
+        rotatable: Option<bool>,
+        #[serde(default)]
+        auto_orient: Option<AutoOrient>,
+        #[serde(default)]
+        sensor: Option<bool>,
+        #[serde(default)]
+        light: Option<TileLight>,
+    },
+    /// `radius` is a fraction of the cell's width, in `(0, 0.5]`.
+    Pillar {
+        texture: String,
+        radius: f32,
+        #[serde(default)]
+        variants: Vec<TileVariant>,
+        #[serde(default)]
+        collider: Option<TileCollider>,
+        #[serde(default)]
+        walkable: Option<bool>,
+        #[serde(default)]
+        friction: Option<f32>,
+        #[serde(default)]
+        move_cost: Option<f32>,
+        #[serde(default)]
+        surface: Option<SurfaceTag>,
+        #[serde(default)]
+        rotatable: Option<bool>,
+        #[serde(default)]
+        auto_orient: Option<AutoOrient>,
+        #[serde(default)]
+        sensor: Option<bool>,
+        #[serde(default)]
+        light: Option<TileLight>,
+    },
+    /// `object` of the OBJ file `obj`, such as `Model(obj: "props.obj", object: "crate")`.
+    Model {
+        obj: String,
+        object: String,
+        #[serde(default)]
+        collider: Option<TileCollider>,
+        #[serde(default)]
+        walkable: Option<bool>,
+        #[serde(default)]
+        friction: Option<f32>,
+        #[serde(default)]
+        move_cost: Option<f32>,
+        #[serde(default)]
+        surface: Option<SurfaceTag>,
+        #[serde(default)]
+        rotatable: Option<bool>,
+        #[serde(default)]
+        auto_orient: Option<AutoOrient>,
+        #[serde(default)]
+        sensor: Option<bool>,
+        #[serde(default)]
+        light: Option<TileLight>,
+    },
+}
+
+impl TileFile {
+    /// Reads a `.tile` file of any version up to [`TILE_FILE_VERSION`], migrating older ones.
+    /// Files without a version are of the first, which wrote the bare shape.
+    pub fn parse(text: &str) -> Result<Self, TileError> {
+        #[derive(Deserialize)]
+        struct Header {
+            version: u32,
+        }
+
+        // The version is read by the header above, and ignored here.
+        #[derive(Deserialize)]
+        struct Versioned<T> {
+            tile: T,
+        }
+
+        let Ok(Header { version }) = ron::from_str::<Header>(text) else {
+            return Ok(ron::from_str::<TileFileV1>(text)?.into())
+        };
+
+        match version {
+            1 => Ok(ron::from_str::<Versioned<TileFileV1>>(text)?.tile.into()),
+            TILE_FILE_VERSION => Ok(ron::from_str::<Versioned<Self>>(text)?.tile),
+            _ => Err(TileError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// The tile's texture, unless it's given per face.
+    #[inline]
+    pub fn texture(&self) -> Option<&str> {
+        match self {
+            Self::Cube {
+                texture: CubeTexture::Faces(..),
+                ..
+            } |
+            Self::Model { .. } => None,
+            Self::Cube {
+                texture: CubeTexture::Single(texture),
+                ..
+            } |
+            Self::Ramp { texture, .. } |
+            Self::Slab { texture, .. } |
+            Self::Pillar { texture, .. } => Some(texture),
+        }
+    }
+
+    /// Textures map cells may show in place of the tile's own.
+    #[inline]
+    pub fn variants(&self) -> &[TileVariant] {
+        match self {
+            Self::Cube { variants, .. } |
+            Self::Ramp { variants, .. } |
+            Self::Slab { variants, .. } |
+            Self::Pillar { variants, .. } => variants,
+            Self::Model { .. } => &[],
+        }
+    }
+
+    /// The light the tile shines in every cell it's placed in.
+    #[inline]
+    pub fn light(&self) -> Option<TileLight> {
+        match *self {
+            Self::Cube { light, .. } |
+            Self::Ramp { light, .. } |
+            Self::Slab { light, .. } |
+            Self::Pillar { light, .. } |
+            Self::Model { light, .. } => light,
+        }
+    }
+
+    /// The collider the file declares, overriding the shape's own.
+    #[inline]
+    pub fn collider(&self) -> Option<TileCollider> {
+        match *self {
+            Self::Cube { collider, .. } |
+            Self::Ramp { collider, .. } |
+            Self::Slab { collider, .. } |
+            Self::Pillar { collider, .. } |
+            Self::Model { collider, .. } => collider,
+        }
+    }
+
+    /// The tile properties the file overrides.
+    #[inline]
+    pub fn properties(&self) -> TilePropertyOverrides {
+        match *self {
+            Self::Cube {
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                rotatable,
+                auto_orient,
+                sensor,
+                ..
+            } |
+            Self::Ramp {
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                rotatable,
+                auto_orient,
+                sensor,
+                ..
+            } |
+            Self::Slab {
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                rotatable,
+                auto_orient,
+                sensor,
+                ..
+            } |
+            Self::Pillar {
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                rotatable,
+                auto_orient,
+                sensor,
+                ..
+            } |
+            Self::Model {
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                rotatable,
+                auto_orient,
+                sensor,
+                ..
+            } => TilePropertyOverrides {
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                rotatable,
+                auto_orient,
+                sensor,
+            },
+        }
+    }
+}
+
+/// A texture that may show in place of a tile's own, either just its path or with its weight
+/// against the tile's own texture's `1`.
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum TileVariant {
+    Path(String),
+    Weighted(String, f32),
+}
+
+impl TileVariant {
+    #[inline]
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Path(path) | Self::Weighted(path, _) => path,
+        }
+    }
+
+    #[inline]
+    pub fn weight(&self) -> f32 {
+        match *self {
+            Self::Path(..) => 1.0,
+            Self::Weighted(_, weight) => weight,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum CubeTexture {
+    /// A 3x2 net, as modelling tools export cube textures.
+    Single(String),
+    Faces(CubeTextures),
+}
+
+/// The texture of each of a cube's faces. Sides left out show [`sides`](Self::sides).
+#[derive(Deserialize, Clone, Default, PartialEq, Debug)]
+#[serde(default)]
+pub struct CubeTextures {
+    pub top: Option<String>,
+    pub bottom: Option<String>,
+    pub sides: Option<String>,
+    pub x: Option<String>,
+    pub neg_x: Option<String>,
+    pub z: Option<String>,
+    pub neg_z: Option<String>,
+}
+
+impl CubeTextures {
+    /// The texture of each face, ordered as [`CUBE_FACES`].
+    pub fn faces(&self) -> Result<[&str; 6], TileError> {
+        #[inline]
+        fn face<'a>(
+            face: &'a Option<String>,
+            fallback: &'a Option<String>,
+            name: &'static str,
+        ) -> Result<&'a str, TileError> {
+            face.as_deref().or(fallback.as_deref()).ok_or(TileError::MissingFace(name))
+        }
+
+        let sides = &self.sides;
+        Ok([
+            face(&self.x, sides, "x")?,
+            face(&self.neg_x, sides, "neg_x")?,
+            face(&self.top, &None, "top")?,
+            face(&self.bottom, &None, "bottom")?,
+            face(&self.z, sides, "z")?,
+            face(&self.neg_z, sides, "neg_z")?,
+        ])
+    }
+}
+
+type Face<'a> = (Vec3, &'a [(Vec3, Vec2)]);
+
+const T: f32 = 1.0 / 3.0;
+const TT: f32 = 2.0 / 3.0;
+
+#[inline]
+const fn corner(x: f32, y: f32, z: f32, u: f32, v: f32) -> (Vec3, Vec2) {
+    (Vec3::new(x * 0.5, y * 0.5, z * 0.5), Vec2::new(u, v))
+}
+
+/// Corners of each face of [`Tile::Cube`] in winding order, with their texture coordinates
+/// before flipping, as a modelled cube's OBJ file would list them.
+pub const CUBE_FACES: [Face<'static>; 6] = [
+    (Vec3::X, &[
+        corner(1.0, 1.0, -1.0, 1.0, 0.5),
+        corner(1.0, 1.0, 1.0, TT, 0.5),
+        corner(1.0, -1.0, 1.0, TT, 0.0),
+        corner(1.0, -1.0, -1.0, 1.0, 0.0),
+    ]),
+    (Vec3::NEG_X, &[
+        corner(-1.0, -1.0, 1.0, 1.0, 0.5),
+        corner(-1.0, 1.0, 1.0, 1.0, 1.0),
+        corner(-1.0, 1.0, -1.0, TT, 1.0),
+        corner(-1.0, -1.0, -1.0, TT, 0.5),
+    ]),
+    (Vec3::Y, &[
+        corner(-1.0, 1.0, 1.0, T, 0.5),
+        corner(1.0, 1.0, 1.0, T, 1.0),
+        corner(1.0, 1.0, -1.0, 0.0, 1.0),
+        corner(-1.0, 1.0, -1.0, 0.0, 0.5),
+    ]),
+    (Vec3::NEG_Y, &[
+        corner(1.0, -1.0, -1.0, 0.0, 0.5),
+        corner(1.0, -1.0, 1.0, 0.0, 0.0),
+        corner(-1.0, -1.0, 1.0, T, 0.0),
+        corner(-1.0, -1.0, -1.0, T, 0.5),
+    ]),
+    (Vec3::Z, &[
+        corner(1.0, -1.0, 1.0, TT, 0.0),
+        corner(1.0, 1.0, 1.0, TT, 0.5),
+        corner(-1.0, 1.0, 1.0, T, 0.5),
+        corner(-1.0, -1.0, 1.0, T, 0.0),
+    ]),
+    (Vec3::NEG_Z, &[
+        corner(-1.0, 1.0, -1.0, TT, 1.0),
+        corner(1.0, 1.0, -1.0, T, 1.0),
+        corner(1.0, -1.0, -1.0, T, 0.5),
+        corner(-1.0, -1.0, -1.0, TT, 0.5),
+    ]),
+];
+
+/// The faces of [`Tile::Ramp`]: the cube's with its top front edge brought down to the bottom, so
+/// the top slopes and the sides are left as triangles.
+pub const RAMP_FACES: [Face<'static>; 5] = [
+    (Vec3::X, &[
+        corner(1.0, 1.0, -1.0, 1.0, 0.5),
+        corner(1.0, -1.0, 1.0, TT, 0.0),
+        corner(1.0, -1.0, -1.0, 1.0, 0.0),
+    ]),
+    (Vec3::NEG_X, &[
+        corner(-1.0, -1.0, 1.0, 1.0, 0.5),
+        corner(-1.0, 1.0, -1.0, TT, 1.0),
+        corner(-1.0, -1.0, -1.0, TT, 0.5),
+    ]),
+    (Vec3::new(0.0, FRAC_1_SQRT_2, FRAC_1_SQRT_2), &[
+        corner(-1.0, -1.0, 1.0, T, 0.5),
+        corner(1.0, -1.0, 1.0, T, 1.0),
+        corner(1.0, 1.0, -1.0, 0.0, 1.0),
+        corner(-1.0, 1.0, -1.0, 0.0, 0.5),
+    ]),
+    CUBE_FACES[3],
+    CUBE_FACES[5],
+];
+
+/// Generates geometry from faces, triangulated as the OBJ loader would.
+fn generate(faces: &[Face], culls: Cull, material: Handle<MtlCollection>, material_key: String) -> Obj {
+    let mut obj = Obj {
+        material,
+        material_key,
+        culls,
+        ..default()
+    };
+
+    for &(normal, corners) in faces {
+        let first = obj.positions.len() as u32;
+        for &(pos, uv) in corners {
+            obj.positions.push(pos);
+            obj.uvs.push(Vec2::new(uv.x, 1.0 - uv.y));
+            obj.normals.push(normal);
+        }
+
+        obj.faces
+            .extend((first + 1..obj.positions.len() as u32 - 1).map(|index| [index, index + 1, first]));
+    }
+
+    obj.calculate_bounds();
+    obj
+}
+
+/// Where a point on a face lands in the cube's texture net, from the cube face it faces most. Faces
+/// smaller than the cube's show the part of the texture they cover.
+fn net_uv(normal: Vec3, pos: Vec3) -> Vec2 {
+    let (_, corners) = CUBE_FACES
+        .into_iter()
+        .max_by(|(a, _), (b, _)| a.dot(normal).total_cmp(&b.dot(normal)))
+        .unwrap();
+
+    let [(origin, uv), (right, right_uv), _, (down, down_uv)] = corners else {
+        unreachable!("cube faces are quads")
+    };
+
+    let (right, down) = (*right - *origin, *down - *origin);
+    let (s, t) = (
+        (pos - *origin).dot(right) / right.length_squared(),
+        (pos - *origin).dot(down) / down.length_squared(),
+    );
+
+    *uv + (*right_uv - *uv) * s + (*down_uv - *uv) * t
+}
+
+/// Generates faces given by corner positions alone, textured by [`net_uv`].
+fn generate_net(
+    faces: impl IntoIterator<Item = (Vec3, Vec<Vec3>)>,
+    culls: Cull,
+    material: Handle<MtlCollection>,
+    material_key: String,
+) -> Obj {
+    let faces = faces
+        .into_iter()
+        .map(|(normal, corners)| (normal, corners.into_iter().map(|pos| (pos, net_uv(normal, pos))).collect()))
+        .collect::<Vec<(Vec3, Vec<_>)>>();
+
+    let faces = faces
+        .iter()
+        .map(|(normal, corners)| (*normal, corners.as_slice()))
+        .collect::<Vec<_>>();
+
+    generate(&faces, culls, material, material_key)
+}
+
+/// Generates [`Tile::Cube`]'s geometry.
+#[inline]
+pub fn cube_obj(material: Handle<MtlCollection>, material_key: String) -> Obj {
+    Obj {
+        collider: TileCollider::Full,
+        ..generate(&CUBE_FACES, Cull::all(), material, material_key)
+    }
+}
+
+/// Generates [`Tile::Ramp`]'s geometry, solid only on its bottom and its tall back face.
+#[inline]
+pub fn ramp_obj(material: Handle<MtlCollection>, material_key: String) -> Obj {
+    Obj {
+        collider: TileCollider::Ramp,
+        ..generate(&RAMP_FACES, Cull::DOWN | Cull::NEG_Z, material, material_key)
+    }
+}
+
+/// Generates [`Tile::Slab`]'s geometry. Its sides show the bottom of the cube's, and only cover
+/// their neighbours' if it's as tall as the cell.
+pub fn slab_obj(material: Handle<MtlCollection>, material_key: String, height: f32) -> Obj {
+    let faces = CUBE_FACES.map(|(normal, corners)| {
+        let corners = corners
+            .iter()
+            .map(|&(pos, _)| Vec3::new(pos.x, (pos.y + 0.5) * height - 0.5, pos.z))
+            .collect();
+        (normal, corners)
+    });
+
+    let culls = if height >= 1.0 { Cull::all() } else { Cull::DOWN };
+    Obj {
+        collider: TileCollider::Slab { height },
+        ..generate_net(faces, culls, material, material_key)
+    }
+}
+
+/// Bakes a texture for each of [`CUBE_FACES`] into a 3x2 net, each upright as seen from outside the
+/// cube, with the top's upper edge at the back and the bottom's at the front.
+pub fn bake_cube_net(faces: [&Image; 6]) -> Result<Image, TileError> {
+    let faces = faces
+        .into_iter()
+        .map(|image| match image.texture_descriptor.format {
+            TextureFormat::Rgba8UnormSrgb => Ok(image.clone()),
+            format => image
+                .convert(TextureFormat::Rgba8UnormSrgb)
+                .ok_or(TileError::UnsupportedFormat(format)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let cell = faces.iter().fold(UVec2::ONE, |cell, image| cell.max(image.size()));
+    let size = cell * UVec2::new(3, 2);
+
+    // Where each face lies in the net, and how its texture coordinates map onto the face.
+    let regions = CUBE_FACES.map(|(normal, corners)| {
+        let flip = |uv: Vec2| Vec2::new(uv.x, 1.0 - uv.y);
+        let [(origin, uv), (right, right_uv), _, (down, down_uv)] = corners else {
+            unreachable!("cube faces are quads")
+        };
+
+        let (min, max) = corners.iter().fold((Vec2::MAX, Vec2::MIN), |(min, max), &(_, uv)| {
+            (min.min(flip(uv)), max.max(flip(uv)))
+        });
+        let to_face = Mat2::from_cols(flip(*right_uv) - flip(*uv), flip(*down_uv) - flip(*uv)).inverse();
+        let up = match normal.y {
+            0.0 => Vec3::Y,
+            y if y > 0.0 => Vec3::NEG_Z,
+            _ => Vec3::Z,
+        };
+
+        (
+            min,
+            max,
+            flip(*uv),
+            to_face,
+            *origin,
+            *right - *origin,
+            *down - *origin,
+            up,
+            up.cross(normal),
+        )
+    });
+
+    let mut data = vec![0; (size.x * size.y * 4) as usize];
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let uv = (UVec2::new(x, y).as_vec2() + 0.5) / size.as_vec2();
+            let Some((face, &(.., uv_origin, to_face, origin, right, down, up, side))) = regions
+                .iter()
+                .enumerate()
+                .find(|(_, &(min, max, ..))| uv.cmpge(min).all() && uv.cmple(max).all())
+            else {
+                continue
+            };
+
+            let param = to_face * (uv - uv_origin);
+            let pos = origin + right * param.x + down * param.y;
+            let local = Vec2::new(pos.dot(side) + 0.5, 0.5 - pos.dot(up));
+
+            let image = &faces[face];
+            let texel = (local * image.size().as_vec2()).as_uvec2().min(image.size() - 1);
+            let from = ((texel.y * image.width() + texel.x) * 4) as usize;
+            let to = ((y * size.x + x) * 4) as usize;
+            data[to..to + 4].copy_from_slice(&image.data[from..from + 4]);
+        }
+    }
+
+    Ok(Image::new(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ))
+}
+
+/// Sides of [`Tile::Pillar`]'s cylinder, shaded flat.
+pub const PILLAR_SEGMENTS: usize = 16;
+
+/// Generates [`Tile::Pillar`]'s geometry. Round, it covers no side of its cell whole, and collides
+/// as its triangles.
+pub fn pillar_obj(material: Handle<MtlCollection>, material_key: String, radius: f32) -> Obj {
+    // Counter-clockwise seen from above.
+    let rim = |index: usize, y: f32| {
+        let angle = index as f32 / PILLAR_SEGMENTS as f32 * TAU;
+        Vec3::new(angle.cos() * radius, y, -angle.sin() * radius)
+    };
+
+    let sides = (0..PILLAR_SEGMENTS).map(|index| {
+        let angle = (index as f32 + 0.5) / PILLAR_SEGMENTS as f32 * TAU;
+        let normal = Vec3::new(angle.cos(), 0.0, -angle.sin());
+        (normal, vec![
+            rim(index, -0.5),
+            rim(index + 1, -0.5),
+            rim(index + 1, 0.5),
+            rim(index, 0.5),
+        ])
+    });
+
+    let top = (Vec3::Y, (0..PILLAR_SEGMENTS).map(|index| rim(index, 0.5)).collect());
+    let bottom = (
+        Vec3::NEG_Y,
+        (0..PILLAR_SEGMENTS).rev().map(|index| rim(index, -0.5)).collect(),
+    );
+    generate_net(sides.chain([top, bottom]), Cull::empty(), material, material_key)
+}
+
+pub struct TileLoader;
+impl AssetLoader for TileLoader {
+    type Asset = Tile;
+    type Settings = TextureSettings;
+    type Error = TileError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        &settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut file = String::new();
+        reader.read_to_string(&mut file).await?;
+
+        let path = load_context.asset_path().clone();
+        let file = TileFile::parse(&file)?;
+        match file {
+            TileFile::Slab { height, .. } if !(height > 0.0 && height <= 1.0) => {
+                return Err(TileError::OutOfRange {
+                    field: "height",
+                    value: height,
+                })
+            }
+            TileFile::Pillar { radius, .. } if !(radius > 0.0 && radius <= 0.5) => {
+                return Err(TileError::OutOfRange {
+                    field: "radius",
+                    value: radius,
+                })
+            }
+            _ => {}
+        }
+
+        let properties = file.properties();
+        for (field, value) in [("friction", properties.friction), ("move_cost", properties.move_cost)] {
+            match value {
+                Some(value) if value < 0.0 => return Err(TileError::Negative { field, value }),
+                _ => {}
+            }
+        }
+
+        for variant in file.variants() {
+            if variant.weight() < 0.0 {
+                return Err(TileError::Negative {
+                    field: "variants.weight",
+                    value: variant.weight(),
+                })
+            }
+        }
+
+        if let (
+            TileFile::Cube {
+                texture: CubeTexture::Faces(..),
+                ..
+            },
+            [_, ..],
+        ) = (&file, file.variants())
+        {
+            return Err(TileError::FacedVariants)
+        }
+
+        if let Some(TileCollider::Slab { height }) = file.collider() {
+            if !(height > 0.0 && height <= 1.0) {
+                return Err(TileError::OutOfRange {
+                    field: "collider.height",
+                    value: height,
+                })
+            }
+        }
+
+        if let TileFile::Model {
+            obj,
+            object,
+            collider,
+            light,
+            ..
+        } = &file
+        {
+            // Loaded directly, so this reloads with the OBJ file.
+            let source = path.resolve_embed(obj)?;
+            let collection = load_context
+                .loader()
+                .direct()
+                .load::<ObjCollection>(source.clone())
+                .await
+                .map_err(Box::new)?;
+
+            let source = source.with_label(format!("obj:{object}")).to_string();
+            let model = collection
+                .get_labeled(format!("obj:{object}"))
+                .and_then(|model| model.get::<Obj>())
+                .ok_or_else(|| TileError::MissingObject(source.clone()))?;
+
+            let obj = load_context.add_labeled_asset("obj:tile".into(), Obj {
+                collider: collider.unwrap_or(model.collider),
+                properties,
+                light: light.or(model.light),
+                ..model.clone()
+            });
+
+            return Ok(Tile::Model { source, obj })
+        }
+
+        let mut image = match (&file, file.texture()) {
+            (.., Some(texture)) => load_context
+                .loader()
+                .with_settings(settings.image_settings())
+                .direct()
+                .load::<Image>(path.resolve_embed(texture)?)
+                .await
+                .map_err(Box::new)?
+                .take(),
+            (
+                TileFile::Cube {
+                    texture: CubeTexture::Faces(faces),
+                    ..
+                },
+                None,
+            ) => {
+                let faces = faces.faces()?;
+                let mut images = HashMap::<&str, Image>::new();
+                for face in faces {
+                    if !images.contains_key(face) {
+                        let image = load_context
+                            .loader()
+                            .with_settings(settings.image_settings())
+                            .direct()
+                            .load::<Image>(path.resolve_embed(face)?)
+                            .await
+                            .map_err(Box::new)?;
+                        images.insert(face, image.take());
+                    }
+                }
+
+                let mut net = bake_cube_net(faces.map(|face| &images[face]))?;
+                settings.apply(&mut net);
+                net
+            }
+            (.., None) => unreachable!("only cubes may have a texture per face, and models have none here"),
+        };
+
+        if settings.premultiply_alpha && !premultiply_alpha(&mut image) {
+            return Err(TileError::UnsupportedFormat(image.texture_descriptor.format))
+        }
+
+        let mut diffuse_variants = Vec::with_capacity(file.variants().len());
+        for (index, variant) in file.variants().iter().enumerate() {
+            let mut image = load_context
+                .loader()
+                .with_settings(settings.image_settings())
+                .direct()
+                .load::<Image>(path.resolve_embed(variant.path())?)
+                .await
+                .map_err(Box::new)?
+                .take();
+
+            if settings.premultiply_alpha && !premultiply_alpha(&mut image) {
+                return Err(TileError::UnsupportedFormat(image.texture_descriptor.format))
+            }
+
+            diffuse_variants.push(TextureVariant {
+                texture: load_context.add_labeled_asset(format!("map_Kd_variant{index}"), image),
+                weight: variant.weight(),
+            });
+        }
+
+        let texture = load_context.add_labeled_asset("map_Kd".into(), image);
+        let material = load_context.labeled_asset_scope("mtl".into(), |_| MtlCollection {
+            materials: [("tile".into(), Mtl {
+                diffuse_texture_id: Some(texture.id()),
+                diffuse_texture: Some(texture.clone()),
+                diffuse_variants,
+                ..default()
+            })]
+            .into_iter()
+            .collect(),
+        });
+
+        let key = String::from("tile");
+        let obj = load_context.labeled_asset_scope("obj:tile".into(), |_| {
+            let obj = match file {
+                TileFile::Cube { .. } => cube_obj(material, key),
+                TileFile::Ramp { .. } => ramp_obj(material, key),
+                TileFile::Slab { height, .. } => slab_obj(material, key, height),
+                TileFile::Pillar { radius, .. } => pillar_obj(material, key, radius),
+                TileFile::Model { .. } => unreachable!("models are loaded above"),
+            };
+
+            Obj {
+                collider: file.collider().unwrap_or(obj.collider),
+                properties,
+                light: file.light(),
+                ..obj
+            }
+        });
+
+        let texture = texture.id();
+        Ok(match file {
+            TileFile::Cube { .. } => Tile::Cube { texture, obj },
+            TileFile::Ramp { .. } => Tile::Ramp { texture, obj },
+            TileFile::Slab { height, .. } => Tile::Slab { texture, obj, height },
+            TileFile::Pillar { radius, .. } => Tile::Pillar { texture, obj, radius },
+            TileFile::Model { .. } => unreachable!("models are loaded above"),
+        })
+    }
+
+    #[inline]
+    fn extensions(&self) -> &[&str] {
+        &["tile"]
+    }
+}
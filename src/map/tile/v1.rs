@@ -0,0 +1,204 @@
+use serde::Deserialize;
+
+use crate::{
+    content::properties::{SurfaceTag, TilePropertyOverrides},
+    map::{
+        lighting::TileLight,
+        tile::{CubeTexture, TileCollider, TileFile, TileVariant},
+    },
+};
+
+/// The first version of [`TileFile`], written as the bare shape without a version. Cubes took
+/// their overrides in order, such as `Cube("ice.png", None, (friction: Some(0.05)))`. Frozen, so
+/// files written with it read the same however the current version grows.
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub enum TileFileV1 {
+    Cube(
+        CubeTexture,
+        #[serde(default)] Option<TileCollider>,
+        #[serde(default)] TilePropertyOverrides,
+        #[serde(default)] Vec<TileVariant>,
+        #[serde(default)] Option<TileLight>,
+    ),
+    Ramp {
+        texture: String,
+        #[serde(default)]
+        variants: Vec<TileVariant>,
+        #[serde(default)]
+        collider: Option<TileCollider>,
+        #[serde(default)]
+        walkable: Option<bool>,
+        #[serde(default)]
+        friction: Option<f32>,
+        #[serde(default)]
+        move_cost: Option<f32>,
+        #[serde(default)]
+        surface: Option<SurfaceTag>,
+        #[serde(default)]
+        light: Option<TileLight>,
+    },
+    Slab {
+        texture: String,
+        height: f32,
+        #[serde(default)]
+        variants: Vec<TileVariant>,
+        #[serde(default)]
+        collider: Option<TileCollider>,
+        #[serde(default)]
+        walkable: Option<bool>,
+        #[serde(default)]
+        friction: Option<f32>,
+        #[serde(default)]
+        move_cost: Option<f32>,
+        #[serde(default)]
+        surface: Option<SurfaceTag>,
+        #[serde(default)]
+        light: Option<TileLight>,
+    },
+    Pillar {
+        texture: String,
+        radius: f32,
+        #[serde(default)]
+        variants: Vec<TileVariant>,
+        #[serde(default)]
+        collider: Option<TileCollider>,
+        #[serde(default)]
+        walkable: Option<bool>,
+        #[serde(default)]
+        friction: Option<f32>,
+        #[serde(default)]
+        move_cost: Option<f32>,
+        #[serde(default)]
+        surface: Option<SurfaceTag>,
+        #[serde(default)]
+        light: Option<TileLight>,
+    },
+    Model {
+        obj: String,
+        object: String,
+        #[serde(default)]
+        collider: Option<TileCollider>,
+        #[serde(default)]
+        walkable: Option<bool>,
+        #[serde(default)]
+        friction: Option<f32>,
+        #[serde(default)]
+        move_cost: Option<f32>,
+        #[serde(default)]
+        surface: Option<SurfaceTag>,
+        #[serde(default)]
+        light: Option<TileLight>,
+    },
+}
+
+impl From<TileFileV1> for TileFile {
+    fn from(file: TileFileV1) -> Self {
+        match file {
+            TileFileV1::Cube(texture, collider, properties, variants, light) => Self::Cube {
+                texture,
+                variants,
+                collider,
+                walkable: properties.walkable,
+                friction: properties.friction,
+                move_cost: properties.move_cost,
+                surface: properties.surface,
+                rotatable: None,
+                auto_orient: None,
+                sensor: None,
+                light,
+            },
+            TileFileV1::Ramp {
+                texture,
+                variants,
+                collider,
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                light,
+            } => Self::Ramp {
+                texture,
+                variants,
+                collider,
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                rotatable: None,
+                auto_orient: None,
+                sensor: None,
+                light,
+            },
+            TileFileV1::Slab {
+                texture,
+                height,
+                variants,
+                collider,
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                light,
+            } => Self::Slab {
+                texture,
+                height,
+                variants,
+                collider,
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                rotatable: None,
+                auto_orient: None,
+                sensor: None,
+                light,
+            },
+            TileFileV1::Pillar {
+                texture,
+                radius,
+                variants,
+                collider,
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                light,
+            } => Self::Pillar {
+                texture,
+                radius,
+                variants,
+                collider,
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                rotatable: None,
+                auto_orient: None,
+                sensor: None,
+                light,
+            },
+            TileFileV1::Model {
+                obj,
+                object,
+                collider,
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                light,
+            } => Self::Model {
+                obj,
+                object,
+                collider,
+                walkable,
+                friction,
+                move_cost,
+                surface,
+                rotatable: None,
+                auto_orient: None,
+                sensor: None,
+                light,
+            },
+        }
+    }
+}
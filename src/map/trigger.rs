@@ -0,0 +1,127 @@
+use avian3d::prelude::*;
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{content::properties::TilePropertyTable, map::Map, physics::GameLayer};
+
+/// A trigger volume filling a cell of its parent map, placed as a sensor tile. Sensing what enters
+/// it is left to whoever reads its collisions, such as the playtest.
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub struct MapTrigger {
+    pub cell: UVec3,
+    /// The key of the sensor tile, telling kinds of triggers apart.
+    pub id: String,
+}
+
+impl MapTrigger {
+    /// Where the trigger is in its map's local space, scaled to fill the cell.
+    #[inline]
+    pub fn transform(&self, tile_size: Vec3) -> Transform {
+        Transform::from_translation(self.cell.as_vec3() * tile_size).with_scale(tile_size)
+    }
+}
+
+/// Each map's sensor tiles, gathered again whenever the map or tile properties change.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct MapTriggers(pub HashMap<AssetId<Map>, Vec<MapTrigger>>);
+
+pub fn gather_map_triggers(
+    mut map_events: EventReader<AssetEvent<Map>>,
+    maps: Res<Assets<Map>>,
+    properties: Res<TilePropertyTable>,
+    mut triggers: ResMut<MapTriggers>,
+) {
+    let mut stale = HashSet::new();
+    for &e in map_events.read() {
+        match e {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } | AssetEvent::LoadedWithDependencies { id } => {
+                stale.insert(id);
+            }
+            AssetEvent::Unused { id } | AssetEvent::Removed { id } => {
+                triggers.remove(&id);
+            }
+        }
+    }
+
+    if properties.is_changed() {
+        stale.extend(maps.ids());
+    }
+
+    for id in stale {
+        let Some(map) = maps.get(id) else { continue };
+        let sensors = map.tile_set.iter().map(|key| properties.get(key).sensor).collect::<Vec<_>>();
+        let found = map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &tile)| {
+                let tile = tile?.get() as usize;
+                sensors.get(tile).copied().unwrap_or(false).then(|| MapTrigger {
+                    cell: map.cell(index),
+                    id: map.tile_set[tile].clone(),
+                })
+            })
+            .collect();
+
+        triggers.insert(id, found);
+    }
+}
+
+/// Keeps a sensor collider under each map entity for every cell of a sensor tile, as the cells
+/// change.
+pub fn update_map_triggers(
+    mut commands: Commands,
+    triggers: Res<MapTriggers>,
+    map_assets: Res<Assets<Map>>,
+    maps: Query<(Entity, &Handle<Map>, Option<&Children>)>,
+    added: Query<(), Added<Handle<Map>>>,
+    mut sensors: Query<(&MapTrigger, &mut Transform)>,
+) {
+    if !triggers.is_changed() && added.is_empty() {
+        return
+    }
+
+    let mut wanted = maps
+        .iter()
+        .filter_map(|(e, handle, _)| Some((e, map_assets.get(handle)?, triggers.get(&handle.id())?)))
+        .flat_map(|(e, map, triggers)| {
+            triggers
+                .iter()
+                .map(move |trigger| ((e, trigger.cell), (trigger, trigger.transform(map.tile_size))))
+        })
+        .collect::<HashMap<_, _>>();
+
+    // Triggers still wanted as they are stay, following their map's tile size; the rest go.
+    for (e, _, children) in &maps {
+        for &child in children.into_iter().flatten() {
+            let Ok((current, mut trns)) = sensors.get_mut(child) else {
+                continue
+            };
+
+            match wanted.remove(&(e, current.cell)) {
+                Some((trigger, local)) if trigger == current => {
+                    if *trns != local {
+                        *trns = local;
+                    }
+                }
+                _ => commands.entity(child).despawn_recursive(),
+            }
+        }
+    }
+
+    for ((e, _), (trigger, local)) in wanted {
+        let child = commands
+            .spawn((
+                TransformBundle::from_transform(local),
+                Sensor,
+                Collider::cuboid(1.0, 1.0, 1.0),
+                GameLayer::trigger(),
+                trigger.clone(),
+            ))
+            .id();
+
+        commands.entity(e).add_child(child);
+    }
+}
@@ -0,0 +1,214 @@
+use bevy::{
+    app::AppExit,
+    prelude::*,
+    window::{PresentMode, PrimaryWindow},
+};
+
+use crate::{
+    editor::{
+        display::{next_present_mode, present_mode_name, toggled_fullscreen},
+        session::{list_maps, EditorSession, SessionRequest},
+        EditorSettings,
+    },
+    AssetRoot, GameState,
+};
+
+const BUTTON_IDLE: Color = Color::srgb(0.15, 0.15, 0.18);
+const BUTTON_HOVERED: Color = Color::srgb(0.25, 0.25, 0.3);
+const BUTTON_PRESSED: Color = Color::srgb(0.35, 0.35, 0.45);
+
+pub struct MenuPlugin;
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Menu), init_menu)
+            .add_systems(Update, (press_buttons, highlight_buttons).run_if(in_state(GameState::Menu)));
+    }
+}
+
+/// Holds the list of maps shown after pressing [`MenuButton::Open`].
+#[derive(Component, Copy, Clone, Default)]
+pub struct MenuMapList;
+
+#[derive(Component, Clone, Debug)]
+pub enum MenuButton {
+    /// Enters the editor without a map to open, reopening the last one.
+    Continue,
+    New,
+    Open,
+    OpenFile(String),
+    /// Cycles through present modes, showing the current one.
+    PresentMode(PresentMode),
+    Fullscreen,
+    /// Loads again what failed to, back in [`GameState::Loading`].
+    Retry,
+    Quit,
+}
+
+impl MenuButton {
+    #[inline]
+    pub fn label(&self) -> &str {
+        match self {
+            Self::Continue => "Continue",
+            Self::New => "New Map",
+            Self::Open => "Open Map",
+            Self::OpenFile(path) => path,
+            &Self::PresentMode(mode) => present_mode_name(mode),
+            Self::Fullscreen => "Toggle Fullscreen",
+            Self::Retry => "Retry",
+            Self::Quit => "Quit",
+        }
+    }
+}
+
+pub fn spawn_button(parent: &mut ChildBuilder, button: MenuButton) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                width: Val::Px(240.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            background_color: BUTTON_IDLE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(button.label(), TextStyle {
+                font_size: 20.0,
+                ..default()
+            }));
+        })
+        .insert(button);
+}
+
+fn init_menu(mut commands: Commands, settings: Res<EditorSettings>) {
+    commands.spawn((Camera2dBundle::default(), StateScoped(GameState::Menu)));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            },
+            StateScoped(GameState::Menu),
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section("Mnemonic", TextStyle {
+                    font_size: 48.0,
+                    ..default()
+                })
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(16.0)),
+                    ..default()
+                }),
+            );
+
+            spawn_button(parent, MenuButton::Continue);
+            spawn_button(parent, MenuButton::New);
+            spawn_button(parent, MenuButton::Open);
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                MenuMapList,
+            ));
+            spawn_button(parent, MenuButton::PresentMode(settings.present_mode));
+            spawn_button(parent, MenuButton::Fullscreen);
+            spawn_button(parent, MenuButton::Quit);
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn press_buttons(
+    mut commands: Commands,
+    root: Res<AssetRoot>,
+    mut settings: ResMut<EditorSettings>,
+    mut buttons: Query<(&Interaction, &mut MenuButton, &Children), Changed<Interaction>>,
+    mut texts: Query<&mut Text>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    list: Query<Entity, With<MenuMapList>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for (&interaction, mut button, children) in &mut buttons {
+        if interaction != Interaction::Pressed {
+            continue
+        }
+
+        let pending = match &*button {
+            MenuButton::Continue => None,
+            MenuButton::New => Some(SessionRequest::New(settings.default_map_size)),
+            MenuButton::OpenFile(path) => Some(SessionRequest::Open(path.clone())),
+            MenuButton::Open => {
+                let Ok(list) = list.get_single() else { continue };
+                let maps = list_maps(&root).unwrap_or_default();
+
+                commands.entity(list).despawn_descendants().with_children(|parent| {
+                    if maps.is_empty() {
+                        parent.spawn(TextBundle::from_section("No saved maps", TextStyle {
+                            font_size: 16.0,
+                            color: Color::srgb(0.6, 0.6, 0.6),
+                            ..default()
+                        }));
+                    }
+
+                    for path in maps {
+                        spawn_button(parent, MenuButton::OpenFile(path));
+                    }
+                });
+                continue
+            }
+            &MenuButton::PresentMode(mode) => {
+                settings.present_mode = next_present_mode(mode);
+                *button = MenuButton::PresentMode(settings.present_mode);
+
+                let mut texts = texts.iter_many_mut(children);
+                while let Some(mut text) = texts.fetch_next() {
+                    text.sections[0].value = button.label().into();
+                }
+                continue
+            }
+            MenuButton::Fullscreen => {
+                for mut window in &mut windows {
+                    window.mode = toggled_fullscreen(window.mode);
+                }
+                continue
+            }
+            // Only shown over a load error, and pressed in `LoadingPlugin`.
+            MenuButton::Retry => continue,
+            MenuButton::Quit => {
+                exit.send(AppExit::Success);
+                continue
+            }
+        };
+
+        commands.insert_resource(EditorSession { pending, ..default() });
+        next_state.set(GameState::Editor);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn highlight_buttons(mut buttons: Query<(&Interaction, &mut BackgroundColor), (With<MenuButton>, Changed<Interaction>)>) {
+    for (&interaction, mut color) in &mut buttons {
+        *color = match interaction {
+            Interaction::Pressed => BUTTON_PRESSED,
+            Interaction::Hovered => BUTTON_HOVERED,
+            Interaction::None => BUTTON_IDLE,
+        }
+        .into();
+    }
+}
@@ -1,5 +1,25 @@
-use bevy::{prelude::*, utils::HashMap};
+use avian3d::{
+    parry::{
+        math::Point,
+        transformation::vhacd::{VHACDParameters, VHACD},
+    },
+    prelude::*,
+};
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+    utils::HashMap,
+};
 use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    content::properties::TilePropertyOverrides,
+    map::{cell_random, lighting::TileLight, pick_weighted, tile::TileCollider},
+};
 
 #[derive(Asset, TypePath, Deref)]
 pub struct ObjCollection {
@@ -7,15 +27,29 @@ pub struct ObjCollection {
     pub objects: HashMap<String, Handle<Obj>>,
 }
 
-#[derive(Asset, TypePath, Default)]
+#[derive(Asset, TypePath, Clone, Default)]
 pub struct Obj {
     #[dependency]
     pub material: Handle<MtlCollection>,
     pub material_key: String,
+    /// Vertex attributes, kept apart as meshes are: building one copies each list as a whole.
     pub positions: Vec<Vec3>,
     pub uvs: Vec<Vec2>,
     pub normals: Vec<Vec3>,
-    pub faces: Vec<[usize; 3]>,
+    /// Triangles, as indices into the vertex attributes; see [`Obj::face_iter`] to index with them.
+    pub faces: Vec<[u32; 3]>,
+    /// Sides the object covers whole, against which neighbouring tiles' faces may be culled.
+    pub culls: Cull,
+    /// The minimum and maximum corners bounding its positions; see [`Obj::aabb`].
+    pub bounds: (Vec3, Vec3),
+    pub collider: TileCollider,
+    /// Collision geometry of the object on its own, generated as
+    /// [`ObjSettings::generate_collider`](crate::obj::loader::ObjSettings::generate_collider) asks.
+    pub collider_data: Option<ColliderData>,
+    /// Properties its `.tile` file gives it.
+    pub properties: TilePropertyOverrides,
+    /// The light its `.tile` file has it shine.
+    pub light: Option<TileLight>,
 }
 
 #[derive(Asset, TypePath, Deref, DerefMut)]
@@ -27,10 +61,160 @@ pub struct MtlCollection {
 #[derive(TypePath, Default)]
 pub struct Mtl {
     pub diffuse_texture: Option<Handle<Image>>,
+    /// The ID of [`diffuse_texture`](Self::diffuse_texture), which identifies the tile's texture in
+    /// the atlas. The handle itself is weakened once the atlas takes the texture, and may outlive
+    /// it.
+    pub diffuse_texture_id: Option<AssetId<Image>>,
+    /// Textures picked per map cell in place of [`diffuse_texture`](Self::diffuse_texture), which
+    /// weighs `1` against theirs.
+    pub diffuse_variants: Vec<TextureVariant>,
+    /// Tangent-space normal map, from `norm` or `map_Bump`.
+    pub normal_texture: Option<Handle<Image>>,
+    pub emissive_texture: Option<Handle<Image>>,
+    /// Opacity, from `d`. Anything below `1` blends by default.
+    pub dissolve: Option<f32>,
+    /// Illumination model, from `illum`. `0` draws the diffuse color without lighting by default.
+    pub illum: Option<u8>,
+}
+
+/// A texture a material may show in place of its diffuse texture.
+#[derive(Clone, Debug)]
+pub struct TextureVariant {
+    pub texture: Handle<Image>,
+    pub weight: f32,
+}
+
+/// What collision geometry [`ObjLoader`](crate::obj::loader::ObjLoader) generates for each object.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum ColliderKind {
+    #[default]
+    None,
+    /// The object's own triangles; hollow, and exact.
+    Trimesh,
+    /// The convex hull of the object's vertices.
+    ConvexHull,
+    /// The box bounding the object's vertices.
+    Aabb,
+    /// Convex hulls approximating the object's volume, for concave objects that must be solid, such
+    /// as props pushed around. Expensive, so it's only ever done by the loader.
+    ConvexDecomposition {
+        /// The most hulls made.
+        max_hulls: u32,
+        /// Voxels along the object's longest side, which the hulls are cut from.
+        resolution: u32,
+    },
+}
+
+/// Collision geometry computed from an object's positions and faces, turned into a [`Collider`]
+/// with [`ColliderData::collider`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum ColliderData {
+    Trimesh { vertices: Vec<Vec3>, indices: Vec<[u32; 3]> },
+    ConvexHull { points: Vec<Vec3> },
+    Aabb { min: Vec3, max: Vec3 },
+    /// The points of each hull, joined into one compound collider.
+    ConvexDecomposition { hulls: Vec<Vec<Vec3>> },
+}
+
+impl ColliderData {
+    /// Computes collision geometry of the given kind, or `None` if it's [`ColliderKind::None`] or
+    /// the geometry is too degenerate to collide, such as having no faces with any area.
+    pub fn generate(kind: ColliderKind, obj: &Obj) -> Option<Self> {
+        let positions = &obj.positions;
+        if kind == ColliderKind::None {
+            return None
+        }
+
+        // Faces without area would trip up the physics engine.
+        let faces = obj
+            .faces
+            .iter()
+            .filter(|&&[a, b, c]| {
+                let [a, b, c] = [a, b, c].map(|index| positions[index as usize]);
+                (b - a).cross(c - a).length_squared() > f32::EPSILON
+            })
+            .collect::<Vec<_>>();
+
+        if faces.is_empty() {
+            return None
+        }
+
+        let used = || faces.iter().flat_map(|face| face.iter().map(|&index| positions[index as usize]));
+        match kind {
+            ColliderKind::None => None,
+            ColliderKind::Trimesh => Some(Self::Trimesh {
+                vertices: positions.to_vec(),
+                indices: faces.iter().map(|&&face| face).collect(),
+            }),
+            ColliderKind::ConvexHull => {
+                // Any face has area, so the hull is flat only if every point lies in its plane.
+                let [a, b, c] = faces.first()?.map(|index| index as usize);
+                let (origin, normal) = (positions[a], (positions[b] - positions[a]).cross(positions[c] - positions[a]));
+                used()
+                    .any(|point| (point - origin).dot(normal).abs() > f32::EPSILON)
+                    .then(|| Self::ConvexHull {
+                        points: used().collect(),
+                    })
+            }
+            ColliderKind::Aabb => {
+                let (min, max) = obj.aabb();
+                Some(Self::Aabb { min, max })
+            }
+            ColliderKind::ConvexDecomposition { max_hulls, resolution } => {
+                let points = positions.iter().map(|&pos| Point::new(pos.x, pos.y, pos.z)).collect::<Vec<_>>();
+                let indices = faces.iter().map(|&&face| face).collect::<Vec<_>>();
+                let params = VHACDParameters {
+                    max_convex_hulls: max_hulls.max(1),
+                    resolution: resolution.max(2),
+                    ..default()
+                };
+
+                // Hulls are made of the object's own triangles the voxels cover, rather than the
+                // voxels themselves, so they fit it closely.
+                let hulls = VHACD::decompose(&params, &points, &indices, true)
+                    .compute_exact_convex_hulls(&points, &indices)
+                    .into_iter()
+                    .map(|(hull, _)| {
+                        hull.into_iter()
+                            .map(|point| Vec3::new(point.x, point.y, point.z))
+                            .collect::<Vec<_>>()
+                    })
+                    .filter(|hull| hull.len() >= 4)
+                    .collect::<Vec<_>>();
+
+                (!hulls.is_empty()).then_some(Self::ConvexDecomposition { hulls })
+            }
+        }
+    }
+
+    /// Builds the physics collider, or `None` if the physics engine can't make it out.
+    pub fn collider(&self) -> Option<Collider> {
+        match self {
+            Self::Trimesh { vertices, indices } => Some(Collider::trimesh(vertices.clone(), indices.clone())),
+            Self::ConvexHull { points } => Collider::convex_hull(points.clone()),
+            &Self::Aabb { min, max } => {
+                let size = max - min;
+                Some(Collider::compound(vec![(
+                    (min + max) / 2.0,
+                    Quat::IDENTITY,
+                    Collider::cuboid(size.x, size.y, size.z),
+                )]))
+            }
+            // Flat hulls are left out, so the rest still collide.
+            Self::ConvexDecomposition { hulls } => {
+                let parts = hulls
+                    .iter()
+                    .filter_map(|hull| Some((Vec3::ZERO, Quat::IDENTITY, Collider::convex_hull(hull.clone())?)))
+                    .collect::<Vec<_>>();
+
+                (!parts.is_empty()).then(|| Collider::compound(parts))
+            }
+        }
+    }
 }
 
 bitflags! {
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
     pub struct Cull: u8 {
         const UP = 1;
         const DOWN = 1 << 1;
@@ -42,6 +226,77 @@ bitflags! {
 }
 
 impl Obj {
+    /// The diffuse texture of this object's material, if it has loaded.
+    #[inline]
+    pub fn diffuse_texture(&self, materials: &Assets<MtlCollection>) -> Option<AssetId<Image>> {
+        materials.get(&self.material)?.get(&self.material_key)?.diffuse_texture_id
+    }
+
+    /// The diffuse texture of this object's material and every variant of it.
+    pub fn diffuse_textures(&self, materials: &Assets<MtlCollection>) -> Vec<AssetId<Image>> {
+        let Some(mtl) = materials.get(&self.material).and_then(|mtl| mtl.get(&self.material_key)) else {
+            return Vec::new()
+        };
+
+        mtl.diffuse_texture_id
+            .into_iter()
+            .chain(mtl.diffuse_variants.iter().map(|variant| variant.texture.id()))
+            .collect()
+    }
+
+    /// The diffuse texture or variant this object shows in a map cell, fixed by the map's seed so
+    /// it's the same every time the map is meshed.
+    pub fn diffuse_texture_at(&self, materials: &Assets<MtlCollection>, seed: u64, cell: UVec3) -> Option<AssetId<Image>> {
+        let mtl = materials.get(&self.material)?.get(&self.material_key)?;
+        let diffuse = mtl.diffuse_texture_id?;
+        if mtl.diffuse_variants.is_empty() {
+            return Some(diffuse)
+        }
+
+        let weights = [1.0]
+            .into_iter()
+            .chain(mtl.diffuse_variants.iter().map(|variant| variant.weight));
+        match pick_weighted(weights, cell_random(seed, cell.as_ivec3())) {
+            Some(index @ 1..) => Some(mtl.diffuse_variants[index - 1].texture.id()),
+            _ => Some(diffuse),
+        }
+    }
+
+    /// Builds a standalone mesh of this object, with texture coordinates local to its own texture.
+    pub fn to_mesh(&self) -> Mesh {
+        Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, self.positions.clone())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs.clone())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals.clone())
+            .with_inserted_indices(Indices::U32(self.faces.iter().flatten().copied().collect()))
+    }
+
+    /// The faces, with indices widened to index the vertex attributes with.
+    #[inline]
+    pub fn face_iter(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        self.faces.iter().map(|face| face.map(|index| index as usize))
+    }
+
     // TODO Calculate face culling in respect to adjacent tiles.
     pub fn calculate_culls(&mut self) {}
+
+    /// The minimum and maximum corners bounding the object, as computed by
+    /// [`calculate_bounds`](Self::calculate_bounds).
+    #[inline]
+    pub fn aabb(&self) -> (Vec3, Vec3) {
+        self.bounds
+    }
+
+    /// Bounds the object's positions, or the origin alone if it has no faces.
+    pub fn calculate_bounds(&mut self) {
+        self.bounds = match self.faces.is_empty() {
+            true => (Vec3::ZERO, Vec3::ZERO),
+            false => self
+                .positions
+                .iter()
+                .fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(min, max), &pos| {
+                    (min.min(pos), max.max(pos))
+                }),
+        };
+    }
 }
@@ -2,7 +2,13 @@ use std::io::Error as IoError;
 
 use bevy::{
     asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, LoadDirectError, ParseAssetPathError},
+    color::{ColorToPacked, Srgba},
     prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::TextureFormat,
+        texture::{ImageLoaderSettings, ImageSampler},
+    },
     utils::{hashbrown::hash_map::EntryRef, Entry, HashMap},
 };
 use nom::{
@@ -12,16 +18,21 @@ use nom::{
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use super::def::{MtlCollection, Obj, ObjCollection};
-use crate::obj::{
-    def::Mtl,
-    parser::{parse_mtl, parse_obj, MtlDirective, ObjDirective},
+use super::def::{ColliderData, ColliderKind, MtlCollection, Obj, ObjCollection, TextureVariant};
+use crate::{
+    content::atlas::TileFilter,
+    obj::{
+        def::Mtl,
+        parser::{parse_mtl, MtlDirective, ObjDirective, ObjDirectives},
+    },
 };
 
 #[derive(Error, Debug)]
 pub enum ObjError {
     #[error("Vertex attribute index out of range: {index} >= {max}.")]
     OutOfRangeIndex { index: usize, max: usize },
+    #[error("More than {} vertices in object.", u32::MAX)]
+    TooManyVertices,
     #[error("Duplicated object '{0}'.")]
     DuplicateObj(String),
     #[error("Missing `{0}`.")]
@@ -42,6 +53,11 @@ pub enum ObjError {
 pub struct ObjSettings {
     pub scale: f32,
     pub flip_v: bool,
+    /// The collision geometry stored in each object's [`Obj::collider_data`], generated here rather
+    /// than when spawning, as decompositions take a while. Without the asset processor enabled, it's
+    /// generated again on every load.
+    #[serde(default)]
+    pub generate_collider: ColliderKind,
 }
 
 impl Default for ObjSettings {
@@ -50,10 +66,101 @@ impl Default for ObjSettings {
         Self {
             scale: 2.0,
             flip_v: true,
+            generate_collider: ColliderKind::None,
         }
     }
 }
 
+/// How tile textures are imported, by both [`MtlLoader`] and
+/// [`TileLoader`](crate::map::tile::TileLoader). Overridable per file through its `.meta`.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct TextureSettings {
+    /// Left readable on the CPU by default, for the tile atlas to pack.
+    pub asset_usage: RenderAssetUsages,
+    /// Whether textures' colors are read as sRGB rather than linear.
+    pub is_srgb: bool,
+    /// How textures are filtered, or the image plugin's default if `None`.
+    pub sampler: Option<TileFilter>,
+    /// Multiplies diffuse textures' colors by their alpha once decoded.
+    pub premultiply_alpha: bool,
+}
+
+impl Default for TextureSettings {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            asset_usage: RenderAssetUsages::default(),
+            is_srgb: true,
+            sampler: None,
+            premultiply_alpha: false,
+        }
+    }
+}
+
+impl TextureSettings {
+    #[inline]
+    pub fn sampler(self) -> ImageSampler {
+        match self.sampler {
+            None => ImageSampler::Default,
+            Some(TileFilter::Nearest) => ImageSampler::nearest(),
+            Some(TileFilter::Linear) => ImageSampler::linear(),
+        }
+    }
+
+    /// Applies these settings to a nested image load.
+    #[inline]
+    pub fn image_settings(self) -> impl Fn(&mut ImageLoaderSettings) + Send + Sync + 'static {
+        move |settings| {
+            settings.asset_usage = self.asset_usage;
+            settings.is_srgb = self.is_srgb;
+            settings.sampler = self.sampler();
+        }
+    }
+
+    /// Applies these settings to an image built rather than loaded, such as from others.
+    pub fn apply(self, image: &mut Image) {
+        image.asset_usage = self.asset_usage;
+        image.sampler = self.sampler();
+        image.texture_descriptor.format = match image.texture_descriptor.format {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb if self.is_srgb => TextureFormat::Rgba8UnormSrgb,
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => TextureFormat::Rgba8Unorm,
+            format => format,
+        };
+    }
+}
+
+/// Multiplies an image's colors by their alpha, in linear space. Images other than 8-bit RGBA are
+/// converted to it first, or returned as is if they can't be.
+pub fn premultiply_alpha(image: &mut Image) -> bool {
+    let format = image.texture_descriptor.format;
+    if !matches!(format, TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb) {
+        let rgba = match format.is_srgb() {
+            true => TextureFormat::Rgba8UnormSrgb,
+            false => TextureFormat::Rgba8Unorm,
+        };
+
+        let Some(converted) = image.convert(rgba) else { return false };
+        *image = converted;
+    }
+
+    let srgb = image.texture_descriptor.format.is_srgb();
+    for texel in image.data.chunks_exact_mut(4) {
+        let [r, g, b, a] = [texel[0], texel[1], texel[2], texel[3]];
+        let alpha = a as f32 / 255.0;
+        let rgb = match srgb {
+            true => {
+                let linear = LinearRgba::from(Srgba::rgba_u8(r, g, b, a)) * alpha;
+                Srgba::from(LinearRgba { alpha, ..linear }).to_u8_array_no_alpha()
+            }
+            false => [r, g, b].map(|channel| (channel as f32 * alpha).round() as u8),
+        };
+
+        texel[..3].copy_from_slice(&rgb);
+    }
+
+    true
+}
+
 pub struct ObjLoader;
 impl AssetLoader for ObjLoader {
     type Asset = ObjCollection;
@@ -75,7 +182,11 @@ impl AssetLoader for ObjLoader {
             })
         }
 
-        let &ObjSettings { scale, flip_v } = settings;
+        let &ObjSettings {
+            scale,
+            flip_v,
+            generate_collider,
+        } = settings;
 
         let mut file = String::new();
         reader.read_to_string(&mut file).await?;
@@ -86,7 +197,7 @@ impl AssetLoader for ObjLoader {
             (
                 Obj,
                 Option<&str>,
-                (Vec<Vec3>, Vec<Vec2>, Vec<Vec3>, HashMap<[usize; 3], usize>),
+                (Vec<Vec3>, Vec<Vec2>, Vec<Vec3>, HashMap<[usize; 3], u32>),
             ),
         >::new();
 
@@ -94,8 +205,8 @@ impl AssetLoader for ObjLoader {
         let mut cull = false;
         let mut current_obj = None;
 
-        for dir in parse_obj::<VerboseError<&str>>(&file).map_err(|e| parse_error(e, &file))?.1 {
-            match dir {
+        for dir in ObjDirectives::<VerboseError<&str>>::new(&file) {
+            match dir.map_err(|e| parse_error(e, &file))? {
                 ObjDirective::Comment(..) => continue,
                 ObjDirective::Preprocess(pre) => {
                     for pre in pre {
@@ -149,12 +260,12 @@ impl AssetLoader for ObjLoader {
                             Vec<Vec3>,
                             Vec<Vec2>,
                             Vec<Vec3>,
-                            HashMap<[usize; 3], usize>,
+                            HashMap<[usize; 3], u32>,
                         ),
                         obj_vertices: (&mut Vec<Vec3>, &mut Vec<Vec2>, &mut Vec<Vec3>),
-                    ) -> Result<usize, ObjError> {
+                    ) -> Result<u32, ObjError> {
                         match vertices.entry([position, uv, normal]) {
-                            Entry::Occupied(vertex) => Ok::<usize, ObjError>(*vertex.get()),
+                            Entry::Occupied(vertex) => Ok::<u32, ObjError>(*vertex.get()),
                             Entry::Vacant(e) => {
                                 let (position, uv, normal) = (
                                     positions.get(position).copied().ok_or(ObjError::OutOfRangeIndex {
@@ -172,7 +283,7 @@ impl AssetLoader for ObjLoader {
                                 );
 
                                 let (positions, uvs, normals) = obj_vertices;
-                                let len = positions.len();
+                                let len = u32::try_from(positions.len()).map_err(|_| ObjError::TooManyVertices)?;
 
                                 positions.push(position?);
                                 uvs.push(uv?);
@@ -234,6 +345,12 @@ impl AssetLoader for ObjLoader {
                     obj.calculate_culls();
                 }
 
+                obj.calculate_bounds();
+                obj.collider_data = ColliderData::generate(generate_collider, &obj);
+                if generate_collider != ColliderKind::None && obj.collider_data.is_none() {
+                    warn!("Object '{id}' of {path} is too degenerate for a {generate_collider:?} collider.");
+                }
+
                 let label = format!("obj:{id}");
                 mapped.insert_unique_unchecked(id, load_context.labeled_asset_scope(label, |_| obj));
             }
@@ -258,6 +375,8 @@ pub enum MtlError {
     Multiple(&'static str),
     #[error("Duplicated material '{0}'.")]
     DuplicateMtl(String),
+    #[error("Can't premultiply {0:?} textures.")]
+    UnsupportedFormat(TextureFormat),
     #[error("Syntax error:\n{0}")]
     Syntax(String),
     #[error(transparent)]
@@ -271,13 +390,13 @@ pub enum MtlError {
 pub struct MtlLoader;
 impl AssetLoader for MtlLoader {
     type Asset = MtlCollection;
-    type Settings = ();
+    type Settings = TextureSettings;
     type Error = MtlError;
 
     async fn load<'a>(
         &'a self,
         reader: &'a mut Reader<'_>,
-        _: &'a Self::Settings,
+        &settings: &'a Self::Settings,
         load_context: &'a mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         #[inline]
@@ -306,19 +425,64 @@ impl AssetLoader for MtlLoader {
                         EntryRef::Vacant(e) => Some(e.insert(Mtl::default())),
                     };
                 }
-                MtlDirective::MapKd(map_kd) => {
+                MtlDirective::D(dissolve) => {
+                    let current_mtl = current_mtl.as_mut().ok_or(MtlError::Missing("mtllib"))?;
+                    if current_mtl.dissolve.replace(dissolve).is_some() {
+                        return Err(MtlError::Multiple("d"))
+                    }
+                }
+                MtlDirective::Illum(illum) => {
+                    let current_mtl = current_mtl.as_mut().ok_or(MtlError::Missing("mtllib"))?;
+                    if current_mtl.illum.replace(illum).is_some() {
+                        return Err(MtlError::Multiple("illum"))
+                    }
+                }
+                MtlDirective::MapKd(file) | MtlDirective::MapKe(file) | MtlDirective::Norm(file) => {
                     let current_mtl = current_mtl.as_mut().ok_or(MtlError::Missing("mtllib"))?;
-                    if current_mtl.diffuse_texture.is_some() {
-                        return Err(MtlError::Multiple("map_Kd"))
+                    let (texture, directive) = match dir {
+                        MtlDirective::MapKd(..) => (&mut current_mtl.diffuse_texture, "map_Kd"),
+                        MtlDirective::MapKe(..) => (&mut current_mtl.emissive_texture, "map_Ke"),
+                        _ => (&mut current_mtl.normal_texture, "norm"),
+                    };
+
+                    if texture.is_some() {
+                        return Err(MtlError::Multiple(directive))
                     }
 
-                    let image = load_context
+                    let mut image = load_context
                         .loader()
+                        .with_settings(settings.image_settings())
                         .direct()
-                        .load::<Image>(path.resolve_embed(map_kd)?)
-                        .await?;
+                        .load::<Image>(path.resolve_embed(file)?)
+                        .await?
+                        .take();
+
+                    if settings.premultiply_alpha && directive == "map_Kd" && !premultiply_alpha(&mut image) {
+                        return Err(MtlError::UnsupportedFormat(image.texture_descriptor.format))
+                    }
+
+                    *texture = Some(load_context.add_labeled_asset(directive.into(), image));
+                    current_mtl.diffuse_texture_id = current_mtl.diffuse_texture.as_ref().map(Handle::id);
+                }
+                MtlDirective::MapKdVariant(weight, file) => {
+                    let current_mtl = current_mtl.as_mut().ok_or(MtlError::Missing("mtllib"))?;
+                    let mut image = load_context
+                        .loader()
+                        .with_settings(settings.image_settings())
+                        .direct()
+                        .load::<Image>(path.resolve_embed(file)?)
+                        .await?
+                        .take();
+
+                    if settings.premultiply_alpha && !premultiply_alpha(&mut image) {
+                        return Err(MtlError::UnsupportedFormat(image.texture_descriptor.format))
+                    }
 
-                    current_mtl.diffuse_texture = Some(load_context.add_loaded_labeled_asset("map_Kd", image));
+                    let label = format!("map_Kd_variant{}", current_mtl.diffuse_variants.len());
+                    current_mtl.diffuse_variants.push(TextureVariant {
+                        texture: load_context.add_labeled_asset(label, image),
+                        weight,
+                    });
                 }
             }
         }
@@ -1,17 +1,21 @@
-use std::str::FromStr;
+use std::{marker::PhantomData, str::FromStr};
 
 use nom::{
     self,
     branch::alt,
     bytes::complete::{tag, take_while, take_while1},
-    character::complete::char,
-    combinator::{cut, map, success},
+    character::complete::{char, u8},
+    combinator::{cut, map, opt, success},
     error::{context, ContextError, ErrorKind, ParseError},
-    multi::{many0, many1, many_m_n},
+    multi::{fold_many_m_n, many0, many1},
     number::complete::float,
     sequence::{preceded, terminated, tuple},
     IResult,
 };
+use smallvec::SmallVec;
+
+/// Vertices of a face, kept inline for up to quads.
+pub type FaceVertices = SmallVec<[[usize; 3]; 4]>;
 
 #[derive(Clone)]
 pub enum ObjDirective<'a> {
@@ -23,7 +27,7 @@ pub enum ObjDirective<'a> {
     Vt(f32, f32),
     Vn(f32, f32, f32),
     Usemtl(&'a str),
-    F(Vec<[usize; 3]>),
+    F(FaceVertices),
 }
 
 #[derive(Clone)]
@@ -31,6 +35,12 @@ pub enum MtlDirective<'a> {
     Comment(&'a str),
     Newmtl(&'a str),
     MapKd(&'a str),
+    /// Weight and file of a `map_Kd_variant`.
+    MapKdVariant(f32, &'a str),
+    MapKe(&'a str),
+    Norm(&'a str),
+    D(f32),
+    Illum(u8),
 }
 
 pub fn sp<'a, E: ParseError<&'a str> + ContextError<&'a str>>(input: &'a str) -> IResult<&'a str, &'a str, E> {
@@ -47,7 +57,7 @@ pub fn term<'a, E: ParseError<&'a str> + ContextError<&'a str>>(input: &'a str)
 pub fn index<'a, E: ParseError<&'a str> + ContextError<&'a str>>(input: &'a str) -> IResult<&'a str, usize, E> {
     context(
         "non-zero index",
-        map(take_while(|c| matches!(c, '0'..='9')), |input| usize::from_str(input)),
+        map(take_while(|c: char| c.is_ascii_digit()), usize::from_str),
     )(input)
     .and_then(|(input, output)| {
         Ok((
@@ -151,7 +161,7 @@ pub fn f<'a, E: ParseError<&'a str> + ContextError<&'a str>>(input: &'a str) ->
         preceded(
             tag("f"),
             cut(map(
-                many_m_n(
+                fold_many_m_n(
                     3,
                     usize::MAX,
                     preceded(
@@ -160,6 +170,11 @@ pub fn f<'a, E: ParseError<&'a str> + ContextError<&'a str>>(input: &'a str) ->
                             [v, vt, vn]
                         }),
                     ),
+                    FaceVertices::new,
+                    |mut vertices, vertex| {
+                        vertices.push(vertex);
+                        vertices
+                    },
                 ),
                 ObjDirective::F,
             )),
@@ -167,13 +182,71 @@ pub fn f<'a, E: ParseError<&'a str> + ContextError<&'a str>>(input: &'a str) ->
     )(input)
 }
 
+/// A single directive, along with the line ending after it.
+pub fn obj_directive<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, ObjDirective<'a>, E> {
+    terminated(alt((obj_comment, mtllib, o, v, vt, vn, usemtl, f)), preceded(sp, term))(input)
+}
+
+/// Parses OBJ directives one at a time as they're iterated, so a file's directives never all have
+/// to be held at once. Like [`parse_obj`], iteration stops at the first line that isn't a directive,
+/// and only malformed directives are errors.
+pub struct ObjDirectives<'a, E> {
+    input: &'a str,
+    done: bool,
+    marker: PhantomData<fn() -> E>,
+}
+
+impl<'a, E> ObjDirectives<'a, E> {
+    #[inline]
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            done: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// The input left after the directives iterated so far.
+    #[inline]
+    pub fn remaining(&self) -> &'a str {
+        self.input
+    }
+}
+
+impl<'a, E: ParseError<&'a str> + ContextError<&'a str>> Iterator for ObjDirectives<'a, E> {
+    type Item = Result<ObjDirective<'a>, nom::Err<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.input.is_empty() {
+            return None
+        }
+
+        match obj_directive(self.input) {
+            Ok((input, directive)) => {
+                self.input = input;
+                Some(Ok(directive))
+            }
+            Err(nom::Err::Error(..)) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Parses every directive at once; see [`ObjDirectives`] to go one at a time.
 pub fn parse_obj<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, Vec<ObjDirective<'a>>, E> {
-    many0(terminated(
-        alt((obj_comment, mtllib, o, v, vt, vn, usemtl, f)),
-        preceded(sp, term),
-    ))(input)
+    let mut directives = ObjDirectives::new(input);
+    let parsed = directives.by_ref().collect::<Result<_, _>>()?;
+    Ok((directives.remaining(), parsed))
 }
 
 pub fn mtl_comment<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
@@ -211,8 +284,58 @@ pub fn map_kd<'a, E: ParseError<&'a str> + ContextError<&'a str>>(input: &'a str
     )(input)
 }
 
+/// `map_Kd_variant [-w weight] file`, a texture map cells may show in place of `map_Kd`.
+pub fn map_kd_variant<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, MtlDirective<'a>, E> {
+    context(
+        "map_Kd_variant",
+        preceded(
+            tag("map_Kd_variant"),
+            cut(map(
+                tuple((opt(preceded(tuple((sp, tag("-w"), sp)), float)), preceded(sp, id))),
+                |(weight, file)| MtlDirective::MapKdVariant(weight.unwrap_or(1.0), file),
+            )),
+        ),
+    )(input)
+}
+
+pub fn map_ke<'a, E: ParseError<&'a str> + ContextError<&'a str>>(input: &'a str) -> IResult<&'a str, MtlDirective<'a>, E> {
+    context(
+        "map_Ke",
+        preceded(tag("map_Ke"), cut(preceded(sp, map(id, MtlDirective::MapKe)))),
+    )(input)
+}
+
+/// `norm`, or `map_Bump` as most exporters write normal maps.
+pub fn norm<'a, E: ParseError<&'a str> + ContextError<&'a str>>(input: &'a str) -> IResult<&'a str, MtlDirective<'a>, E> {
+    context(
+        "norm",
+        preceded(
+            alt((tag("norm"), tag("map_Bump"))),
+            cut(preceded(sp, map(id, MtlDirective::Norm))),
+        ),
+    )(input)
+}
+
+/// Dissolve, where `1` is fully opaque.
+pub fn d<'a, E: ParseError<&'a str> + ContextError<&'a str>>(input: &'a str) -> IResult<&'a str, MtlDirective<'a>, E> {
+    context("d", preceded(tag("d"), cut(preceded(sp, map(float, MtlDirective::D)))))(input)
+}
+
+pub fn illum<'a, E: ParseError<&'a str> + ContextError<&'a str>>(input: &'a str) -> IResult<&'a str, MtlDirective<'a>, E> {
+    context(
+        "illum",
+        preceded(tag("illum"), cut(preceded(sp, map(u8, MtlDirective::Illum)))),
+    )(input)
+}
+
 pub fn parse_mtl<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     input: &'a str,
 ) -> IResult<&'a str, Vec<MtlDirective<'a>>, E> {
-    many0(terminated(alt((mtl_comment, newmtl, map_kd)), preceded(sp, term)))(input)
+    many0(terminated(
+        // Variants go first, or `map_Kd` would take their tag's prefix.
+        alt((mtl_comment, newmtl, map_kd_variant, map_kd, map_ke, norm, d, illum)),
+        preceded(sp, term),
+    ))(input)
 }
@@ -0,0 +1,74 @@
+use avian3d::prelude::*;
+
+/// What colliders are, and what they collide with; see [`GameLayer::map`] and the others for each
+/// kind of body's.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum GameLayer {
+    /// The tiles of a map.
+    Map,
+    /// Objects placed on a map.
+    Prop,
+    /// The playtest character.
+    Player,
+    /// Spatial queries the editor picks with.
+    EditorPick,
+    /// Sensors of a map's trigger volumes.
+    Trigger,
+}
+
+// Not derived, as the derive checks for features of avian's own.
+impl PhysicsLayer for GameLayer {
+    #[inline]
+    fn to_bits(&self) -> u32 {
+        1 << *self as u32
+    }
+
+    #[inline]
+    fn all_bits() -> u32 {
+        (1 << 5) - 1
+    }
+}
+
+impl GameLayer {
+    /// A map's colliders, which stop players and may be picked.
+    #[inline]
+    pub fn map() -> CollisionLayers {
+        CollisionLayers::new(Self::Map, [Self::Prop, Self::Player, Self::EditorPick])
+    }
+
+    /// A prop's colliders, which rest on maps, stop players, and may be picked.
+    #[inline]
+    pub fn prop() -> CollisionLayers {
+        CollisionLayers::new(Self::Prop, [Self::Map, Self::Prop, Self::Player, Self::EditorPick])
+    }
+
+    /// The playtest character, which runs into maps and props, and sets off triggers.
+    #[inline]
+    pub fn player() -> CollisionLayers {
+        CollisionLayers::new(Self::Player, [Self::Map, Self::Prop, Self::Trigger])
+    }
+
+    /// A map's trigger volumes, which only sense players.
+    #[inline]
+    pub fn trigger() -> CollisionLayers {
+        CollisionLayers::new(Self::Trigger, Self::Player)
+    }
+
+    /// Stands in for props that don't collide, only so the editor may still pick them.
+    #[inline]
+    pub fn pick_only() -> CollisionLayers {
+        CollisionLayers::new(Self::EditorPick, LayerMask::NONE)
+    }
+
+    /// Spatial queries moving or grounding the playtest character, which pass through triggers.
+    #[inline]
+    pub fn player_filter() -> SpatialQueryFilter {
+        SpatialQueryFilter::from_mask([Self::Map, Self::Prop])
+    }
+
+    /// Spatial queries the editor picks with, which only see what may be picked.
+    #[inline]
+    pub fn pick_filter() -> SpatialQueryFilter {
+        SpatialQueryFilter::from_mask([Self::Map, Self::Prop])
+    }
+}
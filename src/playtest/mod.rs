@@ -0,0 +1,762 @@
+use std::{
+    f32::consts::FRAC_PI_2,
+    ops::{AddAssign, SubAssign},
+};
+
+use avian3d::prelude::*;
+use bevy::{
+    app::FixedMain,
+    input::mouse::MouseMotion,
+    prelude::*,
+    utils::{Duration, HashMap, HashSet, Instant},
+    window::{CursorGrabMode, PrimaryWindow},
+};
+
+use crate::{
+    character::{CharacterController, CharacterInput},
+    content::properties::{SurfaceTag, TilePropertyTable},
+    editor::{
+        camera::EditorCamera,
+        ghost::PlacementGhost,
+        prompt::Notice,
+        status::StatusBar,
+        tools::{update_cursor_target, CursorTarget},
+        view::bloom_settings,
+        EditorMap, EditorSettings,
+    },
+    map::{trigger::MapTrigger, Map},
+    obj::def::Obj,
+    physics::GameLayer,
+    EditorState, GameState,
+};
+
+pub struct PlaytestPlugin;
+impl Plugin for PlaytestPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlaytestSettings>()
+            .init_resource::<PlaytestSpawn>()
+            .init_resource::<PlaytestRestore>()
+            .init_resource::<ColliderStats>()
+            .init_resource::<PlaytestClock>()
+            .add_event::<Footstep>()
+            .add_event::<TriggerEntered>()
+            .add_event::<TriggerExited>()
+            .add_systems(
+                Update,
+                start_playtest.after(update_cursor_target).run_if(in_state(EditorState::Editing)),
+            )
+            .add_systems(OnEnter(EditorState::Playtest), (init_playtest, reset_playtest_clock))
+            .add_systems(OnExit(EditorState::Playtest), (cleanup_playtest, reset_playtest_clock))
+            .add_systems(
+                Update,
+                (
+                    (
+                        stop_playtest,
+                        control_playtest_clock,
+                        update_playtest_colliders,
+                        toggle_view,
+                        look_player,
+                        (drive_player, respawn_player).run_if(playtest_running),
+                        step_player,
+                        sense_triggers,
+                    )
+                        .chain(),
+                    step_playtest,
+                )
+                    .chain()
+                    .run_if(in_state(EditorState::Playtest)),
+            );
+    }
+}
+
+#[derive(Resource, Copy, Clone)]
+pub struct PlaytestSettings {
+    /// Toggles between the editor and a playtest.
+    pub toggle: KeyCode,
+    /// Switches between first and third person.
+    pub view_toggle: KeyCode,
+    pub pause_toggle: KeyCode,
+    /// Halves the [time scale](PlaytestClock::time_scale).
+    pub slower: KeyCode,
+    /// Doubles the [time scale](PlaytestClock::time_scale).
+    pub faster: KeyCode,
+    /// Advances a single fixed step while paused.
+    pub step: KeyCode,
+    /// Radians turned per moved pixel.
+    pub look_sensitivity: f32,
+    pub radius: f32,
+    /// Length of the capsule's straight section.
+    pub length: f32,
+    /// Height of the eyes above the capsule's center.
+    pub eye_height: f32,
+    /// Distance the camera trails behind the player in third person.
+    pub third_person_distance: f32,
+    /// World units walked on the ground between footsteps.
+    pub step_length: f32,
+    /// Cells along each side of the chunks a map's collider is split into, each rebuilt only when
+    /// its cells change.
+    pub collider_chunk: u32,
+}
+
+impl Default for PlaytestSettings {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            toggle: KeyCode::KeyP,
+            view_toggle: KeyCode::KeyV,
+            pause_toggle: KeyCode::KeyO,
+            slower: KeyCode::Comma,
+            faster: KeyCode::Period,
+            step: KeyCode::Slash,
+            look_sensitivity: 0.003,
+            radius: 0.25,
+            length: 0.9,
+            eye_height: 0.45,
+            third_person_distance: 3.0,
+            step_length: 0.7,
+            collider_chunk: 16,
+        }
+    }
+}
+
+/// Slowest a playtest may run, as a fraction of real time.
+pub const MIN_TIME_SCALE: f32 = 1.0 / 16.0;
+/// Fastest a playtest may run, as a multiple of real time.
+pub const MAX_TIME_SCALE: f32 = 4.0;
+
+/// How fast a playtest runs, if at all. Applies to both [`Time<Virtual>`], which fixed steps and
+/// character controllers follow, and [`Time<Physics>`].
+#[derive(Resource, Copy, Clone, PartialEq, Debug)]
+pub struct PlaytestClock {
+    pub paused: bool,
+    /// Speed relative to real time, within [`MIN_TIME_SCALE`] and [`MAX_TIME_SCALE`].
+    pub time_scale: f32,
+    /// Advances a single fixed step while paused, at the end of this frame's update.
+    pub step: bool,
+}
+
+impl Default for PlaytestClock {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            paused: false,
+            time_scale: 1.0,
+            step: false,
+        }
+    }
+}
+
+impl PlaytestClock {
+    /// Sets both clocks to run as told. Physics runs at full speed while paused, so a single step
+    /// is exactly one timestep.
+    pub fn apply(&self, virtual_time: &mut Time<Virtual>, physics_time: &mut Time<Physics>) {
+        virtual_time.set_relative_speed(self.time_scale);
+        physics_time.set_relative_speed(match self.paused {
+            true => 1.0,
+            false => self.time_scale,
+        });
+
+        match self.paused {
+            true => {
+                virtual_time.pause();
+                physics_time.pause();
+            }
+            false => {
+                virtual_time.unpause();
+                physics_time.unpause();
+            }
+        }
+    }
+}
+
+/// Whether gameplay moves on this frame: the playtest isn't paused, or is stepping.
+#[inline]
+pub fn playtest_running(clock: Res<PlaytestClock>) -> bool {
+    !clock.paused || clock.step
+}
+
+/// Where the player spawns, chosen from the cursor when the playtest starts.
+#[derive(Resource, Copy, Clone, Default, Deref, DerefMut)]
+pub struct PlaytestSpawn(pub Vec3);
+
+/// Editor state set aside for the playtest, put back once it ends.
+#[derive(Resource, Clone, Default)]
+pub struct PlaytestRestore {
+    /// Visibilities of the editor's UI and previews.
+    pub visibilities: Vec<(Entity, Visibility)>,
+    pub grab_mode: CursorGrabMode,
+    pub cursor_visible: bool,
+}
+
+/// The playtest character, moved as a [`CharacterController`] by the keyboard and mouse.
+#[derive(Component, Copy, Clone, Default)]
+pub struct Player {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub third_person: bool,
+    /// Distance walked on the ground since the last footstep.
+    pub stride: f32,
+    /// Where the player was when its steps were last checked.
+    pub last_position: Option<Vec3>,
+}
+
+#[derive(Component, Copy, Clone, Default)]
+pub struct PlayerCamera;
+
+/// Sent when the player takes a step, with what it stepped on and where its feet are.
+#[derive(Event, Copy, Clone, PartialEq, Debug)]
+pub struct Footstep(pub SurfaceTag, pub Vec3);
+
+/// Sent when something, such as the player, enters a [`MapTrigger`] in the cell of a map.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct TriggerEntered {
+    /// The trigger's [`id`](MapTrigger::id).
+    pub id: String,
+    pub cell: UVec3,
+    pub entity: Entity,
+}
+
+/// Sent when something leaves a [`MapTrigger`] it [entered](TriggerEntered).
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct TriggerExited {
+    pub id: String,
+    pub cell: UVec3,
+    pub entity: Entity,
+}
+
+/// A part of a map's collider, of the tiles of one chunk with the same friction, spawned under it
+/// for the playtest.
+#[derive(Component, Copy, Clone, Default, Debug)]
+pub struct PlaytestCollider(pub UVec3);
+
+/// The [`Map::chunk_signatures`] a map's [`PlaytestCollider`]s were built from.
+#[derive(Component, Clone, Default, Debug, Deref, DerefMut)]
+pub struct ColliderChunks(pub HashMap<UVec3, u64>);
+
+/// Counters from maintaining playtest colliders, for judging their cost.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct ColliderStats {
+    /// Chunks rebuilt by the last run of [`update_playtest_colliders`].
+    pub rebuilt: usize,
+    /// Time the last run of [`update_playtest_colliders`] took.
+    pub elapsed: Duration,
+    /// Time the last build that rebuilt anything took, including the first one as the playtest
+    /// starts.
+    pub last_build: Duration,
+    /// What every [`PlaytestCollider`] adds up to.
+    pub cost: ColliderCost,
+}
+
+/// How much a collider holds, looking into compounds: its shapes, and the triangles among them.
+#[derive(Component, Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct ColliderCost {
+    pub shapes: usize,
+    pub triangles: usize,
+}
+
+impl ColliderCost {
+    pub fn of(collider: &Collider) -> Self {
+        let mut cost = Self::default();
+        let mut shapes = vec![collider.shape()];
+        while let Some(shape) = shapes.pop() {
+            if let Some(compound) = shape.as_compound() {
+                shapes.extend(compound.shapes().iter().map(|(_, part)| part));
+                continue
+            }
+
+            cost.shapes += 1;
+            cost.triangles += match shape.as_trimesh() {
+                Some(mesh) => mesh.indices().len(),
+                None => shape.as_triangle().is_some() as usize,
+            };
+        }
+
+        cost
+    }
+}
+
+impl AddAssign for ColliderCost {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        self.shapes += rhs.shapes;
+        self.triangles += rhs.triangles;
+    }
+}
+
+impl SubAssign for ColliderCost {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        self.shapes = self.shapes.saturating_sub(rhs.shapes);
+        self.triangles = self.triangles.saturating_sub(rhs.triangles);
+    }
+}
+
+/// Spawns the colliders of a chunk of a map under it, returning what they cost.
+pub fn spawn_chunk_colliders(
+    parent: &mut ChildBuilder,
+    map: &Map,
+    objs: &Assets<Obj>,
+    properties: &TilePropertyTable,
+    chunk: UVec3,
+    size: u32,
+) -> ColliderCost {
+    let min = chunk * size;
+    let mut total = ColliderCost::default();
+    for (collider, friction) in map.build_colliders_in(objs, properties, min, min + size) {
+        let cost = ColliderCost::of(&collider);
+        total += cost;
+
+        parent.spawn((
+            TransformBundle::default(),
+            collider,
+            friction,
+            GameLayer::map(),
+            PlaytestCollider(chunk),
+            cost,
+            StateScoped(EditorState::Playtest),
+        ));
+    }
+
+    total
+}
+
+pub fn start_playtest(
+    settings: Res<PlaytestSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    target: Res<CursorTarget>,
+    maps: Res<Assets<Map>>,
+    editor_maps: Query<(&Handle<Map>, &GlobalTransform), With<EditorMap>>,
+    mut spawn: ResMut<PlaytestSpawn>,
+    mut next_state: ResMut<NextState<EditorState>>,
+) {
+    if !keys.just_pressed(settings.toggle) || keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return
+    }
+
+    let Ok((handle, &map_trns)) = editor_maps.get_single() else {
+        return
+    };
+    let Some(map) = maps.get(handle) else { return };
+
+    // Stand in the empty cell the cursor would place into, or above the middle of the map.
+    let cell = target.click_cell(true).unwrap_or_else(|| {
+        let size = map.size.as_ivec3();
+        IVec3::new(size.x / 2, size.y, size.z / 2)
+    });
+
+    let feet = map.cell_min(cell).y + 0.05;
+    let center = cell.as_vec3() * map.tile_size;
+    let height = settings.length / 2.0 + settings.radius;
+
+    **spawn = map_trns.transform_point(Vec3::new(center.x, feet + height, center.z));
+    next_state.set(EditorState::Playtest);
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn init_playtest(
+    mut commands: Commands,
+    settings: Res<PlaytestSettings>,
+    editor: Res<EditorSettings>,
+    spawn: Res<PlaytestSpawn>,
+    mut restore: ResMut<PlaytestRestore>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+    mut editor_cameras: Query<&mut Camera, With<EditorCamera>>,
+    mut hidden: Query<
+        (Entity, &mut Visibility),
+        Or<((With<StateScoped<GameState>>, With<Node>, Without<StatusBar>), With<PlacementGhost>)>,
+    >,
+    editor_maps: Query<(Entity, &Handle<Map>), With<EditorMap>>,
+    maps: Res<Assets<Map>>,
+    objs: Res<Assets<Obj>>,
+    properties: Res<TilePropertyTable>,
+    mut stats: ResMut<ColliderStats>,
+) {
+    restore.visibilities.clear();
+    for (e, mut visibility) in &mut hidden {
+        restore.visibilities.push((e, *visibility));
+        *visibility = Visibility::Hidden;
+    }
+
+    for mut camera in &mut editor_cameras {
+        camera.is_active = false;
+    }
+
+    if let Ok(mut window) = window.get_single_mut() {
+        restore.grab_mode = window.cursor.grab_mode;
+        restore.cursor_visible = window.cursor.visible;
+
+        window.cursor.grab_mode = CursorGrabMode::Locked;
+        window.cursor.visible = false;
+    }
+
+    // The map is only solid while playtesting, so edits don't keep rebuilding its colliders. Tiles
+    // are split between them by chunk and friction.
+    let start = Instant::now();
+    *stats = default();
+    for (e, handle) in &editor_maps {
+        let Some(map) = maps.get(handle) else { continue };
+        let chunks = map.chunk_signatures(settings.collider_chunk);
+        commands
+            .entity(e)
+            .insert(RigidBody::Static)
+            .with_children(|parent| {
+                for &chunk in chunks.keys() {
+                    stats.cost += spawn_chunk_colliders(parent, map, &objs, &properties, chunk, settings.collider_chunk);
+                }
+            })
+            .insert(ColliderChunks(chunks));
+    }
+
+    stats.last_build = start.elapsed();
+
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_translation(**spawn)),
+            RigidBody::Kinematic,
+            Collider::capsule(settings.radius, settings.length),
+            GameLayer::player(),
+            CharacterController::default(),
+            CharacterInput::default(),
+            Player::default(),
+            StateScoped(EditorState::Playtest),
+        ))
+        .with_children(|parent| {
+            let mut camera = parent.spawn((
+                Camera3dBundle {
+                    camera: Camera { hdr: true, ..default() },
+                    transform: Transform::from_xyz(0.0, settings.eye_height, 0.0),
+                    tonemapping: editor.tonemapping,
+                    ..default()
+                },
+                PlayerCamera,
+            ));
+
+            if let Some(bloom) = bloom_settings(&editor) {
+                camera.insert(bloom);
+            }
+        });
+}
+
+/// Gives the editor its view back. The player and colliders, scoped to the playtest, are despawned
+/// along with it.
+pub fn cleanup_playtest(
+    mut commands: Commands,
+    mut restore: ResMut<PlaytestRestore>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+    mut editor_cameras: Query<&mut Camera, With<EditorCamera>>,
+    mut visibilities: Query<&mut Visibility>,
+    editor_maps: Query<Entity, With<EditorMap>>,
+    mut stats: ResMut<ColliderStats>,
+) {
+    stats.cost = default();
+
+    for e in &editor_maps {
+        commands.entity(e).remove::<(RigidBody, ColliderChunks)>();
+    }
+
+    for (e, visibility) in restore.visibilities.drain(..) {
+        if let Ok(mut current) = visibilities.get_mut(e) {
+            *current = visibility;
+        }
+    }
+
+    for mut camera in &mut editor_cameras {
+        camera.is_active = true;
+    }
+
+    if let Ok(mut window) = window.get_single_mut() {
+        window.cursor.grab_mode = restore.grab_mode;
+        window.cursor.visible = restore.cursor_visible;
+    }
+}
+
+pub fn stop_playtest(
+    settings: Res<PlaytestSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<EditorState>>,
+) {
+    if keys.any_just_pressed([settings.toggle, KeyCode::Escape]) {
+        next_state.set(EditorState::Editing);
+    }
+}
+
+/// Rebuilds the colliders of the chunks of changed maps whose cells changed. Each chunk's old
+/// colliders are swapped for the new ones at once, so the map never goes without.
+#[allow(clippy::too_many_arguments)]
+pub fn update_playtest_colliders(
+    mut commands: Commands,
+    settings: Res<PlaytestSettings>,
+    mut events: EventReader<AssetEvent<Map>>,
+    maps: Res<Assets<Map>>,
+    objs: Res<Assets<Obj>>,
+    properties: Res<TilePropertyTable>,
+    mut editor_maps: Query<(Entity, &Handle<Map>, &mut ColliderChunks)>,
+    colliders: Query<(Entity, &Parent, &PlaytestCollider, &ColliderCost)>,
+    mut stats: ResMut<ColliderStats>,
+) {
+    let modified = events
+        .read()
+        .filter_map(|e| match *e {
+            AssetEvent::Modified { id } => Some(id),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    let start = Instant::now();
+    stats.rebuilt = 0;
+
+    for (e, handle, mut chunks) in &mut editor_maps {
+        if !modified.contains(&handle.id()) {
+            continue
+        }
+
+        let Some(map) = maps.get(handle) else { continue };
+        let signatures = map.chunk_signatures(settings.collider_chunk);
+        let stale = signatures
+            .iter()
+            .filter(|&(chunk, signature)| chunks.get(chunk) != Some(signature))
+            .map(|(&chunk, _)| chunk)
+            .chain(chunks.keys().copied().filter(|chunk| !signatures.contains_key(chunk)))
+            .collect::<HashSet<_>>();
+
+        if stale.is_empty() {
+            continue
+        }
+
+        for (collider, parent, &PlaytestCollider(chunk), &cost) in &colliders {
+            if parent.get() == e && stale.contains(&chunk) {
+                commands.entity(collider).despawn_recursive();
+                stats.cost -= cost;
+            }
+        }
+
+        commands.entity(e).with_children(|parent| {
+            for &chunk in &stale {
+                stats.cost += spawn_chunk_colliders(parent, map, &objs, &properties, chunk, settings.collider_chunk);
+            }
+        });
+
+        stats.rebuilt += stale.len();
+        chunks.0 = signatures;
+    }
+
+    stats.elapsed = start.elapsed();
+    if stats.rebuilt > 0 {
+        stats.last_build = stats.elapsed;
+    }
+}
+
+/// Pauses, steps, and scales the playtest's time by keys.
+pub fn control_playtest_clock(
+    settings: Res<PlaytestSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut clock: ResMut<PlaytestClock>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut physics_time: ResMut<Time<Physics>>,
+    mut notice: ResMut<Notice>,
+) {
+    let mut next = PlaytestClock {
+        step: false,
+        ..*clock
+    };
+
+    if keys.just_pressed(settings.pause_toggle) {
+        next.paused = !next.paused;
+    }
+
+    if keys.just_pressed(settings.slower) {
+        next.time_scale = (next.time_scale / 2.0).max(MIN_TIME_SCALE);
+    }
+
+    if keys.just_pressed(settings.faster) {
+        next.time_scale = (next.time_scale * 2.0).min(MAX_TIME_SCALE);
+    }
+
+    next.step = next.paused && keys.just_pressed(settings.step);
+    if next.paused != clock.paused || next.time_scale != clock.time_scale {
+        next.apply(&mut virtual_time, &mut physics_time);
+        notice.show(match next.paused {
+            true => format!("Paused at {}x", next.time_scale),
+            false => format!("Running at {}x", next.time_scale),
+        });
+    }
+
+    clock.set_if_neq(next);
+}
+
+/// Advances the paused playtest by a single fixed step if asked to, running [`FixedMain`] once and
+/// physics once after it. Both clocks are advanced by exactly their timestep, as if it had passed.
+pub fn step_playtest(world: &mut World) {
+    if !std::mem::take(&mut world.resource_mut::<PlaytestClock>().step) {
+        return
+    }
+
+    let mut physics_time = world.resource_mut::<Time<Physics>>();
+    let timestep = match physics_time.timestep_mode() {
+        TimestepMode::Fixed { delta, .. } | TimestepMode::FixedOnce { delta } => delta,
+        TimestepMode::Variable { max_delta } => max_delta,
+    };
+
+    // Paused physics runs as far as its clock was advanced by hand.
+    physics_time.advance_by(timestep);
+
+    let timestep = world.resource::<Time<Fixed>>().timestep();
+    world.resource_mut::<Time<Fixed>>().advance_by(timestep);
+    *world.resource_mut::<Time>() = world.resource::<Time<Fixed>>().as_generic();
+    let _ = world.try_run_schedule(FixedMain);
+    *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
+}
+
+/// Playtests start running at full speed, and leave time as it was before.
+pub fn reset_playtest_clock(
+    mut clock: ResMut<PlaytestClock>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut physics_time: ResMut<Time<Physics>>,
+) {
+    *clock = default();
+    clock.apply(&mut virtual_time, &mut physics_time);
+}
+
+pub fn toggle_view(settings: Res<PlaytestSettings>, keys: Res<ButtonInput<KeyCode>>, mut players: Query<&mut Player>) {
+    if keys.just_pressed(settings.view_toggle) {
+        for mut player in &mut players {
+            player.third_person = !player.third_person;
+        }
+    }
+}
+
+pub fn look_player(
+    settings: Res<PlaytestSettings>,
+    mut motion: EventReader<MouseMotion>,
+    mut players: Query<(&mut Player, &CharacterController, &mut Transform, &Children), Without<PlayerCamera>>,
+    mut cameras: Query<&mut Transform, With<PlayerCamera>>,
+) {
+    let look = motion.read().map(|e| e.delta).sum::<Vec2>() * settings.look_sensitivity;
+    for (mut player, controller, mut trns, children) in &mut players {
+        player.yaw -= look.x;
+        player.pitch = (player.pitch - look.y).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+        trns.rotation = Quat::from_rotation_y(player.yaw);
+
+        let pitch = Quat::from_rotation_x(player.pitch);
+        let eye = Vec3::Y * (settings.eye_height + controller.eye_offset);
+        for &child in children {
+            let Ok(mut camera) = cameras.get_mut(child) else { continue };
+            camera.rotation = pitch;
+            camera.translation = match player.third_person {
+                true => eye + pitch * Vec3::Z * settings.third_person_distance,
+                false => eye,
+            };
+        }
+    }
+}
+
+/// Walks the player towards where it looks by the movement keys, and jumps.
+pub fn drive_player(keys: Res<ButtonInput<KeyCode>>, mut players: Query<(&Player, &mut CharacterInput)>) {
+    let mut wish = Vec3::ZERO;
+    for (key, axis) in [
+        (KeyCode::KeyW, Vec3::NEG_Z),
+        (KeyCode::KeyS, Vec3::Z),
+        (KeyCode::KeyA, Vec3::NEG_X),
+        (KeyCode::KeyD, Vec3::X),
+    ] {
+        if keys.pressed(key) {
+            wish += axis;
+        }
+    }
+
+    for (player, mut input) in &mut players {
+        input.walk = (Quat::from_rotation_y(player.yaw) * wish.normalize_or_zero()).xz();
+        // Kept until a fixed step runs, which frames may go without.
+        input.jump |= keys.just_pressed(KeyCode::Space);
+    }
+}
+
+/// Falling off the map starts over from the spawn.
+pub fn respawn_player(
+    spawn: Res<PlaytestSpawn>,
+    mut players: Query<(&mut CharacterController, &mut Transform), With<Player>>,
+) {
+    for (mut controller, mut trns) in &mut players {
+        if trns.translation.y < spawn.y - 100.0 {
+            trns.translation = **spawn;
+            controller.velocity = Vec3::ZERO;
+        }
+    }
+}
+
+pub fn step_player(
+    settings: Res<PlaytestSettings>,
+    properties: Res<TilePropertyTable>,
+    maps: Res<Assets<Map>>,
+    editor_maps: Query<(&Handle<Map>, &GlobalTransform), With<EditorMap>>,
+    mut players: Query<(&mut Player, &CharacterController, &Transform)>,
+    mut footsteps: EventWriter<Footstep>,
+) {
+    for (mut player, controller, trns) in &mut players {
+        // Jumps and falls don't count towards steps.
+        let Some(last) = player.last_position.replace(trns.translation) else {
+            continue
+        };
+        if !controller.grounded {
+            continue
+        }
+
+        player.stride += (trns.translation - last).xz().length();
+        if player.stride < settings.step_length {
+            continue
+        }
+        player.stride %= settings.step_length;
+
+        // Step on whatever is just below the capsule's bottom.
+        let feet = trns.translation - Vec3::Y * (settings.length / 2.0 + settings.radius);
+        let surface = editor_maps.iter().find_map(|(handle, map_trns)| {
+            let map = maps.get(handle)?;
+            let cell = map.cell_at(map_trns.affine().inverse().transform_point3(feet - Vec3::Y * 0.05));
+            map.contains(cell)
+                .then(|| map.surface_at(cell.as_uvec3(), &properties))
+                .flatten()
+        });
+
+        if let Some(surface) = surface {
+            debug!("Stepped on {surface:?} at {feet}.");
+            footsteps.send(Footstep(surface, feet));
+        }
+    }
+}
+
+/// Tells when things enter and leave map triggers, from their sensors' collisions.
+pub fn sense_triggers(
+    mut started: EventReader<CollisionStarted>,
+    mut ended: EventReader<CollisionEnded>,
+    triggers: Query<&MapTrigger>,
+    mut entered: EventWriter<TriggerEntered>,
+    mut exited: EventWriter<TriggerExited>,
+) {
+    // Triggers only sense players, so only one of each pair is a trigger.
+    let sensed = |a: Entity, b: Entity| match (triggers.get(a), triggers.get(b)) {
+        (Ok(trigger), Err(..)) => Some((trigger, b)),
+        (Err(..), Ok(trigger)) => Some((trigger, a)),
+        _ => None,
+    };
+
+    for &CollisionStarted(a, b) in started.read() {
+        if let Some((trigger, entity)) = sensed(a, b) {
+            debug!("{entity} entered trigger `{}` at {}.", trigger.id, trigger.cell);
+            entered.send(TriggerEntered {
+                id: trigger.id.clone(),
+                cell: trigger.cell,
+                entity,
+            });
+        }
+    }
+
+    for &CollisionEnded(a, b) in ended.read() {
+        if let Some((trigger, entity)) = sensed(a, b) {
+            exited.send(TriggerExited {
+                id: trigger.id.clone(),
+                cell: trigger.cell,
+                entity,
+            });
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        settings::WgpuLimits,
+    },
+};
+use mnemonic::content::{build_tile_pages, TileMaps, TileTexture};
+
+fn fill(texel: [u8; 4]) -> Image {
+    Image::new_fill(
+        Extent3d {
+            width: 8,
+            height: 8,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &texel,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::all(),
+    )
+}
+
+/// Adds a diffuse texture and, if given, its normal map, returning the diffuse texture's ID.
+fn add(texture: &mut TileTexture, images: &mut Assets<Image>, diffuse: Image, normal: Option<Image>) -> AssetId<Image> {
+    let mut source = |image: Image| {
+        let handle = images.add(image.clone());
+        texture.sources.insert(handle.id(), (handle.clone(), image));
+        handle.id()
+    };
+
+    let (diffuse, normal) = (source(diffuse), normal.map(&mut source));
+    texture.maps.insert(diffuse, TileMaps { normal, emissive: None });
+    diffuse
+}
+
+#[test]
+fn identical_textures_share_a_rect() {
+    let mut images = Assets::<Image>::default();
+    let mut layouts = Assets::<TextureAtlasLayout>::default();
+    let mut texture = TileTexture {
+        pages: Vec::new(),
+        page_of: default(),
+        maps: default(),
+        sources: default(),
+        released: default(),
+        aliases: default(),
+    };
+
+    let red = [255, 0, 0, 255];
+    let first = add(&mut texture, &mut images, fill(red), None);
+    let copy = add(&mut texture, &mut images, fill(red), None);
+    let other = add(&mut texture, &mut images, fill([0, 255, 0, 255]), None);
+    // Same diffuse texture, but a different normal map, so it can't be collapsed.
+    let bumpy = add(&mut texture, &mut images, fill(red), Some(fill([128, 128, 255, 255])));
+
+    assert_eq!(texture.dedupe(), 1);
+    let canonical = texture.canonical(copy);
+    assert!(canonical == first || canonical == copy);
+    assert_eq!(texture.canonical(texture.canonical(first)), canonical);
+    assert_eq!(texture.canonical(other), other);
+    assert_eq!(texture.canonical(bumpy), bumpy);
+
+    let pages = build_tile_pages(&texture, default(), &WgpuLimits::default()).unwrap();
+    assert_eq!(pages.iter().map(|page| page.layout.textures.len()).sum::<usize>(), 3);
+
+    texture.set_pages(pages, &mut layouts, &mut images);
+    let (first, copy) = (texture.locate(&layouts, first), texture.locate(&layouts, copy));
+    assert!(first.is_some());
+    assert_eq!(first, copy, "duplicates must resolve to the same rect");
+}
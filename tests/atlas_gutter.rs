@@ -0,0 +1,78 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use mnemonic::content::atlas::{extrude_edges, TileAtlasSettings};
+
+/// A texture whose every texel is distinct, so a texel copied from the wrong place shows.
+fn texture(size: UVec2, seed: u8) -> Image {
+    let data = (0..size.y)
+        .flat_map(|y| (0..size.x).flat_map(move |x| [x as u8, y as u8, seed, 255]))
+        .collect();
+
+    Image::new(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::all(),
+    )
+}
+
+fn texel(image: &Image, pos: UVec2) -> [u8; 4] {
+    let index = (pos.y * image.width() + pos.x) as usize * 4;
+    image.data[index..index + 4].try_into().unwrap()
+}
+
+#[test]
+fn gutter_repeats_edge_texels() {
+    let settings = TileAtlasSettings { gutter: 3, ..default() };
+    let padding = settings.padding(1);
+    assert!(padding >= settings.gutter * 2);
+
+    let mut images = Assets::<Image>::default();
+    let textures = [UVec2::new(8, 8), UVec2::new(16, 4), UVec2::new(5, 11)]
+        .into_iter()
+        .enumerate()
+        .map(|(seed, size)| {
+            let image = texture(size, seed as u8);
+            (images.add(image.clone()).id(), image)
+        })
+        .collect::<Vec<_>>();
+
+    let mut builder = TextureAtlasBuilder::default();
+    builder.format(TextureFormat::Rgba8UnormSrgb).padding(UVec2::splat(padding));
+    for (id, image) in &textures {
+        builder.add_texture(Some(*id), image);
+    }
+
+    let (layout, mut atlas) = builder.build().unwrap();
+    extrude_edges(&mut atlas, &layout, padding / 2);
+
+    let size = atlas.size().as_ivec2();
+    for (id, image) in &textures {
+        let rect = layout.textures[layout.get_texture_index(*id).unwrap()];
+        assert_eq!(rect.size(), image.size(), "UV rects must only address the original texture");
+
+        let (min, max) = (rect.min.as_ivec2(), rect.max.as_ivec2() - 1);
+        let gutter = settings.gutter as i32;
+        for y in (min.y - gutter).max(0)..=(max.y + gutter).min(size.y - 1) {
+            for x in (min.x - gutter).max(0)..=(max.x + gutter).min(size.x - 1) {
+                let pos = IVec2::new(x, y);
+                let source = (pos.clamp(min, max) - min).as_uvec2();
+                assert_eq!(
+                    texel(&atlas, pos.as_uvec2()),
+                    texel(image, source),
+                    "texel {pos} should repeat {source} of its texture",
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{AddressMode, Extent3d, FilterMode, TextureDimension, TextureFormat},
+        settings::WgpuLimits,
+        texture::ImageSampler,
+    },
+};
+use mnemonic::content::{
+    atlas::{TileAtlasSettings, TileBackend, TileFilter},
+    build_tile_pages, TileMaps, TileTexture,
+};
+
+fn fill(size: UVec2, texel: [u8; 4], format: TextureFormat) -> Image {
+    Image::new_fill(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &texel,
+        format,
+        RenderAssetUsages::all(),
+    )
+}
+
+/// Tile textures as taken out of the assets, each with a normal map.
+fn tile_texture(images: &mut Assets<Image>) -> TileTexture {
+    let mut texture = TileTexture {
+        pages: Vec::new(),
+        page_of: default(),
+        maps: default(),
+        sources: default(),
+        released: default(),
+        aliases: default(),
+    };
+
+    for (seed, size) in [UVec2::new(8, 8), UVec2::new(16, 4)].into_iter().enumerate() {
+        let diffuse = fill(size, [seed as u8, 0, 0, 255], TextureFormat::Rgba8UnormSrgb);
+        let normal = fill(size, [128, 128, 255, 255], TextureFormat::Rgba8Unorm);
+        let (diffuse_handle, normal_handle) = (images.add(diffuse.clone()), images.add(normal.clone()));
+
+        texture.maps.insert(diffuse_handle.id(), TileMaps {
+            normal: Some(normal_handle.id()),
+            emissive: None,
+        });
+        texture.sources.insert(diffuse_handle.id(), (diffuse_handle, diffuse));
+        texture.sources.insert(normal_handle.id(), (normal_handle, normal));
+    }
+
+    texture
+}
+
+fn assert_sampler(image: &Image, filter: FilterMode, address: AddressMode) {
+    let ImageSampler::Descriptor(ref descriptor) = image.sampler else {
+        panic!("tile textures must not be left to the default sampler");
+    };
+
+    let descriptor = descriptor.as_wgpu();
+    assert_eq!([descriptor.mag_filter, descriptor.min_filter], [filter; 2]);
+    assert_eq!([descriptor.address_mode_u, descriptor.address_mode_v], [address; 2]);
+}
+
+#[test]
+fn pages_use_configured_filter() {
+    let mut images = Assets::<Image>::default();
+    let texture = tile_texture(&mut images);
+
+    for (filter, mode) in [
+        (TileFilter::Nearest, FilterMode::Nearest),
+        (TileFilter::Linear, FilterMode::Linear),
+    ] {
+        let settings = TileAtlasSettings { filter, ..default() };
+        let pages = build_tile_pages(&texture, settings, &WgpuLimits::default()).unwrap();
+        assert!(!pages.is_empty());
+
+        for page in &pages {
+            assert_sampler(&page.atlas, mode, AddressMode::ClampToEdge);
+            assert_sampler(page.normal_atlas.as_ref().unwrap(), mode, AddressMode::ClampToEdge);
+        }
+
+        let settings = TileAtlasSettings {
+            backend: TileBackend::Array,
+            ..settings
+        };
+
+        for page in build_tile_pages(&texture, settings, &WgpuLimits::default()).unwrap() {
+            assert_sampler(&page.atlas, mode, AddressMode::Repeat);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+use mnemonic::{
+    content::properties::{AutoOrient, TileProperties},
+    map::{diff::MapDiff, orientation::TileOrientation, tile::TileFile, Map, MapCell},
+    obj::def::Obj,
+};
+use nonmax::NonMaxU8;
+
+const STONE: NonMaxU8 = NonMaxU8::ZERO;
+const WALL: NonMaxU8 = NonMaxU8::ONE;
+
+fn room(auto_orient: AutoOrient) -> (Map, [TileProperties; 2]) {
+    let mut map = Map::empty(UVec3::new(5, 1, 5));
+    map.tile_set = vec!["stone".into(), "wall".into()];
+
+    let wall = TileProperties {
+        rotatable: false,
+        auto_orient,
+        ..default()
+    };
+    (map, [default(), wall])
+}
+
+/// Places a cell the way the editor does, settling orientations afterwards.
+fn place(map: &mut Map, properties: &[TileProperties], cell: IVec3, value: MapCell) -> MapDiff {
+    let mut diff = map.fill_cells([cell], value);
+    diff.extend(map.orient_after(&diff, properties));
+    diff
+}
+
+#[test]
+fn walls_face_into_rooms() {
+    for auto_orient in [AutoOrient::AwayFromSolid, AutoOrient::TowardEmpty] {
+        let (mut map, properties) = room(auto_orient);
+        let mut diff = MapDiff::default();
+        for corner in [
+            IVec3::new(0, 0, 0),
+            IVec3::new(4, 0, 0),
+            IVec3::new(0, 0, 4),
+            IVec3::new(4, 0, 4),
+        ] {
+            diff.extend(place(&mut map, &properties, corner, MapCell::new(Some(STONE), default())));
+        }
+
+        // Walls are placed in a scattered order and turned every which way, so each settles both as
+        // it's placed and as its neighbors are.
+        let perimeter = (1..4)
+            .flat_map(|i| [(i, 0), (i, 4), (0, i), (4, i)])
+            .map(|(x, z)| IVec3::new(x, 0, z))
+            .collect::<Vec<_>>();
+        for (i, &cell) in perimeter.iter().enumerate().rev() {
+            let value = MapCell::new(Some(WALL), TileOrientation::new(i as i32, false));
+            diff.extend(place(&mut map, &properties, cell, value));
+        }
+
+        for cell in perimeter {
+            let inward = match (cell.x, cell.z) {
+                (_, 0) => IVec3::Z,
+                (_, 4) => IVec3::NEG_Z,
+                (0, _) => IVec3::X,
+                _ => IVec3::NEG_X,
+            };
+
+            let orientation = map.orientation(cell.as_uvec3());
+            assert_eq!(
+                TileOrientation::facing(orientation.turns()),
+                inward,
+                "{auto_orient:?} wall at {cell} faces the wrong way"
+            );
+        }
+
+        // Settled orientations are part of the diff, so undoing it clears the room.
+        diff.revert(&mut map);
+        assert!(map.tiles.iter().all(Option::is_none));
+        assert!(map
+            .orientations
+            .iter()
+            .all(|&orientation| orientation == TileOrientation::IDENTITY));
+    }
+}
+
+#[test]
+fn walls_turn_as_neighbors_change() {
+    let (mut map, properties) = room(AutoOrient::TowardEmpty);
+    let cell = IVec3::new(2, 0, 2);
+    place(&mut map, &properties, cell, MapCell::new(Some(WALL), default()));
+    place(
+        &mut map,
+        &properties,
+        cell + IVec3::NEG_X,
+        MapCell::new(Some(STONE), default()),
+    );
+    assert_eq!(TileOrientation::facing(map.orientation(cell.as_uvec3()).turns()), IVec3::X);
+
+    // Erasing the stone and walling the far side turns the wall around, recorded in the same diff.
+    let mut diff = map.fill_cells([cell + IVec3::NEG_X], MapCell::EMPTY);
+    diff.extend(map.fill_cells([cell + IVec3::X], MapCell::new(Some(STONE), default())));
+    let oriented = map.orient_after(&diff, &properties);
+    assert_eq!(oriented.changed_cells(), 1);
+    assert_eq!(
+        TileOrientation::facing(map.orientation(cell.as_uvec3()).turns()),
+        IVec3::NEG_X
+    );
+}
+
+#[test]
+fn unrotatable_tiles_are_placed_unturned() {
+    let (mut map, mut properties) = room(AutoOrient::None);
+    properties[STONE.get() as usize].rotatable = false;
+    properties[WALL.get() as usize].rotatable = true;
+
+    let turned = TileOrientation::new(1, true);
+    place(&mut map, &properties, IVec3::ZERO, MapCell::new(Some(STONE), turned));
+    place(&mut map, &properties, IVec3::X, MapCell::new(Some(WALL), turned));
+    assert_eq!(map.orientation(UVec3::ZERO), TileOrientation::IDENTITY);
+    assert_eq!(map.orientation(UVec3::X), turned);
+
+    // Neighbors are only settled if they orient themselves.
+    let mut diff = MapDiff::default();
+    map.orientations[0] = turned;
+    diff.extend(map.fill_cells([IVec3::Z], MapCell::new(Some(WALL), default())));
+    assert!(map.orient_after(&diff, &properties).is_empty());
+    assert_eq!(map.orientation(UVec3::ZERO), turned);
+}
+
+#[test]
+fn orientation_flags_come_from_tile_files() {
+    let file = TileFile::parse(
+        r#"(version: 2, tile: Cube(texture: "wall.png", rotatable: Some(false), auto_orient: Some(TowardEmpty)))"#,
+    )
+    .unwrap();
+    let obj = Obj {
+        properties: file.properties(),
+        ..default()
+    };
+
+    assert_eq!(TileProperties::resolve(Some(&obj), None), TileProperties {
+        rotatable: false,
+        auto_orient: AutoOrient::TowardEmpty,
+        ..default()
+    });
+}
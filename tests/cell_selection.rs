@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use mnemonic::{
+    editor::tools::select::{CellSelection, SelectOp},
+    map::{Map, MapCell},
+};
+use nonmax::NonMaxU8;
+
+#[test]
+fn boxes_add_and_take_out_cells() {
+    let mut selection = CellSelection::new((IVec3::ZERO, IVec3::new(4, 1, 4)));
+    assert_eq!(selection.region(), Some((IVec3::ZERO, IVec3::new(4, 1, 4))));
+
+    selection.apply((IVec3::new(1, 0, 1), IVec3::new(3, 1, 3)), SelectOp::Subtract);
+    selection.apply((IVec3::new(2, 0, 2), IVec3::new(6, 1, 3)), SelectOp::Add);
+    assert_eq!(selection.region(), None);
+    assert_eq!(selection.bounds(), Some((IVec3::ZERO, IVec3::new(6, 1, 4))));
+
+    // Later boxes win where they overlap.
+    assert!(selection.contains(IVec3::ZERO));
+    assert!(!selection.contains(IVec3::new(1, 0, 1)));
+    assert!(selection.contains(IVec3::new(2, 0, 2)));
+    assert!(selection.contains(IVec3::new(5, 0, 2)));
+    assert!(!selection.contains(IVec3::new(5, 0, 3)));
+    assert_eq!(selection.iter().count(), 16 - 4 + 3);
+
+    // Replacing forgets every box before.
+    selection.apply((IVec3::new(5, 0, 0), IVec3::new(6, 1, 1)), SelectOp::Replace);
+    assert_eq!(selection.iter().collect::<Vec<_>>(), [IVec3::new(5, 0, 0)]);
+
+    // Taking out every cell leaves nothing.
+    selection.apply((IVec3::ZERO, IVec3::splat(8)), SelectOp::Subtract);
+    assert!(selection.is_empty());
+    selection.apply((IVec3::ZERO, IVec3::ONE), SelectOp::Subtract);
+    assert!(selection.is_empty());
+}
+
+#[test]
+fn only_selected_cells_are_extracted() {
+    let mut map = Map::empty(UVec3::new(3, 1, 1));
+    let cell = MapCell::new(Some(NonMaxU8::ZERO), default());
+    map.tile_set = vec!["stone".into()];
+    map.fill_region(IVec3::ZERO, IVec3::new(3, 1, 1), cell);
+
+    let mut selection = CellSelection::new((IVec3::ZERO, IVec3::new(3, 1, 1)));
+    selection.apply((IVec3::new(1, 0, 0), IVec3::new(2, 1, 1)), SelectOp::Subtract);
+
+    let fragment = selection.extract(&map).unwrap();
+    assert_eq!(fragment.size, UVec3::new(3, 1, 1));
+    assert_eq!(fragment.tiles, [Some(NonMaxU8::ZERO), None, Some(NonMaxU8::ZERO)]);
+}
+
+#[test]
+fn like_tiles_are_selected_together() {
+    let mut map = Map::empty(UVec3::new(4, 2, 3));
+    let stone = MapCell::new(Some(NonMaxU8::ZERO), default());
+    map.fill_region(IVec3::ZERO, IVec3::new(2, 1, 3), stone);
+    map.fill_region(IVec3::new(3, 0, 0), IVec3::new(4, 2, 1), stone);
+
+    // Flooding keeps to the layer and to connected cells.
+    let mut selection = CellSelection::default();
+    selection.apply_cells(map.flood_layer_cells(UVec3::new(1, 0, 1)), SelectOp::Replace);
+    assert_eq!(selection.iter().count(), 6);
+    assert!(!selection.contains(IVec3::new(3, 0, 0)));
+    // Rows of cells are kept as one box each.
+    assert_eq!(selection.boxes().len(), 3);
+
+    selection.apply_cells(map.tile_cells(Some(NonMaxU8::ZERO)), SelectOp::Replace);
+    assert_eq!(selection.iter().count(), 8);
+    assert!(selection.contains(IVec3::new(3, 1, 0)));
+
+    // Empty cells are alike too, and may be taken out all the same.
+    selection.apply_cells(map.tile_cells(None), SelectOp::Add);
+    assert_eq!(selection.iter().count(), 24);
+    selection.apply_cells(map.flood_layer_cells(UVec3::new(3, 1, 0)), SelectOp::Subtract);
+    assert_eq!(selection.iter().count(), 23);
+
+    // Deleting the selection empties every selected cell in one step.
+    let diff = map.fill_cells(selection.iter(), MapCell::EMPTY);
+    assert_eq!(map.tile_cells(Some(NonMaxU8::ZERO)).collect::<Vec<_>>(), [IVec3::new(3, 1, 0)]);
+    diff.revert(&mut map);
+    assert_eq!(map.tile_cells(Some(NonMaxU8::ZERO)).count(), 8);
+}
@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::{prelude::*, scene::ScenePlugin, time::TimeUpdateStrategy};
+use mnemonic::{
+    character::{CharacterConfig, CharacterController, CharacterInput, CharacterPlugin},
+    content::properties::TilePropertyTable,
+    map::{
+        orientation::TileOrientation,
+        tile::{cube_obj, ramp_obj, slab_obj},
+        Map, MapCell,
+    },
+    obj::def::Obj,
+    physics::GameLayer,
+};
+use nonmax::NonMaxU8;
+
+const RADIUS: f32 = 0.25;
+const LENGTH: f32 = 0.9;
+/// Fixed steps are 1/64 of a second by default, and each update runs exactly one.
+const STEP: f32 = 1.0 / 64.0;
+
+#[derive(Copy, Clone)]
+enum Ledge {
+    Ramp,
+    Slab,
+    Cube,
+}
+
+/// A floor along +X with a ledge beginning at `x = 3` in the layer above it, all the way to the
+/// end, and a character standing at its start.
+fn app(ledge: Ledge) -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        AssetPlugin::default(),
+        ScenePlugin,
+        PhysicsPlugins::default(),
+        CharacterPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<Obj>()
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(STEP)));
+
+    let world = app.world_mut();
+    let mut objs = world.resource_mut::<Assets<Obj>>();
+    let handles = vec![
+        objs.add(cube_obj(default(), "cube".into())),
+        objs.add(ramp_obj(default(), "ramp".into())),
+        objs.add(slab_obj(default(), "slab".into(), 0.5)),
+    ];
+
+    let mut map = Map::empty(UVec3::new(12, 3, 1));
+    map.tile_set = vec!["cube".into(), "ramp".into(), "slab".into()];
+    map.tile_handles = handles;
+
+    let tile = |index| Some(NonMaxU8::new(index).unwrap());
+    map.fill_region(IVec3::ZERO, IVec3::new(12, 1, 1), MapCell::new(tile(0), default()));
+    match ledge {
+        // Ramps rise towards their -Z side, so three quarter turns rise towards +X.
+        Ledge::Ramp => {
+            map.set_cell(UVec3::new(3, 1, 0), MapCell::new(tile(1), TileOrientation::new(3, false)));
+            map.fill_region(IVec3::new(4, 1, 0), IVec3::new(12, 2, 1), MapCell::new(tile(0), default()));
+        }
+        Ledge::Slab => {
+            map.fill_region(IVec3::new(3, 1, 0), IVec3::new(12, 2, 1), MapCell::new(tile(2), default()));
+        }
+        Ledge::Cube => {
+            map.fill_region(IVec3::new(3, 1, 0), IVec3::new(12, 2, 1), MapCell::new(tile(0), default()));
+        }
+    }
+
+    let colliders = map.build_colliders(world.resource::<Assets<Obj>>(), &TilePropertyTable::default());
+    world
+        .spawn((TransformBundle::default(), RigidBody::Static))
+        .with_children(|parent| {
+            for (collider, friction) in colliders {
+                parent.spawn((TransformBundle::default(), collider, friction, GameLayer::map()));
+            }
+        });
+
+    let character = world
+        .spawn((
+            TransformBundle::from_transform(Transform::from_xyz(0.0, feet_to_center(0.5) + 0.05, 0.0)),
+            RigidBody::Kinematic,
+            Collider::capsule(RADIUS, LENGTH),
+            GameLayer::player(),
+            CharacterController::default(),
+            CharacterInput::default(),
+        ))
+        .id();
+
+    // Let the map's colliders settle into the spatial query pipeline, and the character onto the
+    // floor.
+    for _ in 0..8 {
+        app.update();
+    }
+
+    (app, character)
+}
+
+fn feet_to_center(feet: f32) -> f32 {
+    feet + LENGTH / 2.0 + RADIUS
+}
+
+/// Walks towards +X for `steps` fixed steps, jumping on the given ones.
+fn walk(app: &mut App, character: Entity, steps: usize, jumps: &[usize]) {
+    for step in 0..steps {
+        let mut input = app.world_mut().get_mut::<CharacterInput>(character).unwrap();
+        input.walk = Vec2::X;
+        input.jump = jumps.contains(&step);
+        app.update();
+    }
+
+    app.world_mut().get_mut::<CharacterInput>(character).unwrap().walk = Vec2::ZERO;
+}
+
+fn state(app: &App, character: Entity) -> (Vec3, CharacterController) {
+    (
+        app.world().get::<Transform>(character).unwrap().translation,
+        *app.world().get::<CharacterController>(character).unwrap(),
+    )
+}
+
+fn feet(app: &App, character: Entity) -> f32 {
+    state(app, character).0.y - LENGTH / 2.0 - RADIUS
+}
+
+#[test]
+fn walks_on_flat_floors() {
+    let (mut app, character) = app(Ledge::Cube);
+    let (start, controller) = state(&app, character);
+    assert!(controller.grounded);
+    assert!((feet(&app, character) - 0.5).abs() < 0.02);
+
+    walk(&mut app, character, 32, &[]);
+    let (end, controller) = state(&app, character);
+    let speed = CharacterConfig::default().walk_speed;
+    assert!(controller.grounded);
+    assert!(
+        ((end.x - start.x) - speed * 32.0 * STEP).abs() < 1e-3,
+        "walked {}",
+        end.x - start.x
+    );
+    assert!((end.y - start.y).abs() < 1e-4);
+}
+
+#[test]
+fn walks_up_ramps() {
+    let (mut app, character) = app(Ledge::Ramp);
+    walk(&mut app, character, 96, &[]);
+
+    let (end, controller) = state(&app, character);
+    assert!(end.x > 5.0, "stuck at {end}");
+    assert!(controller.grounded);
+    assert!(
+        (feet(&app, character) - 1.5).abs() < 0.02,
+        "feet at {}",
+        feet(&app, character)
+    );
+}
+
+#[test]
+fn steps_up_onto_slabs() {
+    let (mut app, character) = app(Ledge::Slab);
+    let mut lowest_eye = 0f32;
+    for _ in 0..64 {
+        walk(&mut app, character, 1, &[]);
+        lowest_eye = lowest_eye.min(state(&app, character).1.eye_offset);
+    }
+
+    let (end, controller) = state(&app, character);
+    assert!(end.x > 5.0, "stuck at {end}");
+    assert!(controller.grounded);
+    assert!(
+        (feet(&app, character) - 1.0).abs() < 0.02,
+        "feet at {}",
+        feet(&app, character)
+    );
+
+    // The eyes lagged behind the step, then caught up.
+    assert!(lowest_eye < -0.25, "eyes lagged {lowest_eye}");
+    assert!(controller.eye_offset.abs() < 0.05);
+}
+
+#[test]
+fn jumps_onto_full_tiles() {
+    let (mut app, character) = app(Ledge::Cube);
+    app.world_mut().resource_mut::<CharacterConfig>().jump_speed = 5.0;
+
+    // Walls of full tiles are too tall to step onto, so they stop the character.
+    walk(&mut app, character, 64, &[]);
+    let (end, _) = state(&app, character);
+    assert!((end.x - (2.5 - RADIUS)).abs() < 0.02, "stopped at {end}");
+    assert!((feet(&app, character) - 0.5).abs() < 0.02);
+
+    walk(&mut app, character, 96, &[0]);
+    let (end, controller) = state(&app, character);
+    assert!(end.x > 3.0, "stuck at {end}");
+    assert!(controller.grounded);
+    assert!(
+        (feet(&app, character) - 1.5).abs() < 0.02,
+        "feet at {}",
+        feet(&app, character)
+    );
+}
+
+#[test]
+fn moves_the_same_every_time() {
+    let run = || {
+        let (mut app, character) = app(Ledge::Ramp);
+        walk(&mut app, character, 80, &[10, 40]);
+        state(&app, character).0
+    };
+
+    assert_eq!(run(), run());
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use mnemonic::{
+    cli::{asset_path, parse_size, Args, CliError, Task},
+    editor::session::SessionRequest,
+    GameState,
+};
+
+fn parse(args: &[&str]) -> Result<Args, CliError> {
+    Args::parse(args.iter().map(|&arg| arg.into()))
+}
+
+fn task(args: &[&str]) -> Result<Task, CliError> {
+    parse(args)?.task(Path::new("assets"))
+}
+
+#[test]
+fn sizes_need_three_sides() {
+    assert_eq!(parse_size("64x64x8"), Some(UVec3::new(64, 64, 8)));
+    assert_eq!(parse_size("4X2x1"), Some(UVec3::new(4, 2, 1)));
+    assert_eq!(parse_size("64x64"), None);
+    assert_eq!(parse_size("64x64x8x1"), None);
+    assert_eq!(parse_size("64x0x8"), None);
+    assert_eq!(parse_size("big"), None);
+
+    assert!(matches!(parse(&["--new", "64x64"]), Err(CliError::InvalidSize(..))));
+    assert!(matches!(parse(&["--new"]), Err(CliError::MissingValue("--new"))));
+    assert!(matches!(parse(&["--frobnicate"]), Err(CliError::Unknown(..))));
+}
+
+#[test]
+fn arguments_pick_a_task() {
+    assert!(matches!(task(&[]), Ok(Task::Edit(None))));
+    assert!(matches!(
+        task(&["--new", "8x2x8"]),
+        Ok(Task::Edit(Some(SessionRequest::New(size)))) if size == UVec3::new(8, 2, 8)
+    ));
+
+    assert!(matches!(task(&["a.mnmap", "b.mnmap", "--validate"]), Ok(Task::Validate(maps)) if maps.len() == 2));
+    assert!(matches!(task(&["--validate"]), Err(CliError::NoMaps(..))));
+    assert!(matches!(task(&["a.mnmap", "--export-gltf", "a.glb"]), Ok(Task::ExportGltf { .. })));
+    assert!(matches!(task(&["a.mnmap", "b.mnmap", "--export-gltf", "a.glb"]), Err(CliError::OneMap(..))));
+    assert!(matches!(task(&["a.mnmap", "--validate", "--new", "1x1x1"]), Err(CliError::Conflict(..))));
+}
+
+#[test]
+fn opened_maps_skip_the_menu() {
+    let args = parse(&["--demo", "--linear-tiles"]).unwrap();
+    let config = args.config(None);
+    assert_eq!(config.after_loading, GameState::Menu);
+    assert!(config.editor.unwrap().demo);
+
+    let config = args.config(Some(SessionRequest::New(UVec3::ONE)));
+    assert_eq!(config.after_loading, GameState::Editor);
+    assert!(matches!(config.open, Some(SessionRequest::New(..))));
+}
+
+#[test]
+fn maps_on_disk_are_named_by_asset_path() {
+    let root = std::env::temp_dir().join(format!("mnemonic-cli-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("maps")).unwrap();
+    std::fs::write(root.join("maps/hall.mnmap"), "").unwrap();
+
+    assert_eq!(asset_path(Path::new("maps/hall.mnmap"), &root).unwrap(), "maps/hall.mnmap");
+    assert_eq!(asset_path(&root.join("maps/hall.mnmap"), &root).unwrap(), "maps/hall.mnmap");
+    assert!(matches!(
+        asset_path(Path::new("nowhere.mnmap"), &root),
+        Err(CliError::OutsideAssets { .. })
+    ));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
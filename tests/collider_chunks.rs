@@ -0,0 +1,135 @@
+use bevy::{prelude::*, utils::HashSet};
+use mnemonic::{
+    content::properties::TilePropertyTable,
+    map::{tile::cube_obj, Map, MapCell},
+    obj::def::Obj,
+    playtest::{
+        spawn_chunk_colliders, update_playtest_colliders, ColliderChunks, ColliderCost, ColliderStats, PlaytestCollider,
+        PlaytestSettings,
+    },
+};
+use nonmax::NonMaxU8;
+
+const CHUNK: u32 = 16;
+
+fn floor(objs: &mut Assets<Obj>) -> Map {
+    let mut map = Map::empty(UVec3::new(40, 2, 40));
+    map.tile_set.push("stone".into());
+    map.tile_handles.push(objs.add(cube_obj(default(), "stone".into())));
+    for x in 0..40 {
+        for z in 0..40 {
+            map.set_cell(UVec3::new(x, 0, z), MapCell::new(Some(NonMaxU8::ZERO), default()));
+        }
+    }
+    map
+}
+
+#[test]
+fn signatures_change_only_where_edited() {
+    let mut objs = Assets::<Obj>::default();
+    let mut map = floor(&mut objs);
+
+    let before = map.chunk_signatures(CHUNK);
+    assert_eq!(before.len(), 9, "empty chunks have no signature");
+
+    map.set_cell(UVec3::new(20, 1, 3), MapCell::new(Some(NonMaxU8::ZERO), default()));
+    let after = map.chunk_signatures(CHUNK);
+    let changed = after
+        .iter()
+        .filter(|&(chunk, signature)| before.get(chunk) != Some(signature))
+        .map(|(&chunk, _)| chunk)
+        .collect::<Vec<_>>();
+    assert_eq!(changed, [UVec3::new(1, 0, 0)]);
+
+    // Every chunk's collider only holds its own cells.
+    let properties = TilePropertyTable::default();
+    for &chunk in after.keys() {
+        let min = chunk * CHUNK;
+        assert!(!map.build_colliders_in(&objs, &properties, min, min + CHUNK).is_empty());
+    }
+    assert!(map
+        .build_colliders_in(&objs, &properties, UVec3::new(0, 1, 16), UVec3::new(40, 2, 40))
+        .is_empty());
+}
+
+#[test]
+fn edits_rebuild_only_their_chunk() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .init_asset::<Map>()
+        .init_asset::<Obj>()
+        .init_resource::<TilePropertyTable>()
+        .init_resource::<ColliderStats>()
+        .insert_resource(PlaytestSettings {
+            collider_chunk: CHUNK,
+            ..default()
+        })
+        .add_systems(Update, update_playtest_colliders);
+
+    let world = app.world_mut();
+    let map = floor(&mut world.resource_mut::<Assets<Obj>>());
+    let chunks = map.chunk_signatures(CHUNK);
+
+    world.resource_scope(|world, objs: Mut<Assets<Obj>>| {
+        let properties = TilePropertyTable::default();
+        let mut cost = ColliderCost::default();
+        let mut commands = world.commands();
+        commands.spawn_empty().with_children(|parent| {
+            for &chunk in chunks.keys() {
+                cost += spawn_chunk_colliders(parent, &map, &objs, &properties, chunk, CHUNK);
+            }
+        });
+        world.flush();
+
+        // Each cube is a shape of its own, none of them triangles.
+        assert_eq!(cost, ColliderCost { shapes: 40 * 40, triangles: 0 });
+        world.resource_mut::<ColliderStats>().cost = cost;
+    });
+
+    let handle = world.resource_mut::<Assets<Map>>().add(map);
+    let e = world.query_filtered::<Entity, Without<Parent>>().single(world);
+    world.entity_mut(e).insert((handle, ColliderChunks(chunks)));
+
+    // Asset events are only sent after `Update`, so each takes a frame to be seen; let the map's
+    // addition pass first.
+    app.update();
+    app.update();
+
+    let colliders = |app: &mut App| {
+        app.world_mut()
+            .query::<(Entity, &PlaytestCollider)>()
+            .iter(app.world())
+            .map(|(e, &PlaytestCollider(chunk))| (e, chunk))
+            .collect::<HashSet<_>>()
+    };
+
+    let before = colliders(&mut app);
+    assert_eq!(before.len(), 9);
+
+    let world = app.world_mut();
+    let handle = world.query::<&Handle<Map>>().single(world).clone();
+    world
+        .resource_mut::<Assets<Map>>()
+        .get_mut(&handle)
+        .unwrap()
+        .set_cell(UVec3::new(39, 1, 39), MapCell::new(Some(NonMaxU8::ZERO), default()));
+    app.update();
+    app.update();
+
+    let stats = app.world().resource::<ColliderStats>();
+    assert_eq!(stats.rebuilt, 1);
+    assert_eq!(stats.cost.shapes, 40 * 40 + 1);
+    assert_eq!(stats.last_build, stats.elapsed);
+
+    let after = colliders(&mut app);
+    let edited = UVec3::new(2, 0, 2);
+    assert_eq!(after.len(), 9);
+    assert_eq!(
+        before.iter().filter(|(_, chunk)| *chunk != edited).collect::<HashSet<_>>(),
+        after.iter().filter(|(_, chunk)| *chunk != edited).collect::<HashSet<_>>(),
+        "untouched chunks keep their colliders",
+    );
+    assert!(!before
+        .iter()
+        .any(|&(e, chunk)| chunk == edited && after.contains(&(e, chunk))));
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::{prelude::*, scene::ScenePlugin, time::TimeUpdateStrategy};
+use mnemonic::physics::GameLayer;
+
+struct Bodies {
+    map: Entity,
+    prop: Entity,
+    trigger: Entity,
+    player: Entity,
+}
+
+/// A floor with a prop on it, a trigger volume over the floor, and a player standing on the prop.
+fn app() -> (App, Bodies) {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), ScenePlugin, PhysicsPlugins::default()))
+        .init_asset::<Mesh>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(1.0 / 60.0)));
+
+    let mut spawn = |translation: Vec3, bundle: (RigidBody, Collider, CollisionLayers)| {
+        app.world_mut()
+            .spawn((
+                TransformBundle::from_transform(Transform::from_translation(translation)),
+                bundle,
+            ))
+            .id()
+    };
+
+    let bodies = Bodies {
+        map: spawn(
+            Vec3::ZERO,
+            (RigidBody::Static, Collider::cuboid(8.0, 1.0, 8.0), GameLayer::map()),
+        ),
+        prop: spawn(
+            Vec3::new(2.0, 1.0, 0.0),
+            (RigidBody::Static, Collider::cuboid(1.0, 1.0, 1.0), GameLayer::prop()),
+        ),
+        trigger: spawn(
+            Vec3::new(-2.0, 2.0, 0.0),
+            (RigidBody::Static, Collider::cuboid(2.0, 2.0, 2.0), GameLayer::trigger()),
+        ),
+        player: spawn(
+            Vec3::new(2.0, 2.5, 0.0),
+            (RigidBody::Kinematic, Collider::capsule(0.25, 0.5), GameLayer::player()),
+        ),
+    };
+
+    app.world_mut().entity_mut(bodies.trigger).insert(Sensor);
+    for _ in 0..3 {
+        app.update();
+    }
+
+    (app, bodies)
+}
+
+fn cast_down(app: &App, x: f32, filter: SpatialQueryFilter) -> Option<Entity> {
+    app.world()
+        .resource::<SpatialQueryPipeline>()
+        .cast_ray(Vec3::new(x, 10.0, 0.0), Dir3::NEG_Y, 100.0, true, filter)
+        .map(|hit| hit.entity)
+}
+
+#[test]
+fn players_only_run_into_maps_and_props() {
+    let (app, bodies) = app();
+
+    // Unfiltered queries see other players, but players look past them and through triggers.
+    assert_eq!(cast_down(&app, 2.0, default()), Some(bodies.player));
+
+    assert_eq!(cast_down(&app, -2.0, default()), Some(bodies.trigger));
+    assert_eq!(cast_down(&app, -2.0, GameLayer::player_filter()), Some(bodies.map));
+    assert_eq!(cast_down(&app, 2.0, GameLayer::player_filter()), Some(bodies.prop));
+
+    // A player grounds itself on what's below it, not on itself.
+    let grounding = GameLayer::player_filter().with_excluded_entities([bodies.player]);
+    let hit = app
+        .world()
+        .resource::<SpatialQueryPipeline>()
+        .cast_shape(
+            &Collider::capsule(0.25, 0.5),
+            Vec3::new(-2.0, 2.5, 0.0),
+            Quat::IDENTITY,
+            Dir3::NEG_Y,
+            10.0,
+            true,
+            grounding,
+        )
+        .unwrap();
+    assert_eq!(hit.entity, bodies.map);
+
+    let player = GameLayer::player();
+    assert!(player.interacts_with(GameLayer::map()) && player.interacts_with(GameLayer::prop()));
+    assert!(!player.interacts_with(player));
+
+    // Triggers sense players, but nothing else does.
+    let trigger = GameLayer::trigger();
+    assert!(player.interacts_with(trigger));
+    assert!(!trigger.interacts_with(GameLayer::map()) && !trigger.interacts_with(GameLayer::prop()));
+}
+
+#[test]
+fn editor_picks_only_see_maps_and_props() {
+    let (app, bodies) = app();
+    assert_eq!(cast_down(&app, -2.0, GameLayer::pick_filter()), Some(bodies.map));
+    assert_eq!(cast_down(&app, 2.0, GameLayer::pick_filter()), Some(bodies.prop));
+    assert_eq!(cast_down(&app, 10.0, GameLayer::pick_filter()), None);
+}
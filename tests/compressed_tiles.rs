@@ -0,0 +1,115 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        settings::WgpuLimits,
+    },
+};
+use mnemonic::content::{
+    atlas::{texture_bytes, TileAtlasSettings},
+    build_tile_pages, TileAtlasError, TileMaps, TileTexture,
+};
+
+/// An 8x8 BC1 texture with its 4x4 mip level, as loaded from a KTX2 file.
+fn bc1(fill: u8, size: u32) -> Image {
+    let mut image = Image::default();
+    image.texture_descriptor.size = Extent3d {
+        width: size,
+        height: size,
+        depth_or_array_layers: 1,
+    };
+    image.texture_descriptor.format = TextureFormat::Bc1RgbaUnormSrgb;
+    image.texture_descriptor.mip_level_count = 2;
+    image.data = vec![fill; ((size / 4).pow(2) + (size / 8).max(1).pow(2)) as usize * 8];
+    image
+}
+
+fn rgba(size: u32) -> Image {
+    Image::new_fill(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[255; 4],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::all(),
+    )
+}
+
+fn tile_texture(images: &mut Assets<Image>, sources: impl IntoIterator<Item = Image>) -> TileTexture {
+    let mut texture = TileTexture {
+        pages: Vec::new(),
+        page_of: default(),
+        maps: default(),
+        sources: default(),
+        released: default(),
+        aliases: default(),
+    };
+
+    for image in sources {
+        let handle = images.add(image.clone());
+        texture.maps.insert(handle.id(), TileMaps::default());
+        texture.sources.insert(handle.id(), (handle, image));
+    }
+
+    texture
+}
+
+#[test]
+fn compressed_tiles_stack_as_they_are() {
+    let mut images = Assets::<Image>::default();
+    let texture = tile_texture(&mut images, [bc1(1, 8), bc1(2, 8), bc1(3, 8)]);
+
+    let pages = build_tile_pages(&texture, default(), &WgpuLimits::default()).unwrap();
+    assert_eq!(pages.len(), 1);
+
+    let array = &pages[0].atlas;
+    assert_eq!(array.texture_descriptor.format, TextureFormat::Bc1RgbaUnormSrgb);
+    assert_eq!(array.texture_descriptor.size.depth_or_array_layers, 3);
+    assert_eq!(array.texture_descriptor.mip_level_count, 2);
+    assert_eq!(array.data.len(), 3 * (4 + 1) * 8);
+    assert_eq!(pages[0].layers.as_ref().map(|layers| layers.len()), Some(3));
+
+    // Each layer keeps its own blocks, in the order layers were given out.
+    let layers = pages[0].layers.as_ref().unwrap();
+    for (id, &layer) in layers {
+        let fill = texture.sources[id].1.data[0];
+        assert!(array.data[layer as usize * 40..][..40].iter().all(|&byte| byte == fill));
+    }
+
+    // The same tiles uncompressed take several times the video memory.
+    let uncompressed = tile_texture(&mut images, [rgba(8), rgba(8), rgba(8)]);
+    let uncompressed = build_tile_pages(&uncompressed, default(), &WgpuLimits::default()).unwrap();
+    assert!(texture_bytes(&uncompressed[0].atlas.texture_descriptor) >= 4 * texture_bytes(&array.texture_descriptor));
+    assert_eq!(pages[0].bytes(), texture_bytes(&array.texture_descriptor));
+}
+
+#[test]
+fn mixed_compression_lists_the_minority() {
+    let mut images = Assets::<Image>::default();
+    let texture = tile_texture(&mut images, [bc1(1, 8), bc1(2, 8), rgba(8)]);
+
+    match build_tile_pages(&texture, TileAtlasSettings::default(), &WgpuLimits::default()) {
+        Err(TileAtlasError::MixedCompression { offenders }) => assert_eq!(offenders.len(), 1),
+        Err(e) => panic!("expected mixed compression, got {e}"),
+        Ok(..) => panic!("mixed compression must fail"),
+    }
+}
+
+#[test]
+fn compressed_tiles_must_match() {
+    let mut images = Assets::<Image>::default();
+    let texture = tile_texture(&mut images, [bc1(1, 8), bc1(2, 8), bc1(3, 16)]);
+
+    match build_tile_pages(&texture, TileAtlasSettings::default(), &WgpuLimits::default()) {
+        Err(TileAtlasError::CompressedMismatch { size, offenders, .. }) => {
+            assert_eq!(size, UVec2::splat(8));
+            assert_eq!(offenders.len(), 1);
+        }
+        Err(e) => panic!("expected mismatched textures, got {e}"),
+        Ok(..) => panic!("mismatched compressed textures must fail"),
+    }
+}
@@ -0,0 +1,44 @@
+use std::{backtrace::Backtrace, fs, io::Write};
+
+use mnemonic::crash::{crash_report, recent_logs, LogFileConfig, LogSink, RotatingLog, RECENT_LOG_LINES};
+
+#[test]
+fn full_logs_move_to_older_files() {
+    let dir = std::env::temp_dir().join(format!("mnemonic-crash-log-{}", std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+
+    let path = dir.join("mnemonic.log");
+    let mut log = RotatingLog::open(LogFileConfig {
+        path: path.clone(),
+        max_size: 8,
+        keep: 2,
+    })
+    .unwrap();
+
+    log.write(b"first\n").unwrap();
+    log.write(b"second\n").unwrap();
+    log.write(b"third\n").unwrap();
+
+    // Only `keep` files are kept; the first went past the end.
+    assert_eq!(fs::read_to_string(&path).unwrap(), "third\n");
+    assert_eq!(fs::read_to_string(dir.join("mnemonic.1.log")).unwrap(), "second\n");
+    assert!(!dir.join("mnemonic.2.log").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn recent_lines_are_kept_for_reports() {
+    let mut sink = LogSink;
+    for i in 0..RECENT_LOG_LINES + 2 {
+        writeln!(sink, "line {i}").unwrap();
+    }
+
+    let logs = recent_logs();
+    assert_eq!(logs.len(), RECENT_LOG_LINES);
+    assert_eq!(logs.first().map(String::as_str), Some("line 2"));
+
+    let report = crash_report("it broke", &Backtrace::disabled(), &logs);
+    assert!(report.starts_with("it broke"));
+    assert!(report.ends_with(&format!("line {}\n", RECENT_LOG_LINES + 1)));
+}
@@ -0,0 +1,72 @@
+use bevy::{
+    prelude::*,
+    window::{PresentMode, WindowMode},
+};
+use mnemonic::{
+    editor::{
+        display::{
+            next_present_mode, toggled_fullscreen, DisplaySettings, WindowState, PRESENT_MODES, WINDOW_SIZE_LIMITS,
+        },
+        EditorSettings,
+    },
+    AppConfig,
+};
+
+#[test]
+fn present_modes_cycle_around() {
+    let mut mode = PRESENT_MODES[0];
+    for _ in 0..PRESENT_MODES.len() {
+        mode = next_present_mode(mode);
+    }
+    assert_eq!(mode, PRESENT_MODES[0]);
+
+    // Modes outside the cycle rejoin it at its start.
+    assert_eq!(next_present_mode(PresentMode::Mailbox), PRESENT_MODES[0]);
+}
+
+#[test]
+fn saved_settings_apply_at_startup() {
+    let settings = EditorSettings {
+        present_mode: PresentMode::Fifo,
+        frame_cap: Some(60.0),
+        ..Default::default()
+    };
+
+    let display = DisplaySettings::from(&settings);
+    let read = ron::from_str::<DisplaySettings>(&ron::to_string(&display).unwrap()).unwrap();
+    assert_eq!(read, display);
+
+    let mut config = AppConfig::default();
+    read.apply(&mut config);
+    assert_eq!(config.present_mode, PresentMode::Fifo);
+    assert_eq!(config.frame_cap, Some(60.0));
+
+    // Files from before the frame cap leave it off.
+    let read = ron::from_str::<DisplaySettings>("(present_mode: AutoVsync)").unwrap();
+    assert_eq!(read.frame_cap, None);
+}
+
+#[test]
+fn restored_windows_stay_sane() {
+    let window = WindowState {
+        size: Vec2::new(100000.0, 10.0),
+        position: Some(IVec2::new(-5000, 40)),
+        maximized: true,
+        fullscreen: false,
+    };
+
+    let mut config = AppConfig::default();
+    DisplaySettings {
+        window: Some(window),
+        ..DisplaySettings::from(&EditorSettings::default())
+    }
+    .apply(&mut config);
+
+    let (min, max) = WINDOW_SIZE_LIMITS;
+    assert_eq!(config.resolution, Some(Vec2::new(max.x, min.y)));
+    assert_eq!(config.position, Some(IVec2::new(-5000, 40)));
+    assert!(config.maximized && !config.fullscreen);
+
+    assert_eq!(toggled_fullscreen(WindowMode::Windowed), WindowMode::BorderlessFullscreen);
+    assert_eq!(toggled_fullscreen(WindowMode::BorderlessFullscreen), WindowMode::Windowed);
+}
@@ -0,0 +1,121 @@
+use bevy::{
+    input::InputPlugin,
+    prelude::*,
+    state::{app::StatesPlugin, state::FreelyMutableState},
+};
+use mnemonic::{
+    editor::prompt::{ActivePrompt, Prompt, PromptPlugin},
+    EditorState, EditorUi, GameState, GameStatePlugin,
+};
+
+/// Times a stand-in for tool input ran.
+#[derive(Resource, Copy, Clone, Default)]
+struct ToolRuns(usize);
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, InputPlugin, StatesPlugin, GameStatePlugin, PromptPlugin))
+        .init_resource::<ToolRuns>()
+        .add_systems(
+            Update,
+            (|mut runs: ResMut<ToolRuns>| runs.0 += 1).run_if(in_state(EditorState::Editing)),
+        );
+
+    app.update();
+    app
+}
+
+fn set<S: FreelyMutableState>(app: &mut App, state: S) {
+    app.world_mut().resource_mut::<NextState<S>>().set(state);
+    app.update();
+}
+
+fn editor_state(app: &App) -> Option<EditorState> {
+    app.world().get_resource::<State<EditorState>>().map(|state| *state.get())
+}
+
+fn tool_runs(app: &App) -> usize {
+    app.world().resource::<ToolRuns>().0
+}
+
+#[test]
+fn editor_states_only_live_in_the_editor() {
+    let mut app = app();
+    assert_eq!(editor_state(&app), None);
+
+    set(&mut app, GameState::Editor);
+    assert_eq!(editor_state(&app), Some(EditorState::Editing));
+    assert!(app.world().contains_resource::<State<EditorUi>>());
+
+    set(&mut app, EditorState::Playtest);
+    assert_eq!(editor_state(&app), Some(EditorState::Playtest));
+    assert!(!app.world().contains_resource::<State<EditorUi>>());
+
+    // Leaving the editor from a playtest takes the playtest along.
+    set(&mut app, GameState::Menu);
+    assert_eq!(editor_state(&app), None);
+    assert!(!app.world().contains_resource::<State<EditorUi>>());
+
+    // Coming back starts out editing again.
+    set(&mut app, GameState::Editor);
+    assert_eq!(editor_state(&app), Some(EditorState::Editing));
+}
+
+#[test]
+fn prompts_hold_tool_input_while_open() {
+    let mut app = app();
+    set(&mut app, GameState::Editor);
+    let runs = tool_runs(&app);
+    assert!(runs > 0);
+
+    app.world_mut().resource_mut::<ActivePrompt>().0 = Some(Prompt::text("test", "Name", ""));
+    app.update();
+    app.update();
+    assert_eq!(editor_state(&app), Some(EditorState::Dialog));
+    assert!(app.world().contains_resource::<State<EditorUi>>());
+    assert_eq!(tool_runs(&app), runs);
+
+    app.world_mut().resource_mut::<ActivePrompt>().0 = None;
+    app.update();
+    assert_eq!(editor_state(&app), Some(EditorState::Editing));
+    assert_eq!(tool_runs(&app), runs + 1);
+}
+
+#[test]
+fn playtests_hold_tool_input() {
+    let mut app = app();
+    set(&mut app, GameState::Editor);
+    set(&mut app, EditorState::Playtest);
+
+    let runs = tool_runs(&app);
+    app.update();
+    assert_eq!(tool_runs(&app), runs);
+
+    // Prompts left open don't pull a playtest into a dialog.
+    app.world_mut().resource_mut::<ActivePrompt>().0 = Some(Prompt::text("test", "Name", ""));
+    app.update();
+    assert_eq!(editor_state(&app), Some(EditorState::Playtest));
+}
+
+#[test]
+fn scoped_entities_leave_with_their_state() {
+    let mut app = app();
+    let loading = app.world_mut().spawn(StateScoped(GameState::Loading)).id();
+
+    set(&mut app, GameState::Editor);
+    assert!(app.world().get_entity(loading).is_none());
+
+    let editor = app.world_mut().spawn(StateScoped(GameState::Editor)).id();
+    set(&mut app, EditorState::Playtest);
+    let player = app.world_mut().spawn(StateScoped(EditorState::Playtest)).id();
+
+    set(&mut app, EditorState::Editing);
+    assert!(app.world().get_entity(player).is_none());
+    assert!(app.world().get_entity(editor).is_some());
+
+    set(&mut app, EditorState::Playtest);
+    let player = app.world_mut().spawn(StateScoped(EditorState::Playtest)).id();
+    set(&mut app, GameState::Menu);
+    assert!(app.world().get_entity(player).is_none());
+    assert!(app.world().get_entity(editor).is_none());
+}
@@ -0,0 +1,77 @@
+use bevy::{input::mouse::MouseMotion, prelude::*, window::PrimaryWindow};
+use mnemonic::editor::camera::{
+    apply_editor_camera, fly_editor_camera, toggle_fly_camera, EditorCamera, EditorCameraSettings,
+};
+
+fn app() -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .init_resource::<EditorCameraSettings>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .add_event::<MouseMotion>()
+        .add_systems(Update, (toggle_fly_camera, fly_editor_camera, apply_editor_camera).chain());
+
+    app.world_mut().spawn((Window::default(), PrimaryWindow));
+
+    let camera = EditorCamera::looking_at(Vec3::new(10.0, 10.0, 10.0), Vec3::new(2.0, 0.0, 3.0));
+    let trns = camera.transform();
+    let camera = app
+        .world_mut()
+        .spawn((
+            camera,
+            trns,
+            Projection::Orthographic(OrthographicProjection {
+                scale: 0.05,
+                area: Rect::new(-16.0, -9.0, 16.0, 9.0),
+                ..default()
+            }),
+        ))
+        .id();
+
+    app.update();
+    (app, camera)
+}
+
+fn toggle(app: &mut App) {
+    let key = app.world().resource::<EditorCameraSettings>().fly_toggle;
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(key);
+    app.update();
+
+    // Without the input plugin, nothing else clears the press.
+    let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    keys.release(key);
+    keys.clear();
+}
+
+#[test]
+fn flying_restores_the_orthographic_view() {
+    let (mut app, camera) = app();
+    toggle(&mut app);
+
+    let entity = app.world().entity(camera);
+    assert!(entity.get::<EditorCamera>().unwrap().is_flying());
+    assert!(matches!(entity.get::<Projection>(), Some(Projection::Perspective(..))));
+
+    // Looking around and moving while flying leaves the orbit rig alone.
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyW);
+    app.update();
+    app.update();
+
+    let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    keys.release(KeyCode::KeyW);
+    keys.clear();
+
+    toggle(&mut app);
+
+    let entity = app.world().entity(camera);
+    let editor_camera = entity.get::<EditorCamera>().unwrap();
+    assert!(!editor_camera.is_flying());
+    assert_eq!(editor_camera.focus, Vec3::new(2.0, 0.0, 3.0));
+    assert_eq!(*entity.get::<Transform>().unwrap(), editor_camera.transform());
+
+    let Some(Projection::Orthographic(ortho)) = entity.get::<Projection>() else {
+        panic!("the orthographic projection wasn't restored")
+    };
+    assert_eq!(ortho.scale, 0.05);
+    assert_eq!(ortho.area, Rect::new(-16.0, -9.0, 16.0, 9.0));
+}
@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use mnemonic::{
+    character::CharacterController,
+    content::properties::{SurfaceTag, TileProperties, TilePropertyTable},
+    editor::EditorMap,
+    map::{Map, MapCell},
+    playtest::{step_player, Footstep, Player, PlaytestSettings},
+};
+use nonmax::NonMaxU8;
+
+#[test]
+fn walking_steps_on_tiles_surfaces() {
+    // A row of metal tiles, then carpet.
+    let mut map = Map::empty(UVec3::new(4, 1, 1));
+    map.tile_set = vec!["metal".into(), "carpet".into()];
+    for (x, tile) in [0, 0, 1, 1].into_iter().enumerate() {
+        map.set_cell(UVec3::new(x as u32, 0, 0), MapCell::new(NonMaxU8::new(tile), default()));
+    }
+
+    let surface = |surface| TileProperties { surface, ..default() };
+    let table = TilePropertyTable(
+        [
+            ("metal".into(), surface(SurfaceTag::Metal)),
+            ("carpet".into(), surface(SurfaceTag::Carpet)),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    assert_eq!(map.surface_at(UVec3::ZERO, &table), Some(SurfaceTag::Metal));
+    assert_eq!(map.surface_at(UVec3::new(3, 0, 0), &table), Some(SurfaceTag::Carpet));
+    assert_eq!(map.surface_at(UVec3::new(4, 0, 0), &table), None);
+
+    let settings = PlaytestSettings::default();
+    let tile_size = map.tile_size;
+    let height = map.cell_min(IVec3::Y).y + settings.length / 2.0 + settings.radius + 0.01;
+
+    let mut app = App::new();
+    let mut maps = Assets::<Map>::default();
+    app.world_mut().spawn((maps.add(map), GlobalTransform::IDENTITY, EditorMap));
+    app.insert_resource(maps)
+        .insert_resource(table)
+        .insert_resource(settings)
+        .add_event::<Footstep>()
+        .add_systems(Update, step_player);
+
+    let player = app
+        .world_mut()
+        .spawn((
+            Transform::from_xyz(0.0, height, 0.0),
+            Player::default(),
+            CharacterController {
+                grounded: true,
+                ..default()
+            },
+        ))
+        .id();
+
+    // Walk across the row in small increments, jumping over the middle.
+    let end = 3.0 * tile_size.x;
+    let increments = 60;
+    let mut steps = Vec::new();
+    for i in 0..=increments {
+        let x = end * i as f32 / increments as f32;
+        let mut entity = app.world_mut().entity_mut(player);
+        entity.get_mut::<Transform>().unwrap().translation.x = x;
+        entity.get_mut::<CharacterController>().unwrap().grounded = !(1.4..1.5).contains(&(x / tile_size.x));
+        app.update();
+
+        let mut footsteps = app.world_mut().resource_mut::<Events<Footstep>>();
+        steps.extend(footsteps.drain().map(|Footstep(surface, pos)| (surface, pos.x / tile_size.x)));
+    }
+
+    let walked = end - 0.1 * tile_size.x;
+    assert_eq!(steps.len(), (walked / settings.step_length).floor() as usize, "{steps:?}");
+    for (surface, x) in steps {
+        let expected = if x < 1.5 { SurfaceTag::Metal } else { SurfaceTag::Carpet };
+        assert_eq!(surface, expected, "step at {x}");
+    }
+}
@@ -0,0 +1,130 @@
+use std::{fs, path::Path};
+
+use bevy::{
+    asset::io::{
+        memory::{Dir, MemoryAssetReader},
+        AssetSource, AssetSourceId,
+    },
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use mnemonic::{
+    cli::EXIT_FAILURE,
+    content::{
+        atlas::TileFilter,
+        render::{TileAlpha, TileRenderFlags},
+    },
+    export::{map_glb, ExportPlugin},
+    harness::{insert_floor_tile, update_until},
+    map::MapPart,
+};
+
+/// The JSON chunk of a GLB file, after checking its header.
+fn glb_json(glb: &[u8]) -> String {
+    let word = |at: usize| u32::from_le_bytes(glb[at..at + 4].try_into().unwrap());
+    assert_eq!(&glb[..4], b"glTF");
+    assert_eq!(word(4), 2);
+    assert_eq!(word(8) as usize, glb.len());
+    assert_eq!(word(16), 0x4E4F534A);
+
+    String::from_utf8(glb[20..20 + word(12) as usize].to_vec()).unwrap()
+}
+
+fn export(dir: Dir, map: &str) -> (AppExit, Option<Vec<u8>>) {
+    let out = std::env::temp_dir().join(format!("mnemonic-export-{}-{map}.glb", std::process::id()));
+    _ = fs::remove_file(&out);
+
+    let mut app = App::new();
+    app.register_asset_source(
+        AssetSourceId::Default,
+        AssetSource::build().with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() })),
+    )
+    .add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        ExportPlugin {
+            map: map.into(),
+            out: out.clone(),
+        },
+    ));
+
+    update_until(&mut app, |app| app.should_exit().is_some());
+    let glb = fs::read(&out).ok();
+    _ = fs::remove_file(&out);
+    (app.should_exit().unwrap(), glb)
+}
+
+fn floor_dir() -> Dir {
+    let dir = Dir::default();
+    insert_floor_tile(&dir.get_or_insert_dir(Path::new("tiles")));
+    dir.insert_asset_text(Path::new("tiles/manifest.ron"), "(tiles: [])");
+    dir.insert_asset_text(
+        Path::new("test.mnmap"),
+        r#"(tile_set: ["tiles/floor.obj#obj:tile"], tiles: [Some(0), None, Some(0)], size: (3, 1, 1))"#,
+    );
+    dir
+}
+
+#[test]
+fn parts_keep_their_pages_and_drawing() {
+    let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 2.0]],
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 1.0, 0.0]; 3])
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]])
+        .with_inserted_indices(Indices::U32(vec![0, 2, 1]));
+
+    let page = Image::new_fill(
+        Extent3d {
+            width: 2,
+            height: 2,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[255, 0, 255, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+
+    let masked = MapPart {
+        page: 0,
+        render: TileRenderFlags {
+            alpha_mode: TileAlpha::Mask(0.5),
+            unlit: true,
+            ..default()
+        },
+    };
+
+    let glb = map_glb(&[(MapPart::page(0), &mesh), (masked, &mesh)], &[&page], TileFilter::Nearest).unwrap();
+    let json = glb_json(&glb);
+
+    // Both parts share the page, embedded once.
+    assert_eq!(json.matches(r#""mimeType":"image/png""#).count(), 1);
+    assert_eq!(json.matches(r#""attributes""#).count(), 2);
+    assert!(json.contains(r#""min":[0,0,0],"max":[1,0,2]"#));
+    assert!(json.contains(r#""alphaMode":"MASK","alphaCutoff":0.5"#));
+    assert!(json.contains(r#""extensionsUsed":["KHR_materials_unlit"]"#));
+}
+
+#[test]
+fn maps_export_without_a_window() {
+    let (exit, glb) = export(floor_dir(), "test.mnmap");
+    assert_eq!(exit, AppExit::Success);
+
+    let json = glb_json(&glb.unwrap());
+    assert!(json.contains(r#""POSITION""#));
+    assert!(json.contains(r#""mimeType":"image/png""#));
+}
+
+#[test]
+fn missing_maps_fail_the_export() {
+    let (exit, glb) = export(floor_dir(), "missing.mnmap");
+    assert_eq!(exit, AppExit::from_code(EXIT_FAILURE));
+    assert!(glb.is_none());
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use bevy::{asset::io::memory::Dir, prelude::*};
+use iyes_progress::prelude::*;
+use mnemonic::{
+    content::LoadError,
+    harness::{test_app, update_until},
+    loading::{retry_failed_assets, watch_asset_failures, FailedAssets, ReloadingAssets},
+    obj::def::Obj,
+    GameState,
+};
+
+#[derive(Resource)]
+struct Floor(Handle<Obj>);
+
+/// Holds loading until the floor tile has loaded, along with everything it needs.
+fn load_floor(server: Res<AssetServer>, floor: Res<Floor>) -> Progress {
+    server.is_loaded_with_dependencies(&floor.0).into()
+}
+
+fn state(app: &App) -> &GameState {
+    app.world().resource::<State<GameState>>().get()
+}
+
+#[test]
+fn failed_assets_are_shown_and_retried() {
+    // The floor tile, missing its material.
+    let dir = Dir::default();
+    dir.insert_asset(
+        Path::new("floor.obj"),
+        include_bytes!("../assets/tiles/liminal/floor.obj").to_vec(),
+    );
+    dir.insert_asset(
+        Path::new("floor.png"),
+        include_bytes!("../assets/tiles/liminal/floor.png").to_vec(),
+    );
+
+    let mut app = test_app(dir.clone());
+    let floor = app.world().resource::<AssetServer>().load("floor.obj#obj:tile");
+    app.insert_resource(Floor(floor))
+        .init_state::<GameState>()
+        .add_plugins(ProgressPlugin::new(GameState::Loading).continue_to(GameState::Menu))
+        .init_resource::<FailedAssets>()
+        .add_systems(
+            Update,
+            (load_floor, watch_asset_failures)
+                .track_progress()
+                .run_if(in_state(GameState::Loading)),
+        )
+        .add_systems(OnExit(GameState::LoadError), retry_failed_assets);
+
+    update_until(&mut app, |app| *state(app) == GameState::LoadError);
+    let error = app.world().resource::<LoadError>();
+    assert_eq!(error.path.as_deref(), Some("floor.mtl"));
+    assert!(!error.message.is_empty());
+
+    // Retrying before the file is back fails just the same.
+    app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Loading);
+    app.update();
+    assert!(app.world().contains_resource::<ReloadingAssets>());
+    update_until(&mut app, |app| *state(app) == GameState::LoadError);
+
+    dir.insert_asset(
+        Path::new("floor.mtl"),
+        include_bytes!("../assets/tiles/liminal/floor.mtl").to_vec(),
+    );
+    app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Loading);
+    update_until(&mut app, |app| *state(app) == GameState::Menu);
+
+    assert!(!app.world().contains_resource::<LoadError>());
+    assert!(!app.world().contains_resource::<ReloadingAssets>());
+    assert!(app.world().resource::<FailedAssets>().is_empty());
+}
@@ -0,0 +1,185 @@
+use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+use avian3d::prelude::*;
+use bevy::{ecs::system::RunSystemOnce, prelude::*, scene::ScenePlugin, time::TimeUpdateStrategy};
+use mnemonic::{
+    content::properties::TilePropertyTable,
+    map::{
+        hit::{MapHit, MapSpatialQuery},
+        tile::cube_obj,
+        GridHit, Map, MapCell,
+    },
+    obj::def::{Cull, Obj},
+    physics::GameLayer,
+};
+use nonmax::NonMaxU8;
+
+#[test]
+fn boundary_hits_belong_to_the_struck_cell() {
+    let map = Map::empty(UVec3::splat(3));
+    let trns = GlobalTransform::IDENTITY;
+
+    // The face between cells 0 and 1 along X, struck from either side.
+    let point = Vec3::new(0.5, 1.0, 1.0);
+    assert_eq!(
+        MapHit::from_ray_hit(&trns, &map, point, Vec3::NEG_X),
+        Some((UVec3::new(1, 1, 1), Cull::NEG_X))
+    );
+    assert_eq!(
+        MapHit::from_ray_hit(&trns, &map, point, Vec3::X),
+        Some((UVec3::new(0, 1, 1), Cull::X))
+    );
+
+    // Corners are nudged along the normal alone.
+    let corner = Vec3::new(0.5, 1.5, 0.5);
+    assert_eq!(
+        MapHit::from_ray_hit(&trns, &map, corner, Vec3::Y),
+        Some((UVec3::new(1, 1, 1), Cull::UP))
+    );
+
+    // Slopes count as the face they lean towards most.
+    let slope = Vec3::new(0.0, 1.0, 1.0).normalize();
+    assert_eq!(
+        MapHit::from_ray_hit(&trns, &map, Vec3::new(1.0, 1.0, 1.0), slope + Vec3::Z * 0.1),
+        Some((UVec3::new(1, 1, 1), Cull::Z))
+    );
+
+    // Hits out of bounds, such as on the outside of the outermost faces, have no cell.
+    assert_eq!(MapHit::from_ray_hit(&trns, &map, Vec3::new(-0.5, 0.0, 0.0), Vec3::X), None);
+    assert_eq!(
+        MapHit::from_ray_hit(&trns, &map, Vec3::new(-0.5, 0.0, 0.0), Vec3::NEG_X),
+        Some((UVec3::ZERO, Cull::NEG_X))
+    );
+}
+
+#[test]
+fn grid_rays_stop_at_their_distance() {
+    let mut map = Map::empty(UVec3::new(4, 1, 1));
+    map.tile_size = Vec3::splat(2.0);
+    map.fill_region(
+        IVec3::new(3, 0, 0),
+        IVec3::new(4, 1, 1),
+        MapCell::new(Some(NonMaxU8::ZERO), default()),
+    );
+
+    // The last cell's near face lies 6 units away, however large the tiles are.
+    let ray = Ray3d::new(Vec3::new(-1.0, 0.0, 0.0), Vec3::X);
+    assert_eq!(map.raycast(ray, 5.9), None);
+    assert_eq!(
+        map.raycast(ray, 6.5),
+        Some(GridHit {
+            cell: UVec3::new(3, 0, 0),
+            normal: IVec3::NEG_X,
+        })
+    );
+}
+
+#[test]
+fn hits_follow_the_map_transform() {
+    let mut map = Map::empty(UVec3::splat(4));
+    map.tile_size = Vec3::new(2.0, 1.0, 2.0);
+
+    // Turned a quarter counter-clockwise, so the map's +X points along world -Z.
+    let trns = GlobalTransform::from(
+        Transform::from_xyz(10.0, 0.0, -5.0)
+            .with_rotation(Quat::from_rotation_y(FRAC_PI_2))
+            .with_scale(Vec3::splat(0.5)),
+    );
+
+    for (cell, normal, face) in [
+        (UVec3::new(2, 1, 3), IVec3::X, Cull::X),
+        (UVec3::new(2, 1, 3), IVec3::NEG_Z, Cull::NEG_Z),
+        (UVec3::new(0, 3, 1), IVec3::Y, Cull::UP),
+        (UVec3::new(3, 0, 0), IVec3::NEG_Y, Cull::DOWN),
+    ] {
+        let local = (cell.as_vec3() + normal.as_vec3() * 0.5) * map.tile_size;
+        let point = trns.transform_point(local);
+        let world_normal = trns.compute_transform().rotation * normal.as_vec3();
+        assert_eq!(
+            MapHit::from_ray_hit(&trns, &map, point, world_normal),
+            Some((cell, face)),
+            "{normal} face of {cell}"
+        );
+    }
+}
+
+#[test]
+fn rays_find_cells_through_playtest_colliders() {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        AssetPlugin::default(),
+        ScenePlugin,
+        PhysicsPlugins::default(),
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<Map>()
+    .init_asset::<Obj>()
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(1.0 / 60.0)));
+
+    let world = app.world_mut();
+    let stone = world.resource_mut::<Assets<Obj>>().add(cube_obj(default(), "stone".into()));
+
+    // A floor with a pillar on it.
+    let mut map = Map::empty(UVec3::new(4, 3, 4));
+    map.tile_set = vec!["stone".into()];
+    map.tile_handles = vec![stone];
+    map.fill_region(
+        IVec3::ZERO,
+        IVec3::new(4, 1, 4),
+        MapCell::new(Some(NonMaxU8::ZERO), default()),
+    );
+    map.fill_region(
+        IVec3::new(1, 1, 2),
+        IVec3::new(2, 3, 3),
+        MapCell::new(Some(NonMaxU8::ZERO), default()),
+    );
+
+    let colliders = map.build_colliders(world.resource::<Assets<Obj>>(), &TilePropertyTable::default());
+    let handle = world.resource_mut::<Assets<Map>>().add(map);
+    let trns = Transform::from_xyz(3.0, 0.0, 0.0).with_rotation(Quat::from_rotation_y(FRAC_PI_2));
+    let map = world
+        .spawn((TransformBundle::from_transform(trns), RigidBody::Static, handle))
+        .with_children(|parent| {
+            for (collider, friction) in colliders {
+                parent.spawn((TransformBundle::default(), collider, friction, GameLayer::map()));
+            }
+        })
+        .id();
+
+    // Something else in the way, which isn't a map.
+    world.spawn((
+        TransformBundle::from_transform(Transform::from_xyz(0.0, 10.0, 0.0)),
+        RigidBody::Static,
+        Collider::cuboid(100.0, 1.0, 100.0),
+        GameLayer::prop(),
+    ));
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let raycast = move |ray: Ray3d| {
+        move |query: MapSpatialQuery| {
+            query
+                .raycast_map(ray, 100.0, default())
+                .map(|hit| (hit.map, hit.cell, hit.face))
+        }
+    };
+
+    // Down onto the top of the pillar; the map's cell (1, 2, 2) is at world (5, 2, -1).
+    let down = Ray3d::new(Vec3::new(5.0, 20.0, -1.0), Vec3::NEG_Y);
+    assert_eq!(
+        app.world_mut().run_system_once(raycast(down)),
+        Some((map, UVec3::new(1, 2, 2), Cull::UP))
+    );
+
+    // Across onto its side, which faces the map's -X, or world +Z.
+    let across = Ray3d::new(Vec3::new(5.0, 1.0, 10.0), Vec3::NEG_Z);
+    assert_eq!(
+        app.world_mut().run_system_once(raycast(across)),
+        Some((map, UVec3::new(1, 1, 2), Cull::NEG_X))
+    );
+}
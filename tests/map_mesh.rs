@@ -0,0 +1,128 @@
+use std::path::Path;
+
+use bevy::{
+    asset::io::{gated::GateOpener, memory::Dir},
+    prelude::*,
+    render::mesh::{Indices, VertexAttributeValues},
+};
+use mnemonic::{
+    content::render::{TileAlpha, TileRenderFlags, TileRenders},
+    harness::{gated_test_app, insert_floor_tile, test_app, update_until},
+    map::{loader::MapFile, Map, MapMeshes, MapPart},
+    obj::def::Obj,
+};
+
+#[derive(Resource, Default)]
+struct MeshBuilds(usize);
+
+fn count_mesh_builds(mut events: EventReader<AssetEvent<Mesh>>, mut builds: ResMut<MeshBuilds>) {
+    for e in events.read() {
+        if matches!(e, AssetEvent::Added { .. } | AssetEvent::Modified { .. }) {
+            builds.0 += 1;
+        }
+    }
+}
+
+fn floor_dir() -> Dir {
+    let dir = Dir::default();
+    insert_floor_tile(&dir);
+    dir.insert_asset_text(
+        Path::new("test.mnmap"),
+        r#"(tile_set: ["floor.obj#obj:tile"], tiles: [Some(0), None, Some(0)], size: (3, 1, 1))"#,
+    );
+    dir
+}
+
+fn app() -> (App, GateOpener) {
+    let (mut app, opener) = gated_test_app(floor_dir());
+    app.init_resource::<MeshBuilds>().add_systems(Last, count_mesh_builds);
+    (app, opener)
+}
+
+#[test]
+fn slow_dependencies_build_once() {
+    let (mut app, opener) = app();
+    let map = app.world().resource::<AssetServer>().load::<Map>("test.mnmap");
+
+    opener.open("test.mnmap");
+    update_until(&mut app, |app| app.world().resource::<Assets<Map>>().contains(&map));
+
+    for _ in 0..10 {
+        app.update();
+    }
+
+    assert_eq!(app.world().resource::<MeshBuilds>().0, 0, "Map was meshed before its tiles loaded.");
+
+    opener.open("floor.obj");
+    opener.open("floor.mtl");
+    opener.open("floor.png");
+    update_until(&mut app, |app| app.world().resource::<MapMeshes>().contains_key(&map.id()));
+
+    for _ in 0..10 {
+        app.update();
+    }
+
+    assert_eq!(app.world().resource::<MeshBuilds>().0, 1);
+}
+
+#[test]
+fn tiles_drawn_differently_mesh_apart() {
+    let (mut app, opener) = app();
+    let blend = TileRenderFlags {
+        alpha_mode: TileAlpha::Blend,
+        ..default()
+    };
+    app.insert_resource(TileRenders([("floor.obj#obj:tile".into(), blend)].into_iter().collect()));
+
+    let map = app.world().resource::<AssetServer>().load::<Map>("test.mnmap");
+    for file in ["test.mnmap", "floor.obj", "floor.mtl", "floor.png"] {
+        opener.open(file);
+    }
+
+    update_until(&mut app, |app| app.world().resource::<MapMeshes>().contains_key(&map.id()));
+
+    let parts = &app.world().resource::<MapMeshes>()[&map.id()];
+    let vertices = |index: usize| {
+        app.world()
+            .resource::<Assets<Mesh>>()
+            .get(&parts[index].1)
+            .unwrap()
+            .count_vertices()
+    };
+
+    assert_eq!(parts.iter().map(|&(part, _)| part).collect::<Vec<_>>(), [
+        MapPart::page(0),
+        MapPart { page: 0, render: blend }
+    ]);
+    assert_eq!(vertices(0), 0, "blended tiles must leave the opaque part");
+    assert!(vertices(1) > 0);
+}
+
+#[test]
+fn meshes_hold_every_tile() {
+    let mut app = test_app(floor_dir());
+    let server = app.world().resource::<AssetServer>().clone();
+
+    let file = MapFile::from_ron(r#"(tile_set: ["floor"], tiles: [Some(0), None, Some(0), Some(0)], size: (2, 2, 1))"#)
+        .unwrap();
+    let tile = server.load::<Obj>("floor.obj#obj:tile");
+    let map = app
+        .world_mut()
+        .resource_mut::<Assets<Map>>()
+        .add(Map::from_file(file, vec![tile.clone()]));
+
+    update_until(&mut app, |app| app.world().resource::<MapMeshes>().contains_key(&map.id()));
+
+    let world = app.world();
+    let obj = world.resource::<Assets<Obj>>().get(&tile).unwrap();
+    let parts = &world.resource::<MapMeshes>()[&map.id()];
+    assert_eq!(parts.len(), 1);
+
+    let mesh = world.resource::<Assets<Mesh>>().get(&parts[0].1).unwrap();
+    assert_eq!(mesh.count_vertices(), obj.positions.len() * 3);
+    assert!(matches!(
+        mesh.attribute(Mesh::ATTRIBUTE_UV_0),
+        Some(VertexAttributeValues::Float32x2(uvs)) if uvs.len() == obj.uvs.len() * 3
+    ));
+    assert!(matches!(mesh.indices(), Some(Indices::U32(indices)) if indices.len() == obj.faces.len() * 9));
+}
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use mnemonic::map::{
+    picking::{MapPick, PickCeiling, PickGround},
+    GridHit, Map, MapCell,
+};
+use nonmax::NonMaxU8;
+
+fn tower() -> Map {
+    let mut map = Map::empty(UVec3::new(3, 3, 3));
+    for y in 0..3 {
+        map.set_cell(UVec3::new(1, y, 1), MapCell::new(Some(NonMaxU8::ZERO), default()));
+    }
+    map
+}
+
+fn down_at(x: f32, z: f32) -> Ray3d {
+    Ray3d::new(Vec3::new(x, 10.0, z), Vec3::NEG_Y)
+}
+
+#[test]
+fn hits_land_on_the_struck_face() {
+    let map = tower();
+    let camera = Entity::PLACEHOLDER;
+
+    let (pick, data) = MapPick::cast(camera, &map, &GlobalTransform::IDENTITY, down_at(1.2, 0.9), None, None).unwrap();
+    assert_eq!(
+        pick.hit,
+        Some(GridHit {
+            cell: UVec3::new(1, 2, 1),
+            normal: IVec3::Y,
+        })
+    );
+
+    let data = data.unwrap();
+    assert_eq!(data.camera, camera);
+    assert!(data.position.unwrap().abs_diff_eq(Vec3::new(1.2, 2.5, 0.9), 1e-5));
+    assert!(data.normal.unwrap().abs_diff_eq(Vec3::Y, 1e-5));
+    assert!((data.depth - 7.5).abs() < 1e-5);
+
+    // Rays missing every cell still pass through the map, but report nothing to pick.
+    let (pick, data) = MapPick::cast(camera, &map, &GlobalTransform::IDENTITY, down_at(0.0, 0.0), None, None).unwrap();
+    assert_eq!(pick.hit, None);
+    assert_eq!(pick.ray, down_at(0.0, 0.0));
+    assert!(data.is_none());
+}
+
+#[test]
+fn hits_follow_the_map_transform() {
+    let map = tower();
+    let trns = GlobalTransform::from(
+        Transform::from_xyz(10.0, 0.0, 0.0)
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2))
+            .with_scale(Vec3::splat(2.0)),
+    );
+
+    // The map's up now points along world -X, so its top face is struck from that side.
+    let ray = Ray3d::new(Vec3::new(-10.0, 2.0, 2.0), Vec3::X);
+    let (pick, data) = MapPick::cast(Entity::PLACEHOLDER, &map, &trns, ray, None, None).unwrap();
+    assert_eq!(
+        pick.hit,
+        Some(GridHit {
+            cell: UVec3::new(1, 2, 1),
+            normal: IVec3::Y,
+        })
+    );
+
+    let data = data.unwrap();
+    assert!(data.position.unwrap().abs_diff_eq(Vec3::new(5.0, 2.0, 2.0), 1e-4));
+    assert!(data.normal.unwrap().abs_diff_eq(Vec3::NEG_X, 1e-5));
+    assert!((data.depth - 15.0).abs() < 1e-4);
+}
+
+#[test]
+fn cells_above_the_ceiling_are_passed_through() {
+    let map = tower();
+    let (pick, _) = MapPick::cast(
+        Entity::PLACEHOLDER,
+        &map,
+        &GlobalTransform::IDENTITY,
+        down_at(1.0, 1.0),
+        Some(PickCeiling(0)),
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(
+        pick.hit,
+        Some(GridHit {
+            cell: UVec3::new(1, 0, 1),
+            normal: IVec3::Y,
+        })
+    );
+}
+
+#[test]
+fn empty_space_falls_back_to_the_ground() {
+    let map = tower();
+    let cast = |ray| {
+        MapPick::cast(Entity::PLACEHOLDER, &map, &GlobalTransform::IDENTITY, ray, None, Some(PickGround(1))).unwrap()
+    };
+
+    // Rays missing every cell meet the floor of the ground layer instead.
+    let (pick, data) = cast(down_at(0.2, 2.1));
+    assert_eq!(pick.hit, None);
+    assert_eq!(pick.ground, Some(IVec3::new(0, 1, 2)));
+
+    let data = data.unwrap();
+    assert!(data.position.unwrap().abs_diff_eq(Vec3::new(0.2, 0.5, 2.1), 1e-5));
+    assert!(data.normal.unwrap().abs_diff_eq(Vec3::Y, 1e-5));
+    assert!((data.depth - 9.5).abs() < 1e-5);
+
+    // Struck cells come first, and the ground only reaches so far past the bounds.
+    assert_eq!(cast(down_at(1.0, 1.0)).0.ground, None);
+    assert_eq!(cast(down_at(-2.0, 0.0)).0.ground, Some(IVec3::new(-2, 1, 0)));
+    assert_eq!(cast(down_at(-9.0, 0.0)).0.ground, None);
+
+    // Rays running along the floor meet it nowhere near.
+    let (pick, data) = cast(Ray3d::new(Vec3::new(0.0, 3.0, 0.0), Vec3::new(1.0, -0.001, 0.0)));
+    assert_eq!(pick.ground, None);
+    assert!(data.is_none());
+}
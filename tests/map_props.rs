@@ -0,0 +1,201 @@
+use avian3d::prelude::*;
+use bevy::{prelude::*, scene::ScenePlugin, utils::HashMap};
+use mnemonic::{
+    map::{
+        loader::MapFile,
+        prop::{build_map_props, sync_map_props, MapProp, Prop, PropBuilt, PropMeshes},
+        tile::cube_obj,
+        Map,
+    },
+    obj::def::{ColliderData, ColliderKind, Mtl, MtlCollection, Obj},
+    physics::GameLayer,
+};
+
+fn crate_at(x: f32) -> Prop {
+    Prop::new("props/crate.obj#obj:crate", Transform::from_xyz(x, 0.0, 1.0))
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        AssetPlugin::default(),
+        ScenePlugin,
+        PhysicsPlugins::default(),
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .init_asset::<Map>()
+    .init_asset::<Obj>()
+    .init_asset::<MtlCollection>()
+    .init_resource::<PropMeshes>()
+    .add_systems(PostUpdate, (sync_map_props, build_map_props).chain());
+    app
+}
+
+fn props(app: &mut App) -> Vec<(usize, Vec3)> {
+    let mut props = app
+        .world_mut()
+        .query::<(&MapProp, &Transform)>()
+        .iter(app.world())
+        .map(|(prop, trns)| (prop.index, trns.translation))
+        .collect::<Vec<_>>();
+    props.sort_by_key(|&(index, _)| index);
+    props
+}
+
+#[test]
+fn props_are_saved_with_the_map() {
+    let mut map = Map::empty(UVec3::new(2, 1, 2));
+    let file = MapFile::from(&map);
+    assert!(!file.to_ron().unwrap().contains("props"));
+
+    let mut turned = crate_at(0.5);
+    turned.transform.rotate_y(1.0);
+    turned.collider = false;
+    map.props = vec![crate_at(1.5), turned];
+
+    let file = MapFile::from(&map);
+    let read = MapFile::from_ron(&file.to_ron().unwrap()).unwrap();
+    assert_eq!(read.props, map.props);
+}
+
+#[test]
+fn prop_edits_undo_and_redo() {
+    let mut map = Map::empty(UVec3::new(2, 1, 2));
+    let added = map.add_prop(crate_at(0.0));
+    let moved = map.set_prop(0, Some(crate_at(1.0)));
+    assert_eq!(map.props, [crate_at(1.0)]);
+
+    // Setting a prop as it is changes nothing.
+    assert!(map.set_prop(0, Some(crate_at(1.0))).is_empty());
+    assert!(map.set_prop(1, None).is_empty());
+
+    moved.revert(&mut map);
+    assert_eq!(map.props, [crate_at(0.0)]);
+    added.revert(&mut map);
+    assert!(map.props.is_empty());
+
+    added.apply(&mut map);
+    moved.apply(&mut map);
+    assert_eq!(map.props, [crate_at(1.0)]);
+
+    let removed = map.set_prop(0, None);
+    assert!(map.props.is_empty());
+    removed.revert(&mut map);
+    assert_eq!(map.props, [crate_at(1.0)]);
+}
+
+#[test]
+fn selected_props_change_in_one_step() {
+    let mut map = Map::empty(UVec3::new(4, 1, 4));
+    map.props = vec![crate_at(0.0), crate_at(1.0), crate_at(2.0)];
+
+    let moved = map.transform_props(&[0, 2], |trns| trns.translation.z += 1.0);
+    assert_eq!(map.props.iter().map(|prop| prop.transform.translation.z).collect::<Vec<_>>(), [
+        2.0, 1.0, 2.0
+    ]);
+
+    // Indices past the last prop are left alone.
+    let removed = map.remove_props(&[2, 0, 5]);
+    assert_eq!(map.props, [crate_at(1.0)]);
+
+    removed.revert(&mut map);
+    assert_eq!(map.props.len(), 3);
+    moved.revert(&mut map);
+    assert_eq!(map.props, [crate_at(0.0), crate_at(1.0), crate_at(2.0)]);
+}
+
+#[test]
+fn props_stay_with_cells_on_resize() {
+    let mut map = Map::empty(UVec3::new(2, 1, 2));
+    map.tile_size = Vec3::splat(2.0);
+    map.props = vec![crate_at(1.0)];
+
+    let diff = map.resize(UVec3::new(4, 1, 2), IVec3::new(2, 0, 0));
+    assert_eq!(map.props[0].transform.translation, Vec3::new(5.0, 0.0, 1.0));
+
+    diff.revert(&mut map);
+    assert_eq!(map.props, [crate_at(1.0)]);
+    diff.apply(&mut map);
+    assert_eq!(map.props[0].transform.translation, Vec3::new(5.0, 0.0, 1.0));
+}
+
+#[test]
+fn prop_entities_follow_edits() {
+    let mut app = app();
+    let mut map = Map::empty(UVec3::new(4, 1, 4));
+    map.props = vec![crate_at(0.0), crate_at(1.0)];
+
+    let handle = app.world_mut().resource_mut::<Assets<Map>>().add(map);
+    let e = app
+        .world_mut()
+        .spawn((SpatialBundle::default(), handle.clone()))
+        .id();
+
+    app.update();
+    assert_eq!(props(&mut app), [(0, Vec3::new(0.0, 0.0, 1.0)), (1, Vec3::new(1.0, 0.0, 1.0))]);
+
+    let children = app.world().get::<Children>(e).unwrap().to_vec();
+    let edit = |app: &mut App, edit: fn(&mut Map)| {
+        edit(app.world_mut().resource_mut::<Assets<Map>>().get_mut(&handle).unwrap());
+        // The map's change is only told the frame after.
+        app.update();
+        app.update();
+    };
+
+    // Moving a prop keeps its entity.
+    edit(&mut app, |map| {
+        map.set_prop(1, Some(crate_at(3.0)));
+    });
+    assert_eq!(props(&mut app), [(0, Vec3::new(0.0, 0.0, 1.0)), (1, Vec3::new(3.0, 0.0, 1.0))]);
+    assert_eq!(app.world().get::<Children>(e).unwrap().to_vec(), children);
+
+    edit(&mut app, |map| {
+        map.set_prop(0, None);
+    });
+    assert_eq!(props(&mut app), [(0, Vec3::new(3.0, 0.0, 1.0))]);
+}
+
+#[test]
+fn props_collide_only_if_asked() {
+    let mut app = app();
+    let world = app.world_mut();
+    let mtl = world.resource_mut::<Assets<MtlCollection>>().add(MtlCollection {
+        materials: HashMap::from_iter([("crate".into(), Mtl::default())]),
+    });
+
+    let mut obj = cube_obj(mtl, "crate".into());
+    obj.collider_data = ColliderData::generate(ColliderKind::Aabb, &obj);
+    let obj = world.resource_mut::<Assets<Obj>>().add(obj);
+
+    let mut spawn = |collider| {
+        let prop = Prop {
+            collider,
+            ..crate_at(0.0)
+        };
+        world
+            .spawn((SpatialBundle::default(), obj.clone(), MapProp { index: 0, prop }))
+            .id()
+    };
+
+    let (solid, ghost) = (spawn(true), spawn(false));
+    app.update();
+
+    let world = app.world();
+    for e in [solid, ghost] {
+        assert!(world.get::<PropBuilt>(e).is_some());
+        assert!(world.get::<Handle<Mesh>>(e).is_some());
+        assert!(world.get::<Collider>(e).is_some());
+    }
+
+    // Both share the object's mesh.
+    assert_eq!(world.get::<Handle<Mesh>>(solid), world.get::<Handle<Mesh>>(ghost));
+
+    assert_eq!(world.get::<CollisionLayers>(solid), Some(&GameLayer::prop()));
+    assert!(world.get::<Sensor>(solid).is_none());
+    assert_eq!(world.get::<CollisionLayers>(ghost), Some(&GameLayer::pick_only()));
+    assert!(world.get::<Sensor>(ghost).is_some());
+}
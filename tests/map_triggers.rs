@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::{prelude::*, scene::ScenePlugin, time::TimeUpdateStrategy};
+use mnemonic::{
+    content::properties::{TileProperties, TilePropertyTable},
+    map::{
+        tile::cube_obj,
+        trigger::{gather_map_triggers, update_map_triggers, MapTrigger, MapTriggers},
+        Map, MapCell,
+    },
+    obj::def::Obj,
+    physics::GameLayer,
+    playtest::{sense_triggers, TriggerEntered, TriggerExited},
+};
+use nonmax::NonMaxU8;
+
+const STONE: NonMaxU8 = NonMaxU8::ZERO;
+const DOOR: NonMaxU8 = NonMaxU8::ONE;
+
+#[derive(Resource, Default)]
+struct Sensed(Vec<(bool, String, UVec3, Entity)>);
+
+fn app() -> (App, Handle<Map>, Entity) {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        AssetPlugin::default(),
+        ScenePlugin,
+        PhysicsPlugins::default(),
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<Map>()
+    .init_asset::<Obj>()
+    .init_resource::<MapTriggers>()
+    .init_resource::<Sensed>()
+    .add_event::<TriggerEntered>()
+    .add_event::<TriggerExited>()
+    .insert_resource(TilePropertyTable(
+        [("door".into(), TileProperties {
+            sensor: true,
+            ..default()
+        })]
+        .into(),
+    ))
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(1.0 / 60.0)))
+    .add_systems(
+        Update,
+        (
+            sense_triggers,
+            |mut entered: EventReader<TriggerEntered>,
+             mut exited: EventReader<TriggerExited>,
+             mut sensed: ResMut<Sensed>| {
+                sensed
+                    .0
+                    .extend(entered.read().map(|e| (true, e.id.clone(), e.cell, e.entity)));
+                sensed
+                    .0
+                    .extend(exited.read().map(|e| (false, e.id.clone(), e.cell, e.entity)));
+            },
+        )
+            .chain(),
+    )
+    .add_systems(PostUpdate, (gather_map_triggers, update_map_triggers).chain());
+
+    let world = app.world_mut();
+    let stone = world.resource_mut::<Assets<Obj>>().add(cube_obj(default(), "stone".into()));
+    let door = world.resource_mut::<Assets<Obj>>().add(cube_obj(default(), "door".into()));
+
+    // A floor with a doorway of two trigger cells on it.
+    let mut map = Map::empty(UVec3::new(5, 3, 1));
+    map.tile_set = vec!["stone".into(), "door".into()];
+    map.tile_handles = vec![stone, door];
+    map.fill_region(IVec3::ZERO, IVec3::new(5, 1, 1), MapCell::new(Some(STONE), default()));
+    map.fill_region(IVec3::new(2, 1, 0), IVec3::new(3, 3, 1), MapCell::new(Some(DOOR), default()));
+
+    let handle = world.resource_mut::<Assets<Map>>().add(map);
+    let e = world
+        .spawn((TransformBundle::default(), RigidBody::Static, handle.clone()))
+        .id();
+
+    for _ in 0..2 {
+        app.update();
+    }
+
+    (app, handle, e)
+}
+
+fn triggers(app: &mut App) -> Vec<(MapTrigger, Vec3)> {
+    let mut triggers = app
+        .world_mut()
+        .query::<(&MapTrigger, &GlobalTransform)>()
+        .iter(app.world())
+        .map(|(trigger, trns)| (trigger.clone(), trns.translation()))
+        .collect::<Vec<_>>();
+    triggers.sort_by_key(|(trigger, _)| trigger.cell.to_array());
+    triggers
+}
+
+#[test]
+fn sensor_tiles_follow_edits() {
+    let (mut app, handle, _) = app();
+
+    let door = |y| MapTrigger {
+        cell: UVec3::new(2, y, 0),
+        id: "door".into(),
+    };
+    assert_eq!(triggers(&mut app), [
+        (door(1), Vec3::new(2.0, 1.0, 0.0)),
+        (door(2), Vec3::new(2.0, 2.0, 0.0))
+    ]);
+
+    // Sensor tiles aren't solid.
+    let map = app.world().resource::<Assets<Map>>().get(&handle).unwrap();
+    let solid = map.build_colliders(app.world().resource::<Assets<Obj>>(), app.world().resource());
+    assert_eq!(solid.len(), 1);
+
+    app.world_mut()
+        .resource_mut::<Assets<Map>>()
+        .get_mut(&handle)
+        .unwrap()
+        .set_cell(UVec3::new(2, 2, 0), MapCell::default());
+
+    // The map's change is only told the frame after.
+    app.update();
+    app.update();
+    assert_eq!(triggers(&mut app), [(door(1), Vec3::new(2.0, 1.0, 0.0))]);
+}
+
+#[test]
+fn players_set_off_triggers() {
+    let (mut app, ..) = app();
+    let player = app
+        .world_mut()
+        .spawn((
+            TransformBundle::from_transform(Transform::from_xyz(0.0, 1.25, 0.0)),
+            RigidBody::Kinematic,
+            Collider::capsule(0.25, 0.5),
+            GameLayer::player(),
+        ))
+        .id();
+
+    // Walk through the doorway and out the other side.
+    for _ in 0..80 {
+        app.world_mut().get_mut::<Transform>(player).unwrap().translation.x += 0.05;
+        app.update();
+    }
+
+    let sensed = &app.world().resource::<Sensed>().0;
+    assert!(sensed.contains(&(true, "door".into(), UVec3::new(2, 1, 0), player)));
+    assert!(sensed.contains(&(false, "door".into(), UVec3::new(2, 1, 0), player)));
+    assert!(sensed.iter().all(|(.., entity)| *entity == player));
+
+    let entered = sensed.iter().position(|&(entered, ..)| entered).unwrap();
+    let exited = sensed.iter().position(|&(entered, ..)| !entered).unwrap();
+    assert!(entered < exited);
+}
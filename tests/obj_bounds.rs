@@ -0,0 +1,52 @@
+use bevy::prelude::*;
+use mnemonic::{
+    map::{
+        orientation::TileOrientation,
+        tile::{cube_obj, pillar_obj, slab_obj},
+        Map, MapCell,
+    },
+    obj::def::Obj,
+};
+use nonmax::NonMaxU8;
+
+#[test]
+fn generated_tiles_are_bounded() {
+    assert_eq!(
+        cube_obj(default(), "tile".into()).aabb(),
+        (Vec3::splat(-0.5), Vec3::splat(0.5))
+    );
+    assert_eq!(
+        slab_obj(default(), "tile".into(), 0.25).aabb(),
+        (Vec3::splat(-0.5), Vec3::new(0.5, -0.25, 0.5))
+    );
+
+    let (min, max) = pillar_obj(default(), "tile".into(), 0.2).aabb();
+    assert!(min.abs_diff_eq(Vec3::new(-0.2, -0.5, -0.2), 1e-5));
+    assert!(max.abs_diff_eq(Vec3::new(0.2, 0.5, 0.2), 1e-5));
+
+    let mut empty = Obj::default();
+    empty.calculate_bounds();
+    assert_eq!(empty.aabb(), (Vec3::ZERO, Vec3::ZERO));
+}
+
+#[test]
+fn maps_are_bounded_by_their_tiles() {
+    let mut objs = Assets::<Obj>::default();
+    let mut map = Map::empty(UVec3::new(4, 2, 4));
+    assert_eq!(map.aabb(&objs), None);
+
+    map.tile_set = vec!["pillar".into(), "unloaded".into()];
+    map.tile_handles = vec![objs.add(pillar_obj(default(), "tile".into(), 0.2)), Handle::default()];
+
+    let (pillar, unloaded) = (NonMaxU8::new(0), NonMaxU8::new(1));
+    map.set_cell(UVec3::new(1, 0, 1), MapCell::new(pillar, TileOrientation::new(1, false)));
+    let (min, max) = map.aabb(&objs).unwrap();
+    assert!(min.abs_diff_eq(Vec3::new(0.8, -0.5, 0.8), 1e-5));
+    assert!(max.abs_diff_eq(Vec3::new(1.2, 0.5, 1.2), 1e-5));
+
+    // Tiles that haven't loaded fill their cells.
+    map.set_cell(UVec3::new(3, 1, 2), MapCell::new(unloaded, default()));
+    let (min, max) = map.aabb(&objs).unwrap();
+    assert!(min.abs_diff_eq(Vec3::new(0.8, -0.5, 0.8), 1e-5));
+    assert_eq!(max, Vec3::new(3.5, 1.5, 2.5));
+}
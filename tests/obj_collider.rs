@@ -0,0 +1,275 @@
+use std::path::Path;
+
+use avian3d::prelude::*;
+use bevy::{
+    asset::io::{
+        memory::{Dir, MemoryAssetReader},
+        AssetSource, AssetSourceId,
+    },
+    prelude::*,
+};
+use mnemonic::{
+    harness::update_until,
+    obj::{
+        def::{ColliderData, ColliderKind, Obj, ObjCollection},
+        ObjPlugin,
+    },
+};
+
+const FLAT: &str = "mtllib floor.mtl
+
+o flat
+v 0 0 0
+v 1 0 0
+v 1 0 1
+v 0 0 1
+vt 0 0
+vn 0 1 0
+usemtl none
+f 1/1/1 3/1/1 2/1/1
+f 1/1/1 4/1/1 3/1/1
+
+o empty
+v 0 0 0
+usemtl none
+";
+
+fn meta(scale: f32, collider: &str) -> String {
+    format!(
+        r#"(
+    meta_format_version: "1.0",
+    asset: Load(
+        loader: "mnemonic::obj::loader::ObjLoader",
+        settings: (scale: {scale:?}, flip_v: true, generate_collider: {collider}),
+    ),
+)"#
+    )
+}
+
+/// An archway of two pillars and a lintel over them, each a box of its own, one unit deep.
+fn arch() -> String {
+    let mut obj = "mtllib floor.mtl\n\no arch\nvt 0 0\nvn 0 1 0\nusemtl none\n".to_string();
+    let boxes = [
+        (Vec3::ZERO, Vec3::new(1.0, 3.0, 1.0)),
+        (Vec3::new(3.0, 0.0, 0.0), Vec3::new(4.0, 3.0, 1.0)),
+        (Vec3::new(0.0, 3.0, 0.0), Vec3::new(4.0, 4.0, 1.0)),
+    ];
+
+    for (index, (min, max)) in boxes.into_iter().enumerate() {
+        for corner in 0..8 {
+            let pick = |bit: usize, min: f32, max: f32| if corner & bit == 0 { min } else { max };
+            obj += &format!(
+                "v {} {} {}\n",
+                pick(1, min.x, max.x),
+                pick(2, min.y, max.y),
+                pick(4, min.z, max.z)
+            );
+        }
+
+        for [a, b, c, d] in [
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+        ] {
+            let [a, b, c, d] = [a, b, c, d].map(|corner| index * 8 + corner + 1);
+            obj += &format!("f {a}/1/1 {b}/1/1 {c}/1/1\nf {a}/1/1 {c}/1/1 {d}/1/1\n");
+        }
+    }
+
+    obj
+}
+
+/// Loads the floor tile's object, and a flat and an empty one, generating colliders of a kind.
+fn load(kind: ColliderKind) -> (Obj, Obj, Obj) {
+    let dir = Dir::default();
+    for file in ["floor.mtl", "floor.png"] {
+        let bytes = std::fs::read(Path::new("assets/tiles/liminal").join(file)).unwrap();
+        dir.insert_asset(Path::new(file), bytes);
+    }
+
+    dir.insert_asset(
+        Path::new("floor.obj"),
+        std::fs::read("assets/tiles/liminal/floor.obj").unwrap(),
+    );
+    dir.insert_asset_text(Path::new("flat.obj"), FLAT);
+    for file in ["floor.obj", "flat.obj"] {
+        dir.insert_meta_text(Path::new(file), &meta(2.0, &format!("{kind:?}")));
+    }
+
+    let loading = app(dir);
+    let (floor, flat) = (
+        loading.server.load::<ObjCollection>("floor.obj"),
+        loading.server.load::<ObjCollection>("flat.obj"),
+    );
+
+    let objs = loading.wait(&[&floor, &flat]);
+    (objs(&floor, "tile"), objs(&flat, "flat"), objs(&flat, "empty"))
+}
+
+/// Loads the archway, generating a collider as `collider` says.
+fn load_arch(collider: &str) -> Obj {
+    let dir = Dir::default();
+    dir.insert_asset(
+        Path::new("floor.mtl"),
+        std::fs::read("assets/tiles/liminal/floor.mtl").unwrap(),
+    );
+    dir.insert_asset(
+        Path::new("floor.png"),
+        std::fs::read("assets/tiles/liminal/floor.png").unwrap(),
+    );
+    dir.insert_asset_text(Path::new("arch.obj"), &arch());
+    dir.insert_meta_text(Path::new("arch.obj"), &meta(1.0, collider));
+
+    let loading = app(dir);
+    let arch = loading.server.load::<ObjCollection>("arch.obj");
+    loading.wait(&[&arch])(&arch, "arch")
+}
+
+/// An app loading objects from memory.
+struct Loading {
+    server: AssetServer,
+    app: App,
+}
+
+fn app(dir: Dir) -> Loading {
+    let mut app = App::new();
+    app.register_asset_source(
+        AssetSourceId::Default,
+        AssetSource::build().with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() })),
+    )
+    .add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        ImagePlugin::default_nearest(),
+        ObjPlugin,
+    ));
+
+    app.finish();
+    app.cleanup();
+
+    Loading {
+        server: app.world().resource::<AssetServer>().clone(),
+        app,
+    }
+}
+
+impl Loading {
+    /// Runs the app until every collection has loaded, returning a way to take objects out of them.
+    fn wait(mut self, collections: &[&Handle<ObjCollection>]) -> impl Fn(&Handle<ObjCollection>, &str) -> Obj {
+        let server = self.server;
+        update_until(&mut self.app, |_| {
+            collections
+                .iter()
+                .all(|&collection| server.is_loaded_with_dependencies(collection))
+        });
+
+        let world = self.app.world_mut();
+        let collections = world.remove_resource::<Assets<ObjCollection>>().unwrap();
+        let objs = world.remove_resource::<Assets<Obj>>().unwrap();
+        move |collection: &Handle<ObjCollection>, name: &str| {
+            objs.get(&collections.get(collection).unwrap()[name]).unwrap().clone()
+        }
+    }
+}
+
+#[test]
+fn floor_colliders_hit_its_faces() {
+    for kind in [ColliderKind::Trimesh, ColliderKind::ConvexHull, ColliderKind::Aabb] {
+        let (floor, ..) = load(kind);
+        let collider = floor.collider_data.as_ref().and_then(ColliderData::collider).unwrap();
+
+        // The floor is a cube spanning a whole cell, so rays meet the cell's top and side.
+        for (origin, direction, plane) in [
+            (Vec3::new(0.1, 5.0, -0.2), Vec3::NEG_Y, Vec3::Y),
+            (Vec3::new(5.0, 0.3, 0.1), Vec3::NEG_X, Vec3::X),
+        ] {
+            let (distance, normal) = collider
+                .cast_ray(Vec3::ZERO, Quat::IDENTITY, origin, direction, 100.0, true)
+                .unwrap_or_else(|| panic!("{kind:?} collider missed a ray toward {direction}"));
+
+            let hit = origin + direction * distance;
+            assert!(
+                (hit.dot(plane) - 0.5).abs() < 1e-4,
+                "{kind:?} hit {hit}, not the plane along {plane}"
+            );
+            assert!(
+                normal.abs().abs_diff_eq(plane, 1e-4),
+                "{kind:?} hit with a normal of {normal}"
+            );
+        }
+
+        // Rays past the cell miss.
+        assert!(collider
+            .cast_ray(Vec3::ZERO, Quat::IDENTITY, Vec3::new(0.6, 5.0, 0.0), Vec3::NEG_Y, 100.0, true)
+            .is_none());
+    }
+}
+
+#[test]
+fn degenerate_objects_have_no_collider() {
+    let (_, flat, empty) = load(ColliderKind::ConvexHull);
+    assert!(flat.collider_data.is_none());
+    assert!(empty.collider_data.is_none());
+
+    // Flat objects still make triangle meshes and boxes.
+    for kind in [ColliderKind::Trimesh, ColliderKind::Aabb] {
+        let (_, flat, empty) = load(kind);
+        assert!(flat.collider_data.as_ref().and_then(ColliderData::collider).is_some());
+        assert!(empty.collider_data.is_none());
+    }
+
+    let (floor, ..) = load(ColliderKind::None);
+    assert!(floor.collider_data.is_none());
+}
+
+#[test]
+fn objects_are_bounded_as_loaded() {
+    let (floor, flat, empty) = load(ColliderKind::None);
+    assert_eq!(floor.aabb(), (Vec3::splat(-0.5), Vec3::splat(0.5)));
+    assert_eq!(flat.aabb(), (Vec3::ZERO, Vec3::new(2.0, 0.0, 2.0)));
+    assert_eq!(empty.aabb(), (Vec3::ZERO, Vec3::ZERO));
+}
+
+#[test]
+fn decomposed_archways_keep_their_opening() {
+    let collider = |kind| {
+        load_arch(kind)
+            .collider_data
+            .as_ref()
+            .and_then(ColliderData::collider)
+            .unwrap()
+    };
+
+    let through = |collider: &Collider| {
+        collider
+            .cast_ray(Vec3::ZERO, Quat::IDENTITY, Vec3::new(2.0, 1.5, -5.0), Vec3::Z, 100.0, true)
+            .is_some()
+    };
+    let pillar = Vec3::new(0.5, 1.5, 0.5);
+
+    // Hulls fill the opening, and triangle meshes are hollow.
+    let hull = collider("ConvexHull");
+    assert!(through(&hull));
+    let trimesh = collider("Trimesh");
+    assert!(!through(&trimesh));
+    assert!(!trimesh.contains_point(Vec3::ZERO, Quat::IDENTITY, pillar));
+
+    let data = load_arch("ConvexDecomposition(max_hulls: 8, resolution: 32)").collider_data;
+    let Some(ColliderData::ConvexDecomposition { hulls }) = &data else {
+        panic!("Decomposed into {data:?}");
+    };
+    assert!((2..=8).contains(&hulls.len()), "{} hulls", hulls.len());
+
+    let decomposed = data.as_ref().and_then(ColliderData::collider).unwrap();
+    assert!(!through(&decomposed));
+    assert!(decomposed.contains_point(Vec3::ZERO, Quat::IDENTITY, pillar));
+    assert!(decomposed.contains_point(Vec3::ZERO, Quat::IDENTITY, Vec3::new(2.0, 3.5, 0.5)));
+
+    // What falls through the opening isn't stopped until the ground.
+    assert!(decomposed
+        .cast_ray(Vec3::ZERO, Quat::IDENTITY, Vec3::new(2.0, 2.5, 0.5), Vec3::NEG_Y, 100.0, true)
+        .is_none());
+}
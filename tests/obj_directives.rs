@@ -0,0 +1,47 @@
+use mnemonic::obj::parser::{parse_obj, ObjDirective, ObjDirectives};
+use nom::error::VerboseError;
+
+const FLOOR: &str = include_str!("../assets/tiles/liminal/floor.obj");
+
+fn directives(input: &str) -> ObjDirectives<'_, VerboseError<&str>> {
+    ObjDirectives::new(input)
+}
+
+#[test]
+fn streamed_directives_match_parsed_ones() {
+    let (rest, parsed) = parse_obj::<VerboseError<&str>>(FLOOR).unwrap();
+    let mut streamed = directives(FLOOR);
+    let count = streamed.by_ref().map(Result::unwrap).count();
+
+    assert_eq!(count, parsed.len());
+    assert_eq!(streamed.remaining(), rest);
+    assert!(parsed.iter().any(|dir| matches!(dir, ObjDirective::F(..))));
+}
+
+#[test]
+fn faces_up_to_quads_stay_inline() {
+    let input = "f 1/1/1 2/2/2 3/3/3 4/4/4\nf 1/1/1 2/2/2 3/3/3 4/4/4 5/5/5\n";
+    let faces = directives(input)
+        .map(|dir| match dir.unwrap() {
+            ObjDirective::F(f) => (f.len(), f.spilled()),
+            _ => panic!("Expected a face."),
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(faces, [(4, false), (5, true)]);
+}
+
+#[test]
+fn streaming_stops_where_parsing_does() {
+    // Unknown directives end the file, as they always have.
+    let mut streamed = directives("o tile\nv 0 0 0\ns off\nv 1 1 1\n");
+    assert_eq!(streamed.by_ref().count(), 2);
+    assert_eq!(streamed.remaining(), "s off\nv 1 1 1\n");
+
+    // Malformed ones fail, and nothing comes after.
+    let mut streamed = directives("o tile\nf 1/1/1 2/2/2\nv 0 0 0\n");
+    assert!(streamed.next().unwrap().is_ok());
+    assert!(streamed.next().unwrap().is_err());
+    assert!(streamed.next().is_none());
+    assert!(parse_obj::<VerboseError<&str>>("o tile\nf 1/1/1 2/2/2\n").is_err());
+}
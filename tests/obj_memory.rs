@@ -0,0 +1,101 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    fmt::Write,
+};
+
+use mnemonic::obj::parser::{parse_obj, ObjDirectives};
+use nom::error::Error;
+
+/// Counts the heap use of the thread it's asked to, so the test runner's own doesn't get in the way.
+struct CountingAlloc;
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static CURRENT: Cell<usize> = const { Cell::new(0) };
+    static PEAK: Cell<usize> = const { Cell::new(0) };
+    static COUNT: Cell<usize> = const { Cell::new(0) };
+}
+
+fn grow(size: usize) {
+    if COUNTING.get() {
+        CURRENT.set(CURRENT.get() + size);
+        PEAK.set(PEAK.get().max(CURRENT.get()));
+        COUNT.set(COUNT.get() + 1);
+    }
+}
+
+fn shrink(size: usize) {
+    if COUNTING.get() {
+        CURRENT.set(CURRENT.get().saturating_sub(size));
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        grow(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        shrink(layout.size());
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        grow(new_size);
+        shrink(layout.size());
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+/// Runs `f`, returning its output along with the most it had allocated at once and how many times
+/// it allocated.
+fn measure<T>(f: impl FnOnce() -> T) -> (T, usize, usize) {
+    CURRENT.set(0);
+    PEAK.set(0);
+    COUNT.set(0);
+
+    COUNTING.set(true);
+    let out = f();
+    COUNTING.set(false);
+    (out, PEAK.get(), COUNT.get())
+}
+
+/// A flat grid of `n` by `n` quads, as big tiles are made of.
+fn grid(n: usize) -> String {
+    let mut obj = String::from("o grid\n");
+    for z in 0..=n {
+        for x in 0..=n {
+            writeln!(obj, "v {x}.0 0.0 {z}.0\nvt {x}.0 {z}.0").unwrap();
+        }
+    }
+
+    obj.push_str("vn 0.0 1.0 0.0\n");
+    let at = |x: usize, z: usize| z * (n + 1) + x + 1;
+    for z in 0..n {
+        for x in 0..n {
+            let [a, b, c, d] = [at(x, z), at(x + 1, z), at(x + 1, z + 1), at(x, z + 1)];
+            writeln!(obj, "f {a}/{a}/1 {b}/{b}/1 {c}/{c}/1 {d}/{d}/1").unwrap();
+        }
+    }
+
+    obj
+}
+
+#[test]
+fn streaming_allocates_nothing() {
+    let obj = grid(100);
+    let (collected, collected_peak, _) = measure(|| parse_obj::<Error<&str>>(&obj).unwrap().1.len());
+    let (streamed, streamed_peak, allocations) =
+        measure(|| ObjDirectives::<Error<&str>>::new(&obj).map(Result::unwrap).count());
+
+    assert_eq!(streamed, collected);
+    assert!(collected_peak > 0);
+
+    // Directives are gone before the next is parsed, and quads keep their vertices inline.
+    assert_eq!((streamed_peak, allocations), (0, 0));
+}
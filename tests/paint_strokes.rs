@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use mnemonic::{
+    editor::history::EditorHistory,
+    map::{Map, MapCell, MapMeshThrottle},
+};
+use nonmax::NonMaxU8;
+
+#[test]
+fn sweeps_record_one_step() {
+    let mut map = Map::empty(UVec3::new(64, 1, 1));
+    let mut history = EditorHistory::default();
+    let cell = MapCell::new(Some(NonMaxU8::ZERO), default());
+
+    for x in 0..64 {
+        assert!(history.stroke(map.fill_cells([IVec3::new(x, 0, 0)], cell)));
+    }
+
+    // Sweeping back over painted cells changes nothing worth keeping.
+    assert!(!history.stroke(map.fill_cells([IVec3::ZERO], cell)));
+
+    // The stroke isn't a step until it ends, though it may already be undone.
+    assert_eq!(history.undo_len(), 0);
+    assert!(history.can_undo());
+
+    assert!(history.end_stroke());
+    assert!(!history.end_stroke());
+    assert_eq!(history.undo_len(), 1);
+}
+
+#[test]
+fn throttled_maps_wait_between_builds() {
+    let id = AssetId::<Map>::default();
+    let ms = Duration::from_millis;
+    let mut throttle = MapMeshThrottle::default();
+    assert!(!throttle.holds(id, ms(0)));
+
+    // Maps without an interval are rebuilt whenever they change.
+    throttle.mark_built(id, ms(0));
+    assert!(!throttle.holds(id, ms(10)));
+
+    throttle.intervals.insert(id, ms(50));
+    assert!(throttle.holds(id, ms(10)));
+    assert!(!throttle.holds(id, ms(50)));
+}
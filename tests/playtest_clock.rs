@@ -0,0 +1,84 @@
+use avian3d::prelude::*;
+use bevy::{ecs::system::RunSystemOnce, prelude::*, utils::Duration};
+use mnemonic::playtest::{step_playtest, PlaytestClock};
+
+#[derive(Resource, Default)]
+struct FixedRuns(usize);
+
+fn count_fixed_runs(mut runs: ResMut<FixedRuns>) {
+    runs.0 += 1;
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .init_resource::<Time<Physics>>()
+        .init_resource::<FixedRuns>()
+        .add_systems(FixedUpdate, count_fixed_runs);
+    app
+}
+
+fn apply(app: &mut App, clock: PlaytestClock) {
+    let world = app.world_mut();
+    world.insert_resource(clock);
+    world.resource_scope(|world, mut virtual_time: Mut<Time<Virtual>>| {
+        clock.apply(&mut virtual_time, &mut world.resource_mut::<Time<Physics>>());
+    });
+}
+
+#[test]
+fn scales_apply_to_both_clocks() {
+    let mut app = app();
+    apply(&mut app, PlaytestClock {
+        time_scale: 0.25,
+        ..default()
+    });
+
+    let world = app.world();
+    assert_eq!(world.resource::<Time<Virtual>>().relative_speed(), 0.25);
+    assert_eq!(world.resource::<Time<Physics>>().relative_speed(), 0.25);
+    assert!(!world.resource::<Time<Physics>>().is_paused());
+
+    // Paused physics steps at full speed, so a step is one whole timestep.
+    apply(&mut app, PlaytestClock {
+        paused: true,
+        time_scale: 0.25,
+        step: false,
+    });
+
+    let world = app.world();
+    assert!(world.resource::<Time<Virtual>>().is_paused());
+    assert!(world.resource::<Time<Physics>>().is_paused());
+    assert_eq!(world.resource::<Time<Physics>>().relative_speed(), 1.0);
+}
+
+#[test]
+fn paused_playtests_step_once() {
+    let mut app = app();
+    apply(&mut app, PlaytestClock {
+        paused: true,
+        ..default()
+    });
+
+    for _ in 0..5 {
+        std::thread::sleep(Duration::from_millis(20));
+        app.update();
+    }
+    assert_eq!(app.world().resource::<FixedRuns>().0, 0);
+
+    // Nothing happens unless asked to step.
+    app.world_mut().run_system_once(step_playtest);
+    assert_eq!(app.world().resource::<FixedRuns>().0, 0);
+
+    app.world_mut().resource_mut::<PlaytestClock>().step = true;
+    app.world_mut().run_system_once(step_playtest);
+
+    let world = app.world();
+    assert_eq!(world.resource::<FixedRuns>().0, 1);
+    assert!(!world.resource::<PlaytestClock>().step);
+    assert_eq!(
+        world.resource::<Time<Physics>>().delta(),
+        Duration::from_secs_f64(1.0 / 60.0)
+    );
+    assert_eq!(world.resource::<Time<Fixed>>().elapsed(), world.resource::<Time<Fixed>>().timestep());
+}
@@ -0,0 +1,68 @@
+use bevy::{asset::io::memory::Dir, ecs::system::SystemState, prelude::*};
+use mnemonic::{
+    content::{take_tile_images, TileTexture, Tiles},
+    harness::{insert_floor_tile, test_app, update_until},
+    obj::def::{MtlCollection, Obj},
+};
+
+/// Takes the tile images into the texture, as building the atlas does.
+fn take(app: &mut App, tiles: &Tiles, texture: &mut TileTexture) -> bool {
+    let mut state =
+        SystemState::<(Res<Assets<Obj>>, ResMut<Assets<MtlCollection>>, ResMut<Assets<Image>>)>::new(app.world_mut());
+    let (objs, mut materials, mut images) = state.get_mut(app.world_mut());
+
+    take_tile_images(tiles, &objs, &mut materials, &mut images, texture).is_ok()
+}
+
+#[test]
+fn released_sources_reload_with_same_id() {
+    let dir = Dir::default();
+    insert_floor_tile(&dir);
+
+    let mut app = test_app(dir);
+    let obj = app.world().resource::<AssetServer>().load::<Obj>("floor.obj#obj:tile");
+    let tiles = Tiles {
+        tiles: [("floor".into(), obj.clone())].into_iter().collect(),
+        names: default(),
+    };
+
+    update_until(&mut app, |app| {
+        app.world().resource::<AssetServer>().is_loaded_with_dependencies(&obj)
+    });
+
+    let mut texture = TileTexture {
+        pages: Vec::new(),
+        page_of: default(),
+        maps: default(),
+        sources: default(),
+        released: default(),
+        aliases: default(),
+    };
+
+    assert!(take(&mut app, &tiles, &mut texture));
+    let diffuse = app
+        .world()
+        .resource::<Assets<Obj>>()
+        .get(&obj)
+        .unwrap()
+        .diffuse_texture(app.world().resource::<Assets<MtlCollection>>());
+
+    let diffuse = diffuse.expect("the tile's material keeps its texture's ID");
+    assert!(texture.sources.contains_key(&diffuse));
+    assert!(!app.world().resource::<Assets<Image>>().contains(diffuse));
+
+    // Nothing but the atlas should stay in memory once released.
+    texture.release_sources();
+    assert!(texture.sources.is_empty(), "loadable sources must be dropped");
+    assert!(texture.released.contains_key(&diffuse));
+    assert!(!take(&mut app, &tiles, &mut texture), "released sources must be loaded again");
+
+    texture.reload_released(app.world().resource::<AssetServer>(), app.world().resource::<Assets<Image>>());
+    update_until(&mut app, |app| take(app, &tiles, &mut texture));
+
+    assert!(texture.released.is_empty());
+    assert!(
+        texture.sources.get(&diffuse).is_some_and(|(handle, _)| handle.is_strong()),
+        "reloaded sources keep their ID and handle"
+    );
+}
@@ -0,0 +1,73 @@
+use mnemonic::content::{
+    manifest::{TileCatalog, TileInfo},
+    names::TileKey,
+};
+
+fn catalog() -> TileCatalog {
+    let info = |name: &str, category: &str, tags: &[&str]| TileInfo {
+        name: name.into(),
+        category: category.into(),
+        tags: tags.iter().map(|&tag| tag.into()).collect(),
+        order: None,
+    };
+
+    TileCatalog::from_infos(
+        [
+            ("liminal.floor", info("Floor", "Liminal/Floors", &["walkable"])),
+            ("liminal.carpet", info("Carpet", "Liminal/Floors", &["walkable", "soft"])),
+            ("liminal.wall", info("Wall", "Liminal/Walls", &[])),
+            ("liminal.wall.corner", info("Wall corner", "Liminal/Walls", &[])),
+            ("mods.flower", info("Flower", "Liminality", &["plant"])),
+            ("loose", info("Loose", "", &[])),
+        ]
+        .into_iter()
+        .map(|(key, info)| (key.into(), info))
+        .collect(),
+    )
+}
+
+fn keys(keys: Vec<TileKey>) -> Vec<String> {
+    keys.into_iter().map(|key| key.0).collect()
+}
+
+#[test]
+fn categories_include_parents() {
+    assert_eq!(catalog().categories(), [
+        "Liminal",
+        "Liminal/Floors",
+        "Liminal/Walls",
+        "Liminality"
+    ]);
+}
+
+#[test]
+fn iterates_nested_categories() {
+    let catalog = catalog();
+    assert_eq!(catalog.iter_category("Liminal/Floors").collect::<Vec<_>>(), [
+        "liminal.carpet",
+        "liminal.floor"
+    ]);
+
+    // `Liminality` only shares a prefix with `Liminal`, and isn't under it.
+    assert_eq!(catalog.iter_category("Liminal").count(), 4);
+    assert_eq!(catalog.iter_category("").count(), 6);
+    assert_eq!(catalog.iter_category("Liminal/Floors/Nothing").count(), 0);
+}
+
+#[test]
+fn search_ranks_matches() {
+    let catalog = catalog();
+
+    assert_eq!(keys(catalog.search("wall")), ["liminal.wall", "liminal.wall.corner"]);
+    assert_eq!(keys(catalog.search("Corner")), ["liminal.wall.corner"]);
+
+    // Names and keys first, then tags, then fuzzy matches.
+    assert_eq!(keys(catalog.search("walk")), ["liminal.carpet", "liminal.floor"]);
+    assert_eq!(keys(catalog.search("soft")), ["liminal.carpet"]);
+    assert_eq!(keys(catalog.search("lflr")), ["liminal.floor"]);
+    assert_eq!(keys(catalog.search("wlcr")), ["liminal.wall.corner"]);
+    assert_eq!(keys(catalog.search("flo")), ["liminal.floor", "mods.flower"]);
+
+    assert!(catalog.search("xyz").is_empty());
+    assert_eq!(catalog.search("  ").len(), 6);
+}
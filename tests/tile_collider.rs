@@ -0,0 +1,104 @@
+use std::{f32::consts::SQRT_2, time::Duration};
+
+use avian3d::prelude::*;
+use bevy::{prelude::*, scene::ScenePlugin, time::TimeUpdateStrategy};
+use mnemonic::{
+    map::{
+        orientation::TileOrientation,
+        tile::{cube_obj, pillar_obj, ramp_obj, slab_obj, TileCollider},
+        Map, MapCell,
+    },
+    obj::def::Obj,
+};
+use nonmax::NonMaxU8;
+
+const RADIUS: f32 = 0.2;
+
+#[test]
+fn bodies_settle_on_tile_colliders() {
+    let mut objs = Assets::<Obj>::default();
+    let tiles = [
+        cube_obj(default(), "tile".into()),
+        slab_obj(default(), "tile".into(), 0.5),
+        ramp_obj(default(), "tile".into()),
+        pillar_obj(default(), "tile".into(), 0.25),
+        Obj {
+            collider: TileCollider::None,
+            ..cube_obj(default(), "tile".into())
+        },
+    ];
+
+    // One of each, with the ramp again turned to ascend towards +Z.
+    let mut map = Map::empty(UVec3::new(6, 1, 1));
+    for (index, tile) in tiles.into_iter().enumerate() {
+        map.tile_set.push(format!("tile{index}"));
+        map.tile_handles.push(objs.add(tile));
+    }
+
+    for (x, (tile, turns)) in [(0, 0), (1, 0), (2, 0), (2, 2), (3, 0), (4, 0)].into_iter().enumerate() {
+        map.set_cell(
+            UVec3::new(x as u32, 0, 0),
+            MapCell::new(NonMaxU8::new(tile), TileOrientation::new(turns, false)),
+        );
+    }
+
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        AssetPlugin::default(),
+        ScenePlugin,
+        PhysicsPlugins::default(),
+    ))
+    .init_asset::<Mesh>()
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(1.0 / 60.0)));
+
+    app.world_mut().spawn((
+        TransformBundle::default(),
+        RigidBody::Static,
+        map.build_collider(&objs, |_| true).expect("the map has solid tiles"),
+    ));
+
+    // Dropped straight down, so they come to rest on slopes too. Off-center on the ramps, where the
+    // turned ramp is higher.
+    let bodies = (0..6)
+        .map(|x| {
+            let z = if matches!(x, 2 | 3) { 0.25 } else { 0.0 };
+            app.world_mut()
+                .spawn((
+                    TransformBundle::from_transform(Transform::from_xyz(x as f32, 1.5, z)),
+                    RigidBody::Dynamic,
+                    Collider::sphere(RADIUS),
+                    LockedAxes::ROTATION_LOCKED.lock_translation_x().lock_translation_z(),
+                ))
+                .id()
+        })
+        .collect::<Vec<_>>();
+
+    for _ in 0..600 {
+        app.update();
+    }
+
+    let heights = bodies
+        .iter()
+        .map(|&e| app.world().get::<Position>(e).unwrap().y)
+        .collect::<Vec<_>>();
+
+    // A ball on a 45° slope rests `RADIUS * √2` above the slope under its center.
+    let expected = [
+        0.5 + RADIUS,
+        RADIUS,
+        -0.25 + RADIUS * SQRT_2,
+        0.25 + RADIUS * SQRT_2,
+        0.5 + RADIUS,
+    ];
+    for (x, (height, expected)) in heights.iter().zip(expected).enumerate() {
+        assert!(
+            (height - expected).abs() < 0.02,
+            "body {x} settled at {height}, not {expected}"
+        );
+    }
+
+    assert!(heights[5] < -1.0, "bodies fall through tiles that don't collide");
+}
@@ -0,0 +1,419 @@
+use std::path::Path;
+
+use bevy::{
+    asset::io::memory::Dir,
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::{ImageFilterMode, ImageSampler},
+    },
+};
+use mnemonic::{
+    harness::{test_app, update_until},
+    map::tile::{
+        bake_cube_net, cube_obj, pillar_obj, ramp_obj, slab_obj, CubeTexture, Tile, TileCollider, TileFile, PILLAR_SEGMENTS,
+    },
+    obj::{
+        def::{Cull, MtlCollection, Obj},
+        loader::premultiply_alpha,
+    },
+};
+
+const LINEAR_META: &str = r#"(
+    meta_format_version: "1.0",
+    asset: Load(
+        loader: "mnemonic::map::tile::TileLoader",
+        settings: (
+            asset_usage: ("MAIN_WORLD | RENDER_WORLD"),
+            is_srgb: false,
+            sampler: Some(Linear),
+            premultiply_alpha: true,
+        ),
+    ),
+)"#;
+
+fn dir() -> Dir {
+    let dir = Dir::default();
+    for file in ["floor.obj", "floor.mtl", "floor.png"] {
+        let bytes = std::fs::read(Path::new("assets/tiles/liminal").join(file)).unwrap();
+        dir.insert_asset(Path::new("liminal").join(file).as_path(), bytes);
+    }
+
+    for file in [
+        "floor.tile",
+        "ramp.tile",
+        "slab.tile",
+        "pillar.tile",
+        "faces.tile",
+        "pole.tile",
+    ] {
+        let bytes = std::fs::read(Path::new("assets/tiles/generated").join(file)).unwrap();
+        dir.insert_asset(Path::new("generated").join(file).as_path(), bytes);
+    }
+
+    // The floor again, imported differently.
+    dir.insert_asset(
+        Path::new("generated/linear.tile"),
+        std::fs::read("assets/tiles/generated/floor.tile").unwrap(),
+    );
+    dir.insert_meta_text(Path::new("generated/linear.tile"), LINEAR_META);
+
+    dir
+}
+
+#[test]
+fn cube_tiles_match_modelled_cubes() {
+    let mut app = test_app(dir());
+    let server = app.world().resource::<AssetServer>().clone();
+    let modelled = server.load::<Obj>("liminal/floor.obj#obj:tile");
+    let tile = server.load::<Tile>("generated/floor.tile");
+
+    update_until(&mut app, |_| {
+        server.is_loaded_with_dependencies(&modelled) && server.is_loaded_with_dependencies(&tile)
+    });
+
+    let world = app.world();
+    let (objs, materials, images) = (
+        world.resource::<Assets<Obj>>(),
+        world.resource::<Assets<MtlCollection>>(),
+        world.resource::<Assets<Image>>(),
+    );
+
+    let generated = world.resource::<Assets<Tile>>().get(&tile).unwrap().obj();
+    assert_eq!(
+        Some(generated.id()),
+        server
+            .get_handle::<Obj>("generated/floor.tile#obj:tile")
+            .map(|handle| handle.id())
+    );
+
+    let (modelled, generated) = (objs.get(&modelled).unwrap(), objs.get(generated).unwrap());
+    assert_eq!(modelled.faces, generated.faces);
+    assert_eq!(modelled.normals, generated.normals);
+    for (a, b) in modelled.positions.iter().zip(&generated.positions) {
+        assert!(a.abs_diff_eq(*b, 1e-6), "{a} != {b}");
+    }
+
+    for (a, b) in modelled.uvs.iter().zip(&generated.uvs) {
+        assert!(a.abs_diff_eq(*b, 1e-6), "{a} != {b}");
+    }
+
+    let texture = |obj: &Obj| images.get(obj.diffuse_texture(materials).unwrap()).unwrap();
+    assert_eq!(texture(modelled).data, texture(generated).data);
+}
+
+/// Every triangle must wind counter-clockwise around its vertices' normal, or it's culled from the
+/// wrong side.
+fn assert_outwards(obj: &Obj) {
+    for [a, b, c] in obj.face_iter() {
+        let [pa, pb, pc] = [a, b, c].map(|index| obj.positions[index]);
+        let winding = (pb - pa).cross(pc - pa).normalize();
+        assert!(winding.abs_diff_eq(obj.normals[a], 1e-5), "{winding} != {}", obj.normals[a]);
+    }
+}
+
+#[test]
+fn ramps_slope_towards_their_back() {
+    let cube = cube_obj(default(), "tile".into());
+    let ramp = ramp_obj(default(), "tile".into());
+    assert_outwards(&cube);
+    assert_outwards(&ramp);
+
+    // A quad for the bottom, back, and slope, and a triangle for either side.
+    assert_eq!(ramp.faces.len(), 2 + 2 + 2 + 1 + 1);
+    assert_eq!(ramp.culls.bits(), (Cull::DOWN | Cull::NEG_Z).bits());
+    assert_eq!(cube.culls.bits(), Cull::all().bits());
+
+    for pos in &ramp.positions {
+        assert!(pos.y < 0.0 || pos.z < 0.0, "only the back rises, but {pos} does");
+    }
+
+    let slope = Vec3::new(0.0, 1.0, 1.0).normalize();
+    assert!(ramp.normals.iter().any(|normal| normal.abs_diff_eq(slope, 1e-6)));
+}
+
+#[test]
+fn ramp_tiles_load() {
+    let mut app = test_app(dir());
+    let server = app.world().resource::<AssetServer>().clone();
+    let tile = server.load::<Tile>("generated/ramp.tile");
+    update_until(&mut app, |_| server.is_loaded_with_dependencies(&tile));
+
+    let world = app.world();
+    let Some(Tile::Ramp { obj, .. }) = world.resource::<Assets<Tile>>().get(&tile) else {
+        panic!("expected a ramp");
+    };
+
+    let obj = world.resource::<Assets<Obj>>().get(obj).unwrap();
+    assert!(obj.diffuse_texture(world.resource::<Assets<MtlCollection>>()).is_some());
+    assert_eq!(obj.faces.len(), 8);
+}
+
+fn bounds(obj: &Obj) -> (Vec3, Vec3) {
+    obj.positions
+        .iter()
+        .fold((Vec3::MAX, Vec3::MIN), |(min, max), &pos| (min.min(pos), max.max(pos)))
+}
+
+#[test]
+fn slabs_are_cut_from_cubes() {
+    let cube = cube_obj(default(), "tile".into());
+    let full = slab_obj(default(), "tile".into(), 1.0);
+    assert_eq!(full.positions, cube.positions);
+    assert_eq!(full.faces, cube.faces);
+    for (a, b) in full.uvs.iter().zip(&cube.uvs) {
+        assert!(a.abs_diff_eq(*b, 1e-6), "{a} != {b}");
+    }
+
+    let half = slab_obj(default(), "tile".into(), 0.5);
+    assert_outwards(&half);
+    assert_eq!(half.positions.len(), 24);
+    assert_eq!(half.faces.len(), 12);
+    assert_eq!(bounds(&half), (Vec3::splat(-0.5), Vec3::new(0.5, 0.0, 0.5)));
+
+    // Only the bottom reaches the cell's boundary whole, so walls stacked above stay drawn.
+    assert_eq!(half.culls.bits(), Cull::DOWN.bits());
+    assert_eq!(full.culls.bits(), Cull::all().bits());
+
+    // The sides show the bottom half of the cube's texture, rather than all of it squashed.
+    let (min_uv, max_uv) = half
+        .uvs
+        .iter()
+        .zip(&half.normals)
+        .filter(|(_, normal)| **normal == Vec3::X)
+        .fold((Vec2::MAX, Vec2::MIN), |(min, max), (&uv, _)| (min.min(uv), max.max(uv)));
+    assert!((max_uv.y - min_uv.y - 0.25).abs() < 1e-6);
+}
+
+#[test]
+fn pillars_are_round() {
+    let pillar = pillar_obj(default(), "tile".into(), 0.25);
+    assert_outwards(&pillar);
+
+    // A quad for each side, and a polygon for either cap.
+    assert_eq!(pillar.positions.len(), PILLAR_SEGMENTS * 4 + PILLAR_SEGMENTS * 2);
+    assert_eq!(pillar.faces.len(), PILLAR_SEGMENTS * 2 + (PILLAR_SEGMENTS - 2) * 2);
+    assert!(pillar.culls.is_empty());
+
+    let (min, max) = bounds(&pillar);
+    assert!(min.abs_diff_eq(Vec3::new(-0.25, -0.5, -0.25), 1e-6), "{min}");
+    assert!(max.abs_diff_eq(Vec3::new(0.25, 0.5, 0.25), 1e-6), "{max}");
+    for pos in &pillar.positions {
+        assert!((Vec2::new(pos.x, pos.z).length() - 0.25).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn primitive_tiles_load() {
+    let mut app = test_app(dir());
+    let server = app.world().resource::<AssetServer>().clone();
+    let slab = server.load::<Tile>("generated/slab.tile");
+    let pillar = server.load::<Tile>("generated/pillar.tile");
+    let pole = server.load::<Tile>("generated/pole.tile");
+    update_until(&mut app, |_| {
+        [&slab, &pillar, &pole]
+            .iter()
+            .all(|tile| server.is_loaded_with_dependencies(*tile))
+    });
+
+    let (tiles, objs) = (app.world().resource::<Assets<Tile>>(), app.world().resource::<Assets<Obj>>());
+    let collider = |tile: &Handle<Tile>| objs.get(tiles.get(tile).unwrap().obj()).unwrap().collider;
+
+    // Colliding as their visual shape, unless the file says otherwise.
+    assert_eq!(collider(&slab), TileCollider::Slab { height: 0.5 });
+    assert_eq!(collider(&pillar), TileCollider::Trimesh);
+    assert_eq!(collider(&pole), TileCollider::None);
+
+    let pole = objs.get(tiles.get(&pole).unwrap().obj()).unwrap();
+    assert_eq!(pole.properties.walkable, Some(false));
+}
+
+fn texel(image: &Image, uv: Vec2) -> [u8; 4] {
+    let texel = (uv * image.size().as_vec2()).as_uvec2().min(image.size() - 1);
+    let index = ((texel.y * image.width() + texel.x) * 4) as usize;
+    image.data[index..index + 4].try_into().unwrap()
+}
+
+#[test]
+fn cube_faces_bake_into_a_net() {
+    let size = Extent3d {
+        width: 4,
+        height: 4,
+        depth_or_array_layers: 1,
+    };
+    let fill = |texel: [u8; 4]| {
+        Image::new_fill(
+            size,
+            TextureDimension::D2,
+            &texel,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::all(),
+        )
+    };
+
+    let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 0, 255], [
+        0, 255, 255, 255,
+    ]];
+    let mut faces = colors.map(fill).to_vec();
+
+    // The front is red over blue, to tell which way up it's baked.
+    let mut front = fill([0, 0, 255, 255]);
+    front.data[..32].copy_from_slice(&[255, 0, 0, 255].repeat(8));
+    faces.insert(4, front);
+
+    let net = bake_cube_net(std::array::from_fn(|index| &faces[index])).unwrap();
+    assert_eq!(net.size(), UVec2::new(12, 8));
+
+    let cube = cube_obj(default(), "tile".into());
+    for (face, corners) in cube.uvs.chunks(4).enumerate() {
+        let center = corners.iter().sum::<Vec2>() / 4.0;
+        if face != 4 {
+            assert_eq!(texel(&net, center), faces[face].data[..4], "face {face}");
+            continue
+        }
+
+        // Halfway between the center and the top edge, and the bottom edge.
+        let positions = &cube.positions[face * 4..][..4];
+        let edge = |top: bool| {
+            let (sum, count) = positions
+                .iter()
+                .zip(corners)
+                .filter(|(pos, _)| (pos.y > 0.0) == top)
+                .fold((Vec2::ZERO, 0.0), |(sum, count), (_, &uv)| (sum + uv, count + 1.0));
+            (sum / count + center) / 2.0
+        };
+
+        assert_eq!(texel(&net, edge(true)), [255, 0, 0, 255], "the front's top must be up");
+        assert_eq!(texel(&net, edge(false)), [0, 0, 255, 255]);
+    }
+}
+
+#[test]
+fn cube_textures_keep_their_old_form() {
+    let single = TileFile::parse(r#"Cube("stone.png")"#).unwrap();
+    assert!(matches!(single, TileFile::Cube {
+        texture: CubeTexture::Single(ref texture),
+        collider: None,
+        ..
+    } if texture == "stone.png"));
+
+    let hollow = TileFile::parse(r#"Cube("stone.png", Some(Slab(height: 0.25)))"#).unwrap();
+    assert_eq!(hollow.collider(), Some(TileCollider::Slab { height: 0.25 }));
+
+    let TileFile::Cube {
+        texture: CubeTexture::Faces(faces),
+        ..
+    } = TileFile::parse(r#"Cube((top: "grass.png", sides: "side.png", bottom: "dirt.png", z: "front.png"))"#).unwrap()
+    else {
+        panic!("expected a texture per face");
+    };
+
+    assert_eq!(faces.faces().unwrap(), [
+        "side.png",
+        "side.png",
+        "grass.png",
+        "dirt.png",
+        "front.png",
+        "side.png"
+    ]);
+
+    let TileFile::Cube {
+        texture: CubeTexture::Faces(faces),
+        ..
+    } = TileFile::parse(r#"Cube((top: "grass.png", bottom: "dirt.png"))"#).unwrap()
+    else {
+        panic!("expected a texture per face");
+    };
+    assert!(faces.faces().is_err(), "sides can't be left out without a fallback");
+}
+
+#[test]
+fn cube_faces_load() {
+    let mut app = test_app(dir());
+    let server = app.world().resource::<AssetServer>().clone();
+    let tile = server.load::<Tile>("generated/faces.tile");
+    let floor = server.load::<Image>("liminal/floor.png");
+    update_until(&mut app, |_| {
+        server.is_loaded_with_dependencies(&tile) && server.is_loaded_with_dependencies(&floor)
+    });
+
+    let world = app.world();
+    let Some(&Tile::Cube { texture, .. }) = world.resource::<Assets<Tile>>().get(&tile) else {
+        panic!("expected a cube");
+    };
+
+    let images = world.resource::<Assets<Image>>();
+    let (net, floor) = (images.get(texture).unwrap(), images.get(&floor).unwrap());
+    assert_eq!(net.size(), floor.size() * UVec2::new(3, 2));
+}
+
+#[test]
+fn alpha_premultiplies_in_linear_space() {
+    let size = Extent3d {
+        width: 3,
+        height: 1,
+        depth_or_array_layers: 1,
+    };
+
+    let texels = [[200, 100, 50, 128], [255, 255, 255, 0], [10, 20, 30, 255]].concat();
+    let mut linear = Image::new(
+        size,
+        TextureDimension::D2,
+        texels.clone(),
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::all(),
+    );
+    assert!(premultiply_alpha(&mut linear));
+    assert_eq!(linear.data, [[100, 50, 25, 128], [0, 0, 0, 0], [10, 20, 30, 255]].concat());
+
+    // Halving sRGB colors' light is brighter than halving their encoding.
+    let mut srgb = Image::new(
+        size,
+        TextureDimension::D2,
+        texels,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::all(),
+    );
+    assert!(premultiply_alpha(&mut srgb));
+    assert!(srgb.data[0] > 100 && srgb.data[0] < 200, "{}", srgb.data[0]);
+    assert_eq!(srgb.data[4..], [[0, 0, 0, 0], [10, 20, 30, 255]].concat());
+}
+
+#[test]
+fn tile_textures_import_as_configured() {
+    let mut app = test_app(dir());
+    let server = app.world().resource::<AssetServer>().clone();
+    let (floor, linear) = (
+        server.load::<Tile>("generated/floor.tile"),
+        server.load::<Tile>("generated/linear.tile"),
+    );
+    update_until(&mut app, |_| {
+        server.is_loaded_with_dependencies(&floor) && server.is_loaded_with_dependencies(&linear)
+    });
+
+    let world = app.world();
+    let (tiles, images) = (world.resource::<Assets<Tile>>(), world.resource::<Assets<Image>>());
+    let texture = |tile: &Handle<Tile>| {
+        let Some(&Tile::Cube { texture, .. }) = tiles.get(tile) else {
+            panic!("expected a cube");
+        };
+        images.get(texture).unwrap()
+    };
+
+    let (floor, linear) = (texture(&floor), texture(&linear));
+    assert_eq!(floor.texture_descriptor.format, TextureFormat::Rgba8UnormSrgb);
+    assert!(matches!(floor.sampler, ImageSampler::Default));
+
+    // Overridden by the file's `.meta`.
+    assert_eq!(linear.texture_descriptor.format, TextureFormat::Rgba8Unorm);
+    assert!(matches!(
+        &linear.sampler,
+        ImageSampler::Descriptor(descriptor) if matches!(descriptor.mag_filter, ImageFilterMode::Linear)
+    ));
+
+    let mut expected = floor.clone();
+    expected.texture_descriptor.format = TextureFormat::Rgba8Unorm;
+    premultiply_alpha(&mut expected);
+    assert_eq!(linear.data, expected.data);
+}
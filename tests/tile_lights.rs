@@ -0,0 +1,165 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{prelude::*, state::app::StatesPlugin};
+use mnemonic::{
+    content::array::MapMaterial,
+    map::{
+        lighting::{MapLight, TileLight, TileLightKind, TileLightSettings},
+        orientation::TileOrientation,
+        tile::{cube_obj, TileFile},
+        Map, MapCell, MapPlugin,
+    },
+    obj::{def::Obj, ObjPlugin},
+};
+use nonmax::NonMaxU8;
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        ImagePlugin::default_nearest(),
+        TransformPlugin,
+        HierarchyPlugin,
+        StatesPlugin,
+        ObjPlugin,
+        MapPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<TextureAtlasLayout>()
+    .init_asset::<MapMaterial>()
+    .insert_resource(TileLightSettings { max_lights: 3 });
+
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// Cells of the lights under the map, sorted.
+fn light_cells(app: &mut App) -> Vec<u32> {
+    let mut cells = app
+        .world_mut()
+        .query::<&MapLight>()
+        .iter(app.world())
+        .map(|light| light.cell.x)
+        .collect::<Vec<_>>();
+
+    cells.sort_unstable();
+    cells
+}
+
+#[test]
+fn tile_lights_parse() {
+    let file = TileFile::parse(
+        r#"Slab(texture: "lamp.png", height: 0.1, light: Some((intensity: 800.0, offset: (0.0, -0.2, 0.0), kind: Spot(angle: 30.0))))"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        file.light(),
+        Some(TileLight {
+            intensity: 800.0,
+            offset: Vec3::new(0.0, -0.2, 0.0),
+            kind: TileLightKind::Spot {
+                direction: Vec3::NEG_Y,
+                angle: 30.0,
+            },
+            ..default()
+        })
+    );
+
+    let cube = TileFile::parse(r#"Cube("lamp.png", None, (), [], Some(()))"#).unwrap();
+    assert_eq!(cube.light(), Some(TileLight::default()));
+}
+
+#[test]
+fn nearest_tile_lights_follow_the_map() {
+    let mut app = app();
+
+    let lamp = app.world_mut().resource_mut::<Assets<Obj>>().add(Obj {
+        light: Some(TileLight {
+            offset: Vec3::new(0.25, 0.4, 0.0),
+            ..default()
+        }),
+        ..cube_obj(default(), "tile".into())
+    });
+
+    let mut map = Map::empty(UVec3::new(10, 1, 1));
+    map.tile_set.push("lamp".into());
+    map.tile_handles.push(lamp);
+    for x in 0..10 {
+        map.set_cell(UVec3::new(x, 0, 0), MapCell::new(NonMaxU8::new(0), default()));
+    }
+
+    // Turned a quarter, the lamp's offset points towards -Z.
+    map.set_cell(
+        UVec3::new(8, 0, 0),
+        MapCell::new(NonMaxU8::new(0), TileOrientation::new(1, false)),
+    );
+
+    let map = app.world_mut().resource_mut::<Assets<Map>>().add(map);
+    let map_entity = app
+        .world_mut()
+        .spawn((SpatialBundle::from_transform(Transform::from_xyz(0.0, 5.0, 0.0)), map.clone()))
+        .id();
+    let camera = app
+        .world_mut()
+        .spawn(Camera3dBundle {
+            transform: Transform::from_xyz(8.0, 6.0, 0.0),
+            ..default()
+        })
+        .id();
+
+    app.update();
+    app.update();
+    assert_eq!(light_cells(&mut app), [7, 8, 9]);
+
+    let turned = app
+        .world_mut()
+        .query::<(&MapLight, &GlobalTransform, &Parent)>()
+        .iter(app.world())
+        .find(|(light, ..)| light.cell.x == 8)
+        .map(|(_, trns, parent)| (trns.translation(), parent.get()))
+        .unwrap();
+    assert_eq!(turned.1, map_entity);
+    assert!(turned.0.distance(Vec3::new(8.0, 5.4, -0.25)) < 1e-4, "{}", turned.0);
+
+    // Erasing a lamp takes its light away, and lets the next nearest in.
+    app.world_mut()
+        .resource_mut::<Assets<Map>>()
+        .get_mut(&map)
+        .unwrap()
+        .set_cell(UVec3::new(8, 0, 0), MapCell::new(None, default()));
+    app.update();
+    app.update();
+    assert_eq!(light_cells(&mut app), [6, 7, 9]);
+
+    // Lights follow the camera.
+    app.world_mut().get_mut::<Transform>(camera).unwrap().translation.x = 0.0;
+    app.update();
+    app.update();
+    assert_eq!(light_cells(&mut app), [0, 1, 2]);
+    assert_eq!(app.world_mut().query::<&PointLight>().iter(app.world()).count(), 3);
+}
+
+#[test]
+fn spot_lights_turn_with_their_tiles() {
+    let light = MapLight {
+        cell: UVec3::new(1, 2, 3),
+        orientation: TileOrientation::new(1, false),
+        light: TileLight {
+            kind: TileLightKind::Spot {
+                direction: Vec3::new(1.0, -1.0, 0.0).normalize(),
+                angle: 30.0,
+            },
+            ..default()
+        },
+    };
+
+    let trns = light.transform(Vec3::ONE);
+    assert_eq!(trns.translation, Vec3::new(1.0, 2.0, 3.0));
+
+    // A quarter turn counter-clockwise takes +X to -Z.
+    let expected = Quat::from_rotation_y(FRAC_PI_2) * Vec3::new(1.0, -1.0, 0.0).normalize();
+    assert!(trns.forward().distance(expected) < 1e-4, "{}", *trns.forward());
+}
@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use bevy::{asset::io::memory::Dir, prelude::*};
+use mnemonic::{
+    content::{manifest::TileManifest, TileFolder, Tiles},
+    harness::{update_until, watched_test_app, AssetNotifier},
+    map::tile::{Tile, TileCollider},
+    obj::def::{Obj, ObjCollection},
+};
+
+const MODEL: &str = r#"Model(obj: "../liminal/floor.obj", object: "tile", collider: Some(Full))"#;
+
+fn dir() -> Dir {
+    let dir = Dir::default();
+    for file in ["floor.obj", "floor.mtl", "floor.png"] {
+        let bytes = std::fs::read(Path::new("assets/tiles/liminal").join(file)).unwrap();
+        dir.insert_asset(Path::new("tiles/liminal").join(file).as_path(), bytes);
+    }
+
+    dir.insert_asset_text(Path::new("tiles/generated/model.tile"), MODEL);
+    dir
+}
+
+fn load(dir: Dir) -> (App, AssetNotifier, Handle<ObjCollection>, Handle<Tile>) {
+    let (mut app, notifier) = watched_test_app(dir);
+    app.init_asset::<TileManifest>();
+    let server = app.world().resource::<AssetServer>().clone();
+    let (floor, model) = (
+        server.load::<ObjCollection>("tiles/liminal/floor.obj"),
+        server.load::<Tile>("tiles/generated/model.tile"),
+    );
+
+    update_until(&mut app, |_| {
+        server.is_loaded_with_dependencies(&floor) && server.is_loaded_with_dependencies(&model)
+    });
+    (app, notifier, floor, model)
+}
+
+#[test]
+fn models_wrap_objects() {
+    let (app, _, floor, model) = load(dir());
+
+    let world = app.world();
+    let (collections, tiles, objs) = (
+        world.resource::<Assets<ObjCollection>>(),
+        world.resource::<Assets<Tile>>(),
+        world.resource::<Assets<Obj>>(),
+    );
+
+    let Some(Tile::Model { source, obj }) = tiles.get(&model) else {
+        panic!("expected a model");
+    };
+    assert_eq!(source, "tiles/liminal/floor.obj#obj:tile");
+
+    let (wrapped, obj) = (
+        objs.get(&collections.get(&floor).unwrap()["tile"]).unwrap(),
+        objs.get(obj).unwrap(),
+    );
+    assert_eq!(obj.positions, wrapped.positions);
+    assert_eq!(obj.material, wrapped.material);
+
+    // Metadata is the wrapper's own.
+    assert_eq!(wrapped.collider, TileCollider::Trimesh);
+    assert_eq!(obj.collider, TileCollider::Full);
+}
+
+#[test]
+fn wrappers_take_over_objects() {
+    let (mut app, _, floor, model) = load(dir());
+
+    app.world_mut().insert_resource(TileFolder {
+        files: [
+            ("tiles/liminal/floor.obj".into(), floor.untyped()),
+            ("tiles/generated/model.tile".into(), model.untyped()),
+        ]
+        .into_iter()
+        .collect(),
+        manifest: default(),
+    });
+    app.world_mut().init_resource::<Tiles>();
+
+    let tiles = app.world().resource::<Tiles>();
+    assert_eq!(tiles.keys().collect::<Vec<_>>(), ["generated.model"]);
+
+    // Maps naming the object by key or path get the wrapper.
+    for name in ["liminal.floor", "tiles/liminal/floor.obj#obj:tile"] {
+        assert_eq!(tiles.resolve(name).as_deref(), Some("generated.model"), "{name}");
+    }
+}
+
+#[test]
+fn models_reload_with_their_objects() {
+    let dir = dir();
+    let (mut app, notifier, _floor, model) = load(dir.clone());
+    let obj = app.world().resource::<Assets<Tile>>().get(&model).unwrap().obj().clone();
+
+    let source = std::fs::read_to_string("assets/tiles/liminal/floor.obj").unwrap();
+    dir.insert_asset_text(Path::new("tiles/liminal/floor.obj"), &source.replace("0.25", "0.5"));
+    notifier.modified("tiles/liminal/floor.obj");
+
+    update_until(&mut app, |app| {
+        let objs = app.world().resource::<Assets<Obj>>();
+        objs.get(&obj).is_some_and(|obj| obj.positions.iter().any(|pos| pos.x == 0.5))
+    });
+}
@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use mnemonic::{
+    content::names::{TileKey, TileNameTable},
+    map::{
+        loader::{MapError, MapFile},
+        Map,
+    },
+};
+
+#[test]
+fn keys_from_paths() {
+    assert_eq!(*TileKey::from_path("tiles/liminal/floor.obj#obj:tile"), "liminal.floor");
+    assert_eq!(
+        *TileKey::from_path("tiles/liminal/walls.obj#obj:corner"),
+        "liminal.walls.corner"
+    );
+    assert_eq!(*TileKey::from_path("mods/rocks.obj#obj:boulder"), "mods.rocks.boulder");
+}
+
+#[test]
+fn old_maps_migrate_to_keys() {
+    let mut names = TileNameTable::default();
+    names.insert("liminal.floor", Some("tiles/liminal/floor.obj#obj:tile"));
+    names.insert("liminal.wall", Some("tiles/liminal/wall.obj#obj:tile"));
+    names.redirects.insert("liminal.carpet".into(), "liminal.floor".into());
+    names
+        .redirects
+        .insert("tiles/old/wall.obj#obj:tile".into(), "tiles/liminal/wall.obj#obj:tile".into());
+
+    let mut file = MapFile::from_ron(
+        r#"(
+            tile_set: ["tiles/liminal/floor.obj#obj:tile", "liminal.carpet", "tiles/old/wall.obj#obj:tile", "liminal.wall", "gone"],
+            tiles: [Some(0), Some(1), Some(2), Some(3), Some(4)],
+            size: (5, 1, 1),
+        )"#,
+    )
+    .unwrap();
+
+    assert_eq!(file.migrate_tiles(&names), 3);
+    assert_eq!(file.tile_set, [
+        "liminal.floor",
+        "liminal.floor",
+        "liminal.wall",
+        "liminal.wall",
+        "gone"
+    ]);
+
+    let map = Map::from_file(file, vec![default(); 5]);
+    match map.validate(&names) {
+        Err(MapError::UnresolvedTiles(tiles)) => assert_eq!(tiles, ["gone"]),
+        other => panic!("expected `gone` to be unresolved, got {other:?}"),
+    }
+}
+
+#[test]
+fn oversized_tile_sets_are_rejected() {
+    let tile_set = (0..=u8::MAX as usize).map(|i| format!("\"tile{i}\"")).collect::<Vec<_>>().join(", ");
+    let text = format!("(tile_set: [{tile_set}], tiles: [Some(0)], size: (1, 1, 1))");
+    assert!(matches!(MapFile::from_ron(&text), Err(MapError::TooManyTiles { count: 256 })));
+}
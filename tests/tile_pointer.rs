@@ -0,0 +1,217 @@
+use bevy::{prelude::*, render::camera::NormalizedRenderTarget};
+use bevy_mod_picking::{
+    backend::HitData,
+    events::{Click, Down, Drag, DragEnd, DragStart, Move, Out, Over, Pointer, Up},
+    pointer::{Location, PointerButton, PointerId},
+};
+use mnemonic::{
+    map::{
+        picking::{
+            send_tile_pointer_events, MapPick, MapPicks, PointerOverUi, TilePointerEvent, TilePointerKind, TilePointers,
+        },
+        GridHit, Map,
+    },
+    obj::def::Cull,
+};
+
+fn app() -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .init_asset::<Map>()
+        .init_resource::<TilePointers>()
+        .init_resource::<MapPicks>()
+        .init_resource::<PointerOverUi>()
+        .add_event::<TilePointerEvent>()
+        .add_event::<Pointer<Over>>()
+        .add_event::<Pointer<Move>>()
+        .add_event::<Pointer<Out>>()
+        .add_event::<Pointer<Down>>()
+        .add_event::<Pointer<Up>>()
+        .add_event::<Pointer<Click>>()
+        .add_event::<Pointer<DragStart>>()
+        .add_event::<Pointer<Drag>>()
+        .add_event::<Pointer<DragEnd>>()
+        .add_systems(Update, send_tile_pointer_events);
+
+    let world = app.world_mut();
+    let handle = world.resource_mut::<Assets<Map>>().add(Map::empty(UVec3::new(4, 1, 4)));
+    let map = world.spawn((SpatialBundle::default(), handle)).id();
+    (app, map)
+}
+
+/// A hit on the top face of a floor cell.
+fn on_top_of(x: f32, z: f32) -> HitData {
+    HitData::new(Entity::PLACEHOLDER, 1.0, Some(Vec3::new(x, 0.5, z)), Some(Vec3::Y))
+}
+
+fn pointer<E: std::fmt::Debug + Clone + Reflect>(target: Entity, event: E) -> Pointer<E> {
+    let location = Location {
+        target: NormalizedRenderTarget::Image(default()),
+        position: Vec2::ZERO,
+    };
+
+    Pointer::new(PointerId::Mouse, location, target, event)
+}
+
+/// Sends a pointer event, returning the tile pointer events it turned into.
+fn send<E: std::fmt::Debug + Clone + Reflect>(app: &mut App, target: Entity, event: E) -> Vec<(UVec3, TilePointerKind)> {
+    let world = app.world_mut();
+    world.resource_mut::<Events<TilePointerEvent>>().clear();
+    world.send_event(pointer(target, event));
+    app.update();
+
+    let events = app.world().resource::<Events<TilePointerEvent>>();
+    events
+        .get_reader()
+        .read(events)
+        .filter(|e| e.entity == target)
+        .inspect(|e| assert_eq!(e.face, Cull::UP))
+        .map(|e| (e.cell, e.kind))
+        .collect()
+}
+
+#[test]
+fn sliding_between_cells_goes_out_and_over() {
+    let (mut app, map) = app();
+
+    let cell = UVec3::new(1, 0, 1);
+    assert_eq!(send(&mut app, map, Over { hit: on_top_of(1.2, 0.9) }), [(cell, TilePointerKind::Over)]);
+
+    // Moving within the same cell says nothing new.
+    let moved = Move {
+        hit: on_top_of(0.8, 1.3),
+        delta: Vec2::ONE,
+    };
+    assert!(send(&mut app, map, moved).is_empty());
+
+    let next = UVec3::new(2, 0, 1);
+    let moved = Move {
+        hit: on_top_of(2.1, 1.0),
+        delta: Vec2::ONE,
+    };
+    assert_eq!(send(&mut app, map, moved), [
+        (cell, TilePointerKind::Out),
+        (next, TilePointerKind::Over)
+    ]);
+
+    assert_eq!(send(&mut app, map, Out { hit: on_top_of(2.1, 1.0) }), [(next, TilePointerKind::Out)]);
+    assert!(app.world().resource::<TilePointers>().hovered.is_empty());
+}
+
+#[test]
+fn presses_and_drags_name_their_cells() {
+    let (mut app, map) = app();
+    send(&mut app, map, Over { hit: on_top_of(0.0, 0.0) });
+
+    let down = Down {
+        button: PointerButton::Secondary,
+        hit: on_top_of(0.0, 0.0),
+    };
+    assert_eq!(send(&mut app, map, down), [(
+        UVec3::ZERO,
+        TilePointerKind::Down(PointerButton::Secondary)
+    )]);
+
+    let start = DragStart {
+        button: PointerButton::Secondary,
+        hit: on_top_of(0.0, 0.0),
+    };
+    assert_eq!(send(&mut app, map, start), [(UVec3::ZERO, TilePointerKind::DragStart)]);
+
+    // Drags carry no hit, and follow the cell the pointer moved over.
+    send(&mut app, map, Move {
+        hit: on_top_of(3.0, 0.0),
+        delta: Vec2::ONE,
+    });
+    let drag = Drag {
+        button: PointerButton::Secondary,
+        distance: Vec2::ONE,
+        delta: Vec2::ONE,
+    };
+    assert_eq!(send(&mut app, map, drag), [(UVec3::new(3, 0, 0), TilePointerKind::Drag)]);
+}
+
+#[test]
+fn other_entities_are_left_alone() {
+    let (mut app, map) = app();
+    let other = app.world_mut().spawn(SpatialBundle::default()).id();
+
+    let down = Down {
+        button: PointerButton::Primary,
+        hit: on_top_of(0.0, 0.0),
+    };
+    assert!(send(&mut app, other, down).is_empty());
+
+    // Hits outside the map bounds strike no cell.
+    assert!(send(&mut app, map, Over { hit: on_top_of(9.0, 9.0) }).is_empty());
+}
+
+#[test]
+fn ui_stops_presses_but_not_drags() {
+    let (mut app, map) = app();
+    send(&mut app, map, Over { hit: on_top_of(0.0, 0.0) });
+    send(&mut app, map, DragStart {
+        button: PointerButton::Primary,
+        hit: on_top_of(0.0, 0.0),
+    });
+
+    // The pointer slides onto a panel, leaving the map as far as the picking stack can tell.
+    app.world_mut().resource_mut::<PointerOverUi>().insert(PointerId::Mouse, true);
+    assert_eq!(send(&mut app, map, Out { hit: on_top_of(0.0, 0.0) }), [(UVec3::ZERO, TilePointerKind::Out)]);
+
+    let down = Down {
+        button: PointerButton::Primary,
+        hit: on_top_of(1.0, 0.0),
+    };
+    assert!(send(&mut app, map, down).is_empty());
+
+    // The drag follows the cell behind the panel instead.
+    app.world_mut().resource_mut::<MapPicks>().insert((PointerId::Mouse, map), MapPick {
+        camera: Entity::PLACEHOLDER,
+        ray: Ray3d::new(Vec3::new(2.0, 5.0, 0.0), Vec3::NEG_Y),
+        hit: Some(GridHit {
+            cell: UVec3::new(2, 0, 0),
+            normal: IVec3::Y,
+        }),
+        ground: None,
+    });
+
+    let drag = Drag {
+        button: PointerButton::Primary,
+        distance: Vec2::ONE,
+        delta: Vec2::ONE,
+    };
+    assert_eq!(send(&mut app, map, drag), [(UVec3::new(2, 0, 0), TilePointerKind::Drag)]);
+}
+
+#[test]
+fn the_ground_stands_in_for_missing_cells() {
+    let (mut app, map) = app();
+    app.world_mut().resource_mut::<MapPicks>().insert((PointerId::Mouse, map), MapPick {
+        camera: Entity::PLACEHOLDER,
+        ray: Ray3d::new(Vec3::new(3.0, 5.0, 2.0), Vec3::NEG_Y),
+        hit: None,
+        ground: Some(IVec3::new(3, 0, 2)),
+    });
+
+    // The hit lies on the floor beneath the ground cell, yet names the cell itself.
+    let floor = HitData::new(Entity::PLACEHOLDER, 1.0, Some(Vec3::new(3.0, -0.5, 2.0)), Some(Vec3::Y));
+    let down = Down {
+        button: PointerButton::Primary,
+        hit: floor.clone(),
+    };
+    assert_eq!(send(&mut app, map, down), [(
+        UVec3::new(3, 0, 2),
+        TilePointerKind::Down(PointerButton::Primary)
+    )]);
+
+    let events = app.world().resource::<Events<TilePointerEvent>>();
+    let e = events.get_reader().read(events).next().copied().unwrap();
+    assert!(e.ground);
+    assert_eq!(e.click_cell(true), Some(IVec3::new(3, 0, 2)));
+
+    // Ground cells past the map bounds aren't tiles to point at.
+    app.world_mut().resource_mut::<MapPicks>().get_mut(&(PointerId::Mouse, map)).unwrap().ground =
+        Some(IVec3::new(5, 0, 2));
+    assert!(send(&mut app, map, Over { hit: floor }).is_empty());
+}
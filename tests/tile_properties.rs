@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use mnemonic::{
+    content::{
+        manifest::TileEntry,
+        properties::{SurfaceTag, TileProperties, TilePropertyTable},
+    },
+    map::{
+        tile::{cube_obj, TileFile},
+        Map, MapCell,
+    },
+    obj::def::Obj,
+};
+use nonmax::NonMaxU8;
+
+#[test]
+fn properties_resolve_from_files_and_manifest() {
+    let cube = TileFile::parse(r#"Cube("ice.png", None, (friction: Some(0.05)))"#).unwrap();
+    let slab = TileFile::parse(
+        r#"Slab(texture: "ice.png", height: 0.5, walkable: Some(false), move_cost: Some(3.0), surface: Some(Metal))"#,
+    )
+    .unwrap();
+
+    let obj = |file: &TileFile| Obj {
+        properties: file.properties(),
+        ..default()
+    };
+
+    assert_eq!(TileProperties::resolve(Some(&obj(&cube)), None), TileProperties {
+        friction: 0.05,
+        ..default()
+    });
+    assert_eq!(TileProperties::resolve(Some(&obj(&slab)), None), TileProperties {
+        walkable: false,
+        move_cost: 3.0,
+        surface: SurfaceTag::Metal,
+        ..default()
+    });
+
+    // The manifest has the last word, and describes OBJ-based tiles too.
+    let entry = ron::from_str::<TileEntry>(r#"(path: "ice.tile", walkable: Some(true), friction: Some(0.9))"#).unwrap();
+    assert_eq!(TileProperties::resolve(Some(&obj(&slab)), Some(&entry)), TileProperties {
+        walkable: true,
+        friction: 0.9,
+        move_cost: 3.0,
+        surface: SurfaceTag::Metal,
+        ..default()
+    });
+    assert_eq!(TileProperties::resolve(None, Some(&entry)), TileProperties {
+        friction: 0.9,
+        ..default()
+    });
+}
+
+#[test]
+fn colliders_split_by_friction() {
+    let mut objs = Assets::<Obj>::default();
+    let mut map = Map::empty(UVec3::new(3, 1, 1));
+    for key in ["ice", "stone"] {
+        map.tile_set.push(key.into());
+        map.tile_handles.push(objs.add(cube_obj(default(), "tile".into())));
+    }
+
+    for (x, tile) in [0, 1, 0].into_iter().enumerate() {
+        map.set_cell(UVec3::new(x as u32, 0, 0), MapCell::new(NonMaxU8::new(tile), default()));
+    }
+
+    let ice = TileProperties {
+        friction: 0.05,
+        ..default()
+    };
+    let table = TilePropertyTable([("ice".into(), ice)].into_iter().collect());
+    assert_eq!(table.for_map(&map), [ice, TileProperties::default()]);
+
+    let colliders = map.build_colliders(&objs, &table);
+    let parts = colliders
+        .iter()
+        .map(|(collider, friction)| {
+            let compound = collider.shape().as_compound().expect("maps collide as compounds");
+            (friction.dynamic_coefficient, compound.shapes().len())
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(parts, [(0.05, 2), (0.5, 1)]);
+}
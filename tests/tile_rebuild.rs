@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use bevy::{asset::io::memory::Dir, prelude::*};
+use mnemonic::{
+    content::{atlas::TileAtlasSettings, service_tile_rebuilds, RebuildTileTexture, TileTextureRebuilt, Tiles},
+    harness::{insert_floor_tile, test_app, update_until},
+    map::{Map, MapMeshes},
+    obj::def::Obj,
+};
+
+#[derive(Resource, Default)]
+struct Counts {
+    meshes: usize,
+    rebuilds: usize,
+}
+
+fn count(
+    mut meshes: EventReader<AssetEvent<Mesh>>,
+    mut rebuilds: EventReader<TileTextureRebuilt>,
+    mut counts: ResMut<Counts>,
+) {
+    counts.meshes += meshes
+        .read()
+        .filter(|e| matches!(e, AssetEvent::Added { .. } | AssetEvent::Modified { .. }))
+        .count();
+    counts.rebuilds += rebuilds.read().count();
+}
+
+#[test]
+fn rebuilds_remesh_maps() {
+    let dir = Dir::default();
+    insert_floor_tile(&dir);
+    dir.insert_asset_text(
+        Path::new("test.mnmap"),
+        r#"(tile_set: ["floor.obj#obj:tile"], tiles: [Some(0), None, Some(0)], size: (3, 1, 1))"#,
+    );
+
+    let mut app = test_app(dir);
+    app.add_event::<RebuildTileTexture>()
+        // Released sources would have to be loaded again for every rebuild.
+        .insert_resource(TileAtlasSettings {
+            keep_sources: true,
+            ..default()
+        })
+        .init_resource::<Counts>()
+        .add_systems(Update, service_tile_rebuilds)
+        .add_systems(Last, count);
+
+    let obj = app.world().resource::<AssetServer>().load::<Obj>("floor.obj#obj:tile");
+    app.insert_resource(Tiles {
+        tiles: [("floor".into(), obj)].into_iter().collect(),
+        names: default(),
+    });
+
+    let map = app.world().resource::<AssetServer>().load::<Map>("test.mnmap");
+    update_until(&mut app, |app| app.world().resource::<MapMeshes>().contains_key(&map.id()));
+
+    // The harness' stand-in atlas meshed the map first; this packs the tiles for real.
+    app.world_mut().send_event(RebuildTileTexture);
+
+    let settle = |app: &mut App| {
+        for _ in 0..5 {
+            app.update();
+        }
+
+        let counts = app.world().resource::<Counts>();
+        (counts.meshes, counts.rebuilds)
+    };
+
+    let (meshes, rebuilds) = settle(&mut app);
+    assert_eq!(rebuilds, 1);
+
+    for _ in 0..2 {
+        app.world_mut().send_event(RebuildTileTexture);
+        app.update();
+    }
+
+    assert_eq!(
+        settle(&mut app),
+        (meshes + 2, rebuilds + 2),
+        "each rebuild remeshes the map once"
+    );
+
+    // Requests within a frame are one rebuild.
+    app.world_mut().send_event(RebuildTileTexture);
+    app.world_mut().send_event(RebuildTileTexture);
+    assert_eq!(settle(&mut app), (meshes + 3, rebuilds + 3));
+}
@@ -0,0 +1,70 @@
+use mnemonic::{
+    content::{
+        manifest::TileEntry,
+        render::{TileAlpha, TileRenderFlags},
+    },
+    obj::{
+        def::Mtl,
+        parser::{parse_mtl, MtlDirective},
+    },
+};
+use nom::error::VerboseError;
+
+fn entry(ron: &str) -> TileEntry {
+    ron::from_str(ron).unwrap()
+}
+
+#[test]
+fn mtl_parses_dissolve_and_illum() {
+    let (rest, directives) = parse_mtl::<VerboseError<&str>>("newmtl glass\nd 0.25\nillum 0\n").unwrap();
+    assert!(rest.is_empty());
+    assert!(matches!(directives[..], [
+        MtlDirective::Newmtl("glass"),
+        MtlDirective::D(dissolve),
+        MtlDirective::Illum(0),
+    ] if dissolve == 0.25));
+}
+
+#[test]
+fn materials_imply_flags() {
+    let mtl = Mtl {
+        dissolve: Some(0.5),
+        illum: Some(0),
+        ..Default::default()
+    };
+
+    let flags = TileRenderFlags::resolve("glass", Some(&mtl), None);
+    assert_eq!(flags.alpha_mode, TileAlpha::Blend);
+    assert!(flags.unlit);
+    assert!(TileRenderFlags::resolve("floor", Some(&Mtl::default()), None).is_default());
+}
+
+#[test]
+fn manifest_overrides_materials() {
+    let mtl = Mtl {
+        dissolve: Some(0.5),
+        illum: Some(0),
+        ..Default::default()
+    };
+
+    let overrides = entry(
+        r#"(
+            path: "glass.obj#obj:tile",
+            alpha_mode: Some(Mask(0.5)),
+            emissive_boost: Some(4.0),
+            unlit: Some(false),
+            double_sided: Some(true),
+        )"#,
+    );
+
+    let flags = TileRenderFlags::resolve("glass", Some(&mtl), Some(&overrides));
+    assert_eq!(flags.alpha_mode, TileAlpha::Mask(0.5));
+    assert_eq!(flags.emissive_boost, 4.0);
+    assert!(!flags.unlit);
+    assert!(flags.double_sided);
+
+    // Fields the manifest leaves out keep what the material implies.
+    let flags = TileRenderFlags::resolve("glass", Some(&mtl), Some(&entry(r#"(path: "glass.obj#obj:tile")"#)));
+    assert_eq!(flags.alpha_mode, TileAlpha::Blend);
+    assert!(flags.unlit);
+}
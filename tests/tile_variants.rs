@@ -0,0 +1,187 @@
+use std::path::Path;
+
+use bevy::{
+    asset::{io::memory::Dir, LoadState},
+    prelude::*,
+    render::{
+        mesh::{PrimitiveTopology, VertexAttributeValues},
+        render_asset::RenderAssetUsages,
+    },
+    utils::HashMap,
+};
+use mnemonic::{
+    content::{AtlasPage, TileTexture},
+    harness::{test_app, update_until},
+    map::{
+        tile::{cube_obj, Tile},
+        Map, MapCell,
+    },
+    obj::def::{Mtl, MtlCollection, Obj, ObjCollection, TextureVariant},
+};
+use nonmax::NonMaxU8;
+
+fn dir() -> Dir {
+    let dir = Dir::default();
+    for file in ["floor.obj", "floor.png"] {
+        let bytes = std::fs::read(Path::new("assets/tiles/liminal").join(file)).unwrap();
+        dir.insert_asset(Path::new("liminal").join(file).as_path(), bytes);
+    }
+
+    dir.insert_asset_text(
+        Path::new("liminal/floor.mtl"),
+        "newmtl m_755d3539-927f-a534-d19b-559ff67d35c8\nmap_Kd floor.png\nmap_Kd_variant floor.png\nmap_Kd_variant -w 3 floor.png\n",
+    );
+    dir.insert_asset_text(
+        Path::new("variants.tile"),
+        r#"Slab(texture: "liminal/floor.png", height: 0.5, variants: ["liminal/floor.png", ("liminal/floor.png", 0.25)])"#,
+    );
+    dir.insert_asset_text(
+        Path::new("faces.tile"),
+        r#"Cube((sides: "liminal/floor.png", top: "liminal/floor.png", bottom: "liminal/floor.png"), None, (), ["liminal/floor.png"])"#,
+    );
+
+    dir
+}
+
+fn variant_weights(materials: &Assets<MtlCollection>, obj: &Obj) -> Vec<f32> {
+    let mtl = materials.get(&obj.material).unwrap().get(&obj.material_key).unwrap();
+    mtl.diffuse_variants.iter().map(|variant| variant.weight).collect()
+}
+
+#[test]
+fn variants_load_from_tile_and_mtl_files() {
+    let mut app = test_app(dir());
+    let server = app.world().resource::<AssetServer>().clone();
+    let tile = server.load::<Tile>("variants.tile");
+    let faces = server.load::<Tile>("faces.tile");
+    let collection = server.load::<ObjCollection>("liminal/floor.obj");
+    update_until(&mut app, |_| {
+        server.is_loaded_with_dependencies(&tile) &&
+            server.is_loaded_with_dependencies(&collection) &&
+            matches!(server.load_state(&faces), LoadState::Failed(..))
+    });
+
+    let (tiles, objs, collections, materials) = (
+        app.world().resource::<Assets<Tile>>(),
+        app.world().resource::<Assets<Obj>>(),
+        app.world().resource::<Assets<ObjCollection>>(),
+        app.world().resource::<Assets<MtlCollection>>(),
+    );
+
+    let tile = objs.get(tiles.get(&tile).unwrap().obj()).unwrap();
+    assert_eq!(variant_weights(materials, tile), [1.0, 0.25]);
+    assert_eq!(tile.diffuse_textures(materials).len(), 3);
+
+    let modelled = objs.get(&collections.get(&collection).unwrap()["tile"]).unwrap();
+    assert_eq!(variant_weights(materials, modelled), [1.0, 3.0]);
+}
+
+#[test]
+fn variants_stay_put_across_rebuilds() {
+    let mut images = Assets::<Image>::default();
+    let mut materials = Assets::<MtlCollection>::default();
+    let mut objs = Assets::<Obj>::default();
+    let mut layouts = Assets::<TextureAtlasLayout>::default();
+
+    // Three textures in one atlas page, telling apart which one a cell shows.
+    let mut builder = TextureAtlasBuilder::default();
+    let textures = [(); 3].map(|_| images.add(Image::default()));
+    for texture in &textures {
+        builder.add_texture(Some(texture.id()), images.get(texture).unwrap());
+    }
+    let (layout, _) = builder.build().unwrap();
+
+    let material = materials.add(MtlCollection {
+        materials: [("tile".into(), Mtl {
+            diffuse_texture: Some(textures[0].clone()),
+            diffuse_texture_id: Some(textures[0].id()),
+            diffuse_variants: textures[1..]
+                .iter()
+                .map(|texture| TextureVariant {
+                    texture: texture.clone(),
+                    weight: 1.0,
+                })
+                .collect(),
+            ..default()
+        })]
+        .into_iter()
+        .collect(),
+    });
+
+    let mut map = Map::empty(UVec3::new(8, 1, 8));
+    map.seed = 7;
+    map.tile_set.push("floor".into());
+    map.tile_handles.push(objs.add(cube_obj(material, "tile".into())));
+    for index in 0..64 {
+        map.set_cell(map.cell(index), MapCell::new(NonMaxU8::new(0), default()));
+    }
+
+    let texture = TileTexture {
+        pages: vec![AtlasPage {
+            layout: layouts.add(layout),
+            atlas: Handle::default(),
+            layers: None,
+            normal_atlas: None,
+            emissive_atlas: None,
+        }],
+        page_of: textures.iter().map(|texture| (texture.id(), 0)).collect(),
+        maps: default(),
+        sources: default(),
+        released: default(),
+        aliases: default(),
+    };
+
+    // Which texture each meshed cell shows, from the rectangle its texture coordinates are in.
+    let rects = textures.each_ref().map(|id| texture.locate(&layouts, id.id()).unwrap().1);
+    let vertices = objs.iter().next().unwrap().1.positions.len();
+    let picks = |map: &Map, include: &dyn Fn(UVec3) -> bool| {
+        let mesh = map.build_mesh(
+            Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default()),
+            &objs,
+            &materials,
+            &texture,
+            &layouts,
+            0,
+            include,
+        );
+
+        let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0) else {
+            panic!("maps have 2D texture coordinates");
+        };
+
+        (0..64)
+            .map(|index| map.cell(index))
+            .filter(|&cell| include(cell))
+            .zip(uvs.chunks(vertices))
+            .map(|(cell, uvs)| {
+                let pick = rects
+                    .iter()
+                    .position(|rect| uvs.iter().all(|&uv| rect.contains(Vec2::from(uv))))
+                    .expect("cells show one texture whole");
+                (cell, pick)
+            })
+            .collect::<HashMap<_, _>>()
+    };
+
+    let all = picks(&map, &|_| true);
+    assert_eq!(all.len(), 64);
+    for variant in 0..3 {
+        assert!(all.values().any(|&pick| pick == variant), "variant {variant} never picked");
+    }
+
+    for (&cell, &pick) in &all {
+        let id = objs.iter().next().unwrap().1.diffuse_texture_at(&materials, map.seed, cell);
+        assert_eq!(id, Some(textures[pick].id()));
+    }
+
+    // Meshing again, or only part of the map as a chunk would, keeps every cell's pick.
+    assert_eq!(picks(&map, &|_| true), all);
+    let chunk = picks(&map, &|cell| cell.x < 4);
+    assert_eq!(chunk.len(), 32);
+    assert!(chunk.iter().all(|(cell, pick)| all[cell] == *pick));
+
+    // Editing a cell leaves the others alone.
+    map.set_cell(UVec3::ZERO, MapCell::new(None, default()));
+    let edited = picks(&map, &|cell| cell != UVec3::ZERO);
+    assert!(edited.iter().all(|(cell, pick)| all[cell] == *pick));
+}
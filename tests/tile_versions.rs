@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use bevy::{asset::io::memory::Dir, prelude::*};
+use mnemonic::{
+    harness::{insert_floor_tile, test_app, update_until},
+    map::tile::{Tile, TileError, TileFile, TILE_FILE_VERSION},
+    obj::def::Obj,
+};
+
+/// Every fixture, written once for each version.
+const FIXTURES: [&str; 8] = ["floor", "faces", "ramp", "slab", "pillar", "pole", "lamp", "model"];
+const VERSIONS: [&str; 2] = ["v1", "v2"];
+
+fn fixture(version: &str, name: &str) -> String {
+    std::fs::read_to_string(Path::new("tests/fixtures/tiles").join(version).join(format!("{name}.tile"))).unwrap()
+}
+
+/// The fixtures of every version, along with the `liminal` floor files they refer to.
+fn fixture_dir() -> Dir {
+    let dir = Dir::default();
+    insert_floor_tile(&dir.get_or_insert_dir(Path::new("liminal")));
+
+    for version in VERSIONS {
+        for name in FIXTURES {
+            dir.insert_asset_text(
+                Path::new(version).join(format!("{name}.tile")).as_path(),
+                &fixture(version, name),
+            );
+        }
+    }
+
+    dir
+}
+
+#[test]
+fn every_version_reads_alike() {
+    assert_eq!(VERSIONS.len(), TILE_FILE_VERSION as usize, "every version needs fixtures");
+    for name in FIXTURES {
+        let current = TileFile::parse(&fixture("v2", name)).unwrap();
+        for version in VERSIONS {
+            let file = TileFile::parse(&fixture(version, name)).unwrap_or_else(|e| panic!("{version}/{name}: {e}"));
+            assert_eq!(file, current, "{version}/{name} migrates differently");
+        }
+    }
+}
+
+#[test]
+fn every_version_loads() {
+    let mut app = test_app(fixture_dir());
+    let server = app.world().resource::<AssetServer>().clone();
+    let tiles = VERSIONS.map(|version| FIXTURES.map(|name| server.load::<Tile>(format!("{version}/{name}.tile"))));
+    update_until(&mut app, |_| {
+        tiles.iter().flatten().all(|tile| server.is_loaded_with_dependencies(tile))
+    });
+
+    let (assets, objs) = (app.world().resource::<Assets<Tile>>(), app.world().resource::<Assets<Obj>>());
+    let obj = |tile: &Handle<Tile>| {
+        let obj = objs.get(assets.get(tile).expect("tile should load").obj()).unwrap();
+        (
+            obj.positions.clone(),
+            obj.faces.clone(),
+            obj.collider,
+            obj.properties,
+            obj.light,
+        )
+    };
+
+    let [old, current] = &tiles;
+    for ((old, current), name) in old.iter().zip(current).zip(FIXTURES) {
+        assert!(obj(old) == obj(current), "{name} loads differently between versions");
+    }
+}
+
+#[test]
+fn future_versions_are_refused() {
+    for version in [0, TILE_FILE_VERSION + 1] {
+        let text = format!(r#"(version: {version}, tile: Cube(texture: "stone.png"))"#);
+        let e = TileFile::parse(&text).unwrap_err();
+        assert!(matches!(e, TileError::UnsupportedVersion(v) if v == version));
+        assert!(e.to_string().contains(&format!("version {version}")), "{e}");
+    }
+
+    // Versions don't read each other's shapes.
+    assert!(TileFile::parse(r#"(version: 1, tile: Cube(texture: "stone.png"))"#).is_err());
+    assert!(TileFile::parse(r#"(version: 2, tile: Cube("stone.png"))"#).is_err());
+}